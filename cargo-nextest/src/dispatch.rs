@@ -8,39 +8,58 @@ use crate::{
     version, ExpectedError, Result, ReuseBuildKind,
 };
 use camino::{Utf8Path, Utf8PathBuf};
-use clap::{builder::BoolishValueParser, ArgAction, Args, Parser, Subcommand, ValueEnum};
+use clap::{
+    builder::{BoolishValueParser, PossibleValue, PossibleValuesParser},
+    ArgAction, Args, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum,
+};
 use guppy::graph::PackageGraph;
 use itertools::Itertools;
 use nextest_filtering::{EvalContext, Filterset, FiltersetKind, ParseContext};
-use nextest_metadata::BuildPlatform;
+use nextest_metadata::{BuildPlatform, TestListSummary};
 use nextest_runner::{
     cargo_config::{CargoConfigs, EnvironmentMap, TargetTriple},
     config::{
         get_num_cpus, ConfigExperimental, EarlyProfile, MaxFail, NextestConfig,
-        NextestVersionConfig, NextestVersionEval, RetryPolicy, TestGroup, TestThreads,
-        ToolConfigFile, VersionOnlyConfig,
+        NextestVersionConfig, NextestVersionEval, OnTeardownFailure, RetryPolicy, TestGroup,
+        TestThreads, ToolConfigFile, VersionOnlyConfig,
     },
     double_spawn::DoubleSpawnInfo,
-    errors::{TargetTripleError, WriteTestListError},
+    errors::{EventLogError, TargetTripleError, WriteEventError, WriteTestListError},
     input::InputHandlerKind,
     list::{
-        BinaryList, OutputFormat, RustTestArtifact, SerializableFormat, TestExecuteContext,
-        TestList,
+        BinaryList, ChangedFiles, OutputFormat, RustTestArtifact, SerializableFormat,
+        TestExecuteContext, TestList,
     },
+    output_index::OutputIndex,
     partition::PartitionerBuilder,
     platform::{BuildPlatforms, HostPlatform, PlatformLibdir, TargetPlatform},
     redact::Redactor,
     reporter::{
-        events::{FinalRunStats, RunStatsFailureKind},
-        highlight_end, structured, FinalStatusLevel, ReporterBuilder, StatusLevel,
+        events::{
+            AggregateRunStats, CancelReason, FinalRunStats, RunStatsFailureKind, SetupScriptEnvMap,
+            TestEventKind,
+        },
+        highlight_end,
+        structured::{
+            self, event_log::convert_event_log_to_ndjson, FormatterArgs, FormatterRegistry,
+        },
+        write_aggregate_summary, AnsiEscapes, FinalStatusLevel, ReporterBuilder, StatusLevel,
         TestOutputDisplay, TestOutputErrorSlice,
     },
     reuse_build::{archive_to_file, ArchiveReporter, PathMapper, ReuseBuildInfo},
-    runner::{configure_handle_inheritance, TestRunnerBuilder},
+    runner::{
+        configure_handle_inheritance, parse_env_file, RepeatUntilFailure, Shuffle,
+        TestRunnerBuilder, VerifyFailures,
+    },
     show_config::{ShowNextestVersion, ShowTestGroupSettings, ShowTestGroups, ShowTestGroupsMode},
     signal::SignalHandlerKind,
     target_runner::{PlatformRunner, TargetRunner},
-    test_filter::{FilterBound, RunIgnored, TestFilterBuilder, TestFilterPatterns},
+    test_filter::{
+        FilterBound, PreviousResult, PreviousResultFilter, RunIgnored, TestFilterBuilder,
+        TestFilterPatterns,
+    },
+    test_output::DeterminismCheck,
+    timings::TimingsStore,
     write_str::WriteStr,
     RustcCli,
 };
@@ -53,7 +72,9 @@ use std::{
     env::VarError,
     fmt,
     io::{Cursor, Write},
+    num::NonZeroUsize,
     sync::Arc,
+    time::Duration,
 };
 use swrite::{swrite, SWrite};
 use tracing::{debug, info, warn, Level};
@@ -87,16 +108,51 @@ impl CargoNextestApp {
         }
     }
 
+    /// Parses the command line, accepting the formats in `formatters` for `--message-format`.
+    ///
+    /// [`Parser::parse`] only accepts the built-in formats. Programs that embed nextest and
+    /// register their own formats should use this, followed by [`Self::exec_with_formatters`].
+    pub fn parse_with_formatters(formatters: &FormatterRegistry) -> Self {
+        let matches = Self::command_with_formatters(formatters).get_matches();
+        Self::from_arg_matches(&matches).unwrap_or_else(|error| error.exit())
+    }
+
+    /// Returns the clap command, accepting the formats in `formatters` for `--message-format`.
+    pub fn command_with_formatters(formatters: &FormatterRegistry) -> clap::Command {
+        let set_parser = |arg: clap::Arg| arg.value_parser(message_format_parser(formatters));
+        Self::command()
+            .mut_subcommand("nextest", |app| {
+                app.mut_subcommand("run", |run| run.mut_arg("message-format", set_parser))
+            })
+            .mut_subcommand("ntr", |ntr| ntr.mut_arg("message-format", set_parser))
+    }
+
     /// Executes the app.
     pub fn exec(
         self,
         cli_args: Vec<String>,
         output: OutputContext,
         output_writer: &mut OutputWriter,
+    ) -> Result<i32> {
+        self.exec_with_formatters(cli_args, output, output_writer, &FormatterRegistry::new())
+    }
+
+    /// Executes the app, with `--message-format` looked up in the given registry.
+    ///
+    /// This allows programs that embed nextest to register their own message formats. The command
+    /// line should have been parsed with [`Self::parse_with_formatters`], using the same registry.
+    pub fn exec_with_formatters(
+        self,
+        cli_args: Vec<String>,
+        output: OutputContext,
+        output_writer: &mut OutputWriter,
+        formatters: &FormatterRegistry,
     ) -> Result<i32> {
         match self.subcommand {
-            NextestSubcommand::Nextest(app) => app.exec(cli_args, output, output_writer),
-            NextestSubcommand::Ntr(opts) => opts.exec(cli_args, output, output_writer),
+            NextestSubcommand::Nextest(app) => {
+                app.exec(cli_args, output, output_writer, formatters)
+            }
+            NextestSubcommand::Ntr(opts) => opts.exec(cli_args, output, output_writer, formatters),
             #[cfg(unix)]
             NextestSubcommand::DoubleSpawn(opts) => opts.exec(output),
         }
@@ -138,6 +194,7 @@ impl AppOpts {
         cli_args: Vec<String>,
         output: OutputContext,
         output_writer: &mut OutputWriter,
+        formatters: &FormatterRegistry,
     ) -> Result<i32> {
         match self.command {
             Command::List {
@@ -145,6 +202,11 @@ impl AppOpts {
                 build_filter,
                 message_format,
                 list_type,
+                with_timings,
+                with_flaky_rates,
+                group_by_platform,
+                changed_since,
+                output_file,
                 reuse_build,
                 ..
             } => {
@@ -157,7 +219,16 @@ impl AppOpts {
                     output_writer,
                 )?;
                 let app = App::new(base, build_filter)?;
-                app.exec_list(message_format, list_type, output_writer)?;
+                app.exec_list(
+                    message_format,
+                    list_type,
+                    with_timings,
+                    with_flaky_rates,
+                    group_by_platform,
+                    changed_since.as_deref(),
+                    output_file.as_deref(),
+                    output_writer,
+                )?;
                 Ok(0)
             }
             Command::Run(run_opts) => {
@@ -172,8 +243,10 @@ impl AppOpts {
                 let app = App::new(base, run_opts.build_filter)?;
                 app.exec_run(
                     run_opts.no_capture,
+                    run_opts.changed_since.as_deref(),
                     &run_opts.runner_opts,
                     &run_opts.reporter_opts,
+                    formatters,
                     cli_args,
                     output_writer,
                 )?;
@@ -301,6 +374,8 @@ impl ConfigOpts {
     }
 }
 
+// Command is parsed once at startup, so the size of the Run variant doesn't matter.
+#[expect(clippy::large_enum_variant)]
 #[derive(Debug, Subcommand)]
 enum Command {
     /// List tests in workspace
@@ -341,6 +416,48 @@ enum Command {
         )]
         list_type: ListType,
 
+        /// Annotate tests with estimated durations from recorded timings
+        ///
+        /// Estimates are the median of each test's recent durations, as recorded by runs with
+        /// `record-timings` enabled in the profile. Tests without recorded timings are not
+        /// annotated.
+        #[arg(long, help_heading = "Output options")]
+        with_timings: bool,
+
+        /// Annotate tests with how often they were flaky in recent runs
+        ///
+        /// A run is flaky if a test failed but then passed on a retry. Flaky rates are computed
+        /// over the most recent runs recorded by runs with `record-timings` enabled in the profile.
+        /// Tests without recorded outcomes are not annotated.
+        #[arg(long, help_heading = "Output options")]
+        with_flaky_rates: bool,
+
+        /// Group human-readable output by build platform
+        ///
+        /// Binaries built for one platform are listed before binaries built for the other, sorted
+        /// by binary ID within each group. This is useful when cross-compiling. Machine-readable
+        /// output is always keyed by binary ID, so this requires `--message-format human`.
+        #[arg(
+            long,
+            value_enum,
+            help_heading = "Output options",
+            value_name = "ORDER"
+        )]
+        group_by_platform: Option<PlatformOrderOpt>,
+
+        /// Only list tests affected by files changed since this git ref
+        ///
+        /// Changed files are the committed, staged and unstaged changes since the ref, along with
+        /// untracked files. A change to an integration test, benchmark or example only affects that
+        /// binary; any other change within a package affects all binaries in that package and in
+        /// packages that depend on it. If nothing changed, no tests are listed.
+        #[arg(long, value_name = "REF", help_heading = "Filter options")]
+        changed_since: Option<String>,
+
+        /// Write the list to this file instead of standard output
+        #[arg(long, value_name = "PATH", help_heading = "Output options")]
+        output_file: Option<Utf8PathBuf>,
+
         #[clap(flatten)]
         reuse_build: ReuseBuildOpts,
     },
@@ -438,6 +555,7 @@ impl NtrOpts {
         cli_args: Vec<String>,
         output: OutputContext,
         output_writer: &mut OutputWriter,
+        formatters: &FormatterRegistry,
     ) -> Result<i32> {
         let base = BaseApp::new(
             output,
@@ -450,8 +568,10 @@ impl NtrOpts {
         let app = App::new(base, self.run_opts.build_filter)?;
         app.exec_run(
             self.run_opts.no_capture,
+            self.run_opts.changed_since.as_deref(),
             &self.run_opts.runner_opts,
             &self.run_opts.reporter_opts,
+            formatters,
             cli_args,
             output_writer,
         )
@@ -479,6 +599,13 @@ struct RunOpts {
     )]
     no_capture: bool,
 
+    /// Only run tests affected by files changed since this git ref
+    ///
+    /// Tests in unaffected binaries are skipped. See `cargo nextest list --changed-since` for how
+    /// changed files are mapped to test binaries.
+    #[arg(long, value_name = "REF", help_heading = "Filter options")]
+    changed_since: Option<String>,
+
     #[clap(flatten)]
     reporter_opts: ReporterOpts,
 
@@ -521,18 +648,56 @@ impl Default for ListType {
     }
 }
 
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum PlatformOrderOpt {
+    /// List host platform binaries first.
+    HostFirst,
+    /// List target platform binaries first.
+    TargetFirst,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum PreviousResultOpt {
+    /// Tests that timed out.
+    TimedOut,
+
+    /// Tests that failed, timed out, or were flaky.
+    Failed,
+}
+
+impl From<PreviousResultOpt> for PreviousResult {
+    fn from(opt: PreviousResultOpt) -> Self {
+        match opt {
+            PreviousResultOpt::TimedOut => PreviousResult::TimedOut,
+            PreviousResultOpt::Failed => PreviousResult::Failed,
+        }
+    }
+}
+
+impl PlatformOrderOpt {
+    fn first_platform(self) -> BuildPlatform {
+        match self {
+            Self::HostFirst => BuildPlatform::Host,
+            Self::TargetFirst => BuildPlatform::Target,
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, ValueEnum)]
 enum MessageFormatOpts {
     Human,
     Json,
     JsonPretty,
+    /// JSON containing only the number of tests, without the full list.
+    JsonSummary,
 }
 
 impl MessageFormatOpts {
     fn to_output_format(self, verbose: bool) -> OutputFormat {
         match self {
             Self::Human => OutputFormat::Human { verbose },
-            Self::Json => OutputFormat::Serializable(SerializableFormat::Json),
+            // json-summary is handled separately by the list command.
+            Self::Json | Self::JsonSummary => OutputFormat::Serializable(SerializableFormat::Json),
             Self::JsonPretty => OutputFormat::Serializable(SerializableFormat::JsonPretty),
         }
     }
@@ -551,10 +716,17 @@ struct TestBuildFilter {
     #[arg(long, value_enum, value_name = "WHICH")]
     run_ignored: Option<RunIgnoredOpt>,
 
-    /// Test partition, e.g. hash:1/2 or count:2/3
+    /// Test partition, e.g. hash:1/2, count:2/3 or timings:1/4
     #[arg(long)]
     partition: Option<PartitionerBuilder>,
 
+    /// Only select tests that had this result in the previous run
+    ///
+    /// Results are read from the profile's timings store, so `record-timings` must be enabled.
+    /// Tests that weren't run in the previous run are skipped.
+    #[arg(long, value_enum, value_name = "RESULT")]
+    previously: Option<PreviousResultOpt>,
+
     /// Filter test binaries by build platform (DEPRECATED)
     ///
     /// Instead, use -E with 'platform(host)' or 'platform(target)'.
@@ -636,29 +808,84 @@ impl TestBuildFilter {
             workspace_root,
             env,
             ecx,
-            if self.ignore_default_filter {
-                FilterBound::All
-            } else {
-                FilterBound::DefaultSet
-            },
+            self.filter_bound(),
             // TODO: do we need to allow customizing this?
             get_num_cpus(),
         )
         .map_err(|err| ExpectedError::CreateTestListError { err })
     }
 
-    fn make_test_filter_builder(&self, filter_exprs: Vec<Filterset>) -> Result<TestFilterBuilder> {
+    fn filter_bound(&self) -> FilterBound {
+        if self.ignore_default_filter {
+            FilterBound::All
+        } else {
+            FilterBound::DefaultSet
+        }
+    }
+
+    fn make_test_filter_builder(
+        &self,
+        filter_exprs: Vec<Filterset>,
+        previous: Option<PreviousResultFilter>,
+    ) -> Result<TestFilterBuilder> {
         // Merge the test binary args into the patterns.
         let mut run_ignored = self.run_ignored.map(Into::into);
         let mut patterns = TestFilterPatterns::new(self.pre_double_dash_filters.clone());
         self.merge_test_binary_args(&mut run_ignored, &mut patterns)?;
 
-        Ok(TestFilterBuilder::new(
+        let mut builder = TestFilterBuilder::new(
             run_ignored.unwrap_or_default(),
             self.partition.clone(),
             patterns,
             filter_exprs,
-        )?)
+        )?;
+        if let Some(previous) = previous {
+            builder.set_previous_results(previous);
+        }
+        Ok(builder)
+    }
+
+    /// Loads the results of the previous run, if `--previously` was passed in.
+    fn previous_result_filter(
+        &self,
+        profile: &EarlyProfile<'_>,
+    ) -> Result<Option<PreviousResultFilter>> {
+        let Some(previously) = self.previously else {
+            return Ok(None);
+        };
+        let store = TimingsStore::load(profile.store_dir())?;
+        match PreviousResultFilter::new(&store, previously.into()) {
+            Some(previous) => Ok(Some(previous)),
+            None => Err(ExpectedError::NoPreviousRun {
+                profile_name: profile.name().to_owned(),
+            }),
+        }
+    }
+
+    /// Warns about tests skipped by `--previously` because they weren't run in the previous run.
+    fn warn_absent_from_previous(
+        &self,
+        previous: Option<&PreviousResultFilter>,
+        test_list: &TestList<'_>,
+    ) {
+        let Some(previous) = previous else {
+            return;
+        };
+        let absent = previous.absent_tests(test_list);
+        if absent.is_empty() {
+            return;
+        }
+        let tests_str = if absent.len() == 1 { "test" } else { "tests" };
+        warn!(
+            "{} {tests_str} skipped by --previously: not run in the previous run",
+            absent.len(),
+        );
+        for test in &absent {
+            debug!(
+                "not in the previous run: {} {}",
+                test.binary_id, test.test_name
+            );
+        }
     }
 
     fn merge_test_binary_args(
@@ -863,6 +1090,11 @@ pub struct TestRunnerOpts {
     )]
     max_fail: Option<MaxFail>,
 
+    /// Count setup script failures towards --max-fail, rather than cancelling the run on the
+    /// first one
+    #[arg(long, conflicts_with = "no-run")]
+    count_setup_script_failures: bool,
+
     /// Behavior if there are no tests to run [default: fail]
     #[arg(
         long,
@@ -873,6 +1105,183 @@ pub struct TestRunnerOpts {
         env = "NEXTEST_NO_TESTS"
     )]
     no_tests: Option<NoTestsBehavior>,
+
+    /// Run each passing test a second time and report tests whose output differs
+    #[arg(
+        long,
+        conflicts_with_all = &["no-run", "no-capture"],
+    )]
+    check_determinism: bool,
+
+    /// Run each test that fails all its attempts once more, and report whether the failure
+    /// reproduced
+    ///
+    /// With `count-flaky`, tests whose failure doesn't reproduce are counted as flaky rather
+    /// than failed.
+    #[arg(
+        long,
+        value_enum,
+        conflicts_with = "no-run",
+        value_name = "MODE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "report",
+        env = "NEXTEST_VERIFY_FAILURES"
+    )]
+    verify_failures: Option<VerifyFailuresOpt>,
+
+    /// Run each passing test again, up to N times in all, until it fails
+    ///
+    /// Useful for reproducing flaky tests. Each iteration after the first is reported as a retry,
+    /// and failing tests aren't retried.
+    #[arg(
+        long,
+        value_name = "N",
+        conflicts_with_all = &["no-run", "retries"],
+        require_equals = true,
+    )]
+    repeat_until_failure: Option<NonZeroUsize>,
+
+    /// With --repeat-until-failure, stop starting new iterations of a test after this long
+    #[arg(
+        long,
+        value_name = "DURATION",
+        requires = "repeat_until_failure",
+        require_equals = true,
+        value_parser = humantime::parse_duration,
+    )]
+    repeat_max_duration: Option<Duration>,
+
+    /// Shuffle the order tests are started in [possible values: integer seed or "random"]
+    ///
+    /// Tests are shuffled after filtering and partitioning, so each shard still runs the same
+    /// tests. The seed is printed at the start of the run, so a failing order can be reproduced.
+    #[arg(
+        long,
+        value_name = "SEED",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "random",
+        conflicts_with_all = &["no-run", "plan"],
+    )]
+    shuffle: Option<Shuffle>,
+
+    /// Set environment variables for tests from this file
+    ///
+    /// Each line is either KEY=VALUE, or KEY<<DELIM followed by a multi-line value ending at a
+    /// line consisting of exactly DELIM. Keys beginning with NEXTEST are reserved. Variables in
+    /// this file override the environment nextest is run in, and are overridden by setup scripts.
+    #[arg(long, value_name = "PATH", conflicts_with = "no-run")]
+    env_file: Option<Utf8PathBuf>,
+
+    /// Run the tests in a test list produced by `cargo nextest list --message-format json`,
+    /// rather than building and listing tests
+    ///
+    /// Pass `-` to read the test list from standard input. The test binaries in the list must
+    /// still exist on disk, and filters are taken from the list as-is.
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = &[
+            "no-run",
+            "run_ignored",
+            "partition",
+            "previously",
+            "filterset",
+            "ignore_default_filter",
+            "FILTERS",
+            "filters",
+        ],
+    )]
+    test_list_from: Option<Utf8PathBuf>,
+
+    /// Run tests with another profile once the previous run finishes
+    ///
+    /// May be specified multiple times. Profiles are run in order, starting with the profile
+    /// selected by `--profile`, and nextest exits with the code for the worst of the runs.
+    #[arg(long, value_name = "PROFILE", conflicts_with = "no-run")]
+    then_profile: Vec<String>,
+
+    /// What to do if a run fails when running several profiles [default: stop]
+    #[arg(
+        long,
+        value_enum,
+        value_name = "ACTION",
+        requires = "then_profile",
+        require_equals = true
+    )]
+    on_profile_failure: Option<OnProfileFailure>,
+
+    /// Print the tests that would be run and their settings, without running anything
+    ///
+    /// The plan includes the setup scripts that would be run, the tests in the order they would be
+    /// started in along with their retries, timeouts and test groups, and an estimate of how long
+    /// the run would take based on recorded timings. [default format: human]
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "human",
+        conflicts_with_all = ["no-run", "then_profile"],
+    )]
+    plan: Option<PlanFormat>,
+}
+
+/// Where the tests for `cargo nextest run` come from.
+enum TestListSource<'g> {
+    /// Build and list test binaries.
+    Build(Arc<BinaryList>),
+
+    /// A test list read in through `--test-list-from`.
+    Summary(Box<TestList<'g>>),
+}
+
+impl TestListSource<'_> {
+    fn build_platforms(&self) -> &BuildPlatforms {
+        match self {
+            Self::Build(binary_list) => &binary_list.rust_build_meta.build_platforms,
+            Self::Summary(test_list) => &test_list.rust_build_meta().build_platforms,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum PlanFormat {
+    /// A human-readable summary.
+    Human,
+
+    /// JSON, for consumption by other tools.
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+enum OnProfileFailure {
+    /// Don't run the remaining profiles.
+    #[default]
+    Stop,
+
+    /// Run the remaining profiles, unless the run was interrupted by a signal.
+    Continue,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum VerifyFailuresOpt {
+    /// Report whether the failure reproduced, but count the test as failed either way.
+    Report,
+
+    /// Count tests whose failure doesn't reproduce as flaky.
+    CountFlaky,
+}
+
+impl From<VerifyFailuresOpt> for VerifyFailures {
+    fn from(opt: VerifyFailuresOpt) -> Self {
+        match opt {
+            VerifyFailuresOpt::Report => VerifyFailures::Report,
+            VerifyFailuresOpt::CountFlaky => VerifyFailures::CountFlaky,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, ValueEnum)]
@@ -913,11 +1322,31 @@ impl TestRunnerOpts {
             builder.set_max_fail(MaxFail::from_fail_fast(true));
             debug!("set max fail via from_fail_fast(true)");
         }
+        builder.set_count_setup_script_failures(self.count_setup_script_failures);
 
         if let Some(test_threads) = self.test_threads {
             builder.set_test_threads(test_threads);
         }
 
+        if self.check_determinism {
+            builder.set_determinism_check(DeterminismCheck::new());
+        }
+
+        if let Some(verify_failures) = self.verify_failures {
+            builder.set_verify_failures(verify_failures.into());
+        }
+
+        if let Some(max_iterations) = self.repeat_until_failure {
+            builder.set_repeat_until_failure(RepeatUntilFailure {
+                max_iterations,
+                max_duration: self.repeat_max_duration,
+            });
+        }
+
+        if let Some(shuffle) = self.shuffle {
+            builder.set_shuffle(shuffle);
+        }
+
         Some(builder)
     }
 }
@@ -928,18 +1357,6 @@ enum IgnoreOverridesOpt {
     All,
 }
 
-#[derive(Clone, Copy, Debug, ValueEnum, Default)]
-enum MessageFormat {
-    /// The default output format.
-    #[default]
-    Human,
-    /// Output test information in the same format as libtest.
-    LibtestJson,
-    /// Output test information in the same format as libtest, with a `nextest` subobject that
-    /// includes additional metadata.
-    LibtestJsonPlus,
-}
-
 #[derive(Debug, Default, Args)]
 #[command(next_help_heading = "Reporter options")]
 struct ReporterOpts {
@@ -963,6 +1380,19 @@ struct ReporterOpts {
     )]
     success_output: Option<TestOutputDisplayOpt>,
 
+    /// Strip or preserve ANSI escapes when displaying captured test output
+    ///
+    /// This is independent of `--color`, which controls nextest's own output. It doesn't affect
+    /// JUnit reports, which never contain escapes.
+    #[arg(
+        long,
+        value_enum,
+        conflicts_with_all = &["no-capture", "no-run"],
+        value_name = "WHEN",
+        env = "NEXTEST_DISPLAY_ANSI_ESCAPES",
+    )]
+    display_ansi_escapes: Option<AnsiEscapesOpt>,
+
     // status_level does not conflict with --no-capture because pass vs skip still makes sense.
     /// Test statuses to output
     #[arg(
@@ -988,6 +1418,17 @@ struct ReporterOpts {
     #[arg(long, env = "NEXTEST_HIDE_PROGRESS_BAR", value_parser = BoolishValueParser::new())]
     hide_progress_bar: bool,
 
+    /// Write run statistics to this path in the Prometheus text format.
+    ///
+    /// The file is written at the end of the run, and replaced atomically.
+    #[arg(
+        long,
+        conflicts_with = "no-run",
+        value_name = "PATH",
+        env = "NEXTEST_PROMETHEUS_PATH"
+    )]
+    prometheus_path: Option<Utf8PathBuf>,
+
     /// Disable handling of input keys from the terminal.
     ///
     /// By default, when running a terminal, nextest accepts the `t` key to dump
@@ -996,16 +1437,20 @@ struct ReporterOpts {
     no_input_handler: bool,
 
     /// Format to use for test results (experimental).
+    ///
+    /// Built-in formats are `human` (the default), `libtest-json`, `libtest-json-plus`, `jsonl`,
+    /// `tap`, and `binary-event-log`.
+    /// Programs that embed nextest may register additional formats.
     #[arg(
         long,
         name = "message-format",
-        value_enum,
-        default_value_t,
+        default_value = FormatterRegistry::HUMAN,
+        value_parser = message_format_parser(&FormatterRegistry::new()),
         conflicts_with = "no-run",
         value_name = "FORMAT",
         env = "NEXTEST_MESSAGE_FORMAT"
     )]
-    message_format: MessageFormat,
+    message_format: String,
 
     /// Version of structured message-format to use (experimental).
     ///
@@ -1021,6 +1466,15 @@ struct ReporterOpts {
     message_format_version: Option<String>,
 }
 
+/// Returns a parser for `--message-format` that accepts the formats in `formatters`.
+fn message_format_parser(formatters: &FormatterRegistry) -> PossibleValuesParser {
+    PossibleValuesParser::new(
+        formatters
+            .names()
+            .map(|name| PossibleValue::new(name.to_owned())),
+    )
+}
+
 impl ReporterOpts {
     fn to_builder(&self, no_capture: bool, should_colorize: bool) -> ReporterBuilder {
         let mut builder = ReporterBuilder::default();
@@ -1033,6 +1487,9 @@ impl ReporterOpts {
         if let Some(success_output) = self.success_output {
             builder.set_success_output(success_output.into());
         }
+        if let Some(display_ansi_escapes) = self.display_ansi_escapes {
+            builder.set_display_ansi_escapes(display_ansi_escapes.into());
+        }
         if let Some(status_level) = self.status_level {
             builder.set_status_level(status_level.into());
         }
@@ -1040,6 +1497,9 @@ impl ReporterOpts {
             builder.set_final_status_level(final_status_level.into());
         }
         builder.set_hide_progress_bar(self.hide_progress_bar);
+        if let Some(prometheus_path) = &self.prometheus_path {
+            builder.set_prometheus_path(prometheus_path.clone());
+        }
         builder
     }
 }
@@ -1063,6 +1523,23 @@ impl From<TestOutputDisplayOpt> for TestOutputDisplay {
     }
 }
 
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum AnsiEscapesOpt {
+    Auto,
+    Strip,
+    Preserve,
+}
+
+impl From<AnsiEscapesOpt> for AnsiEscapes {
+    fn from(opt: AnsiEscapesOpt) -> Self {
+        match opt {
+            AnsiEscapesOpt::Auto => AnsiEscapes::Auto,
+            AnsiEscapesOpt::Strip => AnsiEscapes::Strip,
+            AnsiEscapesOpt::Preserve => AnsiEscapes::Preserve,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, ValueEnum)]
 enum StatusLevelOpt {
     None,
@@ -1516,6 +1993,14 @@ impl BaseApp {
                 NextestConfig::DEFAULT_PROFILE
             }
         });
+        self.load_named_profile(config, profile_name)
+    }
+
+    fn load_named_profile<'cfg>(
+        &self,
+        config: &'cfg NextestConfig,
+        profile_name: &str,
+    ) -> Result<EarlyProfile<'cfg>> {
         let profile = config
             .profile(profile_name)
             .map_err(ExpectedError::profile_not_found)?;
@@ -1590,9 +2075,10 @@ impl App {
         binary_list: Arc<BinaryList>,
         test_filter_builder: TestFilterBuilder,
         ecx: &EvalContext<'_>,
+        store_dir: &Utf8Path,
     ) -> Result<TestList> {
         let env = EnvironmentMap::new(&self.base.cargo_configs);
-        self.build_filter.compute_test_list(
+        let mut test_list = self.build_filter.compute_test_list(
             ctx,
             self.base.graph(),
             self.base.workspace_root.clone(),
@@ -1601,32 +2087,108 @@ impl App {
             env,
             ecx,
             &self.base.reuse_build,
+        )?;
+        // Timings-based partitioning needs the full list of tests, so it's applied here rather
+        // than while listing each binary.
+        if let Some(PartitionerBuilder::Timings {
+            shard,
+            total_shards,
+        }) = &self.build_filter.partition
+        {
+            let timings = TimingsStore::load(store_dir)?;
+            test_list.apply_timings_partition(*shard, *total_shards, &timings);
+        }
+        Ok(test_list)
+    }
+
+    fn read_test_list(&self, path: &Utf8Path) -> Result<TestList<'_>> {
+        let json = if path == "-" {
+            std::io::read_to_string(std::io::stdin().lock())
+        } else {
+            std::fs::read_to_string(path)
+        }
+        .map_err(|err| ExpectedError::TestListReadError {
+            path: path.to_owned(),
+            err,
+        })?;
+        let summary =
+            TestListSummary::parse_json(json).map_err(|err| ExpectedError::TestListParseError {
+                path: path.to_owned(),
+                err,
+            })?;
+
+        let env = EnvironmentMap::new(&self.base.cargo_configs);
+        let test_list = TestList::from_summary(
+            self.base.graph(),
+            summary,
+            self.base.workspace_root.clone(),
+            env,
         )
+        .map_err(|err| ExpectedError::CreateTestListError { err })?;
+        Ok(test_list)
     }
 
+    #[expect(clippy::too_many_arguments)]
     fn exec_list(
         &self,
         message_format: MessageFormatOpts,
         list_type: ListType,
+        with_timings: bool,
+        with_flaky_rates: bool,
+        group_by_platform: Option<PlatformOrderOpt>,
+        changed_since: Option<&str>,
+        output_file: Option<&Utf8Path>,
         output_writer: &mut OutputWriter,
     ) -> Result<()> {
+        if group_by_platform.is_some() && !matches!(message_format, MessageFormatOpts::Human) {
+            return Err(ExpectedError::GroupByPlatformNotHuman);
+        }
+
         let (version_only_config, config) = self.base.load_config()?;
         let profile = self.base.load_profile(&config)?;
         let filter_exprs = self.build_filtering_expressions()?;
-        let test_filter_builder = self.build_filter.make_test_filter_builder(filter_exprs)?;
+        let previous = self.build_filter.previous_result_filter(&profile)?;
+        let test_filter_builder = self
+            .build_filter
+            .make_test_filter_builder(filter_exprs, previous.clone())?;
 
         let binary_list = self.base.build_binary_list()?;
 
+        // Output is never colorized when written to a file.
+        let colorize = output_file.is_none()
+            && self
+                .base
+                .output
+                .color
+                .should_colorize(supports_color::Stream::Stdout);
+        let mut file_writer;
+        let mut stdout_writer;
+        let writer: &mut dyn WriteStr = match output_file {
+            Some(path) => {
+                let file = std::fs::File::create(path).map_err(|err| {
+                    ExpectedError::ListOutputFileCreateError {
+                        path: path.to_owned(),
+                        err,
+                    }
+                })?;
+                file_writer = std::io::BufWriter::new(file);
+                &mut file_writer
+            }
+            None => {
+                stdout_writer = output_writer.stdout_writer();
+                &mut stdout_writer
+            }
+        };
+
         match list_type {
             ListType::BinariesOnly => {
-                let mut writer = output_writer.stdout_writer();
+                if matches!(message_format, MessageFormatOpts::JsonSummary) {
+                    return Err(ExpectedError::JsonSummaryBinariesOnly);
+                }
                 binary_list.write(
                     message_format.to_output_format(self.base.output.verbose),
-                    &mut writer,
-                    self.base
-                        .output
-                        .color
-                        .should_colorize(supports_color::Stream::Stdout),
+                    writer,
+                    colorize,
                 )?;
                 writer.write_str_flush().map_err(WriteTestListError::Io)?;
             }
@@ -1643,18 +2205,43 @@ impl App {
                     profile.apply_build_platforms(&binary_list.rust_build_meta.build_platforms);
                 let ecx = profile.filterset_ecx();
 
-                let test_list =
-                    self.build_test_list(&ctx, binary_list, test_filter_builder, &ecx)?;
-
-                let mut writer = output_writer.stdout_writer();
-                test_list.write(
-                    message_format.to_output_format(self.base.output.verbose),
-                    &mut writer,
-                    self.base
-                        .output
-                        .color
-                        .should_colorize(supports_color::Stream::Stdout),
+                let mut test_list = self.build_test_list(
+                    &ctx,
+                    binary_list,
+                    test_filter_builder,
+                    &ecx,
+                    profile.store_dir(),
                 )?;
+                self.build_filter
+                    .warn_absent_from_previous(previous.as_ref(), &test_list);
+                if with_timings || with_flaky_rates {
+                    let timings = TimingsStore::load(profile.store_dir())?;
+                    if with_timings {
+                        test_list.apply_timings(&timings);
+                    }
+                    if with_flaky_rates {
+                        test_list.apply_flaky_rates(&timings);
+                    }
+                }
+                if let Some(base_ref) = changed_since {
+                    let changed = ChangedFiles::from_git(test_list.workspace_root(), base_ref)?;
+                    test_list.retain_affected(&changed);
+                }
+
+                let verbose = self.base.output.verbose;
+                match (message_format, group_by_platform) {
+                    (MessageFormatOpts::JsonSummary, _) => {
+                        SerializableFormat::Json.to_writer(&test_list.to_count_summary(), writer)?
+                    }
+                    (MessageFormatOpts::Human, Some(order)) => test_list
+                        .write_human_by_platform(order.first_platform(), writer, verbose, colorize)
+                        .map_err(WriteTestListError::Io)?,
+                    (message_format, _) => test_list.write(
+                        message_format.to_output_format(verbose),
+                        writer,
+                        colorize,
+                    )?,
+                }
                 writer.write_str_flush().map_err(WriteTestListError::Io)?;
             }
         }
@@ -1683,7 +2270,10 @@ impl App {
         let settings = ShowTestGroupSettings { mode, show_default };
 
         let filter_exprs = self.build_filtering_expressions()?;
-        let test_filter_builder = self.build_filter.make_test_filter_builder(filter_exprs)?;
+        let previous = self.build_filter.previous_result_filter(&profile)?;
+        let test_filter_builder = self
+            .build_filter
+            .make_test_filter_builder(filter_exprs, previous.clone())?;
 
         let binary_list = self.base.build_binary_list()?;
         let build_platforms = binary_list.rust_build_meta.build_platforms.clone();
@@ -1697,7 +2287,15 @@ impl App {
         let profile = profile.apply_build_platforms(&build_platforms);
         let ecx = profile.filterset_ecx();
 
-        let test_list = self.build_test_list(&ctx, binary_list, test_filter_builder, &ecx)?;
+        let test_list = self.build_test_list(
+            &ctx,
+            binary_list,
+            test_filter_builder,
+            &ecx,
+            profile.store_dir(),
+        )?;
+        self.build_filter
+            .warn_absent_from_previous(previous.as_ref(), &test_list);
 
         let mut writer = output_writer.stdout_writer();
 
@@ -1716,58 +2314,66 @@ impl App {
         Ok(())
     }
 
+    #[expect(clippy::too_many_arguments)]
     fn exec_run(
         &self,
         no_capture: bool,
+        changed_since: Option<&str>,
         runner_opts: &TestRunnerOpts,
         reporter_opts: &ReporterOpts,
+        formatters: &FormatterRegistry,
         cli_args: Vec<String>,
         output_writer: &mut OutputWriter,
     ) -> Result<i32> {
         let (version_only_config, config) = self.base.load_config()?;
-        let profile = self.base.load_profile(&config)?;
-
-        // Construct this here so that errors are reported before the build step.
-        let mut structured_reporter = structured::StructuredReporter::new();
-        match reporter_opts.message_format {
-            MessageFormat::Human => {}
-            MessageFormat::LibtestJson | MessageFormat::LibtestJsonPlus => {
-                // This is currently an experimental feature, and is gated on this environment
-                // variable.
-                const EXPERIMENTAL_ENV: &str = "NEXTEST_EXPERIMENTAL_LIBTEST_JSON";
-                if std::env::var(EXPERIMENTAL_ENV).as_deref() != Ok("1") {
-                    return Err(ExpectedError::ExperimentalFeatureNotEnabled {
-                        name: "libtest JSON output",
-                        var_name: EXPERIMENTAL_ENV,
-                    });
-                }
+        let mut profiles = vec![self.base.load_profile(&config)?];
+        // Load any further profiles here as well, so that errors are reported before the build
+        // step.
+        for profile_name in &runner_opts.then_profile {
+            profiles.push(self.base.load_named_profile(&config, profile_name)?);
+        }
 
-                let libtest = structured::LibtestReporter::new(
-                    reporter_opts.message_format_version.as_deref(),
-                    if matches!(reporter_opts.message_format, MessageFormat::LibtestJsonPlus) {
-                        structured::EmitNextestObject::Yes
-                    } else {
-                        structured::EmitNextestObject::No
-                    },
-                )?;
-                structured_reporter.set_libtest(libtest);
+        // Look this up here so that errors are reported before the build step.
+        let message_format = formatters.get(&reporter_opts.message_format)?;
+        if matches!(
+            message_format.name(),
+            FormatterRegistry::LIBTEST_JSON | FormatterRegistry::LIBTEST_JSON_PLUS
+        ) {
+            // This is currently an experimental feature, and is gated on this environment
+            // variable.
+            const EXPERIMENTAL_ENV: &str = "NEXTEST_EXPERIMENTAL_LIBTEST_JSON";
+            if std::env::var(EXPERIMENTAL_ENV).as_deref() != Ok("1") {
+                return Err(ExpectedError::ExperimentalFeatureNotEnabled {
+                    name: "libtest JSON output",
+                    var_name: EXPERIMENTAL_ENV,
+                });
             }
-        };
+        }
+
         use nextest_runner::test_output::CaptureStrategy;
 
         let cap_strat = if no_capture {
             CaptureStrategy::None
-        } else if matches!(reporter_opts.message_format, MessageFormat::Human) {
+        } else if message_format.is_human() {
             CaptureStrategy::Split
         } else {
             CaptureStrategy::Combined
         };
 
         let filter_exprs = self.build_filtering_expressions()?;
-        let test_filter_builder = self.build_filter.make_test_filter_builder(filter_exprs)?;
+        let mut previous_filters = Vec::with_capacity(profiles.len());
+        for profile in &profiles {
+            previous_filters.push(self.build_filter.previous_result_filter(profile)?);
+        }
 
-        let binary_list = self.base.build_binary_list()?;
-        let build_platforms = &binary_list.rust_build_meta.build_platforms.clone();
+        let test_list_source = match &runner_opts.test_list_from {
+            Some(path) => TestListSource::Summary(Box::new(self.read_test_list(path)?)),
+            None => TestListSource::Build(self.base.build_binary_list()?),
+        };
+        let build_platforms = &test_list_source.build_platforms().clone();
+        let changed = changed_since
+            .map(|base_ref| ChangedFiles::from_git(&self.base.workspace_root, base_ref))
+            .transpose()?;
         let double_spawn = self.base.load_double_spawn();
         let target_runner = self.base.load_runner(build_platforms);
         let ctx = TestExecuteContext {
@@ -1775,62 +2381,223 @@ impl App {
             target_runner,
         };
 
-        let profile = profile.apply_build_platforms(build_platforms);
-        let ecx = profile.filterset_ecx();
-
-        let test_list = self.build_test_list(&ctx, binary_list, test_filter_builder, &ecx)?;
-
-        let output = output_writer.reporter_output();
         let should_colorize = self
             .base
             .output
             .color
             .should_colorize(supports_color::Stream::Stderr);
+        let on_profile_failure = runner_opts.on_profile_failure.unwrap_or_default();
+        let profile_count = profiles.len();
+
+        // Build the test lists for all profiles up front. Events from every run are written to
+        // the same structured reporter, so the test lists must outlive it.
+        let mut runs = Vec::with_capacity(profile_count);
+        for (profile, previous) in profiles.into_iter().zip(previous_filters) {
+            let test_filter_builder = self
+                .build_filter
+                .make_test_filter_builder(filter_exprs.clone(), previous.clone())?;
+            let profile = profile.apply_build_platforms(build_platforms);
+            let ecx = profile.filterset_ecx();
+
+            let filter_summary = test_filter_builder
+                .summary(self.build_filter.filter_bound(), profile.default_filter());
+            let mut test_list = match &test_list_source {
+                TestListSource::Build(binary_list) => self.build_test_list(
+                    &ctx,
+                    binary_list.clone(),
+                    test_filter_builder,
+                    &ecx,
+                    profile.store_dir(),
+                )?,
+                TestListSource::Summary(test_list) => (**test_list).clone(),
+            };
+            self.build_filter
+                .warn_absent_from_previous(previous.as_ref(), &test_list);
+            if let Some(changed) = &changed {
+                test_list.skip_unaffected(changed);
+            }
+            runs.push((profile, test_list, filter_summary));
+        }
 
-        let signal_handler = SignalHandlerKind::Standard;
-        let input_handler = if reporter_opts.no_input_handler {
-            InputHandlerKind::Noop
-        } else {
-            // This means that the input handler determines whether it should be
-            // enabled.
-            InputHandlerKind::Standard
-        };
+        if runner_opts.no_run {
+            return Ok(0);
+        }
 
-        // Make the runner.
-        let runner_builder = match runner_opts.to_builder(cap_strat) {
-            Some(runner_builder) => runner_builder,
-            None => {
-                // This means --no-run was passed in. Exit.
-                return Ok(0);
+        if let Some(plan_format) = runner_opts.plan {
+            // --plan conflicts with --then-profile, so there's only one profile.
+            let (profile, test_list, _) = &runs[0];
+            let runner_builder = runner_opts
+                .to_builder(cap_strat)
+                .expect("--plan conflicts with --no-run");
+            let timings = TimingsStore::load(profile.store_dir())?;
+            let plan = runner_builder.plan(test_list, profile, &timings);
+            let mut writer = output_writer.stdout_writer();
+            match plan_format {
+                PlanFormat::Human => plan
+                    .write_human(
+                        &mut writer,
+                        self.base
+                            .output
+                            .color
+                            .should_colorize(supports_color::Stream::Stdout),
+                    )
+                    .map_err(WriteTestListError::Io)?,
+                PlanFormat::Json => plan.write_json(&mut writer)?,
             }
-        };
+            writer.write_str_flush().map_err(WriteTestListError::Io)?;
+            self.base
+                .check_version_config_final(version_only_config.nextest_version())?;
+            return Ok(0);
+        }
 
-        let runner = runner_builder.build(
-            &test_list,
-            &profile,
-            cli_args,
-            signal_handler,
-            input_handler,
-            double_spawn.clone(),
-            target_runner.clone(),
-        )?;
+        // Make the structured reporter once, so that formats with a header only write it once
+        // even if several profiles are run.
+        let mut structured_reporter = structured::StructuredReporter::new();
+        if let Some(formatter) = message_format.create(&FormatterArgs::new(
+            reporter_opts.message_format_version.as_deref(),
+        ))? {
+            structured_reporter.set_reporter(formatter);
+        }
+        let mut output = output_writer.reporter_output();
+
+        let mut aggregate = AggregateRunStats::new();
+        let mut min_tests_checks = Vec::with_capacity(profile_count);
+        let mut teardown_checks = Vec::with_capacity(profile_count);
+        for (index, (profile, test_list, filter_summary)) in runs.iter().enumerate() {
+            let signal_handler = SignalHandlerKind::Standard;
+            let input_handler = if reporter_opts.no_input_handler {
+                InputHandlerKind::Noop
+            } else {
+                // This means that the input handler determines whether it should be
+                // enabled.
+                InputHandlerKind::Standard
+            };
 
-        // Make the reporter.
-        let mut reporter = reporter_opts
-            .to_builder(no_capture, should_colorize)
-            .set_verbose(self.base.output.verbose)
-            .build(&test_list, &profile, output, structured_reporter);
+            // Make the runner.
+            let mut runner_builder = runner_opts
+                .to_builder(cap_strat)
+                .expect("--no-run was handled above");
+            runner_builder.set_filter_summary(filter_summary.clone());
+            if let Some(env_file) = &runner_opts.env_file {
+                let env_map =
+                    parse_env_file(env_file).map_err(|err| ExpectedError::EnvFileError { err })?;
+                runner_builder.set_extra_env(SetupScriptEnvMap { env_map });
+            }
+
+            let runner = runner_builder.build(
+                test_list,
+                profile,
+                cli_args.clone(),
+                signal_handler,
+                input_handler,
+                double_spawn.clone(),
+                target_runner.clone(),
+            )?;
+
+            // Make the reporter.
+            let mut reporter = reporter_opts
+                .to_builder(no_capture, should_colorize)
+                .set_verbose(self.base.output.verbose)
+                .build(test_list, profile, output, structured_reporter);
+
+            configure_handle_inheritance(no_capture)?;
+            let mut elapsed = std::time::Duration::ZERO;
+            let mut interrupted = false;
+            let run_stats = runner.try_execute(|event| {
+                match &event.kind {
+                    TestEventKind::RunBeginCancel { reason, .. } => {
+                        interrupted |= matches!(
+                            reason,
+                            CancelReason::Signal
+                                | CancelReason::Interrupt
+                                | CancelReason::SecondSignal
+                        );
+                    }
+                    TestEventKind::RunFinished {
+                        elapsed: run_elapsed,
+                        ..
+                    } => {
+                        elapsed = *run_elapsed;
+                    }
+                    _ => {}
+                }
+                // Write and flush the event.
+                reporter.report_event(event)
+            })?;
+            (output, structured_reporter) = reporter.finish_into_outputs();
+
+            aggregate.push(profile.name(), elapsed, run_stats);
+            min_tests_checks.push((profile.name().to_owned(), profile.min_tests()));
+            teardown_checks.push(profile.on_teardown_failure());
+
+            let remaining = profile_count - index - 1;
+            if remaining > 0 {
+                let failed = !matches!(
+                    run_stats.summarize_final(),
+                    FinalRunStats::Success | FinalRunStats::NoTestsRun
+                );
+                if interrupted || (failed && on_profile_failure == OnProfileFailure::Stop) {
+                    warn!(
+                        "not running {remaining} remaining {} after profile `{}` {}",
+                        if remaining == 1 {
+                            "profile"
+                        } else {
+                            "profiles"
+                        },
+                        profile.name(),
+                        if interrupted {
+                            "was interrupted"
+                        } else {
+                            "failed"
+                        },
+                    );
+                    break;
+                }
+            }
+        }
+
+        if profile_count > 1 {
+            structured_reporter
+                .write_aggregate(&aggregate)
+                .map_err(|err| ExpectedError::WriteEventError { err })?;
+            write_aggregate_summary(&aggregate, should_colorize, output).map_err(|err| {
+                ExpectedError::WriteEventError {
+                    err: WriteEventError::Io(err),
+                }
+            })?;
+        }
 
-        configure_handle_inheritance(no_capture)?;
-        let run_stats = runner.try_execute(|event| {
-            // Write and flush the event.
-            reporter.report_event(event)
-        })?;
-        reporter.finish();
         self.base
             .check_version_config_final(version_only_config.nextest_version())?;
 
-        match run_stats.summarize_final() {
+        let final_stats = aggregate.summarize_final();
+        if matches!(
+            final_stats,
+            FinalRunStats::Success | FinalRunStats::NoTestsRun
+        ) {
+            for (run, (profile_name, min_tests)) in aggregate.runs().iter().zip(min_tests_checks) {
+                if !run.run_stats.meets_min_tests(min_tests) {
+                    return Err(ExpectedError::TooFewTestsRun {
+                        profile_name,
+                        finished_count: run.run_stats.finished_count,
+                        min_tests,
+                    });
+                }
+            }
+            // Teardown script failures only fail a run that otherwise succeeded if configured to.
+            for (run, on_teardown_failure) in aggregate.runs().iter().zip(teardown_checks) {
+                if on_teardown_failure == OnTeardownFailure::Fail
+                    && run.run_stats.teardown_scripts_failed > 0
+                {
+                    return Err(ExpectedError::TeardownScriptFailed {
+                        profile_name: run.profile_name.clone(),
+                        failed_count: run.run_stats.teardown_scripts_failed,
+                    });
+                }
+            }
+        }
+
+        match final_stats {
             FinalRunStats::Success => Ok(0),
             FinalRunStats::NoTestsRun => match runner_opts.no_tests {
                 Some(NoTestsBehavior::Pass) => Ok(0),
@@ -1847,7 +2614,14 @@ impl App {
             }
             FinalRunStats::Cancelled(RunStatsFailureKind::Test { .. })
             | FinalRunStats::Failed(RunStatsFailureKind::Test { .. }) => {
-                Err(ExpectedError::test_run_failed())
+                let smoke_failed = aggregate
+                    .worst_run()
+                    .is_some_and(|run| run.run_stats.smoke_failed);
+                if smoke_failed {
+                    Err(ExpectedError::SmokeTestsFailed)
+                } else {
+                    Err(ExpectedError::test_run_failed())
+                }
             }
         }
     }
@@ -2098,6 +2872,27 @@ enum DebugCommand {
         output_format: ExtractOutputFormat,
     },
 
+    /// Convert a binary event log to line-delimited JSON.
+    ///
+    /// Binary event logs are produced by `--message-format binary-event-log`. The JSON is written
+    /// to standard output, one line per record.
+    ConvertEventLog {
+        /// The path to the binary event log.
+        input: Utf8PathBuf,
+    },
+
+    /// Show the tests whose output contains all the terms in a query.
+    ///
+    /// This reads the output index written to per-run artifacts if `index-output` is enabled. The
+    /// matching tests are written to standard output, one per line.
+    SearchOutput {
+        /// The artifacts directory for the run.
+        run_dir: Utf8PathBuf,
+
+        /// The terms to search for.
+        query: String,
+    },
+
     /// Print the current executable path.
     CurrentExe,
 
@@ -2166,6 +2961,21 @@ impl DebugCommand {
                     display_output_slice(output_slice, output_format)?;
                 }
             }
+            DebugCommand::ConvertEventLog { input } => {
+                let convert = || {
+                    let file = std::fs::File::open(&input).map_err(EventLogError::Io)?;
+                    convert_event_log_to_ndjson(file, std::io::stdout().lock())
+                };
+                convert()
+                    .map_err(|err| ExpectedError::DebugConvertEventLogError { path: input, err })?;
+            }
+            DebugCommand::SearchOutput { run_dir, query } => {
+                let index = OutputIndex::read_from_run_dir(&run_dir)
+                    .map_err(|err| ExpectedError::DebugOutputIndexError { err })?;
+                for test in index.query(&query) {
+                    println!("{}", test.id());
+                }
+            }
             DebugCommand::CurrentExe => {
                 let exe = std::env::current_exe()
                     .map_err(|err| ExpectedError::GetCurrentExeFailed { err })?;
@@ -2390,6 +3200,8 @@ mod tests {
             "cargo nextest list --list-type binaries-only",
             "cargo nextest list --list-type full",
             "cargo nextest list --message-format json-pretty",
+            "cargo nextest list --with-timings",
+            "cargo nextest list --with-flaky-rates --with-timings",
             "cargo nextest run --failure-output never",
             "cargo nextest run --success-output=immediate",
             "cargo nextest run --status-level=all",
@@ -2399,6 +3211,14 @@ mod tests {
             "cargo nextest run --final-status-level flaky",
             // retry is an alias for flaky -- ensure that it parses
             "cargo nextest run --final-status-level retry",
+            "cargo nextest run --check-determinism",
+            "cargo nextest run --verify-failures",
+            "cargo nextest run --verify-failures=count-flaky",
+            "cargo nextest run --shuffle",
+            "cargo nextest run --shuffle=12345",
+            "cargo nextest run --shuffle=random",
+            "cargo nextest run --message-format jsonl --message-format-version 1.0",
+            "cargo ntr --message-format tap",
             "NEXTEST_HIDE_PROGRESS_BAR=1 cargo nextest run",
             "NEXTEST_HIDE_PROGRESS_BAR=true cargo nextest run",
             // ---
@@ -2463,6 +3283,22 @@ mod tests {
             // ---
             ("cargo nextest run --no-run -j8", ArgumentConflict),
             ("cargo nextest run --no-run --retries 3", ArgumentConflict),
+            (
+                "cargo nextest run --no-run --check-determinism",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest run --no-capture --check-determinism",
+                ArgumentConflict,
+            ),
+            (
+                "cargo nextest run --no-run --verify-failures",
+                ArgumentConflict,
+            ),
+            ("cargo nextest run --verify-failures=always", InvalidValue),
+            ("cargo nextest run --no-run --shuffle", ArgumentConflict),
+            ("cargo nextest run --plan --shuffle=1", ArgumentConflict),
+            ("cargo nextest run --shuffle=abc", ValueValidation),
             ("cargo nextest run --no-run --fail-fast", ArgumentConflict),
             (
                 "cargo nextest run --no-run --no-fail-fast",
@@ -2564,6 +3400,12 @@ mod tests {
             ),
             // Invalid test threads: 0
             ("cargo nextest run --jobs 0", ValueValidation),
+            // Unknown message formats
+            (
+                "cargo nextest run --message-format my-formatter",
+                InvalidValue,
+            ),
+            ("cargo ntr --message-format my-formatter", InvalidValue),
             // Test threads must be a number
             ("cargo nextest run --jobs -twenty", UnknownArgument),
             ("cargo nextest run --build-jobs -inf1", UnknownArgument),
@@ -2624,6 +3466,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_message_format_parsing_with_formatters() {
+        fn unused<'a>(
+            _args: &FormatterArgs<'a>,
+        ) -> Result<
+            Box<dyn structured::TestReporter<'a> + 'a>,
+            Box<dyn std::error::Error + Send + Sync>,
+        > {
+            Err("not used in this test".into())
+        }
+
+        let mut formatters = FormatterRegistry::new();
+        formatters.register("my-formatter", unused);
+
+        for cmd in [
+            "cargo nextest run --message-format my-formatter",
+            "cargo ntr --message-format my-formatter",
+            "cargo nextest run --message-format jsonl",
+        ] {
+            let matches = CargoNextestApp::command_with_formatters(&formatters)
+                .try_get_matches_from(shell_words::split(cmd).expect("valid command line"))
+                .unwrap_or_else(|error| panic!("{cmd} should have successfully parsed: {error}"));
+            CargoNextestApp::from_arg_matches(&matches)
+                .unwrap_or_else(|error| panic!("{cmd} should have been converted: {error}"));
+        }
+
+        let error = CargoNextestApp::command_with_formatters(&formatters)
+            .try_get_matches_from(["cargo", "nextest", "run", "--message-format", "unknown"])
+            .expect_err("unknown formats are rejected");
+        assert_eq!(error.kind(), clap::error::ErrorKind::InvalidValue);
+    }
+
     #[derive(Debug, Parser)]
     struct TestCli {
         #[structopt(flatten)]
@@ -2635,7 +3509,7 @@ mod tests {
         fn get_test_filter_builder(cmd: &str) -> Result<TestFilterBuilder> {
             let app = TestCli::try_parse_from(shell_words::split(cmd).expect("valid command line"))
                 .unwrap_or_else(|_| panic!("{cmd} should have successfully parsed"));
-            app.build_filter.make_test_filter_builder(vec![])
+            app.build_filter.make_test_filter_builder(vec![], None)
         }
 
         let valid = &[
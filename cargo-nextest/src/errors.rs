@@ -5,7 +5,7 @@ use crate::{output::StderrStyles, ExtractOutputFormat};
 use camino::Utf8PathBuf;
 use itertools::Itertools;
 use nextest_filtering::errors::FiltersetParseErrors;
-use nextest_metadata::NextestExitCode;
+use nextest_metadata::{NextestExitCode, TestListParseError};
 use nextest_runner::{errors::*, redact::Redactor};
 use owo_colors::OwoColorize;
 use semver::Version;
@@ -167,6 +167,12 @@ pub enum ExpectedError {
         #[from]
         err: TestRunnerBuildError,
     },
+    #[error("failed to create list output file")]
+    ListOutputFileCreateError {
+        path: Utf8PathBuf,
+        #[source]
+        err: std::io::Error,
+    },
     #[error("writing test list to output failed")]
     WriteTestListError {
         #[from]
@@ -192,16 +198,53 @@ pub enum ExpectedError {
         #[from]
         err: ShowTestGroupsError,
     },
+    #[error("timings store error")]
+    TimingsStoreError {
+        #[from]
+        err: TimingsStoreError,
+    },
+    #[error("changed files error")]
+    ChangedFilesError {
+        #[from]
+        err: ChangedFilesError,
+    },
+    #[error("no previous run recorded")]
+    NoPreviousRun {
+        /// The name of the profile.
+        profile_name: String,
+    },
+    #[error("json-summary requires a full test list")]
+    JsonSummaryBinariesOnly,
+    #[error("--group-by-platform requires human-readable output")]
+    GroupByPlatformNotHuman,
     #[error("setup script failed")]
     SetupScriptFailed,
     #[error("test run failed")]
     TestRunFailed,
+    #[error("smoke tests failed")]
+    SmokeTestsFailed,
     #[error("no tests to run")]
     NoTestsRun {
         /// The no-tests-run error was chosen because it was the default (we show a hint in this
         /// case)
         is_default: bool,
     },
+    #[error("teardown script failed")]
+    TeardownScriptFailed {
+        /// The name of the profile.
+        profile_name: String,
+        /// The number of teardown scripts that failed.
+        failed_count: usize,
+    },
+    #[error("too few tests run")]
+    TooFewTestsRun {
+        /// The name of the profile.
+        profile_name: String,
+        /// The number of tests that finished running.
+        finished_count: usize,
+        /// The minimum number of tests required by the profile.
+        min_tests: usize,
+    },
     #[cfg(feature = "self-update")]
     #[error("failed to parse --version")]
     UpdateVersionParseError {
@@ -256,10 +299,10 @@ pub enum ExpectedError {
         #[source]
         err: std::io::Error,
     },
-    #[error("message format version is not valid")]
-    InvalidMessageFormatVersion {
+    #[error("error setting up message format")]
+    CreateFormatterError {
         #[from]
-        err: FormatVersionError,
+        err: CreateFormatterError,
     },
     #[error("extract read error")]
     DebugExtractReadError {
@@ -268,12 +311,40 @@ pub enum ExpectedError {
         #[source]
         err: std::io::Error,
     },
+    #[error("test list read error")]
+    TestListReadError {
+        path: Utf8PathBuf,
+        #[source]
+        err: std::io::Error,
+    },
+    #[error("environment file error")]
+    EnvFileError {
+        #[source]
+        err: EnvFileError,
+    },
+    #[error("test list parse error")]
+    TestListParseError {
+        path: Utf8PathBuf,
+        #[source]
+        err: TestListParseError,
+    },
     #[error("extract write error")]
     DebugExtractWriteError {
         format: ExtractOutputFormat,
         #[source]
         err: std::io::Error,
     },
+    #[error("event log conversion error")]
+    DebugConvertEventLogError {
+        path: Utf8PathBuf,
+        #[source]
+        err: EventLogError,
+    },
+    #[error("output index error")]
+    DebugOutputIndexError {
+        #[source]
+        err: OutputIndexError,
+    },
 }
 
 impl ExpectedError {
@@ -412,8 +483,18 @@ impl ExpectedError {
             | Self::DialoguerError { .. }
             | Self::SignalHandlerSetupError { .. }
             | Self::ShowTestGroupsError { .. }
-            | Self::InvalidMessageFormatVersion { .. }
-            | Self::DebugExtractReadError { .. } => NextestExitCode::SETUP_ERROR,
+            | Self::TimingsStoreError { .. }
+            | Self::ChangedFilesError { .. }
+            | Self::NoPreviousRun { .. }
+            | Self::JsonSummaryBinariesOnly
+            | Self::GroupByPlatformNotHuman
+            | Self::CreateFormatterError { .. }
+            | Self::DebugExtractReadError { .. }
+            | Self::TestListReadError { .. }
+            | Self::TestListParseError { .. }
+            | Self::EnvFileError { .. }
+            | Self::DebugConvertEventLogError { .. }
+            | Self::DebugOutputIndexError { .. } => NextestExitCode::SETUP_ERROR,
             Self::ConfigParseError { err } => {
                 // Experimental features not being enabled are their own error.
                 match err.kind() {
@@ -437,9 +518,13 @@ impl ExpectedError {
             }
             Self::SetupScriptFailed => NextestExitCode::SETUP_SCRIPT_FAILED,
             Self::TestRunFailed => NextestExitCode::TEST_RUN_FAILED,
+            Self::SmokeTestsFailed => NextestExitCode::SMOKE_TESTS_FAILED,
             Self::NoTestsRun { .. } => NextestExitCode::NO_TESTS_RUN,
+            Self::TeardownScriptFailed { .. } => NextestExitCode::TEARDOWN_SCRIPT_FAILED,
+            Self::TooFewTestsRun { .. } => NextestExitCode::TOO_FEW_TESTS_RUN,
             Self::ArchiveCreateError { .. } => NextestExitCode::ARCHIVE_CREATION_FAILED,
             Self::WriteTestListError { .. }
+            | Self::ListOutputFileCreateError { .. }
             | Self::WriteEventError { .. }
             // TestRunnerExecuteErrors isn't _quite_ a WRITE_OUTPUT_ERROR, but
             // we keep this for backwards compatibility.
@@ -560,6 +645,17 @@ impl ExpectedError {
                                         index.style(styles.bold)
                                     )
                                 }
+                                ConfigCompileSection::LogicalSuite(name) => format!(
+                                    "profile.{}.logical-suites.{}",
+                                    compile_error.profile_name, name
+                                )
+                                .style(styles.bold)
+                                .to_string(),
+                                ConfigCompileSection::Smoke => {
+                                    format!("profile.{}.smoke.filter", compile_error.profile_name)
+                                        .style(styles.bold)
+                                        .to_string()
+                                }
                             };
                             error!(
                                 "for config file `{}`{}, failed to parse {}",
@@ -754,6 +850,13 @@ impl ExpectedError {
                 error!("{err}");
                 err.source()
             }
+            Self::ListOutputFileCreateError { path, err } => {
+                error!(
+                    "failed to create list output file at `{}`",
+                    path.style(styles.bold)
+                );
+                Some(err as &dyn Error)
+            }
             Self::WriteTestListError { err } => {
                 error!("failed to write test list to output");
                 Some(err as &dyn Error)
@@ -774,6 +877,10 @@ impl ExpectedError {
                 error!("test run failed");
                 None
             }
+            Self::SmokeTestsFailed => {
+                error!("smoke tests failed, so the remaining tests were not run");
+                None
+            }
             Self::NoTestsRun { is_default } => {
                 let hint_str = if *is_default {
                     "\n(hint: use `--no-tests` to customize)"
@@ -783,10 +890,78 @@ impl ExpectedError {
                 error!("no tests to run{hint_str}");
                 None
             }
+            Self::TeardownScriptFailed {
+                profile_name,
+                failed_count,
+            } => {
+                let scripts_str = if *failed_count == 1 {
+                    "script"
+                } else {
+                    "scripts"
+                };
+                error!(
+                    "{} teardown {scripts_str} failed, and profile `{}` sets \
+                     `on-teardown-failure = \"fail\"`",
+                    failed_count.style(styles.bold),
+                    profile_name.style(styles.bold),
+                );
+                None
+            }
+            Self::TooFewTestsRun {
+                profile_name,
+                finished_count,
+                min_tests,
+            } => {
+                let tests_str = if *finished_count == 1 {
+                    "test"
+                } else {
+                    "tests"
+                };
+                error!(
+                    "{} {tests_str} run, but profile `{}` requires at least {} (`min-tests`)\n\
+                     (hint: check that the expected tests were selected by filters)",
+                    finished_count.style(styles.bold),
+                    profile_name.style(styles.bold),
+                    min_tests.style(styles.bold),
+                );
+                None
+            }
             Self::ShowTestGroupsError { err } => {
                 error!("{err}");
                 err.source()
             }
+            Self::TimingsStoreError { err } => {
+                error!("{err}");
+                err.source()
+            }
+            Self::ChangedFilesError { err } => {
+                error!("failed to determine changed files: {err}");
+                err.source()
+            }
+            Self::NoPreviousRun { profile_name } => {
+                error!(
+                    "--previously requires the results of a previous run, but none were recorded \
+                     for profile `{}`\n\
+                     (hint: set `record-timings = true` in the profile, then run tests)",
+                    profile_name.style(styles.bold),
+                );
+                None
+            }
+            Self::JsonSummaryBinariesOnly => {
+                error!(
+                    "--message-format {} is not supported with --list-type {}",
+                    "json-summary".style(styles.bold),
+                    "binaries-only".style(styles.bold),
+                );
+                None
+            }
+            Self::GroupByPlatformNotHuman => {
+                error!(
+                    "--group-by-platform is only supported with --message-format {}",
+                    "human".style(styles.bold),
+                );
+                None
+            }
             Self::RequiredVersionNotMet {
                 required,
                 current,
@@ -867,18 +1042,44 @@ impl ExpectedError {
                 error!("[double-spawn] failed to exec `{command:?}`");
                 Some(err as &dyn Error)
             }
-            Self::InvalidMessageFormatVersion { err } => {
-                error!("error parsing message format version");
-                Some(err as &dyn Error)
+            Self::CreateFormatterError { err } => {
+                error!("{err}");
+                err.source()
             }
             Self::DebugExtractReadError { kind, path, err } => {
                 error!("error reading {kind} file `{}`", path.style(styles.bold),);
                 Some(err as &dyn Error)
             }
+            Self::TestListReadError { path, err } => {
+                error!(
+                    "error reading test list from {}",
+                    test_list_source(path, styles)
+                );
+                Some(err as &dyn Error)
+            }
+            Self::EnvFileError { err } => {
+                error!("{err}");
+                err.source()
+            }
+            Self::TestListParseError { path, err } => {
+                error!(
+                    "error parsing test list from {}",
+                    test_list_source(path, styles)
+                );
+                Some(err as &dyn Error)
+            }
             Self::DebugExtractWriteError { format, err } => {
                 error!("error writing {format} output");
                 Some(err as &dyn Error)
             }
+            Self::DebugConvertEventLogError { path, err } => {
+                error!("error converting event log `{}`", path.style(styles.bold));
+                Some(err as &dyn Error)
+            }
+            Self::DebugOutputIndexError { err } => {
+                error!("{err}");
+                err.source()
+            }
         };
 
         while let Some(err) = next_error {
@@ -887,3 +1088,11 @@ impl ExpectedError {
         }
     }
 }
+
+fn test_list_source(path: &Utf8PathBuf, styles: &StderrStyles) -> String {
+    if path == "-" {
+        "standard input".to_owned()
+    } else {
+        format!("`{}`", path.style(styles.bold))
+    }
+}
@@ -51,8 +51,10 @@ pub static EXPECTED_TEST_SUITES: Lazy<BTreeMap<RustBinaryId, TestSuiteFixture>>
                 TestCaseFixture::new("test_stdin_closed", TestCaseFixtureStatus::Pass),
                 TestCaseFixture::new("test_subprocess_doesnt_exit", TestCaseFixtureStatus::Leak),
                 TestCaseFixture::new("test_subprocess_doesnt_exit_fail", TestCaseFixtureStatus::FailLeak),
+                TestCaseFixture::new("test_subprocess_orphaned", TestCaseFixtureStatus::Pass),
                 TestCaseFixture::new("test_success", TestCaseFixtureStatus::Pass),
                 TestCaseFixture::new("test_success_should_panic", TestCaseFixtureStatus::Pass),
+                TestCaseFixture::new("test_thread_lingers", TestCaseFixtureStatus::Pass),
             ],
         ),
         "nextest-tests::other".into() => TestSuiteFixture::new(
@@ -334,6 +334,63 @@ fn test_subprocess_doesnt_exit_fail() {
     panic!("this is a panic");
 }
 
+#[cfg(any(unix, windows))]
+#[test]
+fn test_subprocess_orphaned() {
+    // Leave a process behind that doesn't inherit stdout or stderr, so it isn't detected by the
+    // leak timeout. Note: this is synchronized with the orphan-processes tests in the main nextest
+    // repo.
+    //
+    // The orphan is only spawned if the with-orphan-processes profile's setup script has set this
+    // environment variable, so that other runs of this fixture don't leave processes behind.
+    if std::env::var("__NEXTEST_SPAWN_ORPHAN").is_err() {
+        return;
+    }
+
+    #[cfg(unix)]
+    {
+        // Double-fork via the shell: the sleep is orphaned once the shell exits.
+        std::process::Command::new("sh")
+            .args(["-c", "sleep 5 &"])
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .unwrap();
+    }
+    #[cfg(windows)]
+    {
+        sleep_cmd(5)
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn()
+            .unwrap();
+    }
+}
+
+#[test]
+fn test_thread_lingers() {
+    // Leave a thread running after the test returns. The thread holds the lock on stdout for a
+    // bit, so the test harness blocks (rather than exiting straight away) when it prints the
+    // result of the test. Note: this is synchronized with the lingering-threads tests in the main
+    // nextest repo.
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::Builder::new()
+        .name("lingering".to_owned())
+        .spawn(move || {
+            let stdout = std::io::stdout().lock();
+            tx.send(()).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(500));
+            drop(stdout);
+            loop {
+                std::thread::sleep(std::time::Duration::from_secs(1));
+            }
+        })
+        .unwrap();
+    rx.recv().unwrap();
+    // Simulate the test doing some work, so that nextest sees the thread the test runs on.
+    std::thread::sleep(std::time::Duration::from_millis(100));
+}
+
 #[cfg(windows)]
 fn sleep_cmd(secs: usize) -> std::process::Command {
     // Apparently, this is the most reliable way to sleep for a bit on Windows.
@@ -27,7 +27,10 @@ use integration_tests::{
     env::set_env_vars,
     nextest_cli::{CargoNextestCli, CargoNextestOutput},
 };
-use nextest_metadata::{BuildPlatform, NextestExitCode, TestListSummary};
+use nextest_metadata::{
+    BuildPlatform, NextestExitCode, RustBinaryId, RustTestSuiteStatusSummary, TestCountSummary,
+    TestListSummary,
+};
 use std::{borrow::Cow, fs::File, io::Write};
 use target_spec::Platform;
 
@@ -166,6 +169,76 @@ fn test_list_binaries_only() {
     check_list_binaries_output(&output.stdout);
 }
 
+#[test]
+fn test_list_output_file() {
+    set_env_vars();
+    let p = TempProject::new().unwrap();
+    let output_file = p.workspace_root().join("test-list.json");
+
+    let output = CargoNextestCli::for_test()
+        .args([
+            "--manifest-path",
+            p.manifest_path().as_str(),
+            "list",
+            "--workspace",
+            "--all-targets",
+            "--message-format",
+            "json",
+            "--output-file",
+            output_file.as_str(),
+        ])
+        .output();
+
+    assert!(
+        output.stdout.is_empty(),
+        "nothing written to stdout: {output}"
+    );
+    let contents = std::fs::read(&output_file).expect("output file was written");
+    check_list_full_output(&contents, None);
+}
+
+#[test]
+fn test_list_json_summary() {
+    set_env_vars();
+    let p = TempProject::new().unwrap();
+    let manifest_path = p.manifest_path();
+
+    let list_args = [
+        "--manifest-path",
+        manifest_path.as_str(),
+        "list",
+        "--workspace",
+        "--all-targets",
+        "-E",
+        "test(test_success) | test(test_cwd)",
+    ];
+    let full = CargoNextestCli::for_test()
+        .args(list_args)
+        .args(["--message-format", "json"])
+        .output()
+        .decode_test_list_json()
+        .expect("full test list is valid JSON");
+
+    let output = CargoNextestCli::for_test()
+        .args(list_args)
+        .args(["--message-format", "json-summary"])
+        .output();
+    let summary = TestCountSummary::parse_json(output.stdout_as_str())
+        .unwrap_or_else(|err| panic!("count summary is valid JSON ({err}): {output}"));
+
+    assert_eq!(summary.binary_count, full.rust_suites.len(), "binary count");
+    assert_eq!(summary.test_count, full.test_count, "test count");
+    assert_eq!(
+        summary.matching_test_count,
+        full.matching_test_count(),
+        "matching test count"
+    );
+    assert!(
+        summary.matching_test_count > 0 && summary.matching_test_count < summary.test_count,
+        "filter selected some but not all tests: {summary:?}"
+    );
+}
+
 #[test]
 fn test_target_dir() {
     set_env_vars();
@@ -295,6 +368,89 @@ fn test_list_target_after_build() {
     check_list_full_output(&output.stdout, Some(BuildPlatform::Target));
 }
 
+#[test]
+fn test_list_changed_since() {
+    set_env_vars();
+
+    let p = TempProject::new().unwrap();
+    save_binaries_metadata(&p);
+
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(p.workspace_root())
+            .status()
+            .expect("git executed");
+        assert!(status.success(), "git {args:?} succeeded");
+    };
+    git(&["init", "--quiet"]);
+    git(&["add", "."]);
+    git(&["commit", "--quiet", "-m", "initial"]);
+
+    let list_changed = || -> TestListSummary {
+        let output = CargoNextestCli::for_test()
+            .args([
+                "--manifest-path",
+                p.manifest_path().as_str(),
+                "list",
+                "--binaries-metadata",
+                p.binaries_metadata_path().as_str(),
+                "--message-format",
+                "json",
+                "--changed-since",
+                "HEAD",
+            ])
+            .output();
+        serde_json::from_slice(&output.stdout).unwrap()
+    };
+    let binary_ids = |summary: &TestListSummary| -> Vec<String> {
+        summary
+            .rust_suites
+            .keys()
+            .map(|id| id.as_str().to_owned())
+            .collect()
+    };
+
+    // No changes: nothing is listed, rather than the whole suite.
+    let summary = list_changed();
+    assert_eq!(summary.test_count, 0);
+    assert_eq!(binary_ids(&summary), Vec::<String>::new());
+
+    // A change to an integration test only affects that binary.
+    let other_rs = p.workspace_root().join("tests/other.rs");
+    let mut contents = std::fs::read_to_string(&other_rs).unwrap();
+    contents.push_str("\n// changed\n");
+    std::fs::write(&other_rs, contents).unwrap();
+    let summary = list_changed();
+    assert_eq!(binary_ids(&summary), vec!["nextest-tests::other"]);
+    assert_eq!(
+        summary.test_count,
+        summary.rust_suites[&RustBinaryId::new("nextest-tests::other")]
+            .test_cases
+            .len()
+    );
+
+    // A change to a library affects it and every package that depends on it.
+    git(&["checkout", "--quiet", "--", "tests/other.rs"]);
+    std::fs::write(p.workspace_root().join("dylib-test/src/new.rs"), "").unwrap();
+    let summary = list_changed();
+    let ids = binary_ids(&summary);
+    assert!(
+        ids.contains(&"dylib-test".to_owned()),
+        "dylib-test listed: {ids:?}"
+    );
+    assert!(
+        ids.contains(&"nextest-tests::other".to_owned()),
+        "dependent package listed: {ids:?}"
+    );
+    assert!(
+        ids.iter()
+            .all(|id| id.starts_with("dylib-test") || id.starts_with("nextest-tests")),
+        "only affected packages listed: {ids:?}"
+    );
+}
+
 #[test]
 fn test_run_no_tests() {
     set_env_vars();
@@ -387,6 +543,794 @@ fn test_run_no_tests() {
     );
 }
 
+#[cfg(unix)]
+#[test]
+fn test_run_on_slow() {
+    set_env_vars();
+
+    let p = TempProject::new().unwrap();
+
+    let output = CargoNextestCli::for_test()
+        .args([
+            "--manifest-path",
+            p.manifest_path().as_str(),
+            "run",
+            "--workspace",
+            "--all-targets",
+            "--profile=with-on-slow",
+            "--run-ignored=only",
+            "-E",
+            "test(=test_slow_timeout_2)",
+        ])
+        .output();
+
+    let stderr = output.stderr_as_str();
+    assert!(
+        stderr.contains("SLOW") && stderr.contains("see the slow test runbook"),
+        "stderr contains the on-slow message: {output}"
+    );
+
+    // The on-slow command runs in the background, so wait a bit for it to finish.
+    let slow_tests_path = p.workspace_root().join("slow-tests.txt");
+    let mut slow_tests = String::new();
+    for _ in 0..50 {
+        slow_tests = std::fs::read_to_string(&slow_tests_path).unwrap_or_default();
+        if !slow_tests.is_empty() {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    assert!(
+        slow_tests
+            .lines()
+            .any(|line| line == "nextest-tests::basic test_slow_timeout_2"),
+        "on-slow command was run for the slow test: {slow_tests:?}"
+    );
+}
+
+#[test]
+fn test_run_min_tests() {
+    set_env_vars();
+
+    let p = TempProject::new().unwrap();
+
+    // The with-min-tests profile requires at least 2 tests to run.
+    let run_with_filter = |filter: &str| {
+        CargoNextestCli::for_test()
+            .args([
+                "--manifest-path",
+                p.manifest_path().as_str(),
+                "run",
+                "--workspace",
+                "--all-targets",
+                "--profile=with-min-tests",
+                "--no-tests=pass",
+                "-E",
+                filter,
+            ])
+            .unchecked(true)
+            .output()
+    };
+
+    // Exactly the minimum.
+    let output = run_with_filter("test(=test_cwd) | test(=test_success)");
+    assert_eq!(
+        output.exit_status.code(),
+        Some(0),
+        "correct exit code for command\n{output}"
+    );
+
+    // One fewer than the minimum.
+    let output = run_with_filter("test(=test_cwd)");
+    assert_eq!(
+        output.exit_status.code(),
+        Some(NextestExitCode::TOO_FEW_TESTS_RUN),
+        "correct exit code for command\n{output}"
+    );
+    let stderr = output.stderr_as_str();
+    assert!(
+        stderr.contains(
+            "error: 1 test run, but profile `with-min-tests` requires at least 2 (`min-tests`)"
+        ),
+        "stderr contains min-tests message: {output}"
+    );
+
+    // The minimum also applies if no tests are run, even with --no-tests=pass.
+    let output = run_with_filter("none()");
+    assert_eq!(
+        output.exit_status.code(),
+        Some(NextestExitCode::TOO_FEW_TESTS_RUN),
+        "correct exit code for command\n{output}"
+    );
+
+    // Test failures take precedence over the minimum.
+    let output = run_with_filter("test(=test_failure_assert)");
+    assert_eq!(
+        output.exit_status.code(),
+        Some(NextestExitCode::TEST_RUN_FAILED),
+        "correct exit code for command\n{output}"
+    );
+}
+
+#[test]
+fn test_run_then_profile() {
+    set_env_vars();
+
+    let p = TempProject::new().unwrap();
+
+    let run = |filter: &str, extra_args: &[&str]| {
+        CargoNextestCli::for_test()
+            .args([
+                "--manifest-path",
+                p.manifest_path().as_str(),
+                "run",
+                "--workspace",
+                "--all-targets",
+                "--then-profile=with-retries",
+                "-E",
+                filter,
+            ])
+            .args(extra_args.iter().copied())
+            .unchecked(true)
+            .output()
+    };
+
+    // Both profiles pass.
+    let output = run("test(=test_success)", &[]);
+    assert_eq!(
+        output.exit_status.code(),
+        Some(0),
+        "correct exit code for command\n{output}"
+    );
+    let stderr = output.stderr_as_str();
+    for line in [
+        "2 tests run across 2 profiles: 2 passed",
+        "Profile default",
+        "Profile with-retries",
+    ] {
+        assert!(stderr.contains(line), "stderr contains {line:?}: {output}");
+    }
+
+    // The first profile fails, so the second one isn't run.
+    let output = run("test(=test_success) | test(=test_failure_assert)", &[]);
+    assert_eq!(
+        output.exit_status.code(),
+        Some(NextestExitCode::TEST_RUN_FAILED),
+        "correct exit code for command\n{output}"
+    );
+    let stderr = output.stderr_as_str();
+    assert!(
+        stderr.contains("not running 1 remaining profile after profile `default` failed"),
+        "stderr contains early stop message: {output}"
+    );
+    assert!(
+        !stderr.contains("Profile with-retries"),
+        "with-retries profile is not run: {output}"
+    );
+
+    // With --on-profile-failure=continue, both profiles run, and the run as a whole fails.
+    let output = run(
+        "test(=test_success) | test(=test_failure_assert)",
+        &["--on-profile-failure=continue"],
+    );
+    assert_eq!(
+        output.exit_status.code(),
+        Some(NextestExitCode::TEST_RUN_FAILED),
+        "correct exit code for command\n{output}"
+    );
+    let stderr = output.stderr_as_str();
+    assert!(
+        stderr.contains("Profile with-retries"),
+        "with-retries profile is run: {output}"
+    );
+
+    // With a structured format, both runs are written to a single stream, followed by the
+    // overall summary.
+    let output = run("test(=test_success)", &["--message-format=jsonl"]);
+    assert_eq!(
+        output.exit_status.code(),
+        Some(0),
+        "correct exit code for command\n{output}"
+    );
+    let events: Vec<serde_json::Value> = output
+        .stdout_as_str()
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("each line is valid JSON"))
+        .collect();
+    let run_started = events
+        .iter()
+        .filter(|event| event["event"] == "run-started")
+        .count();
+    assert_eq!(run_started, 2, "both runs are reported: {output}");
+    let last = events.last().expect("at least one event was written");
+    assert_eq!(last["event"], "aggregate-finished", "{output}");
+    assert_eq!(last["outcome"], "success", "{output}");
+    assert_eq!(last["passed"], 2, "{output}");
+
+    let output = run(
+        "test(=test_success)",
+        &["--message-format=binary-event-log"],
+    );
+    assert_eq!(
+        output.exit_status.code(),
+        Some(0),
+        "correct exit code for command\n{output}"
+    );
+    assert_eq!(
+        output
+            .stdout
+            .windows(4)
+            .filter(|window| *window == b"NXEL")
+            .count(),
+        1,
+        "event log header is written once: {output}"
+    );
+}
+
+#[test]
+fn test_run_env_file() {
+    set_env_vars();
+
+    let p = TempProject::new().unwrap();
+    let env_file = p.temp_root().join("test.env");
+
+    let run = || {
+        CargoNextestCli::for_test()
+            .args([
+                "--manifest-path",
+                p.manifest_path().as_str(),
+                "run",
+                "--workspace",
+                "--all-targets",
+                "--env-file",
+                env_file.as_str(),
+                "-E",
+                "test(=test_success)",
+            ])
+            .unchecked(true)
+            .output()
+    };
+
+    // test_success checks that MY_ENV_VAR isn't set, so it fails if the env file is applied.
+    std::fs::write(&env_file, "MY_ENV_VAR=from-env-file\n").unwrap();
+    let output = run();
+    assert_eq!(
+        output.exit_status.code(),
+        Some(NextestExitCode::TEST_RUN_FAILED),
+        "correct exit code for command\n{output}"
+    );
+
+    // Keys starting with NEXTEST are reserved.
+    std::fs::write(&env_file, "NEXTEST_FOO=bar\n").unwrap();
+    let output = run();
+    assert_eq!(
+        output.exit_status.code(),
+        Some(NextestExitCode::SETUP_ERROR),
+        "correct exit code for command\n{output}"
+    );
+    assert!(
+        output
+            .stderr_as_str()
+            .contains("key `NEXTEST_FOO` begins with `NEXTEST`"),
+        "stderr contains reserved key error: {output}"
+    );
+}
+
+#[test]
+fn test_run_plan() {
+    set_env_vars();
+
+    let p = TempProject::new().unwrap();
+
+    let plan = |format: &str| {
+        CargoNextestCli::for_test()
+            .args([
+                "--manifest-path",
+                p.manifest_path().as_str(),
+                "run",
+                "--workspace",
+                "--all-targets",
+                "--retries=3",
+                format,
+                "-E",
+                "test(=test_success) | test(=test_cargo_env_vars)",
+            ])
+            .output()
+    };
+
+    let output = plan("--plan");
+    let stdout = output.stdout_as_str();
+    assert!(
+        !output.stderr_as_str().contains("Starting"),
+        "no tests are run: {output}"
+    );
+    for line in [
+        "Plan for profile default",
+        "nextest-tests::basic test_success (retries: 3, slow-timeout: 60s)",
+        "2 tests to run",
+        "estimated time: unknown (no recorded timings)",
+    ] {
+        assert!(stdout.contains(line), "stdout contains {line:?}: {output}");
+    }
+    if cfg!(unix) {
+        assert!(
+            stdout.contains("my-script-unix: ./scripts/my-script.sh"),
+            "stdout contains setup script: {output}"
+        );
+    }
+
+    let output = plan("--plan=json");
+    let plan: serde_json::Value = serde_json::from_str(&output.stdout_as_str()).unwrap();
+    assert_eq!(plan["profile-name"], "default");
+    let tests = plan["tests"].as_array().unwrap();
+    let test_names: Vec<_> = tests.iter().map(|test| &test["test-name"]).collect();
+    assert_eq!(test_names, ["test_cargo_env_vars", "test_success"]);
+    for test in tests {
+        assert_eq!(test["binary-id"], "nextest-tests::basic");
+        assert_eq!(test["retries"], 3);
+        assert_eq!(test["slow-timeout-secs"], 60.0);
+        assert_eq!(test["estimated-duration-secs"], serde_json::Value::Null);
+    }
+    assert_eq!(plan["tests-without-timings"], 2);
+    if cfg!(unix) {
+        assert_eq!(plan["setup-scripts"][0]["id"], "my-script-unix");
+    }
+}
+
+#[test]
+fn test_run_artifacts_dir() {
+    set_env_vars();
+
+    let p = TempProject::new().unwrap();
+
+    let output = CargoNextestCli::for_test()
+        .args([
+            "--manifest-path",
+            p.manifest_path().as_str(),
+            "run",
+            "--workspace",
+            "--all-targets",
+            "--profile=with-artifacts",
+            "-E",
+            "test(=test_success) | test(=test_failure_assert)",
+        ])
+        .unchecked(true)
+        .output();
+    assert_eq!(
+        output.exit_status.code(),
+        Some(NextestExitCode::TEST_RUN_FAILED),
+        "correct exit code for command\n{output}"
+    );
+
+    // There's a single run directory, named after the run ID.
+    let runs_dir = p
+        .workspace_root()
+        .join("target/nextest/with-artifacts/runs");
+    let run_dirs: Vec<_> = runs_dir
+        .read_dir_utf8()
+        .unwrap()
+        .map(|entry| entry.unwrap().into_path())
+        .collect();
+    assert_eq!(run_dirs.len(), 1, "one run directory: {run_dirs:?}");
+    let run_dir = &run_dirs[0];
+    let run_id = run_dir.file_name().unwrap();
+
+    let summary: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(run_dir.join("summary.json")).unwrap())
+            .unwrap();
+    assert_eq!(summary["run-id"], run_id);
+    assert_eq!(summary["profile-name"], "with-artifacts");
+    assert_eq!(summary["result"], "failed");
+    assert_eq!(summary["passed"], 1);
+    assert_eq!(summary["failed"], 1);
+
+    let events = std::fs::read_to_string(run_dir.join("events.ndjson")).unwrap();
+    let events: Vec<serde_json::Value> = events
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(events.first().unwrap()["event"], "run-started");
+    assert_eq!(events.last().unwrap()["event"], "run-finished");
+    assert_eq!(
+        events
+            .iter()
+            .filter(|event| event["event"] == "test-finished")
+            .count(),
+        2,
+        "two tests finished: {events:?}"
+    );
+
+    assert!(
+        run_dir.join("junit.xml").is_file(),
+        "JUnit report is copied into the run directory"
+    );
+    let test_dir = run_dir.join("output/nextest-tests%3A%3Abasic");
+    assert!(
+        test_dir.join("test_success/1.stdout").is_file(),
+        "output is written for passing tests"
+    );
+    let stderr = std::fs::read_to_string(test_dir.join("test_failure_assert/1.stderr")).unwrap();
+    assert!(
+        stderr.contains("this is an assertion"),
+        "stderr is written for failing tests: {stderr}"
+    );
+
+    // The output index can be queried for tests that mentioned a term.
+    let search = |query: &str| {
+        let output = CargoNextestCli::for_test()
+            .args(["debug", "search-output", run_dir.as_str(), query])
+            .output();
+        output.stdout_as_str().into_owned()
+    };
+    assert_eq!(
+        search("Assertion failed"),
+        "nextest-tests::basic test_failure_assert\n"
+    );
+    assert_eq!(search("no_such_term"), "");
+}
+
+#[test]
+fn test_run_previously_timed_out() {
+    set_env_vars();
+
+    let p = TempProject::new().unwrap();
+
+    let run = |extra_args: &[&str]| {
+        CargoNextestCli::for_test()
+            .args([
+                "--manifest-path",
+                p.manifest_path().as_str(),
+                "run",
+                "--workspace",
+                "--all-targets",
+                "--profile=with-previous-results",
+                "--run-ignored=all",
+            ])
+            .args(extra_args.iter().copied())
+            .unchecked(true)
+            .output()
+    };
+
+    // Without a previous run, --previously is an error.
+    let output = run(&["--previously=timed-out"]);
+    assert_eq!(
+        output.exit_status.code(),
+        Some(NextestExitCode::SETUP_ERROR),
+        "correct exit code for command\n{output}"
+    );
+    assert!(
+        output.stderr_as_str().contains("record-timings"),
+        "stderr contains a hint about record-timings\n{output}"
+    );
+
+    let output = run(&["-E", "test(=test_slow_timeout_2) | test(=test_success)"]);
+    assert_eq!(
+        output.exit_status.code(),
+        Some(NextestExitCode::TEST_RUN_FAILED),
+        "correct exit code for command\n{output}"
+    );
+
+    // Only the test that timed out is selected. test_failure_assert wasn't in the previous run, so
+    // it's skipped with a warning.
+    let output = run(&[
+        "--previously=timed-out",
+        "-E",
+        "test(=test_slow_timeout_2) | test(=test_success) | test(=test_failure_assert)",
+    ]);
+    assert_eq!(
+        output.exit_status.code(),
+        Some(NextestExitCode::TEST_RUN_FAILED),
+        "correct exit code for command\n{output}"
+    );
+    let stderr = output.stderr_as_str();
+    assert!(
+        stderr.contains("1 test skipped by --previously: not run in the previous run"),
+        "stderr contains warning about absent tests\n{output}"
+    );
+    assert!(
+        stderr.contains("Starting 1 test across"),
+        "only the timed out test is run\n{output}"
+    );
+    assert!(
+        stderr.contains("TIMEOUT") && stderr.contains("test_slow_timeout_2"),
+        "the timed out test is run\n{output}"
+    );
+
+    // Tests that time out also count as failed.
+    let output = run(&[
+        "--previously=failed",
+        "-E",
+        "test(=test_slow_timeout_2) | test(=test_success)",
+    ]);
+    assert_eq!(
+        output.exit_status.code(),
+        Some(NextestExitCode::TEST_RUN_FAILED),
+        "correct exit code for command\n{output}"
+    );
+    let stderr = output.stderr_as_str();
+    assert!(
+        stderr.contains("Starting 1 test across") && stderr.contains("test_slow_timeout_2"),
+        "only the timed out test is run\n{output}"
+    );
+}
+
+#[test]
+fn test_run_logical_suites() {
+    set_env_vars();
+
+    let p = TempProject::new().unwrap();
+
+    let output = CargoNextestCli::for_test()
+        .args([
+            "--manifest-path",
+            p.manifest_path().as_str(),
+            "run",
+            "--workspace",
+            "--all-targets",
+            "--profile=with-logical-suites",
+            "-E",
+            "test(=test_success) | test(=test_cwd) | test(=test_failure_assert)",
+        ])
+        .unchecked(true)
+        .output();
+    assert_eq!(
+        output.exit_status.code(),
+        Some(NextestExitCode::TEST_RUN_FAILED),
+        "correct exit code for command\n{output}"
+    );
+
+    // test_success and test_cwd are in both logical suites.
+    let stderr = output.stderr_as_str();
+    assert!(
+        stderr.contains("Suite basic: 3 tests run: 2 passed, 1 failed, "),
+        "stderr contains summary for the basic suite\n{output}"
+    );
+    assert!(
+        stderr.contains("Suite passing: 2 tests run: 2 passed, 0 skipped\n"),
+        "stderr contains summary for the passing suite\n{output}"
+    );
+
+    let junit = std::fs::read_to_string(
+        p.workspace_root()
+            .join("target/nextest/with-logical-suites/junit.xml"),
+    )
+    .unwrap();
+    let testsuite_line = |name: &str| {
+        let prefix = format!("<testsuite name=\"{name}\"");
+        junit
+            .lines()
+            .map(str::trim_start)
+            .find(|line| line.starts_with(&prefix))
+            .unwrap_or_else(|| panic!("testsuite {name} not found in JUnit report:\n{junit}"))
+    };
+    let basic = testsuite_line("@logical-suite:basic");
+    assert!(
+        basic.contains(r#"tests="3""#),
+        "basic suite has 3 tests: {basic}"
+    );
+    assert!(
+        basic.contains(r#"failures="1""#),
+        "basic suite has 1 failure: {basic}"
+    );
+    let passing = testsuite_line("@logical-suite:passing");
+    assert!(
+        passing.contains(r#"tests="2""#),
+        "passing suite has 2 tests: {passing}"
+    );
+    assert!(
+        passing.contains(r#"failures="0""#),
+        "passing suite has no failures: {passing}"
+    );
+
+    // The test is reported under its binary, and under each logical suite.
+    assert_eq!(
+        junit.matches(r#"<testcase name="test_success""#).count(),
+        3,
+        "test_success is reported 3 times:\n{junit}"
+    );
+}
+
+#[test]
+fn test_run_smoke() {
+    set_env_vars();
+
+    let p = TempProject::new().unwrap();
+
+    // The with-smoke profile selects test_success and test_failure_assert as smoke tests.
+    let run = |expr: &str| {
+        CargoNextestCli::for_test()
+            .args([
+                "--manifest-path",
+                p.manifest_path().as_str(),
+                "run",
+                "--workspace",
+                "--all-targets",
+                "--profile=with-smoke",
+                "-E",
+                expr,
+            ])
+            .unchecked(true)
+            .output()
+    };
+
+    let output = run("test(=test_success) | test(=test_cwd)");
+    assert_eq!(
+        output.exit_status.code(),
+        Some(0),
+        "correct exit code for command
+{output}"
+    );
+    let stderr = output.stderr_as_str();
+    assert!(
+        stderr.contains(
+            "Phase smoke: starting 1 test
+"
+        ),
+        "stderr contains smoke phase start
+{output}"
+    );
+    assert!(
+        stderr.contains(
+            "Phase main: starting 1 test
+"
+        ),
+        "stderr contains main phase start
+{output}"
+    );
+
+    let output = run("test(=test_failure_assert) | test(=test_cwd)");
+    assert_eq!(
+        output.exit_status.code(),
+        Some(NextestExitCode::SMOKE_TESTS_FAILED),
+        "correct exit code for command
+{output}"
+    );
+    let stderr = output.stderr_as_str();
+    assert!(
+        stderr.contains(
+            "Phase smoke: starting 1 test
+"
+        ),
+        "stderr contains smoke phase start
+{output}"
+    );
+    assert!(
+        !stderr.contains("Phase main"),
+        "main phase is not run
+{output}"
+    );
+    assert!(
+        stderr.contains("1/2 tests were not run due to smoke test failure"),
+        "stderr reports the main phase test as not run
+{output}"
+    );
+}
+
+#[test]
+fn test_run_attachments() {
+    set_env_vars();
+
+    let p = TempProject::new().unwrap();
+
+    let output = CargoNextestCli::for_test()
+        .args([
+            "--manifest-path",
+            p.manifest_path().as_str(),
+            "run",
+            "--workspace",
+            "--all-targets",
+            "--profile=with-attachments",
+            "-E",
+            "test(=test_success)",
+        ])
+        .output();
+
+    // A missing attachment is reported, but doesn't fail the run.
+    let missing = p.workspace_root().join("missing-attachment.txt");
+    assert!(
+        output
+            .stderr_as_str()
+            .contains(&format!("warning: attachment {missing} for ")),
+        "stderr warns about the missing attachment\n{output}"
+    );
+
+    let profile_dir = p.workspace_root().join("target/nextest/with-attachments");
+    let junit = std::fs::read_to_string(profile_dir.join("junit.xml")).unwrap();
+    let cargo_toml = p.workspace_root().join("Cargo.toml");
+    assert!(
+        junit.contains(&format!("[[ATTACHMENT|{cargo_toml}]]")),
+        "JUnit report contains the attachment:\n{junit}"
+    );
+    assert!(
+        !junit.contains("missing-attachment.txt"),
+        "JUnit report doesn't contain the missing attachment:\n{junit}"
+    );
+
+    let run_dirs: Vec<_> = profile_dir
+        .join("runs")
+        .read_dir_utf8()
+        .unwrap()
+        .map(|entry| entry.unwrap().into_path())
+        .collect();
+    assert_eq!(run_dirs.len(), 1, "one run directory: {run_dirs:?}");
+    let copied = run_dirs[0].join("attachments/nextest-tests%3A%3Abasic/test_success/Cargo.toml");
+    assert_eq!(
+        std::fs::read_to_string(&copied).unwrap(),
+        std::fs::read_to_string(&cargo_toml).unwrap(),
+        "attachment is copied into the run directory"
+    );
+}
+
+#[test]
+fn test_run_output_compression() {
+    set_env_vars();
+
+    let p = TempProject::new().unwrap();
+
+    let output = CargoNextestCli::for_test()
+        .args([
+            "--manifest-path",
+            p.manifest_path().as_str(),
+            "run",
+            "--workspace",
+            "--all-targets",
+            "--profile=with-compression",
+            "-E",
+            "test(=test_success) | test(=test_failure_assert)",
+        ])
+        .unchecked(true)
+        .output();
+    assert_eq!(
+        output.exit_status.code(),
+        Some(NextestExitCode::TEST_RUN_FAILED),
+        "correct exit code for command\n{output}"
+    );
+
+    let decompress = |path: &Utf8Path| {
+        let compressed =
+            std::fs::read(path).unwrap_or_else(|error| panic!("failed to read {path}: {error}"));
+        String::from_utf8(zstd::decode_all(&compressed[..]).unwrap()).unwrap()
+    };
+
+    let profile_dir = p.workspace_root().join("target/nextest/with-compression");
+    assert!(
+        !profile_dir.join("junit.xml").exists(),
+        "uncompressed JUnit report is not written"
+    );
+    let junit = decompress(&profile_dir.join("junit.xml.zst"));
+    assert!(
+        junit.contains(r#"<testcase name="test_success""#),
+        "JUnit report contains test_success:\n{junit}"
+    );
+
+    let run_dirs: Vec<_> = profile_dir
+        .join("runs")
+        .read_dir_utf8()
+        .unwrap()
+        .map(|entry| entry.unwrap().into_path())
+        .collect();
+    assert_eq!(run_dirs.len(), 1, "one run directory: {run_dirs:?}");
+    let run_dir = &run_dirs[0];
+
+    assert!(
+        !run_dir.join("events.ndjson.zst.tmp").exists(),
+        "temporary event log is moved into place"
+    );
+    let events = decompress(&run_dir.join("events.ndjson.zst"));
+    let events: Vec<serde_json::Value> = events
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(events.first().unwrap()["event"], "run-started");
+    assert_eq!(events.last().unwrap()["event"], "run-finished");
+
+    assert_eq!(
+        decompress(&run_dir.join("junit.xml.zst")),
+        junit,
+        "compressed JUnit report is copied into the run directory"
+    );
+}
+
 #[test]
 fn test_run() {
     set_env_vars();
@@ -626,6 +1570,79 @@ fn test_run_after_build() {
     check_run_output(&output.stderr, 0);
 }
 
+#[test]
+fn test_run_from_test_list() {
+    set_env_vars();
+
+    let p = TempProject::new().unwrap();
+    let manifest_path = p.manifest_path();
+
+    let mut summary = CargoNextestCli::for_test()
+        .args([
+            "--manifest-path",
+            manifest_path.as_str(),
+            "list",
+            "--workspace",
+            "--all-targets",
+            "-E",
+            "test(=test_success) | test(=test_cwd)",
+            "--message-format",
+            "json",
+        ])
+        .output()
+        .decode_test_list_json()
+        .expect("test list is valid JSON");
+    let test_list_path = p.temp_root().join("test-list.json");
+    std::fs::write(&test_list_path, serde_json::to_string(&summary).unwrap()).unwrap();
+
+    let run = |test_list_path: &Utf8Path| {
+        CargoNextestCli::for_test()
+            .args([
+                "--manifest-path",
+                manifest_path.as_str(),
+                "run",
+                "--test-list-from",
+                test_list_path.as_str(),
+            ])
+            .unchecked(true)
+            .output()
+    };
+
+    // Exactly the tests that matched when listing are run.
+    let output = run(&test_list_path);
+    assert_eq!(
+        output.exit_status.code(),
+        Some(0),
+        "correct exit code for command\n{output}"
+    );
+    assert!(
+        output.stderr_as_str().contains("2 tests run: 2 passed"),
+        "stderr contains run summary: {output}"
+    );
+
+    // A test binary that no longer exists is a test list creation error.
+    let suite = summary
+        .rust_suites
+        .values_mut()
+        .find(|suite| suite.status == RustTestSuiteStatusSummary::LISTED)
+        .expect("at least one binary is listed");
+    suite.binary.binary_path = p.temp_root().join("missing-binary");
+    std::fs::write(&test_list_path, serde_json::to_string(&summary).unwrap()).unwrap();
+
+    let output = run(&test_list_path);
+    assert_eq!(
+        output.exit_status.code(),
+        Some(NextestExitCode::TEST_LIST_CREATION_FAILED),
+        "correct exit code for command\n{output}"
+    );
+    assert!(
+        output
+            .stderr_as_str()
+            .contains("missing-binary` doesn't exist"),
+        "stderr contains missing binary error: {output}"
+    );
+}
+
 #[test]
 fn test_relocated_run() {
     set_env_vars();
@@ -1,10 +1,12 @@
 // Copyright (c) The nextest Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use std::{error, fmt};
+use camino::Utf8PathBuf;
+use std::{error, fmt, time::Duration};
 
 /// An error that occurs while running a `cargo nextest` command.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum CommandError {
     /// Executing the process resulted in an error.
     Exec(std::io::Error),
@@ -21,6 +23,24 @@ pub enum CommandError {
 
     /// Error parsing JSON output.
     Json(serde_json::Error),
+
+    /// The JSON output was parsed, but couldn't be interpreted.
+    Parse(TestListParseError),
+
+    /// The file the command wrote its output to couldn't be read.
+    OutputFileRead {
+        /// The path to the output file.
+        path: Utf8PathBuf,
+
+        /// The underlying error.
+        error: std::io::Error,
+    },
+
+    /// The command didn't finish within the configured timeout, and was killed.
+    Timeout {
+        /// The timeout that was exceeded.
+        timeout: Duration,
+    },
 }
 
 impl fmt::Display for CommandError {
@@ -38,9 +58,19 @@ impl fmt::Display for CommandError {
                     "`cargo nextest` failed{exit_code_str}, stderr:\n{stderr}\n"
                 )
             }
-            Self::Json(_) => {
+            Self::Json(_) | Self::Parse(_) => {
                 write!(f, "parsing `cargo nextest` JSON output failed")
             }
+            Self::OutputFileRead { path, .. } => {
+                write!(f, "error reading `cargo nextest` output file `{path}`")
+            }
+            Self::Timeout { timeout } => {
+                write!(
+                    f,
+                    "`cargo nextest` timed out after {:.3}s and was killed",
+                    timeout.as_secs_f64()
+                )
+            }
         }
     }
 }
@@ -49,8 +79,52 @@ impl error::Error for CommandError {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match self {
             Self::Exec(err) => Some(err),
-            Self::CommandFailed { .. } => None,
+            Self::CommandFailed { .. } | Self::Timeout { .. } => None,
+            Self::Json(err) => Some(err),
+            Self::Parse(err) => Some(err),
+            Self::OutputFileRead { error, .. } => Some(error),
+        }
+    }
+}
+
+/// An error that occurs while parsing a [`TestListSummary`](crate::TestListSummary).
+#[derive(Debug)]
+pub enum TestListParseError {
+    /// The input wasn't valid JSON, or didn't match the expected structure.
+    Json(serde_json::Error),
+
+    /// The summary was written in a format version that isn't supported.
+    UnsupportedFormatVersion {
+        /// The format version in the summary.
+        version: u32,
+
+        /// The format version supported by this version of nextest-metadata.
+        supported: u32,
+    },
+}
+
+impl fmt::Display for TestListParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Json(_) => {
+                write!(f, "parsing test list JSON failed")
+            }
+            Self::UnsupportedFormatVersion { version, supported } => {
+                write!(
+                    f,
+                    "test list format version {version} is not supported \
+                     (supported version: {supported})"
+                )
+            }
+        }
+    }
+}
+
+impl error::Error for TestListParseError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
             Self::Json(err) => Some(err),
+            Self::UnsupportedFormatVersion { .. } => None,
         }
     }
 }
@@ -27,6 +27,10 @@ impl NextestExitCode {
     /// [discussion #1646]: https://github.com/nextest-rs/nextest/discussions/1646
     pub const NO_TESTS_RUN: i32 = 4;
 
+    /// Fewer tests were run than required by the `min-tests` configuration setting, but no other
+    /// errors occurred.
+    pub const TOO_FEW_TESTS_RUN: i32 = 5;
+
     /// One or more tests failed.
     pub const TEST_RUN_FAILED: i32 = 100;
 
@@ -39,6 +43,13 @@ impl NextestExitCode {
     /// A setup script failed.
     pub const SETUP_SCRIPT_FAILED: i32 = 105;
 
+    /// Smoke tests failed in gating mode, so the remaining tests were not run.
+    pub const SMOKE_TESTS_FAILED: i32 = 106;
+
+    /// All tests passed, but a teardown script failed and the `on-teardown-failure` configuration
+    /// setting is `"fail"`.
+    pub const TEARDOWN_SCRIPT_FAILED: i32 = 107;
+
     /// Writing data to stdout or stderr produced an error.
     pub const WRITE_OUTPUT_ERROR: i32 = 110;
 
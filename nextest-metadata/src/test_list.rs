@@ -1,7 +1,7 @@
 // Copyright (c) The nextest Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use crate::CommandError;
+use crate::{CommandError, TestListParseError};
 use camino::{Utf8Path, Utf8PathBuf};
 use serde::{Deserialize, Serialize};
 use smol_str::SmolStr;
@@ -10,8 +10,12 @@ use std::{
     cmp::Ordering,
     collections::{BTreeMap, BTreeSet},
     fmt::{self, Write as _},
+    fs::File,
+    io::{BufReader, Read},
     path::PathBuf,
-    process::Command,
+    process::{Child, Command, Output, Stdio},
+    thread,
+    time::{Duration, Instant},
 };
 use target_spec::summaries::PlatformSummary;
 
@@ -22,6 +26,7 @@ pub struct ListCommand {
     manifest_path: Option<Box<Utf8Path>>,
     current_dir: Option<Box<Utf8Path>>,
     args: Vec<Box<str>>,
+    timeout: Option<Duration>,
 }
 
 impl ListCommand {
@@ -65,8 +70,31 @@ impl ListCommand {
         self
     }
 
+    /// Sets the maximum amount of time `cargo nextest list` is allowed to run for.
+    ///
+    /// If the process, including building tests, takes longer than this, it is killed along with
+    /// any processes it started, and [`CommandError::Timeout`] is returned. By default, there is no
+    /// timeout.
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Adds a filterset expression to select tests with, passed in as `-E <expr>`.
+    ///
+    /// This can be called multiple times, in which case tests matching any of the expressions are
+    /// selected. An invalid expression causes `cargo nextest list` to fail before any tests are
+    /// listed, so this can also be used to validate an expression.
+    pub fn filter_expr(&mut self, expr: impl Into<String>) -> &mut Self {
+        self.add_args(["-E".to_owned(), expr.into()])
+    }
+
     /// Builds a command for `cargo nextest list`. This is the first part of the work of [`self.exec`].
     pub fn cargo_command(&self) -> Command {
+        self.cargo_command_with_format("json")
+    }
+
+    fn cargo_command_with_format(&self, message_format: &str) -> Command {
         let cargo_path: PathBuf = self.cargo_path.as_ref().map_or_else(
             || std::env::var_os("CARGO").map_or("cargo".into(), PathBuf::from),
             |path| PathBuf::from(path.as_std_path()),
@@ -80,7 +108,7 @@ impl ListCommand {
             command.current_dir(current_dir);
         }
 
-        command.args(["nextest", "list", "--message-format=json"]);
+        command.args(["nextest", "list", "--message-format", message_format]);
 
         command.args(self.args.iter().map(|s| s.as_ref()));
         command
@@ -88,8 +116,60 @@ impl ListCommand {
 
     /// Executes `cargo nextest list` and parses the output into a [`TestListSummary`].
     pub fn exec(&self) -> Result<TestListSummary, CommandError> {
+        self.exec_with_output().map(|(summary, _)| summary)
+    }
+
+    /// Executes `cargo nextest list` and parses the output into a [`TestListSummary`], also
+    /// returning the standard error of the process.
+    ///
+    /// Standard error includes any warnings printed by Cargo or the compiler while building tests.
+    pub fn exec_with_output(&self) -> Result<(TestListSummary, Vec<u8>), CommandError> {
+        let output = self.output(self.cargo_command())?;
+
+        if !output.status.success() {
+            // The process exited with a non-zero code.
+            let exit_code = output.status.code();
+            let stderr = output.stderr;
+            return Err(CommandError::CommandFailed { exit_code, stderr });
+        }
+
+        // Try parsing stdout.
+        let summary: TestListSummary =
+            serde_json::from_slice(&output.stdout).map_err(CommandError::Json)?;
+        summary
+            .check_format_version()
+            .map_err(CommandError::Parse)?;
+        Ok((summary, output.stderr))
+    }
+
+    /// Executes `cargo nextest list`, writing the output to `path` rather than to standard output.
+    ///
+    /// The returned [`TestListFile`] can be used to parse the file once the command has finished.
+    /// This is useful for large workspaces, since the JSON output is read directly from the file
+    /// rather than being buffered in memory first.
+    pub fn exec_to_file(&self, path: impl Into<Utf8PathBuf>) -> Result<TestListFile, CommandError> {
+        let path = path.into();
         let mut command = self.cargo_command();
-        let output = command.output().map_err(CommandError::Exec)?;
+        command.args(["--output-file", path.as_str()]);
+        let output = self.output(command)?;
+
+        if !output.status.success() {
+            // The process exited with a non-zero code.
+            let exit_code = output.status.code();
+            let stderr = output.stderr;
+            return Err(CommandError::CommandFailed { exit_code, stderr });
+        }
+
+        Ok(TestListFile { path })
+    }
+
+    /// Executes `cargo nextest list --message-format json-summary` and parses the output into a
+    /// [`TestCountSummary`].
+    ///
+    /// This is cheaper than [`Self::exec`] for large workspaces, since only the number of tests is
+    /// returned rather than the full list.
+    pub fn exec_count(&self) -> Result<TestCountSummary, CommandError> {
+        let output = self.output(self.cargo_command_with_format("json-summary"))?;
 
         if !output.status.success() {
             // The process exited with a non-zero code.
@@ -107,7 +187,7 @@ impl ListCommand {
     pub fn exec_binaries_only(&self) -> Result<BinaryListSummary, CommandError> {
         let mut command = self.cargo_command();
         command.arg("--list-type=binaries-only");
-        let output = command.output().map_err(CommandError::Exec)?;
+        let output = self.output(command)?;
 
         if !output.status.success() {
             // The process exited with a non-zero code.
@@ -119,6 +199,126 @@ impl ListCommand {
         // Try parsing stdout.
         serde_json::from_slice(&output.stdout).map_err(CommandError::Json)
     }
+
+    /// Runs `command` to completion, killing it if the timeout is exceeded.
+    fn output(&self, mut command: Command) -> Result<Output, CommandError> {
+        let Some(timeout) = self.timeout else {
+            return command.output().map_err(CommandError::Exec);
+        };
+        // A timeout too large to be represented as a deadline is treated as no timeout.
+        let Some(deadline) = Instant::now().checked_add(timeout) else {
+            return command.output().map_err(CommandError::Exec);
+        };
+
+        // Start the process in its own process group so that the whole tree can be killed on
+        // timeout. This is only done if a timeout is set, since it stops the process from
+        // receiving Ctrl-C from the terminal.
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+
+        let mut child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(CommandError::Exec)?;
+
+        // Read stdout and stderr on separate threads, so that the process doesn't block on a full
+        // pipe while it's being waited on.
+        let stdout = read_to_end_thread(child.stdout.take().expect("stdout is piped"));
+        let stderr = read_to_end_thread(child.stderr.take().expect("stderr is piped"));
+
+        let status = loop {
+            if let Some(status) = child.try_wait().map_err(CommandError::Exec)? {
+                break status;
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                kill_process_tree(&mut child);
+                _ = child.wait();
+                // The reader threads are not joined, since a process that escaped the kill
+                // could be holding the pipes open.
+                return Err(CommandError::Timeout { timeout });
+            }
+            thread::sleep((deadline - now).min(Duration::from_millis(50)));
+        };
+
+        Ok(Output {
+            status,
+            stdout: stdout.join().expect("stdout reader thread panicked"),
+            stderr: stderr.join().expect("stderr reader thread panicked"),
+        })
+    }
+}
+
+/// A test list written to a file by [`ListCommand::exec_to_file`].
+#[derive(Clone, Debug)]
+pub struct TestListFile {
+    path: Utf8PathBuf,
+}
+
+impl TestListFile {
+    /// Returns the path the test list was written to.
+    ///
+    /// If the path was relative, it is relative to the current directory of the `cargo nextest
+    /// list` process.
+    pub fn path(&self) -> &Utf8Path {
+        &self.path
+    }
+
+    /// Parses the file into a [`TestListSummary`].
+    ///
+    /// The file is read incrementally, so its contents are never held in memory all at once.
+    pub fn parse(&self) -> Result<TestListSummary, CommandError> {
+        let file = File::open(&self.path).map_err(|error| CommandError::OutputFileRead {
+            path: self.path.clone(),
+            error,
+        })?;
+        let summary: TestListSummary =
+            serde_json::from_reader(BufReader::new(file)).map_err(CommandError::Json)?;
+        summary
+            .check_format_version()
+            .map_err(CommandError::Parse)?;
+        Ok(summary)
+    }
+}
+
+fn read_to_end_thread(mut reader: impl Read + Send + 'static) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        // Errors are treated as the end of output.
+        _ = reader.read_to_end(&mut buf);
+        buf
+    })
+}
+
+#[cfg(unix)]
+fn kill_process_tree(child: &mut Child) {
+    // The child is the leader of its own process group, so this kills it along with any
+    // processes it started.
+    //
+    // SAFETY: kill has no memory safety preconditions. The process group can't have been reused
+    // yet, since the child (its leader) hasn't been waited on.
+    unsafe {
+        libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+    }
+}
+
+#[cfg(windows)]
+fn kill_process_tree(child: &mut Child) {
+    // /T kills the process along with any processes it started.
+    _ = Command::new("taskkill")
+        .args(["/F", "/T", "/PID", &child.id().to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    // In case taskkill isn't available.
+    _ = child.kill();
+}
+
+#[cfg(not(any(unix, windows)))]
+fn kill_process_tree(child: &mut Child) {
+    _ = child.kill();
 }
 
 /// Root element for a serializable list of tests generated by nextest.
@@ -126,6 +326,14 @@ impl ListCommand {
 #[serde(rename_all = "kebab-case")]
 #[non_exhaustive]
 pub struct TestListSummary {
+    /// The version of the format this summary was written in.
+    ///
+    /// Summaries written by versions of nextest that predate this field are treated as version 1.
+    /// [`TestListSummary::parse_json`] rejects versions other than
+    /// [`TestListSummary::FORMAT_VERSION`].
+    #[serde(default = "default_format_version")]
+    pub format_version: u32,
+
     /// Rust metadata used for builds and test runs.
     pub rust_build_meta: RustBuildMetaSummary,
 
@@ -137,18 +345,178 @@ pub struct TestListSummary {
     pub rust_suites: BTreeMap<RustBinaryId, RustTestSuiteSummary>,
 }
 
+fn default_format_version() -> u32 {
+    1
+}
+
 impl TestListSummary {
+    /// The version of the format written by this version of nextest.
+    pub const FORMAT_VERSION: u32 = 1;
+
     /// Creates a new `TestListSummary` with the given Rust metadata.
     pub fn new(rust_build_meta: RustBuildMetaSummary) -> Self {
         Self {
+            format_version: Self::FORMAT_VERSION,
             rust_build_meta,
             test_count: 0,
             rust_suites: BTreeMap::new(),
         }
     }
+
+    /// Returns the number of tests that match the filter, across all binaries.
+    ///
+    /// These are the tests that will be run. Unlike [`Self::test_count`], this doesn't include
+    /// tests that are skipped.
+    pub fn matching_test_count(&self) -> usize {
+        self.iter_test_cases()
+            .filter(|test_case| test_case.filter_match.is_match())
+            .count()
+    }
+
+    /// Returns the number of tests marked ignored, across all binaries.
+    ///
+    /// This includes ignored tests regardless of whether they match the filter.
+    pub fn ignored_count(&self) -> usize {
+        self.iter_test_cases()
+            .filter(|test_case| test_case.ignored)
+            .count()
+    }
+
+    fn iter_test_cases(&self) -> impl Iterator<Item = &RustTestCaseSummary> + '_ {
+        self.rust_suites
+            .values()
+            .flat_map(|suite| suite.test_cases.values())
+    }
+
     /// Parse JSON output from `cargo nextest list --message-format json`.
-    pub fn parse_json(json: impl AsRef<str>) -> Result<Self, serde_json::Error> {
-        serde_json::from_str(json.as_ref())
+    ///
+    /// Returns an error if the output was written in a format version that this version of
+    /// nextest-metadata doesn't understand.
+    pub fn parse_json(json: impl AsRef<str>) -> Result<Self, TestListParseError> {
+        let summary: Self =
+            serde_json::from_str(json.as_ref()).map_err(TestListParseError::Json)?;
+        summary.check_format_version()?;
+        Ok(summary)
+    }
+
+    fn check_format_version(&self) -> Result<(), TestListParseError> {
+        if self.format_version == Self::FORMAT_VERSION {
+            Ok(())
+        } else {
+            Err(TestListParseError::UnsupportedFormatVersion {
+                version: self.format_version,
+                supported: Self::FORMAT_VERSION,
+            })
+        }
+    }
+
+    /// Iterates over the test suites in this list, grouped by build platform.
+    ///
+    /// Suites built for `first` are returned before suites built for the other platform. Within
+    /// each group, suites are sorted by binary ID. As with [`Self::host_suites`], proc-macro test
+    /// suites are treated as built for the host.
+    pub fn iter_by_build_platform(
+        &self,
+        first: BuildPlatform,
+    ) -> impl Iterator<Item = &RustTestSuiteSummary> + '_ {
+        let (first_suites, rest): (Vec<_>, Vec<_>) = self
+            .rust_suites
+            .values()
+            .partition(|suite| suite_build_platform(suite) == first);
+        first_suites.into_iter().chain(rest)
+    }
+
+    /// Iterates over the test suites in this list that were built for the host platform.
+    ///
+    /// Proc-macro test suites are always built for the host, so they are returned here.
+    pub fn host_suites(&self) -> impl Iterator<Item = &RustTestSuiteSummary> + '_ {
+        self.rust_suites
+            .values()
+            .filter(|suite| suite_build_platform(suite) == BuildPlatform::Host)
+    }
+
+    /// Iterates over the test suites in this list that were built for the target platform.
+    pub fn target_suites(&self) -> impl Iterator<Item = &RustTestSuiteSummary> + '_ {
+        self.rust_suites
+            .values()
+            .filter(|suite| suite_build_platform(suite) == BuildPlatform::Target)
+    }
+
+    /// Returns the number of test suites built for the host and target platforms respectively, as
+    /// `(host, target)`.
+    ///
+    /// Proc-macro test suites are counted under the host platform.
+    pub fn count_by_platform(&self) -> (usize, usize) {
+        let host = self.host_suites().count();
+        (host, self.rust_suites.len() - host)
+    }
+
+    /// Compares this test list against an earlier `baseline`, returning the tests that were added,
+    /// removed, or had their status changed.
+    ///
+    /// Tests are matched up by binary ID and test name. Tests in binaries that were skipped have no
+    /// test cases, so if a binary is skipped in one list but not the other, its tests are reported
+    /// as added or removed.
+    pub fn diff(&self, baseline: &TestListSummary) -> TestListDiff {
+        let mut diff = TestListDiff::default();
+
+        for (binary_id, suite) in &self.rust_suites {
+            let baseline_cases = baseline
+                .rust_suites
+                .get(binary_id)
+                .map(|suite| &suite.test_cases);
+            for (name, test_case) in &suite.test_cases {
+                match baseline_cases.and_then(|cases| cases.get(name)) {
+                    Some(baseline_case) => {
+                        if baseline_case.ignored != test_case.ignored
+                            || baseline_case.filter_match != test_case.filter_match
+                        {
+                            diff.modified.entry(binary_id.clone()).or_default().insert(
+                                name.clone(),
+                                TestCaseChange {
+                                    baseline_ignored: baseline_case.ignored,
+                                    ignored: test_case.ignored,
+                                    baseline_filter_match: baseline_case.filter_match,
+                                    filter_match: test_case.filter_match,
+                                },
+                            );
+                        }
+                    }
+                    None => {
+                        diff.added
+                            .entry(binary_id.clone())
+                            .or_default()
+                            .insert(name.clone());
+                    }
+                }
+            }
+        }
+
+        for (binary_id, baseline_suite) in &baseline.rust_suites {
+            let cases = self
+                .rust_suites
+                .get(binary_id)
+                .map(|suite| &suite.test_cases);
+            for name in baseline_suite.test_cases.keys() {
+                if !cases.is_some_and(|cases| cases.contains_key(name)) {
+                    diff.removed
+                        .entry(binary_id.clone())
+                        .or_default()
+                        .insert(name.clone());
+                }
+            }
+        }
+
+        diff
+    }
+}
+
+/// Returns the platform a test suite was built for, treating proc-macro suites as host-built.
+fn suite_build_platform(suite: &RustTestSuiteSummary) -> BuildPlatform {
+    if suite.binary.kind == RustTestBinaryKind::PROC_MACRO {
+        BuildPlatform::Host
+    } else {
+        suite.binary.build_platform
     }
 }
 
@@ -248,6 +616,82 @@ impl fmt::Display for RustTestBinaryKind {
     }
 }
 
+/// A summary of the number of tests in a test list, generated by
+/// `cargo nextest list --message-format json-summary`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub struct TestCountSummary {
+    /// Number of test binaries, including binaries that were skipped.
+    pub binary_count: usize,
+
+    /// Number of tests (including skipped and ignored) across all binaries.
+    pub test_count: usize,
+
+    /// Number of tests that match the filter across all binaries. These are the tests that will be
+    /// run.
+    pub matching_test_count: usize,
+}
+
+impl TestCountSummary {
+    /// Creates a new `TestCountSummary`.
+    pub fn new(binary_count: usize, test_count: usize, matching_test_count: usize) -> Self {
+        Self {
+            binary_count,
+            test_count,
+            matching_test_count,
+        }
+    }
+
+    /// Parse JSON output from `cargo nextest list --message-format json-summary`.
+    pub fn parse_json(json: impl AsRef<str>) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json.as_ref())
+    }
+}
+
+/// The differences between two test lists, returned by [`TestListSummary::diff`].
+///
+/// All maps are keyed by binary ID, then by test name.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub struct TestListDiff {
+    /// Tests that are present in the current list but not in the baseline.
+    pub added: BTreeMap<RustBinaryId, BTreeSet<String>>,
+
+    /// Tests that are present in the baseline but not in the current list.
+    pub removed: BTreeMap<RustBinaryId, BTreeSet<String>>,
+
+    /// Tests that are present in both lists, but whose ignored or filter match status changed.
+    pub modified: BTreeMap<RustBinaryId, BTreeMap<String, TestCaseChange>>,
+}
+
+impl TestListDiff {
+    /// Returns true if there are no differences between the two lists.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// A change in the status of a test between two test lists.
+///
+/// Part of a [`TestListDiff`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TestCaseChange {
+    /// Whether the test was marked ignored in the baseline.
+    pub baseline_ignored: bool,
+
+    /// Whether the test is marked ignored in the current list.
+    pub ignored: bool,
+
+    /// Whether the test matched the filter in the baseline.
+    pub baseline_filter_match: FilterMatch,
+
+    /// Whether the test matches the filter in the current list.
+    pub filter_match: FilterMatch,
+}
+
 /// A serializable suite of test binaries.
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
@@ -295,6 +739,22 @@ impl RustBinaryId {
     ///
     /// This format is part of nextest's stable API.
     ///
+    /// This is the same scheme nextest uses for the binary IDs it displays and matches with the
+    /// `binary_id()` filterset predicate, so it can be used to build filters reliably. Some notes:
+    ///
+    /// * The package name is always used as written in `Cargo.toml`, including any hyphens. For
+    ///   unit tests, the name of the library target (which has hyphens replaced with underscores)
+    ///   is ignored.
+    /// * Libraries with any crate type (`rlib`, `cdylib`, `staticlib` and so on) have the `lib`
+    ///   kind. A package has at most one library target, which may be a procedural macro, so the
+    ///   package name alone identifies its unit tests.
+    /// * The package name is always a prefix, even if a target has the same name as its package.
+    ///   For example, the binary target `my-package` in `my-package` has the binary ID
+    ///   `my-package::bin/my-package`, and isn't confused with its unit tests (`my-package`).
+    /// * Integration tests are the only other kind without a `{kind}/` prefix, so an integration
+    ///   test named `bin` has the ID `my-package::bin`, while binaries have IDs beginning with
+    ///   `my-package::bin/`.
+    ///
     /// # Examples
     ///
     /// ```
@@ -503,6 +963,36 @@ pub struct RustBuildMetaSummary {
     pub target_platform: Option<String>,
 }
 
+impl RustBuildMetaSummary {
+    /// Returns the linked paths, joined against the target directory.
+    ///
+    /// Linked paths that are already absolute are returned unchanged.
+    pub fn resolved_linked_paths(&self) -> Vec<Utf8PathBuf> {
+        self.linked_paths
+            .iter()
+            .map(|path| self.resolve_path(path))
+            .collect()
+    }
+
+    /// Returns the base output directories, joined against the target directory.
+    ///
+    /// Directories that are already absolute are returned unchanged.
+    pub fn resolved_output_directories(&self) -> Vec<Utf8PathBuf> {
+        self.base_output_directories
+            .iter()
+            .map(|path| self.resolve_path(path))
+            .collect()
+    }
+
+    fn resolve_path(&self, path: &Utf8Path) -> Utf8PathBuf {
+        if path.is_absolute() {
+            path.to_owned()
+        } else {
+            self.target_directory.join(path)
+        }
+    }
+}
+
 /// A non-test Rust binary. Used to set the correct environment
 /// variables in reused builds.
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
@@ -740,6 +1230,65 @@ pub struct RustTestCaseSummary {
     ///
     /// Only tests that match the filter are run.
     pub filter_match: FilterMatch,
+
+    /// The estimated duration of this test, computed as the median of its recorded durations in
+    /// the timings store.
+    ///
+    /// This is only populated if timings were requested while listing tests, and is `None` for
+    /// tests without any recorded history. Serialized as a number of seconds.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "option_duration_secs"
+    )]
+    pub estimated_duration: Option<Duration>,
+
+    /// The duration of this test in the most recent run recorded in the timings store.
+    ///
+    /// Like [`Self::estimated_duration`], this is only populated if timings were requested while
+    /// listing tests, and is `None` for tests without any recorded history. Serialized as a number
+    /// of seconds.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "option_duration_secs"
+    )]
+    pub last_run_time: Option<Duration>,
+
+    /// How often this test was flaky in recent runs.
+    ///
+    /// A run is flaky if the test failed at least once, but then passed on a retry. The rate is
+    /// computed over the most recent runs recorded in the timings store.
+    ///
+    /// This is only populated if flakiness history was requested while listing tests, and is
+    /// `None` for tests without any recorded history.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flaky_rate: Option<FlakyRate>,
+}
+
+/// How often a test was flaky across a number of recorded runs.
+///
+/// Part of a [`RustTestCaseSummary`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct FlakyRate {
+    /// The number of recorded runs in which the test was flaky.
+    pub flaky_runs: usize,
+
+    /// The total number of recorded runs.
+    pub total_runs: usize,
+}
+
+impl FlakyRate {
+    /// Returns the fraction of recorded runs in which the test was flaky, from 0.0 to 1.0.
+    ///
+    /// Returns 0.0 if there are no recorded runs.
+    pub fn rate(&self) -> f64 {
+        if self.total_runs == 0 {
+            return 0.0;
+        }
+        self.flaky_runs as f64 / self.total_runs as f64
+    }
 }
 
 /// An enum describing whether a test matches a filter.
@@ -780,6 +1329,12 @@ pub enum MismatchReason {
     /// This test is in a different partition.
     Partition,
 
+    /// This test didn't have the requested result in the previous run.
+    PreviousResult,
+
+    /// This test is in a binary that isn't affected by the files changed since a git ref.
+    Unchanged,
+
     /// This test is filtered out by the default-filter.
     ///
     /// This is the lowest-priority reason for skipping a test.
@@ -795,6 +1350,12 @@ impl fmt::Display for MismatchReason {
                 write!(f, "does not match the provided expression filters")
             }
             MismatchReason::Partition => write!(f, "is in a different partition"),
+            MismatchReason::PreviousResult => {
+                write!(f, "did not have the requested result in the previous run")
+            }
+            MismatchReason::Unchanged => {
+                write!(f, "is not affected by the changed files")
+            }
             MismatchReason::DefaultFilter => {
                 write!(f, "is filtered out by the profile's default-filter")
             }
@@ -802,6 +1363,34 @@ impl fmt::Display for MismatchReason {
     }
 }
 
+/// Serializes an `Option<Duration>` as an optional number of seconds.
+mod option_duration_secs {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub(super) fn serialize<S>(
+        duration: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match duration {
+            Some(duration) => serializer.serialize_some(&duration.as_secs_f64()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub(super) fn deserialize<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let secs = Option::<f64>::deserialize(deserializer)?;
+        secs.map(|secs| Duration::try_from_secs_f64(secs).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -847,6 +1436,74 @@ mod tests {
         );
     }
 
+    // These are the binary IDs produced by `cargo nextest list` for the fixture workspace.
+    #[test_case("nextest-tests", RustTestBinaryKind::LIB, "nextest_tests", "nextest-tests"; "lib")]
+    #[test_case("cdylib-example", RustTestBinaryKind::LIB, "cdylib_example", "cdylib-example"; "cdylib")]
+    #[test_case("proc-macro-test", RustTestBinaryKind::PROC_MACRO, "proc_macro_test", "proc-macro-test"; "proc-macro")]
+    #[test_case("nextest-tests", RustTestBinaryKind::TEST, "basic", "nextest-tests::basic"; "integration test")]
+    #[test_case("nextest-tests", RustTestBinaryKind::BIN, "nextest-tests", "nextest-tests::bin/nextest-tests"; "bin named after package")]
+    #[test_case("nextest-tests", RustTestBinaryKind::BIN, "other", "nextest-tests::bin/other"; "other bin")]
+    #[test_case("nextest-tests", RustTestBinaryKind::BENCH, "my-bench", "nextest-tests::bench/my-bench"; "bench")]
+    #[test_case("nextest-tests", RustTestBinaryKind::new("example"), "other", "nextest-tests::example/other"; "example")]
+    fn test_binary_id_from_parts(
+        package_name: &str,
+        kind: RustTestBinaryKind,
+        target_name: &str,
+        expected: &str,
+    ) {
+        let id = RustBinaryId::from_parts(package_name, &kind, target_name);
+        assert_eq!(id.as_str(), expected);
+
+        // The components of the ID round-trip.
+        let components = id.components();
+        assert_eq!(components.package_name, package_name);
+        let expected_name_and_kind =
+            if kind == RustTestBinaryKind::LIB || kind == RustTestBinaryKind::PROC_MACRO {
+                RustBinaryIdNameAndKind::None
+            } else if kind == RustTestBinaryKind::TEST {
+                RustBinaryIdNameAndKind::NameOnly {
+                    binary_name: target_name,
+                }
+            } else {
+                RustBinaryIdNameAndKind::NameAndKind {
+                    kind: kind.as_str(),
+                    binary_name: target_name,
+                }
+            };
+        assert_eq!(components.binary_name_and_kind, expected_name_and_kind);
+    }
+
+    // Absolute paths in this test are Unix-style.
+    #[cfg(unix)]
+    #[test]
+    fn test_resolved_paths() {
+        let build_meta = RustBuildMetaSummary {
+            target_directory: "/foo/target".into(),
+            base_output_directories: ["debug".into(), "x86_64-unknown-linux-gnu/debug".into()]
+                .into_iter()
+                .collect(),
+            linked_paths: ["debug/build/foo/out".into(), "/usr/lib/native".into()]
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            build_meta.resolved_linked_paths(),
+            [
+                Utf8PathBuf::from("/usr/lib/native"),
+                Utf8PathBuf::from("/foo/target/debug/build/foo/out"),
+            ]
+        );
+        assert_eq!(
+            build_meta.resolved_output_directories(),
+            [
+                Utf8PathBuf::from("/foo/target/debug"),
+                Utf8PathBuf::from("/foo/target/x86_64-unknown-linux-gnu/debug"),
+            ]
+        );
+    }
+
     #[test]
     fn test_binary_id_ord() {
         let empty = RustBinaryId::new("");
@@ -887,4 +1544,455 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_matching_and_ignored_counts() {
+        let summary = TestListSummary::parse_json(
+            r#"{
+                "rust-build-meta": {
+                    "target-directory": "/foo",
+                    "base-output-directories": [],
+                    "non-test-binaries": {},
+                    "linked-paths": []
+                },
+                "test-count": 5,
+                "rust-suites": {
+                    "my-crate": {
+                        "package-name": "my-crate",
+                        "binary-id": "my-crate",
+                        "binary-name": "my-crate",
+                        "package-id": "my-crate 0.1.0",
+                        "kind": "lib",
+                        "binary-path": "/foo/my-crate",
+                        "build-platform": "target",
+                        "cwd": "/my-crate",
+                        "testcases": {
+                            "test_matches": {
+                                "ignored": false,
+                                "filter-match": { "status": "matches" }
+                            },
+                            "test_ignored_matches": {
+                                "ignored": true,
+                                "filter-match": { "status": "matches" }
+                            },
+                            "test_ignored": {
+                                "ignored": true,
+                                "filter-match": { "status": "mismatch", "reason": "ignored" }
+                            }
+                        }
+                    },
+                    "my-crate::basic": {
+                        "package-name": "my-crate",
+                        "binary-id": "my-crate::basic",
+                        "binary-name": "basic",
+                        "package-id": "my-crate 0.1.0",
+                        "kind": "test",
+                        "binary-path": "/foo/basic",
+                        "build-platform": "target",
+                        "cwd": "/my-crate",
+                        "testcases": {
+                            "test_string_mismatch": {
+                                "ignored": false,
+                                "filter-match": { "status": "mismatch", "reason": "string" }
+                            },
+                            "test_matches_2": {
+                                "ignored": false,
+                                "filter-match": { "status": "matches" }
+                            }
+                        }
+                    },
+                    "my-crate::skipped": {
+                        "package-name": "my-crate",
+                        "binary-id": "my-crate::skipped",
+                        "binary-name": "skipped",
+                        "package-id": "my-crate 0.1.0",
+                        "kind": "test",
+                        "binary-path": "/foo/skipped",
+                        "build-platform": "target",
+                        "cwd": "/my-crate",
+                        "status": "skipped",
+                        "testcases": {}
+                    }
+                }
+            }"#,
+        )
+        .expect("test list summary should deserialize");
+
+        assert_eq!(summary.test_count, 5);
+        assert_eq!(summary.matching_test_count(), 3);
+        assert_eq!(summary.ignored_count(), 2);
+
+        let empty = TestListSummary::new(summary.rust_build_meta.clone());
+        assert_eq!(empty.matching_test_count(), 0);
+        assert_eq!(empty.ignored_count(), 0);
+    }
+
+    #[test]
+    fn test_suites_by_platform() {
+        let suite = |binary_id: &str, kind: &str, build_platform: &str| {
+            format!(
+                r#""{binary_id}": {{
+                    "package-name": "my-crate",
+                    "binary-id": "{binary_id}",
+                    "binary-name": "{binary_id}",
+                    "package-id": "my-crate 0.1.0",
+                    "kind": "{kind}",
+                    "binary-path": "/foo/{binary_id}",
+                    "build-platform": "{build_platform}",
+                    "cwd": "/my-crate",
+                    "testcases": {{}}
+                }}"#
+            )
+        };
+        let suites = [
+            suite("my-crate", "lib", "target"),
+            suite("my-crate::basic", "test", "target"),
+            suite("my-crate::build-tool", "bin", "host"),
+            // Proc-macro suites are reported under host, whatever their build platform says.
+            suite("my-derive", "proc-macro", "target"),
+        ]
+        .join(",");
+        let summary = TestListSummary::parse_json(format!(
+            r#"{{
+                "rust-build-meta": {{
+                    "target-directory": "/foo",
+                    "base-output-directories": [],
+                    "non-test-binaries": {{}},
+                    "linked-paths": []
+                }},
+                "test-count": 0,
+                "rust-suites": {{ {suites} }}
+            }}"#
+        ))
+        .expect("test list summary should deserialize");
+
+        fn binary_ids<'a>(suites: impl Iterator<Item = &'a RustTestSuiteSummary>) -> Vec<&'a str> {
+            suites
+                .map(|suite| suite.binary.binary_id.as_str())
+                .collect()
+        }
+        assert_eq!(
+            binary_ids(summary.host_suites()),
+            ["my-crate::build-tool", "my-derive"]
+        );
+        assert_eq!(
+            binary_ids(summary.target_suites()),
+            ["my-crate", "my-crate::basic"]
+        );
+        assert_eq!(summary.count_by_platform(), (2, 2));
+
+        // Grouping by platform uses the same rules, and keeps binary ID order within each group.
+        assert_eq!(
+            binary_ids(summary.iter_by_build_platform(BuildPlatform::Host)),
+            [
+                "my-crate::build-tool",
+                "my-derive",
+                "my-crate",
+                "my-crate::basic"
+            ]
+        );
+        assert_eq!(
+            binary_ids(summary.iter_by_build_platform(BuildPlatform::Target)),
+            [
+                "my-crate",
+                "my-crate::basic",
+                "my-crate::build-tool",
+                "my-derive"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_list_diff() {
+        let list = |suites: &[String]| {
+            let suites = suites.join(",");
+            TestListSummary::parse_json(format!(
+                r#"{{
+                    "rust-build-meta": {{
+                        "target-directory": "/foo",
+                        "base-output-directories": [],
+                        "non-test-binaries": {{}},
+                        "linked-paths": []
+                    }},
+                    "test-count": 0,
+                    "rust-suites": {{ {suites} }}
+                }}"#
+            ))
+            .expect("test list summary should deserialize")
+        };
+        let matches = r#"{ "ignored": false, "filter-match": { "status": "matches" } }"#;
+        let ignored =
+            r#"{ "ignored": true, "filter-match": { "status": "mismatch", "reason": "ignored" } }"#;
+        let suite = |binary_id: &str, test_cases: &[(&str, &str)]| {
+            let test_cases = test_cases
+                .iter()
+                .map(|(name, test_case)| format!(r#""{name}": {test_case}"#))
+                .collect::<Vec<_>>()
+                .join(",");
+            format!(
+                r#""{binary_id}": {{
+                    "package-name": "my-crate",
+                    "binary-id": "{binary_id}",
+                    "binary-name": "{binary_id}",
+                    "package-id": "my-crate 0.1.0",
+                    "kind": "lib",
+                    "binary-path": "/foo/{binary_id}",
+                    "build-platform": "target",
+                    "cwd": "/my-crate",
+                    "testcases": {{ {test_cases} }}
+                }}"#
+            )
+        };
+
+        let baseline = list(&[
+            suite(
+                "my-crate",
+                &[
+                    ("test_same", matches),
+                    ("test_removed", matches),
+                    ("test_now_ignored", matches),
+                ],
+            ),
+            suite("my-crate::gone", &[("test_a", matches)]),
+        ]);
+        let current = list(&[
+            suite(
+                "my-crate",
+                &[
+                    ("test_same", matches),
+                    ("test_added", matches),
+                    ("test_now_ignored", ignored),
+                ],
+            ),
+            suite("my-crate::new", &[("test_b", matches)]),
+        ]);
+
+        assert!(
+            current.diff(&current).is_empty(),
+            "list has no diff with itself"
+        );
+
+        let diff = current.diff(&baseline);
+        let tests = |binary_id: &str, names: &[&str]| {
+            (
+                RustBinaryId::new(binary_id),
+                names.iter().map(|name| (*name).to_owned()).collect(),
+            )
+        };
+        assert_eq!(
+            diff.added,
+            BTreeMap::from([
+                tests("my-crate", &["test_added"]),
+                tests("my-crate::new", &["test_b"]),
+            ])
+        );
+        assert_eq!(
+            diff.removed,
+            BTreeMap::from([
+                tests("my-crate", &["test_removed"]),
+                tests("my-crate::gone", &["test_a"]),
+            ])
+        );
+        assert_eq!(
+            diff.modified,
+            BTreeMap::from([(
+                RustBinaryId::new("my-crate"),
+                BTreeMap::from([(
+                    "test_now_ignored".to_owned(),
+                    TestCaseChange {
+                        baseline_ignored: false,
+                        ignored: true,
+                        baseline_filter_match: FilterMatch::Matches,
+                        filter_match: FilterMatch::Mismatch {
+                            reason: MismatchReason::Ignored
+                        },
+                    }
+                )]),
+            )])
+        );
+    }
+
+    #[test]
+    fn test_format_version() {
+        let json_with_version = |version: Option<u32>| {
+            let version = version.map_or(String::new(), |v| format!(r#""format-version": {v},"#));
+            format!(
+                r#"{{
+                    {version}
+                    "rust-build-meta": {{
+                        "target-directory": "/foo",
+                        "base-output-directories": [],
+                        "non-test-binaries": {{}},
+                        "linked-paths": []
+                    }},
+                    "test-count": 0,
+                    "rust-suites": {{}}
+                }}"#
+            )
+        };
+
+        // A missing version is treated as version 1.
+        let summary = TestListSummary::parse_json(json_with_version(None))
+            .expect("missing version is accepted");
+        assert_eq!(summary.format_version, 1);
+
+        let summary = TestListSummary::parse_json(json_with_version(Some(1)))
+            .expect("current version is accepted");
+        assert_eq!(summary.format_version, TestListSummary::FORMAT_VERSION);
+
+        // New summaries are written with the current version.
+        let serialized =
+            serde_json::to_string(&TestListSummary::new(summary.rust_build_meta.clone())).unwrap();
+        assert!(
+            serialized.starts_with(r#"{"format-version":1,"#),
+            "serialized summary starts with the format version: {serialized}"
+        );
+
+        for version in [0, 2] {
+            let error = TestListSummary::parse_json(json_with_version(Some(version)))
+                .expect_err("unknown version is rejected");
+            assert!(
+                matches!(
+                    error,
+                    TestListParseError::UnsupportedFormatVersion { version: v, supported: 1 }
+                        if v == version
+                ),
+                "unexpected error: {error:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_count_summary_json() {
+        let summary = TestCountSummary::parse_json(
+            r#"{"binary-count": 3, "test-count": 10, "matching-test-count": 4}"#,
+        )
+        .expect("count summary parsed");
+        assert_eq!(summary, TestCountSummary::new(3, 10, 4));
+
+        let serialized = serde_json::to_string(&summary).expect("count summary serialized");
+        assert_eq!(
+            serialized,
+            r#"{"binary-count":3,"test-count":10,"matching-test-count":4}"#
+        );
+    }
+
+    #[test_case(r#"{
+        "ignored": false,
+        "filter-match": { "status": "matches" }
+    }"#, None, None, None; "no history")]
+    #[test_case(r#"{
+        "ignored": false,
+        "filter-match": { "status": "matches" },
+        "estimated-duration": 1.5
+    }"#, Some(Duration::from_millis(1500)), None, None; "with estimated duration")]
+    #[test_case(r#"{
+        "ignored": false,
+        "filter-match": { "status": "matches" },
+        "estimated-duration": 1.5,
+        "last-run-time": 2.25
+    }"#, Some(Duration::from_millis(1500)), Some(Duration::from_millis(2250)), None; "with last run time")]
+    #[test_case(r#"{
+        "ignored": false,
+        "filter-match": { "status": "matches" },
+        "flaky-rate": { "flaky-runs": 1, "total-runs": 4 }
+    }"#, None, None, Some(FlakyRate { flaky_runs: 1, total_runs: 4 }); "with flaky rate")]
+    fn test_deserialize_test_case_summary(
+        input: &str,
+        expected_duration: Option<Duration>,
+        expected_last_run_time: Option<Duration>,
+        expected_flaky_rate: Option<FlakyRate>,
+    ) {
+        let summary: RustTestCaseSummary =
+            serde_json::from_str(input).expect("test case summary should deserialize");
+        assert_eq!(summary.estimated_duration, expected_duration);
+        assert_eq!(summary.last_run_time, expected_last_run_time);
+        assert_eq!(summary.flaky_rate, expected_flaky_rate);
+        if let Some(flaky_rate) = expected_flaky_rate {
+            assert_eq!(flaky_rate.rate(), 0.25);
+        }
+
+        // Serializing should round-trip, and omit fields that are None.
+        let serialized = serde_json::to_string(&summary).expect("test case summary serializes");
+        assert_eq!(
+            serialized.contains("estimated-duration"),
+            expected_duration.is_some(),
+            "serialized: {serialized}"
+        );
+        assert_eq!(
+            serialized.contains("last-run-time"),
+            expected_last_run_time.is_some(),
+            "serialized: {serialized}"
+        );
+        assert_eq!(
+            serialized.contains("flaky-rate"),
+            expected_flaky_rate.is_some(),
+            "serialized: {serialized}"
+        );
+        let roundtrip: RustTestCaseSummary =
+            serde_json::from_str(&serialized).expect("round-trip deserializes");
+        assert_eq!(roundtrip, summary);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn list_command_timeout() {
+        // Use `sh` as the cargo path, so that `sh nextest list ...` runs the `nextest` script in
+        // the current directory. The script starts a child process, records its PID, and waits on
+        // it.
+        let dir =
+            std::env::temp_dir().join(format!("nextest-metadata-timeout-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("nextest"),
+            "sleep 30 &\necho $! > sleep.pid\nwait\n",
+        )
+        .unwrap();
+        let dir = Utf8PathBuf::try_from(dir).expect("temp dir is UTF-8");
+
+        let start = Instant::now();
+        let error = ListCommand::new()
+            .cargo_path("sh")
+            .current_dir(&dir)
+            .timeout(Duration::from_millis(200))
+            .exec()
+            .expect_err("command times out");
+        let elapsed = start.elapsed();
+        let sleep_pid = std::fs::read_to_string(dir.join("sleep.pid")).unwrap();
+        let sleep_pid = sleep_pid.trim().to_owned();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(
+            matches!(error, CommandError::Timeout { timeout } if timeout == Duration::from_millis(200)),
+            "unexpected error: {error:?}"
+        );
+        assert!(
+            elapsed < Duration::from_secs(10),
+            "command was killed promptly, took {elapsed:?}"
+        );
+
+        // The grandchild should have been killed along with the script. It may take a moment to
+        // be reaped once it has been reparented.
+        let wait_start = Instant::now();
+        while is_process_running(&sleep_pid) {
+            assert!(
+                wait_start.elapsed() < Duration::from_secs(10),
+                "grandchild process {sleep_pid} is still running"
+            );
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    #[cfg(unix)]
+    fn is_process_running(pid: &str) -> bool {
+        // ps prints nothing for a process that doesn't exist, and a state starting with Z for a
+        // process that has exited but not been reaped.
+        let output = std::process::Command::new("ps")
+            .args(["-o", "stat=", "-p", pid])
+            .output()
+            .expect("ps ran successfully");
+        let stat = String::from_utf8_lossy(&output.stdout);
+        let stat = stat.trim();
+        !stat.is_empty() && !stat.starts_with('Z')
+    }
 }
@@ -0,0 +1,36 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+
+/// Type for the on-broken-pipe config key: what to do if the reader of nextest's output goes away.
+///
+/// This typically happens when nextest's output is piped to a command like `head`, which exits
+/// after reading part of its input.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OnBrokenPipe {
+    /// Stop writing to the closed output, and keep running tests. Other outputs, such as JUnit
+    /// reports, are still written.
+    #[default]
+    Ignore,
+
+    /// Cancel the run, as with any other error writing output.
+    Cancel,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_helpers::parse_ci_profile;
+    use test_case::test_case;
+
+    #[test_case("", OnBrokenPipe::Ignore; "default")]
+    #[test_case(r#"on-broken-pipe = "ignore""#, OnBrokenPipe::Ignore; "ignore")]
+    #[test_case(r#"on-broken-pipe = "cancel""#, OnBrokenPipe::Cancel; "cancel")]
+    fn parse_on_broken_pipe(config_line: &str, expected: OnBrokenPipe) {
+        let actual = parse_ci_profile(config_line, |profile| profile.on_broken_pipe())
+            .expect("config file should parse");
+        assert_eq!(actual, expected);
+    }
+}
@@ -4,8 +4,11 @@
 use super::{
     ArchiveConfig, CompiledByProfile, CompiledData, CompiledDefaultFilter, ConfigExperimental,
     CustomTestGroup, DefaultJunitImpl, DeserializedOverride, DeserializedProfileScriptConfig,
-    JunitConfig, JunitImpl, NextestVersionDeserialize, RetryPolicy, ScriptConfig, ScriptId,
-    SettingSource, SetupScripts, SlowTimeout, TestGroup, TestGroupConfig, TestSettings,
+    DeserializedSmokeConfig, DurationHistogramConfig, FailOnStderr, FixtureFailureMarkers,
+    JunitConfig, JunitImpl, LingeringThreads, LogicalSuite, MaxFail, MaxOutputSize, MemoryLimit,
+    NextestVersionDeserialize, OnBrokenPipe, OnSlow, OnTeardownFailure, OrphanProcesses,
+    OutputCompression, RetryPolicy, ScriptConfig, ScriptId, SettingSource, SetupScripts,
+    SlowTimeout, SmokeConfig, TestGroup, TestGroupConfig, TestNameNormalization, TestSettings,
     TestThreads, ThreadsRequired, ToolConfigFile,
 };
 use crate::{
@@ -15,7 +18,8 @@ use crate::{
     },
     list::TestList,
     platform::BuildPlatforms,
-    reporter::{FinalStatusLevel, StatusLevel, TestOutputDisplay},
+    reporter::{AnsiEscapes, FinalStatusLevel, StatusLevel, TestOutputDisplay},
+    signal::SigtstpPolicy,
 };
 use camino::{Utf8Path, Utf8PathBuf};
 use config::{
@@ -25,6 +29,7 @@ use guppy::graph::PackageGraph;
 use indexmap::IndexMap;
 use nextest_filtering::{EvalContext, TestQuery};
 use once_cell::sync::Lazy;
+use regex::Regex;
 use serde::Deserialize;
 use std::{
     collections::{hash_map, BTreeMap, BTreeSet, HashMap},
@@ -462,14 +467,18 @@ impl NextestConfig {
             .default
             .scripts
             .iter()
-            .try_for_each(|scripts| check_script_ids("default", &scripts.setup))?;
+            .try_for_each(|scripts| {
+                check_script_ids("default", &scripts.setup)?;
+                check_script_ids("default", &scripts.teardown)
+            })?;
         this_compiled
             .other
             .iter()
             .try_for_each(|(profile_name, data)| {
-                data.scripts
-                    .iter()
-                    .try_for_each(|scripts| check_script_ids(profile_name, &scripts.setup))
+                data.scripts.iter().try_for_each(|scripts| {
+                    check_script_ids(profile_name, &scripts.setup)?;
+                    check_script_ids(profile_name, &scripts.teardown)
+                })
             })?;
 
         // If there were any unknown scripts, error out.
@@ -600,6 +609,11 @@ pub struct EarlyProfile<'cfg> {
 }
 
 impl<'cfg> EarlyProfile<'cfg> {
+    /// Returns the name of the profile.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     /// Returns the absolute profile-specific store directory.
     pub fn store_dir(&self) -> &Utf8Path {
         &self.store_dir
@@ -745,6 +759,42 @@ impl<'cfg> EvaluatableProfile<'cfg> {
             .unwrap_or(self.default_profile.leak_timeout)
     }
 
+    /// Returns the minimum delay between the starts of consecutive tests.
+    pub fn start_stagger(&self) -> Duration {
+        self.custom_profile
+            .and_then(|profile| profile.start_stagger)
+            .unwrap_or(self.default_profile.start_stagger)
+    }
+
+    /// Returns how long running tests are given to finish after a signal cancels the run, before
+    /// they're terminated.
+    pub fn cancel_grace(&self) -> Duration {
+        self.custom_profile
+            .and_then(|profile| profile.cancel_grace)
+            .unwrap_or(self.default_profile.cancel_grace)
+    }
+
+    /// Returns whether to show a histogram of test durations at the end of a run, and its buckets.
+    pub fn duration_histogram(&self) -> &'cfg DurationHistogramConfig {
+        self.custom_profile
+            .and_then(|profile| profile.duration_histogram.as_ref())
+            .unwrap_or(&self.default_profile.duration_histogram)
+    }
+
+    /// Returns the maximum amount of output captured for each output stream of a test.
+    pub fn max_output_size(&self) -> MaxOutputSize {
+        self.custom_profile
+            .and_then(|profile| profile.max_output_size)
+            .unwrap_or(self.default_profile.max_output_size)
+    }
+
+    /// Returns the memory limit for each process in a test.
+    pub fn memory_limit(&self) -> MemoryLimit {
+        self.custom_profile
+            .and_then(|profile| profile.memory_limit)
+            .unwrap_or(self.default_profile.memory_limit)
+    }
+
     /// Returns the test status level.
     pub fn status_level(&self) -> StatusLevel {
         self.custom_profile
@@ -766,6 +816,14 @@ impl<'cfg> EvaluatableProfile<'cfg> {
             .unwrap_or(self.default_profile.failure_output)
     }
 
+    /// Returns whether to strip or preserve ANSI escapes in captured test output displayed by the
+    /// reporter for this profile.
+    pub fn display_ansi_escapes(&self) -> AnsiEscapes {
+        self.custom_profile
+            .and_then(|profile| profile.display_ansi_escapes)
+            .unwrap_or(self.default_profile.display_ansi_escapes)
+    }
+
     /// Returns the failure output config for this profile.
     pub fn success_output(&self) -> TestOutputDisplay {
         self.custom_profile
@@ -773,13 +831,87 @@ impl<'cfg> EvaluatableProfile<'cfg> {
             .unwrap_or(self.default_profile.success_output)
     }
 
-    /// Returns the fail-fast config for this profile.
-    pub fn fail_fast(&self) -> bool {
+    /// Returns the number of failures after which the run is cancelled for this profile, as
+    /// configured through the `fail-fast` key.
+    pub fn max_fail(&self) -> MaxFail {
         self.custom_profile
             .and_then(|profile| profile.fail_fast)
             .unwrap_or(self.default_profile.fail_fast)
     }
 
+    /// Returns the fail-on-stderr config for this profile.
+    pub fn fail_on_stderr(&self) -> &'cfg FailOnStderr {
+        self.custom_profile
+            .and_then(|profile| profile.fail_on_stderr.as_ref())
+            .unwrap_or(&self.default_profile.fail_on_stderr)
+    }
+
+    /// Returns the fixture-failure-markers config for this profile.
+    pub fn fixture_failure_markers(&self) -> &'cfg FixtureFailureMarkers {
+        self.custom_profile
+            .and_then(|profile| profile.fixture_failure_markers.as_ref())
+            .unwrap_or(&self.default_profile.fixture_failure_markers)
+    }
+
+    /// Returns true if test timings should be recorded to the store directory for this profile.
+    pub fn record_timings(&self) -> bool {
+        self.custom_profile
+            .and_then(|profile| profile.record_timings)
+            .unwrap_or(self.default_profile.record_timings)
+    }
+
+    /// Returns the policy for handling SIGTSTP for this profile.
+    pub fn sigtstp(&self) -> SigtstpPolicy {
+        self.custom_profile
+            .and_then(|profile| profile.sigtstp)
+            .unwrap_or(self.default_profile.sigtstp)
+    }
+
+    /// Returns what to do with processes that tests leave behind, for this profile.
+    pub fn orphan_processes(&self) -> OrphanProcesses {
+        self.custom_profile
+            .and_then(|profile| profile.orphan_processes)
+            .unwrap_or(self.default_profile.orphan_processes)
+    }
+
+    /// Returns whether to look for threads that are still running after a test finishes, for this
+    /// profile.
+    pub fn lingering_threads(&self) -> LingeringThreads {
+        self.custom_profile
+            .and_then(|profile| profile.lingering_threads)
+            .unwrap_or(self.default_profile.lingering_threads)
+    }
+
+    /// Returns what to do if the reader of nextest's output goes away, for this profile.
+    pub fn on_broken_pipe(&self) -> OnBrokenPipe {
+        self.custom_profile
+            .and_then(|profile| profile.on_broken_pipe)
+            .unwrap_or(self.default_profile.on_broken_pipe)
+    }
+
+    /// Returns what to do if a teardown script fails, for this profile.
+    pub fn on_teardown_failure(&self) -> OnTeardownFailure {
+        self.custom_profile
+            .and_then(|profile| profile.on_teardown_failure)
+            .unwrap_or(self.default_profile.on_teardown_failure)
+    }
+
+    /// Returns what to do when a test is marked slow, for this profile.
+    pub fn on_slow(&self) -> &'cfg OnSlow {
+        self.custom_profile
+            .and_then(|profile| profile.on_slow.as_ref())
+            .unwrap_or(&self.default_profile.on_slow)
+    }
+
+    /// Returns the minimum number of tests that must run for this profile.
+    ///
+    /// A value of 0 means that there is no minimum.
+    pub fn min_tests(&self) -> usize {
+        self.custom_profile
+            .and_then(|profile| profile.min_tests)
+            .unwrap_or(self.default_profile.min_tests)
+    }
+
     /// Returns the archive configuration for this profile.
     pub fn archive_config(&self) -> &'cfg ArchiveConfig {
         self.custom_profile
@@ -787,11 +919,33 @@ impl<'cfg> EvaluatableProfile<'cfg> {
             .unwrap_or(&self.default_profile.archive)
     }
 
+    /// Returns the logical suites defined for this profile, sorted by name.
+    ///
+    /// If a suite with the same name is defined in several places, the highest-priority definition
+    /// is used.
+    pub fn logical_suites(&self) -> impl Iterator<Item = &LogicalSuite> + '_ {
+        let mut suites = BTreeMap::new();
+        for suite in &self.compiled_data.logical_suites {
+            suites.entry(suite.name()).or_insert(suite);
+        }
+        suites.into_values()
+    }
+
+    /// Returns the smoke test configuration for this profile, if any.
+    pub fn smoke(&self) -> Option<&SmokeConfig> {
+        self.compiled_data.smoke.as_ref()
+    }
+
     /// Returns the list of setup scripts.
     pub fn setup_scripts(&self, test_list: &TestList<'_>) -> SetupScripts<'_> {
         SetupScripts::new(self, test_list)
     }
 
+    /// Returns the list of teardown scripts, run after all tests have finished.
+    pub fn teardown_scripts(&self, test_list: &TestList<'_>) -> SetupScripts<'_> {
+        SetupScripts::new_teardown(self, test_list)
+    }
+
     /// Returns settings for individual tests.
     pub fn settings_for(&self, query: &TestQuery<'_>) -> TestSettings {
         TestSettings::new(self, query)
@@ -805,6 +959,45 @@ impl<'cfg> EvaluatableProfile<'cfg> {
         TestSettings::new(self, query)
     }
 
+    /// Returns the directory that per-run artifacts are written to, if configured.
+    ///
+    /// Each run's artifacts are written to a subdirectory named after its run ID.
+    pub fn artifacts_dir(&self) -> Option<Utf8PathBuf> {
+        self.custom_profile
+            .and_then(|profile| profile.artifacts_dir.as_deref())
+            .or(self.default_profile.artifacts_dir.as_deref())
+            .map(|dir| self.store_dir.join(dir))
+    }
+
+    /// Returns the patterns for environment variables whose values are hidden in reporter output.
+    pub fn redact_env(&self) -> &'cfg [Regex] {
+        self.custom_profile
+            .and_then(|profile| profile.redact_env.as_deref())
+            .unwrap_or(&self.default_profile.redact_env)
+    }
+
+    /// Returns true if a search index over test output should be written to per-run artifacts.
+    pub fn index_output(&self) -> bool {
+        self.custom_profile
+            .and_then(|profile| profile.index_output)
+            .unwrap_or(self.default_profile.index_output)
+    }
+
+    /// Returns the normalization applied to test names to group them for reporting, if
+    /// configured.
+    pub fn normalize_test_names(&self) -> Option<&'cfg TestNameNormalization> {
+        self.custom_profile
+            .and_then(|profile| profile.normalize_test_names.as_ref())
+            .or(self.default_profile.normalize_test_names.as_ref())
+    }
+
+    /// Returns how JUnit reports and per-run event logs are compressed, for this profile.
+    pub fn output_compression(&self) -> OutputCompression {
+        self.custom_profile
+            .and_then(|profile| profile.output_compression)
+            .unwrap_or(self.default_profile.output_compression)
+    }
+
     /// Returns the JUnit configuration for this profile.
     pub fn junit(&self) -> Option<JunitConfig<'cfg>> {
         JunitConfig::new(
@@ -913,16 +1106,39 @@ pub(super) struct DefaultProfileImpl {
     test_threads: TestThreads,
     threads_required: ThreadsRequired,
     run_extra_args: Vec<String>,
+    redact_env: Vec<Regex>,
     retries: RetryPolicy,
     status_level: StatusLevel,
     final_status_level: FinalStatusLevel,
     failure_output: TestOutputDisplay,
     success_output: TestOutputDisplay,
-    fail_fast: bool,
+    display_ansi_escapes: AnsiEscapes,
+    fail_fast: MaxFail,
     slow_timeout: SlowTimeout,
     leak_timeout: Duration,
+    start_stagger: Duration,
+    cancel_grace: Duration,
+    duration_histogram: DurationHistogramConfig,
+    max_output_size: MaxOutputSize,
+    memory_limit: MemoryLimit,
+    fail_on_stderr: FailOnStderr,
+    fixture_failure_markers: FixtureFailureMarkers,
+    record_timings: bool,
+    sigtstp: SigtstpPolicy,
+    orphan_processes: OrphanProcesses,
+    lingering_threads: LingeringThreads,
+    on_slow: OnSlow,
+    on_broken_pipe: OnBrokenPipe,
+    on_teardown_failure: OnTeardownFailure,
+    min_tests: usize,
+    artifacts_dir: Option<Utf8PathBuf>,
+    index_output: bool,
+    output_compression: OutputCompression,
     overrides: Vec<DeserializedOverride>,
     scripts: Vec<DeserializedProfileScriptConfig>,
+    logical_suites: BTreeMap<String, String>,
+    smoke: Option<DeserializedSmokeConfig>,
+    normalize_test_names: Option<TestNameNormalization>,
     junit: DefaultJunitImpl,
     archive: ArchiveConfig,
 }
@@ -942,6 +1158,7 @@ impl DefaultProfileImpl {
             run_extra_args: p
                 .run_extra_args
                 .expect("run-extra-args present in default profile"),
+            redact_env: p.redact_env.expect("redact-env present in default profile"),
             retries: p.retries.expect("retries present in default profile"),
             status_level: p
                 .status_level
@@ -955,6 +1172,9 @@ impl DefaultProfileImpl {
             success_output: p
                 .success_output
                 .expect("success-output present in default profile"),
+            display_ansi_escapes: p
+                .display_ansi_escapes
+                .expect("display-ansi-escapes present in default profile"),
             fail_fast: p.fail_fast.expect("fail-fast present in default profile"),
             slow_timeout: p
                 .slow_timeout
@@ -962,8 +1182,57 @@ impl DefaultProfileImpl {
             leak_timeout: p
                 .leak_timeout
                 .expect("leak-timeout present in default profile"),
+            start_stagger: p
+                .start_stagger
+                .expect("start-stagger present in default profile"),
+            cancel_grace: p
+                .cancel_grace
+                .expect("cancel-grace present in default profile"),
+            duration_histogram: p
+                .duration_histogram
+                .expect("duration-histogram present in default profile"),
+            max_output_size: p
+                .max_output_size
+                .expect("max-output-size present in default profile"),
+            memory_limit: p
+                .memory_limit
+                .expect("memory-limit present in default profile"),
+            fail_on_stderr: p
+                .fail_on_stderr
+                .expect("fail-on-stderr present in default profile"),
+            fixture_failure_markers: p
+                .fixture_failure_markers
+                .expect("fixture-failure-markers present in default profile"),
+            record_timings: p
+                .record_timings
+                .expect("record-timings present in default profile"),
+            sigtstp: p.sigtstp.expect("sigtstp present in default profile"),
+            orphan_processes: p
+                .orphan_processes
+                .expect("orphan-processes present in default profile"),
+            lingering_threads: p
+                .lingering_threads
+                .expect("lingering-threads present in default profile"),
+            on_slow: p.on_slow.expect("on-slow present in default profile"),
+            on_broken_pipe: p
+                .on_broken_pipe
+                .expect("on-broken-pipe present in default profile"),
+            on_teardown_failure: p
+                .on_teardown_failure
+                .expect("on-teardown-failure present in default profile"),
+            min_tests: p.min_tests.expect("min-tests present in default profile"),
+            artifacts_dir: p.artifacts_dir,
+            index_output: p
+                .index_output
+                .expect("index-output present in default profile"),
+            output_compression: p
+                .output_compression
+                .expect("output-compression present in default profile"),
             overrides: p.overrides,
             scripts: p.scripts,
+            logical_suites: p.logical_suites,
+            smoke: p.smoke,
+            normalize_test_names: p.normalize_test_names,
             junit: DefaultJunitImpl::for_default_profile(p.junit),
             archive: p.archive.expect("archive present in default profile"),
         }
@@ -980,6 +1249,14 @@ impl DefaultProfileImpl {
     pub(super) fn setup_scripts(&self) -> &[DeserializedProfileScriptConfig] {
         &self.scripts
     }
+
+    pub(super) fn logical_suites(&self) -> &BTreeMap<String, String> {
+        &self.logical_suites
+    }
+
+    pub(super) fn smoke(&self) -> Option<&DeserializedSmokeConfig> {
+        self.smoke.as_ref()
+    }
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -996,6 +1273,8 @@ pub(super) struct CustomProfileImpl {
     threads_required: Option<ThreadsRequired>,
     #[serde(default)]
     run_extra_args: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "super::deserialize_opt_redact_env")]
+    redact_env: Option<Vec<Regex>>,
     #[serde(default)]
     status_level: Option<StatusLevel>,
     #[serde(default)]
@@ -1005,16 +1284,60 @@ pub(super) struct CustomProfileImpl {
     #[serde(default)]
     success_output: Option<TestOutputDisplay>,
     #[serde(default)]
-    fail_fast: Option<bool>,
+    display_ansi_escapes: Option<AnsiEscapes>,
+    #[serde(default, deserialize_with = "super::deserialize_fail_fast")]
+    fail_fast: Option<MaxFail>,
     #[serde(default, deserialize_with = "super::deserialize_slow_timeout")]
     slow_timeout: Option<SlowTimeout>,
     #[serde(default, with = "humantime_serde::option")]
     leak_timeout: Option<Duration>,
+    #[serde(default, with = "humantime_serde::option")]
+    start_stagger: Option<Duration>,
+    #[serde(default, with = "humantime_serde::option")]
+    cancel_grace: Option<Duration>,
+    #[serde(default)]
+    duration_histogram: Option<DurationHistogramConfig>,
+    #[serde(default)]
+    max_output_size: Option<MaxOutputSize>,
+    #[serde(default)]
+    memory_limit: Option<MemoryLimit>,
+    #[serde(default, deserialize_with = "super::deserialize_fail_on_stderr")]
+    fail_on_stderr: Option<FailOnStderr>,
+    #[serde(default)]
+    fixture_failure_markers: Option<FixtureFailureMarkers>,
+    #[serde(default)]
+    record_timings: Option<bool>,
+    #[serde(default)]
+    sigtstp: Option<SigtstpPolicy>,
+    #[serde(default)]
+    orphan_processes: Option<OrphanProcesses>,
+    #[serde(default)]
+    lingering_threads: Option<LingeringThreads>,
+    #[serde(default)]
+    on_slow: Option<OnSlow>,
+    #[serde(default)]
+    on_broken_pipe: Option<OnBrokenPipe>,
+    #[serde(default)]
+    on_teardown_failure: Option<OnTeardownFailure>,
+    #[serde(default)]
+    min_tests: Option<usize>,
+    #[serde(default)]
+    artifacts_dir: Option<Utf8PathBuf>,
+    #[serde(default)]
+    index_output: Option<bool>,
+    #[serde(default)]
+    output_compression: Option<OutputCompression>,
     #[serde(default)]
     overrides: Vec<DeserializedOverride>,
     #[serde(default)]
     scripts: Vec<DeserializedProfileScriptConfig>,
     #[serde(default)]
+    logical_suites: BTreeMap<String, String>,
+    #[serde(default)]
+    smoke: Option<DeserializedSmokeConfig>,
+    #[serde(default)]
+    normalize_test_names: Option<TestNameNormalization>,
+    #[serde(default)]
     junit: JunitImpl,
     #[serde(default)]
     archive: Option<ArchiveConfig>,
@@ -1037,6 +1360,14 @@ impl CustomProfileImpl {
     pub(super) fn scripts(&self) -> &[DeserializedProfileScriptConfig] {
         &self.scripts
     }
+
+    pub(super) fn logical_suites(&self) -> &BTreeMap<String, String> {
+        &self.logical_suites
+    }
+
+    pub(super) fn smoke(&self) -> Option<&DeserializedSmokeConfig> {
+        self.smoke.as_ref()
+    }
 }
 
 #[cfg(test)]
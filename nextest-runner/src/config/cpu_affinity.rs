@@ -0,0 +1,276 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+use std::{collections::BTreeSet, fmt, str::FromStr};
+
+/// Type for the cpu-affinity config key: the set of CPUs that a test is pinned to.
+///
+/// CPU affinity is only applied on Linux. On other platforms, it is ignored.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CpuAffinity {
+    // Invariant: this is non-empty.
+    cpus: BTreeSet<usize>,
+}
+
+impl CpuAffinity {
+    /// Whether CPU affinity is supported on this platform.
+    pub const IS_SUPPORTED: bool = cfg!(target_os = "linux");
+
+    /// The number of CPUs that can be specified: CPU indexes must be less than this.
+    ///
+    /// This matches `CPU_SETSIZE` on Linux.
+    pub const MAX_CPUS: usize = 1024;
+
+    /// Creates a new `CpuAffinity` from the given CPUs.
+    ///
+    /// Returns `None` if `cpus` is empty.
+    pub fn new(cpus: impl IntoIterator<Item = usize>) -> Option<Self> {
+        let cpus: BTreeSet<_> = cpus.into_iter().collect();
+        (!cpus.is_empty()).then_some(Self { cpus })
+    }
+
+    /// Returns the CPUs in this set, in ascending order.
+    pub fn cpus(&self) -> impl Iterator<Item = usize> + '_ {
+        self.cpus.iter().copied()
+    }
+
+    /// Returns true if the given CPU is in this set.
+    pub fn contains(&self, cpu: usize) -> bool {
+        self.cpus.contains(&cpu)
+    }
+}
+
+impl FromStr for CpuAffinity {
+    type Err = String;
+
+    /// Parses a CPU list such as `0-3,8`, in the format used by `taskset --cpu-list`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_cpu = |cpu: &str| {
+            let cpu = cpu.trim();
+            match cpu.parse::<usize>() {
+                Ok(index) if index < Self::MAX_CPUS => Ok(index),
+                Ok(_) => Err(format!(
+                    "CPU `{cpu}` in CPU list `{s}` is out of range (must be less than {})",
+                    Self::MAX_CPUS
+                )),
+                Err(_) => Err(format!("invalid CPU `{cpu}` in CPU list `{s}`")),
+            }
+        };
+
+        let mut cpus = BTreeSet::new();
+        for part in s.split(',') {
+            match part.split_once('-') {
+                Some((start, end)) => {
+                    let (start, end) = (parse_cpu(start)?, parse_cpu(end)?);
+                    if start > end {
+                        return Err(format!(
+                            "invalid CPU range `{}` in CPU list `{s}`",
+                            part.trim()
+                        ));
+                    }
+                    cpus.extend(start..=end);
+                }
+                None => {
+                    cpus.insert(parse_cpu(part)?);
+                }
+            }
+        }
+
+        Ok(Self { cpus })
+    }
+}
+
+impl fmt::Display for CpuAffinity {
+    /// Formats this set as a CPU list, collapsing consecutive CPUs into ranges.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut cpus = self.cpus().peekable();
+        let mut first = true;
+        while let Some(start) = cpus.next() {
+            let mut end = start;
+            while cpus.next_if_eq(&(end + 1)).is_some() {
+                end += 1;
+            }
+
+            if !first {
+                write!(f, ",")?;
+            }
+            first = false;
+            if start == end {
+                write!(f, "{start}")?;
+            } else {
+                write!(f, "{start}-{end}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for CpuAffinity {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct V;
+
+        impl<'de2> serde::de::Visitor<'de2> for V {
+            type Value = CpuAffinity;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(
+                    formatter,
+                    "a CPU list (\"0-3,8\") or a non-empty array of CPU indexes ([0, 1])"
+                )
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                v.parse().map_err(E::custom)
+            }
+
+            fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::SeqAccess<'de2>,
+            {
+                let cpus =
+                    Vec::<usize>::deserialize(serde::de::value::SeqAccessDeserializer::new(seq))?;
+                if let Some(&cpu) = cpus.iter().find(|&&cpu| cpu >= CpuAffinity::MAX_CPUS) {
+                    return Err(serde::de::Error::custom(format!(
+                        "CPU {cpu} is out of range (must be less than {})",
+                        CpuAffinity::MAX_CPUS
+                    )));
+                }
+                CpuAffinity::new(cpus).ok_or_else(|| {
+                    serde::de::Error::invalid_length(0, &"a non-empty array of CPU indexes")
+                })
+            }
+        }
+
+        deserializer.deserialize_any(V)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{
+        test_helpers::{binary_query, build_platforms, temp_workspace},
+        NextestConfig,
+    };
+    use camino_tempfile::tempdir;
+    use guppy::graph::cargo::BuildPlatform;
+    use indoc::indoc;
+    use nextest_filtering::TestQuery;
+    use test_case::test_case;
+
+    #[test_case("3", &[3]; "single CPU")]
+    #[test_case("0-3,8", &[0, 1, 2, 3, 8]; "range and single CPU")]
+    #[test_case(" 4 , 1-2 ", &[1, 2, 4]; "whitespace and out of order")]
+    fn parse_cpu_list(input: &str, expected: &[usize]) {
+        let affinity: CpuAffinity = input.parse().expect("CPU list is valid");
+        assert_eq!(affinity.cpus().collect::<Vec<_>>(), expected);
+
+        // Displaying and re-parsing should round-trip.
+        let reparsed: CpuAffinity = affinity.to_string().parse().expect("display is valid");
+        assert_eq!(reparsed, affinity);
+    }
+
+    #[test_case("", "invalid CPU ``"; "empty")]
+    #[test_case("0,x", "invalid CPU `x`"; "not a number")]
+    #[test_case("4-2", "invalid CPU range `4-2`"; "reversed range")]
+    #[test_case("1024", "CPU `1024` in CPU list `1024` is out of range"; "too large")]
+    #[test_case(
+        "0-4294967295",
+        "CPU `4294967295` in CPU list `0-4294967295` is out of range";
+        "range too large"
+    )]
+    fn parse_cpu_list_invalid(input: &str, expected: &str) {
+        let error = input
+            .parse::<CpuAffinity>()
+            .expect_err("CPU list is invalid");
+        assert!(error.contains(expected), "unexpected error: {error}");
+    }
+
+    #[test]
+    fn display_cpu_list() {
+        let affinity = CpuAffinity::new([0, 1, 2, 4, 6, 7]).unwrap();
+        assert_eq!(affinity.to_string(), "0-2,4,6-7");
+    }
+
+    #[test]
+    fn cpu_affinity_overrides() {
+        let config_contents = indoc! {r#"
+            [[profile.default.overrides]]
+            filter = "test(=my_test)"
+            cpu-affinity = "0-1"
+
+            [[profile.default.overrides]]
+            filter = "test(=other_test)"
+            cpu-affinity = [2, 3]
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let package_id = graph.workspace().iter().next().unwrap().id();
+
+        let nextest_config = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            &[][..],
+            &Default::default(),
+        )
+        .expect("config file should parse");
+        let profile = nextest_config
+            .profile(NextestConfig::DEFAULT_PROFILE)
+            .expect("default profile should exist")
+            .apply_build_platforms(&build_platforms());
+
+        let binary_query = binary_query(
+            &graph,
+            package_id,
+            "lib",
+            "my-binary",
+            BuildPlatform::Target,
+        );
+        let settings_for = |test_name| {
+            let query = TestQuery {
+                binary_query: binary_query.to_query(),
+                test_name,
+            };
+            profile.settings_for(&query).cpu_affinity().cloned()
+        };
+
+        assert_eq!(settings_for("my_test"), CpuAffinity::new([0, 1]));
+        assert_eq!(settings_for("other_test"), CpuAffinity::new([2, 3]));
+        assert_eq!(settings_for("unrelated_test"), None);
+    }
+
+    #[test]
+    fn cpu_affinity_empty_array() {
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(
+            workspace_dir.path(),
+            indoc! {r#"
+                [[profile.default.overrides]]
+                filter = "all()"
+                cpu-affinity = []
+            "#},
+        );
+
+        let error = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            &[][..],
+            &Default::default(),
+        )
+        .expect_err("empty CPU list should fail to parse");
+        assert!(
+            format!("{error:?}").contains("non-empty array of CPU indexes"),
+            "unexpected error: {error:?}"
+        );
+    }
+}
@@ -0,0 +1,125 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+use std::{fmt, time::Duration};
+
+/// Type for the duration-histogram config key: whether to show a histogram of test durations at
+/// the end of a run, and the buckets to use for it.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub enum DurationHistogramConfig {
+    /// Don't show a histogram.
+    #[default]
+    Disabled,
+
+    /// Show a histogram with buckets bounded above by these durations, in increasing order.
+    ///
+    /// Tests that take at least as long as the last bound, or that time out, are counted in an
+    /// extra overflow bucket.
+    Buckets(Vec<Duration>),
+}
+
+impl DurationHistogramConfig {
+    /// The buckets used by `duration-histogram = true`.
+    pub const DEFAULT_BUCKETS: [Duration; 4] = [
+        Duration::from_millis(10),
+        Duration::from_millis(100),
+        Duration::from_secs(1),
+        Duration::from_secs(10),
+    ];
+
+    /// Returns the upper bounds of the buckets, or `None` if the histogram is disabled.
+    pub fn buckets(&self) -> Option<&[Duration]> {
+        match self {
+            Self::Disabled => None,
+            Self::Buckets(buckets) => Some(buckets),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for DurationHistogramConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct V;
+
+        impl<'de2> serde::de::Visitor<'de2> for V {
+            type Value = DurationHistogramConfig;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(
+                    formatter,
+                    "a boolean, or a non-empty list of increasing durations such as [\"100ms\", \"1s\"]"
+                )
+            }
+
+            fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                if v {
+                    Ok(DurationHistogramConfig::Buckets(
+                        DurationHistogramConfig::DEFAULT_BUCKETS.to_vec(),
+                    ))
+                } else {
+                    Ok(DurationHistogramConfig::Disabled)
+                }
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::SeqAccess<'de2>,
+            {
+                let mut buckets: Vec<Duration> = Vec::new();
+                while let Some(bucket) = seq.next_element::<humantime_serde::Serde<Duration>>()? {
+                    let bucket = bucket.into_inner();
+                    if let Some(&last) = buckets.last() {
+                        if bucket <= last {
+                            return Err(serde::de::Error::custom(format!(
+                                "duration-histogram buckets must be in increasing order, \
+                                 but {bucket:?} follows {last:?}",
+                            )));
+                        }
+                    }
+                    buckets.push(bucket);
+                }
+
+                if buckets.is_empty() {
+                    return Err(serde::de::Error::invalid_length(0, &self));
+                }
+                Ok(DurationHistogramConfig::Buckets(buckets))
+            }
+        }
+
+        deserializer.deserialize_any(V)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_helpers::parse_ci_profile;
+    use test_case::test_case;
+
+    #[test_case("", Some(DurationHistogramConfig::Disabled); "default")]
+    #[test_case("duration-histogram = false", Some(DurationHistogramConfig::Disabled); "disabled")]
+    #[test_case(
+        "duration-histogram = true",
+        Some(DurationHistogramConfig::Buckets(DurationHistogramConfig::DEFAULT_BUCKETS.to_vec()));
+        "default buckets"
+    )]
+    #[test_case(
+        r#"duration-histogram = ["50ms", "2s"]"#,
+        Some(DurationHistogramConfig::Buckets(vec![Duration::from_millis(50), Duration::from_secs(2)]));
+        "custom buckets"
+    )]
+    #[test_case("duration-histogram = []", None; "empty")]
+    #[test_case(r#"duration-histogram = ["1s", "100ms"]"#, None; "decreasing")]
+    #[test_case(r#"duration-histogram = ["1s", "1s"]"#, None; "repeated")]
+    #[test_case(r#"duration-histogram = ["soon"]"#, None; "invalid duration")]
+    fn parse_duration_histogram(config_line: &str, expected: Option<DurationHistogramConfig>) {
+        let actual = parse_ci_profile(config_line, |profile| profile.duration_histogram().clone());
+        assert_eq!(actual.ok(), expected);
+    }
+}
@@ -0,0 +1,225 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use bstr::ByteSlice;
+use regex::bytes::Regex;
+use serde::Deserialize;
+use std::fmt;
+
+/// Type for the fail-on-stderr config key.
+///
+/// If enabled, tests that pass but write to standard error are marked as failed.
+#[derive(Clone, Debug, Default)]
+pub enum FailOnStderr {
+    /// Standard error output from passing tests is not inspected.
+    #[default]
+    Disabled,
+
+    /// Passing tests fail if they write an unexpected line to standard error.
+    Enabled {
+        /// If specified, only lines matching this pattern are unexpected.
+        pattern: Option<Regex>,
+
+        /// Lines matching this pattern are always allowed.
+        allow: Option<Regex>,
+    },
+}
+
+impl FailOnStderr {
+    /// Returns true if standard error output from passing tests is inspected.
+    pub fn is_enabled(&self) -> bool {
+        matches!(self, Self::Enabled { .. })
+    }
+
+    /// Returns the first unexpected line in `stderr`, if any.
+    ///
+    /// Lines that consist entirely of ASCII whitespace are ignored.
+    pub fn find_unexpected_line<'a>(&self, stderr: &'a [u8]) -> Option<&'a [u8]> {
+        let Self::Enabled { pattern, allow } = self else {
+            return None;
+        };
+
+        stderr.lines().find(|line| {
+            if line.iter().all(u8::is_ascii_whitespace) {
+                return false;
+            }
+            if let Some(allow) = allow {
+                if allow.is_match(line) {
+                    return false;
+                }
+            }
+            match pattern {
+                Some(pattern) => pattern.is_match(line),
+                None => true,
+            }
+        })
+    }
+}
+
+pub(super) fn deserialize_fail_on_stderr<'de, D>(
+    deserializer: D,
+) -> Result<Option<FailOnStderr>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    struct V;
+
+    impl<'de2> serde::de::Visitor<'de2> for V {
+        type Value = Option<FailOnStderr>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            write!(
+                formatter,
+                "a table ({{ pattern = \"warning\", allow = \"^note:\" }}) or a boolean"
+            )
+        }
+
+        fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+        {
+            if v {
+                Ok(Some(FailOnStderr::Enabled {
+                    pattern: None,
+                    allow: None,
+                }))
+            } else {
+                Ok(Some(FailOnStderr::Disabled))
+            }
+        }
+
+        fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+        where
+            A: serde::de::MapAccess<'de2>,
+        {
+            let table =
+                FailOnStderrTable::deserialize(serde::de::value::MapAccessDeserializer::new(map))?;
+            let pattern = table.pattern.as_deref().map(compile_regex).transpose()?;
+            let allow = table.allow.as_deref().map(compile_regex).transpose()?;
+            Ok(Some(FailOnStderr::Enabled { pattern, allow }))
+        }
+    }
+
+    deserializer.deserialize_any(V)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct FailOnStderrTable {
+    #[serde(default)]
+    pattern: Option<String>,
+    #[serde(default)]
+    allow: Option<String>,
+}
+
+fn compile_regex<E: serde::de::Error>(s: &str) -> Result<Regex, E> {
+    Regex::new(s).map_err(E::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{
+        test_helpers::{build_platforms, temp_workspace},
+        NextestConfig,
+    };
+    use camino_tempfile::tempdir;
+    use indoc::indoc;
+    use test_case::test_case;
+
+    #[test_case(
+        "",
+        b"warning: foo\n",
+        None
+
+        ; "disabled by default"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            fail-on-stderr = true
+        "#},
+        b"\n  \nwarning: foo\n",
+        Some(&b"warning: foo"[..])
+
+        ; "enabled, whitespace-only lines ignored"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            fail-on-stderr = { allow = "^note:" }
+        "#},
+        b"note: expected\nwarning: foo\n",
+        Some(&b"warning: foo"[..])
+
+        ; "allowlist"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            fail-on-stderr = { allow = "^note:" }
+        "#},
+        b"note: expected\n",
+        None
+
+        ; "only allowed lines"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            fail-on-stderr = { pattern = "(?i)warning", allow = "deprecated" }
+        "#},
+        b"some debug output\nWARNING: deprecated API\nwarning: foo\n",
+        Some(&b"warning: foo"[..])
+
+        ; "pattern and allowlist"
+    )]
+    fn fail_on_stderr_config(config_contents: &str, stderr: &[u8], expected: Option<&[u8]>) {
+        let workspace_dir = tempdir().unwrap();
+
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+
+        let nextest_config = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            &[][..],
+            &Default::default(),
+        )
+        .expect("config file should parse");
+        let profile = nextest_config
+            .profile(NextestConfig::DEFAULT_PROFILE)
+            .expect("default profile should exist")
+            .apply_build_platforms(&build_platforms());
+
+        assert_eq!(
+            profile.fail_on_stderr().find_unexpected_line(stderr),
+            expected
+        );
+    }
+
+    #[test]
+    fn fail_on_stderr_invalid_regex() {
+        let workspace_dir = tempdir().unwrap();
+
+        let graph = temp_workspace(
+            workspace_dir.path(),
+            indoc! {r#"
+                [profile.default]
+                fail-on-stderr = { allow = "(" }
+            "#},
+        );
+
+        let error = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            &[][..],
+            &Default::default(),
+        )
+        .expect_err("invalid regex should fail to parse");
+        assert!(
+            format!("{error:?}").contains("regex parse error"),
+            "unexpected error: {error:?}"
+        );
+    }
+}
@@ -0,0 +1,145 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::{
+    reporter::events::FailurePhase,
+    test_output::{ChildOutput, ChildSplitOutput},
+};
+use bstr::ByteSlice;
+use regex::bytes::Regex;
+use serde::{Deserialize, Deserializer};
+
+/// Type for the fixture-failure-markers config key.
+///
+/// Test frameworks with fixtures often print a recognizable line when a fixture fails to set up or
+/// tear down. If markers are configured, the output of failing tests is searched for these lines
+/// and the failure is classified accordingly.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct FixtureFailureMarkers {
+    /// Lines matching this pattern indicate that a fixture failed to set up.
+    #[serde(default, deserialize_with = "deserialize_regex")]
+    pub(crate) setup: Option<Regex>,
+
+    /// Lines matching this pattern indicate that a fixture failed to tear down.
+    #[serde(default, deserialize_with = "deserialize_regex")]
+    pub(crate) teardown: Option<Regex>,
+}
+
+impl FixtureFailureMarkers {
+    /// Returns true if at least one marker is configured.
+    pub fn is_enabled(&self) -> bool {
+        self.setup.is_some() || self.teardown.is_some()
+    }
+
+    /// Classifies the phase in which a failing test failed, based on its output.
+    ///
+    /// Returns `None` if no markers are configured. Otherwise, returns
+    /// [`FailurePhase::Setup`] or [`FailurePhase::Teardown`] if a line in the output matches the
+    /// corresponding marker (checking setup first), and [`FailurePhase::Test`] if neither does.
+    pub fn classify(&self, output: &ChildOutput) -> Option<FailurePhase> {
+        if !self.is_enabled() {
+            return None;
+        }
+
+        let bufs: Vec<&[u8]> = match output {
+            ChildOutput::Split(ChildSplitOutput { stdout, stderr }) => [stdout, stderr]
+                .into_iter()
+                .flatten()
+                .map(|output| &output.buf[..])
+                .collect(),
+            ChildOutput::Combined { output } => vec![&output.buf[..]],
+        };
+        let matches = |marker: &Option<Regex>| {
+            marker.as_ref().is_some_and(|marker| {
+                bufs.iter()
+                    .any(|buf| buf.lines().any(|line| marker.is_match(line)))
+            })
+        };
+
+        if matches(&self.setup) {
+            Some(FailurePhase::Setup)
+        } else if matches(&self.teardown) {
+            Some(FailurePhase::Teardown)
+        } else {
+            Some(FailurePhase::Test)
+        }
+    }
+}
+
+fn deserialize_regex<'de, D>(deserializer: D) -> Result<Option<Regex>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    Regex::new(&s).map(Some).map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_helpers::parse_ci_profile;
+    use bytes::Bytes;
+    use test_case::test_case;
+
+    fn split_output(stdout: &'static str, stderr: &'static str) -> ChildOutput {
+        ChildOutput::Split(ChildSplitOutput {
+            stdout: Some(Bytes::from_static(stdout.as_bytes()).into()),
+            stderr: Some(Bytes::from_static(stderr.as_bytes()).into()),
+        })
+    }
+
+    #[test_case(
+        "",
+        "", "FIXTURE SETUP FAILED\n",
+        None
+
+        ; "disabled by default"
+    )]
+    #[test_case(
+        r#"fixture-failure-markers = { setup = "^FIXTURE SETUP FAILED", teardown = "^FIXTURE TEARDOWN FAILED" }"#,
+        "running test\n", "FIXTURE SETUP FAILED: db\n",
+        Some(FailurePhase::Setup)
+
+        ; "setup marker in stderr"
+    )]
+    #[test_case(
+        r#"fixture-failure-markers = { setup = "^FIXTURE SETUP FAILED", teardown = "^FIXTURE TEARDOWN FAILED" }"#,
+        "FIXTURE TEARDOWN FAILED: db\n", "",
+        Some(FailurePhase::Teardown)
+
+        ; "teardown marker in stdout"
+    )]
+    #[test_case(
+        r#"fixture-failure-markers = { teardown = "^FIXTURE TEARDOWN FAILED" }"#,
+        "", "thread 'foo' panicked at src/lib.rs:1:1:\n  FIXTURE TEARDOWN FAILED\n",
+        Some(FailurePhase::Test)
+
+        ; "marker must match a line"
+    )]
+    fn parse_fixture_failure_markers(
+        config_line: &str,
+        stdout: &'static str,
+        stderr: &'static str,
+        expected: Option<FailurePhase>,
+    ) {
+        let phase = parse_ci_profile(config_line, |profile| {
+            profile
+                .fixture_failure_markers()
+                .classify(&split_output(stdout, stderr))
+        })
+        .expect("config file should parse");
+        assert_eq!(phase, expected);
+    }
+
+    #[test]
+    fn parse_invalid_regex() {
+        let error = parse_ci_profile(r#"fixture-failure-markers = { setup = "(" }"#, |_| ())
+            .expect_err("invalid regex should fail to parse");
+        let error_str = format!("{error:?}");
+        assert!(
+            error_str.contains("regex parse error"),
+            "error `{error_str}` contains `regex parse error`"
+        );
+    }
+}
@@ -0,0 +1,47 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+
+/// Type for the lingering-threads config key: whether to look for threads that are still running
+/// after a test has finished.
+///
+/// Lingering threads are detected by periodically listing the threads of the test process while
+/// it runs. This is currently only supported on Linux: on other platforms, this setting has no
+/// effect.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LingeringThreads {
+    /// Don't look for lingering threads.
+    #[default]
+    Ignore,
+
+    /// Mark tests that leave threads running as leaky, and report the threads that were found.
+    Report,
+}
+
+impl LingeringThreads {
+    /// Whether lingering thread detection is supported on this platform.
+    pub const IS_SUPPORTED: bool = cfg!(target_os = "linux");
+
+    /// Returns true if nextest should look for lingering threads.
+    pub fn is_enabled(self) -> bool {
+        Self::IS_SUPPORTED && self != Self::Ignore
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_helpers::parse_ci_profile;
+    use test_case::test_case;
+
+    #[test_case("", LingeringThreads::Ignore; "default")]
+    #[test_case(r#"lingering-threads = "ignore""#, LingeringThreads::Ignore; "ignore")]
+    #[test_case(r#"lingering-threads = "report""#, LingeringThreads::Report; "report")]
+    fn parse_lingering_threads(config_line: &str, expected: LingeringThreads) {
+        let actual = parse_ci_profile(config_line, |profile| profile.lingering_threads())
+            .expect("config file should parse");
+        assert_eq!(actual, expected);
+    }
+}
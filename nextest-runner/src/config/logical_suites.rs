@@ -0,0 +1,184 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::errors::{ConfigCompileError, ConfigCompileErrorKind, ConfigCompileSection};
+use guppy::graph::PackageGraph;
+use nextest_filtering::{CompiledExpr, Filterset, FiltersetKind, ParseContext};
+use std::collections::BTreeMap;
+
+/// A named, logical suite of tests, defined in the `logical-suites` config table.
+///
+/// Logical suites group tests for reporting, independent of the binaries they're in. A test can
+/// belong to any number of logical suites.
+///
+/// Returned by [`EvaluatableProfile::logical_suites`](super::EvaluatableProfile::logical_suites).
+#[derive(Clone, Debug)]
+pub struct LogicalSuite {
+    name: String,
+    filter: String,
+    expr: CompiledExpr,
+}
+
+impl LogicalSuite {
+    /// Returns the name of the logical suite.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the filterset that selects tests in this suite, as written in the config.
+    pub fn filter(&self) -> &str {
+        &self.filter
+    }
+
+    /// Returns the compiled filterset that selects tests in this suite.
+    pub fn expr(&self) -> &CompiledExpr {
+        &self.expr
+    }
+
+    pub(super) fn compile_all(
+        graph: &PackageGraph,
+        profile_name: &str,
+        suites: &BTreeMap<String, String>,
+        errors: &mut Vec<ConfigCompileError>,
+    ) -> Vec<Self> {
+        let cx = ParseContext {
+            graph,
+            kind: FiltersetKind::Test,
+        };
+        suites
+            .iter()
+            .filter_map(
+                |(name, filter)| match Filterset::parse(filter.clone(), &cx) {
+                    Ok(expr) => Some(Self {
+                        name: name.clone(),
+                        filter: expr.input,
+                        expr: expr.compiled,
+                    }),
+                    Err(err) => {
+                        errors.push(ConfigCompileError {
+                            profile_name: profile_name.to_owned(),
+                            section: ConfigCompileSection::LogicalSuite(name.clone()),
+                            kind: ConfigCompileErrorKind::Parse {
+                                host_parse_error: None,
+                                target_parse_error: None,
+                                filter_parse_errors: vec![err],
+                            },
+                        });
+                        None
+                    }
+                },
+            )
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{
+            test_helpers::{binary_query, build_platforms, temp_workspace},
+            NextestConfig,
+        },
+        errors::ConfigParseErrorKind,
+    };
+    use camino_tempfile::tempdir;
+    use guppy::graph::cargo::BuildPlatform;
+    use indoc::indoc;
+    use nextest_filtering::TestQuery;
+
+    #[test]
+    fn parse_logical_suites() {
+        let config_contents = indoc! {r#"
+            [profile.default.logical-suites]
+            payments = "test(/^payments::/)"
+            checkout = "test(/^checkout::/) | test(=payments::refund)"
+
+            [profile.ci.logical-suites]
+            # Overrides the definition in the default profile.
+            checkout = "test(/^checkout::/)"
+            slow = "test(/slow/)"
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let nextest_config = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            &[][..],
+            &Default::default(),
+        )
+        .expect("config file should parse");
+
+        let suites_for = |profile_name: &str, test_name: &str| {
+            let profile = nextest_config
+                .profile(profile_name)
+                .expect("profile should exist")
+                .apply_build_platforms(&build_platforms());
+            let package_id = graph.workspace().iter().next().unwrap().id();
+            let binary_query = binary_query(
+                &graph,
+                package_id,
+                "lib",
+                "my-binary",
+                BuildPlatform::Target,
+            );
+            let query = TestQuery {
+                binary_query: binary_query.to_query(),
+                test_name,
+            };
+            let ecx = profile.filterset_ecx();
+            profile
+                .logical_suites()
+                .filter(|suite| suite.expr().matches_test(&query, &ecx))
+                .map(|suite| suite.name().to_owned())
+                .collect::<Vec<_>>()
+        };
+
+        // A test can be in multiple logical suites.
+        assert_eq!(
+            suites_for("default", "payments::refund"),
+            ["checkout", "payments"]
+        );
+        assert_eq!(suites_for("default", "checkout::cart"), ["checkout"]);
+        assert_eq!(suites_for("default", "other"), Vec::<String>::new());
+
+        // Suites in custom profiles take priority, and are combined with the default profile's.
+        assert_eq!(suites_for("ci", "payments::refund"), ["payments"]);
+        assert_eq!(
+            suites_for("ci", "payments::slow_refund"),
+            ["payments", "slow"]
+        );
+    }
+
+    #[test]
+    fn parse_logical_suites_invalid_filter() {
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(
+            workspace_dir.path(),
+            indoc! {r#"
+                [profile.default.logical-suites]
+                payments = "test(/^payments::/"
+            "#},
+        );
+
+        let error = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            &[][..],
+            &Default::default(),
+        )
+        .expect_err("invalid filterset should fail to parse");
+        let ConfigParseErrorKind::CompileErrors(errors) = error.kind() else {
+            panic!("expected compile errors, found {error:?}");
+        };
+        assert_eq!(errors.len(), 1, "one compile error");
+        assert!(
+            matches!(&errors[0].section, ConfigCompileSection::LogicalSuite(name) if name == "payments"),
+            "error is for the payments suite: {:?}",
+            errors[0].section,
+        );
+    }
+}
@@ -1,7 +1,8 @@
 use crate::errors::MaxFailParseError;
+use serde::Deserialize;
 use std::{fmt, str::FromStr};
 
-/// Type for the max-fail flag
+/// Type for the max-fail flag, and for the fail-fast config key.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum MaxFail {
     /// Allow a specific number of tests to fail before exiting.
@@ -55,9 +56,114 @@ impl fmt::Display for MaxFail {
     }
 }
 
+impl<'de> Deserialize<'de> for MaxFail {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct V;
+
+        impl serde::de::Visitor<'_> for V {
+            type Value = MaxFail;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(formatter, "a positive integer or the string \"all\"")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                if v == "all" {
+                    Ok(MaxFail::All)
+                } else {
+                    Err(serde::de::Error::invalid_value(
+                        serde::de::Unexpected::Str(v),
+                        &self,
+                    ))
+                }
+            }
+
+            // Note that TOML uses i64, not u64.
+            fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                if v > 0 {
+                    Ok(MaxFail::Count(v as usize))
+                } else {
+                    Err(serde::de::Error::invalid_value(
+                        serde::de::Unexpected::Signed(v),
+                        &self,
+                    ))
+                }
+            }
+        }
+
+        deserializer.deserialize_any(V)
+    }
+}
+
+/// Deserializes the fail-fast config key, which is either a boolean or a table with a `max-fail`
+/// key.
+pub(super) fn deserialize_fail_fast<'de, D>(deserializer: D) -> Result<Option<MaxFail>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    struct V;
+
+    impl<'de2> serde::de::Visitor<'de2> for V {
+        type Value = Option<MaxFail>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            write!(formatter, "a table ({{ max-fail = 10 }}) or a boolean")
+        }
+
+        fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
+        where
+            E: serde::de::Error,
+        {
+            Ok(Some(MaxFail::from_fail_fast(v)))
+        }
+
+        fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+        where
+            A: serde::de::MapAccess<'de2>,
+        {
+            let table =
+                FailFastTable::deserialize(serde::de::value::MapAccessDeserializer::new(map))?;
+            Ok(Some(table.max_fail))
+        }
+    }
+
+    deserializer.deserialize_any(V)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct FailFastTable {
+    max_fail: MaxFail,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::test_helpers::parse_ci_profile;
+    use test_case::test_case;
+
+    #[test_case("", Some(MaxFail::Count(1)); "default")]
+    #[test_case("fail-fast = true", Some(MaxFail::Count(1)); "enabled")]
+    #[test_case("fail-fast = false", Some(MaxFail::All); "disabled")]
+    #[test_case("fail-fast = { max-fail = 10 }", Some(MaxFail::Count(10)); "max-fail count")]
+    #[test_case(r#"fail-fast = { max-fail = "all" }"#, Some(MaxFail::All); "max-fail all")]
+    #[test_case("fail-fast = { max-fail = 0 }", None; "max-fail zero")]
+    #[test_case(r#"fail-fast = { max-fail = "some" }"#, None; "max-fail invalid string")]
+    #[test_case("fail-fast = {}", None; "missing max-fail")]
+    #[test_case("fail-fast = { max-fail = 3, other = 1 }", None; "unknown key")]
+    fn parse_fail_fast(config_line: &str, expected: Option<MaxFail>) {
+        let actual = parse_ci_profile(config_line, |profile| profile.max_fail());
+        assert_eq!(actual.ok(), expected);
+    }
 
     #[test]
     fn maxfail_builder_from_str() {
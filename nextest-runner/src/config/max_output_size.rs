@@ -0,0 +1,126 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+use std::fmt;
+
+/// Type for the max-output-size config key: the maximum number of bytes of output captured for
+/// each output stream of a test.
+///
+/// If a test produces more output than this, the start and end of the output are kept, and the
+/// middle is replaced with a marker recording how many bytes were dropped.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum MaxOutputSize {
+    /// Capture all output.
+    #[default]
+    Unlimited,
+
+    /// Capture at most this many bytes.
+    Bytes(u64),
+}
+
+impl MaxOutputSize {
+    /// Returns the limit in bytes, or `None` if output is unlimited.
+    pub fn limit(self) -> Option<u64> {
+        match self {
+            Self::Unlimited => None,
+            Self::Bytes(bytes) => Some(bytes),
+        }
+    }
+}
+
+/// Parses a size such as `1024`, `512KB` or `4 MiB`.
+pub(super) fn parse_size(s: &str) -> Option<u64> {
+    let s = s.trim();
+    let unit_start = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(unit_start);
+    let number: u64 = number.parse().ok()?;
+    let multiplier: u64 = match unit.trim_start() {
+        "" | "B" => 1,
+        "KB" => 1000,
+        "MB" => 1000 * 1000,
+        "GB" => 1000 * 1000 * 1000,
+        "KiB" => 1 << 10,
+        "MiB" => 1 << 20,
+        "GiB" => 1 << 30,
+        _ => return None,
+    };
+    number.checked_mul(multiplier)
+}
+
+impl<'de> Deserialize<'de> for MaxOutputSize {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct V;
+
+        impl serde::de::Visitor<'_> for V {
+            type Value = MaxOutputSize;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(
+                    formatter,
+                    "a positive integer, a size such as \"4MiB\", or the string \"unlimited\""
+                )
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                if v == "unlimited" {
+                    return Ok(MaxOutputSize::Unlimited);
+                }
+                match parse_size(v) {
+                    Some(bytes) if bytes > 0 => Ok(MaxOutputSize::Bytes(bytes)),
+                    _ => Err(serde::de::Error::invalid_value(
+                        serde::de::Unexpected::Str(v),
+                        &self,
+                    )),
+                }
+            }
+
+            // Note that TOML uses i64, not u64.
+            fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                if v > 0 {
+                    Ok(MaxOutputSize::Bytes(v as u64))
+                } else {
+                    Err(serde::de::Error::invalid_value(
+                        serde::de::Unexpected::Signed(v),
+                        &self,
+                    ))
+                }
+            }
+        }
+
+        deserializer.deserialize_any(V)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_helpers::parse_ci_profile;
+    use test_case::test_case;
+
+    #[test_case("", Some(MaxOutputSize::Unlimited); "default")]
+    #[test_case(r#"max-output-size = "unlimited""#, Some(MaxOutputSize::Unlimited); "unlimited")]
+    #[test_case("max-output-size = 4096", Some(MaxOutputSize::Bytes(4096)); "integer")]
+    #[test_case(r#"max-output-size = "100B""#, Some(MaxOutputSize::Bytes(100)); "bytes")]
+    #[test_case(r#"max-output-size = "512KB""#, Some(MaxOutputSize::Bytes(512_000)); "kilobytes")]
+    #[test_case(r#"max-output-size = "4 MiB""#, Some(MaxOutputSize::Bytes(4 << 20)); "mebibytes")]
+    #[test_case(r#"max-output-size = "1GB""#, Some(MaxOutputSize::Bytes(1_000_000_000)); "gigabytes")]
+    #[test_case("max-output-size = 0", None; "zero")]
+    #[test_case("max-output-size = -1", None; "negative")]
+    #[test_case(r#"max-output-size = "0MiB""#, None; "zero with unit")]
+    #[test_case(r#"max-output-size = "4 furlongs""#, None; "unknown unit")]
+    #[test_case(r#"max-output-size = "MiB""#, None; "missing number")]
+    fn parse_max_output_size(config_line: &str, expected: Option<MaxOutputSize>) {
+        let actual = parse_ci_profile(config_line, |profile| profile.max_output_size());
+        assert_eq!(actual.ok(), expected);
+    }
+}
@@ -0,0 +1,104 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use super::max_output_size::parse_size;
+use serde::Deserialize;
+use std::fmt;
+
+/// Type for the memory-limit config key: the maximum amount of memory each process in a test may
+/// commit.
+///
+/// This is currently only enforced on Windows, through job objects. Allocations that would go over
+/// the limit fail, which typically causes the test to abort; the test is then reported as having
+/// failed because it exceeded the memory limit.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum MemoryLimit {
+    /// Don't limit memory usage.
+    #[default]
+    Unlimited,
+
+    /// Limit each process to this many bytes.
+    Bytes(u64),
+}
+
+impl MemoryLimit {
+    /// Returns the limit in bytes, or `None` if memory usage is unlimited.
+    pub fn limit(self) -> Option<u64> {
+        match self {
+            Self::Unlimited => None,
+            Self::Bytes(bytes) => Some(bytes),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for MemoryLimit {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct V;
+
+        impl serde::de::Visitor<'_> for V {
+            type Value = MemoryLimit;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(
+                    formatter,
+                    "a positive integer, a size such as \"2GiB\", or the string \"unlimited\""
+                )
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                if v == "unlimited" {
+                    return Ok(MemoryLimit::Unlimited);
+                }
+                match parse_size(v) {
+                    Some(bytes) if bytes > 0 => Ok(MemoryLimit::Bytes(bytes)),
+                    _ => Err(serde::de::Error::invalid_value(
+                        serde::de::Unexpected::Str(v),
+                        &self,
+                    )),
+                }
+            }
+
+            // Note that TOML uses i64, not u64.
+            fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                if v > 0 {
+                    Ok(MemoryLimit::Bytes(v as u64))
+                } else {
+                    Err(serde::de::Error::invalid_value(
+                        serde::de::Unexpected::Signed(v),
+                        &self,
+                    ))
+                }
+            }
+        }
+
+        deserializer.deserialize_any(V)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_helpers::parse_ci_profile;
+    use test_case::test_case;
+
+    #[test_case("", Some(MemoryLimit::Unlimited); "default")]
+    #[test_case(r#"memory-limit = "unlimited""#, Some(MemoryLimit::Unlimited); "unlimited")]
+    #[test_case("memory-limit = 1048576", Some(MemoryLimit::Bytes(1 << 20)); "integer")]
+    #[test_case(r#"memory-limit = "2GiB""#, Some(MemoryLimit::Bytes(2 << 30)); "gibibytes")]
+    #[test_case(r#"memory-limit = "512 MB""#, Some(MemoryLimit::Bytes(512_000_000)); "megabytes")]
+    #[test_case("memory-limit = 0", None; "zero")]
+    #[test_case(r#"memory-limit = "lots""#, None; "invalid")]
+    fn parse_memory_limit(config_line: &str, expected: Option<MemoryLimit>) {
+        let actual = parse_ci_profile(config_line, |profile| profile.memory_limit());
+        assert_eq!(actual.ok(), expected);
+    }
+}
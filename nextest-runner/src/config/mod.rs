@@ -20,33 +20,63 @@
 //! errors early leads to a better user experience.
 
 mod archive;
+mod broken_pipe;
 mod config_impl;
+mod cpu_affinity;
+mod duration_histogram;
+mod fail_on_stderr;
+mod fixture_markers;
 mod helpers;
 mod identifier;
 mod junit;
+mod lingering_threads;
+mod logical_suites;
 mod max_fail;
+mod max_output_size;
+mod memory_limit;
 mod nextest_version;
+mod on_slow;
+mod orphan_processes;
+mod output_compression;
 mod overrides;
 mod retry_policy;
 mod scripts;
 mod slow_timeout;
+mod smoke;
+mod teardown_failure;
 mod test_group;
+mod test_name_normalization;
 mod test_threads;
 mod threads_required;
 mod tool_config;
 mod track_default;
 
 pub use archive::*;
+pub use broken_pipe::*;
 pub use config_impl::*;
+pub use cpu_affinity::*;
+pub use duration_histogram::*;
+pub use fail_on_stderr::*;
+pub use fixture_markers::*;
 pub use identifier::*;
 pub use junit::*;
+pub use lingering_threads::*;
+pub use logical_suites::*;
 pub use max_fail::*;
+pub use max_output_size::*;
+pub use memory_limit::*;
 pub use nextest_version::*;
+pub use on_slow::*;
+pub use orphan_processes::*;
+pub use output_compression::*;
 pub use overrides::*;
 pub use retry_policy::*;
 pub(super) use scripts::*;
 pub use slow_timeout::*;
+pub use smoke::*;
+pub use teardown_failure::*;
 pub use test_group::*;
+pub use test_name_normalization::*;
 pub use test_threads::*;
 pub use threads_required::*;
 pub use tool_config::*;
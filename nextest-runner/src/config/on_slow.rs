@@ -0,0 +1,127 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Type for the on-slow config key: what to do, in addition to reporting it, when a test is marked
+/// slow.
+///
+/// This takes effect each time a test crosses a multiple of its slow timeout period, including
+/// the final time before it is terminated. It can't affect whether the test is terminated.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct OnSlow {
+    /// A message to display after the slow test's status line, e.g. a link to a runbook.
+    #[serde(default)]
+    pub(crate) message: Option<String>,
+
+    /// A command to run for each slow test. The first element is the program and the second
+    /// element is a list of arguments.
+    #[serde(default, deserialize_with = "deserialize_hook_command")]
+    pub(crate) command: Option<(String, Vec<String>)>,
+
+    /// How long the command is allowed to run for before it is killed.
+    #[serde(default = "default_hook_timeout", with = "humantime_serde")]
+    pub(crate) timeout: Duration,
+}
+
+impl OnSlow {
+    /// Returns the message to display after the slow test's status line, if any.
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    /// Returns the program and arguments of the command to run for each slow test, if any.
+    pub fn command(&self) -> Option<(&str, &[String])> {
+        self.command
+            .as_ref()
+            .map(|(program, args)| (program.as_str(), args.as_slice()))
+    }
+
+    /// Returns how long the command is allowed to run for before it is killed.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+}
+
+fn default_hook_timeout() -> Duration {
+    Duration::from_secs(5)
+}
+
+fn deserialize_hook_command<'de, D>(
+    deserializer: D,
+) -> Result<Option<(String, Vec<String>)>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    super::deserialize_command(deserializer).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_helpers::parse_ci_profile;
+    use test_case::test_case;
+
+    #[test_case(
+        "",
+        Ok(OnSlow { message: None, command: None, timeout: Duration::from_secs(5) })
+
+        ; "default"
+    )]
+    #[test_case(
+        r#"on-slow = { message = "see https://example.com/runbook" }"#,
+        Ok(OnSlow {
+            message: Some("see https://example.com/runbook".to_owned()),
+            command: None,
+            timeout: Duration::from_secs(5),
+        })
+
+        ; "message only"
+    )]
+    #[test_case(
+        r#"on-slow = { command = "scripts/slow.sh --verbose", timeout = "1s" }"#,
+        Ok(OnSlow {
+            message: None,
+            command: Some(("scripts/slow.sh".to_owned(), vec!["--verbose".to_owned()])),
+            timeout: Duration::from_secs(1),
+        })
+
+        ; "command as string"
+    )]
+    #[test_case(
+        r#"on-slow = { command = ["scripts/slow.sh", "a b"] }"#,
+        Ok(OnSlow {
+            message: None,
+            command: Some(("scripts/slow.sh".to_owned(), vec!["a b".to_owned()])),
+            timeout: Duration::from_secs(5),
+        })
+
+        ; "command as list"
+    )]
+    #[test_case(
+        r#"on-slow = { command = [] }"#,
+        Err("invalid length 0")
+
+        ; "empty command"
+    )]
+    fn parse_on_slow(config_line: &str, expected: Result<OnSlow, &str>) {
+        let result = parse_ci_profile(config_line, |profile| profile.on_slow().clone());
+
+        match expected {
+            Ok(expected) => {
+                let on_slow = result.expect("config file should parse");
+                assert_eq!(on_slow, expected);
+            }
+            Err(expected_err) => {
+                let error = result.expect_err("config file should fail to parse");
+                let error_str = format!("{error:?}");
+                assert!(
+                    error_str.contains(expected_err),
+                    "error `{error_str}` contains `{expected_err}`"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,49 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+
+/// Type for the orphan-processes config key: what to do with processes a test leaves behind.
+///
+/// Orphan processes are detected by looking for processes in the test's process group after the
+/// test has exited. This is currently only supported on Linux: on other platforms, this setting
+/// has no effect.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OrphanProcesses {
+    /// Don't look for orphan processes.
+    #[default]
+    Ignore,
+
+    /// Mark tests that leave processes behind as leaky, and report the processes that were found.
+    Report,
+
+    /// Like [`Report`](Self::Report), but also kill the processes that were found.
+    Kill,
+}
+
+impl OrphanProcesses {
+    /// Whether orphan process detection is supported on this platform.
+    pub const IS_SUPPORTED: bool = cfg!(target_os = "linux");
+
+    /// Returns true if nextest should look for orphan processes.
+    pub fn is_enabled(self) -> bool {
+        Self::IS_SUPPORTED && self != Self::Ignore
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_helpers::parse_ci_profile;
+    use test_case::test_case;
+
+    #[test_case("", OrphanProcesses::Ignore; "default")]
+    #[test_case(r#"orphan-processes = "report""#, OrphanProcesses::Report; "report")]
+    #[test_case(r#"orphan-processes = "kill""#, OrphanProcesses::Kill; "kill")]
+    fn parse_orphan_processes(config_line: &str, expected: OrphanProcesses) {
+        let actual = parse_ci_profile(config_line, |profile| profile.orphan_processes())
+            .expect("config file should parse");
+        assert_eq!(actual, expected);
+    }
+}
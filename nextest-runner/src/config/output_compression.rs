@@ -0,0 +1,74 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::Deserialize;
+
+/// Type for the output-compression config key: how JUnit reports and event logs written to disk
+/// are compressed.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputCompression {
+    /// Write outputs uncompressed.
+    #[default]
+    None,
+
+    /// Compress outputs with gzip, adding a `.gz` extension.
+    Gzip,
+
+    /// Compress outputs with zstd, adding a `.zst` extension.
+    Zstd,
+}
+
+impl OutputCompression {
+    /// Returns the file extension added to compressed outputs, or `None` if outputs aren't
+    /// compressed.
+    pub fn extension(self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Gzip => Some("gz"),
+            Self::Zstd => Some("zst"),
+        }
+    }
+
+    /// Returns the path that an output meant to be written to `path` is written to.
+    ///
+    /// For example, with zstd compression, `junit.xml` is written to `junit.xml.zst`.
+    pub fn apply_to_path(self, path: &Utf8Path) -> Utf8PathBuf {
+        match self.extension() {
+            Some(extension) => format!("{path}.{extension}").into(),
+            None => path.to_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_helpers::parse_ci_profile;
+    use test_case::test_case;
+
+    #[test_case("", OutputCompression::None; "default")]
+    #[test_case(r#"output-compression = "none""#, OutputCompression::None; "none")]
+    #[test_case(r#"output-compression = "gzip""#, OutputCompression::Gzip; "gzip")]
+    #[test_case(r#"output-compression = "zstd""#, OutputCompression::Zstd; "zstd")]
+    fn parse_output_compression(config_line: &str, expected: OutputCompression) {
+        let actual = parse_ci_profile(config_line, |profile| profile.output_compression())
+            .expect("config file should parse");
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_apply_to_path() {
+        let path = Utf8Path::new("target/nextest/ci/junit.xml");
+        assert_eq!(OutputCompression::None.apply_to_path(path), path);
+        assert_eq!(
+            OutputCompression::Gzip.apply_to_path(path),
+            "target/nextest/ci/junit.xml.gz"
+        );
+        assert_eq!(
+            OutputCompression::Zstd.apply_to_path(path),
+            "target/nextest/ci/junit.xml.zst"
+        );
+    }
+}
@@ -2,23 +2,30 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use super::{
-    CompiledProfileScripts, DeserializedProfileScriptConfig, EvaluatableProfile, NextestConfig,
-    NextestConfigImpl,
+    CompiledProfileScripts, DeserializedProfileScriptConfig, DeserializedSmokeConfig,
+    EvaluatableProfile, LogicalSuite, NextestConfig, NextestConfigImpl, SmokeConfig,
 };
 use crate::{
-    config::{FinalConfig, PreBuildPlatform, RetryPolicy, SlowTimeout, TestGroup, ThreadsRequired},
+    config::{
+        CpuAffinity, FinalConfig, MaxOutputSize, MemoryLimit, PreBuildPlatform, RetryPolicy,
+        SlowTimeout, TestGroup, ThreadsRequired,
+    },
     errors::{
         ConfigCompileError, ConfigCompileErrorKind, ConfigCompileSection, ConfigParseErrorKind,
     },
     platform::BuildPlatforms,
     reporter::TestOutputDisplay,
 };
+use camino::Utf8PathBuf;
 use guppy::graph::{cargo::BuildPlatform, PackageGraph};
 use nextest_filtering::{CompiledExpr, Filterset, FiltersetKind, ParseContext, TestQuery};
 use owo_colors::{OwoColorize, Style};
 use serde::{Deserialize, Deserializer};
 use smol_str::SmolStr;
-use std::{collections::HashMap, time::Duration};
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::Duration,
+};
 use target_spec::{Platform, TargetSpec};
 
 /// Settings for individual tests.
@@ -34,7 +41,13 @@ pub struct TestSettings<'p, Source = ()> {
     retries: (RetryPolicy, Source),
     slow_timeout: (SlowTimeout, Source),
     leak_timeout: (Duration, Source),
+    max_output_size: (MaxOutputSize, Source),
+    memory_limit: (MemoryLimit, Source),
     test_group: (TestGroup, Source),
+    cpu_affinity: (Option<CpuAffinity>, Source),
+    owner: (Option<&'p str>, Source),
+    attachments: (&'p [Utf8PathBuf], Source),
+    quarantine: (bool, Source),
     success_output: (TestOutputDisplay, Source),
     failure_output: (TestOutputDisplay, Source),
     junit_store_success_output: (bool, Source),
@@ -98,11 +111,51 @@ impl<'p> TestSettings<'p> {
         self.leak_timeout.0
     }
 
+    /// Returns the maximum amount of output captured for each output stream of this test.
+    pub fn max_output_size(&self) -> MaxOutputSize {
+        self.max_output_size.0
+    }
+
+    /// Returns the memory limit for each process in this test.
+    pub fn memory_limit(&self) -> MemoryLimit {
+        self.memory_limit.0
+    }
+
     /// Returns the test group for this test.
     pub fn test_group(&self) -> &TestGroup {
         &self.test_group.0
     }
 
+    /// Returns the CPUs this test is pinned to, if any.
+    ///
+    /// CPU affinity is only applied on Linux.
+    pub fn cpu_affinity(&self) -> Option<&CpuAffinity> {
+        self.cpu_affinity.0.as_ref()
+    }
+
+    /// Returns the owner of this test, if any.
+    ///
+    /// Owners are opaque identifiers, such as team names, that tools consuming nextest's output
+    /// can use to route failures.
+    pub fn owner(&self) -> Option<&'p str> {
+        self.owner.0
+    }
+
+    /// Returns the files that are attached to this test's results, if any.
+    ///
+    /// Relative paths are relative to the test's working directory.
+    pub fn attachments(&self) -> &'p [Utf8PathBuf] {
+        self.attachments.0
+    }
+
+    /// Returns true if this test is quarantined.
+    ///
+    /// Quarantined tests are run and reported as usual, but their failures don't cause the run to
+    /// fail.
+    pub fn quarantine(&self) -> bool {
+        self.quarantine.0
+    }
+
     /// Returns the success output setting for this test.
     pub fn success_output(&self) -> TestOutputDisplay {
         self.success_output.0
@@ -137,7 +190,13 @@ impl<'p, Source: Copy> TestSettings<'p, Source> {
         let mut retries = None;
         let mut slow_timeout = None;
         let mut leak_timeout = None;
+        let mut max_output_size = None;
+        let mut memory_limit = None;
         let mut test_group = None;
+        let mut cpu_affinity = None;
+        let mut owner = None;
+        let mut attachments = None;
+        let mut quarantine = None;
         let mut success_output = None;
         let mut failure_output = None;
         let mut junit_store_success_output = None;
@@ -187,11 +246,41 @@ impl<'p, Source: Copy> TestSettings<'p, Source> {
                     leak_timeout = Some(Source::track_override(l, override_));
                 }
             }
+            if max_output_size.is_none() {
+                if let Some(m) = override_.data.max_output_size {
+                    max_output_size = Some(Source::track_override(m, override_));
+                }
+            }
+            if memory_limit.is_none() {
+                if let Some(m) = override_.data.memory_limit {
+                    memory_limit = Some(Source::track_override(m, override_));
+                }
+            }
             if test_group.is_none() {
                 if let Some(t) = &override_.data.test_group {
                     test_group = Some(Source::track_override(t.clone(), override_));
                 }
             }
+            if cpu_affinity.is_none() {
+                if let Some(c) = &override_.data.cpu_affinity {
+                    cpu_affinity = Some(Source::track_override(Some(c.clone()), override_));
+                }
+            }
+            if owner.is_none() {
+                if let Some(o) = override_.data.owner.as_deref() {
+                    owner = Some(Source::track_override(Some(o), override_));
+                }
+            }
+            if attachments.is_none() {
+                if let Some(a) = override_.data.attachments.as_deref() {
+                    attachments = Some(Source::track_override(a, override_));
+                }
+            }
+            if quarantine.is_none() {
+                if let Some(q) = override_.data.quarantine {
+                    quarantine = Some(Source::track_override(q, override_));
+                }
+            }
             if success_output.is_none() {
                 if let Some(s) = override_.data.success_output {
                     success_output = Some(Source::track_override(s, override_));
@@ -224,7 +313,15 @@ impl<'p, Source: Copy> TestSettings<'p, Source> {
             slow_timeout.unwrap_or_else(|| Source::track_profile(profile.slow_timeout()));
         let leak_timeout =
             leak_timeout.unwrap_or_else(|| Source::track_profile(profile.leak_timeout()));
+        let max_output_size =
+            max_output_size.unwrap_or_else(|| Source::track_profile(profile.max_output_size()));
+        let memory_limit =
+            memory_limit.unwrap_or_else(|| Source::track_profile(profile.memory_limit()));
         let test_group = test_group.unwrap_or_else(|| Source::track_profile(TestGroup::Global));
+        let cpu_affinity = cpu_affinity.unwrap_or_else(|| Source::track_profile(None));
+        let owner = owner.unwrap_or_else(|| Source::track_profile(None));
+        let attachments = attachments.unwrap_or_else(|| Source::track_profile(&[][..]));
+        let quarantine = quarantine.unwrap_or_else(|| Source::track_profile(false));
         let success_output =
             success_output.unwrap_or_else(|| Source::track_profile(profile.success_output()));
         let failure_output =
@@ -244,7 +341,13 @@ impl<'p, Source: Copy> TestSettings<'p, Source> {
             retries,
             slow_timeout,
             leak_timeout,
+            max_output_size,
+            memory_limit,
             test_group,
+            cpu_affinity,
+            owner,
+            attachments,
+            quarantine,
             success_output,
             failure_output,
             junit_store_success_output,
@@ -296,6 +399,8 @@ impl CompiledByProfile {
             Some(config.default_profile().default_filter()),
             config.default_profile().overrides(),
             config.default_profile().setup_scripts(),
+            config.default_profile().logical_suites(),
+            config.default_profile().smoke(),
             &mut errors,
         );
         let other: HashMap<_, _> = config
@@ -309,6 +414,8 @@ impl CompiledByProfile {
                         profile.default_filter(),
                         profile.overrides(),
                         profile.scripts(),
+                        profile.logical_suites(),
+                        profile.smoke(),
                         &mut errors,
                     ),
                 )
@@ -333,6 +440,8 @@ impl CompiledByProfile {
                 profile_default_filter: Some(CompiledDefaultFilter::for_default_config()),
                 overrides: vec![],
                 scripts: vec![],
+                logical_suites: vec![],
+                smoke: None,
             },
             other: HashMap::new(),
         }
@@ -356,6 +465,9 @@ pub struct CompiledDefaultFilter {
     /// code). But it's not too bad.
     pub expr: CompiledExpr,
 
+    // The default filter as written in the config.
+    input: String,
+
     /// The profile name the default filter originates from.
     pub profile: String,
 
@@ -367,11 +479,17 @@ impl CompiledDefaultFilter {
     pub(crate) fn for_default_config() -> Self {
         Self {
             expr: CompiledExpr::ALL,
+            input: "all()".to_owned(),
             profile: NextestConfig::DEFAULT_PROFILE.to_owned(),
             section: CompiledDefaultFilterSection::Profile,
         }
     }
 
+    /// Returns the default filter as written in the config.
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
     /// Displays a configuration string for the default filter.
     pub fn display_config(&self, bold_style: Style) -> String {
         match &self.section {
@@ -410,15 +528,23 @@ pub(super) struct CompiledData<State> {
     pub(super) profile_default_filter: Option<CompiledDefaultFilter>,
     pub(super) overrides: Vec<CompiledOverride<State>>,
     pub(super) scripts: Vec<CompiledProfileScripts<State>>,
+    // Logical suites don't depend on the build platform. Like overrides, earlier suites take
+    // priority over later ones with the same name.
+    pub(super) logical_suites: Vec<LogicalSuite>,
+    // The smoke test configuration specified at the profile level.
+    pub(super) smoke: Option<SmokeConfig>,
 }
 
 impl CompiledData<PreBuildPlatform> {
+    #[expect(clippy::too_many_arguments)]
     fn new(
         graph: &PackageGraph,
         profile_name: &str,
         profile_default_filter: Option<&str>,
         overrides: &[DeserializedOverride],
         scripts: &[DeserializedProfileScriptConfig],
+        logical_suites: &BTreeMap<String, String>,
+        smoke: Option<&DeserializedSmokeConfig>,
         errors: &mut Vec<ConfigCompileError>,
     ) -> Self {
         let profile_default_filter = profile_default_filter.and_then(|filter| {
@@ -429,6 +555,7 @@ impl CompiledData<PreBuildPlatform> {
             match Filterset::parse(filter.to_owned(), &cx) {
                 Ok(expr) => Some(CompiledDefaultFilter {
                     expr: expr.compiled,
+                    input: expr.input,
                     profile: profile_name.to_owned(),
                     section: CompiledDefaultFilterSection::Profile,
                 }),
@@ -461,10 +588,15 @@ impl CompiledData<PreBuildPlatform> {
                 CompiledProfileScripts::new(graph, profile_name, index, source, errors)
             })
             .collect();
+        let logical_suites = LogicalSuite::compile_all(graph, profile_name, logical_suites, errors);
+        let smoke =
+            smoke.and_then(|smoke| SmokeConfig::compile(graph, profile_name, smoke, errors));
         Self {
             profile_default_filter,
             overrides,
             scripts,
+            logical_suites,
+            smoke,
         }
     }
 
@@ -473,26 +605,38 @@ impl CompiledData<PreBuildPlatform> {
         if other.profile_default_filter.is_some() {
             self.profile_default_filter = other.profile_default_filter;
         }
+        // The same goes for the smoke config.
+        if other.smoke.is_some() {
+            self.smoke = other.smoke;
+        }
         self.overrides.extend(other.overrides.into_iter().rev());
         self.scripts.extend(other.scripts.into_iter().rev());
+        self.logical_suites
+            .extend(other.logical_suites.into_iter().rev());
     }
 
     pub(super) fn reverse(&mut self) {
         self.overrides.reverse();
         self.scripts.reverse();
+        self.logical_suites.reverse();
     }
 
     /// Chains this data with another set of data, treating `other` as lower-priority than `self`.
     pub(super) fn chain(self, other: Self) -> Self {
         let profile_default_filter = self.profile_default_filter.or(other.profile_default_filter);
+        let smoke = self.smoke.or(other.smoke);
         let mut overrides = self.overrides;
         let mut setup_scripts = self.scripts;
+        let mut logical_suites = self.logical_suites;
         overrides.extend(other.overrides);
         setup_scripts.extend(other.scripts);
+        logical_suites.extend(other.logical_suites);
         Self {
             profile_default_filter,
             overrides,
             scripts: setup_scripts,
+            logical_suites,
+            smoke,
         }
     }
 
@@ -515,6 +659,8 @@ impl CompiledData<PreBuildPlatform> {
             profile_default_filter,
             overrides,
             scripts: setup_scripts,
+            logical_suites: self.logical_suites,
+            smoke: self.smoke,
         }
     }
 }
@@ -548,7 +694,13 @@ pub(super) struct ProfileOverrideData {
     retries: Option<RetryPolicy>,
     slow_timeout: Option<SlowTimeout>,
     leak_timeout: Option<Duration>,
+    max_output_size: Option<MaxOutputSize>,
+    memory_limit: Option<MemoryLimit>,
     pub(super) test_group: Option<TestGroup>,
+    cpu_affinity: Option<CpuAffinity>,
+    owner: Option<String>,
+    attachments: Option<Vec<Utf8PathBuf>>,
+    quarantine: Option<bool>,
     success_output: Option<TestOutputDisplay>,
     failure_output: Option<TestOutputDisplay>,
     junit: DeserializedJunitOutput,
@@ -607,6 +759,7 @@ impl CompiledOverride<PreBuildPlatform> {
                     (None, Some(default_filter)) => {
                         let compiled = CompiledDefaultFilter {
                             expr: default_filter.compiled,
+                            input: default_filter.input,
                             profile: profile_name.to_owned(),
                             section: CompiledDefaultFilterSection::Override(index),
                         };
@@ -630,7 +783,13 @@ impl CompiledOverride<PreBuildPlatform> {
                         retries: source.retries,
                         slow_timeout: source.slow_timeout,
                         leak_timeout: source.leak_timeout,
+                        max_output_size: source.max_output_size,
+                        memory_limit: source.memory_limit,
                         test_group: source.test_group.clone(),
+                        cpu_affinity: source.cpu_affinity.clone(),
+                        owner: source.owner.clone(),
+                        attachments: source.attachments.clone(),
+                        quarantine: source.quarantine,
                         success_output: source.success_output,
                         failure_output: source.failure_output,
                         junit: source.junit,
@@ -778,8 +937,20 @@ pub(super) struct DeserializedOverride {
     #[serde(default, with = "humantime_serde::option")]
     leak_timeout: Option<Duration>,
     #[serde(default)]
+    max_output_size: Option<MaxOutputSize>,
+    #[serde(default)]
+    memory_limit: Option<MemoryLimit>,
+    #[serde(default)]
     test_group: Option<TestGroup>,
     #[serde(default)]
+    cpu_affinity: Option<CpuAffinity>,
+    #[serde(default)]
+    owner: Option<String>,
+    #[serde(default)]
+    attachments: Option<Vec<Utf8PathBuf>>,
+    #[serde(default)]
+    quarantine: Option<bool>,
+    #[serde(default)]
     success_output: Option<TestOutputDisplay>,
     #[serde(default)]
     failure_output: Option<TestOutputDisplay>,
@@ -854,7 +1025,7 @@ impl<'de> Deserialize<'de> for PlatformStrings {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::{test_helpers::*, NextestConfig};
+    use crate::config::{test_helpers::*, NextestConfig, TerminateSignal};
     use camino::Utf8Path;
     use camino_tempfile::tempdir;
     use indoc::indoc;
@@ -881,6 +1052,8 @@ mod tests {
             retries = 3
             slow-timeout = "60s"
             leak-timeout = "300ms"
+            max-output-size = "1MiB"
+            memory-limit = "2GiB"
             test-group = "my-group"
             failure-output = "final"
             junit = { store-failure-output = false }
@@ -944,9 +1117,12 @@ mod tests {
                 period: Duration::from_secs(60),
                 terminate_after: None,
                 grace_period: Duration::from_secs(10),
+                terminate_signal: TerminateSignal::Term,
             }
         );
         assert_eq!(overrides.leak_timeout(), Duration::from_millis(300));
+        assert_eq!(overrides.max_output_size(), MaxOutputSize::Bytes(1 << 20));
+        assert_eq!(overrides.memory_limit(), MemoryLimit::Bytes(2 << 30));
         assert_eq!(overrides.test_group(), &test_group("my-group"));
         assert_eq!(overrides.success_output(), TestOutputDisplay::Never);
         assert_eq!(overrides.failure_output(), TestOutputDisplay::Final);
@@ -987,6 +1163,7 @@ mod tests {
                 period: Duration::from_secs(120),
                 terminate_after: Some(NonZeroUsize::new(1).unwrap()),
                 grace_period: Duration::ZERO,
+                terminate_signal: TerminateSignal::Term,
             }
         );
         assert_eq!(overrides.leak_timeout(), Duration::from_millis(300));
@@ -1028,6 +1205,194 @@ mod tests {
         assert_eq!(overrides.retries(), RetryPolicy::new_without_delay(0));
     }
 
+    #[test]
+    fn test_overrides_owner() {
+        let config_contents = indoc! {r#"
+            [[profile.default.overrides]]
+            filter = "test(/^net::/)"
+            owner = "networking"
+
+            [[profile.default.overrides]]
+            filter = "test(/^net::tls::/) | test(/^crypto::/)"
+            owner = "security"
+
+            [[profile.default.overrides]]
+            filter = "test(=net::flaky_test)"
+            retries = 3
+
+            [[profile.ci.overrides]]
+            filter = "test(/::slow_/)"
+            owner = "performance"
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let package_id = graph.workspace().iter().next().unwrap().id();
+
+        let nextest_config = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            &[][..],
+            &Default::default(),
+        )
+        .expect("config is valid");
+        let binary_query = binary_query(
+            &graph,
+            package_id,
+            "lib",
+            "my-binary",
+            BuildPlatform::Target,
+        );
+        let owner_for = |profile_name, test_name| {
+            let profile = nextest_config
+                .profile(profile_name)
+                .expect("valid profile name")
+                .apply_build_platforms(&build_platforms());
+            let query = TestQuery {
+                binary_query: binary_query.to_query(),
+                test_name,
+            };
+            profile
+                .settings_for(&query)
+                .owner()
+                .map(|owner| owner.to_owned())
+        };
+
+        // The first override that matches and sets an owner wins.
+        assert_eq!(
+            owner_for("default", "net::connect").as_deref(),
+            Some("networking")
+        );
+        assert_eq!(
+            owner_for("default", "net::tls::handshake").as_deref(),
+            Some("networking")
+        );
+        assert_eq!(
+            owner_for("default", "crypto::sign").as_deref(),
+            Some("security")
+        );
+        // Overrides that match but don't set an owner are skipped.
+        assert_eq!(
+            owner_for("default", "net::flaky_test").as_deref(),
+            Some("networking")
+        );
+        assert_eq!(owner_for("default", "fs::read"), None);
+
+        // Overrides in the selected profile take precedence over the default profile.
+        assert_eq!(
+            owner_for("ci", "net::slow_connect").as_deref(),
+            Some("performance")
+        );
+        assert_eq!(
+            owner_for("ci", "net::connect").as_deref(),
+            Some("networking")
+        );
+    }
+
+    #[test]
+    fn test_overrides_attachments() {
+        let config_contents = indoc! {r#"
+            [[profile.default.overrides]]
+            filter = "test(/^ui::/)"
+            attachments = ["screenshots/ui.png", "logs/ui.log"]
+
+            [[profile.default.overrides]]
+            filter = "test(/^ui::login/)"
+            attachments = ["screenshots/login.png"]
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let package_id = graph.workspace().iter().next().unwrap().id();
+
+        let nextest_config = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            &[][..],
+            &Default::default(),
+        )
+        .expect("config is valid");
+        let profile = nextest_config
+            .profile("default")
+            .expect("valid profile name")
+            .apply_build_platforms(&build_platforms());
+        let binary_query = binary_query(
+            &graph,
+            package_id,
+            "lib",
+            "my-binary",
+            BuildPlatform::Target,
+        );
+        let attachments_for = |test_name| {
+            let query = TestQuery {
+                binary_query: binary_query.to_query(),
+                test_name,
+            };
+            profile.settings_for(&query).attachments().to_vec()
+        };
+
+        // The first override that matches and sets attachments wins: lists aren't merged.
+        assert_eq!(
+            attachments_for("ui::login"),
+            vec![
+                Utf8PathBuf::from("screenshots/ui.png"),
+                Utf8PathBuf::from("logs/ui.log")
+            ],
+        );
+        assert_eq!(attachments_for("fs::read"), Vec::<Utf8PathBuf>::new());
+    }
+
+    #[test]
+    fn test_overrides_quarantine() {
+        let config_contents = indoc! {r#"
+            [[profile.default.overrides]]
+            filter = "test(=net::flaky_but_fixed)"
+            quarantine = false
+
+            [[profile.default.overrides]]
+            filter = "test(/^net::flaky_/)"
+            quarantine = true
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let package_id = graph.workspace().iter().next().unwrap().id();
+
+        let nextest_config = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            &[][..],
+            &Default::default(),
+        )
+        .expect("config is valid");
+        let profile = nextest_config
+            .profile("default")
+            .expect("valid profile name")
+            .apply_build_platforms(&build_platforms());
+        let binary_query = binary_query(
+            &graph,
+            package_id,
+            "lib",
+            "my-binary",
+            BuildPlatform::Target,
+        );
+        let quarantine_for = |test_name| {
+            let query = TestQuery {
+                binary_query: binary_query.to_query(),
+                test_name,
+            };
+            profile.settings_for(&query).quarantine()
+        };
+
+        assert!(quarantine_for("net::flaky_connect"));
+        // An earlier override can exempt a test from quarantine.
+        assert!(!quarantine_for("net::flaky_but_fixed"));
+        assert!(!quarantine_for("net::connect"));
+    }
+
     #[test_case(
         indoc! {r#"
             [[profile.default.overrides]]
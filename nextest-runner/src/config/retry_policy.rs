@@ -1,7 +1,7 @@
 // Copyright (c) The nextest Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::{cmp::Ordering, fmt, time::Duration};
 
 /// Type for the retry config key.
@@ -66,6 +66,43 @@ impl RetryPolicy {
             Self::Fixed { count, .. } | Self::Exponential { count, .. } => *count,
         }
     }
+
+    /// Returns the backoff strategy used to compute delays between retries.
+    pub fn backoff(&self) -> RetryBackoff {
+        match self {
+            Self::Fixed { .. } => RetryBackoff::Fixed,
+            Self::Exponential { .. } => RetryBackoff::Exponential,
+        }
+    }
+
+    /// Returns true if randomness is added to the delay between retries.
+    pub fn jitter(&self) -> bool {
+        match self {
+            Self::Fixed { jitter, .. } | Self::Exponential { jitter, .. } => *jitter,
+        }
+    }
+}
+
+/// The backoff strategy used to compute delays between retries, as reported in
+/// [`RetryData`](crate::reporter::events::RetryData).
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RetryBackoff {
+    /// The same delay is used before each retry.
+    #[default]
+    Fixed,
+
+    /// The delay doubles after each retry, up to an optional maximum.
+    Exponential,
+}
+
+impl fmt::Display for RetryBackoff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Fixed => write!(f, "fixed"),
+            Self::Exponential => write!(f, "exponential"),
+        }
+    }
 }
 
 pub(super) fn deserialize_retry_policy<'de, D>(
@@ -15,13 +15,14 @@ use crate::{
     },
     list::TestList,
     platform::BuildPlatforms,
-    reporter::events::SetupScriptEnvMap,
+    reporter::events::{SetupScriptEnvMap, SetupScriptEnvOverride},
     test_command::{apply_ld_dyld_env, create_command},
 };
 use camino_tempfile::Utf8TempPath;
 use guppy::graph::{cargo::BuildPlatform, PackageGraph};
 use indexmap::IndexMap;
 use nextest_filtering::{EvalContext, Filterset, FiltersetKind, ParseContext, TestQuery};
+use regex::Regex;
 use serde::{de::Error, Deserialize};
 use smol_str::SmolStr;
 use std::{
@@ -32,26 +33,33 @@ use std::{
     time::Duration,
 };
 
-/// Data about setup scripts, returned by an [`EvaluatableProfile`].
+/// Data about setup or teardown scripts, returned by an [`EvaluatableProfile`].
 pub struct SetupScripts<'profile> {
     enabled_scripts: IndexMap<&'profile ScriptId, SetupScript<'profile>>,
 }
 
 impl<'profile> SetupScripts<'profile> {
     pub(super) fn new(profile: &'profile EvaluatableProfile<'_>, test_list: &TestList<'_>) -> Self {
-        Self::new_with_queries(
-            profile,
-            test_list
-                .iter_tests()
-                .filter(|test| test.test_info.filter_match.is_match())
-                .map(|test| test.to_test_query()),
-        )
+        Self::new_with_queries(profile, matching_queries(test_list), |scripts| {
+            &scripts.setup
+        })
+    }
+
+    pub(super) fn new_teardown(
+        profile: &'profile EvaluatableProfile<'_>,
+        test_list: &TestList<'_>,
+    ) -> Self {
+        Self::new_with_queries(profile, matching_queries(test_list), |scripts| {
+            &scripts.teardown
+        })
     }
 
-    // Creates a new `SetupScripts` instance for the given profile and matching tests.
+    // Creates a new `SetupScripts` instance for the given profile and matching tests, with script
+    // IDs selected out of each profile script rule by `select`.
     fn new_with_queries<'a>(
         profile: &'profile EvaluatableProfile<'_>,
         matching_tests: impl IntoIterator<Item = TestQuery<'a>>,
+        select: fn(&CompiledProfileScripts<FinalConfig>) -> &[ScriptId],
     ) -> Self {
         let script_config = profile.script_config();
         let profile_scripts = &profile.compiled_data.scripts;
@@ -64,7 +72,7 @@ impl<'profile> SetupScripts<'profile> {
         // Build a map of setup scripts to the test configurations that enable them.
         let mut by_script_id = HashMap::new();
         for profile_script in profile_scripts {
-            for script_id in &profile_script.setup {
+            for script_id in select(profile_script) {
                 by_script_id
                     .entry(script_id)
                     .or_insert_with(Vec::new)
@@ -122,6 +130,14 @@ impl<'profile> SetupScripts<'profile> {
         self.enabled_scripts.is_empty()
     }
 
+    /// Returns the IDs and configurations of enabled setup scripts, in the order they should be
+    /// run in.
+    pub fn iter(&self) -> impl Iterator<Item = (&ScriptId, &'profile ScriptConfig)> + '_ {
+        self.enabled_scripts
+            .values()
+            .map(|script| (&script.id, script.config))
+    }
+
     /// Returns enabled setup scripts in the order they should be run in.
     #[inline]
     pub(crate) fn into_iter(self) -> impl Iterator<Item = SetupScript<'profile>> {
@@ -129,6 +145,13 @@ impl<'profile> SetupScripts<'profile> {
     }
 }
 
+fn matching_queries<'a>(test_list: &'a TestList<'_>) -> impl Iterator<Item = TestQuery<'a>> {
+    test_list
+        .iter_tests()
+        .filter(|test| test.test_info.filter_match.is_match())
+        .map(|test| test.to_test_query())
+}
+
 /// Data about an individual setup script.
 ///
 /// Returned by [`SetupScripts::iter`].
@@ -159,6 +182,8 @@ pub(crate) struct SetupScriptCommand {
     command: std::process::Command,
     /// The environment file.
     env_path: Utf8TempPath,
+    /// The JSON output file.
+    json_output_path: Utf8TempPath,
     /// Double-spawn context.
     double_spawn: Option<DoubleSpawnContext>,
 }
@@ -181,12 +206,20 @@ impl SetupScriptCommand {
             .tempfile()
             .map_err(|error| ChildStartError::TempPath(Arc::new(error)))?
             .into_temp_path();
+        let json_output_path = camino_tempfile::Builder::new()
+            .prefix("nextest-output")
+            .suffix(".json")
+            .tempfile()
+            .map_err(|error| ChildStartError::TempPath(Arc::new(error)))?
+            .into_temp_path();
 
         cmd.current_dir(test_list.workspace_root())
             // This environment variable is set to indicate that tests are being run under nextest.
             .env("NEXTEST", "1")
             // Setup scripts can define environment variables which are written out here.
-            .env("NEXTEST_ENV", &env_path);
+            .env("NEXTEST_ENV", &env_path)
+            // Setup scripts can also write structured outputs as a JSON object here.
+            .env("NEXTEST_OUTPUT_JSON", &json_output_path);
 
         apply_ld_dyld_env(&mut cmd, test_list.updated_dylib_path());
 
@@ -195,6 +228,7 @@ impl SetupScriptCommand {
         Ok(Self {
             command: cmd,
             env_path,
+            json_output_path,
             double_spawn,
         })
     }
@@ -205,34 +239,75 @@ impl SetupScriptCommand {
         &mut self.command
     }
 
-    pub(crate) fn spawn(self) -> std::io::Result<(tokio::process::Child, Utf8TempPath)> {
+    /// Spawns the command, returning the child along with the paths to the environment file and
+    /// the JSON output file.
+    pub(crate) fn spawn(
+        self,
+    ) -> std::io::Result<(tokio::process::Child, Utf8TempPath, Utf8TempPath)> {
         let mut command = tokio::process::Command::from(self.command);
         let res = command.spawn();
         if let Some(ctx) = self.double_spawn {
             ctx.finish();
         }
         let child = res?;
-        Ok((child, self.env_path))
+        Ok((child, self.env_path, self.json_output_path))
     }
 }
 
 /// Data obtained by executing setup scripts. This is used to set up the environment for tests.
 #[derive(Clone, Debug, Default)]
 pub(crate) struct SetupScriptExecuteData<'profile> {
+    extra_env: Option<SetupScriptEnvMap>,
     env_maps: Vec<(SetupScript<'profile>, SetupScriptEnvMap)>,
 }
 
 impl<'profile> SetupScriptExecuteData<'profile> {
-    pub(crate) fn new() -> Self {
-        Self::default()
+    /// Creates a new `SetupScriptExecuteData`, with extra environment variables applied to all
+    /// tests before any set by setup scripts.
+    pub(crate) fn new(extra_env: Option<SetupScriptEnvMap>) -> Self {
+        Self {
+            extra_env,
+            env_maps: Vec::new(),
+        }
     }
 
-    pub(crate) fn add_script(&mut self, script: SetupScript<'profile>, env_map: SetupScriptEnvMap) {
+    /// Adds the environment set by a setup script that just finished.
+    ///
+    /// Returns the variables that earlier setup scripts had also set, along with the most recent
+    /// script to set each one. Overriding variables in the extra environment isn't reported, since
+    /// setup scripts are expected to take precedence over it.
+    pub(crate) fn add_script(
+        &mut self,
+        script: SetupScript<'profile>,
+        env_map: SetupScriptEnvMap,
+    ) -> Vec<SetupScriptEnvOverride> {
+        let overrides = env_map
+            .env_map
+            .keys()
+            .filter_map(|key| {
+                let (previous, _) = self
+                    .env_maps
+                    .iter()
+                    .rev()
+                    .find(|(_, previous_env_map)| previous_env_map.env_map.contains_key(key))?;
+                Some(SetupScriptEnvOverride {
+                    key: key.clone(),
+                    previous_script_id: previous.id.clone(),
+                })
+            })
+            .collect();
         self.env_maps.push((script, env_map));
+        overrides
     }
 
     /// Applies the data from setup scripts to the given test instance.
     pub(crate) fn apply(&self, test: &TestQuery<'_>, cx: &EvalContext<'_>, command: &mut Command) {
+        // Extra environment variables are applied first, so setup scripts can override them.
+        if let Some(extra_env) = &self.extra_env {
+            for (key, value) in extra_env.env_map.iter() {
+                command.env(key, value);
+            }
+        }
         for (script, env_map) in &self.env_maps {
             if script.is_enabled(test, cx) {
                 for (key, value) in env_map.env_map.iter() {
@@ -241,11 +316,27 @@ impl<'profile> SetupScriptExecuteData<'profile> {
             }
         }
     }
+
+    /// Returns true if `key` is set by a setup script enabled for this test, and the script's
+    /// `redact-env` patterns match it.
+    pub(crate) fn is_redacted(
+        &self,
+        test: &TestQuery<'_>,
+        cx: &EvalContext<'_>,
+        key: &str,
+    ) -> bool {
+        self.env_maps.iter().any(|(script, env_map)| {
+            env_map.env_map.contains_key(key)
+                && script.config.redact_env.iter().any(|re| re.is_match(key))
+                && script.is_enabled(test, cx)
+        })
+    }
 }
 
 #[derive(Clone, Debug)]
 pub(crate) struct CompiledProfileScripts<State> {
     pub(super) setup: Vec<ScriptId>,
+    pub(super) teardown: Vec<ScriptId>,
     pub(super) data: ProfileScriptData,
     state: State,
 }
@@ -289,6 +380,7 @@ impl CompiledProfileScripts<PreBuildPlatform> {
         match (host_spec, target_spec, filter_expr) {
             (Ok(host_spec), Ok(target_spec), Ok(expr)) => Some(Self {
                 setup: source.setup.clone(),
+                teardown: source.teardown.clone(),
                 data: ProfileScriptData {
                     host_spec,
                     target_spec,
@@ -330,6 +422,7 @@ impl CompiledProfileScripts<PreBuildPlatform> {
 
         CompiledProfileScripts {
             setup: self.setup,
+            teardown: self.teardown,
             data: self.data,
             state: FinalConfig {
                 host_eval,
@@ -419,8 +512,12 @@ pub(super) struct DeserializedProfileScriptConfig {
     filter: Option<String>,
 
     /// The setup script or scripts to run.
-    #[serde(deserialize_with = "deserialize_script_ids")]
+    #[serde(default, deserialize_with = "deserialize_script_ids")]
     setup: Vec<ScriptId>,
+
+    /// The teardown script or scripts to run, after all tests have finished.
+    #[serde(default, deserialize_with = "deserialize_script_ids")]
+    teardown: Vec<ScriptId>,
 }
 
 /// Deserialized form of script configuration before compilation.
@@ -453,6 +550,11 @@ pub struct ScriptConfig {
     /// JUnit configuration for this script.
     #[serde(default)]
     pub junit: ScriptJunitConfig,
+
+    /// Patterns matching the keys of environment variables whose values should be hidden from
+    /// reporters.
+    #[serde(default, deserialize_with = "deserialize_redact_env")]
+    pub redact_env: Vec<Regex>,
 }
 
 impl ScriptConfig {
@@ -473,8 +575,28 @@ impl ScriptConfig {
     pub fn no_capture(&self) -> bool {
         !(self.capture_stdout && self.capture_stderr)
     }
+
+    /// Returns a copy of `env_map` with the values of keys matching `redact-env` hidden.
+    pub fn redact_env_map(&self, env_map: &SetupScriptEnvMap) -> SetupScriptEnvMap {
+        let env_map = env_map
+            .env_map
+            .iter()
+            .map(|(key, value)| {
+                let value = if self.redact_env.iter().any(|re| re.is_match(key)) {
+                    REDACTED_ENV_VALUE.to_owned()
+                } else {
+                    value.clone()
+                };
+                (key.clone(), value)
+            })
+            .collect();
+        SetupScriptEnvMap { env_map }
+    }
 }
 
+/// The value reported in place of environment variables matched by `redact-env`.
+pub const REDACTED_ENV_VALUE: &str = "[redacted]";
+
 /// A JUnit override configuration.
 #[derive(Copy, Clone, Debug, Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -505,6 +627,26 @@ fn default_true() -> bool {
     true
 }
 
+fn deserialize_redact_env<'de, D>(deserializer: D) -> Result<Vec<Regex>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let patterns = Vec::<String>::deserialize(deserializer)?;
+    patterns
+        .iter()
+        .map(|pattern| Regex::new(pattern).map_err(D::Error::custom))
+        .collect()
+}
+
+pub(super) fn deserialize_opt_redact_env<'de, D>(
+    deserializer: D,
+) -> Result<Option<Vec<Regex>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    deserialize_redact_env(deserializer).map(Some)
+}
+
 fn deserialize_script_ids<'de, D>(deserializer: D) -> Result<Vec<ScriptId>, D::Error>
 where
     D: serde::Deserializer<'de>,
@@ -540,7 +682,9 @@ where
     deserializer.deserialize_any(ScriptIdVisitor)
 }
 
-fn deserialize_command<'de, D>(deserializer: D) -> Result<(String, Vec<String>), D::Error>
+pub(super) fn deserialize_command<'de, D>(
+    deserializer: D,
+) -> Result<(String, Vec<String>), D::Error>
 where
     D: serde::Deserializer<'de>,
 {
@@ -602,6 +746,7 @@ mod tests {
             platform = { host = "x86_64-unknown-linux-gnu" }
             filter = "test(script1)"
             setup = ["foo", "bar"]
+            teardown = "baz"
 
             [[profile.default.scripts]]
             platform = { target = "aarch64-apple-darwin" }
@@ -685,7 +830,9 @@ mod tests {
             binary_query: host_binary_query.to_query(),
             test_name: "script1",
         };
-        let scripts = SetupScripts::new_with_queries(&profile, std::iter::once(query));
+        let scripts = SetupScripts::new_with_queries(&profile, std::iter::once(query), |scripts| {
+            &scripts.setup
+        });
         assert_eq!(scripts.len(), 2, "two scripts should be enabled");
         assert_eq!(
             scripts.enabled_scripts.get_index(0).unwrap().0.as_str(),
@@ -697,6 +844,18 @@ mod tests {
             "bar",
             "second script should be bar"
         );
+        let teardown_scripts =
+            SetupScripts::new_with_queries(&profile, std::iter::once(query), |scripts| {
+                &scripts.teardown
+            });
+        assert_eq!(
+            teardown_scripts
+                .iter()
+                .map(|(id, _)| id.as_str())
+                .collect::<Vec<_>>(),
+            ["baz"],
+            "baz should be enabled as a teardown script"
+        );
 
         let target_binary_query = binary_query(
             &graph,
@@ -711,7 +870,9 @@ mod tests {
             binary_query: target_binary_query.to_query(),
             test_name: "script2",
         };
-        let scripts = SetupScripts::new_with_queries(&profile, std::iter::once(query));
+        let scripts = SetupScripts::new_with_queries(&profile, std::iter::once(query), |scripts| {
+            &scripts.setup
+        });
         assert_eq!(scripts.len(), 1, "one script should be enabled");
         assert_eq!(
             scripts.enabled_scripts.get_index(0).unwrap().0.as_str(),
@@ -724,7 +885,9 @@ mod tests {
             binary_query: target_binary_query.to_query(),
             test_name: "script3",
         };
-        let scripts = SetupScripts::new_with_queries(&profile, std::iter::once(query));
+        let scripts = SetupScripts::new_with_queries(&profile, std::iter::once(query), |scripts| {
+            &scripts.setup
+        });
         assert_eq!(scripts.len(), 3, "three scripts should be enabled");
         assert_eq!(
             scripts.enabled_scripts.get_index(0).unwrap().0.as_str(),
@@ -743,6 +906,87 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_setup_script_env_overrides() {
+        let config: ScriptConfig =
+            toml::from_str(r#"command = "my-command""#).expect("script config is valid");
+        let script = |id: &str| SetupScript {
+            id: ScriptId::new(id.into()).unwrap(),
+            config: &config,
+            compiled: Vec::new(),
+        };
+        let env_map = |keys: &[&str]| SetupScriptEnvMap {
+            env_map: keys
+                .iter()
+                .map(|key| ((*key).to_owned(), "value".to_owned()))
+                .collect(),
+        };
+
+        // Variables in the extra environment can be overridden without a warning.
+        let mut data = SetupScriptExecuteData::new(Some(env_map(&["EXTRA"])));
+        assert_eq!(
+            data.add_script(script("foo"), env_map(&["A", "B", "EXTRA"])),
+            []
+        );
+        assert_eq!(
+            data.add_script(script("bar"), env_map(&["B", "C"])),
+            [SetupScriptEnvOverride {
+                key: "B".to_owned(),
+                previous_script_id: ScriptId::new("foo".into()).unwrap(),
+            }]
+        );
+        // The most recent script to set a variable is reported.
+        assert_eq!(
+            data.add_script(script("baz"), env_map(&["A", "B"])),
+            [
+                SetupScriptEnvOverride {
+                    key: "A".to_owned(),
+                    previous_script_id: ScriptId::new("foo".into()).unwrap(),
+                },
+                SetupScriptEnvOverride {
+                    key: "B".to_owned(),
+                    previous_script_id: ScriptId::new("bar".into()).unwrap(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_redact_env_map() {
+        let config: ScriptConfig = toml::from_str(indoc! {r#"
+            command = "my-command"
+            redact-env = ["(?i)token", "^SECRET_"]
+        "#})
+        .expect("script config is valid");
+
+        let env_map = SetupScriptEnvMap {
+            env_map: [
+                ("API_TOKEN", "abc"),
+                ("github_token", "def"),
+                ("SECRET_KEY", "ghi"),
+                ("MY_SECRET_KEY", "jkl"),
+                ("DATABASE_URL", "postgres://localhost"),
+            ]
+            .into_iter()
+            .map(|(key, value)| (key.to_owned(), value.to_owned()))
+            .collect(),
+        };
+        let redacted = config.redact_env_map(&env_map);
+        assert_eq!(
+            redacted.env_map,
+            [
+                ("API_TOKEN", REDACTED_ENV_VALUE),
+                ("DATABASE_URL", "postgres://localhost"),
+                ("MY_SECRET_KEY", "jkl"),
+                ("SECRET_KEY", REDACTED_ENV_VALUE),
+                ("github_token", REDACTED_ENV_VALUE),
+            ]
+            .into_iter()
+            .map(|(key, value)| (key.to_owned(), value.to_owned()))
+            .collect(),
+        );
+    }
+
     #[test_case(
         indoc! {r#"
             [script.foo]
@@ -779,6 +1023,16 @@ mod tests {
 
         ; "slow timeout is not a duration"
     )]
+    #[test_case(
+        indoc! {r#"
+            [script.foo]
+            command = "my-command"
+            redact-env = ["TOKEN("]
+        "#},
+        "regex parse error"
+
+        ; "redact-env pattern is invalid"
+    )]
     #[test_case(
         indoc! {r#"
             [script.'@tool:foo']
@@ -14,6 +14,8 @@ pub struct SlowTimeout {
     pub(crate) terminate_after: Option<NonZeroUsize>,
     #[serde(with = "humantime_serde", default = "default_grace_period")]
     pub(crate) grace_period: Duration,
+    #[serde(default)]
+    pub(crate) terminate_signal: TerminateSignal,
 }
 
 impl SlowTimeout {
@@ -23,6 +25,7 @@ impl SlowTimeout {
         period: Duration::from_secs(86400 * 365 * 30),
         terminate_after: None,
         grace_period: Duration::from_secs(10),
+        terminate_signal: TerminateSignal::Term,
     };
 }
 
@@ -30,6 +33,26 @@ fn default_grace_period() -> Duration {
     Duration::from_secs(10)
 }
 
+/// The signal sent to a test on Unix when it times out, before the grace period starts.
+///
+/// If the test is still running at the end of the grace period, it is sent SIGKILL. This is
+/// ignored on Windows, where timed out tests are always terminated immediately.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+pub enum TerminateSignal {
+    /// SIGTERM (the default).
+    #[default]
+    #[serde(rename = "SIGTERM")]
+    Term,
+
+    /// SIGQUIT.
+    #[serde(rename = "SIGQUIT")]
+    Quit,
+
+    /// SIGINT.
+    #[serde(rename = "SIGINT")]
+    Interrupt,
+}
+
 pub(super) fn deserialize_slow_timeout<'de, D>(
     deserializer: D,
 ) -> Result<Option<SlowTimeout>, D::Error>
@@ -60,6 +83,7 @@ where
                     period,
                     terminate_after: None,
                     grace_period: default_grace_period(),
+                    terminate_signal: TerminateSignal::default(),
                 }))
             }
         }
@@ -88,7 +112,7 @@ mod tests {
 
     #[test_case(
         "",
-        Ok(SlowTimeout { period: Duration::from_secs(60), terminate_after: None, grace_period: Duration::from_secs(10) }),
+        Ok(SlowTimeout { period: Duration::from_secs(60), terminate_after: None, grace_period: Duration::from_secs(10), terminate_signal: TerminateSignal::Term }),
         None
 
         ; "empty config is expected to use the hardcoded values"
@@ -98,7 +122,7 @@ mod tests {
             [profile.default]
             slow-timeout = "30s"
         "#},
-        Ok(SlowTimeout { period: Duration::from_secs(30), terminate_after: None, grace_period: Duration::from_secs(10) }),
+        Ok(SlowTimeout { period: Duration::from_secs(30), terminate_after: None, grace_period: Duration::from_secs(10), terminate_signal: TerminateSignal::Term }),
         None
 
         ; "overrides the default profile"
@@ -111,8 +135,8 @@ mod tests {
             [profile.ci]
             slow-timeout = { period = "60s", terminate-after = 3 }
         "#},
-        Ok(SlowTimeout { period: Duration::from_secs(30), terminate_after: None, grace_period: Duration::from_secs(10) }),
-        Some(SlowTimeout { period: Duration::from_secs(60), terminate_after: Some(NonZeroUsize::new(3).unwrap()), grace_period: Duration::from_secs(10) })
+        Ok(SlowTimeout { period: Duration::from_secs(30), terminate_after: None, grace_period: Duration::from_secs(10), terminate_signal: TerminateSignal::Term }),
+        Some(SlowTimeout { period: Duration::from_secs(60), terminate_after: Some(NonZeroUsize::new(3).unwrap()), grace_period: Duration::from_secs(10), terminate_signal: TerminateSignal::Term })
 
         ; "adds a custom profile 'ci'"
     )]
@@ -124,8 +148,8 @@ mod tests {
             [profile.ci]
             slow-timeout = "30s"
         "#},
-        Ok(SlowTimeout { period: Duration::from_secs(60), terminate_after: Some(NonZeroUsize::new(3).unwrap()), grace_period: Duration::from_secs(10) }),
-        Some(SlowTimeout { period: Duration::from_secs(30), terminate_after: None, grace_period: Duration::from_secs(10) })
+        Ok(SlowTimeout { period: Duration::from_secs(60), terminate_after: Some(NonZeroUsize::new(3).unwrap()), grace_period: Duration::from_secs(10), terminate_signal: TerminateSignal::Term }),
+        Some(SlowTimeout { period: Duration::from_secs(30), terminate_after: None, grace_period: Duration::from_secs(10), terminate_signal: TerminateSignal::Term })
 
         ; "ci profile uses string notation"
     )]
@@ -137,17 +161,40 @@ mod tests {
             [profile.ci]
             slow-timeout = "30s"
         "#},
-        Ok(SlowTimeout { period: Duration::from_secs(60), terminate_after: Some(NonZeroUsize::new(3).unwrap()), grace_period: Duration::from_secs(1) }),
-        Some(SlowTimeout { period: Duration::from_secs(30), terminate_after: None, grace_period: Duration::from_secs(10) })
+        Ok(SlowTimeout { period: Duration::from_secs(60), terminate_after: Some(NonZeroUsize::new(3).unwrap()), grace_period: Duration::from_secs(1), terminate_signal: TerminateSignal::Term }),
+        Some(SlowTimeout { period: Duration::from_secs(30), terminate_after: None, grace_period: Duration::from_secs(10), terminate_signal: TerminateSignal::Term })
 
         ; "timeout grace period"
     )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            slow-timeout = { period = "60s", terminate-after = 3, terminate-signal = "SIGQUIT" }
+
+            [profile.ci]
+            slow-timeout = { period = "30s", grace-period = "5s", terminate-signal = "SIGINT" }
+        "#},
+        Ok(SlowTimeout { period: Duration::from_secs(60), terminate_after: Some(NonZeroUsize::new(3).unwrap()), grace_period: Duration::from_secs(10), terminate_signal: TerminateSignal::Quit }),
+        Some(SlowTimeout { period: Duration::from_secs(30), terminate_after: None, grace_period: Duration::from_secs(5), terminate_signal: TerminateSignal::Interrupt })
+
+        ; "terminate signal"
+    )]
+    #[test_case(
+        indoc! {r#"
+            [profile.default]
+            slow-timeout = { period = "60s", terminate-signal = "SIGKILL" }
+        "#},
+        Err("original: enum TerminateSignal does not have variant constructor SIGKILL"),
+        None
+
+        ; "unsupported terminate signal should fail"
+    )]
     #[test_case(
         indoc! {r#"
             [profile.default]
             slow-timeout = { period = "60s" }
         "#},
-        Ok(SlowTimeout { period: Duration::from_secs(60), terminate_after: None, grace_period: Duration::from_secs(10) }),
+        Ok(SlowTimeout { period: Duration::from_secs(60), terminate_after: None, grace_period: Duration::from_secs(10), terminate_signal: TerminateSignal::Term }),
         None
 
         ; "partial table"
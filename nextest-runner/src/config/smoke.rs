@@ -0,0 +1,183 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::errors::{ConfigCompileError, ConfigCompileErrorKind, ConfigCompileSection};
+use guppy::graph::PackageGraph;
+use nextest_filtering::{CompiledExpr, Filterset, FiltersetKind, ParseContext};
+use serde::Deserialize;
+
+/// How smoke tests are run, as specified by the `smoke.mode` config key.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SmokeMode {
+    /// Run the smoke tests first, and only run the remaining tests if all smoke tests pass.
+    #[default]
+    Gate,
+
+    /// Run the smoke tests first, then run the remaining tests regardless of the result.
+    First,
+}
+
+/// Smoke test configuration, defined in the `smoke` config table.
+///
+/// Smoke tests are a fast subset of tests that are run as a separate phase before all the other
+/// tests, so obviously broken builds are caught early.
+///
+/// Returned by [`EvaluatableProfile::smoke`](super::EvaluatableProfile::smoke).
+#[derive(Clone, Debug)]
+pub struct SmokeConfig {
+    filter: String,
+    expr: CompiledExpr,
+    mode: SmokeMode,
+}
+
+impl SmokeConfig {
+    /// Returns the filterset that selects smoke tests, as written in the config.
+    pub fn filter(&self) -> &str {
+        &self.filter
+    }
+
+    /// Returns the compiled filterset that selects smoke tests.
+    pub fn expr(&self) -> &CompiledExpr {
+        &self.expr
+    }
+
+    /// Returns the mode that smoke tests are run in.
+    pub fn mode(&self) -> SmokeMode {
+        self.mode
+    }
+
+    pub(super) fn compile(
+        graph: &PackageGraph,
+        profile_name: &str,
+        source: &DeserializedSmokeConfig,
+        errors: &mut Vec<ConfigCompileError>,
+    ) -> Option<Self> {
+        let cx = ParseContext {
+            graph,
+            kind: FiltersetKind::Test,
+        };
+        match Filterset::parse(source.filter.clone(), &cx) {
+            Ok(expr) => Some(Self {
+                filter: expr.input,
+                expr: expr.compiled,
+                mode: source.mode,
+            }),
+            Err(err) => {
+                errors.push(ConfigCompileError {
+                    profile_name: profile_name.to_owned(),
+                    section: ConfigCompileSection::Smoke,
+                    kind: ConfigCompileErrorKind::Parse {
+                        host_parse_error: None,
+                        target_parse_error: None,
+                        filter_parse_errors: vec![err],
+                    },
+                });
+                None
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(super) struct DeserializedSmokeConfig {
+    filter: String,
+    #[serde(default)]
+    mode: SmokeMode,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{
+            test_helpers::{build_platforms, temp_workspace},
+            NextestConfig,
+        },
+        errors::ConfigParseErrorKind,
+    };
+    use camino_tempfile::tempdir;
+    use indoc::indoc;
+
+    #[test]
+    fn parse_smoke() {
+        let config_contents = indoc! {r#"
+            [profile.default.smoke]
+            filter = "test(/smoke/)"
+
+            [profile.ci.smoke]
+            filter = "test(=basic) | test(/^smoke::/)"
+            mode = "first"
+
+            [profile.inherits]
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let nextest_config = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            &[][..],
+            &Default::default(),
+        )
+        .expect("config file should parse");
+
+        let smoke_for = |profile_name: &str| {
+            let profile = nextest_config
+                .profile(profile_name)
+                .expect("profile should exist")
+                .apply_build_platforms(&build_platforms());
+            profile
+                .smoke()
+                .map(|smoke| (smoke.filter().to_owned(), smoke.mode()))
+        };
+
+        assert_eq!(
+            smoke_for("default"),
+            Some(("test(/smoke/)".to_owned(), SmokeMode::Gate)),
+        );
+        assert_eq!(
+            smoke_for("ci"),
+            Some((
+                "test(=basic) | test(/^smoke::/)".to_owned(),
+                SmokeMode::First
+            )),
+        );
+        assert_eq!(
+            smoke_for("inherits"),
+            Some(("test(/smoke/)".to_owned(), SmokeMode::Gate)),
+        );
+    }
+
+    #[test]
+    fn parse_smoke_invalid_filter() {
+        let config_contents = indoc! {r#"
+            [profile.ci.smoke]
+            filter = "test(/smoke/"
+        "#};
+
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let error = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            &[][..],
+            &Default::default(),
+        )
+        .expect_err("invalid filterset should fail to parse");
+
+        let ConfigParseErrorKind::CompileErrors(errors) = error.kind() else {
+            panic!("expected compile errors, found {error:?}");
+        };
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].profile_name, "ci");
+        assert!(
+            matches!(errors[0].section, ConfigCompileSection::Smoke),
+            "section is smoke: {:?}",
+            errors[0].section
+        );
+    }
+}
@@ -0,0 +1,35 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use serde::Deserialize;
+
+/// Type for the on-teardown-failure config key: what to do if a teardown script fails.
+///
+/// Teardown scripts run after all tests have finished, so a failing teardown script can't affect
+/// which tests are run.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OnTeardownFailure {
+    /// Print a warning, but don't fail a run that otherwise succeeded.
+    #[default]
+    Warn,
+
+    /// Fail the run, even if all tests passed.
+    Fail,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_helpers::parse_ci_profile;
+    use test_case::test_case;
+
+    #[test_case("", OnTeardownFailure::Warn; "default")]
+    #[test_case(r#"on-teardown-failure = "warn""#, OnTeardownFailure::Warn; "warn")]
+    #[test_case(r#"on-teardown-failure = "fail""#, OnTeardownFailure::Fail; "fail")]
+    fn parse_on_teardown_failure(config_line: &str, expected: OnTeardownFailure) {
+        let actual = parse_ci_profile(config_line, |profile| profile.on_teardown_failure())
+            .expect("config file should parse");
+        assert_eq!(actual, expected);
+    }
+}
@@ -3,7 +3,8 @@
 
 use crate::{
     cargo_config::{CargoConfigs, TargetDefinitionLocation, TargetTriple, TargetTripleSource},
-    config::{CustomTestGroup, TestGroup},
+    config::{CustomTestGroup, EvaluatableProfile, NextestConfig, TestGroup},
+    errors::ConfigParseError,
     platform::{BuildPlatforms, HostPlatform, PlatformLibdir, TargetPlatform},
 };
 use camino::{Utf8Path, Utf8PathBuf};
@@ -35,6 +36,31 @@ pub(super) fn temp_workspace(temp_dir: &Utf8Path, config_contents: &str) -> Pack
         .expect("error creating package graph")
 }
 
+/// Parses `profile_contents` as the body of a `[profile.ci]` section in a temporary workspace, then
+/// calls `f` with that profile.
+pub(super) fn parse_ci_profile<T>(
+    profile_contents: &str,
+    f: impl FnOnce(&EvaluatableProfile<'_>) -> T,
+) -> Result<T, ConfigParseError> {
+    let config_contents = format!("[profile.ci]\n{profile_contents}\n");
+
+    let workspace_dir = camino_tempfile::tempdir().unwrap();
+    let graph = temp_workspace(workspace_dir.path(), &config_contents);
+
+    let nextest_config = NextestConfig::from_sources(
+        graph.workspace().root(),
+        &graph,
+        None,
+        [],
+        &Default::default(),
+    )?;
+    let profile = nextest_config
+        .profile("ci")
+        .expect("ci profile should exist")
+        .apply_build_platforms(&build_platforms());
+    Ok(f(&profile))
+}
+
 pub(super) fn cargo_path() -> Utf8PathBuf {
     match std::env::var_os("CARGO") {
         Some(cargo_path) => PathBuf::from(cargo_path)
@@ -0,0 +1,191 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+
+/// Type for the normalize-test-names config key.
+///
+/// Parameterized tests often embed their parameters in the test name, e.g. `test_foo[case=3]`.
+/// Normalization maps each such name to a base name, so tests can be grouped by it for reporting.
+/// Tests are always run and reported individually under their real names.
+///
+/// The pattern must have at least one capture group. If the pattern has a group named `base`, it's
+/// used as the base name; otherwise, the first capture group is used.
+#[derive(Clone, Debug)]
+pub struct TestNameNormalization {
+    pattern: Regex,
+}
+
+impl TestNameNormalization {
+    /// Creates a new `TestNameNormalization` from a regex pattern.
+    pub fn new(pattern: &str) -> Result<Self, String> {
+        let pattern = Regex::new(pattern).map_err(|error| error.to_string())?;
+        // captures_len includes the implicit group for the whole match.
+        if pattern.captures_len() < 2 {
+            return Err(format!(
+                "pattern `{pattern}` must have at least one capture group for the base name"
+            ));
+        }
+        Ok(Self { pattern })
+    }
+
+    /// Returns the pattern, as written in the config.
+    pub fn pattern(&self) -> &str {
+        self.pattern.as_str()
+    }
+
+    /// Returns the base name for a test name.
+    ///
+    /// Returns `None` if the pattern doesn't match the name, or if the base name capture group
+    /// didn't participate in the match.
+    pub fn normalize<'a>(&self, test_name: &'a str) -> Option<&'a str> {
+        let captures = self.pattern.captures(test_name)?;
+        captures
+            .name("base")
+            .or_else(|| captures.get(1))
+            .map(|base| base.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for TestNameNormalization {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Self::new(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{
+        test_helpers::{build_platforms, temp_workspace},
+        NextestConfig,
+    };
+    use camino_tempfile::tempdir;
+    use indoc::indoc;
+    use test_case::test_case;
+
+    #[test_case(
+        r"^(.+)\[.*\]$",
+        "test_foo[case=3]",
+        Some("test_foo")
+
+        ; "first capture group"
+    )]
+    #[test_case(
+        r"^(.+)\[.*\]$",
+        "test_foo",
+        None
+
+        ; "no match"
+    )]
+    #[test_case(
+        r"^(tests::)?(?<base>[^:]+)::case_\d+$",
+        "tests::test_bar::case_10",
+        Some("test_bar")
+
+        ; "named capture group"
+    )]
+    #[test_case(
+        r"^(.+?)(?:_(\d+))?$",
+        "test_baz",
+        Some("test_baz")
+
+        ; "optional suffix"
+    )]
+    #[test_case(
+        r"^(?:(.+)\[.*\]|other)$",
+        "other",
+        None
+
+        ; "group did not participate"
+    )]
+    fn normalize(pattern: &str, test_name: &str, expected: Option<&str>) {
+        let normalization = TestNameNormalization::new(pattern).expect("pattern is valid");
+        assert_eq!(normalization.normalize(test_name), expected);
+    }
+
+    #[test]
+    fn new_invalid() {
+        let error = TestNameNormalization::new("test_foo").expect_err("no capture group");
+        assert!(
+            error.contains("must have at least one capture group"),
+            "error `{error}` mentions capture groups"
+        );
+        let error = TestNameNormalization::new("(").expect_err("invalid regex");
+        assert!(
+            error.contains("regex parse error"),
+            "error `{error}` contains `regex parse error`"
+        );
+    }
+
+    #[test]
+    fn parse_normalize_test_names() {
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(
+            workspace_dir.path(),
+            indoc! {r#"
+                [profile.ci]
+                normalize-test-names = '^(.+)\[.*\]$'
+            "#},
+        );
+
+        let nextest_config = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            &[][..],
+            &Default::default(),
+        )
+        .expect("config file should parse");
+
+        let default_profile = nextest_config
+            .profile("default")
+            .expect("default profile should exist")
+            .apply_build_platforms(&build_platforms());
+        assert!(default_profile.normalize_test_names().is_none());
+
+        let ci_profile = nextest_config
+            .profile("ci")
+            .expect("ci profile should exist")
+            .apply_build_platforms(&build_platforms());
+        let normalization = ci_profile
+            .normalize_test_names()
+            .expect("normalization is configured");
+        assert_eq!(normalization.pattern(), r"^(.+)\[.*\]$");
+        assert_eq!(
+            normalization.normalize("test_foo[case=1]"),
+            Some("test_foo")
+        );
+    }
+
+    #[test]
+    fn parse_invalid_pattern() {
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(
+            workspace_dir.path(),
+            indoc! {r#"
+                [profile.ci]
+                normalize-test-names = "test_foo"
+            "#},
+        );
+
+        let error = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            &[][..],
+            &Default::default(),
+        )
+        .expect_err("pattern without a capture group should fail to parse");
+        let error_str = format!("{error:?}");
+        assert!(
+            error_str.contains("must have at least one capture group"),
+            "error `{error_str}` mentions capture groups"
+        );
+    }
+}
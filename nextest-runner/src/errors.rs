@@ -5,8 +5,8 @@
 
 use crate::{
     cargo_config::{TargetTriple, TargetTripleSource},
-    config::{ConfigExperimental, CustomTestGroup, ScriptId, TestGroup},
-    helpers::{display_exited_with, dylib_path_envvar},
+    config::{ConfigExperimental, CpuAffinity, CustomTestGroup, ScriptId, TestGroup},
+    helpers::{display_exited_with, dylib_path_envvar, plural},
     redact::Redactor,
     reuse_build::{ArchiveFormat, ArchiveStep},
     target_runner::PlatformRunnerSource,
@@ -191,6 +191,12 @@ pub enum ConfigCompileSection {
 
     /// `[[profile.<profile-name>.scripts]]` at the corresponding index.
     Script(usize),
+
+    /// `profile.<profile-name>.logical-suites.<suite-name>`.
+    LogicalSuite(String),
+
+    /// `profile.<profile-name>.smoke.filter`.
+    Smoke,
 }
 
 /// The kind of error that occurred while parsing config overrides.
@@ -283,6 +289,85 @@ pub enum ChildStartError {
     /// An error occurred while spawning the child process.
     #[error("error spawning child process")]
     Spawn(#[source] Arc<std::io::Error>),
+
+    /// An error occurred while setting up CPU affinity for the child process.
+    #[error("error setting up CPU affinity")]
+    CpuAffinity(#[source] CpuAffinityError),
+}
+
+/// An error that occurred while setting up CPU affinity for a test.
+#[derive(Clone, Debug, Error)]
+#[non_exhaustive]
+pub enum CpuAffinityError {
+    /// The requested CPUs include some that aren't available to nextest.
+    #[error(
+        "CPU affinity `{requested}` includes CPUs that are not available: `{unavailable}` \
+         (available CPUs: `{available}`)"
+    )]
+    Unavailable {
+        /// The requested CPUs.
+        requested: CpuAffinity,
+
+        /// The requested CPUs that aren't available.
+        unavailable: CpuAffinity,
+
+        /// The CPUs available to nextest.
+        available: CpuAffinity,
+    },
+
+    /// An error occurred while querying the CPUs available to nextest.
+    #[error("error querying available CPUs")]
+    Query(#[source] Arc<std::io::Error>),
+}
+
+/// An error that occurred while reading an environment file.
+///
+/// Environment files are written by setup scripts, and can also be passed in with `--env-file`.
+#[derive(Clone, Debug, Error)]
+pub enum EnvFileError {
+    /// An error occurred while reading the environment file.
+    #[error("error reading environment file `{path}`")]
+    Read {
+        /// The path to the environment file.
+        path: Utf8PathBuf,
+
+        /// The underlying error.
+        #[source]
+        error: Arc<std::io::Error>,
+    },
+
+    /// A line in the environment file wasn't in a recognized format.
+    #[error("line `{line}` in environment file `{path}` not in KEY=VALUE format")]
+    Parse {
+        /// The path to the environment file.
+        path: Utf8PathBuf,
+
+        /// The line at issue.
+        line: String,
+    },
+
+    /// A multi-line value in the environment file was missing its terminating delimiter.
+    #[error(
+        "multi-line value for key `{key}` in environment file `{path}` \
+         is missing the terminating delimiter `{delimiter}`"
+    )]
+    UnterminatedValue {
+        /// The path to the environment file.
+        path: Utf8PathBuf,
+
+        /// The key whose value was unterminated.
+        key: String,
+
+        /// The delimiter that was expected on a line by itself.
+        delimiter: String,
+    },
+
+    /// An environment variable key was reserved.
+    #[error("key `{key}` begins with `NEXTEST`, which is reserved for internal use")]
+    ReservedKey {
+        /// The environment variable name.
+        key: String,
+    },
 }
 
 /// An error that occurred while reading the output of a setup script.
@@ -319,12 +404,84 @@ pub enum SetupScriptOutputError {
         line: String,
     },
 
+    /// A multi-line value in the setup script environment file was missing its terminating
+    /// delimiter.
+    #[error(
+        "multi-line value for key `{key}` in environment file `{path}` \
+         is missing the terminating delimiter `{delimiter}`"
+    )]
+    EnvFileUnterminatedValue {
+        /// The path to the environment file.
+        path: Utf8PathBuf,
+
+        /// The key whose value was unterminated.
+        key: String,
+
+        /// The delimiter that was expected on a line by itself.
+        delimiter: String,
+    },
+
     /// An environment variable key was reserved.
     #[error("key `{key}` begins with `NEXTEST`, which is reserved for internal use")]
     EnvFileReservedKey {
         /// The environment variable name.
         key: String,
     },
+
+    /// An error occurred while reading the setup script JSON output file.
+    #[error("error reading JSON output file `{path}`")]
+    JsonOutputFileRead {
+        /// The path to the JSON output file.
+        path: Utf8PathBuf,
+
+        /// The underlying error.
+        #[source]
+        error: Arc<std::io::Error>,
+    },
+
+    /// An error occurred while parsing the setup script JSON output file.
+    #[error("error parsing JSON output file `{path}`")]
+    JsonOutputFileParse {
+        /// The path to the JSON output file.
+        path: Utf8PathBuf,
+
+        /// The underlying error.
+        #[source]
+        error: Arc<serde_json::Error>,
+    },
+
+    /// The setup script JSON output file didn't contain a JSON object.
+    #[error("JSON output file `{path}` does not contain an object")]
+    JsonOutputFileNotObject {
+        /// The path to the JSON output file.
+        path: Utf8PathBuf,
+    },
+
+    /// A key in the setup script JSON output file can't be used in an environment variable name.
+    #[error("JSON output key `{key}` is empty or contains `=` or NUL")]
+    JsonOutputInvalidKey {
+        /// The flattened key, with nested keys joined by dots.
+        key: String,
+    },
+}
+
+impl From<EnvFileError> for SetupScriptOutputError {
+    fn from(error: EnvFileError) -> Self {
+        match error {
+            EnvFileError::Read { path, error } => Self::EnvFileRead { path, error },
+            EnvFileError::Parse { path, line } => Self::EnvFileParse { path, line },
+            EnvFileError::UnterminatedValue {
+                path,
+                key,
+                delimiter,
+            } => Self::EnvFileUnterminatedValue {
+                path,
+                key,
+                delimiter,
+            },
+            EnvFileError::ReservedKey { key } => Self::EnvFileReservedKey { key },
+        }
+    }
 }
 
 /// A list of errors that implements `Error`.
@@ -364,7 +521,8 @@ impl<T: std::error::Error> ErrorList<T> {
         }
     }
 
-    pub(crate) fn iter(&self) -> impl Iterator<Item = &T> {
+    /// Iterates over the errors in the list.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
         self.inner.iter()
     }
 }
@@ -452,6 +610,113 @@ pub enum ChildError {
     /// An error occurred while reading the output of a setup script.
     #[error(transparent)]
     SetupScriptOutput(#[from] SetupScriptOutputError),
+
+    /// A test passed, but wrote unexpected output to standard error.
+    #[error(transparent)]
+    UnexpectedStderr(#[from] UnexpectedStderrError),
+
+    /// A test left processes behind in its process group after it exited.
+    #[error(transparent)]
+    OrphanProcesses(#[from] OrphanProcessesError),
+
+    /// A test left threads running after it finished.
+    #[error(transparent)]
+    LingeringThreads(#[from] LingeringThreadsError),
+}
+
+/// A test passed, but wrote a line to standard error that wasn't permitted by the profile's
+/// `fail-on-stderr` setting.
+#[derive(Clone, Debug, Error)]
+#[error("test passed, but wrote unexpected output to standard error: `{line}`")]
+pub struct UnexpectedStderrError {
+    /// The first unexpected line, lossily converted to UTF-8.
+    pub line: String,
+}
+
+/// A test left processes behind in its process group after it exited.
+///
+/// This is only checked for if the profile's `orphan-processes` setting is enabled.
+#[derive(Clone, Debug, Error)]
+pub struct OrphanProcessesError {
+    /// The processes that were found, in ascending order of process ID.
+    pub processes: Vec<OrphanProcess>,
+
+    /// Whether the processes were killed.
+    pub killed: bool,
+}
+
+impl fmt::Display for OrphanProcessesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "test left behind {} {} in its process group",
+            self.processes.len(),
+            plural::processes_str(self.processes.len()),
+        )?;
+        if self.killed {
+            write!(
+                f,
+                ", which {} killed",
+                plural::were_plural_if(self.processes.len() != 1)
+            )?;
+        }
+        write!(f, ": {}", self.processes.iter().join(", "))
+    }
+}
+
+/// A process found in a test's process group after the test exited.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OrphanProcess {
+    /// The process ID.
+    pub pid: u32,
+
+    /// The name of the command the process is running.
+    pub command: String,
+}
+
+impl fmt::Display for OrphanProcess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.pid, self.command)
+    }
+}
+
+/// A test left threads running in its process after the test itself finished.
+///
+/// This is only checked for if the profile's `lingering-threads` setting is enabled.
+#[derive(Clone, Debug, Error)]
+pub struct LingeringThreadsError {
+    /// The threads that were found, in ascending order of thread ID.
+    pub threads: Vec<LingeringThread>,
+}
+
+impl fmt::Display for LingeringThreadsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "test left {} {} running after it finished: {}",
+            self.threads.len(),
+            plural::threads_str(self.threads.len()),
+            self.threads.iter().join(", "),
+        )
+    }
+}
+
+/// A thread found in a test process after the test finished.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LingeringThread {
+    /// The thread ID.
+    pub tid: u32,
+
+    /// The name of the thread, as reported by the operating system.
+    ///
+    /// On Linux, thread names are truncated to 15 bytes.
+    pub name: String,
+}
+
+impl fmt::Display for LingeringThread {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.tid, self.name)
+    }
 }
 
 /// An error was returned while reading from child a file descriptor.
@@ -620,6 +885,16 @@ impl TestThreadsParseError {
     }
 }
 
+/// Error returned while parsing a [`Shuffle`](crate::runner::Shuffle) value.
+#[derive(Clone, Debug, Error)]
+#[error(
+    "unrecognized value for shuffle: {input}\n(hint: expected either an integer seed or \"random\")"
+)]
+pub struct ShuffleParseError {
+    /// The input that failed to parse.
+    pub input: String,
+}
+
 /// An error that occurs while parsing a
 /// [`PartitionerBuilder`](crate::partition::PartitionerBuilder) input.
 #[derive(Clone, Debug, Error)]
@@ -948,6 +1223,34 @@ pub enum CreateTestListError {
     /// Creating a Tokio runtime failed.
     #[error("error creating Tokio runtime")]
     TokioRuntimeCreate(#[source] std::io::Error),
+
+    /// The Rust build metadata in a test list summary couldn't be parsed.
+    #[error("error parsing Rust build metadata in test list")]
+    SummaryBuildMeta(#[source] RustBuildMetaParseError),
+
+    /// The package for a binary in a test list summary wasn't found in the workspace.
+    #[error("for `{binary_id}`, error looking up package in the workspace")]
+    SummaryPackage {
+        /// The binary ID whose package wasn't found.
+        binary_id: RustBinaryId,
+
+        /// The underlying error.
+        #[source]
+        error: guppy::Error,
+    },
+
+    /// A test binary in a test list summary doesn't exist on disk.
+    #[error(
+        "for `{binary_id}`, test binary `{binary_path}` doesn't exist\n\
+         (hint: rebuild tests, or generate the test list again)"
+    )]
+    BinaryMissing {
+        /// The binary ID for which the binary wasn't found.
+        binary_id: RustBinaryId,
+
+        /// The path to the binary that wasn't found.
+        binary_path: Utf8PathBuf,
+    },
 }
 
 impl CreateTestListError {
@@ -968,6 +1271,19 @@ impl CreateTestListError {
     }
 }
 
+/// An error returned by [`TestList::subset`](crate::list::TestList::subset) if some of the
+/// requested tests aren't in the test list.
+#[derive(Clone, Debug, Error)]
+#[error(
+    "{} not found in test list: {}",
+    plural::tests_str(.tests.len()),
+    .tests.iter().map(|(binary_id, name)| format!("{binary_id} {name}")).join(", "),
+)]
+pub struct UnknownTestsError {
+    /// The tests that weren't found, as (binary ID, test name) pairs.
+    pub tests: Vec<(RustBinaryId, String)>,
+}
+
 /// An error that occurs while writing list output.
 #[derive(Debug, Error)]
 #[non_exhaustive]
@@ -1003,6 +1319,17 @@ pub enum TestRunnerBuildError {
     /// An error occurred while setting up signals.
     #[error("error setting up signals")]
     SignalHandlerSetupError(#[from] SignalHandlerSetupError),
+
+    /// A key in the extra environment passed to
+    /// [`TestRunnerBuilder::set_extra_env`](crate::runner::TestRunnerBuilder::set_extra_env) was
+    /// reserved.
+    #[error(
+        "extra environment variable `{key}` begins with `NEXTEST`, which is reserved for internal use"
+    )]
+    ExtraEnvReservedKey {
+        /// The environment variable name.
+        key: String,
+    },
 }
 
 /// Errors that occurred while managing test runner Tokio tasks.
@@ -1333,6 +1660,217 @@ pub enum WriteEventError {
         #[source]
         error: quick_junit::SerializeError,
     },
+
+    /// An error occurred while updating the timings store.
+    #[error("error updating timings store")]
+    Timings(#[source] TimingsStoreError),
+
+    /// An error occurred in a custom [`TestReporter`](crate::reporter::structured::TestReporter).
+    #[error("error in custom reporter")]
+    Custom(#[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+/// An error that occurred while creating a reporter from a
+/// [`FormatterRegistry`](crate::reporter::structured::FormatterRegistry).
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum CreateFormatterError {
+    /// No format with this name is registered.
+    #[error("unknown message format `{name}` (known formats: {})", known.join(", "))]
+    Unknown {
+        /// The name that was requested.
+        name: String,
+
+        /// The names of the registered formats.
+        known: Vec<String>,
+    },
+
+    /// The factory for this format returned an error.
+    #[error("error creating reporter for message format `{name}`")]
+    Factory {
+        /// The name of the format.
+        name: String,
+
+        /// The error returned by the factory.
+        #[source]
+        error: Box<dyn std::error::Error + Send + Sync>,
+    },
+}
+
+/// An error that occurred while reading a binary event log, or converting it to JSON.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum EventLogError {
+    /// An I/O error occurred.
+    #[error("error reading or writing event log")]
+    Io(#[source] std::io::Error),
+
+    /// The input doesn't start with an event log header.
+    #[error("input is not a nextest event log (invalid header)")]
+    InvalidHeader,
+
+    /// The event log was written with a format version this version of nextest doesn't support.
+    #[error("unsupported event log format version {version} (supported: {supported})")]
+    UnsupportedVersion {
+        /// The version in the event log header.
+        version: u16,
+
+        /// The version supported by this version of nextest.
+        supported: u16,
+    },
+
+    /// A record in the event log is invalid.
+    #[error("invalid event log record at byte offset {offset}: {message}")]
+    InvalidRecord {
+        /// The byte offset at which the record starts.
+        offset: u64,
+
+        /// A description of the problem.
+        message: &'static str,
+    },
+
+    /// An error occurred while serializing a record to JSON.
+    #[error("error serializing event log record to JSON")]
+    Serialize(#[source] serde_json::Error),
+}
+
+/// An error that occurred while computing the set of files changed since a git ref.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ChangedFilesError {
+    /// Executing git failed.
+    #[error("failed to execute `{}`", shell_words::join(command))]
+    GitExec {
+        /// The command that was run.
+        command: Vec<String>,
+
+        /// The underlying error.
+        #[source]
+        error: std::io::Error,
+    },
+
+    /// Git exited with a non-zero exit code.
+    #[error(
+        "command `{}` {}\n--- stderr:\n{}\n---",
+        shell_words::join(command),
+        display_exited_with(*exit_status),
+        String::from_utf8_lossy(stderr),
+    )]
+    GitFail {
+        /// The command that was run.
+        command: Vec<String>,
+
+        /// The exit status with which the command failed.
+        exit_status: ExitStatus,
+
+        /// Standard error for the command.
+        stderr: Vec<u8>,
+    },
+
+    /// Git reported a changed path that isn't valid UTF-8.
+    #[error(
+        "git reported a changed path that is not valid UTF-8: {}",
+        String::from_utf8_lossy(path)
+    )]
+    NonUtf8Path {
+        /// The path, as reported by git.
+        path: Vec<u8>,
+    },
+}
+
+/// An error that occurs while reading or writing a [`TimingsStore`](crate::timings::TimingsStore).
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum TimingsStoreError {
+    /// An error occurred while reading the timings file.
+    #[error("error reading timings from {path}")]
+    Read {
+        /// The path to the timings file.
+        path: Utf8PathBuf,
+
+        /// The underlying IO error.
+        #[source]
+        error: std::io::Error,
+    },
+
+    /// An error occurred while deserializing the timings file.
+    #[error("error deserializing timings from {path}")]
+    Deserialize {
+        /// The path to the timings file.
+        path: Utf8PathBuf,
+
+        /// The underlying error.
+        #[source]
+        error: serde_json::Error,
+    },
+
+    /// The timings file has a format version that isn't supported.
+    #[error("timings file {path} has unsupported format version {version}")]
+    UnsupportedVersion {
+        /// The path to the timings file.
+        path: Utf8PathBuf,
+
+        /// The format version found in the file.
+        version: u32,
+    },
+
+    /// An error occurred while writing the timings file.
+    #[error("error writing timings to {path}")]
+    Write {
+        /// The path to the timings file.
+        path: Utf8PathBuf,
+
+        /// The underlying IO error.
+        #[source]
+        error: std::io::Error,
+    },
+}
+
+/// An error that occurs while reading an [`OutputIndex`](crate::output_index::OutputIndex).
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum OutputIndexError {
+    /// An error occurred while reading the index file.
+    #[error("error reading output index from {path}")]
+    Read {
+        /// The path to the index file.
+        path: Utf8PathBuf,
+
+        /// The underlying IO error.
+        #[source]
+        error: std::io::Error,
+    },
+
+    /// An error occurred while deserializing the index file.
+    #[error("error deserializing output index from {path}")]
+    Deserialize {
+        /// The path to the index file.
+        path: Utf8PathBuf,
+
+        /// The underlying error.
+        #[source]
+        error: serde_json::Error,
+    },
+
+    /// The index file has a format version that isn't supported.
+    #[error("output index {path} has unsupported format version {version}")]
+    UnsupportedVersion {
+        /// The path to the index file.
+        path: Utf8PathBuf,
+
+        /// The format version found in the file.
+        version: u32,
+    },
+
+    /// A term in the index refers to a test that isn't in the index.
+    #[error("output index {path} is invalid: term `{term}` refers to an unknown test")]
+    InvalidTerm {
+        /// The path to the index file.
+        path: Utf8PathBuf,
+
+        /// The term.
+        term: String,
+    },
 }
 
 /// An error occurred while constructing a [`CargoConfigs`](crate::cargo_config::CargoConfigs)
@@ -40,6 +40,14 @@ pub(crate) mod plural {
         }
     }
 
+    pub(crate) fn cases_plural_if(plural: bool) -> &'static str {
+        if plural {
+            "cases"
+        } else {
+            "case"
+        }
+    }
+
     pub(crate) fn binaries_str(count: usize) -> &'static str {
         if count == 1 {
             "binary"
@@ -80,6 +88,30 @@ pub(crate) mod plural {
         }
     }
 
+    pub(crate) fn processes_str(count: usize) -> &'static str {
+        if count == 1 {
+            "process"
+        } else {
+            "processes"
+        }
+    }
+
+    pub(crate) fn file_descriptors_str(count: usize) -> &'static str {
+        if count == 1 {
+            "file descriptor"
+        } else {
+            "file descriptors"
+        }
+    }
+
+    pub(crate) fn threads_str(count: usize) -> &'static str {
+        if count == 1 {
+            "thread"
+        } else {
+            "threads"
+        }
+    }
+
     pub(crate) fn libraries_str(count: usize) -> &'static str {
         if count == 1 {
             "library"
@@ -296,6 +328,29 @@ impl fmt::Display for FormattedDuration {
     }
 }
 
+/// Displays a number of bytes using binary units, e.g. `12.3 MiB`.
+pub(crate) struct DisplayBytes(pub(crate) u64);
+
+impl fmt::Display for DisplayBytes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+        if self.0 < 1024 {
+            return write!(f, "{} B", self.0);
+        }
+        let mut value = self.0 as f64 / 1024.0;
+        let mut unit = UNITS[0];
+        for next_unit in &UNITS[1..] {
+            if value < 1024.0 {
+                break;
+            }
+            value /= 1024.0;
+            unit = next_unit;
+        }
+        write!(f, "{value:.1} {unit}")
+    }
+}
+
 // "exited with"/"terminated via"
 pub(crate) fn display_exited_with(exit_status: ExitStatus) -> String {
     match AbortStatus::extract(exit_status) {
@@ -329,6 +384,8 @@ pub(crate) fn display_abort_status(abort_status: AbortStatus) -> String {
         }
         #[cfg(windows)]
         AbortStatus::JobObject => "terminated via job object".to_string(),
+        #[cfg(windows)]
+        AbortStatus::MemoryLimit => "exceeded memory limit".to_string(),
     }
 }
 
@@ -19,6 +19,7 @@ mod helpers;
 pub mod indenter;
 pub mod input;
 pub mod list;
+pub mod output_index;
 pub mod partition;
 pub mod platform;
 pub mod redact;
@@ -36,6 +37,7 @@ mod test_command;
 pub mod test_filter;
 pub mod test_output;
 mod time;
+pub mod timings;
 #[cfg(feature = "self-update")]
 pub mod update;
 pub mod write_str;
@@ -0,0 +1,268 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Support for narrowing down test lists to tests affected by changed files.
+//!
+//! Changed files are mapped to test binaries through the package graph:
+//!
+//! * A file that is the source root of an integration test, benchmark, or example (or lives in the
+//!   directory for one, such as `tests/foo/helper.rs` for `tests/foo/main.rs`) affects just that
+//!   binary.
+//! * Any other file within a workspace package affects every binary in that package, as well as
+//!   every binary in workspace packages that depend on it.
+//! * Files outside all workspace packages don't affect any binaries.
+
+use crate::errors::ChangedFilesError;
+use camino::{Utf8Path, Utf8PathBuf};
+use guppy::{
+    graph::{BuildTargetId, PackageGraph, PackageMetadata},
+    PackageId,
+};
+use nextest_metadata::{RustBinaryId, RustTestBinaryKind};
+use std::{
+    collections::{BTreeSet, HashSet},
+    process::Command,
+};
+use tracing::debug;
+
+/// A set of files that changed in a workspace, relative to the workspace root.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChangedFiles {
+    files: BTreeSet<Utf8PathBuf>,
+}
+
+impl ChangedFiles {
+    /// Creates a new set of changed files from paths relative to the workspace root.
+    pub fn new(files: impl IntoIterator<Item = impl Into<Utf8PathBuf>>) -> Self {
+        Self {
+            files: files.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Computes the set of files changed in the working tree at `workspace_root` since `base_ref`.
+    ///
+    /// This includes committed, staged and unstaged changes to tracked files (including deletions),
+    /// as well as untracked files that aren't ignored.
+    pub fn from_git(workspace_root: &Utf8Path, base_ref: &str) -> Result<Self, ChangedFilesError> {
+        let mut files = BTreeSet::new();
+        for args in [
+            &["diff", "--name-only", "-z", "--relative", base_ref, "--"][..],
+            &["ls-files", "--others", "--exclude-standard", "-z"][..],
+        ] {
+            let output = run_git(workspace_root, args)?;
+            for path in output.split(|&b| b == 0).filter(|path| !path.is_empty()) {
+                let path =
+                    std::str::from_utf8(path).map_err(|_| ChangedFilesError::NonUtf8Path {
+                        path: path.to_vec(),
+                    })?;
+                files.insert(Utf8PathBuf::from(path));
+            }
+        }
+
+        debug!("{} files changed since `{base_ref}`", files.len());
+        Ok(Self { files })
+    }
+
+    /// Returns true if no files changed.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Returns the number of changed files.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Iterates over the changed files, relative to the workspace root.
+    pub fn iter(&self) -> impl Iterator<Item = &Utf8Path> + '_ {
+        self.files.iter().map(|path| path.as_path())
+    }
+
+    /// Returns the workspace packages affected by these changes.
+    ///
+    /// A package is affected if a file within it changed outside of a test, benchmark or example
+    /// target, or if it depends (including through dev-dependencies) on an affected package.
+    pub fn affected_packages<'g>(&self, graph: &'g PackageGraph) -> BTreeSet<&'g PackageId> {
+        let changed: HashSet<_> = self
+            .files
+            .iter()
+            .filter_map(|file| {
+                let package = owning_package(graph, file)?;
+                owning_target(package, file).is_none().then(|| package.id())
+            })
+            .collect();
+        if changed.is_empty() {
+            return BTreeSet::new();
+        }
+
+        let affected = graph
+            .query_reverse(changed)
+            .expect("changed packages are in the graph")
+            .resolve();
+        graph
+            .workspace()
+            .iter()
+            .map(|package| package.id())
+            .filter(|id| {
+                affected
+                    .contains(id)
+                    .expect("workspace package is in the graph")
+            })
+            .collect()
+    }
+
+    /// Returns the test binaries affected by these changes, given the test binaries in a test list.
+    ///
+    /// `binaries` is an iterator over the binary ID, binary kind, binary name and package of each
+    /// test binary.
+    pub fn affected_binaries<'a, 'g: 'a>(
+        &self,
+        graph: &'g PackageGraph,
+        binaries: impl IntoIterator<
+            Item = (
+                &'a RustBinaryId,
+                &'a RustTestBinaryKind,
+                &'a str,
+                PackageMetadata<'g>,
+            ),
+        >,
+    ) -> BTreeSet<RustBinaryId> {
+        let affected_packages = self.affected_packages(graph);
+        let changed_targets: HashSet<_> = self
+            .files
+            .iter()
+            .filter_map(|file| {
+                let package = owning_package(graph, file)?;
+                Some((package.id(), owning_target(package, file)?))
+            })
+            .collect();
+
+        binaries
+            .into_iter()
+            .filter(|(_, kind, name, package)| {
+                affected_packages.contains(package.id())
+                    || target_id(kind, name)
+                        .is_some_and(|id| changed_targets.contains(&(package.id(), id)))
+            })
+            .map(|(binary_id, ..)| binary_id.clone())
+            .collect()
+    }
+}
+
+fn run_git(workspace_root: &Utf8Path, args: &[&str]) -> Result<Vec<u8>, ChangedFilesError> {
+    let command: Vec<String> = std::iter::once("git")
+        .chain(args.iter().copied())
+        .map(ToOwned::to_owned)
+        .collect();
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(workspace_root)
+        .output()
+        .map_err(|error| ChangedFilesError::GitExec {
+            command: command.clone(),
+            error,
+        })?;
+    if !output.status.success() {
+        return Err(ChangedFilesError::GitFail {
+            command,
+            exit_status: output.status,
+            stderr: output.stderr,
+        });
+    }
+    Ok(output.stdout)
+}
+
+/// Returns the workspace package that contains `file` (relative to the workspace root), picking
+/// the innermost one for nested packages.
+fn owning_package<'g>(graph: &'g PackageGraph, file: &Utf8Path) -> Option<PackageMetadata<'g>> {
+    graph
+        .workspace()
+        .iter()
+        .filter_map(|package| {
+            let dir = package.source().workspace_path()?;
+            file.starts_with(dir)
+                .then_some((dir.components().count(), package))
+        })
+        .max_by_key(|(depth, _)| *depth)
+        .map(|(_, package)| package)
+}
+
+/// Returns the test, benchmark or example target within `package` that solely owns `file`, if
+/// any.
+fn owning_target<'g>(package: PackageMetadata<'g>, file: &Utf8Path) -> Option<BuildTargetId<'g>> {
+    let workspace_root = package.graph().workspace().root();
+    let package_dir = package.manifest_path().parent()?;
+    package.build_targets().find_map(|target| {
+        let id = target.id();
+        if !matches!(
+            id,
+            BuildTargetId::Test(_) | BuildTargetId::Benchmark(_) | BuildTargetId::Example(_)
+        ) {
+            return None;
+        }
+
+        let path = target.path();
+        // Targets like `tests/foo/main.rs` own the whole `tests/foo` directory, and targets like
+        // `tests/foo.rs` own the `tests/foo` directory for submodules.
+        let dir = if path.file_name() == Some("main.rs") {
+            path.parent()
+                .filter(|parent| *parent != package_dir.join("src"))
+        } else {
+            None
+        };
+        let module_dir = path.with_extension("");
+        let owns = |candidate: &Utf8Path| {
+            candidate
+                .strip_prefix(workspace_root)
+                .is_ok_and(|candidate| file.starts_with(candidate))
+        };
+        (owns(path) || owns(&module_dir) || dir.is_some_and(owns)).then_some(id)
+    })
+}
+
+fn target_id<'a>(kind: &RustTestBinaryKind, name: &'a str) -> Option<BuildTargetId<'a>> {
+    match kind.as_str() {
+        "test" => Some(BuildTargetId::Test(name)),
+        "bench" => Some(BuildTargetId::Benchmark(name)),
+        "example" => Some(BuildTargetId::Example(name)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use guppy::CargoMetadata;
+
+    static FIXTURE_JSON: &str = include_str!("../../../fixtures/tests-workspace-metadata.json");
+
+    #[test]
+    fn test_affected_packages() {
+        let graph = CargoMetadata::parse_json(FIXTURE_JSON)
+            .unwrap()
+            .build_graph()
+            .unwrap();
+        let names = |files: &[&str]| -> Vec<&str> {
+            ChangedFiles::new(files.iter().copied())
+                .affected_packages(&graph)
+                .into_iter()
+                .map(|id| graph.metadata(id).unwrap().name())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect()
+        };
+
+        assert_eq!(names(&[]), Vec::<&str>::new());
+        // Files outside all packages don't affect anything.
+        assert_eq!(names(&["README.md"]), Vec::<&str>::new());
+        assert_eq!(names(&["crate-g/src/lib.rs"]), vec!["crate_g"]);
+        assert_eq!(
+            names(&["crate-b/Cargo.toml"]),
+            vec!["crate_b", "crate_d", "crate_e", "crate_f", "crate_g"],
+        );
+        assert_eq!(
+            names(&["crate-c/src/lib.rs", "crate-f/src/lib.rs"]),
+            vec!["crate_c", "crate_d", "crate_e", "crate_f", "crate_g"],
+        );
+    }
+}
@@ -8,12 +8,14 @@
 //! * [`BinaryList`] for test binaries
 
 mod binary_list;
+mod changed;
 mod display_filter;
 mod output_format;
 mod rust_build_meta;
 mod test_list;
 
 pub use binary_list::*;
+pub use changed::*;
 pub(crate) use display_filter::*;
 pub use output_format::*;
 pub use rust_build_meta::*;
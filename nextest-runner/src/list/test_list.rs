@@ -5,14 +5,19 @@ use super::{DisplayFilterMatcher, TestListDisplayFilter};
 use crate::{
     cargo_config::EnvironmentMap,
     double_spawn::DoubleSpawnInfo,
-    errors::{CreateTestListError, FromMessagesError, WriteTestListError},
+    errors::{
+        ChangedFilesError, CreateTestListError, FromMessagesError, UnknownTestsError,
+        WriteTestListError,
+    },
     helpers::{convert_build_platform, dylib_path, dylib_path_envvar, write_test_name},
     indenter::indented,
-    list::{BinaryList, OutputFormat, RustBuildMeta, Styles, TestListState},
+    list::{BinaryList, ChangedFiles, OutputFormat, RustBuildMeta, Styles, TestListState},
+    partition::TimingsPartition,
     reuse_build::PathMapper,
     target_runner::{PlatformRunner, TargetRunner},
     test_command::{LocalExecuteContext, TestCommand},
     test_filter::{BinaryMismatchReason, FilterBinaryMatch, FilterBound, TestFilterBuilder},
+    timings::TimingsStore,
     write_str::WriteStr,
 };
 use camino::{Utf8Path, Utf8PathBuf};
@@ -25,7 +30,7 @@ use nextest_filtering::{BinaryQuery, EvalContext, TestQuery};
 use nextest_metadata::{
     BuildPlatform, FilterMatch, MismatchReason, RustBinaryId, RustNonTestBinaryKind,
     RustTestBinaryKind, RustTestBinarySummary, RustTestCaseSummary, RustTestSuiteStatusSummary,
-    RustTestSuiteSummary, TestListSummary,
+    RustTestSuiteSummary, TestCountSummary, TestListSummary,
 };
 use owo_colors::OwoColorize;
 use std::{
@@ -107,30 +112,10 @@ impl<'g> RustTestArtifact<'g> {
             let binary_path = path_mapper.map_binary(binary.path.clone());
             let cwd = path_mapper.map_cwd(cwd);
 
-            // Non-test binaries are only exposed to integration tests and benchmarks.
-            let non_test_binaries = if binary.kind == RustTestBinaryKind::TEST
-                || binary.kind == RustTestBinaryKind::BENCH
-            {
-                // Note we must use the TestListState rust_build_meta here to ensure we get remapped
-                // paths.
-                match rust_build_meta.non_test_binaries.get(package_id.repr()) {
-                    Some(binaries) => binaries
-                        .iter()
-                        .filter(|binary| {
-                            // Only expose BIN_EXE non-test files.
-                            binary.kind == RustNonTestBinaryKind::BIN_EXE
-                        })
-                        .map(|binary| {
-                            // Convert relative paths to absolute ones by joining with the target directory.
-                            let abs_path = rust_build_meta.target_directory.join(&binary.path);
-                            (binary.name.clone(), abs_path)
-                        })
-                        .collect(),
-                    None => BTreeSet::new(),
-                }
-            } else {
-                BTreeSet::new()
-            };
+            // Note we must use the TestListState rust_build_meta here to ensure we get remapped
+            // paths.
+            let non_test_binaries =
+                non_test_binaries_for(&binary.kind, &package_id, rust_build_meta);
 
             binaries.push(RustTestArtifact {
                 binary_id: binary.id.clone(),
@@ -189,6 +174,34 @@ impl<'g> RustTestArtifact<'g> {
     }
 }
 
+/// Returns the non-test binaries to be exposed to a test binary at runtime.
+fn non_test_binaries_for(
+    kind: &RustTestBinaryKind,
+    package_id: &PackageId,
+    rust_build_meta: &RustBuildMeta<TestListState>,
+) -> BTreeSet<(String, Utf8PathBuf)> {
+    // Non-test binaries are only exposed to integration tests and benchmarks.
+    if *kind != RustTestBinaryKind::TEST && *kind != RustTestBinaryKind::BENCH {
+        return BTreeSet::new();
+    }
+
+    match rust_build_meta.non_test_binaries.get(package_id.repr()) {
+        Some(binaries) => binaries
+            .iter()
+            .filter(|binary| {
+                // Only expose BIN_EXE non-test files.
+                binary.kind == RustNonTestBinaryKind::BIN_EXE
+            })
+            .map(|binary| {
+                // Convert relative paths to absolute ones by joining with the target directory.
+                let abs_path = rust_build_meta.target_directory.join(&binary.path);
+                (binary.name.clone(), abs_path)
+            })
+            .collect(),
+        None => BTreeSet::new(),
+    }
+}
+
 /// Information about skipped tests and binaries.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct SkipCounts {
@@ -305,6 +318,102 @@ impl<'g> TestList<'g> {
         })
     }
 
+    /// Creates a test list from a summary produced by [`Self::to_summary`], without building or
+    /// listing test binaries.
+    ///
+    /// The test cases in the summary, and whether they matched filters, are used as-is. Test
+    /// binaries that were listed must still exist on disk.
+    pub fn from_summary(
+        graph: &'g PackageGraph,
+        summary: TestListSummary,
+        workspace_root: Utf8PathBuf,
+        env: EnvironmentMap,
+    ) -> Result<Self, CreateTestListError> {
+        let rust_build_meta = RustBuildMeta::<TestListState>::from_summary(summary.rust_build_meta)
+            .map_err(CreateTestListError::SummaryBuildMeta)?;
+        let updated_dylib_path = Self::create_dylib_path(&rust_build_meta)?;
+
+        let rust_suites = summary
+            .rust_suites
+            .into_values()
+            .map(|suite| {
+                let RustTestSuiteSummary {
+                    binary,
+                    cwd,
+                    status,
+                    test_cases,
+                    ..
+                } = suite;
+                let RustTestBinarySummary {
+                    binary_id,
+                    binary_name,
+                    package_id,
+                    kind,
+                    binary_path,
+                    build_platform,
+                } = binary;
+
+                let package_id = PackageId::new(package_id);
+                let package = graph.metadata(&package_id).map_err(|error| {
+                    CreateTestListError::SummaryPackage {
+                        binary_id: binary_id.clone(),
+                        error,
+                    }
+                })?;
+
+                let status = if status == RustTestSuiteStatusSummary::LISTED {
+                    // Skipped binaries are never run, so they don't need to exist.
+                    if !binary_path.is_file() {
+                        return Err(CreateTestListError::BinaryMissing {
+                            binary_id,
+                            binary_path,
+                        });
+                    }
+                    RustTestSuiteStatus::Listed { test_cases }
+                } else if status == RustTestSuiteStatusSummary::SKIPPED_DEFAULT_FILTER {
+                    RustTestSuiteStatus::Skipped {
+                        reason: BinaryMismatchReason::DefaultSet,
+                    }
+                } else {
+                    RustTestSuiteStatus::Skipped {
+                        reason: BinaryMismatchReason::Expression,
+                    }
+                };
+
+                let non_test_binaries = non_test_binaries_for(&kind, &package_id, &rust_build_meta);
+                Ok((
+                    binary_id.clone(),
+                    RustTestSuite {
+                        binary_id,
+                        binary_path,
+                        package,
+                        binary_name,
+                        kind,
+                        non_test_binaries,
+                        cwd,
+                        build_platform,
+                        status,
+                    },
+                ))
+            })
+            .collect::<Result<BTreeMap<_, _>, _>>()?;
+
+        let test_count = rust_suites
+            .values()
+            .map(|suite| suite.status.test_count())
+            .sum();
+
+        Ok(Self {
+            rust_suites,
+            workspace_root,
+            env,
+            rust_build_meta,
+            updated_dylib_path,
+            test_count,
+            skip_counts: OnceLock::new(),
+        })
+    }
+
     /// Creates a new test list with the given binary names and outputs.
     #[cfg(test)]
     fn new_with_outputs(
@@ -448,6 +557,11 @@ impl<'g> TestList<'g> {
         &self.updated_dylib_path
     }
 
+    /// Constructs a serializable summary of the number of tests in this test list.
+    pub fn to_count_summary(&self) -> TestCountSummary {
+        TestCountSummary::new(self.binary_count(), self.test_count(), self.run_count())
+    }
+
     /// Constructs a serializble summary for this test list.
     pub fn to_summary(&self) -> TestListSummary {
         let rust_suites = self
@@ -478,6 +592,235 @@ impl<'g> TestList<'g> {
         summary
     }
 
+    /// Annotates each test in this list with its estimated duration and its duration in the most
+    /// recent run, from the timings store.
+    ///
+    /// Tests that have no recorded history are annotated with `None`.
+    pub fn apply_timings(&mut self, timings: &TimingsStore) {
+        for suite in self.rust_suites.values_mut() {
+            if let RustTestSuiteStatus::Listed { test_cases } = &mut suite.status {
+                for (name, test_case) in test_cases.iter_mut() {
+                    let test_timings = timings.test_timings(&suite.binary_id, name);
+                    test_case.estimated_duration = test_timings.and_then(|t| t.median());
+                    test_case.last_run_time = test_timings.and_then(|t| t.last());
+                }
+            }
+        }
+    }
+
+    /// Annotates each test in this list with its flaky rate from the timings store.
+    ///
+    /// Tests that have no recorded outcomes are annotated with `None`.
+    pub fn apply_flaky_rates(&mut self, timings: &TimingsStore) {
+        for suite in self.rust_suites.values_mut() {
+            if let RustTestSuiteStatus::Listed { test_cases } = &mut suite.status {
+                for (name, test_case) in test_cases.iter_mut() {
+                    test_case.flaky_rate = timings.flaky_rate(&suite.binary_id, name);
+                }
+            }
+        }
+    }
+
+    /// Partitions the tests in this list by their recorded durations, for
+    /// [`PartitionerBuilder::Timings`](crate::partition::PartitionerBuilder::Timings).
+    ///
+    /// Only tests that match all other filters are partitioned. Tests outside of `shard` are marked
+    /// as skipped with [`MismatchReason::Partition`].
+    pub fn apply_timings_partition(
+        &mut self,
+        shard: u64,
+        total_shards: u64,
+        timings: &TimingsStore,
+    ) {
+        let mut partition = TimingsPartition::new(shard, total_shards);
+        for suite in self.rust_suites.values() {
+            if let RustTestSuiteStatus::Listed { test_cases } = &suite.status {
+                for (name, test_case) in test_cases.iter() {
+                    if test_case.filter_match.is_match() {
+                        partition.add_test(
+                            &suite.binary_id,
+                            name,
+                            timings.estimated_duration(&suite.binary_id, name),
+                        );
+                    }
+                }
+            }
+        }
+        let mut in_shard: BTreeMap<RustBinaryId, BTreeSet<String>> = BTreeMap::new();
+        for (binary_id, name) in partition.into_shard() {
+            in_shard
+                .entry(binary_id.clone())
+                .or_default()
+                .insert(name.to_owned());
+        }
+
+        for suite in self.rust_suites.values_mut() {
+            let suite_in_shard = in_shard.get(&suite.binary_id);
+            if let RustTestSuiteStatus::Listed { test_cases } = &mut suite.status {
+                for (name, test_case) in test_cases.iter_mut() {
+                    if test_case.filter_match.is_match()
+                        && !suite_in_shard.is_some_and(|names| names.contains(name.as_str()))
+                    {
+                        test_case.filter_match = FilterMatch::Mismatch {
+                            reason: MismatchReason::Partition,
+                        };
+                    }
+                }
+            }
+        }
+        self.skip_counts = OnceLock::new();
+    }
+
+    /// Removes test binaries that aren't affected by the given changed files.
+    ///
+    /// See [`ChangedFiles`] for how changed files are mapped to test binaries. If no files changed,
+    /// all binaries are removed.
+    pub fn retain_affected(&mut self, changed: &ChangedFiles) {
+        let affected = self.affected_binaries(changed);
+        self.rust_suites
+            .retain(|binary_id, _| affected.contains(binary_id));
+        self.test_count = self
+            .rust_suites
+            .values()
+            .map(|suite| suite.status.test_count())
+            .sum();
+        self.skip_counts = OnceLock::new();
+    }
+
+    /// Marks tests in binaries that aren't affected by the given changed files as skipped with
+    /// [`MismatchReason::Unchanged`].
+    ///
+    /// Unlike [`Self::retain_affected`], unaffected tests are kept in the list, so they're reported
+    /// as skipped in a test run. Tests that are already filtered out keep their original reason.
+    pub fn skip_unaffected(&mut self, changed: &ChangedFiles) {
+        let affected = self.affected_binaries(changed);
+        for suite in self.rust_suites.values_mut() {
+            if affected.contains(&suite.binary_id) {
+                continue;
+            }
+            if let RustTestSuiteStatus::Listed { test_cases } = &mut suite.status {
+                for test_case in test_cases.values_mut() {
+                    if test_case.filter_match.is_match() {
+                        test_case.filter_match = FilterMatch::Mismatch {
+                            reason: MismatchReason::Unchanged,
+                        };
+                    }
+                }
+            }
+        }
+        self.skip_counts = OnceLock::new();
+    }
+
+    fn affected_binaries(&self, changed: &ChangedFiles) -> BTreeSet<RustBinaryId> {
+        let Some(graph) = self
+            .rust_suites
+            .values()
+            .next()
+            .map(|suite| suite.package.graph())
+        else {
+            return BTreeSet::new();
+        };
+        changed.affected_binaries(
+            graph,
+            self.rust_suites.values().map(|suite| {
+                (
+                    &suite.binary_id,
+                    &suite.kind,
+                    suite.binary_name.as_str(),
+                    suite.package,
+                )
+            }),
+        )
+    }
+
+    /// Constructs a serializable summary for the subset of this list affected by changes in the
+    /// working tree since `base_ref`.
+    ///
+    /// If nothing changed since `base_ref`, the summary has no test suites.
+    pub fn changed_summary(&self, base_ref: &str) -> Result<TestListSummary, ChangedFilesError> {
+        let changed = ChangedFiles::from_git(&self.workspace_root, base_ref)?;
+        let mut list = self.clone();
+        list.retain_affected(&changed);
+        Ok(list.to_summary())
+    }
+
+    /// Returns a copy of this list that contains only the given tests, for running a hand-picked
+    /// set of tests without a filterset.
+    ///
+    /// All of the given tests are marked as matching, even if they were filtered out of this list.
+    /// Every other test is removed, along with binaries that have no tests left, so the run count
+    /// of the returned list is exactly the number of distinct tests given.
+    ///
+    /// Returns an error listing the tests that aren't in this list, if any.
+    pub fn subset<'a>(
+        &self,
+        tests: impl IntoIterator<Item = TestInstanceId<'a>>,
+    ) -> Result<Self, UnknownTestsError> {
+        let mut selected: BTreeMap<&RustBinaryId, BTreeSet<&str>> = BTreeMap::new();
+        for id in tests {
+            selected
+                .entry(id.binary_id)
+                .or_default()
+                .insert(id.test_name);
+        }
+
+        let mut unknown = Vec::new();
+        let mut rust_suites = BTreeMap::new();
+        for (binary_id, names) in selected {
+            let suite = self.rust_suites.get(binary_id);
+            let all_test_cases = match suite.map(|suite| &suite.status) {
+                Some(RustTestSuiteStatus::Listed { test_cases }) => Some(test_cases),
+                _ => None,
+            };
+
+            let mut test_cases = BTreeMap::new();
+            for name in names {
+                match all_test_cases.and_then(|test_cases| test_cases.get(name)) {
+                    Some(test_case) => {
+                        test_cases.insert(
+                            name.to_owned(),
+                            RustTestCaseSummary {
+                                filter_match: FilterMatch::Matches,
+                                ..test_case.clone()
+                            },
+                        );
+                    }
+                    None => unknown.push((binary_id.clone(), name.to_owned())),
+                }
+            }
+
+            if let Some(suite) = suite {
+                if !test_cases.is_empty() {
+                    rust_suites.insert(
+                        binary_id.clone(),
+                        RustTestSuite {
+                            status: RustTestSuiteStatus::Listed { test_cases },
+                            ..suite.clone()
+                        },
+                    );
+                }
+            }
+        }
+
+        if !unknown.is_empty() {
+            return Err(UnknownTestsError { tests: unknown });
+        }
+
+        let test_count = rust_suites
+            .values()
+            .map(|suite| suite.status.test_count())
+            .sum();
+        Ok(Self {
+            test_count,
+            rust_build_meta: self.rust_build_meta.clone(),
+            rust_suites,
+            workspace_root: self.workspace_root.clone(),
+            env: self.env.clone(),
+            updated_dylib_path: self.updated_dylib_path.clone(),
+            skip_counts: OnceLock::new(),
+        })
+    }
+
     /// Outputs this list to the given writer.
     pub fn write(
         &self,
@@ -493,12 +836,15 @@ impl<'g> TestList<'g> {
         }
     }
 
-    /// Iterates over all the test suites.
+    /// Iterates over all the test suites, sorted by binary ID.
     pub fn iter(&self) -> impl Iterator<Item = &RustTestSuite> + '_ {
         self.rust_suites.values()
     }
 
     /// Iterates over the list of tests, returning the path and test name.
+    ///
+    /// Tests are sorted by binary ID and then by test name, the same as the order of their
+    /// [`TestInstanceId`]s. Test runs start tests in this order.
     pub fn iter_tests(&self) -> impl Iterator<Item = TestInstance<'_>> + '_ {
         self.rust_suites.values().flat_map(|test_suite| {
             test_suite
@@ -592,6 +938,9 @@ impl<'g> TestList<'g> {
                         bound,
                         false,
                     ),
+                    estimated_duration: None,
+                    last_run_time: None,
+                    flaky_rate: None,
                 },
             );
         }
@@ -613,6 +962,9 @@ impl<'g> TestList<'g> {
                         bound,
                         true,
                     ),
+                    estimated_duration: None,
+                    last_run_time: None,
+                    flaky_rate: None,
                 },
             );
         }
@@ -668,7 +1020,21 @@ impl<'g> TestList<'g> {
         verbose: bool,
         colorize: bool,
     ) -> io::Result<()> {
-        self.write_human_impl(None, writer, verbose, colorize)
+        self.write_human_impl(None, None, writer, verbose, colorize)
+    }
+
+    /// Writes this test list out in a human-friendly format, grouped by build platform.
+    ///
+    /// Binaries built for `first` are written before binaries built for the other platform. Within
+    /// each group, binaries are sorted by binary ID.
+    pub fn write_human_by_platform(
+        &self,
+        first: BuildPlatform,
+        writer: &mut dyn WriteStr,
+        verbose: bool,
+        colorize: bool,
+    ) -> io::Result<()> {
+        self.write_human_impl(None, Some(first), writer, verbose, colorize)
     }
 
     /// Writes this test list out in a human-friendly format with the given filter.
@@ -679,12 +1045,13 @@ impl<'g> TestList<'g> {
         verbose: bool,
         colorize: bool,
     ) -> io::Result<()> {
-        self.write_human_impl(Some(filter), writer, verbose, colorize)
+        self.write_human_impl(Some(filter), None, writer, verbose, colorize)
     }
 
     fn write_human_impl(
         &self,
         filter: Option<&TestListDisplayFilter<'_>>,
+        first_platform: Option<BuildPlatform>,
         mut writer: &mut dyn WriteStr,
         verbose: bool,
         colorize: bool,
@@ -694,7 +1061,20 @@ impl<'g> TestList<'g> {
             styles.colorize();
         }
 
-        for info in self.rust_suites.values() {
+        let suites: Vec<_> = match first_platform {
+            Some(first) => {
+                // rust_suites is sorted by binary ID, and partitioning preserves that order.
+                let (first_suites, rest): (Vec<_>, Vec<_>) = self
+                    .rust_suites
+                    .values()
+                    .partition(|info| info.build_platform == first);
+                first_suites.into_iter().chain(rest).collect()
+            }
+            None => self.rust_suites.values().collect(),
+        };
+        let mut current_platform = None;
+
+        for info in suites {
             let matcher = match filter {
                 Some(filter) => match filter.matcher_for(&info.binary_id) {
                     Some(matcher) => matcher,
@@ -714,6 +1094,18 @@ impl<'g> TestList<'g> {
                 continue;
             }
 
+            if first_platform.is_some() && current_platform != Some(info.build_platform) {
+                if current_platform.is_some() {
+                    writeln!(writer)?;
+                }
+                writeln!(
+                    writer,
+                    "{}",
+                    format!("{} platform:", info.build_platform).style(styles.heading)
+                )?;
+                current_platform = Some(info.build_platform);
+            }
+
             writeln!(writer, "{}:", info.binary_id.style(styles.binary_id))?;
             if verbose {
                 writeln!(
@@ -746,10 +1138,12 @@ impl<'g> TestList<'g> {
                             match (verbose, info.filter_match.is_match()) {
                                 (_, true) => {
                                     write_test_name(name, &styles, &mut indented)?;
+                                    write_history(info, &styles, &mut indented)?;
                                     writeln!(indented)?;
                                 }
                                 (true, false) => {
                                     write_test_name(name, &styles, &mut indented)?;
+                                    write_history(info, &styles, &mut indented)?;
                                     writeln!(indented, " (skipped)")?;
                                 }
                                 (false, false) => {
@@ -770,6 +1164,31 @@ impl<'g> TestList<'g> {
     }
 }
 
+fn write_history(
+    test_case: &RustTestCaseSummary,
+    styles: &Styles,
+    writer: &mut dyn WriteStr,
+) -> io::Result<()> {
+    if let Some(duration) = test_case.estimated_duration {
+        write!(
+            writer,
+            " ({} {:.3}s)",
+            "est.".style(styles.field),
+            duration.as_secs_f64()
+        )?;
+    }
+    // Only call out tests that have actually been flaky.
+    if let Some(flaky_rate) = test_case.flaky_rate.filter(|rate| rate.flaky_runs > 0) {
+        write!(
+            writer,
+            " ({} {:.0}%)",
+            "flaky".style(styles.field),
+            flaky_rate.rate() * 100.0
+        )?;
+    }
+    Ok(())
+}
+
 /// A suite of tests within a single Rust test binary.
 ///
 /// This is a representation of [`nextest_metadata::RustTestSuiteSummary`] used internally by the runner.
@@ -1213,26 +1632,44 @@ mod tests {
                             "tests::foo::test_bar".to_owned() => RustTestCaseSummary {
                                 ignored: false,
                                 filter_match: FilterMatch::Matches,
+                                estimated_duration: None,
+                                last_run_time: None,
+                                flaky_rate: None,
                             },
                             "tests::baz::test_quux".to_owned() => RustTestCaseSummary {
                                 ignored: false,
                                 filter_match: FilterMatch::Matches,
+                                estimated_duration: None,
+                                last_run_time: None,
+                                flaky_rate: None,
                             },
                             "benches::bench_foo".to_owned() => RustTestCaseSummary {
                                 ignored: false,
                                 filter_match: FilterMatch::Matches,
+                                estimated_duration: None,
+                                last_run_time: None,
+                                flaky_rate: None,
                             },
                             "tests::ignored::test_bar".to_owned() => RustTestCaseSummary {
                                 ignored: true,
                                 filter_match: FilterMatch::Mismatch { reason: MismatchReason::Ignored },
+                                estimated_duration: None,
+                                last_run_time: None,
+                                flaky_rate: None,
                             },
                             "tests::baz::test_ignored".to_owned() => RustTestCaseSummary {
                                 ignored: true,
                                 filter_match: FilterMatch::Mismatch { reason: MismatchReason::Ignored },
+                                estimated_duration: None,
+                                last_run_time: None,
+                                flaky_rate: None,
                             },
                             "benches::ignored_bench_foo".to_owned() => RustTestCaseSummary {
                                 ignored: true,
                                 filter_match: FilterMatch::Mismatch { reason: MismatchReason::Ignored },
+                                estimated_duration: None,
+                                last_run_time: None,
+                                flaky_rate: None,
                             },
                         },
                     },
@@ -1260,6 +1697,7 @@ mod tests {
                 },
             }
         );
+        assert_eq!(test_list.to_count_summary(), TestCountSummary::new(2, 6, 3));
 
         // Check that the expected outputs are valid.
         static EXPECTED_HUMAN: &str = indoc! {"
@@ -1287,6 +1725,7 @@ mod tests {
         "};
         static EXPECTED_JSON_PRETTY: &str = indoc! {r#"
             {
+              "format-version": 1,
               "rust-build-meta": {
                 "target-directory": "/fake",
                 "base-output-directories": [],
@@ -1418,6 +1857,203 @@ mod tests {
                 .expect("json-pretty succeeded"),
             EXPECTED_JSON_PRETTY
         );
+
+        static EXPECTED_HUMAN_BY_PLATFORM: &str = indoc! {"
+            host platform:
+            fake-package::skipped-binary:
+              bin: /fake/skipped-binary
+              cwd: /fake/cwd
+              build platform: host
+                (test binary didn't match filtersets, skipped)
+
+            target platform:
+            fake-package::fake-binary:
+              bin: /fake/binary
+              cwd: /fake/cwd
+              build platform: target
+                benches::bench_foo
+                benches::ignored_bench_foo (skipped)
+                tests::baz::test_ignored (skipped)
+                tests::baz::test_quux
+                tests::foo::test_bar
+                tests::ignored::test_bar (skipped)
+        "};
+        let mut by_platform = String::new();
+        test_list
+            .write_human_by_platform(BuildPlatform::Host, &mut by_platform, true, false)
+            .expect("human by platform succeeded");
+        assert_eq!(by_platform, EXPECTED_HUMAN_BY_PLATFORM);
+    }
+
+    #[test]
+    fn test_subset() {
+        let test_case = |filter_match| RustTestCaseSummary {
+            ignored: false,
+            filter_match,
+            estimated_duration: None,
+            last_run_time: None,
+            flaky_rate: None,
+        };
+        let suite = |binary_id: &RustBinaryId, status| RustTestSuite {
+            binary_id: binary_id.clone(),
+            binary_path: "/fake/binary".into(),
+            package: package_metadata(),
+            binary_name: binary_id.to_string(),
+            kind: RustTestBinaryKind::LIB,
+            cwd: "/fake/cwd".into(),
+            build_platform: BuildPlatform::Target,
+            non_test_binaries: BTreeSet::new(),
+            status,
+        };
+
+        let binary_a = RustBinaryId::new("fake-package::a");
+        let binary_b = RustBinaryId::new("fake-package::b");
+        let binary_skipped = RustBinaryId::new("fake-package::skipped");
+        let test_list = TestList {
+            test_count: 3,
+            rust_build_meta: RustBuildMeta::empty(),
+            rust_suites: btreemap! {
+                binary_a.clone() => suite(&binary_a, RustTestSuiteStatus::Listed {
+                    test_cases: btreemap! {
+                        "test_1".to_owned() => test_case(FilterMatch::Matches),
+                        "test_2".to_owned() => test_case(FilterMatch::Mismatch {
+                            reason: MismatchReason::Expression,
+                        }),
+                    },
+                }),
+                binary_b.clone() => suite(&binary_b, RustTestSuiteStatus::Listed {
+                    test_cases: btreemap! {
+                        "test_1".to_owned() => test_case(FilterMatch::Matches),
+                    },
+                }),
+                binary_skipped.clone() => suite(&binary_skipped, RustTestSuiteStatus::Skipped {
+                    reason: BinaryMismatchReason::Expression,
+                }),
+            },
+            workspace_root: "/fake/path".into(),
+            env: EnvironmentMap::empty(),
+            updated_dylib_path: OsString::new(),
+            skip_counts: OnceLock::new(),
+        };
+        assert_eq!(test_list.run_count(), 2);
+
+        let id = |binary_id, test_name| TestInstanceId {
+            binary_id,
+            test_name,
+        };
+
+        // Tests filtered out of the original list can be selected, and repeated tests are only
+        // counted once.
+        let subset = test_list
+            .subset([
+                id(&binary_a, "test_2"),
+                id(&binary_a, "test_1"),
+                id(&binary_a, "test_2"),
+            ])
+            .expect("all tests are known");
+        assert_eq!(subset.test_count(), 2);
+        assert_eq!(subset.run_count(), 2);
+        assert_eq!(subset.binary_count(), 1);
+        assert_eq!(
+            subset
+                .iter_tests()
+                .map(|test| test.id())
+                .collect::<Vec<_>>(),
+            vec![id(&binary_a, "test_1"), id(&binary_a, "test_2")],
+        );
+
+        let subset = test_list.subset([]).expect("no tests is valid");
+        assert_eq!(subset.run_count(), 0);
+        assert_eq!(subset.binary_count(), 0);
+
+        let error = test_list
+            .subset([
+                id(&binary_b, "test_1"),
+                id(&binary_b, "test_missing"),
+                id(&binary_skipped, "test_1"),
+            ])
+            .expect_err("unknown tests are an error");
+        assert_eq!(
+            error.tests,
+            vec![
+                (binary_b.clone(), "test_missing".to_owned()),
+                (binary_skipped.clone(), "test_1".to_owned()),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_skip_unaffected() {
+        let test_case = |filter_match| RustTestCaseSummary {
+            ignored: false,
+            filter_match,
+            estimated_duration: None,
+            last_run_time: None,
+            flaky_rate: None,
+        };
+        // Changes are only mapped to workspace packages.
+        let package = PACKAGE_GRAPH_FIXTURE
+            .workspace()
+            .iter()
+            .next()
+            .expect("fixture has a workspace package");
+        let binary_id = RustBinaryId::new("metadata-base");
+        let test_list = TestList {
+            test_count: 2,
+            rust_build_meta: RustBuildMeta::empty(),
+            rust_suites: btreemap! {
+                binary_id.clone() => RustTestSuite {
+                    binary_id: binary_id.clone(),
+                    binary_path: "/fake/binary".into(),
+                    package,
+                    binary_name: "metadata-base".to_owned(),
+                    kind: RustTestBinaryKind::LIB,
+                    cwd: "/fake/cwd".into(),
+                    build_platform: BuildPlatform::Target,
+                    non_test_binaries: BTreeSet::new(),
+                    status: RustTestSuiteStatus::Listed {
+                        test_cases: btreemap! {
+                            "test_1".to_owned() => test_case(FilterMatch::Matches),
+                            "test_2".to_owned() => test_case(FilterMatch::Mismatch {
+                                reason: MismatchReason::Expression,
+                            }),
+                        },
+                    },
+                },
+            },
+            workspace_root: "/fake/path".into(),
+            env: EnvironmentMap::empty(),
+            updated_dylib_path: OsString::new(),
+            skip_counts: OnceLock::new(),
+        };
+        let filter_matches = |list: &TestList<'_>| -> Vec<FilterMatch> {
+            list.iter_tests()
+                .map(|instance| instance.test_info.filter_match)
+                .collect()
+        };
+
+        // A change within the package leaves the list as it is.
+        let mut affected = test_list.clone();
+        affected.skip_unaffected(&ChangedFiles::new(["src/lib.rs"]));
+        assert_eq!(affected.run_count(), 1);
+        assert_eq!(filter_matches(&affected), filter_matches(&test_list));
+
+        // If nothing changed, matching tests are skipped, and other tests keep their reason.
+        let mut unaffected = test_list.clone();
+        unaffected.skip_unaffected(&ChangedFiles::default());
+        assert_eq!(unaffected.run_count(), 0);
+        assert_eq!(unaffected.test_count(), 2);
+        assert_eq!(
+            filter_matches(&unaffected),
+            vec![
+                FilterMatch::Mismatch {
+                    reason: MismatchReason::Unchanged,
+                },
+                FilterMatch::Mismatch {
+                    reason: MismatchReason::Expression,
+                },
+            ]
+        );
     }
 
     static PACKAGE_GRAPH_FIXTURE: Lazy<PackageGraph> = Lazy::new(|| {
@@ -0,0 +1,334 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! A search index over the test output in per-run artifacts.
+//!
+//! If `index-output` is enabled for a profile with `artifacts-dir` set, nextest writes an inverted
+//! index to `output-index.json` in each run's artifacts directory at the end of the run. The index
+//! maps each term that appeared in a test's captured output to the tests whose output contained it,
+//! so finding which tests mentioned a term doesn't require reading every output file.
+//!
+//! Output is split into terms at every character that isn't alphanumeric or `_`, and terms are
+//! lowercased. For example, `thread 'main' panicked at src/lib.rs:10:5` has the terms `thread`,
+//! `main`, `panicked`, `at`, `src`, `lib`, `rs`, `10` and `5`. Terms longer than
+//! [`OutputIndex::MAX_TERM_LEN`] bytes aren't indexed.
+
+use crate::{errors::OutputIndexError, list::TestInstanceId};
+use atomicwrites::{AtomicFile, OverwriteBehavior};
+use camino::{Utf8Path, Utf8PathBuf};
+use nextest_metadata::RustBinaryId;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{self, Write},
+};
+
+/// An inverted index over the captured output of the tests in a run.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct OutputIndex {
+    tests: Vec<OutputIndexTest>,
+    // Maps each term to indexes into `tests`, in ascending order.
+    terms: BTreeMap<String, Vec<usize>>,
+}
+
+/// A test in an [`OutputIndex`], along with its output files.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct OutputIndexTest {
+    /// The binary ID.
+    pub binary_id: RustBinaryId,
+
+    /// The name of the test.
+    pub test_name: String,
+
+    /// The output files that were indexed for this test, relative to the run directory.
+    pub files: Vec<Utf8PathBuf>,
+}
+
+impl OutputIndexTest {
+    /// Returns the ID of this test.
+    pub fn id(&self) -> TestInstanceId<'_> {
+        TestInstanceId {
+            binary_id: &self.binary_id,
+            test_name: &self.test_name,
+        }
+    }
+}
+
+impl OutputIndex {
+    /// The name of the index file within a run's artifacts directory.
+    pub const FILE_NAME: &'static str = "output-index.json";
+
+    /// The current format version of the index file.
+    pub const FORMAT_VERSION: u32 = 1;
+
+    /// The maximum length of an indexed term, in bytes.
+    pub const MAX_TERM_LEN: usize = 64;
+
+    /// Reads the index from the given run directory.
+    pub fn read_from_run_dir(run_dir: &Utf8Path) -> Result<Self, OutputIndexError> {
+        let path = run_dir.join(Self::FILE_NAME);
+        let contents = std::fs::read_to_string(&path).map_err(|error| OutputIndexError::Read {
+            path: path.clone(),
+            error,
+        })?;
+        let serialized: OutputIndexSerialized =
+            serde_json::from_str(&contents).map_err(|error| OutputIndexError::Deserialize {
+                path: path.clone(),
+                error,
+            })?;
+        if serialized.format_version != Self::FORMAT_VERSION {
+            return Err(OutputIndexError::UnsupportedVersion {
+                path,
+                version: serialized.format_version,
+            });
+        }
+
+        let test_count = serialized.tests.len();
+        if let Some((term, _)) = serialized
+            .terms
+            .iter()
+            .find(|(_, tests)| tests.iter().any(|&index| index >= test_count))
+        {
+            return Err(OutputIndexError::InvalidTerm {
+                path,
+                term: term.clone(),
+            });
+        }
+
+        Ok(Self {
+            tests: serialized.tests,
+            terms: serialized.terms,
+        })
+    }
+
+    /// Returns all the tests in the index, in the order they finished.
+    pub fn tests(&self) -> &[OutputIndexTest] {
+        &self.tests
+    }
+
+    /// Returns the tests whose output contains every term in `query`.
+    ///
+    /// `query` is split into terms the same way output is, so `"Connection refused"` matches tests
+    /// whose output contains both `connection` and `refused`, though not necessarily next to each
+    /// other. Returns nothing if `query` has no terms.
+    pub fn query(&self, query: &str) -> Vec<&OutputIndexTest> {
+        let mut matches: Option<BTreeSet<usize>> = None;
+        for term in terms(query) {
+            // Terms that are too long are never indexed.
+            let Some(tests) = self.terms.get(&term) else {
+                return Vec::new();
+            };
+            let tests: BTreeSet<_> = tests.iter().copied().collect();
+            matches = Some(match matches {
+                Some(matches) => matches.intersection(&tests).copied().collect(),
+                None => tests,
+            });
+        }
+
+        matches
+            .unwrap_or_default()
+            .into_iter()
+            .map(|index| &self.tests[index])
+            .collect()
+    }
+
+    /// Writes the index to the given run directory.
+    ///
+    /// The file is written atomically, so readers never observe a partially-written index.
+    pub(crate) fn write_to_run_dir(&self, run_dir: &Utf8Path) -> io::Result<Utf8PathBuf> {
+        let path = run_dir.join(Self::FILE_NAME);
+        let serialized = OutputIndexSerializedRef {
+            format_version: Self::FORMAT_VERSION,
+            tests: &self.tests,
+            terms: &self.terms,
+        };
+        let file = AtomicFile::new(&path, OverwriteBehavior::AllowOverwrite);
+        file.write(|f| {
+            serde_json::to_writer(&mut *f, &serialized)?;
+            f.write_all(b"\n")
+        })
+        .map_err(|error| match error {
+            atomicwrites::Error::Internal(error) | atomicwrites::Error::User(error) => error,
+        })?;
+        Ok(path)
+    }
+}
+
+/// Builds an [`OutputIndex`] as tests finish.
+#[derive(Debug, Default)]
+pub(crate) struct OutputIndexBuilder {
+    index: OutputIndex,
+}
+
+impl OutputIndexBuilder {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the output of a test, written to `file` within the run directory.
+    ///
+    /// All the output files for a test must be added before moving on to the next test.
+    pub(crate) fn add(
+        &mut self,
+        binary_id: &RustBinaryId,
+        test_name: &str,
+        file: Utf8PathBuf,
+        contents: &[u8],
+    ) {
+        let index = &mut self.index;
+        let is_current = index
+            .tests
+            .last()
+            .is_some_and(|test| &test.binary_id == binary_id && test.test_name == test_name);
+        if !is_current {
+            index.tests.push(OutputIndexTest {
+                binary_id: binary_id.clone(),
+                test_name: test_name.to_owned(),
+                files: Vec::new(),
+            });
+        }
+        let test_index = index.tests.len() - 1;
+        index.tests[test_index].files.push(file);
+
+        for term in terms(&String::from_utf8_lossy(contents)) {
+            let tests = index.terms.entry(term).or_default();
+            // Tests are added in order, so a test that contains the term will be last.
+            if tests.last() != Some(&test_index) {
+                tests.push(test_index);
+            }
+        }
+    }
+
+    pub(crate) fn finish(self) -> OutputIndex {
+        self.index
+    }
+}
+
+/// Splits text into the terms that are indexed.
+fn terms(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|term| !term.is_empty() && term.len() <= OutputIndex::MAX_TERM_LEN)
+        .map(|term| term.to_lowercase())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct OutputIndexSerialized {
+    format_version: u32,
+    tests: Vec<OutputIndexTest>,
+    terms: BTreeMap<String, Vec<usize>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct OutputIndexSerializedRef<'a> {
+    format_version: u32,
+    tests: &'a [OutputIndexTest],
+    terms: &'a BTreeMap<String, Vec<usize>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use camino_tempfile::tempdir;
+
+    fn ids(tests: &[&OutputIndexTest]) -> Vec<String> {
+        tests.iter().map(|test| test.id().to_string()).collect()
+    }
+
+    fn build_index() -> OutputIndex {
+        let binary_id = RustBinaryId::new("my-crate");
+        let mut builder = OutputIndexBuilder::new();
+        builder.add(
+            &binary_id,
+            "tests::connect",
+            "output/my-crate/tests%3A%3Aconnect/1.stdout".into(),
+            b"running 1 test\n",
+        );
+        builder.add(
+            &binary_id,
+            "tests::connect",
+            "output/my-crate/tests%3A%3Aconnect/1.stderr".into(),
+            b"thread 'tests::connect' panicked at src/lib.rs:10:5:\nConnection refused\n",
+        );
+        builder.add(
+            &binary_id,
+            "tests::listen",
+            "output/my-crate/tests%3A%3Alisten/1.output".into(),
+            b"listening on port 8080; connection accepted\n",
+        );
+        builder.finish()
+    }
+
+    #[test]
+    fn test_terms() {
+        assert_eq!(
+            terms("thread 'main' panicked at src/lib.rs:10:5").collect::<Vec<_>>(),
+            ["thread", "main", "panicked", "at", "src", "lib", "rs", "10", "5"],
+        );
+        assert_eq!(
+            terms("Ünïcode  SNAKE_case\n").collect::<Vec<_>>(),
+            ["ünïcode", "snake_case"],
+        );
+        let long = "a".repeat(OutputIndex::MAX_TERM_LEN + 1);
+        assert_eq!(
+            terms(&format!("{long} short")).collect::<Vec<_>>(),
+            ["short"]
+        );
+    }
+
+    #[test]
+    fn test_query() {
+        let index = build_index();
+        assert_eq!(
+            index.tests()[0].files,
+            [
+                "output/my-crate/tests%3A%3Aconnect/1.stdout",
+                "output/my-crate/tests%3A%3Aconnect/1.stderr"
+            ],
+        );
+
+        assert_eq!(ids(&index.query("panicked")), ["my-crate tests::connect"]);
+        assert_eq!(
+            ids(&index.query("CONNECTION")),
+            ["my-crate tests::connect", "my-crate tests::listen"],
+        );
+        assert_eq!(
+            ids(&index.query("connection refused")),
+            ["my-crate tests::connect"],
+        );
+        assert_eq!(ids(&index.query("8080")), ["my-crate tests::listen"]);
+        assert_eq!(ids(&index.query("missing")), Vec::<String>::new());
+        assert_eq!(
+            ids(&index.query("connection missing")),
+            Vec::<String>::new()
+        );
+        assert_eq!(ids(&index.query("  ")), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_read_write() {
+        let dir = tempdir().unwrap();
+        let index = build_index();
+        let path = index.write_to_run_dir(dir.path()).unwrap();
+        assert_eq!(path, dir.path().join(OutputIndex::FILE_NAME));
+        assert_eq!(OutputIndex::read_from_run_dir(dir.path()).unwrap(), index);
+
+        std::fs::write(&path, r#"{"format-version": 2, "tests": [], "terms": {}}"#).unwrap();
+        assert!(matches!(
+            OutputIndex::read_from_run_dir(dir.path()),
+            Err(OutputIndexError::UnsupportedVersion { version: 2, .. })
+        ));
+
+        std::fs::write(
+            &path,
+            r#"{"format-version": 1, "tests": [], "terms": {"foo": [0]}}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            OutputIndex::read_from_run_dir(dir.path()),
+            Err(OutputIndexError::InvalidTerm { term, .. }) if term == "foo"
+        ));
+    }
+}
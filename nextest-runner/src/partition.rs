@@ -3,19 +3,21 @@
 
 //! Support for partitioning test runs across several machines.
 //!
-//! At the moment this only supports simple hash-based and count-based sharding. In the future it
-//! could potentially be made smarter: e.g. using data to pick different sets of binaries and tests
-//! to run, with an aim to minimize total build and test times.
+//! This supports simple hash-based and count-based sharding, as well as timings-based sharding that
+//! uses recorded test durations to balance the time taken by each shard.
 
 use crate::errors::PartitionerBuilderParseError;
-use std::{fmt, str::FromStr};
+use nextest_metadata::RustBinaryId;
+use serde::{Deserialize, Serialize};
+use std::{cmp::Reverse, fmt, str::FromStr, time::Duration};
 use xxhash_rust::xxh64::xxh64;
 
 /// A builder for creating `Partitioner` instances.
 ///
 /// The relationship between `PartitionerBuilder` and `Partitioner` is similar to that between
 /// `std`'s `BuildHasher` and `Hasher`.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
 #[non_exhaustive]
 pub enum PartitionerBuilder {
     /// Partition based on counting test numbers.
@@ -24,6 +26,7 @@ pub enum PartitionerBuilder {
         shard: u64,
 
         /// The total number of shards.
+        #[serde(rename = "total-shards")]
         total_shards: u64,
     },
 
@@ -33,6 +36,20 @@ pub enum PartitionerBuilder {
         shard: u64,
 
         /// The total number of shards.
+        #[serde(rename = "total-shards")]
+        total_shards: u64,
+    },
+
+    /// Partition based on recorded test durations.
+    ///
+    /// Unlike the other kinds of partitioning, this is applied across all test binaries at once,
+    /// after the whole list of tests is known: see [`TimingsPartition`].
+    Timings {
+        /// The shard this is in, counting up from 1.
+        shard: u64,
+
+        /// The total number of shards.
+        #[serde(rename = "total-shards")]
         total_shards: u64,
     },
 }
@@ -56,6 +73,9 @@ impl PartitionerBuilder {
                 shard,
                 total_shards,
             } => Box::new(HashPartitioner::new(*shard, *total_shards)),
+            // Timings-based partitioning needs to see every test, so it can't be done per binary.
+            // All tests are let through here, and shards are assigned later.
+            PartitionerBuilder::Timings { .. } => Box::new(AllPartitioner),
         }
     }
 }
@@ -79,10 +99,19 @@ impl FromStr for PartitionerBuilder {
                 shard,
                 total_shards,
             })
+        } else if let Some(input) = s.strip_prefix("timings:") {
+            let (shard, total_shards) = parse_shards(input, "timings:M/N")?;
+
+            Ok(PartitionerBuilder::Timings {
+                shard,
+                total_shards,
+            })
         } else {
             Err(PartitionerBuilderParseError::new(
                 None,
-                format!("partition input '{s}' must begin with \"hash:\" or \"count:\""),
+                format!(
+                    "partition input '{s}' must begin with \"hash:\", \"count:\" or \"timings:\""
+                ),
             ))
         }
     }
@@ -179,6 +208,91 @@ impl Partitioner for HashPartitioner {
     }
 }
 
+#[derive(Clone, Debug)]
+struct AllPartitioner;
+
+impl Partitioner for AllPartitioner {
+    fn test_matches(&mut self, _test_name: &str) -> bool {
+        true
+    }
+}
+
+/// Assigns tests to shards by their recorded durations, so that each shard takes about as long.
+///
+/// Tests with recorded durations are assigned longest first, each to the shard with the least total
+/// duration so far. Tests without recorded durations are then dealt out round-robin.
+///
+/// The assignment only depends on the set of tests and their durations, not on the order they're
+/// added in, so reruns with the same timings produce the same shards.
+#[derive(Clone, Debug)]
+pub struct TimingsPartition<'a> {
+    shard_minus_one: u64,
+    total_shards: u64,
+    timed: Vec<(Duration, &'a RustBinaryId, &'a str)>,
+    untimed: Vec<(&'a RustBinaryId, &'a str)>,
+}
+
+impl<'a> TimingsPartition<'a> {
+    /// Creates a new `TimingsPartition` for the given shard, counting up from 1.
+    pub fn new(shard: u64, total_shards: u64) -> Self {
+        Self {
+            shard_minus_one: shard - 1,
+            total_shards,
+            timed: Vec::new(),
+            untimed: Vec::new(),
+        }
+    }
+
+    /// Adds a test to be partitioned, along with its recorded duration if any.
+    pub fn add_test(
+        &mut self,
+        binary_id: &'a RustBinaryId,
+        test_name: &'a str,
+        duration: Option<Duration>,
+    ) {
+        match duration {
+            Some(duration) => self.timed.push((duration, binary_id, test_name)),
+            None => self.untimed.push((binary_id, test_name)),
+        }
+    }
+
+    /// Returns the tests assigned to this shard, sorted by binary ID and test name.
+    pub fn into_shard(mut self) -> Vec<(&'a RustBinaryId, &'a str)> {
+        let total_shards =
+            usize::try_from(self.total_shards).expect("total shards fits in a usize");
+        let mut loads = vec![Duration::ZERO; total_shards];
+        let mut matches = Vec::new();
+
+        // Longest first, with ties broken by name so that the result is deterministic.
+        self.timed
+            .sort_unstable_by_key(|&(duration, binary_id, test_name)| {
+                (Reverse(duration), binary_id, test_name)
+            });
+        for (duration, binary_id, test_name) in self.timed {
+            // min_by_key returns the first minimum, i.e. the lowest-numbered shard.
+            let (index, load) = loads
+                .iter_mut()
+                .enumerate()
+                .min_by_key(|(_, load)| **load)
+                .expect("there's at least one shard");
+            *load = load.saturating_add(duration);
+            if index as u64 == self.shard_minus_one {
+                matches.push((binary_id, test_name));
+            }
+        }
+
+        self.untimed.sort_unstable();
+        for (index, test) in self.untimed.into_iter().enumerate() {
+            if index as u64 % self.total_shards == self.shard_minus_one {
+                matches.push(test);
+            }
+        }
+
+        matches.sort_unstable();
+        matches
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -207,10 +321,19 @@ mod tests {
                     total_shards: 200,
                 },
             ),
+            (
+                "timings:2/3",
+                PartitionerBuilder::Timings {
+                    shard: 2,
+                    total_shards: 3,
+                },
+            ),
         ];
 
         let failures = vec![
             "foo",
+            "timings:0/2",
+            "timings:1",
             "hash",
             "hash:",
             "hash:1",
@@ -237,4 +360,45 @@ mod tests {
                 .expect_err(&format!("expected input '{input}' to fail"));
         }
     }
+
+    #[test]
+    fn timings_partition() {
+        let binary_a = RustBinaryId::new("a");
+        let binary_b = RustBinaryId::new("b");
+        let tests = [
+            (&binary_a, "slow", Some(Duration::from_secs(10))),
+            (&binary_a, "medium", Some(Duration::from_secs(6))),
+            (&binary_b, "medium", Some(Duration::from_secs(5))),
+            (&binary_b, "fast", Some(Duration::from_secs(1))),
+            (&binary_a, "new_1", None),
+            (&binary_b, "new_2", None),
+            (&binary_a, "new_3", None),
+        ];
+
+        let shard = |shard: u64, tests: &[(&RustBinaryId, &'static str, Option<Duration>)]| {
+            let mut partition = TimingsPartition::new(shard, 2);
+            for &(binary_id, test_name, duration) in tests {
+                partition.add_test(binary_id, test_name, duration);
+            }
+            partition
+                .into_shard()
+                .into_iter()
+                .map(|(binary_id, test_name)| format!("{binary_id}::{test_name}"))
+                .collect::<Vec<_>>()
+        };
+
+        // 10s + 1s on shard 1, 6s + 5s on shard 2. Untimed tests are dealt out round-robin in
+        // sorted order.
+        assert_eq!(
+            shard(1, &tests),
+            ["a::new_1", "a::slow", "b::fast", "b::new_2"]
+        );
+        assert_eq!(shard(2, &tests), ["a::medium", "a::new_3", "b::medium"]);
+
+        // The order in which tests are added doesn't matter.
+        let mut reversed = tests;
+        reversed.reverse();
+        assert_eq!(shard(1, &reversed), shard(1, &tests));
+        assert_eq!(shard(2, &reversed), shard(2, &tests));
+    }
 }
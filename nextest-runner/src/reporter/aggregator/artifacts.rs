@@ -0,0 +1,475 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Writes per-run artifacts into a structured directory layout.
+//!
+//! If `artifacts-dir` is configured, each run gets its own directory named after its run ID:
+//!
+//! ```text
+//! <artifacts-dir>/<run-id>/
+//!   events.ndjson          one event log record per line, written as events happen
+//!   output/<binary-id>/<test-name>/<attempt>.{stdout,stderr,output,error}
+//!   attachments/<binary-id>/<test-name>/<file-name>   copies of each test's attachments, if any
+//!   junit.xml              a copy of the JUnit report, if JUnit is enabled
+//!   output-index.json      a search index over test output, if index-output is enabled
+//!   summary.json           written last, once the run has finished
+//! ```
+//!
+//! Everything but `summary.json` is written as the run progresses, so artifacts from a run that
+//! was interrupted or crashed are kept. The presence of `summary.json` indicates that the run
+//! finished.
+//!
+//! If `output-compression` is set, the event log is compressed as it's written to
+//! `events.ndjson.<ext>.tmp`, then moved to `events.ndjson.<ext>` once the run has finished. The
+//! copy of the JUnit report gets the same extension.
+
+use super::compression::CompressedWriter;
+use crate::{
+    config::OutputCompression,
+    errors::{DisplayErrorChain, WriteEventError},
+    output_index::{OutputIndex, OutputIndexBuilder},
+    reporter::{
+        events::{TestEvent, TestEventKind},
+        structured::event_log::EventLogRecord,
+    },
+    test_output::{ChildExecutionOutput, ChildOutput},
+};
+use atomicwrites::{AtomicFile, OverwriteBehavior};
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::Serialize;
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{self, Write},
+};
+
+/// The version of the `summary.json` format written by this version of nextest.
+const SUMMARY_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub(super) struct RunArtifacts {
+    base_dir: Utf8PathBuf,
+    compression: OutputCompression,
+    index_output: bool,
+    // Set once the run has started.
+    run: Option<RunDir>,
+}
+
+#[derive(Debug)]
+struct RunDir {
+    dir: Utf8PathBuf,
+    profile_name: String,
+    // Taken once the run has finished.
+    events: Option<EventsFile>,
+    // Set if index-output is enabled, and taken once the run has finished.
+    output_index: Option<OutputIndexBuilder>,
+    // Set once the run has finished.
+    summary: Option<RunSummary>,
+}
+
+impl RunArtifacts {
+    pub(super) fn new(
+        base_dir: Utf8PathBuf,
+        compression: OutputCompression,
+        index_output: bool,
+    ) -> Self {
+        Self {
+            base_dir,
+            compression,
+            index_output,
+            run: None,
+        }
+    }
+
+    pub(super) fn write_event(&mut self, event: &TestEvent<'_>) -> Result<(), WriteEventError> {
+        if let TestEventKind::RunStarted {
+            run_id,
+            profile_name,
+            ..
+        } = &event.kind
+        {
+            let dir = self.base_dir.join(run_id.to_string());
+            create_dir_all(&dir)?;
+            let events = EventsFile::create(&dir, self.compression)?;
+            self.run = Some(RunDir {
+                dir,
+                profile_name: profile_name.clone(),
+                events: Some(events),
+                output_index: self.index_output.then(OutputIndexBuilder::new),
+                summary: None,
+            });
+        }
+
+        let Some(run) = &mut self.run else {
+            return Ok(());
+        };
+
+        if let Some(record) = EventLogRecord::from_event(event) {
+            let mut line = serde_json::to_vec(&record)
+                .map_err(|error| WriteEventError::Io(io::Error::from(error)))?;
+            line.push(b'\n');
+            if let Some(events) = &mut run.events {
+                events.write_line(&line)?;
+            }
+        }
+
+        match &event.kind {
+            TestEventKind::TestFinished {
+                test_instance,
+                attachments,
+                run_statuses,
+                ..
+            } => {
+                let binary_id = &test_instance.suite_info.binary_id;
+                // Relative paths always use forward slashes, so that they're the same on every
+                // platform.
+                let rel_test_dir = format!(
+                    "output/{}/{}",
+                    sanitize(binary_id.as_str()),
+                    sanitize(test_instance.name)
+                );
+                create_dir_all(&run.dir.join(&rel_test_dir))?;
+                for status in run_statuses.iter() {
+                    write_output(
+                        &run.dir,
+                        &rel_test_dir,
+                        status.retry_data.attempt,
+                        &status.output,
+                        |rel_path, contents| {
+                            if let Some(output_index) = &mut run.output_index {
+                                output_index.add(binary_id, test_instance.name, rel_path, contents);
+                            }
+                        },
+                    )?;
+                }
+
+                if !attachments.found.is_empty() {
+                    let attachments_dir = run.dir.join(format!(
+                        "attachments/{}/{}",
+                        sanitize(binary_id.as_str()),
+                        sanitize(test_instance.name)
+                    ));
+                    copy_attachments(&attachments_dir, &attachments.found)?;
+                }
+            }
+            TestEventKind::RunFinished {
+                run_id,
+                start_time,
+                elapsed,
+                run_stats,
+                ..
+            } => {
+                let result = run_stats.summarize_final().to_static_str();
+                run.summary = Some(RunSummary {
+                    format_version: SUMMARY_FORMAT_VERSION,
+                    run_id: run_id.to_string(),
+                    profile_name: run.profile_name.clone(),
+                    start_time: start_time.to_rfc3339(),
+                    elapsed_secs: elapsed.as_secs_f64(),
+                    result,
+                    initial_run_count: run_stats.initial_run_count,
+                    finished_count: run_stats.finished_count,
+                    passed: run_stats.passed,
+                    flaky: run_stats.flaky,
+                    failed: run_stats.failed,
+                    timed_out: run_stats.timed_out,
+                    exec_failed: run_stats.exec_failed,
+                    leaky: run_stats.leaky,
+                    skipped: run_stats.skipped,
+                    quarantined_passed: run_stats.quarantined_passed,
+                    quarantined_failed: run_stats.quarantined_failed,
+                });
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Finishes writing artifacts once the run has finished and the JUnit report (if any) has been
+    /// written out.
+    pub(super) fn finish(&mut self, junit_path: Option<&Utf8Path>) -> Result<(), WriteEventError> {
+        let Some(run) = &mut self.run else {
+            return Ok(());
+        };
+        let Some(summary) = run.summary.take() else {
+            return Ok(());
+        };
+
+        if let Some(events) = run.events.take() {
+            events.finish()?;
+        }
+
+        if let Some(junit_path) = junit_path {
+            // The JUnit report is already compressed, if compression is enabled.
+            let dest = self.compression.apply_to_path(&run.dir.join("junit.xml"));
+            std::fs::copy(junit_path, &dest)
+                .map_err(|error| WriteEventError::Fs { file: dest, error })?;
+        }
+
+        if let Some(output_index) = run.output_index.take() {
+            output_index
+                .finish()
+                .write_to_run_dir(&run.dir)
+                .map_err(|error| WriteEventError::Fs {
+                    file: run.dir.join(OutputIndex::FILE_NAME),
+                    error,
+                })?;
+        }
+
+        // The summary is written last (and atomically), so that its presence indicates that all
+        // other artifacts are complete.
+        let path = run.dir.join("summary.json");
+        let file = AtomicFile::new(&path, OverwriteBehavior::AllowOverwrite);
+        file.write(|f| {
+            serde_json::to_writer_pretty(&mut *f, &summary)?;
+            f.write_all(b"\n")
+        })
+        .map_err(|error| WriteEventError::Fs {
+            file: path,
+            error: match error {
+                atomicwrites::Error::Internal(error) | atomicwrites::Error::User(error) => error,
+            },
+        })
+    }
+}
+
+#[derive(Debug)]
+struct EventsFile {
+    // The path the event log ends up at.
+    path: Utf8PathBuf,
+    writer: EventsWriter,
+}
+
+#[derive(Debug)]
+enum EventsWriter {
+    // The file is unbuffered, so each line is written out as soon as the event happens.
+    Plain(File),
+    // Compressed event logs are only valid once the compressor is finished, so they're written to
+    // a temporary path and renamed into place at the end.
+    Compressed {
+        tmp_path: Utf8PathBuf,
+        writer: CompressedWriter<File>,
+    },
+}
+
+impl EventsFile {
+    fn create(dir: &Utf8Path, compression: OutputCompression) -> Result<Self, WriteEventError> {
+        let path = compression.apply_to_path(&dir.join("events.ndjson"));
+        let create = |path: &Utf8Path| {
+            File::create(path).map_err(|error| WriteEventError::Fs {
+                file: path.to_owned(),
+                error,
+            })
+        };
+
+        let writer = match compression {
+            OutputCompression::None => EventsWriter::Plain(create(&path)?),
+            OutputCompression::Gzip | OutputCompression::Zstd => {
+                let tmp_path = Utf8PathBuf::from(format!("{path}.tmp"));
+                let file = create(&tmp_path)?;
+                let writer = CompressedWriter::new(file, compression).map_err(|error| {
+                    WriteEventError::Fs {
+                        file: tmp_path.clone(),
+                        error,
+                    }
+                })?;
+                EventsWriter::Compressed { tmp_path, writer }
+            }
+        };
+        Ok(Self { path, writer })
+    }
+
+    fn write_line(&mut self, line: &[u8]) -> Result<(), WriteEventError> {
+        let (file, res) = match &mut self.writer {
+            EventsWriter::Plain(file_handle) => (&self.path, file_handle.write_all(line)),
+            EventsWriter::Compressed { tmp_path, writer } => (&*tmp_path, writer.write_all(line)),
+        };
+        res.map_err(|error| WriteEventError::Fs {
+            file: file.clone(),
+            error,
+        })
+    }
+
+    fn finish(self) -> Result<(), WriteEventError> {
+        match self.writer {
+            EventsWriter::Plain(_) => Ok(()),
+            EventsWriter::Compressed { tmp_path, writer } => {
+                let fs_error = |error| WriteEventError::Fs {
+                    file: tmp_path.clone(),
+                    error,
+                };
+                let file = writer.finish().map_err(fs_error)?;
+                file.sync_all().map_err(fs_error)?;
+                std::fs::rename(&tmp_path, &self.path).map_err(fs_error)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct RunSummary {
+    format_version: u32,
+    run_id: String,
+    profile_name: String,
+    start_time: String,
+    elapsed_secs: f64,
+    result: &'static str,
+    initial_run_count: usize,
+    finished_count: usize,
+    passed: usize,
+    flaky: usize,
+    failed: usize,
+    timed_out: usize,
+    exec_failed: usize,
+    leaky: usize,
+    skipped: usize,
+    quarantined_passed: usize,
+    quarantined_failed: usize,
+}
+
+/// Writes the output of an attempt to `run_dir/rel_test_dir`, calling `on_write` with the path
+/// relative to `run_dir` and the contents of each file written.
+fn write_output(
+    run_dir: &Utf8Path,
+    rel_test_dir: &str,
+    attempt: usize,
+    output: &ChildExecutionOutput,
+    mut on_write: impl FnMut(Utf8PathBuf, &[u8]),
+) -> Result<(), WriteEventError> {
+    let mut write = |extension: &str, contents: &[u8]| {
+        let rel_path = Utf8PathBuf::from(format!("{rel_test_dir}/{attempt}.{extension}"));
+        let path = run_dir.join(&rel_path);
+        std::fs::write(&path, contents)
+            .map_err(|error| WriteEventError::Fs { file: path, error })?;
+        on_write(rel_path, contents);
+        Ok(())
+    };
+
+    match output {
+        ChildExecutionOutput::Output { output, .. } => match output {
+            ChildOutput::Split(split) => {
+                if let Some(stdout) = &split.stdout {
+                    write("stdout", &stdout.buf)?;
+                }
+                if let Some(stderr) = &split.stderr {
+                    write("stderr", &stderr.buf)?;
+                }
+            }
+            ChildOutput::Combined { output } => write("output", &output.buf)?,
+        },
+        ChildExecutionOutput::StartError(error) => {
+            write(
+                "error",
+                DisplayErrorChain::new(error).to_string().as_bytes(),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Copies attachments into `dir`, keeping their file names.
+///
+/// If more than one attachment has the same file name, all but the first are prefixed with their
+/// index in `attachments`.
+fn copy_attachments(dir: &Utf8Path, attachments: &[Utf8PathBuf]) -> Result<(), WriteEventError> {
+    create_dir_all(dir)?;
+    let mut file_names = HashSet::new();
+    for (index, path) in attachments.iter().enumerate() {
+        let file_name = path.file_name().unwrap_or("attachment");
+        let dest = if file_names.insert(file_name) {
+            dir.join(file_name)
+        } else {
+            dir.join(format!("{index}-{file_name}"))
+        };
+        std::fs::copy(path, &dest).map_err(|error| WriteEventError::Fs { file: dest, error })?;
+    }
+    Ok(())
+}
+
+fn create_dir_all(dir: &Utf8Path) -> Result<(), WriteEventError> {
+    std::fs::create_dir_all(dir).map_err(|error| WriteEventError::Fs {
+        file: dir.to_owned(),
+        error,
+    })
+}
+
+/// Makes a binary ID or test name safe to use as a single path component.
+///
+/// ASCII alphanumerics, `-`, `_` and `.` are kept, and every other byte is percent-encoded as
+/// `%XX`. A leading `.` is also encoded, to avoid special path components like `.` and `..`. Since
+/// `%` is always encoded, this is reversible, so distinct names never map to the same path.
+fn sanitize(name: &str) -> String {
+    // The empty name is the only one that would otherwise map to an empty path component. `%`
+    // alone can't be produced by any other name.
+    if name.is_empty() {
+        return "%".to_owned();
+    }
+
+    let mut sanitized = String::with_capacity(name.len());
+    for (index, byte) in name.bytes().enumerate() {
+        let keep = byte.is_ascii_alphanumeric()
+            || matches!(byte, b'-' | b'_')
+            || (byte == b'.' && index > 0);
+        if keep {
+            sanitized.push(char::from(byte));
+        } else {
+            sanitized.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    sanitized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copy_attachments() {
+        let src = camino_tempfile::tempdir().unwrap();
+        std::fs::create_dir(src.path().join("a")).unwrap();
+        std::fs::create_dir(src.path().join("b")).unwrap();
+        let attachments = [
+            src.path().join("a/screenshot.png"),
+            src.path().join("b/screenshot.png"),
+            src.path().join("test.log"),
+        ];
+        for (index, path) in attachments.iter().enumerate() {
+            std::fs::write(path, index.to_string()).unwrap();
+        }
+
+        let dest = camino_tempfile::tempdir().unwrap();
+        let dir = dest.path().join("attachments/my-binary/my_test");
+        copy_attachments(&dir, &attachments).unwrap();
+
+        let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+        assert_eq!(read("screenshot.png"), "0");
+        assert_eq!(read("1-screenshot.png"), "1");
+        assert_eq!(read("test.log"), "2");
+    }
+
+    #[test]
+    fn test_sanitize() {
+        assert_eq!(
+            sanitize("my-crate::bin/my-bin"),
+            "my-crate%3A%3Abin%2Fmy-bin"
+        );
+        assert_eq!(sanitize("tests::foo_bar.baz"), "tests%3A%3Afoo_bar.baz");
+        assert_eq!(sanitize("with space"), "with%20space");
+        assert_eq!(sanitize("100%"), "100%25");
+        assert_eq!(sanitize("caf\u{e9}"), "caf%C3%A9");
+        assert_eq!(sanitize("."), "%2E");
+        assert_eq!(sanitize(".."), "%2E.");
+        assert_eq!(sanitize(""), "%");
+    }
+
+    #[test]
+    fn test_sanitize_no_collisions() {
+        let names = [
+            "a::b", "a__b", "a_3A_3Ab", "a%3A%3Ab", "a/b", "a_b", "a b", "", "%", ".", "%2E", "..",
+        ];
+        let sanitized: HashSet<_> = names.iter().map(|name| sanitize(name)).collect();
+        assert_eq!(sanitized.len(), names.len(), "sanitized: {sanitized:?}");
+    }
+}
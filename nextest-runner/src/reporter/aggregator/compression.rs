@@ -0,0 +1,122 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Streaming compression for outputs written to disk.
+
+use crate::config::OutputCompression;
+use std::io::{self, Write};
+
+/// A writer that compresses data as it's written, according to an [`OutputCompression`].
+pub(super) enum CompressedWriter<W: Write> {
+    None(W),
+    Gzip(flate2::write::GzEncoder<W>),
+    Zstd(zstd::Encoder<'static, W>),
+}
+
+impl<W: Write> CompressedWriter<W> {
+    pub(super) fn new(inner: W, compression: OutputCompression) -> io::Result<Self> {
+        match compression {
+            OutputCompression::None => Ok(Self::None(inner)),
+            OutputCompression::Gzip => Ok(Self::Gzip(flate2::write::GzEncoder::new(
+                inner,
+                flate2::Compression::default(),
+            ))),
+            OutputCompression::Zstd => {
+                // Level 0 means zstd's default level.
+                zstd::Encoder::new(inner, 0).map(Self::Zstd)
+            }
+        }
+    }
+
+    /// Writes out any remaining compressed data, including the trailer, and returns the inner
+    /// writer.
+    ///
+    /// The output isn't valid until this is called.
+    pub(super) fn finish(self) -> io::Result<W> {
+        match self {
+            Self::None(mut inner) => {
+                inner.flush()?;
+                Ok(inner)
+            }
+            Self::Gzip(encoder) => encoder.finish(),
+            Self::Zstd(encoder) => encoder.finish(),
+        }
+    }
+}
+
+impl<W: Write> Write for CompressedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::None(inner) => inner.write(buf),
+            Self::Gzip(encoder) => encoder.write(buf),
+            Self::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::None(inner) => inner.flush(),
+            Self::Gzip(encoder) => encoder.flush(),
+            Self::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+impl<W: Write> std::fmt::Debug for CompressedWriter<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self {
+            Self::None(_) => "None",
+            Self::Gzip(_) => "Gzip",
+            Self::Zstd(_) => "Zstd",
+        };
+        f.debug_struct("CompressedWriter")
+            .field("kind", &kind)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use test_case::test_case;
+
+    #[test_case(OutputCompression::None; "none")]
+    #[test_case(OutputCompression::Gzip; "gzip")]
+    #[test_case(OutputCompression::Zstd; "zstd")]
+    fn compressed_writer_round_trip(compression: OutputCompression) {
+        let input = "{\"type\":\"test-finished\"}\n".repeat(100);
+
+        let mut writer = CompressedWriter::new(Vec::new(), compression).unwrap();
+        // Write in several pieces, as the aggregator would.
+        for line in input.split_inclusive('\n') {
+            writer.write_all(line.as_bytes()).unwrap();
+        }
+        let compressed = writer.finish().unwrap();
+
+        let mut output = String::new();
+        match compression {
+            OutputCompression::None => {
+                output = String::from_utf8(compressed.clone()).unwrap();
+            }
+            OutputCompression::Gzip => {
+                flate2::read::GzDecoder::new(&compressed[..])
+                    .read_to_string(&mut output)
+                    .unwrap();
+            }
+            OutputCompression::Zstd => {
+                zstd::Decoder::new(&compressed[..])
+                    .unwrap()
+                    .read_to_string(&mut output)
+                    .unwrap();
+            }
+        }
+        assert_eq!(output, input);
+        if compression != OutputCompression::None {
+            assert!(
+                compressed.len() < input.len(),
+                "repetitive input is compressed"
+            );
+        }
+    }
+}
@@ -1,31 +1,111 @@
 // Copyright (c) The nextest Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use super::junit::MetadataJunit;
-use crate::{config::EvaluatableProfile, errors::WriteEventError, reporter::events::TestEvent};
+use super::{artifacts::RunArtifacts, junit::MetadataJunit};
+use crate::{
+    config::EvaluatableProfile,
+    errors::WriteEventError,
+    reporter::{
+        events::{ExecutionDescription, ExecutionResult, TestEvent, TestEventKind},
+        logical_suites::LogicalSuiteMembership,
+    },
+    timings::{TestOutcome, TimingsStore},
+};
 use camino::Utf8PathBuf;
+use std::sync::Arc;
 
-#[derive(Clone, Debug)]
-#[expect(dead_code)]
+#[derive(Debug)]
 pub(crate) struct EventAggregator<'cfg> {
     store_dir: Utf8PathBuf,
     // TODO: log information in a JSONable report (converting that to XML later) instead of directly
     // writing it to XML
     junit: Option<MetadataJunit<'cfg>>,
+    // Timings recorded during this run, if record-timings is enabled.
+    timings: Option<TimingsStore>,
+    // Per-run artifacts, if artifacts-dir is configured.
+    artifacts: Option<RunArtifacts>,
 }
 
 impl<'cfg> EventAggregator<'cfg> {
-    pub(crate) fn new(profile: &EvaluatableProfile<'cfg>) -> Self {
+    pub(crate) fn new(
+        profile: &EvaluatableProfile<'cfg>,
+        logical_suites: Arc<LogicalSuiteMembership>,
+    ) -> Self {
+        let compression = profile.output_compression();
         Self {
             store_dir: profile.store_dir().to_owned(),
-            junit: profile.junit().map(MetadataJunit::new),
+            junit: profile
+                .junit()
+                .map(|config| MetadataJunit::new(config, compression, logical_suites)),
+            timings: profile.record_timings().then(TimingsStore::new),
+            artifacts: profile
+                .artifacts_dir()
+                .map(|dir| RunArtifacts::new(dir, compression, profile.index_output())),
         }
     }
 
     pub(crate) fn write_event(&mut self, event: TestEvent<'cfg>) -> Result<(), WriteEventError> {
+        if let Some(timings) = &mut self.timings {
+            match &event.kind {
+                TestEventKind::TestFinished {
+                    test_instance,
+                    run_statuses,
+                    ..
+                } => {
+                    let binary_id = &test_instance.suite_info.binary_id;
+                    let outcome = match run_statuses.describe() {
+                        ExecutionDescription::Success { .. } => TestOutcome::Passed,
+                        ExecutionDescription::Flaky { .. } => TestOutcome::Flaky,
+                        ExecutionDescription::Failure { last_status, .. } => {
+                            if matches!(last_status.result, ExecutionResult::Timeout { .. }) {
+                                TestOutcome::TimedOut
+                            } else {
+                                TestOutcome::Failed
+                            }
+                        }
+                    };
+                    timings.record_outcome(
+                        binary_id,
+                        test_instance.name,
+                        outcome,
+                        run_statuses.len(),
+                    );
+
+                    // Only record timings for passing tests: failures and timeouts aren't
+                    // representative of how long a test usually takes.
+                    let last_status = run_statuses.last_status();
+                    if last_status.result.is_success() {
+                        timings.record(binary_id, test_instance.name, last_status.time_taken);
+                    }
+                }
+                TestEventKind::RunFinished { .. } => {
+                    // Timings from this run are more recent than the ones on disk.
+                    let mut store =
+                        TimingsStore::load(&self.store_dir).map_err(WriteEventError::Timings)?;
+                    store.merge(timings);
+                    store
+                        .save(&self.store_dir)
+                        .map_err(WriteEventError::Timings)?;
+                }
+                _ => {}
+            }
+        }
+
+        let run_finished = matches!(event.kind, TestEventKind::RunFinished { .. });
+        if let Some(artifacts) = &mut self.artifacts {
+            artifacts.write_event(&event)?;
+        }
+
         if let Some(junit) = &mut self.junit {
             junit.write_event(event)?;
         }
+
+        // The artifacts include a copy of the JUnit report, so they're finished after it's written.
+        if run_finished {
+            if let Some(artifacts) = &mut self.artifacts {
+                artifacts.finish(self.junit.as_ref().map(|junit| junit.path()))?;
+            }
+        }
         Ok(())
     }
 }
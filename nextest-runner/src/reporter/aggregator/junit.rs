@@ -3,23 +3,27 @@
 
 //! Code to generate JUnit XML reports from test events.
 
+use super::compression::CompressedWriter;
 use crate::{
-    config::{JunitConfig, ScriptId},
+    config::{JunitConfig, OutputCompression, ScriptId},
     errors::{DisplayErrorChain, WriteEventError},
     list::TestInstanceId,
     reporter::{
         events::{ExecutionDescription, ExecutionResult, TestEvent, TestEventKind, UnitKind},
+        logical_suites::LogicalSuiteMembership,
         UnitErrorDescription,
     },
     test_output::{ChildExecutionOutput, ChildOutput},
 };
+use atomicwrites::{AtomicFile, OverwriteBehavior};
+use camino::{Utf8Path, Utf8PathBuf};
 use debug_ignore::DebugIgnore;
 use indexmap::IndexMap;
 use nextest_metadata::RustBinaryId;
 use quick_junit::{
     NonSuccessKind, Report, TestCase, TestCaseStatus, TestRerun, TestSuite, XmlString,
 };
-use std::{fmt, fs::File};
+use std::{collections::BTreeMap, fmt, io, sync::Arc};
 
 static STDOUT_STDERR_COMBINED: &str = "(stdout and stderr are combined)";
 static STDOUT_NOT_CAPTURED: &str = "(stdout not captured)";
@@ -29,23 +33,48 @@ static PROCESS_FAILED_TO_START: &str = "(process failed to start)";
 #[derive(Clone, Debug)]
 pub(super) struct MetadataJunit<'cfg> {
     config: JunitConfig<'cfg>,
+    compression: OutputCompression,
+    // The path the report is written to, including the extension for the compression, if any.
+    path: Utf8PathBuf,
     test_suites: DebugIgnore<IndexMap<SuiteKey<'cfg>, TestSuite>>,
+    logical_suites: Arc<LogicalSuiteMembership>,
+    // Testsuites for logical suites, keyed by index into `logical_suites.suites()`. These are
+    // written out after the testsuites for binaries.
+    logical_test_suites: DebugIgnore<BTreeMap<usize, TestSuite>>,
 }
 
 impl<'cfg> MetadataJunit<'cfg> {
-    pub(super) fn new(config: JunitConfig<'cfg>) -> Self {
+    pub(super) fn new(
+        config: JunitConfig<'cfg>,
+        compression: OutputCompression,
+        logical_suites: Arc<LogicalSuiteMembership>,
+    ) -> Self {
+        let path = compression.apply_to_path(config.path());
         Self {
             config,
+            compression,
+            path,
             test_suites: DebugIgnore(IndexMap::new()),
+            logical_suites,
+            logical_test_suites: DebugIgnore(BTreeMap::new()),
         }
     }
 
+    pub(super) fn path(&self) -> &Utf8Path {
+        &self.path
+    }
+
     pub(super) fn write_event(&mut self, event: TestEvent<'cfg>) -> Result<(), WriteEventError> {
         match event.kind {
             TestEventKind::RunStarted { .. }
             | TestEventKind::RunPaused { .. }
             | TestEventKind::RunContinued { .. } => {}
             TestEventKind::SetupScriptStarted { .. } | TestEventKind::SetupScriptSlow { .. } => {}
+            TestEventKind::TeardownScriptStarted { .. }
+            | TestEventKind::TeardownScriptSlow { .. }
+            | TestEventKind::TeardownScriptFinished { .. } => {
+                // Teardown scripts don't affect test results, so they aren't part of the report.
+            }
             TestEventKind::SetupScriptFinished {
                 index: _,
                 total: _,
@@ -55,6 +84,7 @@ impl<'cfg> MetadataJunit<'cfg> {
                 junit_store_success_output,
                 junit_store_failure_output,
                 no_capture: _,
+                env_map,
                 run_status,
             } => {
                 let is_success = run_status.result.is_success();
@@ -94,19 +124,29 @@ impl<'cfg> MetadataJunit<'cfg> {
                 test_suite.add_property(("command", command));
                 test_suite.add_property(("args".to_owned(), shell_words::join(args)));
                 // Also add environment variables set by the script.
-                if let Some(env_map) = run_status.env_map {
+                if let Some(env_map) = env_map {
                     for (key, value) in env_map.env_map {
                         test_suite.add_property((format!("output-env:{key}"), value));
                     }
                 }
             }
+            TestEventKind::SetupScriptEnvOverridden { .. } => {
+                // The environment set by each script is recorded in SetupScriptFinished.
+            }
             TestEventKind::InfoStarted { .. }
             | TestEventKind::InfoResponse { .. }
             | TestEventKind::InfoFinished { .. } => {}
             TestEventKind::InputEnter { .. } => {}
+            TestEventKind::PhaseStarted { .. } | TestEventKind::PhaseFinished { .. } => {}
+            TestEventKind::BinaryStarted { .. } | TestEventKind::BinaryFinished { .. } => {
+                // Test suites are created as tests finish.
+            }
             TestEventKind::TestStarted { .. } => {}
             TestEventKind::TestSlow { .. } => {}
+            TestEventKind::TestOutputAfterExit { .. } => {}
+            TestEventKind::TestOutputMismatch { .. } => {}
             TestEventKind::TestAttemptFailedWillRetry { .. }
+            | TestEventKind::TestRetryDelayStarted { .. }
             | TestEventKind::TestRetryStarted { .. } => {
                 // Retries are recorded in TestFinished.
             }
@@ -115,10 +155,11 @@ impl<'cfg> MetadataJunit<'cfg> {
                 run_statuses,
                 junit_store_success_output,
                 junit_store_failure_output,
+                owner,
+                attachments,
+                quarantined,
                 ..
             } => {
-                let testsuite = self.testsuite_for_test(test_instance.id());
-
                 let (mut testcase_status, main_status, reruns) = match run_statuses.describe() {
                     ExecutionDescription::Success { single_status } => {
                         (TestCaseStatus::success(), single_status, &[][..])
@@ -178,7 +219,63 @@ impl<'cfg> MetadataJunit<'cfg> {
                     TestcaseOrRerun::Testcase(&mut testcase),
                 );
 
-                testsuite.add_test_case(testcase);
+                // Record the owner of failing tests, so that failures can be routed to them.
+                if let Some(owner) = owner {
+                    if !is_success {
+                        testcase.add_property(("owner", owner));
+                    }
+                }
+
+                // Quarantined failures don't fail the run, so mark them as such for tools reading
+                // the report.
+                if quarantined {
+                    testcase.add_property(("quarantined", "true"));
+                }
+
+                // Record whether the failure was in a fixture or in the test itself.
+                if let Some(phase) = main_status.failure_phase {
+                    testcase.add_property(("failure-phase", phase.as_str()));
+                }
+
+                // Record the peak memory usage, so that memory regressions can be tracked.
+                if let Some(peak_rss) = main_status.peak_rss {
+                    testcase.add_property(("peak-rss".to_owned(), peak_rss.to_string()));
+                }
+
+                // Record how long the test spent setting up, if it marked the start of its body.
+                if let Some(setup_time) = main_status.setup_time {
+                    testcase.add_property((
+                        "setup-time".to_owned(),
+                        format!("{:.3}", setup_time.as_secs_f64()),
+                    ));
+                }
+
+                // Record how long the test waited for block I/O, if delay accounting is available.
+                if let Some(io_wait) = main_status.io_wait {
+                    testcase.add_property((
+                        "io-wait".to_owned(),
+                        format!("{:.3}", io_wait.as_secs_f64()),
+                    ));
+                }
+
+                // Properties the test attached to itself through its output.
+                if let ChildExecutionOutput::Output { output, .. } = &main_status.output {
+                    for property in output.test_properties() {
+                        testcase.add_property(property);
+                    }
+                }
+
+                add_attachments(&mut testcase, &attachments.found);
+
+                // A test is also reported as part of each logical suite it belongs to.
+                let logical_suites = self.logical_suites.clone();
+                for &index in logical_suites.suites_for(test_instance.id()) {
+                    self.testsuite_for_logical_suite(index)
+                        .add_test_case(testcase.clone());
+                }
+
+                self.testsuite_for_test(test_instance.id())
+                    .add_test_case(testcase);
             }
             TestEventKind::TestSkipped { .. } => {
                 // TODO: report skipped tests? causes issues if we want to aggregate runs across
@@ -205,25 +302,43 @@ impl<'cfg> MetadataJunit<'cfg> {
                     .set_report_uuid(run_id)
                     .set_timestamp(start_time)
                     .set_time(elapsed)
-                    .add_test_suites(self.test_suites.drain(..).map(|(_, testsuite)| testsuite));
+                    .add_test_suites(self.test_suites.drain(..).map(|(_, testsuite)| testsuite))
+                    .add_test_suites(std::mem::take(&mut *self.logical_test_suites).into_values());
 
-                let junit_path = self.config.path();
+                let junit_path = &self.path;
                 let junit_dir = junit_path.parent().expect("junit path must have a parent");
                 std::fs::create_dir_all(junit_dir).map_err(|error| WriteEventError::Fs {
                     file: junit_dir.to_path_buf(),
                     error,
                 })?;
 
-                let f = File::create(junit_path).map_err(|error| WriteEventError::Fs {
-                    file: junit_path.to_path_buf(),
-                    error,
+                // The report is streamed through the compressor (if any) into a temporary file,
+                // which is moved into place once complete.
+                let file = AtomicFile::new(junit_path, OverwriteBehavior::AllowOverwrite);
+                file.write(|f| {
+                    let mut writer =
+                        CompressedWriter::new(f, self.compression).map_err(JunitWriteError::Io)?;
+                    report
+                        .serialize(&mut writer)
+                        .map_err(JunitWriteError::Serialize)?;
+                    writer.finish().map_err(JunitWriteError::Io)?;
+                    Ok(())
+                })
+                .map_err(|error| match error {
+                    atomicwrites::Error::Internal(error)
+                    | atomicwrites::Error::User(JunitWriteError::Io(error)) => {
+                        WriteEventError::Fs {
+                            file: junit_path.clone(),
+                            error,
+                        }
+                    }
+                    atomicwrites::Error::User(JunitWriteError::Serialize(error)) => {
+                        WriteEventError::Junit {
+                            file: junit_path.clone(),
+                            error,
+                        }
+                    }
                 })?;
-                report
-                    .serialize(f)
-                    .map_err(|error| WriteEventError::Junit {
-                        file: junit_path.to_path_buf(),
-                        error,
-                    })?;
             }
         }
 
@@ -237,6 +352,15 @@ impl<'cfg> MetadataJunit<'cfg> {
             .or_insert_with(|| TestSuite::new(key.to_string()))
     }
 
+    fn testsuite_for_logical_suite(&mut self, index: usize) -> &mut TestSuite {
+        let suite = &self.logical_suites.suites()[index];
+        self.logical_test_suites.entry(index).or_insert_with(|| {
+            let mut testsuite = TestSuite::new(format!("@logical-suite:{}", suite.name));
+            testsuite.add_property(("filter", suite.filter.as_str()));
+            testsuite
+        })
+    }
+
     fn testsuite_for_test(&mut self, test_instance: TestInstanceId<'cfg>) -> &mut TestSuite {
         let key = SuiteKey::TestBinary(test_instance.binary_id);
         self.test_suites
@@ -245,6 +369,12 @@ impl<'cfg> MetadataJunit<'cfg> {
     }
 }
 
+/// An error that occurred while writing out a JUnit report.
+enum JunitWriteError {
+    Io(io::Error),
+    Serialize(quick_junit::SerializeError),
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 enum SuiteKey<'cfg> {
     // Each script gets a separate suite, because in the future we'll likely want to set u
@@ -285,9 +415,9 @@ fn non_success_kind_and_type(kind: UnitKind, result: ExecutionResult) -> (NonSuc
             abort_status: None,
             leaked: false,
         } => (NonSuccessKind::Failure, format!("{kind} failure")),
-        ExecutionResult::Timeout => (NonSuccessKind::Failure, format!("{kind} timeout")),
+        ExecutionResult::Timeout { .. } => (NonSuccessKind::Failure, format!("{kind} timeout")),
         ExecutionResult::ExecFail => (NonSuccessKind::Error, "execution failure".to_owned()),
-        ExecutionResult::Leak => (
+        ExecutionResult::Leak { .. } => (
             NonSuccessKind::Error,
             format!("{kind} passed but leaked handles"),
         ),
@@ -396,6 +526,26 @@ fn set_execute_status_props(
     }
 }
 
+/// Adds attachments to a test case, using the `[[ATTACHMENT|path]]` convention in `system-out`
+/// that's understood by Jenkins and GitLab.
+fn add_attachments(testcase: &mut TestCase, attachments: &[Utf8PathBuf]) {
+    if attachments.is_empty() {
+        return;
+    }
+
+    let mut system_out = testcase
+        .system_out
+        .as_deref()
+        .map_or_else(String::new, str::to_owned);
+    for path in attachments {
+        if !system_out.is_empty() && !system_out.ends_with('\n') {
+            system_out.push('\n');
+        }
+        system_out.push_str(&format!("[[ATTACHMENT|{path}]]"));
+    }
+    testcase.set_system_out(system_out);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -656,6 +806,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_add_attachments() {
+        let attachments = [
+            Utf8PathBuf::from("/ws/screenshots/login.png"),
+            Utf8PathBuf::from("/ws/logs/login.log"),
+        ];
+
+        // With no stored output, system-out only has the attachments.
+        let mut testcase = TestCase::new("test", TestCaseStatus::success());
+        add_attachments(&mut testcase, &attachments);
+        assert_eq!(
+            testcase.system_out.as_deref(),
+            Some("[[ATTACHMENT|/ws/screenshots/login.png]]\n[[ATTACHMENT|/ws/logs/login.log]]"),
+        );
+
+        // Attachments are added after any stored output, on their own lines.
+        let mut testcase = TestCase::new("test", TestCaseStatus::success());
+        testcase.set_system_out("stdout");
+        add_attachments(&mut testcase, &attachments[..1]);
+        assert_eq!(
+            testcase.system_out.as_deref(),
+            Some("stdout\n[[ATTACHMENT|/ws/screenshots/login.png]]"),
+        );
+
+        // No attachments means system-out isn't touched.
+        let mut testcase = TestCase::new("test", TestCaseStatus::success());
+        add_attachments(&mut testcase, &[]);
+        assert_eq!(testcase.system_out, None);
+    }
+
     #[derive(Debug)]
     struct ExecuteStatusPropsCase<'a> {
         comment: &'a str,
@@ -3,6 +3,8 @@
 
 //! Aggregates events, storing them in the store directory.
 
+mod artifacts;
+mod compression;
 mod imp;
 mod junit;
 
@@ -1,14 +1,14 @@
 // Copyright (c) The nextest Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-//! Display helpers for durations.
+//! Display helpers for durations and sizes.
 
 use crate::{
     config::CompiledDefaultFilter,
     helpers::plural,
     list::SkipCounts,
     reporter::{
-        events::{CancelReason, FinalRunStats, RunStatsFailureKind},
+        events::{CancelReason, FinalRunStats, RunStats, RunStatsFailureKind},
         helpers::Styles,
     },
 };
@@ -212,11 +212,45 @@ pub(super) fn write_final_warnings(
     Ok(())
 }
 
+/// Writes a warning if any teardown scripts failed.
+///
+/// Teardown scripts don't change the outcome of a run, so this is written out separately from
+/// [`write_final_warnings`].
+pub(super) fn write_teardown_warnings(
+    run_stats: &RunStats,
+    styles: &Styles,
+    writer: &mut dyn Write,
+) -> io::Result<()> {
+    if run_stats.teardown_scripts_failed > 0 {
+        let finished = run_stats.teardown_scripts_finished_count;
+        writeln!(
+            writer,
+            "{}: {}/{} teardown {} failed",
+            "warning".style(styles.skip),
+            run_stats.teardown_scripts_failed.style(styles.count),
+            finished.style(styles.count),
+            if finished == 1 { "script" } else { "scripts" },
+        )?;
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::CompiledDefaultFilterSection;
-    use nextest_filtering::CompiledExpr;
+    use crate::{config::CompiledDefaultFilterSection, helpers::DisplayBytes};
+
+    #[test]
+    fn test_display_bytes() {
+        assert_eq!(DisplayBytes(0).to_string(), "0 B");
+        assert_eq!(DisplayBytes(1023).to_string(), "1023 B");
+        assert_eq!(DisplayBytes(1024).to_string(), "1.0 KiB");
+        assert_eq!(DisplayBytes(12_900_000).to_string(), "12.3 MiB");
+        assert_eq!(DisplayBytes(3 << 30).to_string(), "3.0 GiB");
+        assert_eq!(DisplayBytes(5 << 40).to_string(), "5.0 TiB");
+        assert_eq!(DisplayBytes(2048 << 40).to_string(), "2048.0 TiB");
+    }
 
     #[test]
     fn test_write_skip_counts() {
@@ -327,22 +361,14 @@ mod tests {
     }
 
     fn skip_counts_str(skip_counts: &SkipCounts, override_section: bool) -> String {
+        let mut default_filter = CompiledDefaultFilter::for_default_config();
+        default_filter.profile = "my-profile".to_owned();
+        if override_section {
+            default_filter.section = CompiledDefaultFilterSection::Override(0);
+        }
+
         let mut buf = Vec::new();
-        write_skip_counts(
-            skip_counts,
-            &CompiledDefaultFilter {
-                expr: CompiledExpr::ALL,
-                profile: "my-profile".to_owned(),
-                section: if override_section {
-                    CompiledDefaultFilterSection::Override(0)
-                } else {
-                    CompiledDefaultFilterSection::Profile
-                },
-            },
-            &Styles::default(),
-            &mut buf,
-        )
-        .unwrap();
+        write_skip_counts(skip_counts, &default_filter, &Styles::default(), &mut buf).unwrap();
         String::from_utf8(buf).unwrap()
     }
 
@@ -404,6 +430,34 @@ mod tests {
         assert_eq!(warnings, "");
     }
 
+    #[test]
+    fn test_teardown_warnings() {
+        let warnings = teardown_warnings_for(RunStats::default());
+        assert_eq!(warnings, "");
+
+        let warnings = teardown_warnings_for(RunStats {
+            teardown_scripts_finished_count: 1,
+            teardown_scripts_failed: 1,
+            ..RunStats::default()
+        });
+        assert_eq!(warnings, "warning: 1/1 teardown script failed\n");
+
+        let warnings = teardown_warnings_for(RunStats {
+            teardown_scripts_finished_count: 3,
+            teardown_scripts_passed: 2,
+            teardown_scripts_failed: 1,
+            ..RunStats::default()
+        });
+        assert_eq!(warnings, "warning: 1/3 teardown scripts failed\n");
+    }
+
+    fn teardown_warnings_for(run_stats: RunStats) -> String {
+        let mut buf: Vec<u8> = Vec::new();
+        let styles = Styles::default();
+        write_teardown_warnings(&run_stats, &styles, &mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
     fn final_warnings_for(stats: FinalRunStats, cancel_status: Option<CancelReason>) -> String {
         let mut buf: Vec<u8> = Vec::new();
         let styles = Styles::default();
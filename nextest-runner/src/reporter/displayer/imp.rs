@@ -7,20 +7,23 @@
 
 use super::{
     formatters::{
-        write_final_warnings, write_skip_counts, DisplayBracketedDuration, DisplayDurationBy,
-        DisplaySlowDuration,
+        write_final_warnings, write_skip_counts, write_teardown_warnings, DisplayBracketedDuration,
+        DisplayDurationBy, DisplaySlowDuration,
     },
     progress::{progress_bar_msg, progress_str, write_summary_str, ProgressBarState},
-    unit_output::TestOutputDisplay,
+    unit_output::{AnsiEscapes, TestOutputDisplay},
     ChildOutputSpec, FinalStatusLevel, OutputStoreFinal, StatusLevel, StatusLevels,
     UnitOutputReporter,
 };
 use crate::{
     config::{CompiledDefaultFilter, ScriptId},
     errors::WriteEventError,
-    helpers::{plural, DisplayScriptInstance, DisplayTestInstance},
+    helpers::{plural, DisplayBytes, DisplayScriptInstance, DisplayTestInstance},
     list::{TestInstance, TestInstanceId},
-    reporter::{events::*, helpers::Styles, imp::ReporterStderr},
+    reporter::{
+        events::*, helpers::Styles, imp::ReporterStderr, logical_suites::LogicalSuiteMembership,
+        test_name_groups::TestNameGroups,
+    },
 };
 use debug_ignore::DebugIgnore;
 use indent_write::io::IndentWriter;
@@ -30,6 +33,7 @@ use std::{
     borrow::Cow,
     cmp::Reverse,
     io::{self, BufWriter, Write},
+    sync::Arc,
     time::Duration,
 };
 use swrite::{swrite, SWrite};
@@ -40,8 +44,13 @@ pub(crate) struct DisplayReporterBuilder {
     pub(crate) test_count: usize,
     pub(crate) success_output: Option<TestOutputDisplay>,
     pub(crate) failure_output: Option<TestOutputDisplay>,
+    pub(crate) ansi_escapes: AnsiEscapes,
+    pub(crate) slow_message: Option<String>,
+    pub(crate) logical_suites: Arc<LogicalSuiteMembership>,
+    pub(crate) test_name_groups: TestNameGroups,
     pub(crate) should_colorize: bool,
     pub(crate) no_capture: bool,
+    pub(crate) verbose: bool,
     pub(crate) hide_progress_bar: bool,
 }
 
@@ -118,10 +127,36 @@ impl DisplayReporterBuilder {
                     final_status_level: self.status_levels.final_status_level,
                 },
                 no_capture: self.no_capture,
+                verbose: self.verbose,
                 styles,
                 theme_characters,
                 cancel_status: None,
-                unit_output: UnitOutputReporter::new(force_success_output, force_failure_output),
+                slow_message: self.slow_message,
+                logical_suite_stats: self
+                    .logical_suites
+                    .suites()
+                    .iter()
+                    .map(|suite| RunStats {
+                        initial_run_count: suite.initial_run_count,
+                        ..RunStats::default()
+                    })
+                    .collect(),
+                logical_suites: self.logical_suites,
+                test_name_group_stats: self
+                    .test_name_groups
+                    .groups()
+                    .iter()
+                    .map(|group| RunStats {
+                        initial_run_count: group.initial_run_count,
+                        ..RunStats::default()
+                    })
+                    .collect(),
+                test_name_groups: self.test_name_groups,
+                unit_output: UnitOutputReporter::new(
+                    force_success_output,
+                    force_failure_output,
+                    self.ansi_escapes,
+                ),
                 final_outputs: DebugIgnore(Vec::new()),
             },
             stderr,
@@ -167,6 +202,16 @@ impl<'a> DisplayReporter<'a> {
     pub(crate) fn finish(&mut self) {
         self.stderr.finish_and_clear_bar();
     }
+
+    /// Returns the output this reporter was built with, so that it can be reused.
+    pub(crate) fn into_output(self) -> ReporterStderr<'a> {
+        match self.stderr {
+            ReporterStderrImpl::TerminalWithBar { .. } | ReporterStderrImpl::TerminalWithoutBar => {
+                ReporterStderr::Terminal
+            }
+            ReporterStderrImpl::Buffer(buf) => ReporterStderr::Buffer(buf),
+        }
+    }
 }
 
 enum ReporterStderrImpl<'a> {
@@ -203,6 +248,7 @@ enum FinalOutput {
     Executed {
         run_statuses: ExecutionStatuses,
         display_output: bool,
+        quarantined: bool,
     },
 }
 
@@ -219,9 +265,17 @@ struct DisplayReporterImpl<'a> {
     default_filter: CompiledDefaultFilter,
     status_levels: StatusLevels,
     no_capture: bool,
+    verbose: bool,
     styles: Box<Styles>,
     theme_characters: ThemeCharacters,
     cancel_status: Option<CancelReason>,
+    slow_message: Option<String>,
+    logical_suites: Arc<LogicalSuiteMembership>,
+    // Statistics for each logical suite, in the same order as `logical_suites.suites()`.
+    logical_suite_stats: Vec<RunStats>,
+    test_name_groups: TestNameGroups,
+    // Statistics for each group of tests, in the same order as `test_name_groups.groups()`.
+    test_name_group_stats: Vec<RunStats>,
     unit_output: UnitOutputReporter,
     final_outputs: DebugIgnore<Vec<(TestInstance<'a>, FinalOutput)>>,
 }
@@ -238,6 +292,8 @@ impl<'a> DisplayReporterImpl<'a> {
                 run_id,
                 profile_name,
                 cli_args: _,
+                filter: _,
+                shuffle_seed,
             } => {
                 writeln!(writer, "{}", self.theme_characters.hbar(12))?;
                 write!(writer, "{:>12} ", "Nextest run".style(self.styles.pass))?;
@@ -270,6 +326,15 @@ impl<'a> DisplayReporterImpl<'a> {
                 )?;
 
                 writeln!(writer)?;
+
+                if let Some(seed) = shuffle_seed {
+                    writeln!(
+                        writer,
+                        "{:>12} test order with seed {} (rerun with --shuffle={seed} to reproduce)",
+                        "Shuffling".style(self.styles.pass),
+                        seed.style(count_style),
+                    )?;
+                }
             }
             TestEventKind::SetupScriptStarted {
                 index,
@@ -294,6 +359,7 @@ impl<'a> DisplayReporterImpl<'a> {
                 args,
                 elapsed,
                 will_terminate,
+                ..
             } => {
                 if !*will_terminate && self.status_levels.status_level >= StatusLevel::Slow {
                     write!(writer, "{:>12} ", "SETUP SLOW".style(self.styles.skip))?;
@@ -315,7 +381,9 @@ impl<'a> DisplayReporterImpl<'a> {
                 run_status,
                 ..
             } => {
-                self.write_setup_script_status_line(script_id, command, args, run_status, writer)?;
+                self.write_script_status_line(
+                    "SETUP", script_id, command, args, run_status, writer,
+                )?;
                 // Always display failing setup script output if it exists. We may change this in
                 // the future.
                 if !run_status.result.is_success() {
@@ -324,6 +392,127 @@ impl<'a> DisplayReporterImpl<'a> {
                     )?;
                 }
             }
+            TestEventKind::SetupScriptEnvOverridden {
+                script_id,
+                overrides,
+            } => {
+                for SetupScriptEnvOverride {
+                    key,
+                    previous_script_id,
+                } in overrides
+                {
+                    writeln!(
+                        writer,
+                        "{}: setup script {} overrides {}, also set by setup script {}",
+                        "warning".style(self.styles.skip),
+                        script_id.style(self.styles.script_id),
+                        key.style(self.styles.count),
+                        previous_script_id.style(self.styles.script_id),
+                    )?;
+                }
+            }
+            TestEventKind::TeardownScriptStarted {
+                index,
+                total,
+                script_id,
+                command,
+                args,
+                ..
+            } => {
+                writeln!(
+                    writer,
+                    "{:>12} [{:>9}] {}",
+                    "TEARDOWN".style(self.styles.pass),
+                    // index + 1 so that it displays as e.g. "1/2" and "2/2".
+                    format!("{}/{}", index + 1, total),
+                    self.display_script_instance(script_id.clone(), command, args)
+                )?;
+            }
+            TestEventKind::TeardownScriptSlow {
+                script_id,
+                command,
+                args,
+                elapsed,
+                will_terminate,
+                ..
+            } => {
+                if !*will_terminate && self.status_levels.status_level >= StatusLevel::Slow {
+                    write!(writer, "{:>12} ", "TEARDOWN SLOW".style(self.styles.skip))?;
+                } else if *will_terminate {
+                    write!(writer, "{:>12} ", "TERMINATING".style(self.styles.fail))?;
+                }
+
+                writeln!(
+                    writer,
+                    "{}{}",
+                    DisplaySlowDuration(*elapsed),
+                    self.display_script_instance(script_id.clone(), command, args)
+                )?;
+            }
+            TestEventKind::TeardownScriptFinished {
+                script_id,
+                command,
+                args,
+                run_status,
+                ..
+            } => {
+                self.write_script_status_line(
+                    "TEARDOWN", script_id, command, args, run_status, writer,
+                )?;
+                // As with setup scripts, always display failing teardown script output.
+                if !run_status.result.is_success() {
+                    self.write_setup_script_execute_status(
+                        script_id, command, args, run_status, writer,
+                    )?;
+                }
+            }
+            TestEventKind::PhaseStarted { phase, run_count } => {
+                writeln!(
+                    writer,
+                    "{:>12} {}: starting {} {}",
+                    "Phase".style(self.styles.pass),
+                    phase.to_static_str().style(self.styles.count),
+                    run_count.style(self.styles.count),
+                    plural::tests_str(*run_count),
+                )?;
+            }
+            TestEventKind::PhaseFinished {
+                phase,
+                elapsed,
+                run_stats,
+            } => {
+                let phase_style = match run_stats.summarize_final() {
+                    FinalRunStats::Success => self.styles.pass,
+                    FinalRunStats::NoTestsRun => self.styles.skip,
+                    FinalRunStats::Failed(_) | FinalRunStats::Cancelled(_) => self.styles.fail,
+                };
+                write!(
+                    writer,
+                    "{:>12} {} [{:>8.3?}s] {}",
+                    "Phase".style(phase_style),
+                    phase.to_static_str().style(self.styles.count),
+                    elapsed.as_secs_f64(),
+                    run_stats.finished_count.style(self.styles.count),
+                )?;
+                if run_stats.finished_count != run_stats.initial_run_count {
+                    write!(
+                        writer,
+                        "/{}",
+                        run_stats.initial_run_count.style(self.styles.count)
+                    )?;
+                }
+
+                let tests_str = plural::tests_plural_if(
+                    run_stats.initial_run_count != 1 || run_stats.finished_count != 1,
+                );
+                let mut summary_str = String::new();
+                write_summary_str(run_stats, &self.styles, &mut summary_str);
+                writeln!(writer, " {tests_str} run: {summary_str}")?;
+            }
+            TestEventKind::BinaryStarted { .. } | TestEventKind::BinaryFinished { .. } => {
+                // Tests from different binaries are interleaved, so per-binary progress isn't
+                // shown in human-readable output.
+            }
             TestEventKind::TestStarted { test_instance, .. } => {
                 // In no-capture mode, print out a test start event.
                 if self.no_capture {
@@ -341,6 +530,7 @@ impl<'a> DisplayReporterImpl<'a> {
                 retry_data,
                 elapsed,
                 will_terminate,
+                ..
             } => {
                 if !*will_terminate && self.status_levels.status_level >= StatusLevel::Slow {
                     if retry_data.total_attempts > 1 {
@@ -377,6 +567,24 @@ impl<'a> DisplayReporterImpl<'a> {
                     DisplaySlowDuration(*elapsed),
                     self.display_test_instance(test_instance.id())
                 )?;
+                if let Some(message) = &self.slow_message {
+                    // The spacing is to align the message with the test instance.
+                    writeln!(writer, "{:>12}             {}", "", message)?;
+                }
+            }
+
+            TestEventKind::TestOutputAfterExit {
+                test_instance,
+                bytes,
+                ..
+            } => {
+                writeln!(
+                    writer,
+                    "{}: {} wrote {} of output after exiting (it may have left processes running)",
+                    "warning".style(self.styles.skip),
+                    self.display_test_instance(test_instance.id()),
+                    DisplayBytes(*bytes).style(self.styles.count),
+                )?;
             }
 
             TestEventKind::TestAttemptFailedWillRetry {
@@ -433,18 +641,29 @@ impl<'a> DisplayReporterImpl<'a> {
                             DisplayDurationBy(*delay_before_next_attempt)
                         )?;
 
-                        // Print the name of the test.
-                        writeln!(writer, "{}", self.display_test_instance(test_instance.id()))?;
+                        // Print the name of the test, followed by the backoff strategy.
+                        write!(writer, "{}", self.display_test_instance(test_instance.id()))?;
+                        let retry_data = &run_status.retry_data;
+                        if retry_data.jitter {
+                            writeln!(writer, " ({}, jittered)", retry_data.backoff)?;
+                        } else {
+                            writeln!(writer, " ({})", retry_data.backoff)?;
+                        }
                     }
                 }
             }
+            TestEventKind::TestRetryDelayStarted { .. } => {
+                // The delay is shown as part of TestAttemptFailedWillRetry.
+            }
             TestEventKind::TestRetryStarted {
                 test_instance,
                 retry_data:
                     RetryData {
                         attempt,
                         total_attempts,
+                        ..
                     },
+                ..
             } => {
                 let retry_string = format!("RETRY {attempt}/{total_attempts}");
                 write!(writer, "{:>12} ", retry_string.style(self.styles.retry))?;
@@ -457,13 +676,47 @@ impl<'a> DisplayReporterImpl<'a> {
                     self.display_test_instance(test_instance.id())
                 )?;
             }
+            TestEventKind::TestOutputMismatch {
+                test_instance,
+                mismatch,
+                rerun_status,
+            } => {
+                if self.status_levels.status_level >= StatusLevel::Retry {
+                    write!(
+                        writer,
+                        "{:>12} {}",
+                        "MISMATCH".style(self.styles.retry),
+                        DisplayBracketedDuration(rerun_status.time_taken),
+                    )?;
+                    writeln!(
+                        writer,
+                        "{} ({} differs from first run)",
+                        self.display_test_instance(test_instance.id()),
+                        mismatch.description(),
+                    )?;
+                }
+            }
             TestEventKind::TestFinished {
                 test_instance,
                 success_output,
                 failure_output,
+                attachments,
+                quarantined,
                 run_statuses,
                 ..
             } => {
+                let on_test_finished = if *quarantined {
+                    RunStats::on_quarantined_test_finished
+                } else {
+                    RunStats::on_test_finished
+                };
+                for &index in self.logical_suites.suites_for(test_instance.id()) {
+                    on_test_finished(&mut self.logical_suite_stats[index], run_statuses);
+                }
+                if let Some(index) = self.test_name_groups.group_for(test_instance.id()) {
+                    on_test_finished(&mut self.test_name_group_stats[index], run_statuses);
+                }
+
                 let describe = run_statuses.describe();
                 let last_status = run_statuses.last_status();
                 let test_output_display = match last_status.result.is_success() {
@@ -473,17 +726,31 @@ impl<'a> DisplayReporterImpl<'a> {
 
                 let output_on_test_finished = self.status_levels.compute_output_on_test_finished(
                     test_output_display,
-                    self.cancel_status,
+                    self.cancel_status.clone(),
                     describe.status_level(),
                     describe.final_status_level(),
                 );
 
                 if output_on_test_finished.write_status_line {
-                    self.write_status_line(*test_instance, describe, writer)?;
+                    self.write_status_line(*test_instance, describe, *quarantined, writer)?;
+                    if let Some(verification) = run_statuses.verification() {
+                        self.write_verification_line(*test_instance, verification, writer)?;
+                    }
                 }
                 if output_on_test_finished.show_immediate {
                     self.write_test_execute_status(test_instance, last_status, false, writer)?;
                 }
+                // Missing attachments are always reported, since they're likely to indicate a
+                // misconfiguration.
+                for path in &attachments.missing {
+                    writeln!(
+                        writer,
+                        "{}: attachment {} for {} not found",
+                        "warning".style(self.styles.skip),
+                        path.style(self.styles.count),
+                        self.display_test_instance(test_instance.id()),
+                    )?;
+                }
                 if let OutputStoreFinal::Yes { display_output } =
                     output_on_test_finished.store_final
                 {
@@ -492,6 +759,7 @@ impl<'a> DisplayReporterImpl<'a> {
                         FinalOutput::Executed {
                             run_statuses: run_statuses.clone(),
                             display_output,
+                            quarantined: *quarantined,
                         },
                     ));
                 }
@@ -500,6 +768,13 @@ impl<'a> DisplayReporterImpl<'a> {
                 test_instance,
                 reason,
             } => {
+                for &index in self.logical_suites.suites_for(test_instance.id()) {
+                    self.logical_suite_stats[index].skipped += 1;
+                }
+                if let Some(index) = self.test_name_groups.group_for(test_instance.id()) {
+                    self.test_name_group_stats[index].skipped += 1;
+                }
+
                 if self.status_levels.status_level >= StatusLevel::Skip {
                     self.write_skip_line(test_instance.id(), writer)?;
                 }
@@ -512,8 +787,10 @@ impl<'a> DisplayReporterImpl<'a> {
                 setup_scripts_running,
                 running,
                 reason,
+                grace_deadline,
+                ..
             } => {
-                self.cancel_status = self.cancel_status.max(Some(*reason));
+                self.cancel_status = self.cancel_status.take().max(Some(reason.clone()));
 
                 write!(
                     writer,
@@ -538,6 +815,14 @@ impl<'a> DisplayReporterImpl<'a> {
                         running.style(self.styles.count),
                     )?;
                 }
+                if let Some(grace_deadline) = grace_deadline {
+                    let grace = grace_deadline.saturating_sub(event.elapsed);
+                    write!(
+                        writer,
+                        ", waiting up to {:.3?}s before terminating",
+                        grace.as_secs_f64(),
+                    )?;
+                }
                 writeln!(writer)?;
             }
             TestEventKind::RunBeginKill {
@@ -545,7 +830,7 @@ impl<'a> DisplayReporterImpl<'a> {
                 running,
                 reason,
             } => {
-                self.cancel_status = self.cancel_status.max(Some(*reason));
+                self.cancel_status = self.cancel_status.take().max(Some(reason.clone()));
 
                 write!(
                     writer,
@@ -575,6 +860,8 @@ impl<'a> DisplayReporterImpl<'a> {
             TestEventKind::RunPaused {
                 setup_scripts_running,
                 running,
+                not_stopped,
+                ..
             } => {
                 write!(
                     writer,
@@ -600,10 +887,20 @@ impl<'a> DisplayReporterImpl<'a> {
                     )?;
                 }
                 writeln!(writer)?;
+
+                for test_instance in not_stopped {
+                    writeln!(
+                        writer,
+                        "{}: {} did not stop (it may be handling or ignoring SIGTSTP)",
+                        "warning".style(self.styles.skip),
+                        self.display_test_instance(*test_instance),
+                    )?;
+                }
             }
             TestEventKind::RunContinued {
                 setup_scripts_running,
                 running,
+                ..
             } => {
                 write!(
                     writer,
@@ -661,7 +958,7 @@ impl<'a> DisplayReporterImpl<'a> {
             } => {
                 self.write_info_response(*index, *total, response, writer)?;
             }
-            TestEventKind::InfoFinished { missing } => {
+            TestEventKind::InfoFinished { missing, queued } => {
                 let hbar = self.theme_characters.hbar(12);
 
                 if *missing > 0 {
@@ -675,6 +972,10 @@ impl<'a> DisplayReporterImpl<'a> {
                     )?;
                 }
 
+                if queued.count > 0 {
+                    self.write_queued_tests(queued, writer)?;
+                }
+
                 writeln!(writer, "{hbar}")?;
             }
             TestEventKind::InputEnter {
@@ -691,7 +992,7 @@ impl<'a> DisplayReporterImpl<'a> {
                         event.elapsed,
                         current_stats,
                         *running,
-                        *cancel_reason,
+                        cancel_reason.clone(),
                         &self.styles,
                     )
                 )?;
@@ -700,6 +1001,7 @@ impl<'a> DisplayReporterImpl<'a> {
                 start_time: _start_time,
                 elapsed,
                 run_stats,
+                duration_histogram,
                 ..
             } => {
                 let stats_summary = run_stats.summarize_final();
@@ -741,7 +1043,22 @@ impl<'a> DisplayReporterImpl<'a> {
 
                 let mut summary_str = String::new();
                 write_summary_str(run_stats, &self.styles, &mut summary_str);
-                writeln!(writer, " {tests_str} run: {summary_str}")?;
+                write!(writer, " {tests_str} run: {summary_str}")?;
+                if self.verbose && run_stats.finished_count > 0 {
+                    write!(
+                        writer,
+                        " ({} parallelism)",
+                        format!("{:.2}x", run_stats.parallelism_ratio(*elapsed))
+                            .style(self.styles.count)
+                    )?;
+                }
+                writeln!(writer)?;
+
+                if let Some(duration_histogram) = duration_histogram {
+                    self.write_duration_histogram(duration_histogram, writer)?;
+                }
+                self.write_logical_suite_summaries(writer)?;
+                self.write_test_name_group_summaries(writer)?;
 
                 // Don't print out test outputs after Ctrl-C, but *do* print them after SIGTERM or
                 // SIGHUP since those tend to be automated tasks performing kills.
@@ -765,12 +1082,14 @@ impl<'a> DisplayReporterImpl<'a> {
                             FinalOutput::Executed {
                                 run_statuses,
                                 display_output,
+                                quarantined,
                             } => {
                                 let last_status = run_statuses.last_status();
 
                                 self.write_final_status_line(
                                     test_instance.id(),
                                     run_statuses.describe(),
+                                    *quarantined,
                                     writer,
                                 )?;
                                 if *display_output {
@@ -787,7 +1106,13 @@ impl<'a> DisplayReporterImpl<'a> {
                 }
 
                 // Print out warnings at the end, if any.
-                write_final_warnings(stats_summary, self.cancel_status, &self.styles, writer)?;
+                write_final_warnings(
+                    stats_summary,
+                    self.cancel_status.clone(),
+                    &self.styles,
+                    writer,
+                )?;
+                write_teardown_warnings(run_stats, &self.styles, writer)?;
             }
         }
 
@@ -810,8 +1135,10 @@ impl<'a> DisplayReporterImpl<'a> {
         Ok(())
     }
 
-    fn write_setup_script_status_line(
+    // `kind` is "SETUP" or "TEARDOWN".
+    fn write_script_status_line(
         &self,
+        kind: &str,
         script_id: &ScriptId,
         command: &str,
         args: &[String],
@@ -820,17 +1147,25 @@ impl<'a> DisplayReporterImpl<'a> {
     ) -> io::Result<()> {
         match status.result {
             ExecutionResult::Pass => {
-                write!(writer, "{:>12} ", "SETUP PASS".style(self.styles.pass))?;
+                write!(
+                    writer,
+                    "{:>12} ",
+                    format!("{kind} PASS").style(self.styles.pass)
+                )?;
             }
-            ExecutionResult::Leak => {
-                write!(writer, "{:>12} ", "SETUP LEAK".style(self.styles.skip))?;
+            ExecutionResult::Leak { .. } => {
+                write!(
+                    writer,
+                    "{:>12} ",
+                    format!("{kind} LEAK").style(self.styles.skip)
+                )?;
             }
             other => {
                 let status_str = short_status_str(other);
                 write!(
                     writer,
                     "{:>12} ",
-                    format!("SETUP {status_str}").style(self.styles.fail),
+                    format!("{kind} {status_str}").style(self.styles.fail),
                 )?;
             }
         }
@@ -849,12 +1184,16 @@ impl<'a> DisplayReporterImpl<'a> {
         &self,
         test_instance: TestInstance<'a>,
         describe: ExecutionDescription<'_>,
+        quarantined: bool,
         writer: &mut dyn Write,
     ) -> io::Result<()> {
         let last_status = describe.last_status();
         match describe {
+            _ if quarantined => {
+                self.write_quarantined_status(last_status, writer)?;
+            }
             ExecutionDescription::Success { .. } => {
-                if last_status.result == ExecutionResult::Leak {
+                if matches!(last_status.result, ExecutionResult::Leak { .. }) {
                     write!(writer, "{:>12} ", "LEAK".style(self.styles.skip))?;
                 } else {
                     write!(writer, "{:>12} ", "PASS".style(self.styles.pass))?;
@@ -895,6 +1234,13 @@ impl<'a> DisplayReporterImpl<'a> {
             self.display_test_instance(test_instance.id())
         )?;
 
+        write_failure_phase_line(last_status, &self.styles, writer)?;
+        if self.verbose {
+            write_peak_rss_line(last_status, &self.styles, writer)?;
+            write_setup_time_line(last_status, &self.styles, writer)?;
+            write_io_wait_line(last_status, &self.styles, writer)?;
+        }
+
         // On Windows, also print out the exception if available.
         #[cfg(windows)]
         if let ExecutionResult::Fail {
@@ -908,23 +1254,139 @@ impl<'a> DisplayReporterImpl<'a> {
         Ok(())
     }
 
+    fn write_quarantined_status(
+        &self,
+        last_status: &ExecuteStatus,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        // Quarantined failures don't fail the run, but they shouldn't look like passes either, so
+        // use the skip color for them.
+        let style = if last_status.result.is_success() {
+            self.styles.pass
+        } else {
+            self.styles.skip
+        };
+        let status_str = format!("QUAR {}", short_status_str(last_status.result));
+        write!(writer, "{:>12} ", status_str.style(style))
+    }
+
+    fn write_verification_line(
+        &self,
+        test_instance: TestInstance<'a>,
+        verification: &FailureVerification,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        let status = &verification.status;
+        let verify_string = format!("VERIFY {}", short_status_str(status.result));
+        // A failure that reproduces is a real failure, while one that doesn't
+        // is likely flaky.
+        let style = if verification.reproduced() {
+            self.styles.fail
+        } else {
+            self.styles.skip
+        };
+        writeln!(
+            writer,
+            "{:>12} {}{}",
+            verify_string.style(style),
+            DisplayBracketedDuration(status.time_taken),
+            self.display_test_instance(test_instance.id())
+        )
+    }
+
+    fn write_duration_histogram(
+        &self,
+        duration_histogram: &DurationHistogram,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        if duration_histogram.total() == 0 {
+            return Ok(());
+        }
+
+        let counts = duration_histogram.counts();
+        write!(
+            writer,
+            "{:>12} [{}]",
+            "Durations".style(self.styles.count),
+            self.theme_characters.sparkline(counts),
+        )?;
+        let bounds = duration_histogram.bounds();
+        for (index, count) in counts.iter().enumerate() {
+            let separator = if index == 0 { " " } else { ", " };
+            let label = match bounds.get(index) {
+                Some(bound) => format!("<{bound:?}"),
+                None => bounds
+                    .last()
+                    .map_or_else(|| "all".to_owned(), |bound| format!(">={bound:?}")),
+            };
+            write!(
+                writer,
+                "{separator}{label}: {}",
+                count.style(self.styles.count)
+            )?;
+        }
+        writeln!(writer)
+    }
+
+    fn write_logical_suite_summaries(&self, writer: &mut dyn Write) -> io::Result<()> {
+        for (suite, run_stats) in self
+            .logical_suites
+            .suites()
+            .iter()
+            .zip(&self.logical_suite_stats)
+        {
+            let suite_style = match run_stats.summarize_final() {
+                FinalRunStats::Success => self.styles.pass,
+                FinalRunStats::NoTestsRun => self.styles.skip,
+                FinalRunStats::Failed(_) | FinalRunStats::Cancelled(_) => self.styles.fail,
+            };
+            write!(
+                writer,
+                "{:>12} {}: {}",
+                "Suite".style(suite_style),
+                suite.name.style(self.styles.script_id),
+                run_stats.finished_count.style(self.styles.count)
+            )?;
+            if run_stats.finished_count != run_stats.initial_run_count {
+                write!(
+                    writer,
+                    "/{}",
+                    run_stats.initial_run_count.style(self.styles.count)
+                )?;
+            }
+
+            let tests_str = plural::tests_plural_if(
+                run_stats.initial_run_count != 1 || run_stats.finished_count != 1,
+            );
+            let mut summary_str = String::new();
+            write_summary_str(run_stats, &self.styles, &mut summary_str);
+            writeln!(writer, " {tests_str} run: {summary_str}")?;
+        }
+
+        Ok(())
+    }
+
     fn write_final_status_line(
         &self,
         test_instance: TestInstanceId<'a>,
         describe: ExecutionDescription<'_>,
+        quarantined: bool,
         writer: &mut dyn Write,
     ) -> io::Result<()> {
         let last_status = describe.last_status();
         match describe {
+            _ if quarantined => {
+                self.write_quarantined_status(last_status, writer)?;
+            }
             ExecutionDescription::Success { .. } => {
                 match (last_status.is_slow, last_status.result) {
-                    (true, ExecutionResult::Leak) => {
+                    (true, ExecutionResult::Leak { .. }) => {
                         write!(writer, "{:>12} ", "SLOW + LEAK".style(self.styles.skip))?;
                     }
                     (true, _) => {
                         write!(writer, "{:>12} ", "SLOW".style(self.styles.skip))?;
                     }
-                    (false, ExecutionResult::Leak) => {
+                    (false, ExecutionResult::Leak { .. }) => {
                         write!(writer, "{:>12} ", "LEAK".style(self.styles.skip))?;
                     }
                     (false, _) => {
@@ -971,6 +1433,8 @@ impl<'a> DisplayReporterImpl<'a> {
             self.display_test_instance(test_instance),
         )?;
 
+        write_failure_phase_line(last_status, &self.styles, writer)?;
+
         // On Windows, also print out the exception if available.
         #[cfg(windows)]
         if let ExecutionResult::Fail {
@@ -984,6 +1448,44 @@ impl<'a> DisplayReporterImpl<'a> {
         Ok(())
     }
 
+    fn write_test_name_group_summaries(&self, writer: &mut dyn Write) -> io::Result<()> {
+        for (group, run_stats) in self
+            .test_name_groups
+            .groups()
+            .iter()
+            .zip(&self.test_name_group_stats)
+        {
+            let group_style = match run_stats.summarize_final() {
+                FinalRunStats::Success => self.styles.pass,
+                FinalRunStats::NoTestsRun => self.styles.skip,
+                FinalRunStats::Failed(_) | FinalRunStats::Cancelled(_) => self.styles.fail,
+            };
+            write!(
+                writer,
+                "{:>12} {}: {}",
+                "Group".style(group_style),
+                self.display_test_instance(group.id()),
+                run_stats.finished_count.style(self.styles.count)
+            )?;
+            if run_stats.finished_count != run_stats.initial_run_count {
+                write!(
+                    writer,
+                    "/{}",
+                    run_stats.initial_run_count.style(self.styles.count)
+                )?;
+            }
+
+            let cases_str = plural::cases_plural_if(
+                run_stats.initial_run_count != 1 || run_stats.finished_count != 1,
+            );
+            let mut summary_str = String::new();
+            write_summary_str(run_stats, &self.styles, &mut summary_str);
+            writeln!(writer, " {cases_str} run: {summary_str}")?;
+        }
+
+        Ok(())
+    }
+
     fn display_test_instance(&self, instance: TestInstanceId<'a>) -> DisplayTestInstance<'_> {
         DisplayTestInstance::new(instance, &self.styles.list_styles)
     }
@@ -997,6 +1499,33 @@ impl<'a> DisplayReporterImpl<'a> {
         DisplayScriptInstance::new(script_id, command, args, self.styles.script_id)
     }
 
+    fn write_queued_tests(
+        &self,
+        queued: &QueuedTests<'a>,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        writeln!(
+            writer,
+            "{}: {} {} queued, next to start:",
+            "info".style(self.styles.pass),
+            queued.count.style(self.styles.count),
+            plural::tests_str(queued.count),
+        )?;
+        for &test_instance in &queued.next {
+            writeln!(writer, "  - {}", self.display_test_instance(test_instance))?;
+        }
+        let remaining = queued.count.saturating_sub(queued.next.len());
+        if remaining > 0 {
+            writeln!(
+                writer,
+                "  - and {} more",
+                remaining.style(self.styles.count)
+            )?;
+        }
+
+        Ok(())
+    }
+
     fn write_info_response(
         &self,
         index: usize,
@@ -1069,6 +1598,7 @@ impl<'a> DisplayReporterImpl<'a> {
                 test_instance,
                 retry_data,
                 state,
+                cpu_affinity,
                 output,
             }) => {
                 // Write the test name.
@@ -1097,7 +1627,16 @@ impl<'a> DisplayReporterImpl<'a> {
                     &mut writer,
                 )?;
 
-                // Write the output of the test.
+                if let Some(cpu_affinity) = cpu_affinity {
+                    writeln!(
+                        writer,
+                        "{}: pinned to CPUs {}",
+                        "affinity".style(self.styles.count),
+                        cpu_affinity.style(self.styles.count),
+                    )?;
+                }
+
+                // Write the output of the test.
                 if state.has_valid_output() {
                     self.unit_output.write_child_execution_output(
                         &self.styles,
@@ -1338,14 +1877,38 @@ impl<'a> DisplayReporterImpl<'a> {
 
                 write!(writer, "{}", "passed".style(style))
             }
-            Some(ExecutionResult::Leak) => write!(
-                writer,
-                "{}",
-                "passed with leaked handles".style(self.styles.skip)
-            ),
-            Some(ExecutionResult::Timeout) => {
+            Some(ExecutionResult::Leak { details }) => {
+                write!(
+                    writer,
+                    "{}",
+                    "passed with leaked handles".style(self.styles.skip)
+                )?;
+                if let Some(details) = details {
+                    write!(
+                        writer,
+                        " ({} {} with {} {} open)",
+                        details.processes.style(self.styles.count),
+                        plural::processes_str(details.processes),
+                        details.open_fds.style(self.styles.count),
+                        plural::file_descriptors_str(details.open_fds),
+                    )?;
+                }
+                Ok(())
+            }
+            Some(ExecutionResult::Timeout {
+                termination: TimeoutTermination::Graceful,
+            }) => {
                 write!(writer, "{}", "timed out".style(self.styles.fail))
             }
+            Some(ExecutionResult::Timeout {
+                termination: TimeoutTermination::Killed,
+            }) => {
+                write!(
+                    writer,
+                    "{} and was killed",
+                    "timed out".style(self.styles.fail)
+                )
+            }
             Some(ExecutionResult::Fail {
                 abort_status,
                 leaked,
@@ -1611,6 +2174,11 @@ fn status_str(result: ExecutionResult) -> Cow<'static, str> {
             // do for now.
             "ABORT".into()
         }
+        #[cfg(windows)]
+        ExecutionResult::Fail {
+            abort_status: Some(AbortStatus::MemoryLimit),
+            leaked: _,
+        } => "MEMORY LIMIT".into(),
         ExecutionResult::Fail {
             abort_status: None,
             leaked: true,
@@ -1621,8 +2189,8 @@ fn status_str(result: ExecutionResult) -> Cow<'static, str> {
         } => "FAIL".into(),
         ExecutionResult::ExecFail => "XFAIL".into(),
         ExecutionResult::Pass => "PASS".into(),
-        ExecutionResult::Leak => "LEAK".into(),
-        ExecutionResult::Timeout => "TIMEOUT".into(),
+        ExecutionResult::Leak { .. } => "LEAK".into(),
+        ExecutionResult::Timeout { .. } => "TIMEOUT".into(),
     }
 }
 
@@ -1646,15 +2214,89 @@ fn short_status_str(result: ExecutionResult) -> Cow<'static, str> {
             // do for now.
             "ABORT".into()
         }
+        #[cfg(windows)]
+        ExecutionResult::Fail {
+            abort_status: Some(AbortStatus::MemoryLimit),
+            leaked: _,
+        } => "OOM".into(),
         ExecutionResult::Fail {
             abort_status: None,
             leaked: _,
         } => "FAIL".into(),
         ExecutionResult::ExecFail => "XFAIL".into(),
         ExecutionResult::Pass => "PASS".into(),
-        ExecutionResult::Leak => "LEAK".into(),
-        ExecutionResult::Timeout => "TMT".into(),
+        ExecutionResult::Leak { .. } => "LEAK".into(),
+        ExecutionResult::Timeout { .. } => "TMT".into(),
+    }
+}
+
+/// If a failure was classified as being in a fixture, say so.
+fn write_failure_phase_line(
+    status: &ExecuteStatus,
+    styles: &Styles,
+    writer: &mut dyn Write,
+) -> io::Result<()> {
+    if let Some(phase @ (FailurePhase::Setup | FailurePhase::Teardown)) = status.failure_phase {
+        writeln!(
+            writer,
+            "{:>12} {} in fixture {}",
+            "-",
+            "failed".style(styles.fail),
+            phase.style(styles.count),
+        )?;
+    }
+    Ok(())
+}
+
+/// If the peak memory usage of the test is known, print it out.
+fn write_peak_rss_line(
+    status: &ExecuteStatus,
+    styles: &Styles,
+    writer: &mut dyn Write,
+) -> io::Result<()> {
+    if let Some(peak_rss) = status.peak_rss {
+        writeln!(
+            writer,
+            "{:>12} peak memory usage: {}",
+            "-",
+            DisplayBytes(peak_rss).style(styles.count),
+        )?;
     }
+    Ok(())
+}
+
+/// If the test reported when its body started, print out how long setup took.
+fn write_setup_time_line(
+    status: &ExecuteStatus,
+    styles: &Styles,
+    writer: &mut dyn Write,
+) -> io::Result<()> {
+    if let Some(setup_time) = status.setup_time {
+        writeln!(
+            writer,
+            "{:>12} setup time: {} of {}",
+            "-",
+            format_args!("{:.3}s", setup_time.as_secs_f64()).style(styles.count),
+            format_args!("{:.3}s", status.time_taken.as_secs_f64()).style(styles.count),
+        )?;
+    }
+    Ok(())
+}
+
+fn write_io_wait_line(
+    status: &ExecuteStatus,
+    styles: &Styles,
+    writer: &mut dyn Write,
+) -> io::Result<()> {
+    if let Some(io_wait) = status.io_wait {
+        writeln!(
+            writer,
+            "{:>12} I/O wait: {}",
+            "-",
+            format_args!("{:.3}s", io_wait.as_secs_f64()).style(styles.count),
+        )?;
+    }
+    Ok(())
 }
 
 #[cfg(windows)]
@@ -1687,6 +2329,15 @@ fn write_windows_message_line(
                 "job object".style(styles.count),
             )
         }
+        AbortStatus::MemoryLimit => {
+            writeln!(
+                writer,
+                "{:>12} {} {}",
+                "-",
+                "exceeded".style(styles.fail),
+                "memory limit".style(styles.count),
+            )
+        }
     }
 }
 
@@ -1694,6 +2345,8 @@ fn write_windows_message_line(
 struct ThemeCharacters {
     hbar: char,
     progress_chars: &'static str,
+    // Characters for the levels of a sparkline, from empty to full.
+    sparkline_chars: &'static [char],
 }
 
 impl Default for ThemeCharacters {
@@ -1701,6 +2354,7 @@ impl Default for ThemeCharacters {
         Self {
             hbar: '-',
             progress_chars: "=> ",
+            sparkline_chars: &[' ', '.', ':', '-', '=', '+', '*', '#'],
         }
     }
 }
@@ -1710,6 +2364,26 @@ impl ThemeCharacters {
         self.hbar = '─';
         // https://mike42.me/blog/2018-06-make-better-cli-progress-bars-with-unicode-block-characters
         self.progress_chars = "█▉▊▋▌▍▎▏ ";
+        self.sparkline_chars = &[' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    }
+
+    /// Returns a sparkline for `counts`, scaled so that the largest count is a full bar.
+    ///
+    /// Nonzero counts are never shown as empty.
+    fn sparkline(&self, counts: &[usize]) -> String {
+        let max = counts.iter().copied().max().unwrap_or(0);
+        let levels = self.sparkline_chars.len() - 1;
+        counts
+            .iter()
+            .map(|&count| {
+                let level = if max == 0 {
+                    0
+                } else {
+                    (count * levels).div_ceil(max)
+                };
+                self.sparkline_chars[level]
+            })
+            .collect()
     }
 
     fn hbar(&self, width: usize) -> String {
@@ -1717,6 +2391,102 @@ impl ThemeCharacters {
     }
 }
 
+/// Writes a summary of a sequence of runs with different profiles.
+///
+/// This is meant to be written once the last run has finished, after that run's own summary.
+pub fn write_aggregate_summary(
+    aggregate: &AggregateRunStats,
+    should_colorize: bool,
+    output: ReporterStderr<'_>,
+) -> io::Result<()> {
+    let mut styles = Styles::default();
+    if should_colorize {
+        styles.colorize();
+    }
+    let mut theme_characters = ThemeCharacters::default();
+    let mut out = String::new();
+
+    let total = aggregate.total();
+    let summary_style = match aggregate.summarize_final() {
+        FinalRunStats::Success => styles.pass,
+        FinalRunStats::NoTestsRun => styles.skip,
+        FinalRunStats::Failed(_) | FinalRunStats::Cancelled(_) => styles.fail,
+    };
+    match output {
+        ReporterStderr::Terminal => {
+            if supports_unicode::on(supports_unicode::Stream::Stderr) {
+                theme_characters.use_unicode();
+            }
+        }
+        ReporterStderr::Buffer(_) => theme_characters.use_unicode(),
+    }
+
+    swrite!(
+        out,
+        "{}\n{:>12} [{:>8.3?}s] ",
+        theme_characters.hbar(12),
+        "Overall".style(summary_style),
+        aggregate.elapsed().as_secs_f64(),
+    );
+    write_run_count(&total, &styles, &mut out);
+    let profile_count = aggregate.runs().len();
+    swrite!(
+        out,
+        " across {} {}: ",
+        profile_count.style(styles.count),
+        if profile_count == 1 {
+            "profile"
+        } else {
+            "profiles"
+        },
+    );
+    write_summary_str(&total, &styles, &mut out);
+    out.push('\n');
+
+    for run in aggregate.runs() {
+        let style = match run.run_stats.summarize_final() {
+            FinalRunStats::Success => styles.pass,
+            FinalRunStats::NoTestsRun => styles.skip,
+            FinalRunStats::Failed(_) | FinalRunStats::Cancelled(_) => styles.fail,
+        };
+        swrite!(
+            out,
+            "{:>12} {} [{:>8.3?}s] ",
+            "Profile".style(style),
+            run.profile_name.style(styles.script_id),
+            run.elapsed.as_secs_f64(),
+        );
+        write_run_count(&run.run_stats, &styles, &mut out);
+        out.push_str(": ");
+        write_summary_str(&run.run_stats, &styles, &mut out);
+        out.push('\n');
+    }
+
+    match output {
+        ReporterStderr::Terminal => {
+            let mut stderr = io::stderr().lock();
+            stderr.write_all(out.as_bytes())?;
+            stderr.flush()
+        }
+        ReporterStderr::Buffer(buf) => {
+            buf.extend_from_slice(out.as_bytes());
+            Ok(())
+        }
+    }
+}
+
+/// Writes "N tests run", or "N/M tests run" if not all tests finished.
+fn write_run_count(run_stats: &RunStats, styles: &Styles, out: &mut String) {
+    swrite!(out, "{}", run_stats.finished_count.style(styles.count));
+    if run_stats.finished_count != run_stats.initial_run_count {
+        swrite!(out, "/{}", run_stats.initial_run_count.style(styles.count));
+    }
+    // Both initial and finished counts must be 1 for the singular form.
+    let tests_str =
+        plural::tests_plural_if(run_stats.initial_run_count != 1 || run_stats.finished_count != 1);
+    swrite!(out, " {tests_str} run");
+}
+
 fn decimal_char_width(n: usize) -> u32 {
     // checked_ilog10 returns 0 for 1-9, 1 for 10-99, 2 for 100-999, etc. (And
     // None for 0 which we unwrap to the same as 1). Add 1 to it to get the
@@ -1728,6 +2498,7 @@ fn decimal_char_width(n: usize) -> u32 {
 mod tests {
     use super::*;
     use crate::{
+        config::{CpuAffinity, RetryBackoff},
         errors::{ChildError, ChildFdError, ChildStartError, ErrorList},
         reporter::events::UnitTerminateReason,
         test_output::{ChildExecutionOutput, ChildOutput, ChildSplitOutput},
@@ -1735,6 +2506,7 @@ mod tests {
     use bytes::Bytes;
     use chrono::Local;
     use nextest_metadata::RustBinaryId;
+    use quick_junit::ReportUuid;
     use smol_str::SmolStr;
     use std::sync::Arc;
 
@@ -1754,8 +2526,13 @@ mod tests {
             test_count: 0,
             success_output: Some(TestOutputDisplay::Immediate),
             failure_output: Some(TestOutputDisplay::Immediate),
+            ansi_escapes: AnsiEscapes::Auto,
+            slow_message: None,
+            logical_suites: Arc::default(),
+            test_name_groups: TestNameGroups::default(),
             should_colorize: false,
             no_capture: true,
+            verbose: false,
             hide_progress_bar: false,
         };
         let output = ReporterStderr::Buffer(out);
@@ -1780,7 +2557,11 @@ mod tests {
             retry_data: RetryData {
                 attempt: 1,
                 total_attempts: 2,
+                backoff: RetryBackoff::Fixed,
+                jitter: false,
             },
+            test_run_uuid: ReportUuid::nil(),
+            attempt_uuid: ReportUuid::nil(),
             // output is not relevant here.
             output: make_split_output(Some(fail_result), "", ""),
             result: fail_result,
@@ -1788,6 +2569,10 @@ mod tests {
             time_taken: Duration::from_secs(1),
             is_slow: false,
             delay_before_start: Duration::ZERO,
+            failure_phase: None,
+            peak_rss: None,
+            setup_time: None,
+            io_wait: None,
         };
         let fail_describe = ExecutionDescription::Failure {
             first_status: &fail_status,
@@ -1799,7 +2584,11 @@ mod tests {
             retry_data: RetryData {
                 attempt: 2,
                 total_attempts: 2,
+                backoff: RetryBackoff::Fixed,
+                jitter: false,
             },
+            test_run_uuid: ReportUuid::nil(),
+            attempt_uuid: ReportUuid::nil(),
             // output is not relevant here.
             output: make_split_output(Some(fail_result), "", ""),
             result: ExecutionResult::Pass,
@@ -1807,6 +2596,10 @@ mod tests {
             time_taken: Duration::from_secs(2),
             is_slow: false,
             delay_before_start: Duration::ZERO,
+            failure_phase: None,
+            peak_rss: None,
+            setup_time: None,
+            io_wait: None,
         };
 
         // Make an `ExecutionStatuses` with a failure and a success, indicating flakiness.
@@ -1823,6 +2616,7 @@ mod tests {
                     .write_final_status_line(
                         test_instance,
                         fail_describe,
+                        false,
                         reporter.stderr.buf_mut().unwrap(),
                     )
                     .unwrap();
@@ -1832,6 +2626,7 @@ mod tests {
                     .write_final_status_line(
                         test_instance,
                         flaky_describe,
+                        false,
                         reporter.stderr.buf_mut().unwrap(),
                     )
                     .unwrap();
@@ -1845,6 +2640,384 @@ mod tests {
         );
     }
 
+    #[test]
+    fn final_status_line_failure_phase() {
+        let binary_id = RustBinaryId::new("my-binary-id");
+        let test_instance = TestInstanceId {
+            binary_id: &binary_id,
+            test_name: "test1",
+        };
+
+        let fail_result = ExecutionResult::Fail {
+            abort_status: None,
+            leaked: false,
+        };
+        let make_status = |failure_phase| ExecuteStatus {
+            retry_data: RetryData {
+                attempt: 1,
+                total_attempts: 1,
+                backoff: RetryBackoff::Fixed,
+                jitter: false,
+            },
+            test_run_uuid: ReportUuid::nil(),
+            attempt_uuid: ReportUuid::nil(),
+            // output is not relevant here.
+            output: make_split_output(Some(fail_result), "", ""),
+            result: fail_result,
+            start_time: Local::now().into(),
+            time_taken: Duration::from_secs(1),
+            is_slow: false,
+            delay_before_start: Duration::ZERO,
+            failure_phase,
+            peak_rss: None,
+            setup_time: None,
+            io_wait: None,
+        };
+
+        for (failure_phase, expected) in [
+            (None, None),
+            (Some(FailurePhase::Test), None),
+            (Some(FailurePhase::Setup), Some("failed in fixture setup")),
+            (
+                Some(FailurePhase::Teardown),
+                Some("failed in fixture teardown"),
+            ),
+        ] {
+            let status = make_status(failure_phase);
+            let describe = ExecutionDescription::Failure {
+                first_status: &status,
+                last_status: &status,
+                retries: &[],
+            };
+
+            let mut out = Vec::new();
+            with_reporter(
+                |mut reporter| {
+                    reporter
+                        .inner
+                        .write_final_status_line(
+                            test_instance,
+                            describe,
+                            false,
+                            reporter.stderr.buf_mut().unwrap(),
+                        )
+                        .unwrap();
+                },
+                &mut out,
+            );
+
+            let out = String::from_utf8(out).expect("output only consists of UTF-8");
+            let lines: Vec<_> = out.lines().collect();
+            match expected {
+                Some(expected) => {
+                    assert_eq!(lines.len(), 2, "{failure_phase:?}: output: {out}");
+                    assert_eq!(lines[1].trim(), format!("- {expected}"));
+                }
+                None => assert_eq!(lines.len(), 1, "{failure_phase:?}: output: {out}"),
+            }
+        }
+    }
+
+    #[test]
+    fn final_status_line_quarantined() {
+        let binary_id = RustBinaryId::new("my-binary-id");
+        let test_instance = TestInstanceId {
+            binary_id: &binary_id,
+            test_name: "test1",
+        };
+
+        let make_status = |result| ExecuteStatus {
+            retry_data: RetryData {
+                attempt: 1,
+                total_attempts: 1,
+                backoff: RetryBackoff::Fixed,
+                jitter: false,
+            },
+            test_run_uuid: ReportUuid::nil(),
+            attempt_uuid: ReportUuid::nil(),
+            // output is not relevant here.
+            output: make_split_output(Some(result), "", ""),
+            result,
+            start_time: Local::now().into(),
+            time_taken: Duration::from_secs(1),
+            is_slow: false,
+            delay_before_start: Duration::ZERO,
+            failure_phase: None,
+            peak_rss: None,
+            setup_time: None,
+            io_wait: None,
+        };
+
+        let fail_status = make_status(ExecutionResult::Fail {
+            abort_status: None,
+            leaked: false,
+        });
+        let pass_status = make_status(ExecutionResult::Pass);
+        for (describe, expected) in [
+            (
+                ExecutionDescription::Failure {
+                    first_status: &fail_status,
+                    last_status: &fail_status,
+                    retries: &[],
+                },
+                "QUAR FAIL",
+            ),
+            (
+                ExecutionDescription::Success {
+                    single_status: &pass_status,
+                },
+                "QUAR PASS",
+            ),
+        ] {
+            let mut out = Vec::new();
+            with_reporter(
+                |mut reporter| {
+                    reporter
+                        .inner
+                        .write_final_status_line(
+                            test_instance,
+                            describe,
+                            true,
+                            reporter.stderr.buf_mut().unwrap(),
+                        )
+                        .unwrap();
+                },
+                &mut out,
+            );
+
+            let out = String::from_utf8(out).expect("output only consists of UTF-8");
+            assert!(out.trim_start().starts_with(expected), "output: {out}");
+        }
+    }
+
+    #[test]
+    fn peak_rss_line() {
+        let make_status = |peak_rss| ExecuteStatus {
+            retry_data: RetryData {
+                attempt: 1,
+                total_attempts: 1,
+                backoff: RetryBackoff::Fixed,
+                jitter: false,
+            },
+            test_run_uuid: ReportUuid::nil(),
+            attempt_uuid: ReportUuid::nil(),
+            output: make_split_output(Some(ExecutionResult::Pass), "", ""),
+            result: ExecutionResult::Pass,
+            start_time: Local::now().into(),
+            time_taken: Duration::from_secs(1),
+            is_slow: false,
+            delay_before_start: Duration::ZERO,
+            failure_phase: None,
+            peak_rss,
+            setup_time: None,
+            io_wait: None,
+        };
+
+        let mut out = Vec::new();
+        write_peak_rss_line(&make_status(Some(25 << 20)), &Styles::default(), &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "           - peak memory usage: 25.0 MiB\n"
+        );
+
+        let mut out = Vec::new();
+        write_peak_rss_line(&make_status(None), &Styles::default(), &mut out).unwrap();
+        assert!(out.is_empty(), "no line is written if peak RSS is unknown");
+    }
+
+    #[test]
+    fn setup_time_line() {
+        let make_status = |setup_time| ExecuteStatus {
+            retry_data: RetryData {
+                attempt: 1,
+                total_attempts: 1,
+                backoff: RetryBackoff::Fixed,
+                jitter: false,
+            },
+            test_run_uuid: ReportUuid::nil(),
+            attempt_uuid: ReportUuid::nil(),
+            output: make_split_output(Some(ExecutionResult::Pass), "", ""),
+            result: ExecutionResult::Pass,
+            start_time: Local::now().into(),
+            time_taken: Duration::from_millis(1500),
+            is_slow: false,
+            delay_before_start: Duration::ZERO,
+            failure_phase: None,
+            peak_rss: None,
+            setup_time,
+            io_wait: None,
+        };
+
+        let mut out = Vec::new();
+        write_setup_time_line(
+            &make_status(Some(Duration::from_millis(1250))),
+            &Styles::default(),
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "           - setup time: 1.250s of 1.500s\n"
+        );
+
+        let mut out = Vec::new();
+        write_setup_time_line(&make_status(None), &Styles::default(), &mut out).unwrap();
+        assert!(
+            out.is_empty(),
+            "no line is written if the test didn't mark its body"
+        );
+    }
+
+    #[test]
+    fn io_wait_line() {
+        let make_status = |io_wait| ExecuteStatus {
+            retry_data: RetryData {
+                attempt: 1,
+                total_attempts: 1,
+                backoff: RetryBackoff::Fixed,
+                jitter: false,
+            },
+            test_run_uuid: ReportUuid::nil(),
+            attempt_uuid: ReportUuid::nil(),
+            output: make_split_output(Some(ExecutionResult::Pass), "", ""),
+            result: ExecutionResult::Pass,
+            start_time: Local::now().into(),
+            time_taken: Duration::from_millis(1500),
+            is_slow: false,
+            delay_before_start: Duration::ZERO,
+            failure_phase: None,
+            peak_rss: None,
+            setup_time: None,
+            io_wait,
+        };
+
+        let mut out = Vec::new();
+        write_io_wait_line(
+            &make_status(Some(Duration::from_millis(730))),
+            &Styles::default(),
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "           - I/O wait: 0.730s\n"
+        );
+
+        let mut out = Vec::new();
+        write_io_wait_line(&make_status(None), &Styles::default(), &mut out).unwrap();
+        assert!(
+            out.is_empty(),
+            "no line is written if delay accounting isn't available"
+        );
+    }
+
+    #[test]
+    fn test_name_group_summaries() {
+        let normalization = crate::config::TestNameNormalization::new(r"^(.+)\[.*\]$").unwrap();
+        let binary_id = RustBinaryId::new("my-binary-id");
+        let tests = [
+            (&binary_id, "test_bar[a]", true),
+            (&binary_id, "test_foo[case=1]", true),
+            (&binary_id, "test_foo[case=2]", true),
+            (&binary_id, "test_foo[case=3]", true),
+        ];
+
+        let mut out = Vec::new();
+        with_reporter(
+            |mut reporter| {
+                reporter.inner.test_name_groups = TestNameGroups::from_tests(&normalization, tests);
+                reporter.inner.test_name_group_stats = vec![
+                    RunStats {
+                        initial_run_count: 1,
+                        finished_count: 1,
+                        passed: 1,
+                        ..RunStats::default()
+                    },
+                    RunStats {
+                        initial_run_count: 3,
+                        finished_count: 3,
+                        passed: 2,
+                        failed: 1,
+                        ..RunStats::default()
+                    },
+                ];
+                reporter
+                    .inner
+                    .write_test_name_group_summaries(reporter.stderr.buf_mut().unwrap())
+                    .unwrap();
+            },
+            &mut out,
+        );
+
+        assert_eq!(
+            String::from_utf8(out).expect("output only consists of UTF-8"),
+            "       Group my-binary-id test_bar: 1 case run: 1 passed, 0 skipped\n       \
+                    Group my-binary-id test_foo: 3 cases run: 2 passed, 1 failed, 0 skipped\n",
+        );
+    }
+
+    #[test]
+    fn run_finished_parallelism() {
+        let run_stats = RunStats {
+            initial_run_count: 2,
+            finished_count: 2,
+            passed: 2,
+            total_test_time: Duration::from_secs(7),
+            ..RunStats::default()
+        };
+        let run_finished = |verbose: bool| {
+            let mut out = Vec::new();
+            with_reporter(
+                |mut reporter| {
+                    reporter.inner.verbose = verbose;
+                    reporter
+                        .write_event(&TestEvent {
+                            timestamp: Local::now().into(),
+                            elapsed: Duration::ZERO,
+                            kind: TestEventKind::RunFinished {
+                                run_id: ReportUuid::nil(),
+                                start_time: Local::now().into(),
+                                elapsed: Duration::from_secs(2),
+                                run_stats,
+                                package_stats: PackageStats::default(),
+                                duration_histogram: None,
+                                flaky_tests: Default::default(),
+                            },
+                        })
+                        .unwrap();
+                },
+                &mut out,
+            );
+            String::from_utf8(out).expect("output only consists of UTF-8")
+        };
+
+        let summary_line = |output: &str| {
+            output
+                .lines()
+                .find(|line| line.contains("Summary"))
+                .unwrap_or_else(|| panic!("summary line not found in output:\n{output}"))
+                .to_owned()
+        };
+        assert_eq!(
+            summary_line(&run_finished(false)),
+            "     Summary [   2.000s] 2 tests run: 2 passed, 0 skipped",
+        );
+        assert_eq!(
+            summary_line(&run_finished(true)),
+            "     Summary [   2.000s] 2 tests run: 2 passed, 0 skipped (3.50x parallelism)",
+        );
+    }
+
+    #[test]
+    fn sparkline() {
+        let mut theme_characters = ThemeCharacters::default();
+        assert_eq!(theme_characters.sparkline(&[0, 0, 0]), "   ");
+        assert_eq!(theme_characters.sparkline(&[0, 1, 30, 60, 100]), " .-+#");
+
+        theme_characters.use_unicode();
+        assert_eq!(theme_characters.sparkline(&[3, 12, 7, 0, 1]), "▂█▅ ▁");
+    }
+
     // ---
 
     /// Send an information response to the reporter and return the output.
@@ -1878,10 +3051,22 @@ mod tests {
                                 flaky: 2,
                                 failed: 2,
                                 failed_slow: 1,
+                                failed_after_retries: 1,
+                                failed_memory_limit: 0,
+                                iterations_before_first_failure: None,
                                 timed_out: 1,
+                                timed_out_killed: 0,
                                 leaky: 1,
                                 exec_failed: 1,
                                 skipped: 5,
+                                quarantined_passed: 0,
+                                quarantined_failed: 0,
+                                total_test_time: Duration::from_secs(40),
+                                setup_scripts_time: Duration::from_secs(1),
+                                smoke_failed: false,
+                                teardown_scripts_finished_count: 0,
+                                teardown_scripts_passed: 0,
+                                teardown_scripts_failed: 0,
                             },
                         },
                     })
@@ -2062,12 +3247,15 @@ mod tests {
                                 retry_data: RetryData {
                                     attempt: 1,
                                     total_attempts: 1,
+                                    backoff: RetryBackoff::Fixed,
+                                    jitter: false,
                                 },
                                 state: UnitState::Running {
                                     pid: 12345,
                                     time_taken: Duration::from_millis(400),
                                     slow_after: None,
                                 },
+                                cpu_affinity: CpuAffinity::new([0, 1, 2, 4]),
                                 output: make_split_output(None, "abc", "def"),
                             }),
                         },
@@ -2090,6 +3278,8 @@ mod tests {
                                 retry_data: RetryData {
                                     attempt: 2,
                                     total_attempts: 3,
+                                    backoff: RetryBackoff::Fixed,
+                                    jitter: false,
                                 },
                                 state: UnitState::Terminating(UnitTerminatingState {
                                     pid: 12346,
@@ -2099,6 +3289,7 @@ mod tests {
                                     waiting_duration: Duration::from_millis(6789),
                                     remaining: Duration::from_millis(9786),
                                 }),
+                                cpu_affinity: None,
                                 output: make_split_output(None, "abc", "def"),
                             }),
                         },
@@ -2121,6 +3312,8 @@ mod tests {
                                 retry_data: RetryData {
                                     attempt: 2,
                                     total_attempts: 3,
+                                    backoff: RetryBackoff::Fixed,
+                                    jitter: false,
                                 },
                                 state: UnitState::Exiting {
                                     pid: 99999,
@@ -2130,6 +3323,7 @@ mod tests {
                                     waiting_duration: Duration::from_millis(1),
                                     remaining: Duration::from_millis(999),
                                 },
+                                cpu_affinity: None,
                                 output: make_split_output(None, "abc", "def"),
                             }),
                         },
@@ -2152,12 +3346,15 @@ mod tests {
                                 retry_data: RetryData {
                                     attempt: 1,
                                     total_attempts: 5,
+                                    backoff: RetryBackoff::Fixed,
+                                    jitter: false,
                                 },
                                 state: UnitState::Exited {
                                     result: ExecutionResult::Pass,
                                     time_taken: Duration::from_millis(99999),
                                     slow_after: Some(Duration::from_millis(33333)),
                                 },
+                                cpu_affinity: None,
                                 output: make_combined_output_with_errors(
                                     Some(ExecutionResult::Pass),
                                     "abc\ndef\nghi\n",
@@ -2189,6 +3386,8 @@ mod tests {
                                     // case.
                                     attempt: 1,
                                     total_attempts: 5,
+                                    backoff: RetryBackoff::Fixed,
+                                    jitter: false,
                                 },
                                 state: UnitState::DelayBeforeNextAttempt {
                                     previous_result: ExecutionResult::ExecFail,
@@ -2198,6 +3397,7 @@ mod tests {
                                 },
                                 // In reality, the output isn't available at this point,
                                 // and it shouldn't be shown.
+                                cpu_affinity: None,
                                 output: make_combined_output_with_errors(
                                     Some(ExecutionResult::Pass),
                                     "*** THIS OUTPUT SHOULD BE IGNORED",
@@ -2212,11 +3412,66 @@ mod tests {
                     })
                     .unwrap();
 
+                // A test that has exited, leaving processes behind.
                 reporter
                     .write_event(&TestEvent {
                         timestamp: Local::now().into(),
                         elapsed: Duration::ZERO,
-                        kind: TestEventKind::InfoFinished { missing: 2 },
+                        kind: TestEventKind::InfoResponse {
+                            index: 10,
+                            total: 20,
+                            response: InfoResponse::Test(TestInfoResponse {
+                                test_instance: TestInstanceId {
+                                    binary_id: &binary_id,
+                                    test_name: "test5",
+                                },
+                                retry_data: RetryData {
+                                    attempt: 1,
+                                    total_attempts: 1,
+                                    backoff: RetryBackoff::Fixed,
+                                    jitter: false,
+                                },
+                                state: UnitState::Exited {
+                                    result: ExecutionResult::Leak {
+                                        details: Some(LeakDetails {
+                                            processes: 2,
+                                            open_fds: 5,
+                                        }),
+                                    },
+                                    time_taken: Duration::from_millis(4321),
+                                    slow_after: None,
+                                },
+                                cpu_affinity: None,
+                                output: make_split_output(
+                                    Some(ExecutionResult::Leak { details: None }),
+                                    "",
+                                    "",
+                                ),
+                            }),
+                        },
+                    })
+                    .unwrap();
+
+                reporter
+                    .write_event(&TestEvent {
+                        timestamp: Local::now().into(),
+                        elapsed: Duration::ZERO,
+                        kind: TestEventKind::InfoFinished {
+                            missing: 2,
+                            queued: QueuedTests {
+                                count: 7,
+                                next: vec![
+                                    TestInstanceId {
+                                        binary_id: &binary_id,
+                                        test_name: "test6",
+                                    },
+                                    TestInstanceId {
+                                        binary_id: &binary_id,
+                                        test_name: "test7",
+                                    },
+                                ],
+                            },
+                        },
                     })
                     .unwrap();
             },
@@ -2327,6 +3582,7 @@ mod windows_tests {
             to_message_line(AbortStatus::WindowsNtStatus(STATUS_CONTROL_STACK_VIOLATION)),
         );
         insta::assert_snapshot!("job_object", to_message_line(AbortStatus::JobObject));
+        insta::assert_snapshot!("memory_limit", to_message_line(AbortStatus::MemoryLimit));
     }
 
     #[track_caller]
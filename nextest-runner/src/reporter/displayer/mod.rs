@@ -9,6 +9,7 @@ mod progress;
 mod status_level;
 mod unit_output;
 
+pub use imp::write_aggregate_summary;
 pub(crate) use imp::*;
 pub use status_level::*;
 pub use unit_output::*;
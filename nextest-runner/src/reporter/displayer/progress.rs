@@ -65,13 +65,15 @@ impl ProgressBarState {
         let before_should_hide = self.should_hide();
 
         match &event.kind {
-            TestEventKind::SetupScriptStarted { no_capture, .. } => {
+            TestEventKind::SetupScriptStarted { no_capture, .. }
+            | TestEventKind::TeardownScriptStarted { no_capture, .. } => {
                 // Hide the progress bar if either stderr or stdout are being passed through.
                 if *no_capture {
                     self.hidden_no_capture = true;
                 }
             }
-            TestEventKind::SetupScriptFinished { no_capture, .. } => {
+            TestEventKind::SetupScriptFinished { no_capture, .. }
+            | TestEventKind::TeardownScriptFinished { no_capture, .. } => {
                 // Restore the progress bar if it was hidden.
                 if *no_capture {
                     self.hidden_no_capture = false;
@@ -89,8 +91,11 @@ impl ProgressBarState {
                 cancel_state,
                 ..
             } => {
-                self.bar
-                    .set_prefix(progress_bar_prefix(current_stats, *cancel_state, styles));
+                self.bar.set_prefix(progress_bar_prefix(
+                    current_stats,
+                    cancel_state.clone(),
+                    styles,
+                ));
                 self.bar
                     .set_message(progress_bar_msg(current_stats, *running, styles));
                 // If there are skipped tests, the initial run count will be lower than when constructed
@@ -120,7 +125,7 @@ impl ProgressBarState {
             TestEventKind::RunBeginCancel { reason, .. }
             | TestEventKind::RunBeginKill { reason, .. } => {
                 self.bar
-                    .set_prefix(progress_bar_cancel_prefix(*reason, styles));
+                    .set_prefix(progress_bar_cancel_prefix(reason, styles));
             }
             _ => {}
         }
@@ -247,6 +252,25 @@ pub(super) fn write_summary_str(run_stats: &RunStats, styles: &Styles, out: &mut
         );
     }
 
+    let quarantined = run_stats.quarantined_passed + run_stats.quarantined_failed;
+    if quarantined > 0 {
+        swrite!(
+            out,
+            "{} {}",
+            quarantined.style(styles.count),
+            "quarantined".style(styles.skip),
+        );
+        if run_stats.quarantined_failed > 0 {
+            swrite!(
+                out,
+                " ({} {})",
+                run_stats.quarantined_failed.style(styles.count),
+                "failed".style(styles.skip),
+            );
+        }
+        swrite!(out, ", ");
+    }
+
     swrite!(
         out,
         "{} {}",
@@ -255,10 +279,12 @@ pub(super) fn write_summary_str(run_stats: &RunStats, styles: &Styles, out: &mut
     );
 }
 
-fn progress_bar_cancel_prefix(reason: CancelReason, styles: &Styles) -> String {
+fn progress_bar_cancel_prefix(reason: &CancelReason, styles: &Styles) -> String {
     let status = match reason {
         CancelReason::SetupScriptFailure
         | CancelReason::TestFailure
+        | CancelReason::SmokeTestFailure
+        | CancelReason::Custom(_)
         | CancelReason::ReportError
         | CancelReason::Signal
         | CancelReason::Interrupt => "Cancelling",
@@ -272,7 +298,7 @@ fn progress_bar_prefix(
     cancel_reason: Option<CancelReason>,
     styles: &Styles,
 ) -> String {
-    if let Some(reason) = cancel_reason {
+    if let Some(reason) = &cancel_reason {
         return progress_bar_cancel_prefix(reason, styles);
     }
 
@@ -59,6 +59,41 @@ impl TestOutputDisplay {
     }
 }
 
+/// Whether to strip or preserve ANSI escape sequences in captured test output displayed by the
+/// reporter.
+///
+/// This is independent of whether nextest's own output is colorized, and only applies to output
+/// that's displayed: JUnit reports never contain escapes. Escapes are stripped from the complete
+/// captured output rather than from individual reads, so sequences that were split across reads
+/// are handled correctly.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Deserialize)]
+#[cfg_attr(test, derive(test_strategy::Arbitrary))]
+#[serde(rename_all = "kebab-case")]
+pub enum AnsiEscapes {
+    /// Preserve escapes if nextest's output is colorized, and strip them otherwise.
+    ///
+    /// This is the default.
+    #[default]
+    Auto,
+
+    /// Always strip escapes.
+    Strip,
+
+    /// Always preserve escapes.
+    Preserve,
+}
+
+impl AnsiEscapes {
+    /// Returns true if escapes should be stripped, given whether nextest's output is colorized.
+    pub fn should_strip(self, is_colorized: bool) -> bool {
+        match self {
+            AnsiEscapes::Auto => !is_colorized,
+            AnsiEscapes::Strip => true,
+            AnsiEscapes::Preserve => false,
+        }
+    }
+}
+
 /// Formatting options for writing out child process output.
 ///
 /// TODO: should these be lazily generated? Can't imagine this ever being
@@ -76,6 +111,7 @@ pub(super) struct ChildOutputSpec {
 pub(super) struct UnitOutputReporter {
     force_success_output: Option<TestOutputDisplay>,
     force_failure_output: Option<TestOutputDisplay>,
+    ansi_escapes: AnsiEscapes,
     display_empty_outputs: bool,
 }
 
@@ -83,6 +119,7 @@ impl UnitOutputReporter {
     pub(super) fn new(
         force_success_output: Option<TestOutputDisplay>,
         force_failure_output: Option<TestOutputDisplay>,
+        ansi_escapes: AnsiEscapes,
     ) -> Self {
         // Ordinarily, empty stdout and stderr are not displayed. This
         // environment variable is set in integration tests to ensure that they
@@ -93,6 +130,7 @@ impl UnitOutputReporter {
         Self {
             force_success_output,
             force_failure_output,
+            ansi_escapes,
             display_empty_outputs,
         }
     }
@@ -244,18 +282,17 @@ impl UnitOutputReporter {
         description: Option<ByteSubslice<'_>>,
         writer: &mut dyn Write,
     ) -> io::Result<()> {
-        if styles.is_colorized {
-            if let Some(subslice) = description {
-                write_output_with_highlight(&output.buf, subslice, &styles.fail, writer)?;
-            } else {
-                // Output the text without stripping ANSI escapes, then reset the color afterwards
-                // in case the output is malformed.
-                write_output_with_trailing_newline(&output.buf, RESET_COLOR, writer)?;
-            }
-        } else {
-            // Strip ANSI escapes from the output if nextest itself isn't colorized.
+        let strip = self.ansi_escapes.should_strip(styles.is_colorized);
+        if let Some(subslice) = description.filter(|_| styles.is_colorized) {
+            write_output_with_highlight(&output.buf, subslice, &styles.fail, strip, writer)?;
+        } else if strip {
+            // Strip ANSI escapes from the output (by default, if nextest itself isn't colorized).
             let mut no_color = strip_ansi_escapes::Writer::new(writer);
             write_output_with_trailing_newline(&output.buf, b"", &mut no_color)?;
+        } else {
+            // Output the text without stripping ANSI escapes, then reset the color afterwards
+            // in case the output is malformed.
+            write_output_with_trailing_newline(&output.buf, RESET_COLOR, writer)?;
         }
 
         Ok(())
@@ -268,13 +305,18 @@ fn write_output_with_highlight(
     output: &[u8],
     ByteSubslice { slice, start }: ByteSubslice,
     highlight_style: &Style,
+    strip: bool,
     mut writer: &mut dyn Write,
 ) -> io::Result<()> {
     let end = start + highlight_end(slice);
 
-    // Output the start and end of the test without stripping ANSI escapes, then reset
+    // Output the start and end of the test, only stripping ANSI escapes if requested, then reset
     // the color afterwards in case the output is malformed.
-    writer.write_all(&output[..start])?;
+    if strip {
+        writer.write_all(&strip_ansi_escapes::strip(&output[..start]))?;
+    } else {
+        writer.write_all(&output[..start])?;
+    }
     writer.write_all(RESET_COLOR)?;
 
     // Some systems (e.g. GitHub Actions, Buildomat) don't handle multiline ANSI
@@ -299,7 +341,12 @@ fn write_output_with_highlight(
     // `end` is guaranteed to be within the bounds of `output.buf`. (It is actually safe
     // for it to be equal to `output.buf.len()` -- it gets treated as an empty list in
     // that case.)
-    write_output_with_trailing_newline(&output[end..], RESET_COLOR, writer)?;
+    if strip {
+        let rest = strip_ansi_escapes::strip(&output[end..]);
+        write_output_with_trailing_newline(&rest, RESET_COLOR, writer)?;
+    } else {
+        write_output_with_trailing_newline(&output[end..], RESET_COLOR, writer)?;
+    }
 
     Ok(())
 }
@@ -343,6 +390,7 @@ impl fmt::Display for FmtSuffix<'_> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use bytes::Bytes;
 
     #[test]
     fn test_write_output_with_highlight() {
@@ -397,9 +445,68 @@ mod tests {
             output.as_bytes(),
             subslice,
             &Style::new().red().bold(),
+            false,
             &mut buf,
         )
         .unwrap();
         String::from_utf8(buf).unwrap()
     }
+
+    #[test]
+    fn test_write_output_ansi_escapes() {
+        const RESET_COLOR: &str = "\u{1b}[0m";
+        const BOLD_RED: &str = "\u{1b}[31;1m";
+
+        // Simulate output where escape sequences (including a hyperlink with a multi-byte URL)
+        // were split across reads.
+        let chunks: [&[u8]; 4] = [
+            b"\x1b[3",
+            "2mok\x1b[0m caf\u{e9} \x1b]8;;https://\u{4f8b}.jp\x07".as_bytes(),
+            b"link\x1b]8;;\x07\nhigh\x1b[1mlight\x1b",
+            b"[0m\n\x1b[33mrest\x1b[0m",
+        ];
+        let output: ChildSingleOutput = Bytes::from(chunks.concat()).into();
+        let raw = String::from_utf8(output.buf.to_vec()).unwrap();
+        let highlight_start = raw.find("high").unwrap();
+        let highlight_end = raw.find("rest").unwrap() - "\x1b[33m".len();
+        let highlight = ByteSubslice {
+            start: highlight_start,
+            slice: &output.buf[highlight_start..highlight_end],
+        };
+
+        let mut colorized = Styles::default();
+        colorized.colorize();
+        let plain = Styles::default();
+
+        let write = |ansi_escapes, styles: &Styles, highlight| {
+            let reporter = UnitOutputReporter::new(None, None, ansi_escapes);
+            let mut buf = Vec::new();
+            reporter
+                .write_test_single_output_with_description(styles, &output, highlight, &mut buf)
+                .unwrap();
+            String::from_utf8(buf).unwrap()
+        };
+
+        let stripped = "ok caf\u{e9} link\nhighlight\nrest\n";
+        assert_eq!(write(AnsiEscapes::Auto, &plain, None), stripped);
+        assert_eq!(write(AnsiEscapes::Strip, &plain, None), stripped);
+        assert_eq!(write(AnsiEscapes::Strip, &colorized, None), stripped);
+        // Passing in a highlight only has an effect if nextest's output is colorized.
+        assert_eq!(write(AnsiEscapes::Strip, &plain, Some(highlight)), stripped);
+
+        let preserved = format!("{raw}{RESET_COLOR}\n");
+        assert_eq!(write(AnsiEscapes::Auto, &colorized, None), preserved);
+        assert_eq!(write(AnsiEscapes::Preserve, &colorized, None), preserved);
+        assert_eq!(write(AnsiEscapes::Preserve, &plain, None), preserved);
+
+        // Stripping also applies around and within the highlighted section.
+        assert_eq!(
+            write(AnsiEscapes::Strip, &colorized, Some(highlight)),
+            format!(
+                "ok caf\u{e9} link\n{RESET_COLOR}\
+                 {BOLD_RED}highlight{RESET_COLOR}\n\
+                 rest{RESET_COLOR}\n"
+            )
+        );
+    }
 }
@@ -8,14 +8,22 @@
 
 use super::{FinalStatusLevel, StatusLevel, TestOutputDisplay};
 use crate::{
-    config::ScriptId,
+    config::{CpuAffinity, RetryBackoff, ScriptId},
     list::{TestInstance, TestInstanceId, TestList},
-    test_output::ChildExecutionOutput,
+    test_filter::TestFilterSummary,
+    test_output::{ChildExecutionOutput, OutputMismatch},
 };
-use chrono::{DateTime, FixedOffset};
-use nextest_metadata::MismatchReason;
+use camino::{Utf8Path, Utf8PathBuf};
+use chrono::{DateTime, FixedOffset, TimeDelta};
+use nextest_metadata::{MismatchReason, RustBinaryId};
 use quick_junit::ReportUuid;
-use std::{collections::BTreeMap, fmt, process::ExitStatus, time::Duration};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    process::ExitStatus,
+    time::Duration,
+};
 
 /// A test event.
 ///
@@ -53,6 +61,19 @@ pub enum TestEventKind<'a> {
 
         /// The command-line arguments for the process.
         cli_args: Vec<String>,
+
+        /// A structured summary of the filters that selected the tests in this run.
+        ///
+        /// This is `None` if no summary was provided via
+        /// [`TestRunnerBuilder::set_filter_summary`](crate::runner::TestRunnerBuilder::set_filter_summary).
+        filter: Option<TestFilterSummary>,
+
+        /// The seed used to shuffle the order tests are started in, if they were shuffled.
+        ///
+        /// Passing this seed to
+        /// [`TestRunnerBuilder::set_shuffle`](crate::runner::TestRunnerBuilder::set_shuffle)
+        /// reproduces the same order.
+        shuffle_seed: Option<u64>,
     },
 
     /// A setup script started.
@@ -87,6 +108,11 @@ pub enum TestEventKind<'a> {
         /// The arguments to the command.
         args: &'a [String],
 
+        /// The configured slow timeout period for the script.
+        ///
+        /// `elapsed` is a multiple of this.
+        slow_timeout: Duration,
+
         /// The amount of time elapsed since the start of execution.
         elapsed: Duration,
 
@@ -120,18 +146,184 @@ pub enum TestEventKind<'a> {
         /// True if some output from the setup script was passed through.
         no_capture: bool,
 
+        /// The environment variables set by the script, with the values of keys matching the
+        /// script's `redact-env` patterns hidden.
+        ///
+        /// `None` if the script failed or its environment couldn't be read.
+        env_map: Option<SetupScriptEnvMap>,
+
         /// The execution status of the setup script.
         run_status: SetupScriptExecuteStatus,
     },
 
-    // TODO: add events for BinaryStarted and BinaryFinished? May want a slightly different way to
-    // do things, maybe a couple of reporter traits (one for the run as a whole and one for each
-    // binary).
+    /// A setup script set environment variables that earlier setup scripts had also set.
+    ///
+    /// For tests that more than one of the scripts applies to, the value from the latest script
+    /// is used. This event is emitted after the corresponding [`Self::SetupScriptFinished`].
+    SetupScriptEnvOverridden {
+        /// The script that set the variables.
+        script_id: ScriptId,
+
+        /// The variables that were overridden, sorted by key.
+        overrides: Vec<SetupScriptEnvOverride>,
+    },
+
+    /// A teardown script started.
+    ///
+    /// Teardown scripts are run after all tests have finished, unless the run was interrupted by a
+    /// signal.
+    TeardownScriptStarted {
+        /// The teardown script index.
+        index: usize,
+
+        /// The total number of teardown scripts.
+        total: usize,
+
+        /// The script ID.
+        script_id: ScriptId,
+
+        /// The command to run.
+        command: &'a str,
+
+        /// The arguments to the command.
+        args: &'a [String],
+
+        /// True if some output from the teardown script is being passed through.
+        no_capture: bool,
+    },
+
+    /// A teardown script was slow.
+    TeardownScriptSlow {
+        /// The script ID.
+        script_id: ScriptId,
+
+        /// The command to run.
+        command: &'a str,
+
+        /// The arguments to the command.
+        args: &'a [String],
+
+        /// The configured slow timeout period for the script.
+        ///
+        /// `elapsed` is a multiple of this.
+        slow_timeout: Duration,
+
+        /// The amount of time elapsed since the start of execution.
+        elapsed: Duration,
+
+        /// True if the script has hit its timeout and is about to be terminated.
+        will_terminate: bool,
+    },
+
+    /// A teardown script completed execution.
+    ///
+    /// A failing teardown script doesn't cancel the run or change its outcome. Whether it fails
+    /// the run is decided by the `on-teardown-failure` profile setting.
+    TeardownScriptFinished {
+        /// The teardown script index.
+        index: usize,
+
+        /// The total number of teardown scripts.
+        total: usize,
+
+        /// The script ID.
+        script_id: ScriptId,
+
+        /// The command to run.
+        command: &'a str,
+
+        /// The arguments to the command.
+        args: &'a [String],
+
+        /// True if some output from the teardown script was passed through.
+        no_capture: bool,
+
+        /// The execution status of the teardown script.
+        run_status: SetupScriptExecuteStatus,
+    },
+
+    /// A phase of a staged run started.
+    ///
+    /// Runs are only staged if smoke tests are configured. Test events between this and the
+    /// corresponding [`Self::PhaseFinished`] belong to this phase.
+    PhaseStarted {
+        /// The phase that started.
+        phase: RunPhase,
+
+        /// The number of tests expected to run in this phase.
+        run_count: usize,
+    },
+
+    /// A phase of a staged run finished.
+    PhaseFinished {
+        /// The phase that finished.
+        phase: RunPhase,
+
+        /// The amount of time the phase took.
+        elapsed: Duration,
+
+        /// Statistics for the tests in this phase.
+        run_stats: RunStats,
+    },
+
+    /// The first test in a binary started running or was skipped.
+    ///
+    /// Tests from different binaries run in parallel, so events for several binaries may be
+    /// interleaved. Every test in the binary is reported via [`Self::TestFinished`] or
+    /// [`Self::TestSkipped`] between this event and the corresponding [`Self::BinaryFinished`].
+    ///
+    /// For binaries that don't contain any tests, this event and the corresponding
+    /// [`Self::BinaryFinished`] are emitted right after [`Self::RunStarted`]. Binaries that
+    /// weren't listed, for example because they were filtered out by platform, don't have
+    /// events emitted for them.
+    BinaryStarted {
+        /// The binary ID.
+        binary_id: &'a RustBinaryId,
+
+        /// The name of the package the binary is in.
+        package_name: &'a str,
+
+        /// The number of tests in the binary, including tests that will be skipped.
+        test_count: usize,
+
+        /// The number of tests in the binary that will be run.
+        run_count: usize,
+    },
+
+    /// All the tests in a binary finished running or were skipped.
+    ///
+    /// If the run is cancelled, this is emitted before [`Self::RunFinished`] for every binary that
+    /// was started but didn't finish. In that case, `run_stats` indicates that some tests
+    /// weren't run.
+    BinaryFinished {
+        /// The binary ID.
+        binary_id: &'a RustBinaryId,
+
+        /// The name of the package the binary is in.
+        package_name: &'a str,
+
+        /// The amount of time elapsed since the binary was started.
+        elapsed: Duration,
+
+        /// Statistics for the tests in this binary.
+        run_stats: RunStats,
+    },
+
     /// A test started running.
     TestStarted {
         /// The test instance that was started.
         test_instance: TestInstance<'a>,
 
+        /// The program and arguments used to run the test, including any target runner.
+        command_line: Vec<String>,
+
+        /// The environment variables nextest set for the test, on top of those inherited from
+        /// nextest's own environment.
+        ///
+        /// Values of variables matching the profile's `redact-env` patterns, or the `redact-env`
+        /// patterns of the setup script that set them, are replaced with `[redacted]`.
+        extra_env: BTreeMap<String, String>,
+
         /// Current run statistics so far.
         current_stats: RunStats,
 
@@ -150,6 +342,12 @@ pub enum TestEventKind<'a> {
         /// Retry data.
         retry_data: RetryData,
 
+        /// The slow timeout period configured for this test, taking per-test overrides into
+        /// account.
+        ///
+        /// `elapsed` is a multiple of this.
+        slow_timeout: Duration,
+
         /// The amount of time that has elapsed since the beginning of the test.
         elapsed: Duration,
 
@@ -157,6 +355,23 @@ pub enum TestEventKind<'a> {
         will_terminate: bool,
     },
 
+    /// A test wrote output after its process exited, while nextest was waiting for its standard
+    /// output and standard error to be closed.
+    ///
+    /// This usually means that the test spawned processes that outlived it. If those processes
+    /// were still holding standard output or standard error open at the end of the leak timeout,
+    /// the attempt is also marked as leaky with [`ExecutionResult::Leak`].
+    TestOutputAfterExit {
+        /// The test instance that wrote output.
+        test_instance: TestInstance<'a>,
+
+        /// Retry data.
+        retry_data: RetryData,
+
+        /// The number of bytes written after the test's process exited.
+        bytes: u64,
+    },
+
     /// A test attempt failed and will be retried in the future.
     ///
     /// This event does not occur on the final run of a failing test.
@@ -174,6 +389,28 @@ pub enum TestEventKind<'a> {
         failure_output: TestOutputDisplay,
     },
 
+    /// The delay before the next attempt of a test has started.
+    ///
+    /// This is emitted after [`Self::TestAttemptFailedWillRetry`] if the delay before the next
+    /// attempt is nonzero. It is emitted again if the delay is resumed after nextest was suspended
+    /// (for example, with Ctrl-Z), since that moves the resume time. Displays can use `resume_time`
+    /// to show a countdown, without needing further events.
+    TestRetryDelayStarted {
+        /// The test instance that is being retried.
+        test_instance: TestInstance<'a>,
+
+        /// Data for the attempt that starts once the delay is over.
+        retry_data: RetryData,
+
+        /// How much of the delay remains.
+        remaining: Duration,
+
+        /// The time at which the next attempt is scheduled to start.
+        ///
+        /// This is `None` if the delay is too long for the resume time to be represented.
+        resume_time: Option<DateTime<FixedOffset>>,
+    },
+
     /// A retry has started.
     TestRetryStarted {
         /// The test instance that is being retried.
@@ -181,6 +418,33 @@ pub enum TestEventKind<'a> {
 
         /// Data related to retries.
         retry_data: RetryData,
+
+        /// A unique identifier shared by all attempts of this test in this run.
+        ///
+        /// This is the same as [`ExecuteStatus::test_run_uuid`].
+        test_run_uuid: ReportUuid,
+
+        /// A unique identifier for the attempt that is starting.
+        ///
+        /// This is the same as the [`ExecuteStatus::attempt_uuid`] reported once the attempt
+        /// finishes.
+        attempt_uuid: ReportUuid,
+    },
+
+    /// A passing test was run a second time as part of a determinism check, and the two runs
+    /// differed.
+    ///
+    /// This event is emitted before the corresponding [`Self::TestFinished`], and does not by
+    /// itself cause the test to be marked as failed.
+    TestOutputMismatch {
+        /// The test instance whose runs differed.
+        test_instance: TestInstance<'a>,
+
+        /// The first difference found between the two runs.
+        mismatch: OutputMismatch,
+
+        /// The status of the second run.
+        rerun_status: ExecuteStatus,
     },
 
     /// A test finished running.
@@ -200,6 +464,19 @@ pub enum TestEventKind<'a> {
         /// Whether the JUnit report should store failure output for this test.
         junit_store_failure_output: bool,
 
+        /// The owner of this test, as configured via the `owner` per-test override.
+        owner: Option<&'a str>,
+
+        /// Files attached to this test's results.
+        attachments: TestAttachments,
+
+        /// True if this test is quarantined, as configured via the `quarantine` per-test
+        /// override.
+        ///
+        /// The results of quarantined tests are counted separately in [`RunStats`], and their
+        /// failures don't cause the run to fail.
+        quarantined: bool,
+
         /// Information about all the runs for this test.
         run_statuses: ExecutionStatuses,
 
@@ -249,6 +526,12 @@ pub enum TestEventKind<'a> {
         /// The number of responses that were not received. In most cases, this
         /// is 0.
         missing: usize,
+
+        /// Tests that haven't started yet.
+        ///
+        /// Queued tests don't send responses, so they don't count towards
+        /// `missing`.
+        queued: QueuedTests<'a>,
     },
 
     /// `Enter` was pressed. Either a newline or a progress bar snapshot needs
@@ -274,6 +557,20 @@ pub enum TestEventKind<'a> {
 
         /// The reason this run was cancelled.
         reason: CancelReason,
+
+        /// The specific event that triggered this cancellation, if known.
+        ///
+        /// This is `None` if the trigger is ambient, e.g. an interrupt from
+        /// Ctrl-C or a reporting error.
+        trigger: Option<CancelTrigger<'a>>,
+
+        /// If running tests and setup scripts are being given time to finish before they're
+        /// terminated, the point at which they will be terminated, measured like
+        /// [`TestEvent::elapsed`].
+        ///
+        /// This is `None` if running units are terminated right away, or if they aren't
+        /// terminated at all (for example, if the run was cancelled because of a test failure).
+        grace_deadline: Option<Duration>,
     },
 
     /// A forcible kill was requested due to receiving a signal.
@@ -295,6 +592,16 @@ pub enum TestEventKind<'a> {
 
         /// The number of tests currently running.
         running: usize,
+
+        /// The running tests that were sent SIGTSTP, other than those in `not_stopped`.
+        ///
+        /// This includes tests for which it couldn't be determined whether they stopped, and tests
+        /// that were waiting to be retried.
+        stopped: BTreeSet<TestInstanceId<'a>>,
+
+        /// The running tests that were sent SIGTSTP but were seen to keep running, most likely
+        /// because they handle or ignore the signal.
+        not_stopped: BTreeSet<TestInstanceId<'a>>,
     },
 
     /// A SIGCONT event was received and the run is being continued.
@@ -304,6 +611,9 @@ pub enum TestEventKind<'a> {
 
         /// The number of tests that will be started up again.
         running: usize,
+
+        /// The tests that will be started up again.
+        continued: BTreeSet<TestInstanceId<'a>>,
     },
 
     /// The test run finished.
@@ -319,9 +629,125 @@ pub enum TestEventKind<'a> {
 
         /// Statistics for the run.
         run_stats: RunStats,
+
+        /// Statistics for the run, broken down by package.
+        package_stats: PackageStats,
+
+        /// A histogram of how long tests took, if enabled with the `duration-histogram` profile
+        /// setting.
+        duration_histogram: Option<DurationHistogram>,
+
+        /// The tests that were flaky, as determined by [`ExecutionStatuses::is_flaky`].
+        ///
+        /// Quarantined tests aren't included.
+        flaky_tests: BTreeSet<TestInstanceId<'a>>,
     },
 }
 
+/// Statistics for a test run, broken down by package.
+///
+/// Tests from the same package are counted together, even if they were built for different
+/// platforms. Every package with tests in the test list has an entry, including packages where
+/// all tests were skipped.
+///
+/// Part of [`TestEventKind::RunFinished`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PackageStats {
+    stats: BTreeMap<String, RunStats>,
+}
+
+impl PackageStats {
+    /// Returns the statistics for the given package, if it had any tests.
+    pub fn get(&self, package_name: &str) -> Option<&RunStats> {
+        self.stats.get(package_name)
+    }
+
+    /// Iterates over package names and their statistics, in order of package name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &RunStats)> + '_ {
+        self.stats
+            .iter()
+            .map(|(package_name, stats)| (package_name.as_str(), stats))
+    }
+
+    /// Returns the number of packages.
+    pub fn len(&self) -> usize {
+        self.stats.len()
+    }
+
+    /// Returns true if there are no packages.
+    pub fn is_empty(&self) -> bool {
+        self.stats.is_empty()
+    }
+
+    /// Returns the statistics for the given package, creating an empty entry if it doesn't exist.
+    pub(crate) fn stats_mut(&mut self, package_name: &str) -> &mut RunStats {
+        // Avoid allocating a new string for the common case where the package already exists.
+        if !self.stats.contains_key(package_name) {
+            self.stats
+                .insert(package_name.to_owned(), RunStats::default());
+        }
+        self.stats
+            .get_mut(package_name)
+            .expect("package was just inserted")
+    }
+}
+
+/// A histogram of how long tests took to run.
+///
+/// Each test that finishes is counted once, in the first bucket whose upper bound is more than the
+/// time taken by its last attempt. Tests that take at least as long as the last bound, or that
+/// time out, are counted in an extra overflow bucket at the end.
+///
+/// Part of [`TestEventKind::RunFinished`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DurationHistogram {
+    bounds: Vec<Duration>,
+    // One more than bounds, for the overflow bucket.
+    counts: Vec<usize>,
+}
+
+impl DurationHistogram {
+    /// Creates a new, empty histogram with buckets bounded above by `bounds`.
+    ///
+    /// `bounds` must be in increasing order.
+    pub fn new(bounds: Vec<Duration>) -> Self {
+        debug_assert!(
+            bounds.windows(2).all(|w| w[0] < w[1]),
+            "bounds are in increasing order: {bounds:?}"
+        );
+        let counts = vec![0; bounds.len() + 1];
+        Self { bounds, counts }
+    }
+
+    /// Returns the upper bounds of the buckets, not including the overflow bucket.
+    pub fn bounds(&self) -> &[Duration] {
+        &self.bounds
+    }
+
+    /// Returns the number of tests in each bucket.
+    ///
+    /// This has one more element than [`Self::bounds`]: the last element is the overflow bucket.
+    pub fn counts(&self) -> &[usize] {
+        &self.counts
+    }
+
+    /// Returns the total number of tests counted in the histogram.
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    pub(crate) fn on_test_finished(&mut self, run_statuses: &ExecutionStatuses) {
+        let last_status = run_statuses.last_status();
+        let index = if matches!(last_status.result, ExecutionResult::Timeout { .. }) {
+            self.bounds.len()
+        } else {
+            self.bounds
+                .partition_point(|&bound| bound <= last_status.time_taken)
+        };
+        self.counts[index] += 1;
+    }
+}
+
 /// Statistics for a test run.
 #[derive(Copy, Clone, Default, Debug, Eq, PartialEq)]
 pub struct RunStats {
@@ -331,6 +757,12 @@ pub struct RunStats {
     pub initial_run_count: usize,
 
     /// The total number of tests that finished running.
+    ///
+    /// A test is counted once its last attempt has finished, so a test that is retried is only
+    /// counted once. Tests that were skipped by filters aren't part of either this count or
+    /// `initial_run_count`. If the run is cancelled, tests that were never started, or that were
+    /// still running and didn't finish, aren't counted, so this will be less than
+    /// `initial_run_count` at the end.
     pub finished_count: usize,
 
     /// The total number of setup scripts that were expected to be run at the beginning.
@@ -368,9 +800,31 @@ pub struct RunStats {
     /// The number of failed tests that were slow.
     pub failed_slow: usize,
 
+    /// The number of tests that were retried but still didn't pass.
+    ///
+    /// These are also counted in `failed`, `timed_out` or `exec_failed`, depending on the result of
+    /// the last attempt.
+    pub failed_after_retries: usize,
+
+    /// The number of tests that failed because they exceeded their memory limit.
+    ///
+    /// These are also counted in `failed`. Memory limits are currently only enforced on Windows.
+    pub failed_memory_limit: usize,
+
+    /// In repeat-until-failure mode, the number of iterations that passed before the first test
+    /// failure.
+    ///
+    /// This is `None` if no tests have failed, or if the run isn't in repeat-until-failure mode.
+    pub iterations_before_first_failure: Option<usize>,
+
     /// The number of tests that timed out.
     pub timed_out: usize,
 
+    /// The number of tests that timed out and had to be forcibly killed.
+    ///
+    /// These are also counted in `timed_out`.
+    pub timed_out_killed: usize,
+
     /// The number of tests that passed but leaked handles.
     pub leaky: usize,
 
@@ -379,10 +833,54 @@ pub struct RunStats {
 
     /// The number of tests that were skipped.
     pub skipped: usize,
+
+    /// The number of quarantined tests that passed.
+    ///
+    /// Quarantined tests are only counted here and in [`Self::quarantined_failed`], not in
+    /// `passed`, `flaky` or `leaky`.
+    pub quarantined_passed: usize,
+
+    /// The number of quarantined tests that failed, timed out or encountered an execution
+    /// failure.
+    ///
+    /// These aren't counted in `failed`, `timed_out` or `exec_failed`, and don't cause the run to
+    /// fail.
+    pub quarantined_failed: usize,
+
+    /// The total wall-clock time taken by tests, summed over every attempt of every test that
+    /// finished.
+    ///
+    /// This is the time that test processes were running for, not the time they spent on a CPU.
+    /// Since tests run in parallel, it's usually more than the time taken by the run as a whole:
+    /// see [`Self::parallelism_ratio`].
+    pub total_test_time: Duration,
+
+    /// The total wall-clock time taken by setup scripts that finished, measured the same way as
+    /// [`Self::total_test_time`].
+    ///
+    /// Setup scripts run serially before tests, so they're counted separately.
+    pub setup_scripts_time: Duration,
+
+    /// True if smoke tests failed in gating mode, so the remaining tests weren't run.
+    pub smoke_failed: bool,
+
+    /// The total number of teardown scripts that finished running.
+    pub teardown_scripts_finished_count: usize,
+
+    /// The number of teardown scripts that passed.
+    pub teardown_scripts_passed: usize,
+
+    /// The number of teardown scripts that failed, timed out, or encountered an execution failure.
+    ///
+    /// Teardown scripts run after all tests have finished, so these aren't considered by
+    /// [`Self::has_failures`] or [`Self::summarize_final`].
+    pub teardown_scripts_failed: usize,
 }
 
 impl RunStats {
     /// Returns true if there are any failures recorded in the stats.
+    ///
+    /// Failures of quarantined tests are ignored.
     pub fn has_failures(&self) -> bool {
         self.failed_setup_script_count() > 0 || self.failed_count() > 0
     }
@@ -392,11 +890,55 @@ impl RunStats {
         self.setup_scripts_failed + self.setup_scripts_exec_failed + self.setup_scripts_timed_out
     }
 
-    /// Returns count of tests that did not pass.
+    /// Returns count of tests that did not pass, excluding quarantined tests.
     pub fn failed_count(&self) -> usize {
         self.failed + self.exec_failed + self.timed_out
     }
 
+    /// Returns true if at least `min_tests` tests finished running.
+    ///
+    /// This is checked against the `min-tests` profile setting at the end of a run.
+    pub fn meets_min_tests(&self, min_tests: usize) -> bool {
+        self.finished_count >= min_tests
+    }
+
+    /// Returns the ratio of [`Self::total_test_time`] to the given wall-clock time, typically the
+    /// time taken by the run.
+    ///
+    /// This is a measure of how well tests were parallelized: a ratio of 4.0 means that on
+    /// average, 4 tests were running at any given time. Setup scripts aren't included. If
+    /// `elapsed` is zero, this returns 0.0.
+    pub fn parallelism_ratio(&self, elapsed: Duration) -> f64 {
+        if elapsed.is_zero() {
+            return 0.0;
+        }
+        self.total_test_time.as_secs_f64() / elapsed.as_secs_f64()
+    }
+
+    /// Returns the fraction of tests expected to run that have finished, between 0.0 and 1.0.
+    ///
+    /// This is `finished_count` divided by `initial_run_count`. Since a test is only counted as
+    /// finished after its last attempt, retries don't advance the fraction, and it never
+    /// decreases over the course of a run. This makes it suitable for progress indicators such
+    /// as `[ 42%]`.
+    ///
+    /// If the run is cancelled, this stays below 1.0, since tests that didn't finish aren't
+    /// counted. Setup scripts aren't included. If no tests were expected to run, this returns
+    /// 1.0.
+    pub fn progress_fraction(&self) -> f64 {
+        if self.initial_run_count == 0 {
+            return 1.0;
+        }
+        (self.finished_count as f64 / self.initial_run_count as f64).clamp(0.0, 1.0)
+    }
+
+    /// Returns true if any setup scripts or tests that were expected to run didn't finish, for
+    /// example because the run was cancelled.
+    pub fn has_unfinished(&self) -> bool {
+        self.setup_scripts_initial_count > self.setup_scripts_finished_count
+            || self.initial_run_count > self.finished_count
+    }
+
     /// Summarizes the stats as an enum at the end of a test run.
     pub fn summarize_final(&self) -> FinalRunStats {
         // Check for failures first. The order of setup scripts vs tests should not be important,
@@ -424,9 +966,10 @@ impl RunStats {
 
     pub(crate) fn on_setup_script_finished(&mut self, status: &SetupScriptExecuteStatus) {
         self.setup_scripts_finished_count += 1;
+        self.setup_scripts_time += status.time_taken;
 
         match status.result {
-            ExecutionResult::Pass | ExecutionResult::Leak => {
+            ExecutionResult::Pass | ExecutionResult::Leak { .. } => {
                 self.setup_scripts_passed += 1;
             }
             ExecutionResult::Fail { .. } => {
@@ -435,14 +978,40 @@ impl RunStats {
             ExecutionResult::ExecFail => {
                 self.setup_scripts_exec_failed += 1;
             }
-            ExecutionResult::Timeout => {
+            ExecutionResult::Timeout { .. } => {
                 self.setup_scripts_timed_out += 1;
             }
         }
     }
 
+    pub(crate) fn on_teardown_script_finished(&mut self, status: &SetupScriptExecuteStatus) {
+        self.teardown_scripts_finished_count += 1;
+        if status.result.is_success() {
+            self.teardown_scripts_passed += 1;
+        } else {
+            self.teardown_scripts_failed += 1;
+        }
+    }
+
+    pub(crate) fn on_quarantined_test_finished(&mut self, run_statuses: &ExecutionStatuses) {
+        self.finished_count += 1;
+        self.total_test_time += run_statuses
+            .iter()
+            .map(|status| status.time_taken)
+            .sum::<Duration>();
+        if run_statuses.last_status().result.is_success() {
+            self.quarantined_passed += 1;
+        } else {
+            self.quarantined_failed += 1;
+        }
+    }
+
     pub(crate) fn on_test_finished(&mut self, run_statuses: &ExecutionStatuses) {
         self.finished_count += 1;
+        self.total_test_time += run_statuses
+            .iter()
+            .map(|status| status.time_taken)
+            .sum::<Duration>();
         // run_statuses is guaranteed to have at least one element.
         // * If the last element is success, treat it as success (and possibly flaky).
         // * If the last element is a failure, use it to determine fail/exec fail.
@@ -462,7 +1031,7 @@ impl RunStats {
                     self.flaky += 1;
                 }
             }
-            ExecutionResult::Leak => {
+            ExecutionResult::Leak { .. } => {
                 self.passed += 1;
                 self.leaky += 1;
                 if last_status.is_slow {
@@ -472,14 +1041,37 @@ impl RunStats {
                     self.flaky += 1;
                 }
             }
-            ExecutionResult::Fail { .. } => {
+            ExecutionResult::Fail { abort_status, .. } => {
                 self.failed += 1;
                 if last_status.is_slow {
                     self.failed_slow += 1;
                 }
+                if run_statuses.len() > 1 {
+                    self.failed_after_retries += 1;
+                }
+                if abort_status.is_some_and(AbortStatus::is_memory_limit) {
+                    self.failed_memory_limit += 1;
+                }
+            }
+            ExecutionResult::Timeout { termination } => {
+                self.timed_out += 1;
+                if termination == TimeoutTermination::Killed {
+                    self.timed_out_killed += 1;
+                }
+                if run_statuses.len() > 1 {
+                    self.failed_after_retries += 1;
+                }
+            }
+            ExecutionResult::ExecFail => {
+                self.exec_failed += 1;
+                if run_statuses.len() > 1 {
+                    self.failed_after_retries += 1;
+                }
             }
-            ExecutionResult::Timeout => self.timed_out += 1,
-            ExecutionResult::ExecFail => self.exec_failed += 1,
+        }
+
+        if !last_status.result.is_success() && self.iterations_before_first_failure.is_none() {
+            self.iterations_before_first_failure = run_statuses.passed_iterations();
         }
     }
 }
@@ -500,6 +1092,178 @@ pub enum FinalRunStats {
     Failed(RunStatsFailureKind),
 }
 
+impl FinalRunStats {
+    pub(crate) fn to_static_str(self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::NoTestsRun => "no-tests-run",
+            Self::Cancelled(_) => "cancelled",
+            Self::Failed(_) => "failed",
+        }
+    }
+
+    /// Returns how bad this outcome is, for picking the worst of several runs.
+    ///
+    /// Failures are worse than cancellations, which are worse than runs with no tests.
+    fn severity(&self) -> u8 {
+        match self {
+            Self::Success => 0,
+            Self::NoTestsRun => 1,
+            Self::Cancelled(_) => 2,
+            Self::Failed(_) => 3,
+        }
+    }
+}
+
+/// Statistics for a sequence of test runs with different profiles, in the order they were run.
+///
+/// This is used to combine the results of running several profiles one after the other into a
+/// single outcome.
+#[derive(Clone, Debug, Default)]
+pub struct AggregateRunStats {
+    runs: Vec<ProfileRunStats>,
+}
+
+impl AggregateRunStats {
+    /// Creates a new, empty `AggregateRunStats`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the statistics for a run that has finished.
+    pub fn push(
+        &mut self,
+        profile_name: impl Into<String>,
+        elapsed: Duration,
+        run_stats: RunStats,
+    ) {
+        self.runs.push(ProfileRunStats {
+            profile_name: profile_name.into(),
+            elapsed,
+            run_stats,
+        });
+    }
+
+    /// Returns the runs, in the order they were added.
+    pub fn runs(&self) -> &[ProfileRunStats] {
+        &self.runs
+    }
+
+    /// Returns the total time taken by all runs.
+    pub fn elapsed(&self) -> Duration {
+        self.runs.iter().map(|run| run.elapsed).sum()
+    }
+
+    /// Returns the sum of the statistics for all runs.
+    pub fn total(&self) -> RunStats {
+        let mut total = RunStats::default();
+        for run in &self.runs {
+            // Destructure the stats so that new fields aren't missed.
+            let RunStats {
+                initial_run_count,
+                finished_count,
+                setup_scripts_initial_count,
+                setup_scripts_finished_count,
+                setup_scripts_passed,
+                setup_scripts_failed,
+                setup_scripts_exec_failed,
+                setup_scripts_timed_out,
+                passed,
+                passed_slow,
+                flaky,
+                failed,
+                failed_slow,
+                failed_after_retries,
+                failed_memory_limit,
+                iterations_before_first_failure,
+                timed_out,
+                timed_out_killed,
+                leaky,
+                exec_failed,
+                skipped,
+                quarantined_passed,
+                quarantined_failed,
+                total_test_time,
+                setup_scripts_time,
+                smoke_failed,
+                teardown_scripts_finished_count,
+                teardown_scripts_passed,
+                teardown_scripts_failed,
+            } = run.run_stats;
+            total.initial_run_count += initial_run_count;
+            total.finished_count += finished_count;
+            total.setup_scripts_initial_count += setup_scripts_initial_count;
+            total.setup_scripts_finished_count += setup_scripts_finished_count;
+            total.setup_scripts_passed += setup_scripts_passed;
+            total.setup_scripts_failed += setup_scripts_failed;
+            total.setup_scripts_exec_failed += setup_scripts_exec_failed;
+            total.setup_scripts_timed_out += setup_scripts_timed_out;
+            total.passed += passed;
+            total.passed_slow += passed_slow;
+            total.flaky += flaky;
+            total.failed += failed;
+            total.failed_slow += failed_slow;
+            total.failed_after_retries += failed_after_retries;
+            total.failed_memory_limit += failed_memory_limit;
+            total.iterations_before_first_failure = total
+                .iterations_before_first_failure
+                .or(iterations_before_first_failure);
+            total.timed_out += timed_out;
+            total.timed_out_killed += timed_out_killed;
+            total.leaky += leaky;
+            total.exec_failed += exec_failed;
+            total.skipped += skipped;
+            total.quarantined_passed += quarantined_passed;
+            total.quarantined_failed += quarantined_failed;
+            total.total_test_time += total_test_time;
+            total.setup_scripts_time += setup_scripts_time;
+            total.smoke_failed |= smoke_failed;
+            total.teardown_scripts_finished_count += teardown_scripts_finished_count;
+            total.teardown_scripts_passed += teardown_scripts_passed;
+            total.teardown_scripts_failed += teardown_scripts_failed;
+        }
+        total
+    }
+
+    /// Returns the run with the worst outcome, or `None` if there are no runs.
+    ///
+    /// Failures are worse than cancellations, which are worse than runs with no tests, which are
+    /// worse than successes. If several runs are equally bad, the first one is returned.
+    pub fn worst_run(&self) -> Option<&ProfileRunStats> {
+        self.runs.iter().reduce(|worst, run| {
+            if run.run_stats.summarize_final().severity()
+                > worst.run_stats.summarize_final().severity()
+            {
+                run
+            } else {
+                worst
+            }
+        })
+    }
+
+    /// Summarizes the runs as the outcome of the worst run.
+    ///
+    /// If there are no runs, this is [`FinalRunStats::NoTestsRun`].
+    pub fn summarize_final(&self) -> FinalRunStats {
+        self.worst_run().map_or(FinalRunStats::NoTestsRun, |run| {
+            run.run_stats.summarize_final()
+        })
+    }
+}
+
+/// Statistics for one of the runs in an [`AggregateRunStats`].
+#[derive(Clone, Debug)]
+pub struct ProfileRunStats {
+    /// The name of the profile the run used.
+    pub profile_name: String,
+
+    /// The amount of time the run took.
+    pub elapsed: Duration,
+
+    /// Statistics for the run.
+    pub run_stats: RunStats,
+}
+
 /// A type summarizing the step at which a test run failed.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum RunStatsFailureKind {
@@ -522,36 +1286,92 @@ pub enum RunStatsFailureKind {
 pub struct ExecutionStatuses {
     /// This is guaranteed to be non-empty.
     statuses: Vec<ExecuteStatus>,
+
+    /// The verification rerun of a failing test, if failures are being verified.
+    ///
+    /// This is boxed to keep test events small, since verification is rare.
+    verification: Option<Box<FailureVerification>>,
+
+    /// In repeat-until-failure mode, the number of iterations that passed before the last one.
+    passed_iterations: Option<usize>,
 }
 
 #[expect(clippy::len_without_is_empty)] // RunStatuses is never empty
 impl ExecutionStatuses {
     pub(crate) fn new(statuses: Vec<ExecuteStatus>) -> Self {
-        Self { statuses }
+        Self {
+            statuses,
+            verification: None,
+            passed_iterations: None,
+        }
+    }
+
+    pub(crate) fn with_verification(
+        mut self,
+        verification: Option<Box<FailureVerification>>,
+    ) -> Self {
+        self.verification = verification;
+        self
+    }
+
+    pub(crate) fn with_passed_iterations(mut self, passed_iterations: Option<usize>) -> Self {
+        self.passed_iterations = passed_iterations;
+        self
     }
 
     /// Returns the last execution status.
     ///
-    /// This status is typically used as the final result.
+    /// This status is typically used as the final result. If the test is
+    /// counted as flaky because of a [`FailureVerification`], this is the
+    /// status of the verification rerun.
     pub fn last_status(&self) -> &ExecuteStatus {
-        self.statuses
-            .last()
-            .expect("execution statuses is non-empty")
+        self.describe().last_status()
     }
 
     /// Iterates over all the statuses.
+    ///
+    /// This does not include the verification rerun, if any: see
+    /// [`verification`](Self::verification).
     pub fn iter(&self) -> impl DoubleEndedIterator<Item = &'_ ExecuteStatus> + '_ {
         self.statuses.iter()
     }
 
-    /// Returns the number of times the test was executed.
+    /// Returns the number of times the test was executed, not including the
+    /// verification rerun.
     pub fn len(&self) -> usize {
         self.statuses.len()
     }
 
+    /// Returns the verification rerun of this test, if one was performed.
+    ///
+    /// Verification reruns are only performed for tests that failed all their
+    /// attempts, and only if failures are being verified.
+    pub fn verification(&self) -> Option<&FailureVerification> {
+        self.verification.as_deref()
+    }
+
+    /// Returns the number of iterations of the test that passed before the last one, if the run
+    /// is in repeat-until-failure mode.
+    ///
+    /// Only the last iteration is included in the statuses. If the test failed, this is the
+    /// number of iterations that passed before the failure.
+    pub fn passed_iterations(&self) -> Option<usize> {
+        self.passed_iterations
+    }
+
+    /// Returns true if the test was flaky: it failed at least once, but ultimately passed.
+    ///
+    /// This is the case if [`Self::describe`] returns [`ExecutionDescription::Flaky`].
+    pub fn is_flaky(&self) -> bool {
+        matches!(self.describe(), ExecutionDescription::Flaky { .. })
+    }
+
     /// Returns a description of self.
     pub fn describe(&self) -> ExecutionDescription<'_> {
-        let last_status = self.last_status();
+        let last_status = self
+            .statuses
+            .last()
+            .expect("execution statuses is non-empty");
         if last_status.result.is_success() {
             if self.statuses.len() > 1 {
                 ExecutionDescription::Flaky {
@@ -563,6 +1383,15 @@ impl ExecutionStatuses {
                     single_status: last_status,
                 }
             }
+        } else if let Some(verification) = self
+            .verification
+            .as_ref()
+            .filter(|verification| verification.counts_as_flaky && !verification.reproduced())
+        {
+            ExecutionDescription::Flaky {
+                last_status: &verification.status,
+                prior_statuses: &self.statuses,
+            }
         } else {
             let first_status = self
                 .statuses
@@ -578,6 +1407,28 @@ impl ExecutionStatuses {
     }
 }
 
+/// A rerun of a test that failed all its attempts, performed to check whether
+/// the failure reproduces.
+///
+/// Verification reruns are distinct from retries: they happen after all
+/// configured attempts have failed, and they only make the test count as
+/// flaky if `counts_as_flaky` is true.
+#[derive(Clone, Debug)]
+pub struct FailureVerification {
+    /// The status of the verification rerun.
+    pub status: ExecuteStatus,
+
+    /// Whether the test counts as flaky if the failure doesn't reproduce.
+    pub counts_as_flaky: bool,
+}
+
+impl FailureVerification {
+    /// Returns true if the verification rerun failed as well.
+    pub fn reproduced(&self) -> bool {
+        !self.status.result.is_success()
+    }
+}
+
 /// A description of test executions obtained from `ExecuteStatuses`.
 ///
 /// This can be used to quickly determine whether a test passed, failed or was flaky.
@@ -618,7 +1469,7 @@ impl<'a> ExecutionDescription<'a> {
     pub fn status_level(&self) -> StatusLevel {
         match self {
             ExecutionDescription::Success { single_status } => {
-                if single_status.result == ExecutionResult::Leak {
+                if matches!(single_status.result, ExecutionResult::Leak { .. }) {
                     StatusLevel::Leak
                 } else {
                     StatusLevel::Pass
@@ -637,7 +1488,7 @@ impl<'a> ExecutionDescription<'a> {
                 // Slow is higher priority than leaky, so return slow first here.
                 if single_status.is_slow {
                     FinalStatusLevel::Slow
-                } else if single_status.result == ExecutionResult::Leak {
+                } else if matches!(single_status.result, ExecutionResult::Leak { .. }) {
                     FinalStatusLevel::Leak
                 } else {
                     FinalStatusLevel::Pass
@@ -666,11 +1517,19 @@ impl<'a> ExecutionDescription<'a> {
 pub struct ExecuteStatus {
     /// Retry-related data.
     pub retry_data: RetryData,
+    /// A unique identifier shared by all attempts of this test in this run, including reruns for
+    /// determinism checks and failure verification.
+    pub test_run_uuid: ReportUuid,
+    /// A unique identifier for this attempt.
+    pub attempt_uuid: ReportUuid,
     /// The stdout and stderr output for this test.
     pub output: ChildExecutionOutput,
     /// The execution result for this test: pass, fail or execution error.
     pub result: ExecutionResult,
-    /// The time at which the test started.
+    /// The time at which the test process was started.
+    ///
+    /// If the profile sets a `start-stagger`, this is after the test has waited for its turn to
+    /// start.
     pub start_time: DateTime<FixedOffset>,
     /// The time it took for the test to run.
     pub time_taken: Duration,
@@ -678,6 +1537,94 @@ pub struct ExecuteStatus {
     pub is_slow: bool,
     /// The delay will be non-zero if this is a retry and delay was specified.
     pub delay_before_start: Duration,
+    /// For failing tests, the phase in which the test failed.
+    ///
+    /// This is `None` if the test passed, or if the profile doesn't configure
+    /// fixture failure markers.
+    pub failure_phase: Option<FailurePhase>,
+    /// The peak resident set size of the test process, in bytes.
+    ///
+    /// This is sampled while the test is running, and is currently only available on Linux and
+    /// macOS. It is `None` if the memory usage of the process couldn't be determined, for example
+    /// because the test exited too quickly for it to be sampled.
+    pub peak_rss: Option<u64>,
+    /// The time between the test process being spawned and the test printing
+    /// [`TEST_BODY_STARTED_MARKER`](crate::test_output::TEST_BODY_STARTED_MARKER) to standard
+    /// output, which is taken to be the time spent setting up fixtures.
+    ///
+    /// This is `None` if the test didn't print the marker, or if its output wasn't captured.
+    pub setup_time: Option<Duration>,
+    /// The total time the test process spent waiting for block I/O.
+    ///
+    /// This is sampled while the test is running, and is currently only available on Linux with
+    /// delay accounting enabled. Together with [`Self::time_taken`], it helps explain why a test
+    /// took longer than its CPU time alone would suggest.
+    pub io_wait: Option<Duration>,
+}
+
+/// The phase in which a test failed, as determined by fixture failure markers in its output.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FailurePhase {
+    /// The test failed while setting up a fixture.
+    Setup,
+
+    /// The test failed in the test body.
+    Test,
+
+    /// The test failed while tearing down a fixture.
+    Teardown,
+}
+
+impl FailurePhase {
+    /// Returns a short string representation of this phase.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FailurePhase::Setup => "setup",
+            FailurePhase::Test => "test",
+            FailurePhase::Teardown => "teardown",
+        }
+    }
+}
+
+impl fmt::Display for FailurePhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Files attached to a test's results, as configured via the `attachments` per-test override.
+///
+/// Part of [`TestEventKind::TestFinished`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TestAttachments {
+    /// Attachments that exist, as absolute paths.
+    pub found: Vec<Utf8PathBuf>,
+
+    /// Attachments that were configured but don't exist once the test has finished, as absolute
+    /// paths.
+    pub missing: Vec<Utf8PathBuf>,
+}
+
+impl TestAttachments {
+    /// Checks for the given attachments once a test has finished. Relative paths are resolved
+    /// against `cwd`, the test's working directory.
+    pub(crate) fn collect(cwd: &Utf8Path, paths: &[Utf8PathBuf]) -> Self {
+        let mut attachments = Self::default();
+        for path in paths {
+            let path = cwd.join(path);
+            if path.is_file() {
+                attachments.found.push(path);
+            } else {
+                attachments.missing.push(path);
+            }
+        }
+        attachments
+    }
+
+    /// Returns true if no attachments were configured for this test.
+    pub fn is_empty(&self) -> bool {
+        self.found.is_empty() && self.missing.is_empty()
+    }
 }
 
 /// Information about the execution of a setup script.
@@ -702,9 +1649,24 @@ pub struct SetupScriptExecuteStatus {
     ///
     /// `None` if an error occurred while running the script or reading the
     /// environment map.
+    ///
+    /// These values are not redacted. Reporters should use the `env_map` in
+    /// [`TestEventKind::SetupScriptFinished`] instead.
     pub env_map: Option<SetupScriptEnvMap>,
 }
 
+/// An environment variable set by a setup script that an earlier setup script had also set.
+///
+/// Part of [`TestEventKind::SetupScriptEnvOverridden`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SetupScriptEnvOverride {
+    /// The name of the environment variable.
+    pub key: String,
+
+    /// The most recent earlier setup script that set this variable.
+    pub previous_script_id: ScriptId,
+}
+
 /// A map of environment variables set by a setup script.
 ///
 /// Part of [`SetupScriptExecuteStatus`].
@@ -722,6 +1684,12 @@ pub struct RetryData {
 
     /// The total number of times this test can be run. Equal to `1 + retries`.
     pub total_attempts: usize,
+
+    /// The backoff strategy used to compute delays between attempts.
+    pub backoff: RetryBackoff,
+
+    /// True if randomness is added to delays between attempts.
+    pub jitter: bool,
 }
 
 impl RetryData {
@@ -741,7 +1709,13 @@ pub enum ExecutionResult {
     /// the test failed.
     ///
     /// This is treated as a pass.
-    Leak,
+    Leak {
+        /// What the test left behind, if it could be determined.
+        ///
+        /// This is `None` if the platform doesn't support enumerating leaked
+        /// resources.
+        details: Option<LeakDetails>,
+    },
     /// The test failed.
     Fail {
         /// The abort status of the test, if any (for example, the signal on Unix).
@@ -755,21 +1729,69 @@ pub enum ExecutionResult {
     /// An error occurred while executing the test.
     ExecFail,
     /// The test was terminated due to a timeout.
-    Timeout,
+    Timeout {
+        /// How the test was terminated.
+        termination: TimeoutTermination,
+    },
 }
 
 impl ExecutionResult {
     /// Returns true if the test was successful.
     pub fn is_success(self) -> bool {
         match self {
-            ExecutionResult::Pass | ExecutionResult::Leak => true,
-            ExecutionResult::Fail { .. } | ExecutionResult::ExecFail | ExecutionResult::Timeout => {
-                false
-            }
+            ExecutionResult::Pass | ExecutionResult::Leak { .. } => true,
+            ExecutionResult::Fail { .. }
+            | ExecutionResult::ExecFail
+            | ExecutionResult::Timeout { .. } => false,
+        }
+    }
+
+    /// Returns this result with any [`LeakDetails`] removed.
+    ///
+    /// Leak details depend on timing, so this is useful when comparing results
+    /// across runs.
+    pub fn without_leak_details(self) -> Self {
+        match self {
+            ExecutionResult::Leak { .. } => ExecutionResult::Leak { details: None },
+            other => other,
         }
     }
 }
 
+/// Resources a test or setup script left open after its main process exited.
+///
+/// Returned as part of the [`ExecutionResult::Leak`] variant. This is sampled
+/// immediately after the main process exits, before waiting for the leak
+/// timeout, so it may include processes that would have exited during it.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct LeakDetails {
+    /// The number of processes still running in the unit's process group.
+    pub processes: usize,
+
+    /// The number of file descriptors held open by those processes.
+    pub open_fds: usize,
+}
+
+/// How a test or setup script that timed out was terminated.
+///
+/// Returned as part of the [`ExecutionResult::Timeout`] variant.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TimeoutTermination {
+    /// The unit exited on its own within the grace period after being asked to
+    /// terminate (with `SIGTERM` on Unix).
+    ///
+    /// This usually means the unit was merely running for too long.
+    Graceful,
+
+    /// The unit was forcibly killed (with `SIGKILL` on Unix, or by terminating
+    /// its job object on Windows).
+    ///
+    /// This happens if the unit didn't exit within the grace period, which
+    /// often indicates that it ignores termination signals, or if the grace
+    /// period is zero.
+    Killed,
+}
+
 /// A regular exit code or Windows NT abort status for a test.
 ///
 /// Returned as part of the [`ExecutionResult::Fail`] variant.
@@ -786,6 +1808,10 @@ pub enum AbortStatus {
     /// The test was terminated via job object on Windows.
     #[cfg(windows)]
     JobObject,
+
+    /// The test failed after exceeding the memory limit set on its job object on Windows.
+    #[cfg(windows)]
+    MemoryLimit,
 }
 
 impl AbortStatus {
@@ -805,11 +1831,48 @@ impl AbortStatus {
             }
         }
     }
+
+    /// Returns true if the test failed because it exceeded its memory limit.
+    pub fn is_memory_limit(self) -> bool {
+        cfg_if::cfg_if! {
+            if #[cfg(windows)] {
+                self == AbortStatus::MemoryLimit
+            } else {
+                false
+            }
+        }
+    }
 }
 
-// Note: the order here matters -- it indicates severity of cancellation
-/// The reason why a test run is being cancelled.
+/// A phase of a staged run.
+///
+/// Part of [`TestEventKind::PhaseStarted`] and [`TestEventKind::PhaseFinished`].
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum RunPhase {
+    /// The smoke tests, run first.
+    Smoke,
+
+    /// All the other tests.
+    Main,
+}
+
+impl RunPhase {
+    pub(crate) fn to_static_str(self) -> &'static str {
+        match self {
+            RunPhase::Smoke => "smoke",
+            RunPhase::Main => "main",
+        }
+    }
+}
+
+/// The reason why a test run is being cancelled.
+///
+/// Reasons are ordered by increasing severity: a more severe reason replaces a less severe one
+/// if both occur during a run, and reporters compare against variants like [`Self::Signal`] to
+/// decide how much output to show. [`Self::Custom`] sits alongside the failure-driven reasons,
+/// above [`Self::SmokeTestFailure`] and below [`Self::ReportError`]. Custom reasons compare
+/// among themselves by their message.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
 #[cfg_attr(test, derive(test_strategy::Arbitrary))]
 pub enum CancelReason {
     /// A setup script failed.
@@ -818,6 +1881,21 @@ pub enum CancelReason {
     /// A test failed and --no-fail-fast wasn't specified.
     TestFailure,
 
+    /// A smoke test failed, and smoke tests gate the remaining tests.
+    SmokeTestFailure,
+
+    /// The cancel hook registered by the embedding application returned a reason.
+    ///
+    /// The run is cancelled the same way as for [`Self::TestFailure`]. For more, see
+    /// [`TestRunnerBuilder::set_cancel_hook`](crate::runner::TestRunnerBuilder::set_cancel_hook).
+    Custom(
+        #[cfg_attr(test, strategy(proptest::strategy::Strategy::prop_map(
+            proptest::prelude::any::<String>(),
+            Cow::Owned,
+        )))]
+        Cow<'static, str>,
+    ),
+
     /// An error occurred while reporting results.
     ReportError,
 
@@ -832,10 +1910,12 @@ pub enum CancelReason {
 }
 
 impl CancelReason {
-    pub(crate) fn to_static_str(self) -> &'static str {
+    pub(crate) fn to_static_str(&self) -> &str {
         match self {
             CancelReason::SetupScriptFailure => "setup script failure",
             CancelReason::TestFailure => "test failure",
+            CancelReason::SmokeTestFailure => "smoke test failure",
+            CancelReason::Custom(reason) => reason,
             CancelReason::ReportError => "reporting error",
             CancelReason::Signal => "signal",
             CancelReason::Interrupt => "interrupt",
@@ -843,6 +1923,33 @@ impl CancelReason {
         }
     }
 }
+
+/// The specific event that triggered a cancellation.
+///
+/// Part of [`TestEventKind::RunBeginCancel`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CancelTrigger<'a> {
+    /// A setup script failed.
+    SetupScriptFailure {
+        /// The identifier of the setup script that failed.
+        script_id: ScriptId,
+    },
+
+    /// A test failed, and the number of failures exceeded the max-fail
+    /// threshold.
+    TestFailure {
+        /// The test instance whose failure triggered cancellation.
+        test_instance: TestInstanceId<'a>,
+    },
+
+    /// A termination signal was received.
+    #[cfg(unix)]
+    Signal {
+        /// The signal number, e.g. `SIGTERM`.
+        signal: i32,
+    },
+}
+
 /// The kind of unit of work that nextest is executing.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum UnitKind {
@@ -894,6 +2001,26 @@ pub enum InfoResponse<'a> {
     Test(TestInfoResponse<'a>),
 }
 
+/// Tests that are expected to run but haven't started yet, as of an information request.
+///
+/// Part of [`TestEventKind::InfoFinished`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct QueuedTests<'a> {
+    /// The number of tests that haven't started yet.
+    pub count: usize,
+
+    /// The next few tests to be started, in the order they're queued in.
+    ///
+    /// This has at most [`QueuedTests::MAX_NEXT`] entries. Test groups and
+    /// `threads-required` can cause tests to start slightly out of this order.
+    pub next: Vec<TestInstanceId<'a>>,
+}
+
+impl QueuedTests<'_> {
+    /// The maximum number of entries in [`Self::next`].
+    pub const MAX_NEXT: usize = 5;
+}
+
 /// A setup script's response to an information request.
 #[derive(Clone, Debug)]
 pub struct SetupScriptInfoResponse<'a> {
@@ -925,6 +2052,11 @@ pub struct TestInfoResponse<'a> {
     /// The state of the test.
     pub state: UnitState,
 
+    /// The CPUs the test is pinned to, if CPU affinity was applied.
+    ///
+    /// CPU affinity is only applied on Linux, so this is always `None` on other platforms.
+    pub cpu_affinity: Option<CpuAffinity>,
+
     /// Output obtained from the test.
     pub output: ChildExecutionOutput,
 }
@@ -1018,6 +2150,36 @@ impl UnitState {
             UnitState::DelayBeforeNextAttempt { .. } => false,
         }
     }
+
+    /// Returns how much longer nextest will wait before the unit moves on to its next state.
+    ///
+    /// * For [`Exiting`](Self::Exiting), this is the time until the unit is marked leaky.
+    /// * For [`Terminating`](Self::Terminating), this is the time until the process is killed.
+    /// * For [`DelayBeforeNextAttempt`](Self::DelayBeforeNextAttempt), this is the time until the
+    ///   next attempt is started.
+    ///
+    /// Returns `None` for [`Running`](Self::Running) and [`Exited`](Self::Exited), since there is
+    /// no deadline for those states.
+    pub fn remaining_until_transition(&self) -> Option<Duration> {
+        match self {
+            UnitState::Exiting { remaining, .. }
+            | UnitState::Terminating(UnitTerminatingState { remaining, .. })
+            | UnitState::DelayBeforeNextAttempt { remaining, .. } => Some(*remaining),
+            UnitState::Running { .. } | UnitState::Exited { .. } => None,
+        }
+    }
+
+    /// Returns the time at which the unit is projected to move on to its next state, given that
+    /// the state was observed at `now`.
+    ///
+    /// Returns `None` in the same cases as [`Self::remaining_until_transition`].
+    pub fn projected_transition(
+        &self,
+        now: DateTime<FixedOffset>,
+    ) -> Option<DateTime<FixedOffset>> {
+        let remaining = TimeDelta::from_std(self.remaining_until_transition()?).ok()?;
+        now.checked_add_signed(remaining)
+    }
 }
 
 /// The current terminating state of a test or script process.
@@ -1131,6 +2293,333 @@ impl fmt::Display for UnitTerminateSignal {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_output::{ChildOutput, ChildSplitOutput};
+    use chrono::Local;
+
+    fn make_statuses(results: &[ExecutionResult]) -> ExecutionStatuses {
+        let total_attempts = results.len();
+        ExecutionStatuses::new(
+            results
+                .iter()
+                .enumerate()
+                .map(|(i, &result)| ExecuteStatus {
+                    retry_data: RetryData {
+                        attempt: i + 1,
+                        total_attempts,
+                        backoff: RetryBackoff::Fixed,
+                        jitter: false,
+                    },
+                    test_run_uuid: ReportUuid::nil(),
+                    attempt_uuid: ReportUuid::nil(),
+                    output: ChildExecutionOutput::Output {
+                        result: Some(result),
+                        output: ChildOutput::Split(ChildSplitOutput {
+                            stdout: None,
+                            stderr: None,
+                        }),
+                        errors: None,
+                    },
+                    result,
+                    start_time: Local::now().into(),
+                    time_taken: Duration::from_secs(1),
+                    is_slow: false,
+                    delay_before_start: Duration::ZERO,
+                    failure_phase: None,
+                    peak_rss: None,
+                    setup_time: None,
+                    io_wait: None,
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_on_test_finished_retries() {
+        let fail = ExecutionResult::Fail {
+            abort_status: None,
+            leaked: false,
+        };
+        let mut stats = RunStats::default();
+
+        stats.on_test_finished(&make_statuses(&[ExecutionResult::Pass]));
+        stats.on_test_finished(&make_statuses(&[fail]));
+        stats.on_test_finished(&make_statuses(&[fail, ExecutionResult::Pass]));
+        stats.on_test_finished(&make_statuses(&[fail, fail, fail]));
+        stats.on_test_finished(&make_statuses(&[
+            fail,
+            ExecutionResult::Timeout {
+                termination: TimeoutTermination::Graceful,
+            },
+        ]));
+        stats.on_test_finished(&make_statuses(&[fail, ExecutionResult::ExecFail]));
+        stats.on_test_finished(&make_statuses(&[ExecutionResult::Timeout {
+            termination: TimeoutTermination::Killed,
+        }]));
+
+        assert_eq!(stats.finished_count, 7);
+        assert_eq!(stats.passed, 2);
+        assert_eq!(stats.flaky, 1, "retried and passed");
+        assert_eq!(stats.failed, 2);
+        assert_eq!(stats.timed_out, 2);
+        assert_eq!(stats.timed_out_killed, 1, "one timed-out test was killed");
+        assert_eq!(stats.exec_failed, 1);
+        assert_eq!(
+            stats.failed_after_retries, 3,
+            "retried and still failed, excluding the failure on the first try"
+        );
+        assert_eq!(stats.failed_count(), 5);
+    }
+
+    #[test]
+    fn test_on_quarantined_test_finished() {
+        let fail = ExecutionResult::Fail {
+            abort_status: None,
+            leaked: false,
+        };
+        let mut stats = RunStats {
+            initial_run_count: 4,
+            ..RunStats::default()
+        };
+
+        stats.on_test_finished(&make_statuses(&[ExecutionResult::Pass]));
+        stats.on_quarantined_test_finished(&make_statuses(&[ExecutionResult::Pass]));
+        stats.on_quarantined_test_finished(&make_statuses(&[fail, fail]));
+        stats.on_quarantined_test_finished(&make_statuses(&[ExecutionResult::Timeout {
+            termination: TimeoutTermination::Killed,
+        }]));
+
+        assert_eq!(stats.finished_count, 4);
+        assert_eq!(
+            stats.passed, 1,
+            "quarantined tests aren't counted as passed"
+        );
+        assert_eq!(stats.quarantined_passed, 1);
+        assert_eq!(stats.quarantined_failed, 2);
+        assert_eq!(stats.failed_after_retries, 0);
+        assert_eq!(
+            stats.timed_out_killed, 0,
+            "quarantined timeouts aren't counted"
+        );
+        assert!(!stats.has_failures(), "quarantined failures are ignored");
+        assert_eq!(stats.summarize_final(), FinalRunStats::Success);
+    }
+
+    #[test]
+    fn test_total_test_time_and_parallelism_ratio() {
+        let fail = ExecutionResult::Fail {
+            abort_status: None,
+            leaked: false,
+        };
+        let mut stats = RunStats::default();
+        assert_eq!(
+            stats.parallelism_ratio(Duration::from_secs(1)),
+            0.0,
+            "no tests run => 0.0"
+        );
+
+        // Each attempt takes 1 second, and every attempt is counted.
+        stats.on_test_finished(&make_statuses(&[ExecutionResult::Pass]));
+        stats.on_test_finished(&make_statuses(&[fail, fail, ExecutionResult::Pass]));
+        assert_eq!(stats.total_test_time, Duration::from_secs(4));
+
+        // Setup scripts are counted separately.
+        stats.on_setup_script_finished(&SetupScriptExecuteStatus {
+            output: ChildExecutionOutput::Output {
+                result: Some(ExecutionResult::Pass),
+                output: ChildOutput::Split(ChildSplitOutput {
+                    stdout: None,
+                    stderr: None,
+                }),
+                errors: None,
+            },
+            result: ExecutionResult::Pass,
+            start_time: Local::now().into(),
+            time_taken: Duration::from_secs(3),
+            is_slow: false,
+            env_map: None,
+        });
+        assert_eq!(stats.total_test_time, Duration::from_secs(4));
+        assert_eq!(stats.setup_scripts_time, Duration::from_secs(3));
+
+        assert_eq!(stats.parallelism_ratio(Duration::from_secs(2)), 2.0);
+        assert_eq!(stats.parallelism_ratio(Duration::from_secs(8)), 0.5);
+        assert_eq!(
+            stats.parallelism_ratio(Duration::ZERO),
+            0.0,
+            "zero elapsed => 0.0, not NaN or infinity"
+        );
+    }
+
+    #[test]
+    fn test_duration_histogram() {
+        let finished = |result, time_taken| {
+            let mut statuses = make_statuses(&[result]);
+            statuses.statuses[0].time_taken = time_taken;
+            statuses
+        };
+        let mut histogram =
+            DurationHistogram::new(vec![Duration::from_millis(100), Duration::from_secs(1)]);
+        assert_eq!(histogram.counts(), [0, 0, 0]);
+
+        histogram.on_test_finished(&finished(ExecutionResult::Pass, Duration::from_millis(5)));
+        histogram.on_test_finished(&finished(ExecutionResult::Pass, Duration::from_millis(99)));
+        // Bounds are exclusive.
+        histogram.on_test_finished(&finished(ExecutionResult::Pass, Duration::from_millis(100)));
+        histogram.on_test_finished(&finished(ExecutionResult::ExecFail, Duration::ZERO));
+        histogram.on_test_finished(&finished(ExecutionResult::Pass, Duration::from_secs(3)));
+        // Tests that time out are always counted in the overflow bucket.
+        histogram.on_test_finished(&finished(
+            ExecutionResult::Timeout {
+                termination: TimeoutTermination::Graceful,
+            },
+            Duration::from_millis(50),
+        ));
+
+        assert_eq!(histogram.counts(), [3, 1, 2]);
+        assert_eq!(histogram.total(), 6);
+    }
+
+    #[test]
+    fn test_unit_state_transition() {
+        let now = DateTime::parse_from_rfc3339("2024-01-01T00:00:00+00:00").unwrap();
+
+        let running = UnitState::Running {
+            pid: 1,
+            time_taken: Duration::from_secs(5),
+            slow_after: Some(Duration::from_secs(2)),
+        };
+        assert_eq!(running.remaining_until_transition(), None);
+        assert_eq!(running.projected_transition(now), None);
+
+        let exiting = UnitState::Exiting {
+            pid: 1,
+            time_taken: Duration::from_secs(5),
+            slow_after: None,
+            tentative_result: Some(ExecutionResult::Pass),
+            waiting_duration: Duration::from_millis(50),
+            remaining: Duration::from_millis(150),
+        };
+        assert_eq!(
+            exiting.remaining_until_transition(),
+            Some(Duration::from_millis(150))
+        );
+        assert_eq!(
+            exiting.projected_transition(now),
+            Some(DateTime::parse_from_rfc3339("2024-01-01T00:00:00.15+00:00").unwrap())
+        );
+
+        let delay = UnitState::DelayBeforeNextAttempt {
+            previous_result: ExecutionResult::Fail {
+                leaked: false,
+                abort_status: None,
+            },
+            previous_slow: false,
+            waiting_duration: Duration::from_secs(1),
+            remaining: Duration::from_secs(2),
+        };
+        assert_eq!(
+            delay.projected_transition(now),
+            Some(DateTime::parse_from_rfc3339("2024-01-01T00:00:02+00:00").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_meets_min_tests() {
+        let stats = RunStats {
+            initial_run_count: 10,
+            finished_count: 10,
+            ..RunStats::default()
+        };
+        assert!(stats.meets_min_tests(0), "no minimum");
+        assert!(stats.meets_min_tests(9), "N - 1 => met");
+        assert!(stats.meets_min_tests(10), "N => met");
+        assert!(!stats.meets_min_tests(11), "N + 1 => not met");
+        assert!(
+            RunStats::default().meets_min_tests(0),
+            "empty run with no minimum => met"
+        );
+        assert!(
+            !RunStats::default().meets_min_tests(1),
+            "empty run with a minimum => not met"
+        );
+    }
+
+    #[test]
+    fn test_progress_fraction() {
+        let stats = |initial_run_count, finished_count| RunStats {
+            initial_run_count,
+            finished_count,
+            ..RunStats::default()
+        };
+        assert_eq!(stats(0, 0).progress_fraction(), 1.0, "no tests => done");
+        assert_eq!(stats(10, 0).progress_fraction(), 0.0);
+        assert_eq!(stats(8, 2).progress_fraction(), 0.25);
+        assert_eq!(stats(10, 10).progress_fraction(), 1.0);
+        assert_eq!(
+            stats(10, 12).progress_fraction(),
+            1.0,
+            "more finished than expected => clamped"
+        );
+    }
+
+    #[test]
+    fn test_aggregate_run_stats() {
+        let passing = RunStats {
+            initial_run_count: 3,
+            finished_count: 3,
+            passed: 3,
+            ..RunStats::default()
+        };
+        let failing = RunStats {
+            initial_run_count: 2,
+            finished_count: 2,
+            passed: 1,
+            failed: 1,
+            ..RunStats::default()
+        };
+        let cancelled = RunStats {
+            initial_run_count: 4,
+            finished_count: 1,
+            passed: 1,
+            ..RunStats::default()
+        };
+
+        let mut aggregate = AggregateRunStats::new();
+        assert_eq!(aggregate.summarize_final(), FinalRunStats::NoTestsRun);
+
+        aggregate.push("unit", Duration::from_secs(1), passing);
+        assert_eq!(aggregate.summarize_final(), FinalRunStats::Success);
+
+        aggregate.push("empty", Duration::from_secs(1), RunStats::default());
+        assert_eq!(
+            aggregate.summarize_final(),
+            FinalRunStats::NoTestsRun,
+            "a run with no tests is worse than a success"
+        );
+
+        aggregate.push("integration", Duration::from_secs(2), failing);
+        aggregate.push("cancelled", Duration::from_secs(3), cancelled);
+        assert_eq!(
+            aggregate.worst_run().map(|run| run.profile_name.as_str()),
+            Some("integration"),
+            "failures are worse than cancellations"
+        );
+        assert_eq!(
+            aggregate.summarize_final(),
+            FinalRunStats::Failed(RunStatsFailureKind::Test {
+                initial_run_count: 2,
+                not_run: 0,
+            })
+        );
+
+        let total = aggregate.total();
+        assert_eq!(total.initial_run_count, 9);
+        assert_eq!(total.finished_count, 6);
+        assert_eq!(total.passed, 5);
+        assert_eq!(total.failed, 1);
+        assert!(total.has_unfinished());
+        assert_eq!(aggregate.elapsed(), Duration::from_secs(7));
+    }
 
     #[test]
     fn test_is_success() {
@@ -1272,4 +2761,33 @@ mod tests {
             "setup scripts passed => success, but no tests run"
         );
     }
+
+    #[test]
+    fn test_collect_attachments() {
+        let dir = camino_tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("logs")).unwrap();
+        std::fs::write(dir.path().join("logs/test.log"), "log").unwrap();
+        let absolute = dir.path().join("screenshot.png");
+        std::fs::write(&absolute, "png").unwrap();
+
+        let attachments = TestAttachments::collect(
+            dir.path(),
+            &[
+                "logs/test.log".into(),
+                absolute.clone(),
+                "missing.txt".into(),
+                // Directories aren't valid attachments.
+                "logs".into(),
+            ],
+        );
+        assert_eq!(
+            attachments,
+            TestAttachments {
+                found: vec![dir.path().join("logs/test.log"), absolute],
+                missing: vec![dir.path().join("missing.txt"), dir.path().join("logs")],
+            }
+        );
+        assert!(!attachments.is_empty());
+        assert!(TestAttachments::collect(dir.path(), &[]).is_empty());
+    }
 }
@@ -7,14 +7,21 @@
 
 use super::{
     displayer::{DisplayReporter, DisplayReporterBuilder, StatusLevels},
-    FinalStatusLevel, StatusLevel, TestOutputDisplay,
+    AnsiEscapes, FinalStatusLevel, StatusLevel, TestOutputDisplay,
 };
 use crate::{
-    config::EvaluatableProfile,
+    config::{EvaluatableProfile, OnBrokenPipe},
     errors::WriteEventError,
     list::TestList,
-    reporter::{aggregator::EventAggregator, events::*, structured::StructuredReporter},
+    reporter::{
+        aggregator::EventAggregator, events::*, logical_suites::LogicalSuiteMembership,
+        prometheus::PrometheusReporter, structured::StructuredReporter,
+        test_name_groups::TestNameGroups,
+    },
 };
+use camino::Utf8PathBuf;
+use std::{io, sync::Arc};
+use tracing::debug;
 
 /// Standard error destination for the reporter.
 ///
@@ -36,11 +43,13 @@ pub struct ReporterBuilder {
     should_colorize: bool,
     failure_output: Option<TestOutputDisplay>,
     success_output: Option<TestOutputDisplay>,
+    display_ansi_escapes: Option<AnsiEscapes>,
     status_level: Option<StatusLevel>,
     final_status_level: Option<FinalStatusLevel>,
 
     verbose: bool,
     hide_progress_bar: bool,
+    prometheus_path: Option<Utf8PathBuf>,
 }
 
 impl ReporterBuilder {
@@ -71,6 +80,14 @@ impl ReporterBuilder {
         self
     }
 
+    /// Sets whether to strip or preserve ANSI escapes in captured test output when it's displayed.
+    ///
+    /// This overrides the `display-ansi-escapes` setting in the profile.
+    pub fn set_display_ansi_escapes(&mut self, display_ansi_escapes: AnsiEscapes) -> &mut Self {
+        self.display_ansi_escapes = Some(display_ansi_escapes);
+        self
+    }
+
     /// Sets the kinds of statuses to output.
     pub fn set_status_level(&mut self, status_level: StatusLevel) -> &mut Self {
         self.status_level = Some(status_level);
@@ -95,6 +112,16 @@ impl ReporterBuilder {
         self.hide_progress_bar = hide_progress_bar;
         self
     }
+
+    /// Sets a path to write run statistics to at the end of the run, in the Prometheus text
+    /// exposition format.
+    ///
+    /// The file is replaced atomically, so it can be placed in a directory read by the node
+    /// exporter's textfile collector.
+    pub fn set_prometheus_path(&mut self, path: Utf8PathBuf) -> &mut Self {
+        self.prometheus_path = Some(path);
+        self
+    }
 }
 
 impl ReporterBuilder {
@@ -106,7 +133,12 @@ impl ReporterBuilder {
         output: ReporterStderr<'a>,
         structured_reporter: StructuredReporter<'a>,
     ) -> Reporter<'a> {
-        let aggregator = EventAggregator::new(profile);
+        let ansi_escapes = self
+            .display_ansi_escapes
+            .unwrap_or_else(|| profile.display_ansi_escapes());
+        let logical_suites = Arc::new(LogicalSuiteMembership::new(profile, test_list));
+        let aggregator = EventAggregator::new(profile, logical_suites.clone());
+        let test_name_groups = TestNameGroups::new(profile, test_list);
 
         let status_level = self.status_level.unwrap_or_else(|| profile.status_level());
         let final_status_level = self
@@ -122,8 +154,13 @@ impl ReporterBuilder {
             test_count: test_list.test_count(),
             success_output: self.success_output,
             failure_output: self.failure_output,
+            ansi_escapes,
+            slow_message: profile.on_slow().message().map(ToOwned::to_owned),
+            logical_suites,
+            test_name_groups,
             should_colorize: self.should_colorize,
             no_capture: self.no_capture,
+            verbose: self.verbose,
             hide_progress_bar: self.hide_progress_bar,
         }
         .build(output);
@@ -132,6 +169,12 @@ impl ReporterBuilder {
             display_reporter,
             structured_reporter,
             metadata_reporter: aggregator,
+            prometheus_reporter: self.prometheus_path.clone().map(PrometheusReporter::new),
+            on_broken_pipe: profile.on_broken_pipe(),
+            display_closed: false,
+            structured_closed: false,
+            #[cfg(feature = "otel")]
+            otel_reporter: super::otel::OtelReporter::from_env(),
         }
     }
 }
@@ -145,6 +188,17 @@ pub struct Reporter<'a> {
     metadata_reporter: EventAggregator<'a>,
     /// Used to emit test events in machine-readable format(s) to stdout
     structured_reporter: StructuredReporter<'a>,
+    /// Used to write run statistics in the Prometheus format, if configured
+    prometheus_reporter: Option<PrometheusReporter>,
+    /// Used to export test results to an OpenTelemetry collector, if configured
+    #[cfg(feature = "otel")]
+    otel_reporter: Option<super::otel::OtelReporter>,
+    /// What to do if the reader of an output goes away
+    on_broken_pipe: OnBrokenPipe,
+    /// Set if standard error was closed by its reader and is no longer written to
+    display_closed: bool,
+    /// Set if structured output was closed by its reader and is no longer written to
+    structured_closed: bool,
 }
 
 impl<'a> Reporter<'a> {
@@ -158,6 +212,19 @@ impl<'a> Reporter<'a> {
         self.display_reporter.finish();
     }
 
+    /// Marks the reporter done, and returns the outputs it was built with.
+    ///
+    /// This is used to report a sequence of runs with different profiles to the same outputs. The
+    /// structured reporter is only created once, so formats that start with a header write it
+    /// once.
+    pub fn finish_into_outputs(mut self) -> (ReporterStderr<'a>, StructuredReporter<'a>) {
+        self.finish();
+        (
+            self.display_reporter.into_output(),
+            self.structured_reporter,
+        )
+    }
+
     // ---
     // Helper methods
     // ---
@@ -165,9 +232,109 @@ impl<'a> Reporter<'a> {
     /// Report this test event to the given writer.
     fn write_event(&mut self, event: TestEvent<'a>) -> Result<(), WriteEventError> {
         // TODO: write to all of these even if one of them fails?
-        self.display_reporter.write_event(&event)?;
-        self.structured_reporter.write_event(&event)?;
+        if !self.display_closed {
+            let res = self.display_reporter.write_event(&event);
+            handle_broken_pipe(res, self.on_broken_pipe, &mut self.display_closed)?;
+        }
+        if !self.structured_closed {
+            // RunStarted is the first event in a run, so this is called before anything is
+            // written to the structured reporter.
+            if let TestEventKind::RunStarted { run_id, .. } = &event.kind {
+                let res = self.structured_reporter.on_run_id(*run_id);
+                handle_broken_pipe(res, self.on_broken_pipe, &mut self.structured_closed)?;
+            }
+        }
+        if !self.structured_closed {
+            let res = self.structured_reporter.write_event(&event);
+            handle_broken_pipe(res, self.on_broken_pipe, &mut self.structured_closed)?;
+        }
+        #[cfg(feature = "otel")]
+        if let Some(otel_reporter) = &mut self.otel_reporter {
+            otel_reporter.write_event(&event);
+        }
+        if let Some(prometheus_reporter) = &mut self.prometheus_reporter {
+            prometheus_reporter.write_event(&event)?;
+        }
         self.metadata_reporter.write_event(event)?;
         Ok(())
     }
 }
+
+/// Handles an error writing to an output that may have been closed by its reader.
+///
+/// With [`OnBrokenPipe::Ignore`], a broken pipe marks the output as closed and isn't treated as an
+/// error. All other errors are returned as-is.
+fn handle_broken_pipe(
+    res: Result<(), WriteEventError>,
+    on_broken_pipe: OnBrokenPipe,
+    closed: &mut bool,
+) -> Result<(), WriteEventError> {
+    match res {
+        Err(WriteEventError::Io(error))
+            if error.kind() == io::ErrorKind::BrokenPipe
+                && on_broken_pipe == OnBrokenPipe::Ignore =>
+        {
+            debug!("output closed by its reader, no longer writing to it");
+            *closed = true;
+            Ok(())
+        }
+        res => res,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// A writer whose reader has gone away.
+    struct ClosedWriter;
+
+    impl Write for ClosedWriter {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+    }
+
+    fn write_closed() -> Result<(), WriteEventError> {
+        ClosedWriter
+            .write_all(b"PASS test\n")
+            .map_err(WriteEventError::Io)
+    }
+
+    #[test]
+    fn broken_pipe_ignore() {
+        let mut closed = false;
+        handle_broken_pipe(write_closed(), OnBrokenPipe::Ignore, &mut closed)
+            .expect("broken pipe is ignored");
+        assert!(closed, "output is marked closed");
+
+        // Other errors are still returned.
+        let mut closed = false;
+        let error = handle_broken_pipe(
+            Err(WriteEventError::Io(io::ErrorKind::PermissionDenied.into())),
+            OnBrokenPipe::Ignore,
+            &mut closed,
+        )
+        .expect_err("other errors are returned");
+        assert!(
+            matches!(error, WriteEventError::Io(error) if error.kind() == io::ErrorKind::PermissionDenied)
+        );
+        assert!(!closed, "output isn't marked closed");
+    }
+
+    #[test]
+    fn broken_pipe_cancel() {
+        let mut closed = false;
+        let error = handle_broken_pipe(write_closed(), OnBrokenPipe::Cancel, &mut closed)
+            .expect_err("broken pipe is returned as an error");
+        assert!(
+            matches!(error, WriteEventError::Io(error) if error.kind() == io::ErrorKind::BrokenPipe)
+        );
+        assert!(!closed, "output isn't marked closed");
+    }
+}
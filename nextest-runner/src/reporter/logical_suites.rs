@@ -0,0 +1,88 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Membership of tests in logical suites, used for reporting.
+//!
+//! Logical suites are defined in the `logical-suites` config table. Membership is computed once,
+//! when the reporter is built, and shared between the displayer (which reports per-suite
+//! statistics) and the JUnit aggregator (which writes out a testsuite per logical suite).
+
+use crate::{
+    config::EvaluatableProfile,
+    list::{TestInstanceId, TestList},
+};
+use nextest_metadata::RustBinaryId;
+use std::collections::HashMap;
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct LogicalSuiteMembership {
+    suites: Vec<LogicalSuiteInfo>,
+    // Binary ID -> test name -> indexes into `suites`.
+    tests: HashMap<RustBinaryId, HashMap<String, Vec<usize>>>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct LogicalSuiteInfo {
+    pub(crate) name: String,
+    pub(crate) filter: String,
+    // The number of tests in this suite that are expected to run.
+    pub(crate) initial_run_count: usize,
+}
+
+impl LogicalSuiteMembership {
+    pub(crate) fn new(profile: &EvaluatableProfile<'_>, test_list: &TestList<'_>) -> Self {
+        let logical_suites: Vec<_> = profile.logical_suites().collect();
+        if logical_suites.is_empty() {
+            return Self::default();
+        }
+
+        let mut suites: Vec<_> = logical_suites
+            .iter()
+            .map(|suite| LogicalSuiteInfo {
+                name: suite.name().to_owned(),
+                filter: suite.filter().to_owned(),
+                initial_run_count: 0,
+            })
+            .collect();
+
+        let ecx = profile.filterset_ecx();
+        let mut tests: HashMap<RustBinaryId, HashMap<String, Vec<usize>>> = HashMap::new();
+        for test_instance in test_list.iter_tests() {
+            let query = test_instance.to_test_query();
+            let indexes: Vec<usize> = logical_suites
+                .iter()
+                .enumerate()
+                .filter(|(_, suite)| suite.expr().matches_test(&query, &ecx))
+                .map(|(index, _)| index)
+                .collect();
+            if indexes.is_empty() {
+                continue;
+            }
+
+            if test_instance.test_info.filter_match.is_match() {
+                for &index in &indexes {
+                    suites[index].initial_run_count += 1;
+                }
+            }
+            tests
+                .entry(test_instance.suite_info.binary_id.clone())
+                .or_default()
+                .insert(test_instance.name.to_owned(), indexes);
+        }
+
+        Self { suites, tests }
+    }
+
+    /// Returns the logical suites, sorted by name.
+    pub(crate) fn suites(&self) -> &[LogicalSuiteInfo] {
+        &self.suites
+    }
+
+    /// Returns the indexes (into [`Self::suites`]) of the logical suites a test belongs to.
+    pub(crate) fn suites_for(&self, test_instance: TestInstanceId<'_>) -> &[usize] {
+        self.tests
+            .get(test_instance.binary_id)
+            .and_then(|tests| tests.get(test_instance.test_name))
+            .map_or(&[], |indexes| indexes.as_slice())
+    }
+}
@@ -11,9 +11,16 @@ mod error_description;
 pub mod events;
 mod helpers;
 mod imp;
+mod logical_suites;
+#[cfg(feature = "otel")]
+mod otel;
+mod prometheus;
 pub mod structured;
+mod test_name_groups;
 
-pub use displayer::{FinalStatusLevel, StatusLevel, TestOutputDisplay};
+pub use displayer::{
+    write_aggregate_summary, AnsiEscapes, FinalStatusLevel, StatusLevel, TestOutputDisplay,
+};
 pub use error_description::*;
 pub use helpers::highlight_end;
 pub use imp::*;
@@ -0,0 +1,634 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Export test results to an OpenTelemetry collector.
+//!
+//! Each test attempt is exported as a span, parented to a single span for the whole run. The
+//! trace ID is derived from the run ID, so traces can be correlated with JUnit reports and other
+//! output.
+//!
+//! Spans are sent over OTLP/HTTP using the JSON encoding. The exporter is configured through the
+//! standard `OTEL_*` environment variables, and is only enabled if an endpoint is specified.
+//! Exporting happens on a background thread so that slow or unavailable collectors don't hold up
+//! the test run.
+
+use crate::{
+    list::TestInstance,
+    reporter::events::{ExecuteStatus, ExecutionResult, RunStats, TestEvent, TestEventKind},
+};
+use chrono::{DateTime, FixedOffset};
+use newtype_uuid::GenericUuid;
+use nextest_metadata::BuildPlatform;
+use rand::Rng;
+use serde::Serialize;
+use std::{sync::mpsc, thread, time::Duration};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tracing::{debug, warn};
+
+/// Configuration for the OpenTelemetry exporter, read from the environment.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct OtelConfig {
+    /// The full URL spans are sent to.
+    traces_endpoint: String,
+    /// Extra headers to send with each request.
+    headers: Vec<(String, String)>,
+    /// The `service.name` resource attribute.
+    service_name: String,
+    /// The timeout for each export, and for flushing spans at the end of the run.
+    timeout: Duration,
+}
+
+impl OtelConfig {
+    const DEFAULT_SERVICE_NAME: &'static str = "cargo-nextest";
+    const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Reads the configuration from the process environment.
+    ///
+    /// Returns `None` if no OTLP endpoint is specified.
+    pub(crate) fn from_env() -> Option<Self> {
+        Self::from_env_vars(|name| std::env::var(name).ok())
+    }
+
+    fn from_env_vars(var: impl Fn(&str) -> Option<String>) -> Option<Self> {
+        let var = |name: &str| var(name).filter(|value| !value.is_empty());
+
+        // Per the OTLP exporter specification, the signal-specific endpoint is used as-is, while
+        // the generic endpoint has the signal path appended to it.
+        let traces_endpoint = match var("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT") {
+            Some(endpoint) => endpoint,
+            None => {
+                let endpoint = var("OTEL_EXPORTER_OTLP_ENDPOINT")?;
+                format!("{}/v1/traces", endpoint.trim_end_matches('/'))
+            }
+        };
+
+        let headers = var("OTEL_EXPORTER_OTLP_TRACES_HEADERS")
+            .or_else(|| var("OTEL_EXPORTER_OTLP_HEADERS"))
+            .map(|headers| parse_headers(&headers))
+            .unwrap_or_default();
+
+        let timeout = var("OTEL_EXPORTER_OTLP_TRACES_TIMEOUT")
+            .or_else(|| var("OTEL_EXPORTER_OTLP_TIMEOUT"))
+            .and_then(|timeout| match timeout.parse::<u64>() {
+                Ok(millis) => Some(Duration::from_millis(millis)),
+                Err(_) => {
+                    warn!("ignoring invalid OTLP timeout `{timeout}` (expected milliseconds)");
+                    None
+                }
+            })
+            .unwrap_or(Self::DEFAULT_TIMEOUT);
+
+        Some(Self {
+            traces_endpoint,
+            headers,
+            service_name: var("OTEL_SERVICE_NAME")
+                .unwrap_or_else(|| Self::DEFAULT_SERVICE_NAME.to_owned()),
+            timeout,
+        })
+    }
+}
+
+/// Parses headers in the `key1=value1,key2=value2` format used by `OTEL_EXPORTER_OTLP_HEADERS`.
+fn parse_headers(headers: &str) -> Vec<(String, String)> {
+    headers
+        .split(',')
+        .filter(|pair| !pair.trim().is_empty())
+        .filter_map(|pair| match pair.split_once('=') {
+            Some((key, value)) => Some((key.trim().to_owned(), value.trim().to_owned())),
+            None => {
+                warn!("ignoring invalid OTLP header `{pair}` (expected `key=value`)");
+                None
+            }
+        })
+        .collect()
+}
+
+/// Reports test events as OpenTelemetry spans.
+pub(crate) struct OtelReporter {
+    exporter: Exporter,
+    trace_id: u128,
+    root_span_id: u64,
+    run_id: String,
+    profile_name: String,
+    host_triple: String,
+    target_triple: Option<String>,
+    // The filter summary for the run, serialized as JSON.
+    filter: Option<String>,
+}
+
+impl OtelReporter {
+    /// Creates a new reporter if an OTLP endpoint is configured in the environment.
+    pub(crate) fn from_env() -> Option<Self> {
+        let config = OtelConfig::from_env()?;
+        debug!(
+            "exporting test results to OpenTelemetry collector at {}",
+            config.traces_endpoint
+        );
+        Some(Self {
+            exporter: Exporter::new(config),
+            trace_id: 0,
+            root_span_id: new_span_id(),
+            run_id: String::new(),
+            profile_name: String::new(),
+            host_triple: String::new(),
+            target_triple: None,
+            filter: None,
+        })
+    }
+
+    pub(crate) fn write_event(&mut self, event: &TestEvent<'_>) {
+        match &event.kind {
+            TestEventKind::RunStarted {
+                test_list,
+                run_id,
+                profile_name,
+                filter,
+                ..
+            } => {
+                self.trace_id = run_id.into_untyped_uuid().as_u128();
+                self.run_id = run_id.to_string();
+                self.profile_name = profile_name.clone();
+                self.filter = filter
+                    .as_ref()
+                    .and_then(|filter| serde_json::to_string(filter).ok());
+
+                let build_platforms = &test_list.rust_build_meta().build_platforms;
+                self.host_triple = build_platforms.host.platform.triple_str().to_owned();
+                self.target_triple = build_platforms
+                    .target
+                    .as_ref()
+                    .map(|target| target.triple.platform.triple_str().to_owned());
+            }
+            TestEventKind::TestFinished {
+                test_instance,
+                run_statuses,
+                ..
+            } => {
+                let spans = run_statuses
+                    .iter()
+                    .map(|status| self.attempt_span(test_instance, status))
+                    .collect();
+                self.exporter.export(spans);
+            }
+            TestEventKind::RunFinished {
+                start_time,
+                elapsed,
+                run_stats,
+                ..
+            } => {
+                let span = self.run_span(*start_time, *elapsed, run_stats);
+                self.exporter.export(vec![span]);
+                self.exporter.finish();
+            }
+            _ => {}
+        }
+    }
+
+    fn attempt_span(&self, test_instance: &TestInstance<'_>, status: &ExecuteStatus) -> Span {
+        let binary_id = &test_instance.suite_info.binary_id;
+        let platform = match test_instance.suite_info.build_platform {
+            BuildPlatform::Host => &self.host_triple,
+            BuildPlatform::Target => self.target_triple.as_ref().unwrap_or(&self.host_triple),
+        };
+
+        let status_code = if status.result.is_success() {
+            SpanStatus::ok()
+        } else {
+            SpanStatus::error(format!("test {}", result_str(status.result)))
+        };
+
+        Span {
+            trace_id: format!("{:032x}", self.trace_id),
+            span_id: format!("{:016x}", new_span_id()),
+            parent_span_id: Some(format!("{:016x}", self.root_span_id)),
+            name: format!("{binary_id} {}", test_instance.name),
+            kind: SPAN_KIND_INTERNAL,
+            start_time_unix_nano: unix_nanos(status.start_time),
+            end_time_unix_nano: unix_nanos_after(status.start_time, status.time_taken),
+            attributes: vec![
+                KeyValue::string("nextest.run_id", &self.run_id),
+                KeyValue::string("nextest.binary_id", binary_id.as_str()),
+                KeyValue::string("nextest.test_name", test_instance.name),
+                KeyValue::string("nextest.platform", platform),
+                KeyValue::string("nextest.result", result_str(status.result)),
+                KeyValue::int("nextest.attempt", status.retry_data.attempt),
+                KeyValue::int("nextest.total_attempts", status.retry_data.total_attempts),
+                KeyValue::double("nextest.duration_secs", status.time_taken.as_secs_f64()),
+            ],
+            status: status_code,
+        }
+    }
+
+    fn run_span(
+        &self,
+        start_time: DateTime<FixedOffset>,
+        elapsed: Duration,
+        run_stats: &RunStats,
+    ) -> Span {
+        let status = if run_stats.has_failures() {
+            SpanStatus::error(format!("{} tests failed", run_stats.failed_count()))
+        } else {
+            SpanStatus::ok()
+        };
+
+        let mut attributes = vec![
+            KeyValue::string("nextest.run_id", &self.run_id),
+            KeyValue::string("nextest.profile", &self.profile_name),
+            KeyValue::int("nextest.tests.finished", run_stats.finished_count),
+            KeyValue::int("nextest.tests.passed", run_stats.passed),
+            KeyValue::int("nextest.tests.failed", run_stats.failed_count()),
+            KeyValue::double("nextest.duration_secs", elapsed.as_secs_f64()),
+        ];
+        if let Some(filter) = &self.filter {
+            attributes.push(KeyValue::string("nextest.filter", filter));
+        }
+
+        Span {
+            trace_id: format!("{:032x}", self.trace_id),
+            span_id: format!("{:016x}", self.root_span_id),
+            parent_span_id: None,
+            name: "nextest run".to_owned(),
+            kind: SPAN_KIND_INTERNAL,
+            start_time_unix_nano: unix_nanos(start_time),
+            end_time_unix_nano: unix_nanos_after(start_time, elapsed),
+            attributes,
+            status,
+        }
+    }
+}
+
+fn result_str(result: ExecutionResult) -> &'static str {
+    match result {
+        ExecutionResult::Pass => "pass",
+        ExecutionResult::Leak { .. } => "leak",
+        ExecutionResult::Fail { .. } => "fail",
+        ExecutionResult::ExecFail => "exec-fail",
+        ExecutionResult::Timeout { .. } => "timeout",
+    }
+}
+
+fn new_span_id() -> u64 {
+    // Span IDs must be non-zero.
+    rand::thread_rng().gen_range(1..=u64::MAX)
+}
+
+fn unix_nanos(time: DateTime<FixedOffset>) -> String {
+    // OTLP/JSON encodes 64-bit integers as strings.
+    time.timestamp_nanos_opt().unwrap_or_default().to_string()
+}
+
+fn unix_nanos_after(time: DateTime<FixedOffset>, duration: Duration) -> String {
+    let end = chrono::Duration::from_std(duration)
+        .ok()
+        .and_then(|duration| time.checked_add_signed(duration))
+        .unwrap_or(time);
+    unix_nanos(end)
+}
+
+/// Sends spans to the collector from a background thread.
+struct Exporter {
+    sender: Option<UnboundedSender<Vec<Span>>>,
+    done: mpsc::Receiver<()>,
+    endpoint: String,
+    timeout: Duration,
+}
+
+impl Exporter {
+    fn new(config: OtelConfig) -> Self {
+        let (sender, receiver) = unbounded_channel();
+        let (done_sender, done) = mpsc::channel();
+        let endpoint = config.traces_endpoint.clone();
+        let timeout = config.timeout;
+
+        let spawn_result = thread::Builder::new()
+            .name("nextest-otel-exporter".to_owned())
+            .spawn(move || {
+                run_exporter(config, receiver);
+                _ = done_sender.send(());
+            });
+        let sender = match spawn_result {
+            Ok(_) => Some(sender),
+            Err(error) => {
+                warn!("failed to start OpenTelemetry exporter: {error}");
+                None
+            }
+        };
+
+        Self {
+            sender,
+            done,
+            endpoint,
+            timeout,
+        }
+    }
+
+    fn export(&self, spans: Vec<Span>) {
+        if let Some(sender) = &self.sender {
+            // This never blocks: the channel is unbounded.
+            _ = sender.send(spans);
+        }
+    }
+
+    /// Waits for all pending spans to be exported, up to the configured timeout.
+    fn finish(&mut self) {
+        if self.sender.take().is_none() {
+            return;
+        }
+        if self.done.recv_timeout(self.timeout).is_err() {
+            warn!(
+                "timed out exporting test results to OpenTelemetry collector at {}",
+                self.endpoint
+            );
+        }
+    }
+}
+
+fn run_exporter(config: OtelConfig, mut receiver: UnboundedReceiver<Vec<Span>>) {
+    let runtime = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(error) => {
+            warn!("failed to start OpenTelemetry exporter: {error}");
+            return;
+        }
+    };
+
+    runtime.block_on(async move {
+        let client = match reqwest::Client::builder().timeout(config.timeout).build() {
+            Ok(client) => client,
+            Err(error) => {
+                warn!("failed to start OpenTelemetry exporter: {error}");
+                return;
+            }
+        };
+
+        while let Some(mut spans) = receiver.recv().await {
+            // Batch up any other spans that arrived while the previous request was in flight.
+            while let Ok(more) = receiver.try_recv() {
+                spans.extend(more);
+            }
+
+            let body = match serde_json::to_vec(&ExportTraceServiceRequest::new(&config, spans)) {
+                Ok(body) => body,
+                Err(error) => {
+                    warn!("failed to serialize OpenTelemetry spans: {error}");
+                    continue;
+                }
+            };
+
+            let mut request = client
+                .post(&config.traces_endpoint)
+                .header("content-type", "application/json")
+                .body(body);
+            for (key, value) in &config.headers {
+                request = request.header(key, value);
+            }
+
+            match request
+                .send()
+                .await
+                .and_then(|resp| resp.error_for_status())
+            {
+                Ok(_) => {}
+                Err(error) => {
+                    warn!(
+                        "error exporting test results to OpenTelemetry collector at {}: {error}",
+                        config.traces_endpoint
+                    );
+                }
+            }
+        }
+    });
+}
+
+// ---
+// OTLP/JSON types
+//
+// See https://opentelemetry.io/docs/specs/otlp/#json-protobuf-encoding. Trace and span IDs are
+// hex-encoded, enums are integers, and 64-bit integers are strings.
+// ---
+
+const SPAN_KIND_INTERNAL: u8 = 1;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportTraceServiceRequest {
+    resource_spans: Vec<ResourceSpans>,
+}
+
+impl ExportTraceServiceRequest {
+    fn new(config: &OtelConfig, spans: Vec<Span>) -> Self {
+        Self {
+            resource_spans: vec![ResourceSpans {
+                resource: Resource {
+                    attributes: vec![KeyValue::string("service.name", &config.service_name)],
+                },
+                scope_spans: vec![ScopeSpans {
+                    scope: Scope {
+                        name: env!("CARGO_PKG_NAME"),
+                        version: env!("CARGO_PKG_VERSION"),
+                    },
+                    spans,
+                }],
+            }],
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ResourceSpans {
+    resource: Resource,
+    scope_spans: Vec<ScopeSpans>,
+}
+
+#[derive(Serialize)]
+struct Resource {
+    attributes: Vec<KeyValue>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScopeSpans {
+    scope: Scope,
+    spans: Vec<Span>,
+}
+
+#[derive(Serialize)]
+struct Scope {
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Span {
+    trace_id: String,
+    span_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parent_span_id: Option<String>,
+    name: String,
+    kind: u8,
+    start_time_unix_nano: String,
+    end_time_unix_nano: String,
+    attributes: Vec<KeyValue>,
+    status: SpanStatus,
+}
+
+#[derive(Debug, Serialize)]
+struct SpanStatus {
+    code: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+}
+
+impl SpanStatus {
+    fn ok() -> Self {
+        Self {
+            code: 1,
+            message: None,
+        }
+    }
+
+    fn error(message: String) -> Self {
+        Self {
+            code: 2,
+            message: Some(message),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct KeyValue {
+    key: &'static str,
+    value: AnyValue,
+}
+
+impl KeyValue {
+    fn string(key: &'static str, value: &str) -> Self {
+        Self {
+            key,
+            value: AnyValue::String(value.to_owned()),
+        }
+    }
+
+    fn int(key: &'static str, value: usize) -> Self {
+        Self {
+            key,
+            value: AnyValue::Int(value.to_string()),
+        }
+    }
+
+    fn double(key: &'static str, value: f64) -> Self {
+        Self {
+            key,
+            value: AnyValue::Double(value),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+enum AnyValue {
+    #[serde(rename = "stringValue")]
+    String(String),
+    #[serde(rename = "intValue")]
+    Int(String),
+    #[serde(rename = "doubleValue")]
+    Double(f64),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn config_from_env_vars() {
+        let config_for = |vars: &[(&str, &str)]| {
+            let vars: HashMap<_, _> = vars.iter().copied().collect();
+            OtelConfig::from_env_vars(|name| vars.get(name).map(|value| (*value).to_owned()))
+        };
+
+        assert_eq!(config_for(&[]), None, "no endpoint => disabled");
+        assert_eq!(
+            config_for(&[("OTEL_EXPORTER_OTLP_ENDPOINT", "")]),
+            None,
+            "empty endpoint => disabled"
+        );
+
+        assert_eq!(
+            config_for(&[("OTEL_EXPORTER_OTLP_ENDPOINT", "http://localhost:4318/")]),
+            Some(OtelConfig {
+                traces_endpoint: "http://localhost:4318/v1/traces".to_owned(),
+                headers: vec![],
+                service_name: "cargo-nextest".to_owned(),
+                timeout: Duration::from_secs(10),
+            }),
+        );
+
+        assert_eq!(
+            config_for(&[
+                ("OTEL_EXPORTER_OTLP_ENDPOINT", "http://localhost:4318"),
+                (
+                    "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+                    "http://collector/traces"
+                ),
+                (
+                    "OTEL_EXPORTER_OTLP_HEADERS",
+                    "api-key=secret, x-team = ci,invalid"
+                ),
+                ("OTEL_EXPORTER_OTLP_TIMEOUT", "500"),
+                ("OTEL_SERVICE_NAME", "my-tests"),
+            ]),
+            Some(OtelConfig {
+                traces_endpoint: "http://collector/traces".to_owned(),
+                headers: vec![
+                    ("api-key".to_owned(), "secret".to_owned()),
+                    ("x-team".to_owned(), "ci".to_owned()),
+                ],
+                service_name: "my-tests".to_owned(),
+                timeout: Duration::from_millis(500),
+            }),
+        );
+    }
+
+    #[test]
+    fn span_serialization() {
+        let start_time = DateTime::parse_from_rfc3339("2025-01-01T00:00:00Z").unwrap();
+        let span = Span {
+            trace_id: format!("{:032x}", 0xabcd_u128),
+            span_id: format!("{:016x}", 0x12_u64),
+            parent_span_id: None,
+            name: "my-package::my-binary test_foo".to_owned(),
+            kind: SPAN_KIND_INTERNAL,
+            start_time_unix_nano: unix_nanos(start_time),
+            end_time_unix_nano: unix_nanos_after(start_time, Duration::from_millis(1500)),
+            attributes: vec![
+                KeyValue::string("nextest.result", "pass"),
+                KeyValue::int("nextest.attempt", 1),
+                KeyValue::double("nextest.duration_secs", 1.5),
+            ],
+            status: SpanStatus::ok(),
+        };
+
+        assert_eq!(
+            serde_json::to_value(&span).unwrap(),
+            serde_json::json!({
+                "traceId": "0000000000000000000000000000abcd",
+                "spanId": "0000000000000012",
+                "name": "my-package::my-binary test_foo",
+                "kind": 1,
+                "startTimeUnixNano": "1735689600000000000",
+                "endTimeUnixNano": "1735689601500000000",
+                "attributes": [
+                    { "key": "nextest.result", "value": { "stringValue": "pass" } },
+                    { "key": "nextest.attempt", "value": { "intValue": "1" } },
+                    { "key": "nextest.duration_secs", "value": { "doubleValue": 1.5 } },
+                ],
+                "status": { "code": 1 },
+            }),
+        );
+    }
+}
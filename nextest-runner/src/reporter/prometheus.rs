@@ -0,0 +1,254 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Export run statistics in the Prometheus text exposition format.
+//!
+//! At the end of a run, the final [`RunStats`] are written out as a set of gauges, suitable for
+//! the node exporter's textfile collector. The file is replaced atomically, so a scrape never sees
+//! a partially-written file.
+
+use crate::{
+    errors::WriteEventError,
+    reporter::events::{RunStats, TestEvent, TestEventKind},
+};
+use atomicwrites::{AtomicFile, OverwriteBehavior};
+use camino::Utf8PathBuf;
+use chrono::{DateTime, FixedOffset};
+use std::{io::Write as _, time::Duration};
+use swrite::{swriteln, SWrite};
+
+#[derive(Debug)]
+pub(crate) struct PrometheusReporter {
+    path: Utf8PathBuf,
+    // Set by the RunStarted event.
+    profile_name: Option<String>,
+}
+
+impl PrometheusReporter {
+    pub(crate) fn new(path: Utf8PathBuf) -> Self {
+        Self {
+            path,
+            profile_name: None,
+        }
+    }
+
+    pub(crate) fn write_event(&mut self, event: &TestEvent<'_>) -> Result<(), WriteEventError> {
+        match &event.kind {
+            TestEventKind::RunStarted { profile_name, .. } => {
+                self.profile_name = Some(profile_name.clone());
+            }
+            TestEventKind::RunFinished {
+                start_time,
+                elapsed,
+                run_stats,
+                ..
+            } => {
+                let profile_name = self.profile_name.as_deref().unwrap_or_default();
+                let metrics = render_metrics(profile_name, *start_time, *elapsed, run_stats);
+
+                let file = AtomicFile::new(&self.path, OverwriteBehavior::AllowOverwrite);
+                file.write(|f| f.write_all(metrics.as_bytes()))
+                    .map_err(|error| WriteEventError::Fs {
+                        file: self.path.clone(),
+                        error: match error {
+                            atomicwrites::Error::Internal(error)
+                            | atomicwrites::Error::User(error) => error,
+                        },
+                    })?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Renders the final statistics for a run as Prometheus metrics.
+///
+/// Metric names are part of nextest's stable interface. Each metric is a gauge, since the file is
+/// overwritten by every run.
+fn render_metrics(
+    profile_name: &str,
+    start_time: DateTime<FixedOffset>,
+    elapsed: Duration,
+    run_stats: &RunStats,
+) -> String {
+    let labels = format!("{{profile=\"{}\"}}", escape_label_value(profile_name));
+    let success = u8::from(!run_stats.has_failures() && !run_stats.has_unfinished());
+
+    let metrics: [(&str, &str, String); 17] = [
+        (
+            "nextest_tests_initial",
+            "Number of tests that were expected to run.",
+            run_stats.initial_run_count.to_string(),
+        ),
+        (
+            "nextest_tests_finished",
+            "Number of tests that finished running.",
+            run_stats.finished_count.to_string(),
+        ),
+        (
+            "nextest_tests_passed",
+            "Number of tests that passed, including flaky and leaky tests.",
+            run_stats.passed.to_string(),
+        ),
+        (
+            "nextest_tests_failed",
+            "Number of tests that failed, timed out, or could not be executed.",
+            run_stats.failed_count().to_string(),
+        ),
+        (
+            "nextest_tests_flaky",
+            "Number of tests that passed on retry.",
+            run_stats.flaky.to_string(),
+        ),
+        (
+            "nextest_tests_timed_out",
+            "Number of tests that timed out.",
+            run_stats.timed_out.to_string(),
+        ),
+        (
+            "nextest_tests_exec_failed",
+            "Number of tests that could not be executed.",
+            run_stats.exec_failed.to_string(),
+        ),
+        (
+            "nextest_tests_leaky",
+            "Number of tests that passed but leaked handles.",
+            run_stats.leaky.to_string(),
+        ),
+        (
+            "nextest_tests_skipped",
+            "Number of tests that were skipped.",
+            run_stats.skipped.to_string(),
+        ),
+        (
+            "nextest_tests_quarantined_passed",
+            "Number of quarantined tests that passed.",
+            run_stats.quarantined_passed.to_string(),
+        ),
+        (
+            "nextest_tests_quarantined_failed",
+            "Number of quarantined tests that failed.",
+            run_stats.quarantined_failed.to_string(),
+        ),
+        (
+            "nextest_setup_scripts_failed",
+            "Number of setup scripts that did not pass.",
+            run_stats.failed_setup_script_count().to_string(),
+        ),
+        (
+            "nextest_teardown_scripts_failed",
+            "Number of teardown scripts that did not pass.",
+            run_stats.teardown_scripts_failed.to_string(),
+        ),
+        (
+            "nextest_run_duration_seconds",
+            "Time taken by the run.",
+            elapsed.as_secs_f64().to_string(),
+        ),
+        (
+            "nextest_tests_duration_seconds",
+            "Time taken by tests, summed over every attempt.",
+            run_stats.total_test_time.as_secs_f64().to_string(),
+        ),
+        (
+            "nextest_run_start_time_seconds",
+            "Time at which the run started, in seconds since the Unix epoch.",
+            (start_time.timestamp_millis() as f64 / 1000.0).to_string(),
+        ),
+        (
+            "nextest_run_success",
+            "1 if every test and setup script that was expected to run passed, 0 otherwise.",
+            success.to_string(),
+        ),
+    ];
+
+    let mut out = String::new();
+    for (name, help, value) in metrics {
+        swriteln!(out, "# HELP {name} {help}");
+        swriteln!(out, "# TYPE {name} gauge");
+        swriteln!(out, "{name}{labels} {value}");
+    }
+    out
+}
+
+/// Escapes a label value as required by the text exposition format.
+fn escape_label_value(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_metrics_output() {
+        let run_stats = RunStats {
+            initial_run_count: 10,
+            finished_count: 9,
+            passed: 6,
+            flaky: 1,
+            failed: 2,
+            timed_out: 1,
+            skipped: 3,
+            total_test_time: Duration::from_millis(12500),
+            ..RunStats::default()
+        };
+        let start_time = DateTime::parse_from_rfc3339("2024-01-01T00:00:00.5+00:00").unwrap();
+        let metrics = render_metrics(
+            "ci \"nightly\"",
+            start_time,
+            Duration::from_millis(4250),
+            &run_stats,
+        );
+
+        let samples: Vec<_> = metrics
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .collect();
+        assert_eq!(
+            samples,
+            [
+                r#"nextest_tests_initial{profile="ci \"nightly\""} 10"#,
+                r#"nextest_tests_finished{profile="ci \"nightly\""} 9"#,
+                r#"nextest_tests_passed{profile="ci \"nightly\""} 6"#,
+                r#"nextest_tests_failed{profile="ci \"nightly\""} 3"#,
+                r#"nextest_tests_flaky{profile="ci \"nightly\""} 1"#,
+                r#"nextest_tests_timed_out{profile="ci \"nightly\""} 1"#,
+                r#"nextest_tests_exec_failed{profile="ci \"nightly\""} 0"#,
+                r#"nextest_tests_leaky{profile="ci \"nightly\""} 0"#,
+                r#"nextest_tests_skipped{profile="ci \"nightly\""} 3"#,
+                r#"nextest_tests_quarantined_passed{profile="ci \"nightly\""} 0"#,
+                r#"nextest_tests_quarantined_failed{profile="ci \"nightly\""} 0"#,
+                r#"nextest_setup_scripts_failed{profile="ci \"nightly\""} 0"#,
+                r#"nextest_teardown_scripts_failed{profile="ci \"nightly\""} 0"#,
+                r#"nextest_run_duration_seconds{profile="ci \"nightly\""} 4.25"#,
+                r#"nextest_tests_duration_seconds{profile="ci \"nightly\""} 12.5"#,
+                r#"nextest_run_start_time_seconds{profile="ci \"nightly\""} 1704067200.5"#,
+                r#"nextest_run_success{profile="ci \"nightly\""} 0"#,
+            ]
+        );
+        assert!(
+            metrics.starts_with(
+                "# HELP nextest_tests_initial Number of tests that were expected to run.\n\
+                 # TYPE nextest_tests_initial gauge\n"
+            ),
+            "metrics start with HELP and TYPE lines: {metrics}"
+        );
+    }
+
+    #[test]
+    fn escape_label_values() {
+        assert_eq!(escape_label_value("default"), "default");
+        assert_eq!(escape_label_value("a\\b\"c\nd"), "a\\\\b\\\"c\\nd");
+    }
+}
@@ -0,0 +1,781 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! A compact, length-prefixed binary log of test events.
+//!
+//! Line-delimited JSON is verbose for very large test suites. The binary event log carries the
+//! same information as [`EventLogRecord`] in much less space, and is meant for tools, such as
+//! local supervisors, that consume nextest's output as it is produced. Use
+//! [`convert_event_log_to_ndjson`] (or `cargo nextest debug convert-event-log`) to inspect a log.
+//!
+//! # Format
+//!
+//! A log consists of a header followed by any number of records.
+//!
+//! * The header is the magic bytes `NXEL`, followed by the format version as a little-endian
+//!   `u16`. The current version is [`EVENT_LOG_FORMAT_VERSION`].
+//! * Each record is the length of its payload in bytes, followed by the payload. The first byte
+//!   of the payload is a tag identifying the kind of record, and the rest are its fields in
+//!   declaration order.
+//!
+//! Within records, integers (including lengths) are unsigned LEB128, strings and byte strings are
+//! a length followed by that many bytes, durations are seconds followed by nanoseconds, and
+//! optional values are prefixed by a byte that is 0 if the value is absent and 1 if it is present.
+
+use super::TestReporter;
+use crate::{
+    errors::{EventLogError, WriteEventError},
+    reporter::events::{ExecutionResult, TestEvent, TestEventKind},
+    test_output::{ChildExecutionOutput, ChildOutput},
+};
+use serde::{Serialize, Serializer};
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    time::Duration,
+};
+
+/// The magic bytes at the start of every binary event log.
+pub const EVENT_LOG_MAGIC: [u8; 4] = *b"NXEL";
+
+/// The version of the binary event log format written by this version of nextest.
+pub const EVENT_LOG_FORMAT_VERSION: u16 = 1;
+
+/// A single record in a binary event log.
+///
+/// When converted to JSON, each record is an object with an `event` field indicating its kind.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(
+    tag = "event",
+    rename_all = "kebab-case",
+    rename_all_fields = "kebab-case"
+)]
+#[non_exhaustive]
+pub enum EventLogRecord {
+    /// A test run started.
+    RunStarted {
+        /// The unique ID for this run.
+        run_id: String,
+
+        /// The nextest profile chosen for this run.
+        profile_name: String,
+
+        /// The number of tests that will be run.
+        test_count: u64,
+    },
+
+    /// A test started running.
+    TestStarted {
+        /// The binary ID of the test.
+        binary_id: String,
+
+        /// The name of the test.
+        test_name: String,
+    },
+
+    /// A test finished running, including any retries.
+    TestFinished {
+        /// The binary ID of the test.
+        binary_id: String,
+
+        /// The name of the test.
+        test_name: String,
+
+        /// The result of the last attempt.
+        result: EventLogResult,
+
+        /// The number of times the test was run.
+        attempts: u64,
+
+        /// The time the last attempt took.
+        #[serde(serialize_with = "serialize_duration_secs")]
+        time_taken: Duration,
+
+        /// The output of the last attempt, if it didn't succeed.
+        #[serde(serialize_with = "serialize_lossy")]
+        output: Option<Vec<u8>>,
+    },
+
+    /// A test was skipped.
+    TestSkipped {
+        /// The binary ID of the test.
+        binary_id: String,
+
+        /// The name of the test.
+        test_name: String,
+
+        /// The reason the test was skipped.
+        reason: String,
+    },
+
+    /// A test run finished.
+    RunFinished {
+        /// The unique ID for this run.
+        run_id: String,
+
+        /// The amount of time it took for the tests to run.
+        #[serde(serialize_with = "serialize_duration_secs")]
+        elapsed: Duration,
+
+        /// The number of tests that passed.
+        passed: u64,
+
+        /// The number of tests that failed, including those that timed out or failed to start.
+        failed: u64,
+
+        /// The number of tests that were skipped.
+        skipped: u64,
+    },
+}
+
+impl EventLogRecord {
+    /// Converts a test event into a record, if it's one of the events recorded in event logs.
+    pub fn from_event(event: &TestEvent<'_>) -> Option<Self> {
+        let record = match &event.kind {
+            TestEventKind::RunStarted {
+                test_list,
+                run_id,
+                profile_name,
+                ..
+            } => Self::RunStarted {
+                run_id: run_id.to_string(),
+                profile_name: profile_name.clone(),
+                test_count: test_list.run_count() as u64,
+            },
+            TestEventKind::TestStarted { test_instance, .. } => Self::TestStarted {
+                binary_id: test_instance.suite_info.binary_id.to_string(),
+                test_name: test_instance.name.to_owned(),
+            },
+            TestEventKind::TestFinished {
+                test_instance,
+                run_statuses,
+                ..
+            } => {
+                let last_status = run_statuses.last_status();
+                let output =
+                    (!last_status.result.is_success()).then(|| output_bytes(&last_status.output));
+                Self::TestFinished {
+                    binary_id: test_instance.suite_info.binary_id.to_string(),
+                    test_name: test_instance.name.to_owned(),
+                    result: last_status.result.into(),
+                    attempts: run_statuses.len() as u64,
+                    time_taken: last_status.time_taken,
+                    output,
+                }
+            }
+            TestEventKind::TestSkipped {
+                test_instance,
+                reason,
+            } => Self::TestSkipped {
+                binary_id: test_instance.suite_info.binary_id.to_string(),
+                test_name: test_instance.name.to_owned(),
+                reason: reason.to_string(),
+            },
+            TestEventKind::RunFinished {
+                run_id,
+                elapsed,
+                run_stats,
+                ..
+            } => Self::RunFinished {
+                run_id: run_id.to_string(),
+                elapsed: *elapsed,
+                passed: run_stats.passed as u64,
+                failed: run_stats.failed_count() as u64,
+                skipped: run_stats.skipped as u64,
+            },
+            _ => return None,
+        };
+        Some(record)
+    }
+
+    fn tag(&self) -> u8 {
+        match self {
+            Self::RunStarted { .. } => 0,
+            Self::TestStarted { .. } => 1,
+            Self::TestFinished { .. } => 2,
+            Self::TestSkipped { .. } => 3,
+            Self::RunFinished { .. } => 4,
+        }
+    }
+
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.push(self.tag());
+        match self {
+            Self::RunStarted {
+                run_id,
+                profile_name,
+                test_count,
+            } => {
+                encode_bytes(buf, run_id.as_bytes());
+                encode_bytes(buf, profile_name.as_bytes());
+                encode_varint(buf, *test_count);
+            }
+            Self::TestStarted {
+                binary_id,
+                test_name,
+            } => {
+                encode_bytes(buf, binary_id.as_bytes());
+                encode_bytes(buf, test_name.as_bytes());
+            }
+            Self::TestFinished {
+                binary_id,
+                test_name,
+                result,
+                attempts,
+                time_taken,
+                output,
+            } => {
+                encode_bytes(buf, binary_id.as_bytes());
+                encode_bytes(buf, test_name.as_bytes());
+                buf.push(*result as u8);
+                encode_varint(buf, *attempts);
+                encode_duration(buf, *time_taken);
+                match output {
+                    Some(output) => {
+                        buf.push(1);
+                        encode_bytes(buf, output);
+                    }
+                    None => buf.push(0),
+                }
+            }
+            Self::TestSkipped {
+                binary_id,
+                test_name,
+                reason,
+            } => {
+                encode_bytes(buf, binary_id.as_bytes());
+                encode_bytes(buf, test_name.as_bytes());
+                encode_bytes(buf, reason.as_bytes());
+            }
+            Self::RunFinished {
+                run_id,
+                elapsed,
+                passed,
+                failed,
+                skipped,
+            } => {
+                encode_bytes(buf, run_id.as_bytes());
+                encode_duration(buf, *elapsed);
+                encode_varint(buf, *passed);
+                encode_varint(buf, *failed);
+                encode_varint(buf, *skipped);
+            }
+        }
+    }
+
+    fn decode(payload: &[u8]) -> Result<Self, &'static str> {
+        let mut decoder = Decoder { payload };
+        let record = match decoder.byte()? {
+            0 => Self::RunStarted {
+                run_id: decoder.string()?,
+                profile_name: decoder.string()?,
+                test_count: decoder.varint()?,
+            },
+            1 => Self::TestStarted {
+                binary_id: decoder.string()?,
+                test_name: decoder.string()?,
+            },
+            2 => Self::TestFinished {
+                binary_id: decoder.string()?,
+                test_name: decoder.string()?,
+                result: EventLogResult::from_u8(decoder.byte()?)?,
+                attempts: decoder.varint()?,
+                time_taken: decoder.duration()?,
+                output: match decoder.byte()? {
+                    0 => None,
+                    1 => Some(decoder.bytes()?.to_vec()),
+                    _ => return Err("invalid option marker"),
+                },
+            },
+            3 => Self::TestSkipped {
+                binary_id: decoder.string()?,
+                test_name: decoder.string()?,
+                reason: decoder.string()?,
+            },
+            4 => Self::RunFinished {
+                run_id: decoder.string()?,
+                elapsed: decoder.duration()?,
+                passed: decoder.varint()?,
+                failed: decoder.varint()?,
+                skipped: decoder.varint()?,
+            },
+            _ => return Err("unknown record tag"),
+        };
+
+        if decoder.payload.is_empty() {
+            Ok(record)
+        } else {
+            Err("trailing bytes after record")
+        }
+    }
+}
+
+/// The result of a test in an [`EventLogRecord`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+#[repr(u8)]
+pub enum EventLogResult {
+    /// The test passed.
+    Pass = 0,
+
+    /// The test passed but leaked handles.
+    Leak = 1,
+
+    /// The test failed.
+    Fail = 2,
+
+    /// The test could not be executed.
+    ExecFail = 3,
+
+    /// The test was terminated due to a timeout.
+    Timeout = 4,
+}
+
+impl EventLogResult {
+    fn from_u8(value: u8) -> Result<Self, &'static str> {
+        match value {
+            0 => Ok(Self::Pass),
+            1 => Ok(Self::Leak),
+            2 => Ok(Self::Fail),
+            3 => Ok(Self::ExecFail),
+            4 => Ok(Self::Timeout),
+            _ => Err("unknown test result"),
+        }
+    }
+}
+
+impl From<ExecutionResult> for EventLogResult {
+    fn from(result: ExecutionResult) -> Self {
+        match result {
+            ExecutionResult::Pass => Self::Pass,
+            ExecutionResult::Leak { .. } => Self::Leak,
+            ExecutionResult::Fail { .. } => Self::Fail,
+            ExecutionResult::ExecFail => Self::ExecFail,
+            ExecutionResult::Timeout { .. } => Self::Timeout,
+        }
+    }
+}
+
+/// Writes [`EventLogRecord`]s to a binary event log.
+#[derive(Debug)]
+pub struct EventLogWriter<W> {
+    writer: W,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> EventLogWriter<W> {
+    /// Creates a new writer, writing out the event log header.
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(&EVENT_LOG_MAGIC)?;
+        writer.write_all(&EVENT_LOG_FORMAT_VERSION.to_le_bytes())?;
+        Ok(Self {
+            writer,
+            buf: Vec::new(),
+        })
+    }
+
+    /// Writes a single record, and flushes the underlying writer.
+    ///
+    /// Records are flushed individually so that consumers see events as they happen.
+    pub fn write_record(&mut self, record: &EventLogRecord) -> io::Result<()> {
+        let mut payload = Vec::new();
+        record.encode(&mut payload);
+
+        self.buf.clear();
+        encode_varint(&mut self.buf, payload.len() as u64);
+        self.buf.extend_from_slice(&payload);
+        self.writer.write_all(&self.buf)?;
+        self.writer.flush()
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Reads [`EventLogRecord`]s from a binary event log.
+#[derive(Debug)]
+pub struct EventLogReader<R> {
+    reader: BufReader<R>,
+    offset: u64,
+}
+
+impl<R: Read> EventLogReader<R> {
+    /// Creates a new reader, reading and validating the event log header.
+    pub fn new(reader: R) -> Result<Self, EventLogError> {
+        let mut reader = BufReader::new(reader);
+        let mut header = [0; 6];
+        reader.read_exact(&mut header).map_err(|error| {
+            if error.kind() == io::ErrorKind::UnexpectedEof {
+                EventLogError::InvalidHeader
+            } else {
+                EventLogError::Io(error)
+            }
+        })?;
+        if header[..4] != EVENT_LOG_MAGIC {
+            return Err(EventLogError::InvalidHeader);
+        }
+        let version = u16::from_le_bytes([header[4], header[5]]);
+        if version != EVENT_LOG_FORMAT_VERSION {
+            return Err(EventLogError::UnsupportedVersion {
+                version,
+                supported: EVENT_LOG_FORMAT_VERSION,
+            });
+        }
+
+        Ok(Self {
+            reader,
+            offset: header.len() as u64,
+        })
+    }
+
+    /// Reads the next record from the log, returning `None` at the end of the log.
+    pub fn read_record(&mut self) -> Result<Option<EventLogRecord>, EventLogError> {
+        let offset = self.offset;
+        let invalid = |message| EventLogError::InvalidRecord { offset, message };
+
+        // A clean end of the log is only possible at a record boundary.
+        if self
+            .reader
+            .fill_buf()
+            .map_err(EventLogError::Io)?
+            .is_empty()
+        {
+            return Ok(None);
+        }
+
+        let mut len = 0u64;
+        let mut shift = 0;
+        loop {
+            let mut byte = [0];
+            self.reader
+                .read_exact(&mut byte)
+                .map_err(|error| read_error(error, offset))?;
+            self.offset += 1;
+            if shift >= 64 {
+                return Err(invalid("record length is too large"));
+            }
+            len |= u64::from(byte[0] & 0x7f) << shift;
+            if byte[0] & 0x80 == 0 {
+                break;
+            }
+            shift += 7;
+        }
+
+        let mut payload = Vec::new();
+        (&mut self.reader)
+            .take(len)
+            .read_to_end(&mut payload)
+            .map_err(EventLogError::Io)?;
+        if payload.len() as u64 != len {
+            return Err(invalid("log ends in the middle of a record"));
+        }
+        self.offset += len;
+
+        EventLogRecord::decode(&payload).map(Some).map_err(invalid)
+    }
+}
+
+impl<R: Read> Iterator for EventLogReader<R> {
+    type Item = Result<EventLogRecord, EventLogError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+/// Converts a binary event log into line-delimited JSON, one record per line.
+///
+/// This is meant for debugging: the JSON representation isn't covered by the event log's format
+/// version. Returns the number of records converted.
+pub fn convert_event_log_to_ndjson(
+    reader: impl Read,
+    mut writer: impl Write,
+) -> Result<usize, EventLogError> {
+    let mut count = 0;
+    for record in EventLogReader::new(reader)? {
+        let mut line = serde_json::to_vec(&record?).map_err(EventLogError::Serialize)?;
+        line.push(b'\n');
+        writer.write_all(&line).map_err(EventLogError::Io)?;
+        count += 1;
+    }
+    writer.flush().map_err(EventLogError::Io)?;
+    Ok(count)
+}
+
+/// A reporter that writes test events to a binary event log.
+#[derive(Debug)]
+pub struct EventLogReporter<W> {
+    writer: EventLogWriter<W>,
+}
+
+impl<W: Write> EventLogReporter<W> {
+    /// Creates a new reporter, writing the event log header to `writer`.
+    pub fn new(writer: W) -> io::Result<Self> {
+        Ok(Self {
+            writer: EventLogWriter::new(writer)?,
+        })
+    }
+}
+
+impl<'a, W: Write + Send> TestReporter<'a> for EventLogReporter<W> {
+    fn write_event(&mut self, event: &TestEvent<'a>) -> Result<(), WriteEventError> {
+        if let Some(record) = EventLogRecord::from_event(event) {
+            self.writer
+                .write_record(&record)
+                .map_err(WriteEventError::Io)?;
+        }
+        Ok(())
+    }
+}
+
+fn read_error(error: io::Error, offset: u64) -> EventLogError {
+    if error.kind() == io::ErrorKind::UnexpectedEof {
+        EventLogError::InvalidRecord {
+            offset,
+            message: "log ends in the middle of a record",
+        }
+    } else {
+        EventLogError::Io(error)
+    }
+}
+
+fn output_bytes(output: &ChildExecutionOutput) -> Vec<u8> {
+    match output {
+        ChildExecutionOutput::Output { output, .. } => match output {
+            ChildOutput::Combined { output } => output.buf.to_vec(),
+            ChildOutput::Split(split) => {
+                let mut buf = Vec::new();
+                for single in [&split.stdout, &split.stderr].into_iter().flatten() {
+                    buf.extend_from_slice(&single.buf);
+                }
+                buf
+            }
+        },
+        ChildExecutionOutput::StartError(error) => error.to_string().into_bytes(),
+    }
+}
+
+fn encode_varint(buf: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf.push(byte);
+            break;
+        }
+        buf.push(byte | 0x80);
+    }
+}
+
+fn encode_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    encode_varint(buf, bytes.len() as u64);
+    buf.extend_from_slice(bytes);
+}
+
+fn encode_duration(buf: &mut Vec<u8>, duration: Duration) {
+    encode_varint(buf, duration.as_secs());
+    encode_varint(buf, u64::from(duration.subsec_nanos()));
+}
+
+struct Decoder<'p> {
+    payload: &'p [u8],
+}
+
+impl<'p> Decoder<'p> {
+    fn byte(&mut self) -> Result<u8, &'static str> {
+        let (&byte, rest) = self
+            .payload
+            .split_first()
+            .ok_or("record ends unexpectedly")?;
+        self.payload = rest;
+        Ok(byte)
+    }
+
+    fn varint(&mut self) -> Result<u64, &'static str> {
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let byte = self.byte()?;
+            if shift >= 64 {
+                return Err("integer is too large");
+            }
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    fn bytes(&mut self) -> Result<&'p [u8], &'static str> {
+        let len = usize::try_from(self.varint()?).map_err(|_| "length is too large")?;
+        if len > self.payload.len() {
+            return Err("record ends unexpectedly");
+        }
+        let (bytes, rest) = self.payload.split_at(len);
+        self.payload = rest;
+        Ok(bytes)
+    }
+
+    fn string(&mut self) -> Result<String, &'static str> {
+        let bytes = self.bytes()?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| "string is not valid UTF-8")
+    }
+
+    fn duration(&mut self) -> Result<Duration, &'static str> {
+        let secs = self.varint()?;
+        let nanos = u32::try_from(self.varint()?)
+            .ok()
+            .filter(|nanos| *nanos < 1_000_000_000)
+            .ok_or("invalid duration")?;
+        Ok(Duration::new(secs, nanos))
+    }
+}
+
+fn serialize_duration_secs<S: Serializer>(duration: &Duration, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_f64(duration.as_secs_f64())
+}
+
+fn serialize_lossy<S: Serializer>(bytes: &Option<Vec<u8>>, s: S) -> Result<S::Ok, S::Error> {
+    match bytes {
+        Some(bytes) => s.serialize_some(&String::from_utf8_lossy(bytes)),
+        None => s.serialize_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records() -> Vec<EventLogRecord> {
+        vec![
+            EventLogRecord::RunStarted {
+                run_id: "c0ffee".to_owned(),
+                profile_name: "default".to_owned(),
+                test_count: 3,
+            },
+            EventLogRecord::TestStarted {
+                binary_id: "my-crate::basic".to_owned(),
+                test_name: "test_success".to_owned(),
+            },
+            EventLogRecord::TestFinished {
+                binary_id: "my-crate::basic".to_owned(),
+                test_name: "test_success".to_owned(),
+                result: EventLogResult::Pass,
+                attempts: 1,
+                time_taken: Duration::from_millis(1500),
+                output: None,
+            },
+            EventLogRecord::TestFinished {
+                binary_id: "my-crate::basic".to_owned(),
+                test_name: "test_failure".to_owned(),
+                result: EventLogResult::Fail,
+                attempts: 3,
+                time_taken: Duration::from_millis(250),
+                // Output longer than 127 bytes has a multi-byte length.
+                output: Some(b"thread panicked\n\xff".repeat(20)),
+            },
+            EventLogRecord::TestSkipped {
+                binary_id: "my-crate::basic".to_owned(),
+                test_name: "test_ignored".to_owned(),
+                reason: "ignored".to_owned(),
+            },
+            EventLogRecord::RunFinished {
+                run_id: "c0ffee".to_owned(),
+                elapsed: Duration::new(2, 5),
+                passed: 1,
+                failed: 1,
+                skipped: 1,
+            },
+        ]
+    }
+
+    fn write_log(records: &[EventLogRecord]) -> Vec<u8> {
+        let mut writer = EventLogWriter::new(Vec::new()).unwrap();
+        for record in records {
+            writer.write_record(record).unwrap();
+        }
+        writer.into_inner()
+    }
+
+    #[test]
+    fn event_log_roundtrip() {
+        let records = records();
+        let log = write_log(&records);
+        assert_eq!(&log[..6], b"NXEL\x01\x00", "header is written");
+
+        let read: Vec<_> = EventLogReader::new(log.as_slice())
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(read, records);
+
+        // An empty log only has a header.
+        let log = write_log(&[]);
+        assert_eq!(EventLogReader::new(log.as_slice()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn event_log_to_ndjson() {
+        let log = write_log(&records()[2..4]);
+        let mut ndjson = Vec::new();
+        let count = convert_event_log_to_ndjson(log.as_slice(), &mut ndjson).unwrap();
+        assert_eq!(count, 2);
+
+        let lines: Vec<serde_json::Value> = ndjson
+            .split(|&b| b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+        assert_eq!(
+            lines[0],
+            serde_json::json!({
+                "event": "test-finished",
+                "binary-id": "my-crate::basic",
+                "test-name": "test_success",
+                "result": "pass",
+                "attempts": 1,
+                "time-taken": 1.5,
+                "output": null,
+            })
+        );
+        assert_eq!(lines[1]["result"], "fail");
+        assert!(lines[1]["output"]
+            .as_str()
+            .unwrap()
+            .starts_with("thread panicked\n\u{fffd}"));
+    }
+
+    #[test]
+    fn event_log_invalid() {
+        let error = EventLogReader::new(&b"{\"type\": \"suite\"}"[..]).unwrap_err();
+        assert!(
+            matches!(error, EventLogError::InvalidHeader),
+            "unexpected error: {error:?}"
+        );
+
+        let error = EventLogReader::new(&b"NXEL\x02\x00"[..]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "unsupported event log format version 2 (supported: 1)"
+        );
+
+        // A log that's cut off in the middle of a record is reported as such.
+        let mut log = write_log(&records()[..2]);
+        log.truncate(log.len() - 3);
+        let mut reader = EventLogReader::new(log.as_slice()).unwrap();
+        assert!(reader.read_record().unwrap().is_some());
+        let error = reader.read_record().unwrap_err();
+        assert!(
+            matches!(
+                error,
+                EventLogError::InvalidRecord {
+                    message: "log ends in the middle of a record",
+                    ..
+                }
+            ),
+            "unexpected error: {error:?}"
+        );
+    }
+}
@@ -4,16 +4,19 @@
 //! Functionality for emitting structured, machine readable output in different
 //! formats
 
-use super::LibtestReporter;
-use crate::{errors::WriteEventError, reporter::events::TestEvent};
+use super::{LibtestReporter, TestReporter};
+use crate::{
+    errors::WriteEventError,
+    reporter::events::{AggregateRunStats, TestEvent},
+};
+use quick_junit::ReportUuid;
 
 /// A reporter for structured, machine-readable formats.
 #[derive(Default)]
 pub struct StructuredReporter<'a> {
-    /// Libtest-compatible output written to stdout
-    libtest: Option<LibtestReporter<'a>>,
-    // Internal structured reporter.
-    // internal: Option<T>,
+    /// The reporter for the selected message format, e.g. libtest-compatible
+    /// output written to stdout.
+    reporter: Option<Box<dyn TestReporter<'a> + 'a>>,
 }
 
 impl<'a> StructuredReporter<'a> {
@@ -24,14 +27,39 @@ impl<'a> StructuredReporter<'a> {
 
     /// Sets libtest output for the `StructuredReporter`.
     pub fn set_libtest(&mut self, libtest: LibtestReporter<'a>) -> &mut Self {
-        self.libtest = Some(libtest);
+        self.set_reporter(Box::new(libtest))
+    }
+
+    /// Sets the reporter for the `StructuredReporter`, typically created by a
+    /// [`FormatterRegistry`](super::FormatterRegistry).
+    pub fn set_reporter(&mut self, reporter: Box<dyn TestReporter<'a> + 'a>) -> &mut Self {
+        self.reporter = Some(reporter);
         self
     }
 
+    pub(crate) fn on_run_id(&mut self, run_id: ReportUuid) -> Result<(), WriteEventError> {
+        if let Some(reporter) = &mut self.reporter {
+            reporter.on_run_id(run_id)?;
+        }
+        Ok(())
+    }
+
     #[inline]
     pub(crate) fn write_event(&mut self, event: &TestEvent<'a>) -> Result<(), WriteEventError> {
-        if let Some(libtest) = &mut self.libtest {
-            libtest.write_event(event)?;
+        if let Some(reporter) = &mut self.reporter {
+            reporter.write_event(event)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the statistics for a sequence of runs with different profiles, once the last run has
+    /// finished.
+    pub fn write_aggregate(
+        &mut self,
+        aggregate: &AggregateRunStats,
+    ) -> Result<(), WriteEventError> {
+        if let Some(reporter) = &mut self.reporter {
+            reporter.write_aggregate(aggregate)?;
         }
         Ok(())
     }
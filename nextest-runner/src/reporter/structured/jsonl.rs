@@ -0,0 +1,1151 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! A line-delimited JSON stream of test events.
+//!
+//! Each event is written as a single JSON object followed by a newline, as soon as it happens.
+//! This is meant for tools, such as live dashboards, that want to observe a run while it's in
+//! progress rather than waiting for the JUnit report at the end.
+//!
+//! Every line is a [`JsonlRecord`], which carries [`JSONL_SCHEMA_VERSION`] so that consumers can
+//! detect breaking changes. Fields may be added to records, and new kinds of events may be
+//! introduced, without changing the schema version; consumers should ignore what they don't
+//! understand.
+
+use super::{event_log::EventLogResult, TestReporter};
+use crate::{
+    config::RetryBackoff,
+    errors::WriteEventError,
+    list::{TestInstance, TestInstanceId},
+    reporter::events::{
+        AggregateRunStats, CancelReason, FinalRunStats, RetryData, TestEvent, TestEventKind,
+    },
+};
+use serde::{Serialize, Serializer};
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+    time::Duration,
+};
+
+/// The version of the line-delimited JSON schema written by this version of nextest.
+pub const JSONL_SCHEMA_VERSION: u32 = 1;
+
+/// A single line in a line-delimited JSON stream of test events.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub struct JsonlRecord {
+    /// The schema version, always [`JSONL_SCHEMA_VERSION`].
+    pub schema_version: u32,
+
+    /// The time at which the event was generated, in RFC 3339 format.
+    pub timestamp: String,
+
+    /// The amount of time elapsed since the start of the test run.
+    #[serde(serialize_with = "serialize_duration_secs")]
+    pub elapsed: Duration,
+
+    /// The event itself, flattened into the record.
+    #[serde(flatten)]
+    pub event: JsonlEvent,
+}
+
+impl JsonlRecord {
+    /// Converts a test event into a record.
+    ///
+    /// Returns `None` for events that only matter to interactive displays, such as responses to
+    /// information requests.
+    pub fn from_event(event: &TestEvent<'_>) -> Option<Self> {
+        Some(Self {
+            schema_version: JSONL_SCHEMA_VERSION,
+            timestamp: event.timestamp.to_rfc3339(),
+            elapsed: event.elapsed,
+            event: JsonlEvent::from_kind(&event.kind)?,
+        })
+    }
+
+    /// Converts the statistics for a sequence of runs with different profiles into a record.
+    ///
+    /// The record's `elapsed` is the total time taken by all the runs.
+    pub fn from_aggregate(aggregate: &AggregateRunStats) -> Self {
+        let total = aggregate.total();
+        Self {
+            schema_version: JSONL_SCHEMA_VERSION,
+            timestamp: chrono::Local::now().fixed_offset().to_rfc3339(),
+            elapsed: aggregate.elapsed(),
+            event: JsonlEvent::AggregateFinished {
+                outcome: final_outcome_str(aggregate.summarize_final()),
+                passed: total.passed as u64,
+                failed: total.failed_count() as u64,
+                skipped: total.skipped as u64,
+                flaky: total.flaky as u64,
+                runs: aggregate
+                    .runs()
+                    .iter()
+                    .map(|run| JsonlProfileRun {
+                        profile_name: run.profile_name.clone(),
+                        outcome: final_outcome_str(run.run_stats.summarize_final()),
+                        time_taken: run.elapsed,
+                        passed: run.run_stats.passed as u64,
+                        failed: run.run_stats.failed_count() as u64,
+                        skipped: run.run_stats.skipped as u64,
+                    })
+                    .collect(),
+            },
+        }
+    }
+}
+
+/// The identity of a test in a [`JsonlEvent`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct JsonlTestId {
+    /// The binary ID of the test.
+    pub binary_id: String,
+
+    /// The name of the test.
+    pub test_name: String,
+}
+
+impl JsonlTestId {
+    fn new(test_instance: &TestInstance<'_>) -> Self {
+        Self::from_id(&test_instance.id())
+    }
+
+    fn from_id(id: &TestInstanceId<'_>) -> Self {
+        Self {
+            binary_id: id.binary_id.to_string(),
+            test_name: id.test_name.to_owned(),
+        }
+    }
+}
+
+/// One of the runs in a [`JsonlEvent::AggregateFinished`].
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct JsonlProfileRun {
+    /// The name of the profile the run used.
+    pub profile_name: String,
+
+    /// The outcome of the run.
+    pub outcome: &'static str,
+
+    /// The amount of time the run took.
+    #[serde(serialize_with = "serialize_duration_secs")]
+    pub time_taken: Duration,
+
+    /// The number of tests that passed.
+    pub passed: u64,
+
+    /// The number of tests that failed, including those that timed out or failed to start.
+    pub failed: u64,
+
+    /// The number of tests that were skipped.
+    pub skipped: u64,
+}
+
+/// The kind of event in a [`JsonlRecord`].
+///
+/// When serialized, each event has an `event` field indicating its kind.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(
+    tag = "event",
+    rename_all = "kebab-case",
+    rename_all_fields = "kebab-case"
+)]
+#[non_exhaustive]
+pub enum JsonlEvent {
+    /// A test run started.
+    RunStarted {
+        /// The unique ID for this run.
+        run_id: String,
+
+        /// The nextest profile chosen for this run.
+        profile_name: String,
+
+        /// The number of tests that will be run.
+        test_count: u64,
+
+        /// The seed used to shuffle the order tests are started in, if they were shuffled.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        shuffle_seed: Option<u64>,
+    },
+
+    /// A setup script started.
+    SetupScriptStarted {
+        /// The script ID.
+        script_id: String,
+
+        /// The setup script index.
+        index: u64,
+
+        /// The total number of setup scripts.
+        total: u64,
+    },
+
+    /// A setup script was slow.
+    SetupScriptSlow {
+        /// The script ID.
+        script_id: String,
+
+        /// The configured slow timeout period for the script.
+        #[serde(serialize_with = "serialize_duration_secs")]
+        slow_timeout: Duration,
+
+        /// The amount of time elapsed since the start of execution.
+        #[serde(serialize_with = "serialize_duration_secs")]
+        time_taken: Duration,
+
+        /// True if the script has hit its timeout and is about to be terminated.
+        will_terminate: bool,
+    },
+
+    /// A setup script finished.
+    SetupScriptFinished {
+        /// The script ID.
+        script_id: String,
+
+        /// The setup script index.
+        index: u64,
+
+        /// The total number of setup scripts.
+        total: u64,
+
+        /// The result of the script.
+        result: EventLogResult,
+
+        /// The time the script took.
+        #[serde(serialize_with = "serialize_duration_secs")]
+        time_taken: Duration,
+
+        /// The environment variables set by the script, with sensitive values redacted.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        env_map: Option<BTreeMap<String, String>>,
+    },
+
+    /// A setup script set environment variables that earlier setup scripts had also set.
+    SetupScriptEnvOverridden {
+        /// The script ID.
+        script_id: String,
+
+        /// A map from each overridden variable to the most recent earlier script that set it.
+        overrides: BTreeMap<String, String>,
+    },
+
+    /// A teardown script started.
+    TeardownScriptStarted {
+        /// The script ID.
+        script_id: String,
+
+        /// The teardown script index.
+        index: u64,
+
+        /// The total number of teardown scripts.
+        total: u64,
+    },
+
+    /// A teardown script was slow.
+    TeardownScriptSlow {
+        /// The script ID.
+        script_id: String,
+
+        /// The configured slow timeout period for the script.
+        #[serde(serialize_with = "serialize_duration_secs")]
+        slow_timeout: Duration,
+
+        /// The amount of time elapsed since the start of execution.
+        #[serde(serialize_with = "serialize_duration_secs")]
+        time_taken: Duration,
+
+        /// True if the script has hit its timeout and is about to be terminated.
+        will_terminate: bool,
+    },
+
+    /// A teardown script finished.
+    TeardownScriptFinished {
+        /// The script ID.
+        script_id: String,
+
+        /// The teardown script index.
+        index: u64,
+
+        /// The total number of teardown scripts.
+        total: u64,
+
+        /// The result of the script.
+        result: EventLogResult,
+
+        /// The time the script took.
+        #[serde(serialize_with = "serialize_duration_secs")]
+        time_taken: Duration,
+    },
+
+    /// The first test in a binary started running or was skipped.
+    BinaryStarted {
+        /// The binary ID.
+        binary_id: String,
+
+        /// The name of the package the binary is in.
+        package_name: String,
+
+        /// The number of tests in the binary, including tests that will be skipped.
+        test_count: u64,
+
+        /// The number of tests in the binary that will be run.
+        run_count: u64,
+    },
+
+    /// All the tests in a binary finished running or were skipped.
+    BinaryFinished {
+        /// The binary ID.
+        binary_id: String,
+
+        /// The name of the package the binary is in.
+        package_name: String,
+
+        /// The amount of time elapsed since the binary was started.
+        #[serde(serialize_with = "serialize_duration_secs")]
+        time_taken: Duration,
+
+        /// The number of tests in the binary that passed.
+        passed: u64,
+
+        /// The number of tests in the binary that failed, including those that timed out or
+        /// failed to start.
+        failed: u64,
+
+        /// The number of tests in the binary that were skipped.
+        skipped: u64,
+    },
+
+    /// A test started running.
+    TestStarted {
+        /// The test that started.
+        #[serde(flatten)]
+        test: JsonlTestId,
+
+        /// The number of tests currently running, including this one.
+        running: u64,
+
+        /// The fraction of tests expected to run that have finished, between 0.0 and 1.0.
+        progress: f64,
+
+        /// The program and arguments used to run the test.
+        command_line: Vec<String>,
+
+        /// The environment variables nextest set for the test, with sensitive values redacted.
+        extra_env: BTreeMap<String, String>,
+    },
+
+    /// A test wrote output after its process exited.
+    TestOutputAfterExit {
+        /// The test that wrote output.
+        #[serde(flatten)]
+        test: JsonlTestId,
+
+        /// The current attempt, starting from 1.
+        attempt: u64,
+
+        /// The number of bytes written after the test's process exited.
+        bytes: u64,
+    },
+
+    /// A test was slower than a configured soft timeout.
+    TestSlow {
+        /// The test that was slow.
+        #[serde(flatten)]
+        test: JsonlTestId,
+
+        /// The current attempt, starting from 1.
+        attempt: u64,
+
+        /// The slow timeout period configured for the test.
+        #[serde(serialize_with = "serialize_duration_secs")]
+        slow_timeout: Duration,
+
+        /// The amount of time that has elapsed since the beginning of the attempt.
+        #[serde(serialize_with = "serialize_duration_secs")]
+        time_taken: Duration,
+
+        /// True if the test has hit its timeout and is about to be terminated.
+        will_terminate: bool,
+    },
+
+    /// A test attempt failed and will be retried.
+    TestAttemptFailed {
+        /// The test that failed.
+        #[serde(flatten)]
+        test: JsonlTestId,
+
+        /// The attempt that failed, starting from 1.
+        attempt: u64,
+
+        /// The result of the attempt.
+        result: EventLogResult,
+
+        /// The time the attempt took.
+        #[serde(serialize_with = "serialize_duration_secs")]
+        time_taken: Duration,
+
+        /// The delay before the next attempt.
+        #[serde(serialize_with = "serialize_duration_secs")]
+        delay_before_next_attempt: Duration,
+
+        /// The backoff strategy used to compute the delay.
+        backoff: RetryBackoff,
+
+        /// True if randomness was added to the delay.
+        jitter: bool,
+
+        /// The identifier shared by all attempts of the test.
+        test_run_uuid: String,
+
+        /// The identifier of the attempt that failed.
+        attempt_uuid: String,
+    },
+
+    /// A retry of a test started.
+    TestRetryStarted {
+        /// The test being retried.
+        #[serde(flatten)]
+        test: JsonlTestId,
+
+        /// The attempt that started, starting from 1.
+        attempt: u64,
+
+        /// The identifier shared by all attempts of the test.
+        test_run_uuid: String,
+
+        /// The identifier of the attempt that started.
+        attempt_uuid: String,
+    },
+
+    /// A test finished running, including any retries.
+    TestFinished {
+        /// The test that finished.
+        #[serde(flatten)]
+        test: JsonlTestId,
+
+        /// The result of the last attempt.
+        result: EventLogResult,
+
+        /// The number of times the test was run.
+        attempts: u64,
+
+        /// True if the test failed at first but passed on a retry.
+        flaky: bool,
+
+        /// True if the test is quarantined, so its failures don't cause the run to fail.
+        quarantined: bool,
+
+        /// The time the last attempt took.
+        #[serde(serialize_with = "serialize_duration_secs")]
+        time_taken: Duration,
+
+        /// The time the last attempt spent waiting for block I/O, if known.
+        #[serde(
+            skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_opt_duration_secs"
+        )]
+        io_wait: Option<Duration>,
+
+        /// The fraction of tests expected to run that have finished, including this one, between
+        /// 0.0 and 1.0.
+        progress: f64,
+
+        /// The identifier shared by all attempts of the test.
+        test_run_uuid: String,
+    },
+
+    /// A test was skipped.
+    TestSkipped {
+        /// The test that was skipped.
+        #[serde(flatten)]
+        test: JsonlTestId,
+
+        /// The reason the test was skipped.
+        reason: String,
+    },
+
+    /// The run began to be cancelled.
+    RunBeginCancel {
+        /// The reason the run is being cancelled.
+        reason: &'static str,
+
+        /// The message returned by the cancel hook, if the reason is `custom`.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        message: Option<String>,
+
+        /// The number of setup scripts still running.
+        setup_scripts_running: u64,
+
+        /// The number of tests still running.
+        running: u64,
+
+        /// If running units are being given time to finish before they're terminated, the
+        /// elapsed time at which they will be terminated.
+        #[serde(
+            skip_serializing_if = "Option::is_none",
+            serialize_with = "serialize_opt_duration_secs"
+        )]
+        grace_deadline: Option<Duration>,
+    },
+
+    /// The run began to be forcibly killed.
+    RunBeginKill {
+        /// The reason the run is being killed.
+        reason: &'static str,
+
+        /// The number of setup scripts still running.
+        setup_scripts_running: u64,
+
+        /// The number of tests still running.
+        running: u64,
+    },
+
+    /// The run was paused.
+    RunPaused {
+        /// The number of setup scripts running.
+        setup_scripts_running: u64,
+
+        /// The number of tests running.
+        running: u64,
+
+        /// The running tests that were stopped.
+        stopped: Vec<JsonlTestId>,
+
+        /// The running tests that were seen to keep running after being sent SIGTSTP.
+        not_stopped: Vec<JsonlTestId>,
+    },
+
+    /// The run was continued after being paused.
+    RunContinued {
+        /// The number of setup scripts that will be started up again.
+        setup_scripts_running: u64,
+
+        /// The number of tests that will be started up again.
+        running: u64,
+
+        /// The tests that will be started up again.
+        continued: Vec<JsonlTestId>,
+    },
+
+    /// A test run finished.
+    RunFinished {
+        /// The unique ID for this run.
+        run_id: String,
+
+        /// The number of tests that passed.
+        passed: u64,
+
+        /// The number of tests that failed, including those that timed out or failed to start.
+        failed: u64,
+
+        /// The number of tests that were skipped.
+        skipped: u64,
+
+        /// The number of tests that passed on a retry.
+        flaky: u64,
+
+        /// The tests that were flaky, sorted by binary ID and test name. Quarantined tests aren't
+        /// included.
+        flaky_tests: Vec<JsonlTestId>,
+
+        /// The number of quarantined tests that failed. These aren't counted in `failed`.
+        quarantined_failed: u64,
+
+        /// The number of teardown scripts that didn't pass.
+        teardown_scripts_failed: u64,
+    },
+
+    /// A sequence of runs with different profiles finished.
+    ///
+    /// This is written after the last run's `run-finished` event, and only if more than one
+    /// profile was run.
+    AggregateFinished {
+        /// The outcome of the worst run, which determines the exit code.
+        outcome: &'static str,
+
+        /// The number of tests that passed across all runs.
+        passed: u64,
+
+        /// The number of tests that failed across all runs.
+        failed: u64,
+
+        /// The number of tests that were skipped across all runs.
+        skipped: u64,
+
+        /// The number of tests that passed on a retry across all runs.
+        flaky: u64,
+
+        /// The runs, in the order they were run.
+        runs: Vec<JsonlProfileRun>,
+    },
+}
+
+impl JsonlEvent {
+    fn from_kind(kind: &TestEventKind<'_>) -> Option<Self> {
+        let event = match kind {
+            TestEventKind::RunStarted {
+                test_list,
+                run_id,
+                profile_name,
+                shuffle_seed,
+                ..
+            } => Self::RunStarted {
+                run_id: run_id.to_string(),
+                profile_name: profile_name.clone(),
+                test_count: test_list.run_count() as u64,
+                shuffle_seed: *shuffle_seed,
+            },
+            TestEventKind::SetupScriptStarted {
+                index,
+                total,
+                script_id,
+                ..
+            } => Self::SetupScriptStarted {
+                script_id: script_id.to_string(),
+                index: *index as u64,
+                total: *total as u64,
+            },
+            TestEventKind::SetupScriptSlow {
+                script_id,
+                slow_timeout,
+                elapsed,
+                will_terminate,
+                ..
+            } => Self::SetupScriptSlow {
+                script_id: script_id.to_string(),
+                slow_timeout: *slow_timeout,
+                time_taken: *elapsed,
+                will_terminate: *will_terminate,
+            },
+            TestEventKind::SetupScriptFinished {
+                index,
+                total,
+                script_id,
+                env_map,
+                run_status,
+                ..
+            } => Self::SetupScriptFinished {
+                script_id: script_id.to_string(),
+                index: *index as u64,
+                total: *total as u64,
+                result: run_status.result.into(),
+                time_taken: run_status.time_taken,
+                env_map: env_map.as_ref().map(|env_map| env_map.env_map.clone()),
+            },
+            TestEventKind::SetupScriptEnvOverridden {
+                script_id,
+                overrides,
+            } => Self::SetupScriptEnvOverridden {
+                script_id: script_id.to_string(),
+                overrides: overrides
+                    .iter()
+                    .map(|o| (o.key.clone(), o.previous_script_id.to_string()))
+                    .collect(),
+            },
+            TestEventKind::TeardownScriptStarted {
+                index,
+                total,
+                script_id,
+                ..
+            } => Self::TeardownScriptStarted {
+                script_id: script_id.to_string(),
+                index: *index as u64,
+                total: *total as u64,
+            },
+            TestEventKind::TeardownScriptSlow {
+                script_id,
+                slow_timeout,
+                elapsed,
+                will_terminate,
+                ..
+            } => Self::TeardownScriptSlow {
+                script_id: script_id.to_string(),
+                slow_timeout: *slow_timeout,
+                time_taken: *elapsed,
+                will_terminate: *will_terminate,
+            },
+            TestEventKind::TeardownScriptFinished {
+                index,
+                total,
+                script_id,
+                run_status,
+                ..
+            } => Self::TeardownScriptFinished {
+                script_id: script_id.to_string(),
+                index: *index as u64,
+                total: *total as u64,
+                result: run_status.result.into(),
+                time_taken: run_status.time_taken,
+            },
+            TestEventKind::BinaryStarted {
+                binary_id,
+                package_name,
+                test_count,
+                run_count,
+            } => Self::BinaryStarted {
+                binary_id: binary_id.to_string(),
+                package_name: (*package_name).to_owned(),
+                test_count: *test_count as u64,
+                run_count: *run_count as u64,
+            },
+            TestEventKind::BinaryFinished {
+                binary_id,
+                package_name,
+                elapsed,
+                run_stats,
+            } => Self::BinaryFinished {
+                binary_id: binary_id.to_string(),
+                package_name: (*package_name).to_owned(),
+                time_taken: *elapsed,
+                passed: run_stats.passed as u64,
+                failed: run_stats.failed_count() as u64,
+                skipped: run_stats.skipped as u64,
+            },
+            TestEventKind::TestStarted {
+                test_instance,
+                command_line,
+                extra_env,
+                current_stats,
+                running,
+                ..
+            } => Self::TestStarted {
+                test: JsonlTestId::new(test_instance),
+                running: *running as u64,
+                progress: current_stats.progress_fraction(),
+                command_line: command_line.clone(),
+                extra_env: extra_env.clone(),
+            },
+            TestEventKind::TestSlow {
+                test_instance,
+                retry_data,
+                slow_timeout,
+                elapsed,
+                will_terminate,
+            } => Self::TestSlow {
+                test: JsonlTestId::new(test_instance),
+                attempt: attempt(retry_data),
+                slow_timeout: *slow_timeout,
+                time_taken: *elapsed,
+                will_terminate: *will_terminate,
+            },
+            TestEventKind::TestOutputAfterExit {
+                test_instance,
+                retry_data,
+                bytes,
+            } => Self::TestOutputAfterExit {
+                test: JsonlTestId::new(test_instance),
+                attempt: attempt(retry_data),
+                bytes: *bytes,
+            },
+            TestEventKind::TestAttemptFailedWillRetry {
+                test_instance,
+                run_status,
+                delay_before_next_attempt,
+                ..
+            } => Self::TestAttemptFailed {
+                test: JsonlTestId::new(test_instance),
+                attempt: attempt(&run_status.retry_data),
+                result: run_status.result.into(),
+                time_taken: run_status.time_taken,
+                delay_before_next_attempt: *delay_before_next_attempt,
+                backoff: run_status.retry_data.backoff,
+                jitter: run_status.retry_data.jitter,
+                test_run_uuid: run_status.test_run_uuid.to_string(),
+                attempt_uuid: run_status.attempt_uuid.to_string(),
+            },
+            TestEventKind::TestRetryStarted {
+                test_instance,
+                retry_data,
+                test_run_uuid,
+                attempt_uuid,
+            } => Self::TestRetryStarted {
+                test: JsonlTestId::new(test_instance),
+                attempt: attempt(retry_data),
+                test_run_uuid: test_run_uuid.to_string(),
+                attempt_uuid: attempt_uuid.to_string(),
+            },
+            TestEventKind::TestFinished {
+                test_instance,
+                quarantined,
+                run_statuses,
+                current_stats,
+                ..
+            } => {
+                let last_status = run_statuses.last_status();
+                Self::TestFinished {
+                    test: JsonlTestId::new(test_instance),
+                    result: last_status.result.into(),
+                    attempts: run_statuses.len() as u64,
+                    flaky: last_status.result.is_success() && run_statuses.len() > 1,
+                    quarantined: *quarantined,
+                    time_taken: last_status.time_taken,
+                    io_wait: last_status.io_wait,
+                    progress: current_stats.progress_fraction(),
+                    test_run_uuid: last_status.test_run_uuid.to_string(),
+                }
+            }
+            TestEventKind::TestSkipped {
+                test_instance,
+                reason,
+            } => Self::TestSkipped {
+                test: JsonlTestId::new(test_instance),
+                reason: reason.to_string(),
+            },
+            TestEventKind::RunBeginCancel {
+                setup_scripts_running,
+                running,
+                reason,
+                grace_deadline,
+                ..
+            } => Self::RunBeginCancel {
+                reason: cancel_reason_str(reason),
+                message: match reason {
+                    CancelReason::Custom(message) => Some(message.to_string()),
+                    _ => None,
+                },
+                setup_scripts_running: *setup_scripts_running as u64,
+                running: *running as u64,
+                grace_deadline: *grace_deadline,
+            },
+            TestEventKind::RunBeginKill {
+                setup_scripts_running,
+                running,
+                reason,
+            } => Self::RunBeginKill {
+                reason: cancel_reason_str(reason),
+                setup_scripts_running: *setup_scripts_running as u64,
+                running: *running as u64,
+            },
+            TestEventKind::RunPaused {
+                setup_scripts_running,
+                running,
+                stopped,
+                not_stopped,
+            } => Self::RunPaused {
+                setup_scripts_running: *setup_scripts_running as u64,
+                running: *running as u64,
+                stopped: stopped.iter().map(JsonlTestId::from_id).collect(),
+                not_stopped: not_stopped.iter().map(JsonlTestId::from_id).collect(),
+            },
+            TestEventKind::RunContinued {
+                setup_scripts_running,
+                running,
+                continued,
+            } => Self::RunContinued {
+                setup_scripts_running: *setup_scripts_running as u64,
+                running: *running as u64,
+                continued: continued.iter().map(JsonlTestId::from_id).collect(),
+            },
+            TestEventKind::RunFinished {
+                run_id,
+                run_stats,
+                flaky_tests,
+                ..
+            } => Self::RunFinished {
+                run_id: run_id.to_string(),
+                passed: run_stats.passed as u64,
+                failed: run_stats.failed_count() as u64,
+                skipped: run_stats.skipped as u64,
+                flaky: run_stats.flaky as u64,
+                flaky_tests: flaky_tests.iter().map(JsonlTestId::from_id).collect(),
+                quarantined_failed: run_stats.quarantined_failed as u64,
+                teardown_scripts_failed: run_stats.teardown_scripts_failed as u64,
+            },
+            TestEventKind::PhaseStarted { .. }
+            | TestEventKind::PhaseFinished { .. }
+            | TestEventKind::TestRetryDelayStarted { .. }
+            | TestEventKind::TestOutputMismatch { .. }
+            | TestEventKind::InfoStarted { .. }
+            | TestEventKind::InfoResponse { .. }
+            | TestEventKind::InfoFinished { .. }
+            | TestEventKind::InputEnter { .. } => return None,
+        };
+        Some(event)
+    }
+}
+
+/// A reporter that writes test events as line-delimited JSON.
+#[derive(Debug)]
+pub struct JsonlReporter<W> {
+    writer: W,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> JsonlReporter<W> {
+    /// Creates a new reporter that writes to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Writes a single record, and flushes the underlying writer.
+    ///
+    /// Records are flushed individually so that consumers see events as they happen.
+    pub fn write_record(&mut self, record: &JsonlRecord) -> io::Result<()> {
+        self.buf.clear();
+        serde_json::to_writer(&mut self.buf, record)?;
+        self.buf.push(b'\n');
+        self.writer.write_all(&self.buf)?;
+        self.writer.flush()
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<'a, W: Write + Send> TestReporter<'a> for JsonlReporter<W> {
+    fn write_event(&mut self, event: &TestEvent<'a>) -> Result<(), WriteEventError> {
+        if let Some(record) = JsonlRecord::from_event(event) {
+            self.write_record(&record).map_err(WriteEventError::Io)?;
+        }
+        Ok(())
+    }
+
+    fn write_aggregate(&mut self, aggregate: &AggregateRunStats) -> Result<(), WriteEventError> {
+        self.write_record(&JsonlRecord::from_aggregate(aggregate))
+            .map_err(WriteEventError::Io)
+    }
+}
+
+fn attempt(retry_data: &RetryData) -> u64 {
+    retry_data.attempt as u64
+}
+
+fn final_outcome_str(final_stats: FinalRunStats) -> &'static str {
+    match final_stats {
+        FinalRunStats::Success => "success",
+        FinalRunStats::NoTestsRun => "no-tests-run",
+        FinalRunStats::Cancelled(_) => "cancelled",
+        FinalRunStats::Failed(_) => "failed",
+    }
+}
+
+fn cancel_reason_str(reason: &CancelReason) -> &'static str {
+    match reason {
+        CancelReason::SetupScriptFailure => "setup-script-failure",
+        CancelReason::TestFailure => "test-failure",
+        CancelReason::SmokeTestFailure => "smoke-test-failure",
+        CancelReason::Custom(_) => "custom",
+        CancelReason::ReportError => "report-error",
+        CancelReason::Signal => "signal",
+        CancelReason::Interrupt => "interrupt",
+        CancelReason::SecondSignal => "second-signal",
+    }
+}
+
+fn serialize_duration_secs<S: Serializer>(duration: &Duration, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_f64(duration.as_secs_f64())
+}
+
+fn serialize_opt_duration_secs<S: Serializer>(
+    duration: &Option<Duration>,
+    s: S,
+) -> Result<S::Ok, S::Error> {
+    match duration {
+        Some(duration) => serialize_duration_secs(duration, s),
+        None => s.serialize_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reporter::events::RunStats;
+
+    fn record(event: JsonlEvent) -> JsonlRecord {
+        JsonlRecord {
+            schema_version: JSONL_SCHEMA_VERSION,
+            timestamp: "2024-01-01T00:00:00+00:00".to_owned(),
+            elapsed: Duration::from_millis(2500),
+            event,
+        }
+    }
+
+    fn test_id() -> JsonlTestId {
+        JsonlTestId {
+            binary_id: "my-crate::basic".to_owned(),
+            test_name: "test_failure".to_owned(),
+        }
+    }
+
+    #[test]
+    fn jsonl_reporter_output() {
+        let mut reporter = JsonlReporter::new(Vec::new());
+        reporter
+            .write_record(&record(JsonlEvent::TestFinished {
+                test: test_id(),
+                result: EventLogResult::Fail,
+                attempts: 3,
+                flaky: false,
+                quarantined: false,
+                time_taken: Duration::from_millis(250),
+                io_wait: Some(Duration::from_millis(125)),
+                progress: 0.5,
+                test_run_uuid: "c0ffee00-0000-4000-8000-000000000000".to_owned(),
+            }))
+            .unwrap();
+        reporter
+            .write_record(&record(JsonlEvent::RunBeginCancel {
+                reason: cancel_reason_str(&CancelReason::TestFailure),
+                message: None,
+                setup_scripts_running: 0,
+                running: 2,
+                grace_deadline: Some(Duration::from_millis(12500)),
+            }))
+            .unwrap();
+        let output = reporter.into_inner();
+
+        let lines: Vec<serde_json::Value> = output
+            .split(|&b| b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2, "one line per record");
+        assert_eq!(
+            lines[0],
+            serde_json::json!({
+                "schema-version": 1,
+                "timestamp": "2024-01-01T00:00:00+00:00",
+                "elapsed": 2.5,
+                "event": "test-finished",
+                "binary-id": "my-crate::basic",
+                "test-name": "test_failure",
+                "result": "fail",
+                "attempts": 3,
+                "flaky": false,
+                "quarantined": false,
+                "time-taken": 0.25,
+                "io-wait": 0.125,
+                "progress": 0.5,
+                "test-run-uuid": "c0ffee00-0000-4000-8000-000000000000",
+            })
+        );
+        assert_eq!(lines[1]["event"], "run-begin-cancel");
+        assert_eq!(lines[1]["reason"], "test-failure");
+        assert_eq!(lines[1]["running"], 2);
+        assert_eq!(lines[1]["grace-deadline"], 12.5);
+    }
+
+    #[test]
+    fn jsonl_run_finished_flaky_tests() {
+        let mut reporter = JsonlReporter::new(Vec::new());
+        reporter
+            .write_record(&record(JsonlEvent::RunFinished {
+                run_id: "c0ffee00-0000-4000-8000-000000000000".to_owned(),
+                passed: 4,
+                failed: 0,
+                skipped: 0,
+                flaky: 1,
+                flaky_tests: vec![test_id()],
+                quarantined_failed: 0,
+                teardown_scripts_failed: 0,
+            }))
+            .unwrap();
+        let output = reporter.into_inner();
+
+        let line: serde_json::Value = serde_json::from_slice(output.trim_ascii_end()).unwrap();
+        assert_eq!(line["event"], "run-finished");
+        assert_eq!(line["flaky"], 1);
+        assert_eq!(
+            line["flaky-tests"],
+            serde_json::json!([
+                {
+                    "binary-id": "my-crate::basic",
+                    "test-name": "test_failure",
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn jsonl_aggregate_finished() {
+        let mut aggregate = AggregateRunStats::new();
+        aggregate.push(
+            "default",
+            Duration::from_millis(2000),
+            RunStats {
+                initial_run_count: 2,
+                finished_count: 2,
+                passed: 2,
+                ..RunStats::default()
+            },
+        );
+        aggregate.push(
+            "integration",
+            Duration::from_millis(500),
+            RunStats {
+                initial_run_count: 1,
+                finished_count: 1,
+                failed: 1,
+                ..RunStats::default()
+            },
+        );
+
+        let mut reporter = JsonlReporter::new(Vec::new());
+        reporter.write_aggregate(&aggregate).unwrap();
+        let output = reporter.into_inner();
+
+        let line: serde_json::Value = serde_json::from_slice(output.trim_ascii_end()).unwrap();
+        assert_eq!(line["event"], "aggregate-finished");
+        assert_eq!(line["elapsed"], 2.5);
+        assert_eq!(line["outcome"], "failed");
+        assert_eq!(line["passed"], 2);
+        assert_eq!(line["failed"], 1);
+        assert_eq!(
+            line["runs"],
+            serde_json::json!([
+                {
+                    "profile-name": "default",
+                    "outcome": "success",
+                    "time-taken": 2.0,
+                    "passed": 2,
+                    "failed": 0,
+                    "skipped": 0,
+                },
+                {
+                    "profile-name": "integration",
+                    "outcome": "failed",
+                    "time-taken": 0.5,
+                    "passed": 0,
+                    "failed": 1,
+                    "skipped": 0,
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn jsonl_run_paused_tests() {
+        let mut reporter = JsonlReporter::new(Vec::new());
+        reporter
+            .write_record(&record(JsonlEvent::RunPaused {
+                setup_scripts_running: 0,
+                running: 1,
+                stopped: Vec::new(),
+                not_stopped: vec![test_id()],
+            }))
+            .unwrap();
+        let output = reporter.into_inner();
+
+        let line: serde_json::Value = serde_json::from_slice(output.trim_ascii_end()).unwrap();
+        assert_eq!(line["event"], "run-paused");
+        assert_eq!(line["stopped"], serde_json::json!([]));
+        assert_eq!(
+            line["not-stopped"],
+            serde_json::json!([
+                {
+                    "binary-id": "my-crate::basic",
+                    "test-name": "test_failure",
+                },
+            ])
+        );
+    }
+}
@@ -246,10 +246,10 @@ impl<'cfg> LibtestReporter<'cfg> {
                 (
                     KIND_TEST,
                     match run_statuses.last_status().result {
-                        ExecutionResult::Pass | ExecutionResult::Leak => EVENT_OK,
+                        ExecutionResult::Pass | ExecutionResult::Leak { .. } => EVENT_OK,
                         ExecutionResult::Fail { .. }
                         | ExecutionResult::ExecFail
-                        | ExecutionResult::Timeout => EVENT_FAILED,
+                        | ExecutionResult::Timeout { .. } => EVENT_FAILED,
                     },
                     test_instance,
                 )
@@ -382,7 +382,7 @@ impl<'cfg> LibtestReporter<'cfg> {
                         )?;
                         out.extend_from_slice(b"\"");
                     }
-                    ExecutionResult::Timeout => {
+                    ExecutionResult::Timeout { .. } => {
                         test_suite.failed += 1;
                         out.extend_from_slice(br#","reason":"time limit exceeded""#);
                     }
@@ -3,11 +3,19 @@
 
 //! Reporting of data in a streaming, structured fashion.
 //!
-//! Currently, the only output supported is a compatibility layer with libtest.
-//! At some point it would be worth designing a full-fidelity structured output.
+//! The built-in outputs are a compatibility layer with libtest, a
+//! [line-delimited JSON](jsonl) stream of events, [TAP](tap) output, and a
+//! compact binary [event log](event_log), but other formats can be added through a
+//! [`FormatterRegistry`]. At some point it would be worth designing a
+//! full-fidelity structured output.
 
+pub mod event_log;
 mod imp;
+pub mod jsonl;
 mod libtest;
+mod registry;
+pub mod tap;
 
 pub use imp::*;
 pub use libtest::*;
+pub use registry::*;
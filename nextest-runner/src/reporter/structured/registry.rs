@@ -0,0 +1,408 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! A registry of message formats, selected by name via `--message-format`.
+
+use super::{
+    event_log::{EventLogReporter, EVENT_LOG_FORMAT_VERSION},
+    jsonl::{JsonlReporter, JSONL_SCHEMA_VERSION},
+    tap::{TapReporter, TAP_VERSION},
+    EmitNextestObject, LibtestReporter,
+};
+use crate::{
+    errors::{CreateFormatterError, WriteEventError},
+    reporter::events::{AggregateRunStats, TestEvent},
+};
+use quick_junit::ReportUuid;
+use std::{collections::BTreeMap, error, fmt, io};
+
+/// A reporter that consumes test events and writes them out in some format.
+///
+/// Implementations are created by a [`TestReporterFactory`] registered with a
+/// [`FormatterRegistry`]. Test events are reported from a separate thread, so
+/// reporters must be `Send`.
+pub trait TestReporter<'a>: Send {
+    /// Called with the ID of the test run, before any events are written.
+    ///
+    /// This can be used to set up output that depends on the run ID, such as a
+    /// log file named after it. The default implementation does nothing.
+    fn on_run_id(&mut self, run_id: ReportUuid) -> Result<(), WriteEventError> {
+        let _ = run_id;
+        Ok(())
+    }
+
+    /// Writes a single test event.
+    fn write_event(&mut self, event: &TestEvent<'a>) -> Result<(), WriteEventError>;
+
+    /// Called once after the last of a sequence of runs with different profiles has finished,
+    /// with the statistics for all of them.
+    ///
+    /// This is only called if more than one profile was run. The default implementation does
+    /// nothing.
+    fn write_aggregate(&mut self, aggregate: &AggregateRunStats) -> Result<(), WriteEventError> {
+        let _ = aggregate;
+        Ok(())
+    }
+}
+
+impl<'a> TestReporter<'a> for LibtestReporter<'a> {
+    fn write_event(&mut self, event: &TestEvent<'a>) -> Result<(), WriteEventError> {
+        LibtestReporter::write_event(self, event)
+    }
+}
+
+/// Arguments passed into a [`TestReporterFactory`].
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct FormatterArgs<'a> {
+    /// The version of the format requested via `--message-format-version`, if any.
+    pub version: Option<&'a str>,
+}
+
+impl<'a> FormatterArgs<'a> {
+    /// Creates a new `FormatterArgs`.
+    pub fn new(version: Option<&'a str>) -> Self {
+        Self { version }
+    }
+}
+
+/// A function that creates a [`TestReporter`] for a message format.
+///
+/// The factory is called once per test run, after the list of tests is built. The
+/// returned reporter receives every event in the run, after the human-readable
+/// output is written to stderr. Errors returned by the factory are reported as
+/// setup errors.
+pub type TestReporterFactory =
+    for<'a> fn(
+        &FormatterArgs<'a>,
+    ) -> Result<Box<dyn TestReporter<'a> + 'a>, Box<dyn error::Error + Send + Sync>>;
+
+#[derive(Clone, Copy)]
+enum Formatter {
+    Human,
+    Structured(TestReporterFactory),
+}
+
+/// A message format looked up in a [`FormatterRegistry`].
+#[derive(Clone, Copy)]
+pub struct MessageFormat<'r> {
+    name: &'r str,
+    formatter: Formatter,
+}
+
+impl<'r> MessageFormat<'r> {
+    /// Returns the name of this format.
+    pub fn name(&self) -> &'r str {
+        self.name
+    }
+
+    /// Returns true if this is the [`HUMAN`](FormatterRegistry::HUMAN) format, which doesn't
+    /// produce any output beyond the human-readable output written to stderr.
+    pub fn is_human(&self) -> bool {
+        matches!(self.formatter, Formatter::Human)
+    }
+
+    /// Creates the reporter for this format.
+    ///
+    /// Returns `Ok(None)` for the [`HUMAN`](FormatterRegistry::HUMAN) format.
+    pub fn create<'a>(
+        &self,
+        args: &FormatterArgs<'a>,
+    ) -> Result<Option<Box<dyn TestReporter<'a> + 'a>>, CreateFormatterError> {
+        match self.formatter {
+            Formatter::Human => Ok(None),
+            Formatter::Structured(factory) => {
+                factory(args)
+                    .map(Some)
+                    .map_err(|error| CreateFormatterError::Factory {
+                        name: self.name.to_owned(),
+                        error,
+                    })
+            }
+        }
+    }
+}
+
+impl fmt::Debug for MessageFormat<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MessageFormat")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A mapping of message format names to the [`TestReporter`]s that implement them.
+///
+/// [`FormatterRegistry::new`] returns a registry with nextest's built-in formats;
+/// embedders can add their own with [`register`](Self::register) before
+/// invoking the run.
+#[derive(Clone)]
+pub struct FormatterRegistry {
+    formatters: BTreeMap<String, Formatter>,
+}
+
+impl FormatterRegistry {
+    /// The name of the default format, which only writes human-readable output to stderr.
+    pub const HUMAN: &'static str = "human";
+
+    /// The name of the libtest-compatible JSON format.
+    pub const LIBTEST_JSON: &'static str = "libtest-json";
+
+    /// The name of the libtest-compatible JSON format with an additional `nextest` subobject.
+    pub const LIBTEST_JSON_PLUS: &'static str = "libtest-json-plus";
+
+    /// The name of the compact binary [event log](super::event_log) format.
+    pub const BINARY_EVENT_LOG: &'static str = "binary-event-log";
+
+    /// The name of the [line-delimited JSON](super::jsonl) event stream format.
+    pub const JSONL: &'static str = "jsonl";
+
+    /// The name of the [Test Anything Protocol](super::tap) format.
+    pub const TAP: &'static str = "tap";
+
+    /// Creates a new registry with the built-in formats registered.
+    pub fn new() -> Self {
+        let mut formatters = BTreeMap::new();
+        formatters.insert(Self::HUMAN.to_owned(), Formatter::Human);
+        formatters.insert(
+            Self::LIBTEST_JSON.to_owned(),
+            Formatter::Structured(libtest_json),
+        );
+        formatters.insert(
+            Self::LIBTEST_JSON_PLUS.to_owned(),
+            Formatter::Structured(libtest_json_plus),
+        );
+        formatters.insert(
+            Self::BINARY_EVENT_LOG.to_owned(),
+            Formatter::Structured(binary_event_log),
+        );
+        formatters.insert(Self::JSONL.to_owned(), Formatter::Structured(jsonl));
+        formatters.insert(Self::TAP.to_owned(), Formatter::Structured(tap));
+        Self { formatters }
+    }
+
+    /// Registers a format under the given name.
+    ///
+    /// If a format with this name already exists, including a built-in one, it is replaced.
+    pub fn register(&mut self, name: impl Into<String>, factory: TestReporterFactory) -> &mut Self {
+        self.formatters
+            .insert(name.into(), Formatter::Structured(factory));
+        self
+    }
+
+    /// Returns the names of all registered formats, in sorted order.
+    pub fn names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.formatters.keys().map(|name| name.as_str())
+    }
+
+    /// Returns true if a format with this name is registered.
+    pub fn contains(&self, name: &str) -> bool {
+        self.formatters.contains_key(name)
+    }
+
+    /// Looks up the format with the given name.
+    pub fn get(&self, name: &str) -> Result<MessageFormat<'_>, CreateFormatterError> {
+        match self.formatters.get_key_value(name) {
+            Some((name, formatter)) => Ok(MessageFormat {
+                name,
+                formatter: *formatter,
+            }),
+            None => Err(CreateFormatterError::Unknown {
+                name: name.to_owned(),
+                known: self.names().map(|name| name.to_owned()).collect(),
+            }),
+        }
+    }
+
+    /// Creates the reporter for the format with the given name.
+    ///
+    /// This is a shortcut for [`get`](Self::get) followed by [`MessageFormat::create`].
+    pub fn create<'a>(
+        &self,
+        name: &str,
+        args: &FormatterArgs<'a>,
+    ) -> Result<Option<Box<dyn TestReporter<'a> + 'a>>, CreateFormatterError> {
+        self.get(name)?.create(args)
+    }
+}
+
+impl Default for FormatterRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for FormatterRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FormatterRegistry")
+            .field("names", &self.formatters.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+fn libtest_json<'a>(
+    args: &FormatterArgs<'a>,
+) -> Result<Box<dyn TestReporter<'a> + 'a>, Box<dyn error::Error + Send + Sync>> {
+    Ok(Box::new(LibtestReporter::new(
+        args.version,
+        EmitNextestObject::No,
+    )?))
+}
+
+fn libtest_json_plus<'a>(
+    args: &FormatterArgs<'a>,
+) -> Result<Box<dyn TestReporter<'a> + 'a>, Box<dyn error::Error + Send + Sync>> {
+    Ok(Box::new(LibtestReporter::new(
+        args.version,
+        EmitNextestObject::Yes,
+    )?))
+}
+
+fn binary_event_log<'a>(
+    args: &FormatterArgs<'a>,
+) -> Result<Box<dyn TestReporter<'a> + 'a>, Box<dyn error::Error + Send + Sync>> {
+    if let Some(version) = args.version {
+        if version.parse::<u16>() != Ok(EVENT_LOG_FORMAT_VERSION) {
+            return Err(format!(
+                "unsupported event log format version `{version}` \
+                 (supported: {EVENT_LOG_FORMAT_VERSION})"
+            )
+            .into());
+        }
+    }
+    Ok(Box::new(EventLogReporter::new(io::stdout())?))
+}
+
+fn jsonl<'a>(
+    args: &FormatterArgs<'a>,
+) -> Result<Box<dyn TestReporter<'a> + 'a>, Box<dyn error::Error + Send + Sync>> {
+    if let Some(version) = args.version {
+        if version.parse::<u32>() != Ok(JSONL_SCHEMA_VERSION) {
+            return Err(format!(
+                "unsupported JSONL schema version `{version}` \
+                 (supported: {JSONL_SCHEMA_VERSION})"
+            )
+            .into());
+        }
+    }
+    Ok(Box::new(JsonlReporter::new(io::stdout())))
+}
+
+fn tap<'a>(
+    args: &FormatterArgs<'a>,
+) -> Result<Box<dyn TestReporter<'a> + 'a>, Box<dyn error::Error + Send + Sync>> {
+    if let Some(version) = args.version {
+        if version.parse::<u32>() != Ok(TAP_VERSION) {
+            return Err(
+                format!("unsupported TAP version `{version}` (supported: {TAP_VERSION})").into(),
+            );
+        }
+    }
+    Ok(Box::new(TapReporter::new(io::stdout())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct NoopReporter;
+
+    impl<'a> TestReporter<'a> for NoopReporter {
+        fn write_event(&mut self, _event: &TestEvent<'a>) -> Result<(), WriteEventError> {
+            Ok(())
+        }
+    }
+
+    fn noop<'a>(
+        _args: &FormatterArgs<'a>,
+    ) -> Result<Box<dyn TestReporter<'a> + 'a>, Box<dyn error::Error + Send + Sync>> {
+        Ok(Box::new(NoopReporter))
+    }
+
+    #[test]
+    fn formatter_registry() {
+        let mut registry = FormatterRegistry::new();
+        assert_eq!(
+            registry.names().collect::<Vec<_>>(),
+            [
+                "binary-event-log",
+                "human",
+                "jsonl",
+                "libtest-json",
+                "libtest-json-plus",
+                "tap"
+            ],
+        );
+        assert!(registry
+            .create(FormatterRegistry::HUMAN, &FormatterArgs::default())
+            .expect("human format is valid")
+            .is_none());
+        assert!(registry
+            .create(FormatterRegistry::LIBTEST_JSON, &FormatterArgs::default())
+            .expect("libtest-json format is valid")
+            .is_some());
+
+        // Errors from the factory are surfaced.
+        let error = registry
+            .create(
+                FormatterRegistry::LIBTEST_JSON,
+                &FormatterArgs::new(Some("foo")),
+            )
+            .err()
+            .expect("invalid version is rejected");
+        assert!(
+            matches!(&error, CreateFormatterError::Factory { name, .. } if name == "libtest-json"),
+            "unexpected error: {error:?}"
+        );
+
+        // Unknown formats are rejected.
+        assert!(registry.get(FormatterRegistry::HUMAN).unwrap().is_human());
+        let error = registry
+            .get("my-formatter")
+            .expect_err("my-formatter isn't registered yet");
+        assert_eq!(
+            error.to_string(),
+            "unknown message format `my-formatter` \
+             (known formats: binary-event-log, human, jsonl, libtest-json, libtest-json-plus, tap)"
+        );
+
+        registry.register("my-formatter", noop);
+        assert!(registry.contains("my-formatter"));
+        assert!(registry
+            .create("my-formatter", &FormatterArgs::default())
+            .expect("my-formatter is now registered")
+            .is_some());
+    }
+
+    #[test]
+    fn on_run_id() {
+        use crate::reporter::structured::StructuredReporter;
+        use std::sync::{Arc, Mutex};
+
+        struct RunIdReporter(Arc<Mutex<Option<ReportUuid>>>);
+
+        impl<'a> TestReporter<'a> for RunIdReporter {
+            fn on_run_id(&mut self, run_id: ReportUuid) -> Result<(), WriteEventError> {
+                *self.0.lock().unwrap() = Some(run_id);
+                Ok(())
+            }
+
+            fn write_event(&mut self, _event: &TestEvent<'a>) -> Result<(), WriteEventError> {
+                Ok(())
+            }
+        }
+
+        // The default implementation does nothing.
+        let mut reporter = noop(&FormatterArgs::default()).unwrap();
+        reporter
+            .on_run_id(ReportUuid::new_v4())
+            .expect("default on_run_id succeeds");
+
+        let run_id = ReportUuid::new_v4();
+        let seen = Arc::new(Mutex::new(None));
+        let mut structured = StructuredReporter::new();
+        structured.set_reporter(Box::new(RunIdReporter(seen.clone())));
+        structured.on_run_id(run_id).unwrap();
+        assert_eq!(*seen.lock().unwrap(), Some(run_id));
+    }
+}
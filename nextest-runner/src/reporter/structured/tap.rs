@@ -0,0 +1,287 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Output in the [Test Anything Protocol](https://testanything.org/tap-version-14-specification.html)
+//! (TAP) format.
+//!
+//! Each test is written out as a TAP version 14 test point as soon as it finishes or is skipped.
+//! Failing tests are followed by a YAML diagnostic block with the test's captured output.
+//!
+//! Test points are numbered in the order tests finish, so the plan is written at the end of the
+//! run rather than at the beginning.
+
+use super::TestReporter;
+use crate::{
+    errors::{DisplayErrorChain, WriteEventError},
+    list::TestInstance,
+    reporter::events::{
+        ExecuteStatus, ExecutionDescription, ExecutionResult, ExecutionStatuses, TestEvent,
+        TestEventKind,
+    },
+    test_output::{ChildExecutionOutput, ChildOutput, ChildSingleOutput},
+};
+use std::{
+    fmt,
+    io::{self, Write},
+};
+use swrite::{swrite, swriteln, SWrite};
+
+/// The version of TAP written by this version of nextest.
+pub const TAP_VERSION: u32 = 14;
+
+/// A reporter that writes test events in the TAP format.
+#[derive(Debug)]
+pub struct TapReporter<W> {
+    writer: W,
+    buf: String,
+    // The number of test points written so far.
+    test_points: usize,
+}
+
+impl<W: Write> TapReporter<W> {
+    /// Creates a new reporter that writes to `writer`.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            buf: String::new(),
+            test_points: 0,
+        }
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_event_impl(&mut self, event: &TestEvent<'_>) -> io::Result<()> {
+        self.buf.clear();
+        match &event.kind {
+            TestEventKind::RunStarted { .. } => {
+                swriteln!(self.buf, "TAP version {TAP_VERSION}");
+            }
+            TestEventKind::TestFinished {
+                test_instance,
+                quarantined,
+                run_statuses,
+                ..
+            } => {
+                self.test_points += 1;
+                write_test_finished(
+                    &mut self.buf,
+                    self.test_points,
+                    test_instance,
+                    *quarantined,
+                    run_statuses,
+                );
+            }
+            TestEventKind::TestSkipped {
+                test_instance,
+                reason,
+            } => {
+                self.test_points += 1;
+                swriteln!(
+                    self.buf,
+                    "ok {} - {} # SKIP {reason}",
+                    self.test_points,
+                    TapDescription(test_instance),
+                );
+            }
+            TestEventKind::RunFinished { run_stats, .. } => {
+                // Skipped tests aren't part of initial_run_count, but they're still written out
+                // as test points. If the run was cancelled, fewer test points than this will have
+                // been written, which TAP consumers treat as a failure.
+                swriteln!(
+                    self.buf,
+                    "1..{}",
+                    run_stats.initial_run_count + run_stats.skipped
+                );
+            }
+            _ => return Ok(()),
+        }
+
+        // Flush after every event so that consumers see test points as they happen.
+        self.writer.write_all(self.buf.as_bytes())?;
+        self.writer.flush()
+    }
+}
+
+impl<'a, W: Write + Send> TestReporter<'a> for TapReporter<W> {
+    fn write_event(&mut self, event: &TestEvent<'a>) -> Result<(), WriteEventError> {
+        self.write_event_impl(event).map_err(WriteEventError::Io)
+    }
+}
+
+fn write_test_finished(
+    out: &mut String,
+    number: usize,
+    test_instance: &TestInstance<'_>,
+    quarantined: bool,
+    run_statuses: &ExecutionStatuses,
+) {
+    let description = TapDescription(test_instance);
+    match run_statuses.describe() {
+        ExecutionDescription::Success { .. } => {
+            swriteln!(out, "ok {number} - {description}");
+        }
+        ExecutionDescription::Flaky { prior_statuses, .. } => {
+            // TODO tests are ones that aren't expected to pass yet, which is the closest TAP has
+            // to a flaky test.
+            swriteln!(
+                out,
+                "ok {number} - {description} # TODO flaky: passed after {} failed {}",
+                prior_statuses.len(),
+                if prior_statuses.len() == 1 {
+                    "attempt"
+                } else {
+                    "attempts"
+                },
+            );
+        }
+        ExecutionDescription::Failure { last_status, .. } => {
+            // Failures of quarantined tests don't cause the run to fail, and failing TODO tests
+            // don't cause TAP consumers to fail either.
+            swrite!(out, "not ok {number} - {description}");
+            if quarantined {
+                swrite!(out, " # TODO quarantined");
+            }
+            out.push('\n');
+            write_diagnostics(out, run_statuses.len(), last_status);
+        }
+    }
+}
+
+/// Writes a YAML diagnostic block for a failing test.
+fn write_diagnostics(out: &mut String, attempts: usize, last_status: &ExecuteStatus) {
+    swriteln!(out, "  ---");
+    swriteln!(out, "  result: {}", result_str(last_status.result));
+    swriteln!(out, "  attempts: {attempts}");
+    swriteln!(out, "  duration_ms: {}", last_status.time_taken.as_millis());
+    match &last_status.output {
+        ChildExecutionOutput::Output { output, errors, .. } => {
+            match output {
+                ChildOutput::Combined { output } => write_yaml_output(out, "output", output),
+                ChildOutput::Split(split) => {
+                    if let Some(stdout) = &split.stdout {
+                        write_yaml_output(out, "stdout", stdout);
+                    }
+                    if let Some(stderr) = &split.stderr {
+                        write_yaml_output(out, "stderr", stderr);
+                    }
+                }
+            }
+            if let Some(errors) = errors {
+                write_yaml_block(out, "errors", &DisplayErrorChain::new(errors).to_string());
+            }
+        }
+        ChildExecutionOutput::StartError(error) => {
+            write_yaml_block(out, "errors", &DisplayErrorChain::new(error).to_string());
+        }
+    }
+    swriteln!(out, "  ...");
+}
+
+fn write_yaml_output(out: &mut String, key: &str, output: &ChildSingleOutput) {
+    // Escape sequences aren't allowed in YAML, so strip colors from the output.
+    let stripped = strip_ansi_escapes::strip(output.as_str_lossy());
+    write_yaml_block(out, key, &String::from_utf8_lossy(&stripped));
+}
+
+/// Writes `text` as a YAML literal block scalar within a diagnostic block.
+fn write_yaml_block(out: &mut String, key: &str, text: &str) {
+    if text.is_empty() {
+        swriteln!(out, "  {key}: ''");
+        return;
+    }
+
+    // YAML detects the indentation of a block scalar from its first non-empty line, so if that
+    // line starts with a space, the indentation has to be specified explicitly.
+    let first_line_indented = text
+        .lines()
+        .find(|line| !line.is_empty())
+        .is_some_and(|line| line.starts_with(' '));
+    let indicator = if first_line_indented { "2" } else { "" };
+    swriteln!(out, "  {key}: |{indicator}");
+    for line in text.lines() {
+        if line.is_empty() {
+            out.push('\n');
+        } else {
+            swriteln!(out, "    {line}");
+        }
+    }
+}
+
+fn result_str(result: ExecutionResult) -> &'static str {
+    match result {
+        ExecutionResult::Pass => "pass",
+        ExecutionResult::Leak { .. } => "leak",
+        ExecutionResult::Fail { .. } => "fail",
+        ExecutionResult::ExecFail => "exec-fail",
+        ExecutionResult::Timeout { .. } => "timeout",
+    }
+}
+
+/// The description of a test point: the binary ID and test name, with TAP's special characters
+/// escaped.
+struct TapDescription<'a, 'b>(&'a TestInstance<'b>);
+
+impl fmt::Display for TapDescription<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_escaped(f, self.0.suite_info.binary_id.as_str())?;
+        f.write_str(" ")?;
+        write_escaped(f, self.0.name)
+    }
+}
+
+/// Escapes `#` and `\` in a test point description, so that they aren't interpreted as the start
+/// of a directive.
+fn write_escaped(f: &mut impl fmt::Write, s: &str) -> fmt::Result {
+    for c in s.chars() {
+        match c {
+            '\\' => f.write_str("\\\\")?,
+            '#' => f.write_str("\\#")?,
+            c => f.write_char(c)?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_description() {
+        let mut out = String::new();
+        write_escaped(&mut out, r"test_#1 with \ backslash").unwrap();
+        assert_eq!(out, r"test_\#1 with \\ backslash");
+    }
+
+    #[test]
+    fn yaml_blocks() {
+        let mut out = String::new();
+        write_yaml_block(&mut out, "output", "");
+        write_yaml_block(&mut out, "output", "running 1 test\n\nthread panicked\n");
+        write_yaml_block(&mut out, "stderr", "\n   indented\nnot indented");
+        assert_eq!(
+            out,
+            "  output: ''\n\
+             \x20 output: |\n\
+             \x20   running 1 test\n\
+             \n\
+             \x20   thread panicked\n\
+             \x20 stderr: |2\n\
+             \n\
+             \x20      indented\n\
+             \x20   not indented\n"
+        );
+    }
+
+    #[test]
+    fn yaml_output_strips_colors() {
+        let mut out = String::new();
+        let output: ChildSingleOutput =
+            bytes::Bytes::from_static(b"\x1b[31merror\x1b[0m: oh no\n").into();
+        write_yaml_output(&mut out, "output", &output);
+        assert_eq!(out, "  output: |\n    error: oh no\n");
+    }
+}
@@ -0,0 +1,163 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Grouping of tests by normalized name, used for reporting.
+//!
+//! If `normalize-test-names` is set, each test whose name matches the pattern is grouped with the
+//! other tests in the same binary that have the same base name. Groups are computed once, when the
+//! reporter is built. This is presentation-only: tests are still run and reported individually.
+
+use crate::{
+    config::{EvaluatableProfile, TestNameNormalization},
+    list::{TestInstanceId, TestList},
+};
+use nextest_metadata::RustBinaryId;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct TestNameGroups {
+    groups: Vec<TestNameGroupInfo>,
+    // Binary ID -> test name -> index into `groups`.
+    tests: HashMap<RustBinaryId, HashMap<String, usize>>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct TestNameGroupInfo {
+    pub(crate) binary_id: RustBinaryId,
+    pub(crate) base_name: String,
+    // The number of cases in this group that are expected to run.
+    pub(crate) initial_run_count: usize,
+}
+
+impl TestNameGroupInfo {
+    pub(crate) fn id(&self) -> TestInstanceId<'_> {
+        TestInstanceId {
+            binary_id: &self.binary_id,
+            test_name: &self.base_name,
+        }
+    }
+}
+
+impl TestNameGroups {
+    pub(crate) fn new(profile: &EvaluatableProfile<'_>, test_list: &TestList<'_>) -> Self {
+        let Some(normalization) = profile.normalize_test_names() else {
+            return Self::default();
+        };
+
+        Self::from_tests(
+            normalization,
+            test_list.iter_tests().map(|test_instance| {
+                (
+                    &test_instance.suite_info.binary_id,
+                    test_instance.name,
+                    test_instance.test_info.filter_match.is_match(),
+                )
+            }),
+        )
+    }
+
+    /// Groups tests, given as `(binary ID, test name, is expected to run)`.
+    pub(super) fn from_tests<'a>(
+        normalization: &TestNameNormalization,
+        tests: impl IntoIterator<Item = (&'a RustBinaryId, &'a str, bool)>,
+    ) -> Self {
+        // Collect into a BTreeMap first so groups are sorted by binary ID, then base name.
+        let mut members: BTreeMap<(&RustBinaryId, &str), (usize, Vec<&str>)> = BTreeMap::new();
+        for (binary_id, test_name, is_match) in tests {
+            let Some(base_name) = normalization.normalize(test_name) else {
+                continue;
+            };
+            let (initial_run_count, test_names) =
+                members.entry((binary_id, base_name)).or_default();
+            if is_match {
+                *initial_run_count += 1;
+            }
+            test_names.push(test_name);
+        }
+
+        let mut groups = Vec::with_capacity(members.len());
+        let mut tests: HashMap<RustBinaryId, HashMap<String, usize>> = HashMap::new();
+        for ((binary_id, base_name), (initial_run_count, test_names)) in members {
+            let index = groups.len();
+            groups.push(TestNameGroupInfo {
+                binary_id: binary_id.clone(),
+                base_name: base_name.to_owned(),
+                initial_run_count,
+            });
+            let binary_tests = tests.entry(binary_id.clone()).or_default();
+            for test_name in test_names {
+                binary_tests.insert(test_name.to_owned(), index);
+            }
+        }
+
+        Self { groups, tests }
+    }
+
+    /// Returns the groups, sorted by binary ID and then base name.
+    pub(crate) fn groups(&self) -> &[TestNameGroupInfo] {
+        &self.groups
+    }
+
+    /// Returns the index (into [`Self::groups`]) of the group a test belongs to, if any.
+    pub(crate) fn group_for(&self, test_instance: TestInstanceId<'_>) -> Option<usize> {
+        self.tests
+            .get(test_instance.binary_id)
+            .and_then(|tests| tests.get(test_instance.test_name))
+            .copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_tests() {
+        let normalization = TestNameNormalization::new(r"^(.+)\[.*\]$").unwrap();
+        let binary_a = RustBinaryId::new("crate-a");
+        let binary_b = RustBinaryId::new("crate-b");
+        let tests = [
+            (&binary_b, "test_foo[case=1]", true),
+            (&binary_a, "test_foo[case=2]", true),
+            (&binary_a, "test_foo[case=1]", true),
+            (&binary_a, "test_foo[case=3]", false),
+            (&binary_a, "test_bar[x]", true),
+            (&binary_a, "test_plain", true),
+        ];
+        let groups = TestNameGroups::from_tests(&normalization, tests);
+
+        // Groups are sorted, and the same base name in different binaries is a different group.
+        let summary: Vec<_> = groups
+            .groups()
+            .iter()
+            .map(|group| (group.id().to_string(), group.initial_run_count))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("crate-a test_bar".to_owned(), 1),
+                ("crate-a test_foo".to_owned(), 2),
+                ("crate-b test_foo".to_owned(), 1),
+            ],
+        );
+
+        let group_for = |binary_id, test_name| {
+            groups
+                .group_for(TestInstanceId {
+                    binary_id,
+                    test_name,
+                })
+                .map(|index| groups.groups()[index].id().to_string())
+        };
+        assert_eq!(
+            group_for(&binary_a, "test_foo[case=3]").as_deref(),
+            Some("crate-a test_foo"),
+        );
+        assert_eq!(
+            group_for(&binary_b, "test_foo[case=1]").as_deref(),
+            Some("crate-b test_foo"),
+        );
+        assert_eq!(group_for(&binary_a, "test_plain"), None);
+        assert_eq!(group_for(&binary_b, "test_bar[x]"), None);
+    }
+}
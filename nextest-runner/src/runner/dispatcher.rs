@@ -7,28 +7,44 @@
 //! receives events from the executor and from other inputs (e.g. signal and
 //! input handling), and sends events to the reporter.
 
-use super::{RunUnitRequest, RunnerTaskState, ShutdownRequest};
+#[cfg(unix)]
+use super::UnitStopStatus;
+use super::{CancelHook, RunUnitRequest, RunnerTaskState, ShutdownRequest};
 use crate::{
-    config::{MaxFail, ScriptConfig, ScriptId},
+    config::{MaxFail, OnSlow, ScriptConfig, ScriptId, SmokeMode},
     input::{InputEvent, InputHandler},
-    list::{TestInstance, TestInstanceId, TestList},
+    list::{RustTestSuiteStatus, TestInstance, TestInstanceId, TestList},
     reporter::events::{
-        CancelReason, ExecuteStatus, ExecutionStatuses, InfoResponse, RunStats, TestEvent,
+        CancelReason, CancelTrigger, DurationHistogram, ExecuteStatus, ExecutionStatuses,
+        InfoResponse, PackageStats, QueuedTests, RetryData, RunPhase, RunStats, TestEvent,
         TestEventKind,
     },
     runner::{ExecutorEvent, RunUnitQuery, SignalRequest},
-    signal::{JobControlEvent, ShutdownEvent, SignalEvent, SignalHandler, SignalInfoEvent},
+    signal::{
+        JobControlEvent, ShutdownEvent, SignalEvent, SignalHandler, SignalInfoEvent, SigtstpPolicy,
+    },
+    test_filter::TestFilterSummary,
     time::StopwatchStart,
 };
+use camino::{Utf8Path, Utf8PathBuf};
 use chrono::Local;
 use debug_ignore::DebugIgnore;
+use futures::FutureExt;
+#[cfg(unix)]
+use futures::{stream::FuturesUnordered, StreamExt};
+use nextest_metadata::RustBinaryId;
 use quick_junit::ReportUuid;
-use std::{collections::BTreeMap, time::Duration};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    process::Stdio,
+    time::Duration,
+};
 use tokio::sync::{
     mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
     oneshot,
 };
-use tracing::debug;
+use tracing::{debug, warn};
 
 /// Context for the dispatcher.
 ///
@@ -43,10 +59,24 @@ pub(super) struct DispatcherContext<'a, F> {
     cli_args: Vec<String>,
     stopwatch: StopwatchStart,
     run_stats: RunStats,
+    package_stats: PackageStats,
+    duration_histogram: Option<DurationHistogram>,
+    flaky_tests: BTreeSet<TestInstanceId<'a>>,
     max_fail: MaxFail,
+    count_setup_script_failures: bool,
+    #[cfg_attr(not(unix), expect(dead_code))]
+    sigtstp: SigtstpPolicy,
+    slow_hook: Option<SlowHook>,
+    smoke_mode: Option<SmokeMode>,
+    current_phase: Option<ContextPhase>,
+    binaries: BTreeMap<&'a RustBinaryId, ContextBinary<'a>>,
     running_setup_script: Option<ContextSetupScript<'a>>,
     running_tests: BTreeMap<TestInstanceId<'a>, ContextTestInstance<'a>>,
+    queued_tests: TestQueue<'a>,
     cancel_state: Option<CancelReason>,
+    cancel_grace: Duration,
+    // Set once a signal cancels the run with a non-zero cancel grace period.
+    grace_deadline: Option<Duration>,
     signal_count: Option<SignalCount>,
     #[cfg(test)]
     disable_signal_3_times_panic: bool,
@@ -56,6 +86,7 @@ impl<'a, F> DispatcherContext<'a, F>
 where
     F: FnMut(TestEvent<'a>) + Send,
 {
+    #[expect(clippy::too_many_arguments)]
     pub(super) fn new(
         callback: F,
         run_id: ReportUuid,
@@ -63,6 +94,12 @@ where
         cli_args: Vec<String>,
         initial_run_count: usize,
         max_fail: MaxFail,
+        count_setup_script_failures: bool,
+        sigtstp: SigtstpPolicy,
+        slow_hook: Option<SlowHook>,
+        smoke_mode: Option<SmokeMode>,
+        duration_histogram: Option<DurationHistogram>,
+        cancel_grace: Duration,
     ) -> Self {
         Self {
             callback: DebugIgnore(callback),
@@ -74,10 +111,22 @@ where
                 initial_run_count,
                 ..RunStats::default()
             },
+            package_stats: PackageStats::default(),
+            duration_histogram,
+            flaky_tests: BTreeSet::new(),
             max_fail,
+            count_setup_script_failures,
+            sigtstp,
+            slow_hook,
+            smoke_mode,
+            current_phase: None,
+            binaries: BTreeMap::new(),
             running_setup_script: None,
             running_tests: BTreeMap::new(),
+            queued_tests: TestQueue::default(),
             cancel_state: None,
+            cancel_grace,
+            grace_deadline: None,
             signal_count: None,
             #[cfg(test)]
             disable_signal_3_times_panic: false,
@@ -97,13 +146,27 @@ where
         signal_handler: &mut SignalHandler,
         input_handler: &mut InputHandler,
         report_cancel_rx: oneshot::Receiver<()>,
+        cancel_hook: Option<CancelHook>,
     ) -> RunnerTaskState {
         let mut report_cancel_rx = std::pin::pin!(report_cancel_rx);
 
+        // The hook's future is created here so that it can make use of the
+        // Tokio runtime.
+        let mut cancel_hook_done = cancel_hook.is_none();
+        let mut cancel_hook_fut = match cancel_hook {
+            Some(hook) => hook.into_future(),
+            None => std::future::pending().boxed(),
+        };
+
         let mut signals_done = false;
         let mut inputs_done = false;
         let mut report_cancel_rx_done = false;
 
+        // While running units are being given time to finish after a signal, the shutdown request
+        // to send them once the grace period is over.
+        let mut pending_shutdown = None;
+        let mut grace_sleep = std::pin::pin!(tokio::time::sleep(Duration::ZERO));
+
         loop {
             let internal_event = tokio::select! {
                 internal_event = executor_rx.recv() => {
@@ -154,20 +217,40 @@ where
                         }
                     }
                 }
+                _ = grace_sleep.as_mut(), if pending_shutdown.is_some() => {
+                    let req = pending_shutdown.take().expect("checked above");
+                    debug!("cancel grace period is over, terminating running units");
+                    self.broadcast_request(RunUnitRequest::Signal(SignalRequest::Shutdown(req)));
+                    continue;
+                }
+                reason = &mut cancel_hook_fut, if !cancel_hook_done => {
+                    // The hook is only polled until it completes, whether or
+                    // not it returned a reason.
+                    cancel_hook_done = true;
+                    match reason {
+                        Some(reason) => InternalEvent::CustomCancel(reason),
+                        None => continue,
+                    }
+                }
             };
 
             match self.handle_event(internal_event) {
                 #[cfg(unix)]
                 HandleEventResponse::JobControl(JobControlEvent::Stop) => {
-                    // This is in reality bounded by the number of tests
-                    // currently running.
-                    let (status_tx, mut status_rx) = unbounded_channel();
-                    self.broadcast_request(RunUnitRequest::Signal(SignalRequest::Stop(status_tx)));
-
-                    debug!(
-                        remaining = status_rx.sender_strong_count(),
-                        "stopping tests"
-                    );
+                    // Each unit responds on its own channel, so that tests
+                    // which keep running can be identified.
+                    let mut pending: FuturesUnordered<_> = self
+                        .request_stop()
+                        .into_iter()
+                        .map(|(id, mut status_rx)| async move { (id, status_rx.recv().await) })
+                        .collect();
+
+                    // Tests are assumed to have stopped unless they report
+                    // otherwise.
+                    let mut stopped: BTreeSet<_> = self.running_tests.keys().copied().collect();
+                    let mut not_stopped = BTreeSet::new();
+
+                    debug!(remaining = pending.len(), "stopping tests");
 
                     // There's a possibility of a race condition between a test
                     // exiting and sending the message to the receiver. For that
@@ -176,21 +259,37 @@ where
 
                     loop {
                         tokio::select! {
-                            res = status_rx.recv() => {
+                            res = pending.next() => {
+                                let Some((id, status)) = res else {
+                                    // All units have responded.
+                                    break;
+                                };
                                 debug!(
-                                    res = ?res,
-                                    remaining = status_rx.sender_strong_count(),
-                                    "test stopped",
+                                    ?id,
+                                    ?status,
+                                    remaining = pending.len(),
+                                    "unit stopped",
                                 );
-                                if res.is_none() {
-                                    // No remaining message in the channel's
-                                    // buffer.
-                                    break;
+                                let Some(id) = id else {
+                                    // Setup scripts are only counted.
+                                    continue;
+                                };
+                                match status {
+                                    Some(UnitStopStatus::NotStopped) => {
+                                        stopped.remove(&id);
+                                        not_stopped.insert(id);
+                                    }
+                                    Some(UnitStopStatus::Stopped | UnitStopStatus::Unknown) => {}
+                                    None => {
+                                        // The test exited before it could
+                                        // respond.
+                                        stopped.remove(&id);
+                                    }
                                 }
                             }
                             _ = &mut sleep => {
                                 debug!(
-                                    remaining = status_rx.sender_strong_count(),
+                                    remaining = pending.len(),
                                     "timeout waiting for tests to stop, ignoring",
                                 );
                                 break;
@@ -198,6 +297,13 @@ where
                         };
                     }
 
+                    self.basic_callback(TestEventKind::RunPaused {
+                        setup_scripts_running: self.setup_scripts_running(),
+                        running: self.running(),
+                        stopped,
+                        not_stopped,
+                    });
+
                     // Restore the terminal state.
                     input_handler.suspend();
 
@@ -285,6 +391,28 @@ where
                             // A test failure has caused cancellation to begin.
                             self.broadcast_request(RunUnitRequest::OtherCancel);
                         }
+                        CancelEvent::Custom => {
+                            // The cancel hook has caused cancellation to begin.
+                            // This is treated the same as a test failure.
+                            self.broadcast_request(RunUnitRequest::OtherCancel);
+                        }
+                        CancelEvent::Signal(req @ ShutdownRequest::Once(_))
+                            if self.grace_deadline.is_some() =>
+                        {
+                            // A signal has caused cancellation to begin, but running units
+                            // are given until the grace deadline to finish on their own.
+                            // Treat this like any other cancellation until then.
+                            let deadline = self.grace_deadline.expect("checked above");
+                            if pending_shutdown.is_none() {
+                                let remaining =
+                                    deadline.saturating_sub(self.stopwatch.snapshot().active);
+                                grace_sleep
+                                    .as_mut()
+                                    .reset(tokio::time::Instant::now() + remaining);
+                            }
+                            pending_shutdown = Some(req);
+                            self.broadcast_request(RunUnitRequest::OtherCancel);
+                        }
                         CancelEvent::Signal(req) => {
                             // A signal has caused cancellation to begin. Let all the child
                             // processes know about the signal, and continue to handle
@@ -293,6 +421,7 @@ where
                             // Ignore errors here: if there are no receivers to cancel, so
                             // be it. Also note the ordering here: cancelled_ref is set
                             // *before* this is sent.
+                            pending_shutdown = None;
                             self.broadcast_request(RunUnitRequest::Signal(
                                 SignalRequest::Shutdown(req),
                             ));
@@ -304,13 +433,61 @@ where
         }
     }
 
-    pub(super) fn run_started(&mut self, test_list: &'a TestList) {
+    /// Reports that the run has started.
+    ///
+    /// `queue` is the tests that will be run, in the order they'll be started in.
+    pub(super) fn run_started(
+        &mut self,
+        test_list: &'a TestList,
+        filter: Option<TestFilterSummary>,
+        shuffle_seed: Option<u64>,
+        queue: impl IntoIterator<Item = TestInstanceId<'a>>,
+    ) {
+        self.queued_tests = TestQueue::new(queue);
+
         self.basic_callback(TestEventKind::RunStarted {
             test_list,
             run_id: self.run_id,
             profile_name: self.profile_name.clone(),
             cli_args: self.cli_args.clone(),
-        })
+            filter,
+            shuffle_seed,
+        });
+
+        for suite in test_list.iter() {
+            let RustTestSuiteStatus::Listed { test_cases } = &suite.status else {
+                continue;
+            };
+            let initial_run_count = test_cases
+                .values()
+                .filter(|case| case.filter_match.is_match())
+                .count();
+            let binary = ContextBinary {
+                package_name: suite.package.name(),
+                test_count: test_cases.len(),
+                remaining: test_cases.len(),
+                start: None,
+                run_stats: RunStats {
+                    initial_run_count,
+                    ..RunStats::default()
+                },
+            };
+            // Binaries for the same package (e.g. for the host and target platforms) are
+            // merged into one entry.
+            if !test_cases.is_empty() {
+                self.package_stats
+                    .stats_mut(suite.package.name())
+                    .initial_run_count += initial_run_count;
+            }
+            self.binaries.insert(&suite.binary_id, binary);
+
+            // Binaries without any tests will never see a test event, so report them right
+            // away.
+            if test_cases.is_empty() {
+                self.binary_started(&suite.binary_id);
+                self.binary_finished(&suite.binary_id);
+            }
+        }
     }
 
     #[inline]
@@ -370,12 +547,14 @@ where
             InternalEvent::Executor(ExecutorEvent::SetupScriptSlow {
                 script_id,
                 config,
+                slow_timeout,
                 elapsed,
                 will_terminate,
             }) => self.callback_none_response(TestEventKind::SetupScriptSlow {
                 script_id,
                 command: config.program(),
                 args: config.args(),
+                slow_timeout,
                 elapsed,
                 will_terminate: will_terminate.is_some(),
             }),
@@ -388,30 +567,175 @@ where
             }) => {
                 self.finish_setup_script();
                 self.run_stats.on_setup_script_finished(&status);
-                // Setup scripts failing always cause the entire test run to be cancelled
-                // (--no-fail-fast is ignored).
-                let fail_cancel = !status.result.is_success();
+                // By default, setup scripts failing always cause the entire test run to be
+                // cancelled (--no-fail-fast is ignored). Otherwise, they count towards max-fail.
+                let fail_cancel = !status.result.is_success()
+                    && (!self.count_setup_script_failures
+                        || self.max_fail.is_exceeded(self.max_fail_count()));
 
                 self.basic_callback(TestEventKind::SetupScriptFinished {
                     index,
                     total,
-                    script_id,
+                    script_id: script_id.clone(),
                     command: config.program(),
                     args: config.args(),
                     no_capture: config.no_capture(),
                     junit_store_success_output: config.junit.store_success_output,
                     junit_store_failure_output: config.junit.store_failure_output,
+                    env_map: status
+                        .env_map
+                        .as_ref()
+                        .map(|env_map| config.redact_env_map(env_map)),
                     run_status: status,
                 });
 
                 if fail_cancel {
-                    self.begin_cancel(CancelReason::SetupScriptFailure, CancelEvent::TestFailure)
+                    self.begin_cancel(
+                        CancelReason::SetupScriptFailure,
+                        Some(CancelTrigger::SetupScriptFailure { script_id }),
+                        CancelEvent::TestFailure,
+                    )
+                } else {
+                    HandleEventResponse::None
+                }
+            }
+            InternalEvent::Executor(ExecutorEvent::SetupScriptEnvOverridden {
+                script_id,
+                overrides,
+            }) => self.callback_none_response(TestEventKind::SetupScriptEnvOverridden {
+                script_id,
+                overrides,
+            }),
+            InternalEvent::Executor(ExecutorEvent::TeardownScriptsReady { run_stats_tx }) => {
+                if self.is_interrupted() {
+                    // Dropping the sender causes teardown scripts to be skipped.
+                    return HandleEventResponse::None;
+                }
+                let _ = run_stats_tx.send(self.run_stats);
+                HandleEventResponse::None
+            }
+            InternalEvent::Executor(ExecutorEvent::TeardownScriptStarted {
+                script_id,
+                config,
+                index,
+                total,
+                req_rx_tx,
+            }) => {
+                // Teardown scripts are run even if the run was cancelled because of a failure, but
+                // not if it was interrupted.
+                if self.is_interrupted() {
+                    return HandleEventResponse::None;
+                }
+
+                let (req_tx, req_rx) = unbounded_channel();
+                match req_rx_tx.send(req_rx) {
+                    Ok(_) => {}
+                    Err(_) => {
+                        // The test task died?
+                        debug!(?script_id, "test task died, ignoring");
+                        return HandleEventResponse::None;
+                    }
+                }
+                // Teardown scripts share the setup script slot, so signals and info queries are
+                // relayed to them the same way.
+                self.new_setup_script(script_id.clone(), config, index, total, req_tx);
+                self.callback_none_response(TestEventKind::TeardownScriptStarted {
+                    index,
+                    total,
+                    script_id,
+                    command: config.program(),
+                    args: config.args(),
+                    no_capture: config.no_capture(),
+                })
+            }
+            InternalEvent::Executor(ExecutorEvent::TeardownScriptSlow {
+                script_id,
+                config,
+                slow_timeout,
+                elapsed,
+                will_terminate,
+            }) => self.callback_none_response(TestEventKind::TeardownScriptSlow {
+                script_id,
+                command: config.program(),
+                args: config.args(),
+                slow_timeout,
+                elapsed,
+                will_terminate: will_terminate.is_some(),
+            }),
+            InternalEvent::Executor(ExecutorEvent::TeardownScriptFinished {
+                script_id,
+                config,
+                index,
+                total,
+                status,
+            }) => {
+                self.finish_setup_script();
+                self.run_stats.on_teardown_script_finished(&status);
+                // Teardown script failures don't cancel the run: they're reported as a warning at
+                // the end, and may fail the run depending on the on-teardown-failure setting.
+                self.callback_none_response(TestEventKind::TeardownScriptFinished {
+                    index,
+                    total,
+                    script_id,
+                    command: config.program(),
+                    args: config.args(),
+                    no_capture: config.no_capture(),
+                    run_status: status,
+                })
+            }
+            InternalEvent::Executor(ExecutorEvent::PhaseStarted { phase, run_count }) => {
+                if self.cancel_state.is_some() {
+                    // The run has been cancelled: none of the tests in this phase will be run.
+                    return HandleEventResponse::None;
+                }
+
+                self.current_phase = Some(ContextPhase {
+                    phase,
+                    start: self.stopwatch.snapshot().active,
+                    run_stats: RunStats {
+                        initial_run_count: run_count,
+                        ..RunStats::default()
+                    },
+                });
+                self.callback_none_response(TestEventKind::PhaseStarted { phase, run_count })
+            }
+            InternalEvent::Executor(ExecutorEvent::PhaseFinished { phase }) => {
+                let Some(current_phase) = self.current_phase.take() else {
+                    // The phase was never started because the run was cancelled.
+                    return HandleEventResponse::None;
+                };
+                debug_assert_eq!(current_phase.phase, phase, "phase finished matches started");
+
+                let elapsed = self
+                    .stopwatch
+                    .snapshot()
+                    .active
+                    .saturating_sub(current_phase.start);
+                let run_stats = current_phase.run_stats;
+                self.basic_callback(TestEventKind::PhaseFinished {
+                    phase,
+                    elapsed,
+                    run_stats,
+                });
+
+                let gate_failed = phase == RunPhase::Smoke
+                    && self.smoke_mode == Some(SmokeMode::Gate)
+                    && run_stats.failed_count() > 0;
+                if gate_failed {
+                    self.run_stats.smoke_failed = true;
+                    self.begin_cancel(
+                        CancelReason::SmokeTestFailure,
+                        None,
+                        CancelEvent::TestFailure,
+                    )
                 } else {
                     HandleEventResponse::None
                 }
             }
             InternalEvent::Executor(ExecutorEvent::Started {
                 test_instance,
+                command_line,
+                extra_env,
                 req_rx_tx,
             }) => {
                 if self.cancel_state.is_some() {
@@ -429,23 +753,50 @@ where
                     }
                 }
                 self.new_test(test_instance, req_tx);
+                self.queued_tests.on_started(test_instance.id());
+                self.binary_started(&test_instance.suite_info.binary_id);
                 self.callback_none_response(TestEventKind::TestStarted {
                     test_instance,
+                    command_line,
+                    extra_env,
                     current_stats: self.run_stats,
                     running: self.running_tests.len(),
-                    cancel_state: self.cancel_state,
+                    cancel_state: self.cancel_state.clone(),
                 })
             }
             InternalEvent::Executor(ExecutorEvent::Slow {
                 test_instance,
                 retry_data,
+                slow_timeout,
                 elapsed,
                 will_terminate,
-            }) => self.callback_none_response(TestEventKind::TestSlow {
+            }) => {
+                if let Some(slow_hook) = &self.slow_hook {
+                    slow_hook.spawn(
+                        self.run_id,
+                        &self.profile_name,
+                        test_instance.id(),
+                        retry_data,
+                        elapsed,
+                        will_terminate.is_some(),
+                    );
+                }
+                self.callback_none_response(TestEventKind::TestSlow {
+                    test_instance,
+                    retry_data,
+                    slow_timeout,
+                    elapsed,
+                    will_terminate: will_terminate.is_some(),
+                })
+            }
+            InternalEvent::Executor(ExecutorEvent::OutputAfterExit {
                 test_instance,
                 retry_data,
-                elapsed,
-                will_terminate: will_terminate.is_some(),
+                bytes,
+            }) => self.callback_none_response(TestEventKind::TestOutputAfterExit {
+                test_instance,
+                retry_data,
+                bytes,
             }),
             InternalEvent::Executor(ExecutorEvent::AttemptFailedWillRetry {
                 test_instance,
@@ -462,9 +813,29 @@ where
                     delay_before_next_attempt,
                 })
             }
+            InternalEvent::Executor(ExecutorEvent::RetryDelayStarted {
+                test_instance,
+                retry_data,
+                remaining,
+                resume_time,
+            }) => {
+                if self.cancel_state.is_some() {
+                    // The delay is cut short if the run is cancelled.
+                    return HandleEventResponse::None;
+                }
+
+                self.callback_none_response(TestEventKind::TestRetryDelayStarted {
+                    test_instance,
+                    retry_data,
+                    remaining,
+                    resume_time,
+                })
+            }
             InternalEvent::Executor(ExecutorEvent::RetryStarted {
                 test_instance,
                 retry_data,
+                test_run_uuid,
+                attempt_uuid,
                 tx,
             }) => {
                 if self.cancel_state.is_some() {
@@ -485,6 +856,26 @@ where
                 self.callback_none_response(TestEventKind::TestRetryStarted {
                     test_instance,
                     retry_data,
+                    test_run_uuid,
+                    attempt_uuid,
+                })
+            }
+            InternalEvent::Executor(ExecutorEvent::OutputMismatch {
+                test_instance,
+                mismatch,
+                rerun_status,
+            }) => {
+                if self.cancel_state.is_some() {
+                    // The rerun may have been interrupted by the cancellation,
+                    // so the comparison isn't meaningful.
+                    debug!(test = ?test_instance.id(), "run cancelled, ignoring output mismatch");
+                    return HandleEventResponse::None;
+                }
+
+                self.callback_none_response(TestEventKind::TestOutputMismatch {
+                    test_instance,
+                    mismatch,
+                    rerun_status,
                 })
             }
             InternalEvent::Executor(ExecutorEvent::Finished {
@@ -493,13 +884,55 @@ where
                 failure_output,
                 junit_store_success_output,
                 junit_store_failure_output,
+                owner,
+                attachments,
+                quarantined,
                 last_run_status,
+                verification,
+                passed_iterations,
             }) => {
-                let run_statuses = self.finish_test(test_instance.id(), last_run_status);
-                self.run_stats.on_test_finished(&run_statuses);
+                let run_statuses = self
+                    .finish_test(test_instance.id(), last_run_status)
+                    .with_verification(verification)
+                    .with_passed_iterations(passed_iterations);
+                if quarantined {
+                    self.run_stats.on_quarantined_test_finished(&run_statuses);
+                } else {
+                    self.run_stats.on_test_finished(&run_statuses);
+                }
+                if let Some(current_phase) = &mut self.current_phase {
+                    if quarantined {
+                        current_phase
+                            .run_stats
+                            .on_quarantined_test_finished(&run_statuses);
+                    } else {
+                        current_phase.run_stats.on_test_finished(&run_statuses);
+                    }
+                }
+                if let Some(binary) = self.binaries.get_mut(&test_instance.suite_info.binary_id) {
+                    if quarantined {
+                        binary.run_stats.on_quarantined_test_finished(&run_statuses);
+                    } else {
+                        binary.run_stats.on_test_finished(&run_statuses);
+                    }
+                }
+                let package_stats = self
+                    .package_stats
+                    .stats_mut(test_instance.suite_info.package.name());
+                if quarantined {
+                    package_stats.on_quarantined_test_finished(&run_statuses);
+                } else {
+                    package_stats.on_test_finished(&run_statuses);
+                }
+                if let Some(duration_histogram) = &mut self.duration_histogram {
+                    duration_histogram.on_test_finished(&run_statuses);
+                }
+                if !quarantined && run_statuses.is_flaky() {
+                    self.flaky_tests.insert(test_instance.id());
+                }
 
                 // should this run be cancelled because of a failure?
-                let fail_cancel = self.max_fail.is_exceeded(self.run_stats.failed_count());
+                let fail_cancel = self.max_fail.is_exceeded(self.max_fail_count());
 
                 self.basic_callback(TestEventKind::TestFinished {
                     test_instance,
@@ -507,15 +940,25 @@ where
                     failure_output,
                     junit_store_success_output,
                     junit_store_failure_output,
+                    owner,
+                    attachments,
+                    quarantined,
                     run_statuses,
                     current_stats: self.run_stats,
                     running: self.running(),
-                    cancel_state: self.cancel_state,
+                    cancel_state: self.cancel_state.clone(),
                 });
+                self.binary_unit_done(&test_instance.suite_info.binary_id);
 
                 if fail_cancel {
                     // A test failed: start cancellation if required.
-                    self.begin_cancel(CancelReason::TestFailure, CancelEvent::TestFailure)
+                    self.begin_cancel(
+                        CancelReason::TestFailure,
+                        Some(CancelTrigger::TestFailure {
+                            test_instance: test_instance.id(),
+                        }),
+                        CancelEvent::TestFailure,
+                    )
                 } else {
                     HandleEventResponse::None
                 }
@@ -525,10 +968,23 @@ where
                 reason,
             }) => {
                 self.run_stats.skipped += 1;
-                self.callback_none_response(TestEventKind::TestSkipped {
+                if let Some(current_phase) = &mut self.current_phase {
+                    current_phase.run_stats.skipped += 1;
+                }
+                let binary_id = &test_instance.suite_info.binary_id;
+                if let Some(binary) = self.binaries.get_mut(binary_id) {
+                    binary.run_stats.skipped += 1;
+                }
+                self.package_stats
+                    .stats_mut(test_instance.suite_info.package.name())
+                    .skipped += 1;
+                self.binary_started(binary_id);
+                self.basic_callback(TestEventKind::TestSkipped {
                     test_instance,
                     reason,
-                })
+                });
+                self.binary_unit_done(binary_id);
+                HandleEventResponse::None
             }
             InternalEvent::Signal(event) => self.handle_signal_event(event),
             InternalEvent::Input(InputEvent::Info) => {
@@ -539,15 +995,33 @@ where
                 self.callback_none_response(TestEventKind::InputEnter {
                     current_stats: self.run_stats,
                     running: self.running(),
-                    cancel_reason: self.cancel_state,
+                    cancel_reason: self.cancel_state.clone(),
                 })
             }
             InternalEvent::ReportCancel => {
-                self.begin_cancel(CancelReason::ReportError, CancelEvent::Report)
+                self.begin_cancel(CancelReason::ReportError, None, CancelEvent::Report)
+            }
+            InternalEvent::CustomCancel(reason) => {
+                self.begin_cancel(CancelReason::Custom(reason), None, CancelEvent::Custom)
             }
         }
     }
 
+    /// Returns the number of failures that count towards max-fail.
+    fn max_fail_count(&self) -> usize {
+        let mut count = self.run_stats.failed_count();
+        if self.count_setup_script_failures {
+            count += self.run_stats.failed_setup_script_count();
+        }
+        count
+    }
+
+    /// Returns true if the run was cancelled by a signal or an interrupt, rather than because of
+    /// a failure.
+    fn is_interrupted(&self) -> bool {
+        self.cancel_state >= Some(CancelReason::Signal)
+    }
+
     fn new_setup_script(
         &mut self,
         id: ScriptId,
@@ -617,6 +1091,56 @@ where
             .finish(last_run_status)
     }
 
+    /// Reports that a binary started, if this is the first test in it to be started or skipped.
+    fn binary_started(&mut self, binary_id: &'a RustBinaryId) {
+        let active = self.stopwatch.snapshot().active;
+        let Some(binary) = self.binaries.get_mut(binary_id) else {
+            return;
+        };
+        if binary.start.is_some() {
+            return;
+        }
+        binary.start = Some(active);
+        let (package_name, test_count, run_count) = (
+            binary.package_name,
+            binary.test_count,
+            binary.run_stats.initial_run_count,
+        );
+        self.basic_callback(TestEventKind::BinaryStarted {
+            binary_id,
+            package_name,
+            test_count,
+            run_count,
+        });
+    }
+
+    /// Records that a test in a binary finished or was skipped, reporting that the binary
+    /// finished if it was the last one.
+    fn binary_unit_done(&mut self, binary_id: &'a RustBinaryId) {
+        let Some(binary) = self.binaries.get_mut(binary_id) else {
+            return;
+        };
+        binary.remaining = binary.remaining.saturating_sub(1);
+        if binary.remaining == 0 {
+            self.binary_finished(binary_id);
+        }
+    }
+
+    fn binary_finished(&mut self, binary_id: &'a RustBinaryId) {
+        let Some(binary) = self.binaries.remove(binary_id) else {
+            return;
+        };
+        let elapsed = binary.start.map_or(Duration::ZERO, |start| {
+            self.stopwatch.snapshot().active.saturating_sub(start)
+        });
+        self.basic_callback(TestEventKind::BinaryFinished {
+            binary_id,
+            package_name: binary.package_name,
+            elapsed,
+            run_stats: binary.run_stats,
+        });
+    }
+
     fn setup_scripts_running(&self) -> usize {
         if self.running_setup_script.is_some() {
             1
@@ -629,6 +1153,43 @@ where
         self.running_tests.len()
     }
 
+    /// Sends a stop request to each running unit, returning a receiver for
+    /// each unit's response, along with the test's ID if the unit is a test.
+    #[cfg(unix)]
+    fn request_stop(
+        &self,
+    ) -> Vec<(
+        Option<TestInstanceId<'a>>,
+        UnboundedReceiver<UnitStopStatus>,
+    )> {
+        let setup_script = self
+            .running_setup_script
+            .as_ref()
+            .map(|setup_script| (None, &setup_script.req_tx));
+        let tests = self
+            .running_tests
+            .iter()
+            .map(|(key, instance)| (Some(*key), &instance.req_tx));
+
+        setup_script
+            .into_iter()
+            .chain(tests)
+            .filter_map(|(id, req_tx)| {
+                let (status_tx, status_rx) = unbounded_channel();
+                match req_tx.send(RunUnitRequest::Signal(SignalRequest::Stop(status_tx))) {
+                    Ok(()) => Some((id, status_rx)),
+                    Err(_) => {
+                        // The most likely reason for this error is that the
+                        // unit has been marked as closed but we haven't
+                        // processed the exit event yet.
+                        debug!(?id, "failed to send stop request to unit (likely closed)");
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
     /// Returns the number of units the request was broadcast to.
     fn broadcast_request(&self, req: RunUnitRequest<'a>) -> usize {
         let mut count = 0;
@@ -668,16 +1229,22 @@ where
                 let req = signal_count.to_request(event);
                 let cancel_reason = event_to_cancel_reason(event);
 
-                self.begin_cancel(cancel_reason, CancelEvent::Signal(req))
+                self.begin_cancel(
+                    cancel_reason,
+                    event_to_cancel_trigger(event),
+                    CancelEvent::Signal(req),
+                )
             }
             #[cfg(unix)]
             SignalEvent::JobControl(JobControlEvent::Stop) => {
-                // Debounce stop signals.
+                if self.sigtstp == SigtstpPolicy::Ignore {
+                    debug!("ignoring SIGTSTP as configured by the sigtstp policy");
+                    return HandleEventResponse::None;
+                }
+
+                // Debounce stop signals. The RunPaused event is sent once
+                // running units have been stopped.
                 if !self.stopwatch.is_paused() {
-                    self.basic_callback(TestEventKind::RunPaused {
-                        setup_scripts_running: self.setup_scripts_running(),
-                        running: self.running(),
-                    });
                     self.stopwatch.pause();
                     HandleEventResponse::JobControl(JobControlEvent::Stop)
                 } else {
@@ -692,6 +1259,7 @@ where
                     self.basic_callback(TestEventKind::RunContinued {
                         setup_scripts_running: self.setup_scripts_running(),
                         running: self.running(),
+                        continued: self.running_tests.keys().copied().collect(),
                     });
                     HandleEventResponse::JobControl(JobControlEvent::Continue)
                 } else {
@@ -722,7 +1290,8 @@ where
     }
 
     fn info_finished(&mut self, missing: usize) {
-        self.basic_callback(TestEventKind::InfoFinished { missing });
+        let queued = self.queued_tests.snapshot(QueuedTests::MAX_NEXT);
+        self.basic_callback(TestEventKind::InfoFinished { missing, queued });
     }
 
     fn increment_signal_count(&mut self) -> SignalCount {
@@ -752,8 +1321,16 @@ where
     /// Begin cancellation of a test run. Report it if the current cancel state
     /// is less than the required one.
     ///
+    /// `trigger` is the specific event that caused the cancellation, if known.
+    /// It is only reported if this call raises the cancel state.
+    ///
     /// Returns the corresponding `HandleEventResponse`.
-    fn begin_cancel(&mut self, reason: CancelReason, event: CancelEvent) -> HandleEventResponse {
+    fn begin_cancel(
+        &mut self,
+        reason: CancelReason,
+        trigger: Option<CancelTrigger<'a>>,
+        event: CancelEvent,
+    ) -> HandleEventResponse {
         // TODO: combine reason and event? The Twice block ignoring the event
         // seems to indicate a data modeling issue.
         if event == CancelEvent::Signal(ShutdownRequest::Twice) {
@@ -766,12 +1343,25 @@ where
                 reason: CancelReason::SecondSignal,
             });
             HandleEventResponse::Cancel(event)
-        } else if self.cancel_state < Some(reason) {
-            self.cancel_state = Some(reason);
+        } else if self.cancel_state.as_ref() < Some(&reason) {
+            self.cancel_state = Some(reason.clone());
+            let setup_scripts_running = self.setup_scripts_running();
+            let running = self.running();
+            // Running units are only terminated if a signal caused the cancellation, so that's
+            // the only case where a grace period applies.
+            if matches!(event, CancelEvent::Signal(ShutdownRequest::Once(_)))
+                && !self.cancel_grace.is_zero()
+                && setup_scripts_running + running > 0
+                && self.grace_deadline.is_none()
+            {
+                self.grace_deadline = Some(self.stopwatch.snapshot().active + self.cancel_grace);
+            }
             self.basic_callback(TestEventKind::RunBeginCancel {
-                setup_scripts_running: self.setup_scripts_running(),
-                running: self.running(),
+                setup_scripts_running,
+                running,
                 reason,
+                trigger,
+                grace_deadline: self.grace_deadline,
             });
             HandleEventResponse::Cancel(event)
         } else {
@@ -780,12 +1370,26 @@ where
     }
 
     pub(super) fn run_finished(&mut self) {
+        // If the run was cancelled, some binaries that were started won't have finished.
+        let unfinished: Vec<_> = self
+            .binaries
+            .iter()
+            .filter(|(_, binary)| binary.start.is_some())
+            .map(|(&binary_id, _)| binary_id)
+            .collect();
+        for binary_id in unfinished {
+            self.binary_finished(binary_id);
+        }
+
         let stopwatch_end = self.stopwatch.snapshot();
         self.basic_callback(TestEventKind::RunFinished {
             start_time: stopwatch_end.start_time.fixed_offset(),
             run_id: self.run_id,
             elapsed: stopwatch_end.active,
             run_stats: self.run_stats,
+            package_stats: self.package_stats.clone(),
+            duration_histogram: self.duration_histogram.clone(),
+            flaky_tests: self.flaky_tests.clone(),
         })
     }
 
@@ -794,6 +1398,55 @@ where
     }
 }
 
+/// Tests that are expected to run but haven't started yet, in the order they're queued in.
+#[derive(Clone, Debug, Default)]
+struct TestQueue<'a> {
+    queue: VecDeque<TestInstanceId<'a>>,
+    // Tests are mostly started in queue order, so started tests are only removed from the queue
+    // once they reach its front. Until then, they're tracked here.
+    started: BTreeSet<TestInstanceId<'a>>,
+}
+
+impl<'a> TestQueue<'a> {
+    fn new(queue: impl IntoIterator<Item = TestInstanceId<'a>>) -> Self {
+        Self {
+            queue: queue.into_iter().collect(),
+            started: BTreeSet::new(),
+        }
+    }
+
+    fn on_started(&mut self, id: TestInstanceId<'a>) {
+        if self.queue.front() == Some(&id) {
+            self.queue.pop_front();
+            self.pop_started();
+        } else {
+            self.started.insert(id);
+        }
+    }
+
+    fn pop_started(&mut self) {
+        while let Some(front) = self.queue.front() {
+            if !self.started.remove(front) {
+                break;
+            }
+            self.queue.pop_front();
+        }
+    }
+
+    fn snapshot(&self, max_next: usize) -> QueuedTests<'a> {
+        QueuedTests {
+            count: self.queue.len() - self.started.len(),
+            next: self
+                .queue
+                .iter()
+                .filter(|id| !self.started.contains(id))
+                .take(max_next)
+                .copied()
+                .collect(),
+        }
+    }
+}
+
 fn event_to_cancel_reason(event: ShutdownEvent) -> CancelReason {
     match event {
         #[cfg(unix)]
@@ -802,6 +1455,40 @@ fn event_to_cancel_reason(event: ShutdownEvent) -> CancelReason {
     }
 }
 
+fn event_to_cancel_trigger<'a>(event: ShutdownEvent) -> Option<CancelTrigger<'a>> {
+    cfg_if::cfg_if! {
+        if #[cfg(unix)] {
+            event
+                .trigger_signal()
+                .map(|signal| CancelTrigger::Signal { signal })
+        } else {
+            // On Windows, the only shutdown event is an interrupt, which is
+            // ambient.
+            let _ = event;
+            None
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct ContextPhase {
+    phase: RunPhase,
+    // The active time of the run when the phase started.
+    start: Duration,
+    run_stats: RunStats,
+}
+
+#[derive(Clone, Debug)]
+struct ContextBinary<'a> {
+    package_name: &'a str,
+    test_count: usize,
+    // The number of tests that haven't finished running or been skipped yet.
+    remaining: usize,
+    // The active time of the run when the binary was started, or None if it hasn't started yet.
+    start: Option<Duration>,
+    run_stats: RunStats,
+}
+
 #[derive(Clone, Debug)]
 struct ContextSetupScript<'a> {
     id: ScriptId,
@@ -842,6 +1529,7 @@ enum InternalEvent<'a> {
     Signal(SignalEvent),
     Input(InputEvent),
     ReportCancel,
+    CustomCancel(Cow<'static, str>),
 }
 
 /// The return result of `handle_event`.
@@ -875,6 +1563,7 @@ enum InfoEvent {
 enum CancelEvent {
     Report,
     TestFailure,
+    Custom,
     Signal(ShutdownRequest),
 }
 
@@ -893,6 +1582,102 @@ impl SignalCount {
     }
 }
 
+/// A command to run in the background each time a test is marked slow, configured via `on-slow`.
+#[derive(Clone, Debug)]
+pub(super) struct SlowHook {
+    program: String,
+    args: Vec<String>,
+    timeout: Duration,
+    cwd: Utf8PathBuf,
+}
+
+impl SlowHook {
+    /// Creates a new `SlowHook` from the profile's `on-slow` configuration, if a command is
+    /// configured.
+    pub(super) fn new(on_slow: &OnSlow, workspace_root: &Utf8Path) -> Option<Self> {
+        let (program, args) = on_slow.command()?;
+        Some(Self {
+            program: program.to_owned(),
+            args: args.to_vec(),
+            timeout: on_slow.timeout(),
+            cwd: workspace_root.to_owned(),
+        })
+    }
+
+    /// Spawns the command in the background.
+    ///
+    /// The dispatcher never waits on the command: it is killed if it runs for longer than the
+    /// configured timeout, or if the run finishes before it does.
+    fn spawn(
+        &self,
+        run_id: ReportUuid,
+        profile_name: &str,
+        id: TestInstanceId<'_>,
+        retry_data: RetryData,
+        elapsed: Duration,
+        will_terminate: bool,
+    ) {
+        let mut command = tokio::process::Command::new(&self.program);
+        command
+            .args(&self.args)
+            .current_dir(&self.cwd)
+            .env("NEXTEST", "1")
+            .env("NEXTEST_RUN_ID", run_id.to_string())
+            .env("NEXTEST_PROFILE", profile_name)
+            .env("NEXTEST_SLOW_BINARY_ID", id.binary_id.as_str())
+            .env("NEXTEST_SLOW_TEST_NAME", id.test_name)
+            .env(
+                "NEXTEST_SLOW_ELAPSED_SECS",
+                format!("{:.3}", elapsed.as_secs_f64()),
+            )
+            .env("NEXTEST_SLOW_ATTEMPT", retry_data.attempt.to_string())
+            .env(
+                "NEXTEST_SLOW_TOTAL_ATTEMPTS",
+                retry_data.total_attempts.to_string(),
+            )
+            .env(
+                "NEXTEST_SLOW_WILL_TERMINATE",
+                if will_terminate { "1" } else { "0" },
+            )
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true);
+
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(error) => {
+                warn!(
+                    "failed to spawn on-slow command `{}`: {error}",
+                    self.program
+                );
+                return;
+            }
+        };
+        let test = id.to_string();
+        let timeout = self.timeout;
+        tokio::spawn(async move {
+            match tokio::time::timeout(timeout, child.wait()).await {
+                Ok(Ok(status)) if !status.success() => {
+                    debug!(%test, "on-slow command exited with {status}");
+                }
+                Ok(Ok(_)) => {}
+                Ok(Err(error)) => {
+                    debug!(%test, "error waiting for on-slow command: {error}");
+                }
+                Err(_) => {
+                    warn!(
+                        "on-slow command for {test} didn't finish within {:?}, killing it",
+                        timeout
+                    );
+                    // The child is also killed on drop, but kill it explicitly to reap it.
+                    _ = child.kill().await;
+                }
+            }
+        });
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -911,6 +1696,12 @@ mod tests {
             vec![],
             0,
             MaxFail::All,
+            false,
+            SigtstpPolicy::Pause,
+            None,
+            None,
+            None,
+            Duration::ZERO,
         );
         cx.disable_signal_3_times_panic = true;
 
@@ -929,6 +1720,8 @@ mod tests {
                 setup_scripts_running,
                 running,
                 reason,
+                trigger,
+                grace_deadline,
             } = event.kind
             else {
                 panic!("expected RunBeginCancel event, found {:?}", event.kind);
@@ -936,6 +1729,8 @@ mod tests {
             assert_eq!(setup_scripts_running, 0, "expected 0 setup scripts running");
             assert_eq!(running, 0, "expected 0 tests running");
             assert_eq!(reason, CancelReason::ReportError, "expected report error");
+            assert_eq!(trigger, None, "report errors have no trigger");
+            assert_eq!(grace_deadline, None, "report errors don't terminate units");
         }
 
         // Send another report error, ensuring it's ignored.
@@ -973,6 +1768,8 @@ mod tests {
                         setup_scripts_running,
                         running,
                         reason,
+                        trigger,
+                        grace_deadline,
                     } = event.kind
                     else {
                         panic!("expected RunBeginCancel event, found {:?}", event.kind);
@@ -980,6 +1777,13 @@ mod tests {
                     assert_eq!(setup_scripts_running, 0, "expected 0 setup scripts running");
                     assert_eq!(running, 0, "expected 0 tests running");
                     assert_eq!(reason, event_to_cancel_reason(*sig1), "expected signal");
+                    // Interrupts are ambient, so they have no trigger.
+                    assert_eq!(
+                        trigger.is_some(),
+                        *sig1 != ShutdownEvent::Interrupt,
+                        "expected a trigger for non-interrupt signals"
+                    );
+                    assert_eq!(grace_deadline, None, "no cancel grace period is configured");
                 }
 
                 // Another report error, ensuring it's ignored.
@@ -1017,6 +1821,215 @@ mod tests {
         }
     }
 
+    #[test]
+    fn begin_cancel_custom() {
+        let events = Mutex::new(Vec::new());
+        let mut cx = DispatcherContext::new(
+            |event| {
+                events.lock().unwrap().push(event);
+            },
+            ReportUuid::new_v4(),
+            "default",
+            vec![],
+            0,
+            MaxFail::All,
+            false,
+            SigtstpPolicy::Pause,
+            None,
+            None,
+            None,
+            Duration::ZERO,
+        );
+
+        // A custom reason cancels the run like a test failure does.
+        let response = cx.handle_event(InternalEvent::CustomCancel("budget exceeded".into()));
+        assert_eq!(
+            response,
+            HandleEventResponse::Cancel(CancelEvent::Custom),
+            "expected custom"
+        );
+        {
+            let mut events = events.lock().unwrap();
+            assert_eq!(events.len(), 1, "expected 1 event");
+            let event = events.pop().unwrap();
+            let TestEventKind::RunBeginCancel {
+                reason, trigger, ..
+            } = event.kind
+            else {
+                panic!("expected RunBeginCancel event, found {:?}", event.kind);
+            };
+            assert_eq!(
+                reason,
+                CancelReason::Custom("budget exceeded".into()),
+                "expected custom reason"
+            );
+            assert_eq!(reason.to_static_str(), "budget exceeded");
+            assert_eq!(trigger, None, "custom reasons have no trigger");
+        }
+
+        // Custom reasons are more severe than test failures, so a test failure
+        // afterwards doesn't raise the cancel state.
+        let response = cx.begin_cancel(CancelReason::TestFailure, None, CancelEvent::TestFailure);
+        assert_noop(response, &events);
+
+        // A report error is more severe, so it does.
+        let response = cx.handle_event(InternalEvent::ReportCancel);
+        assert_eq!(
+            response,
+            HandleEventResponse::Cancel(CancelEvent::Report),
+            "expected report"
+        );
+        assert_eq!(cx.cancel_state, Some(CancelReason::ReportError));
+    }
+
+    #[test]
+    fn max_fail_count_setup_scripts() {
+        for (count_setup_script_failures, expected) in [(false, 1), (true, 3)] {
+            let mut cx = DispatcherContext::new(
+                |_event| {},
+                ReportUuid::new_v4(),
+                "default",
+                vec![],
+                0,
+                MaxFail::Count(3),
+                count_setup_script_failures,
+                SigtstpPolicy::Pause,
+                None,
+                None,
+                None,
+                Duration::ZERO,
+            );
+            cx.run_stats.failed = 1;
+            cx.run_stats.setup_scripts_failed = 1;
+            cx.run_stats.setup_scripts_timed_out = 1;
+            assert_eq!(
+                cx.max_fail_count(),
+                expected,
+                "with count_setup_script_failures = {count_setup_script_failures}"
+            );
+            assert_eq!(
+                cx.max_fail.is_exceeded(cx.max_fail_count()),
+                count_setup_script_failures,
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn sigtstp_policy() {
+        let events = Mutex::new(Vec::new());
+        let mut cx = DispatcherContext::new(
+            |event| {
+                events.lock().unwrap().push(event);
+            },
+            ReportUuid::new_v4(),
+            "default",
+            vec![],
+            0,
+            MaxFail::All,
+            false,
+            SigtstpPolicy::Ignore,
+            None,
+            None,
+            None,
+            Duration::ZERO,
+        );
+
+        // With the ignore policy, SIGTSTP and SIGCONT are both no-ops.
+        let response = cx.handle_event(InternalEvent::Signal(SignalEvent::JobControl(
+            JobControlEvent::Stop,
+        )));
+        assert_noop(response, &events);
+        assert!(!cx.stopwatch.is_paused(), "stopwatch should not be paused");
+
+        let response = cx.handle_event(InternalEvent::Signal(SignalEvent::JobControl(
+            JobControlEvent::Continue,
+        )));
+        assert_noop(response, &events);
+
+        // With the pause policy, SIGTSTP pauses the run.
+        cx.sigtstp = SigtstpPolicy::Pause;
+        let response = cx.handle_event(InternalEvent::Signal(SignalEvent::JobControl(
+            JobControlEvent::Stop,
+        )));
+        assert_eq!(
+            response,
+            HandleEventResponse::JobControl(JobControlEvent::Stop),
+            "expected stop"
+        );
+        // The RunPaused event is only sent once running units have been
+        // stopped, in the run loop.
+        assert_eq!(events.lock().unwrap().len(), 0, "expected no new events");
+        assert!(cx.stopwatch.is_paused(), "stopwatch should be paused");
+
+        let response = cx.handle_event(InternalEvent::Signal(SignalEvent::JobControl(
+            JobControlEvent::Continue,
+        )));
+        assert_eq!(
+            response,
+            HandleEventResponse::JobControl(JobControlEvent::Continue),
+            "expected continue"
+        );
+        let event = events.lock().unwrap().pop().expect("expected 1 event");
+        assert!(
+            matches!(event.kind, TestEventKind::RunContinued { .. }),
+            "expected RunContinued event, found {:?}",
+            event.kind
+        );
+        assert!(!cx.stopwatch.is_paused(), "stopwatch should be resumed");
+    }
+
+    #[test]
+    fn test_queue() {
+        let binary_id = RustBinaryId::new("my-binary-id");
+        let ids: Vec<_> = ["test1", "test2", "test3", "test4", "test5"]
+            .into_iter()
+            .map(|test_name| TestInstanceId {
+                binary_id: &binary_id,
+                test_name,
+            })
+            .collect();
+        let mut queue = TestQueue::new(ids.iter().copied());
+        assert_eq!(
+            queue.snapshot(2),
+            QueuedTests {
+                count: 5,
+                next: ids[..2].to_vec(),
+            }
+        );
+
+        // Tests started in order are removed from the queue.
+        queue.on_started(ids[0]);
+        assert_eq!(
+            queue.snapshot(2),
+            QueuedTests {
+                count: 4,
+                next: ids[1..3].to_vec(),
+            }
+        );
+
+        // Tests started out of order are skipped over.
+        queue.on_started(ids[2]);
+        assert_eq!(
+            queue.snapshot(2),
+            QueuedTests {
+                count: 3,
+                next: vec![ids[1], ids[3]],
+            }
+        );
+        queue.on_started(ids[1]);
+        assert_eq!(
+            queue.queue.len(),
+            2,
+            "started tests at the front are removed"
+        );
+        assert!(queue.started.is_empty());
+
+        queue.on_started(ids[3]);
+        queue.on_started(ids[4]);
+        assert_eq!(queue.snapshot(2), QueuedTests::default());
+    }
+
     #[track_caller]
     fn assert_noop(response: HandleEventResponse, events: &Mutex<Vec<TestEvent<'_>>>) {
         assert_eq!(response, HandleEventResponse::None, "expected no response");
@@ -14,33 +14,46 @@
 use super::HandleSignalResult;
 use crate::{
     config::{
-        EvaluatableProfile, RetryPolicy, ScriptConfig, ScriptId, SetupScriptCommand,
-        SetupScriptExecuteData, SlowTimeout, TestSettings,
+        CpuAffinity, EvaluatableProfile, MaxOutputSize, OrphanProcesses, RetryPolicy, ScriptConfig,
+        ScriptId, SetupScriptCommand, SetupScriptExecuteData, SlowTimeout, TestSettings,
+        REDACTED_ENV_VALUE,
     },
     double_spawn::DoubleSpawnInfo,
-    errors::{ChildError, ChildFdError, ChildStartError, ErrorList},
+    errors::{
+        ChildError, ChildFdError, ChildStartError, ErrorList, LingeringThreadsError,
+        OrphanProcessesError, UnexpectedStderrError,
+    },
     list::{TestExecuteContext, TestInstance, TestList},
     reporter::events::{
-        AbortStatus, ExecutionResult, InfoResponse, RetryData, SetupScriptInfoResponse,
-        TestInfoResponse, UnitKind, UnitState,
+        AbortStatus, ExecutionResult, FailureVerification, InfoResponse, LeakDetails, RetryData,
+        RunStats, SetupScriptEnvMap, SetupScriptInfoResponse, TestAttachments, TestInfoResponse,
+        UnitKind, UnitState,
     },
     runner::{
-        parse_env_file, ExecutorEvent, InternalExecuteStatus, InternalSetupScriptExecuteStatus,
-        InternalTerminateReason, RunUnitQuery, RunUnitRequest, SignalRequest, UnitExecuteStatus,
+        apply_json_outputs, parse_json_output_file, parse_setup_script_env_file, DryRun,
+        ExecutorEvent, InternalExecuteStatus, InternalSetupScriptExecuteStatus,
+        InternalTerminateReason, IoWaitTracker, LingeringThreadTracker, PeakMemoryTracker,
+        RepeatUntilFailure, RunUnitQuery, RunUnitRequest, SignalRequest, TestArgsHook,
+        UnitExecuteStatus, VerifyFailures,
     },
     target_runner::TargetRunner,
-    test_command::{ChildAccumulator, ChildFds},
-    test_output::{CaptureStrategy, ChildExecutionOutput, ChildOutput, ChildSplitOutput},
+    test_command::{ChildAccumulator, ChildFds, TestCommand},
+    test_output::{
+        CaptureStrategy, ChildExecutionOutput, ChildOutput, ChildSplitOutput, DeterminismCheck,
+    },
     time::{PausableSleep, StopwatchStart},
 };
+use chrono::{DateTime, FixedOffset, Local, TimeDelta};
+use futures::FutureExt;
 use nextest_metadata::FilterMatch;
 use quick_junit::ReportUuid;
 use rand::{distributions::OpenClosed01, thread_rng, Rng};
 use std::{
+    collections::BTreeMap,
     num::NonZeroUsize,
     pin::Pin,
     process::{ExitStatus, Stdio},
-    sync::Arc,
+    sync::{Arc, Mutex},
     time::Duration,
 };
 use tokio::{
@@ -49,6 +62,7 @@ use tokio::{
         mpsc::{UnboundedReceiver, UnboundedSender},
         oneshot,
     },
+    time::Instant,
 };
 use tracing::{debug, instrument};
 
@@ -62,9 +76,23 @@ pub(super) struct ExecutorContext<'a> {
     capture_strategy: CaptureStrategy,
     // This is Some if the user specifies a retry policy over the command-line.
     force_retries: Option<RetryPolicy>,
+    // This is Some if passing tests should be rerun to check for determinism.
+    determinism_check: Option<DeterminismCheck>,
+    // This is Some if failing tests should be rerun to check whether the failure reproduces.
+    verify_failures: Option<VerifyFailures>,
+    // This is Some if passing tests should be run repeatedly until they fail.
+    repeat_until_failure: Option<RepeatUntilFailure>,
+    // Environment variables applied to all tests, before those set by setup scripts.
+    extra_env: Option<SetupScriptEnvMap>,
+    // Extra arguments to pass to each test, after nextest's own and the profile's.
+    test_args_hook: Option<TestArgsHook>,
+    // This is Some if tests and setup scripts should be reported as passing without being run.
+    dry_run: Option<DryRun>,
+    start_stagger: StartStagger,
 }
 
 impl<'a> ExecutorContext<'a> {
+    #[expect(clippy::too_many_arguments)]
     pub(super) fn new(
         run_id: ReportUuid,
         profile: &'a EvaluatableProfile<'a>,
@@ -73,6 +101,12 @@ impl<'a> ExecutorContext<'a> {
         target_runner: TargetRunner,
         capture_strategy: CaptureStrategy,
         force_retries: Option<RetryPolicy>,
+        determinism_check: Option<DeterminismCheck>,
+        verify_failures: Option<VerifyFailures>,
+        repeat_until_failure: Option<RepeatUntilFailure>,
+        extra_env: Option<SetupScriptEnvMap>,
+        test_args_hook: Option<TestArgsHook>,
+        dry_run: Option<DryRun>,
     ) -> Self {
         Self {
             run_id,
@@ -82,6 +116,13 @@ impl<'a> ExecutorContext<'a> {
             target_runner,
             capture_strategy,
             force_retries,
+            determinism_check,
+            verify_failures,
+            repeat_until_failure,
+            extra_env,
+            test_args_hook,
+            dry_run,
+            start_stagger: StartStagger::new(profile.start_stagger()),
         }
     }
 
@@ -94,7 +135,7 @@ impl<'a> ExecutorContext<'a> {
         let total = setup_scripts.len();
         debug!("running {} setup scripts", total);
 
-        let mut setup_script_data = SetupScriptExecuteData::new();
+        let mut setup_script_data = SetupScriptExecuteData::new(self.extra_env.clone());
 
         // Run setup scripts one by one.
         for (index, script) in setup_scripts.into_iter().enumerate() {
@@ -124,6 +165,7 @@ impl<'a> ExecutorContext<'a> {
                 let packet = SetupScriptPacket {
                     script_id: script_id.clone(),
                     config,
+                    kind: ScriptKind::Setup,
                 };
 
                 let status = self
@@ -150,13 +192,82 @@ impl<'a> ExecutorContext<'a> {
 
             // Run this setup script to completion.
             if let Some((script, env_map)) = script_fut.await {
-                setup_script_data.add_script(script, env_map);
+                let script_id = script.id.clone();
+                let overrides = setup_script_data.add_script(script, env_map);
+                if !overrides.is_empty() {
+                    let _ = resp_tx.send(ExecutorEvent::SetupScriptEnvOverridden {
+                        script_id,
+                        overrides,
+                    });
+                }
             }
         }
 
         setup_script_data
     }
 
+    /// Run teardown scripts, once all tests have finished.
+    ///
+    /// Teardown scripts are passed statistics for the run through environment variables. They're
+    /// not run if the run was interrupted by a signal.
+    pub(super) async fn run_teardown_scripts(&self, resp_tx: UnboundedSender<ExecutorEvent<'a>>) {
+        let teardown_scripts = self.profile.teardown_scripts(self.test_list);
+        if teardown_scripts.is_empty() {
+            return;
+        }
+
+        let (run_stats_tx, run_stats_rx) = oneshot::channel();
+        let _ = resp_tx.send(ExecutorEvent::TeardownScriptsReady { run_stats_tx });
+        let Ok(run_stats) = run_stats_rx.await else {
+            debug!("dispatcher declined to run teardown scripts");
+            return;
+        };
+
+        let total = teardown_scripts.len();
+        debug!("running {} teardown scripts", total);
+
+        // Run teardown scripts one by one. Unlike with setup scripts, a failing teardown script
+        // doesn't stop later ones from running.
+        for (index, script) in teardown_scripts.into_iter().enumerate() {
+            let script_id = script.id.clone();
+            let config = script.config;
+
+            let (req_rx_tx, req_rx_rx) = oneshot::channel();
+            let _ = resp_tx.send(ExecutorEvent::TeardownScriptStarted {
+                script_id: script_id.clone(),
+                config,
+                index,
+                total,
+                req_rx_tx,
+            });
+            let Ok(mut req_rx) = req_rx_rx.await else {
+                // The receiver was dropped -- the dispatcher has signaled that this unit should
+                // exit.
+                return;
+            };
+
+            let packet = SetupScriptPacket {
+                script_id: script_id.clone(),
+                config,
+                kind: ScriptKind::Teardown {
+                    run_stats: Box::new(run_stats),
+                },
+            };
+
+            let status = self.run_setup_script(packet, &resp_tx, &mut req_rx).await;
+
+            drain_req_rx(req_rx, UnitExecuteStatus::SetupScript(&status));
+
+            let _ = resp_tx.send(ExecutorEvent::TeardownScriptFinished {
+                script_id,
+                config,
+                index,
+                total,
+                status: status.into_external(),
+            });
+        }
+    }
+
     /// Returns a future that runs all attempts of a single test instance.
     pub(super) async fn run_test_instance(
         &self,
@@ -169,8 +280,17 @@ impl<'a> ExecutorContext<'a> {
 
         let settings = Arc::new(settings);
 
-        let retry_policy = self.force_retries.unwrap_or_else(|| settings.retries());
-        let total_attempts = retry_policy.count() + 1;
+        // In repeat-until-failure mode, each attempt is an iteration of a passing test rather than
+        // a retry of a failing one, so retries aren't performed.
+        let retry_policy = if self.repeat_until_failure.is_some() {
+            RetryPolicy::new_without_delay(0)
+        } else {
+            self.force_retries.unwrap_or_else(|| settings.retries())
+        };
+        let total_attempts = match self.repeat_until_failure {
+            Some(repeat) => repeat.max_iterations.get(),
+            None => retry_policy.count() + 1,
+        };
         let mut backoff_iter = BackoffIter::new(retry_policy);
 
         if let FilterMatch::Mismatch { reason } = test_instance.test_info.filter_match {
@@ -182,12 +302,37 @@ impl<'a> ExecutorContext<'a> {
             return;
         }
 
+        // Report the command that will be run. Environment variables that vary by attempt
+        // aren't included.
+        let (command_line, extra_env) = {
+            let cmd = self.make_test_command(test_instance, &settings, &setup_script_data);
+            let query = test_instance.to_test_query();
+            let ecx = self.profile.filterset_ecx();
+            let redact_env = self.profile.redact_env();
+            let extra_env = cmd
+                .env_overrides()
+                .into_iter()
+                .map(|(key, value)| {
+                    if redact_env.iter().any(|re| re.is_match(&key))
+                        || setup_script_data.is_redacted(&query, &ecx, &key)
+                    {
+                        (key, REDACTED_ENV_VALUE.to_owned())
+                    } else {
+                        (key, value)
+                    }
+                })
+                .collect();
+            (cmd.command_line().to_vec(), extra_env)
+        };
+
         let (req_rx_tx, req_rx_rx) = oneshot::channel();
 
         // Wait for the Started event to be processed by the
         // execution future.
         _ = resp_tx.send(ExecutorEvent::Started {
             test_instance,
+            command_line,
+            extra_env,
             req_rx_tx,
         });
         let mut req_rx = match req_rx_rx.await {
@@ -199,13 +344,29 @@ impl<'a> ExecutorContext<'a> {
             }
         };
 
+        // Wait for this test's turn to start. This happens after the Started event has been
+        // acknowledged, so tests that are never started because the run was cancelled don't wait.
+        // It also happens before the first attempt's stopwatch is started, so the wait doesn't
+        // count towards the slow timeout.
+        if self.dry_run.is_none() {
+            self.start_stagger.wait().await;
+        }
+
+        // All attempts of this test share test_run_uuid, and each attempt
+        // gets its own attempt_uuid.
+        let test_run_uuid = ReportUuid::new_v4();
         let mut attempt = 0;
         let mut delay = Duration::ZERO;
+        let repeat_start = Instant::now();
+        let mut passed_iterations = 0;
         let last_run_status = loop {
             attempt += 1;
+            let attempt_uuid = ReportUuid::new_v4();
             let retry_data = RetryData {
                 attempt,
                 total_attempts,
+                backoff: retry_policy.backoff(),
+                jitter: retry_policy.jitter(),
             };
 
             if retry_data.attempt > 1 {
@@ -216,6 +377,8 @@ impl<'a> ExecutorContext<'a> {
                 _ = resp_tx.send(ExecutorEvent::RetryStarted {
                     test_instance,
                     retry_data,
+                    test_run_uuid,
+                    attempt_uuid,
                     tx,
                 });
 
@@ -235,6 +398,8 @@ impl<'a> ExecutorContext<'a> {
             let packet = TestPacket {
                 test_instance,
                 retry_data,
+                test_run_uuid,
+                attempt_uuid,
                 settings: settings.clone(),
                 setup_script_data: setup_script_data.clone(),
                 delay_before_start: delay,
@@ -243,9 +408,16 @@ impl<'a> ExecutorContext<'a> {
             let run_status = self.run_test(packet.clone(), &resp_tx, &mut req_rx).await;
 
             if run_status.result.is_success() {
+                if self.should_repeat(retry_data, repeat_start) {
+                    // Run another iteration to look for a failure.
+                    passed_iterations += 1;
+                    continue;
+                }
                 // The test succeeded.
                 break run_status;
-            } else if retry_data.attempt < retry_data.total_attempts {
+            } else if self.repeat_until_failure.is_none()
+                && retry_data.attempt < retry_data.total_attempts
+            {
                 // Retry this test: send a retry event, then retry the loop.
                 delay = backoff_iter
                     .next()
@@ -267,6 +439,7 @@ impl<'a> ExecutorContext<'a> {
                     previous_result,
                     previous_slow,
                     delay,
+                    &resp_tx,
                     &mut req_rx,
                 )
                 .await;
@@ -276,12 +449,73 @@ impl<'a> ExecutorContext<'a> {
             }
         };
 
+        // If determinism checks are enabled, run a passing test once more and
+        // compare the two runs.
+        let rerun_status = match &self.determinism_check {
+            Some(_) if last_run_status.result.is_success() => {
+                let packet = TestPacket {
+                    test_instance,
+                    retry_data: last_run_status.test.retry_data,
+                    test_run_uuid,
+                    attempt_uuid: ReportUuid::new_v4(),
+                    settings: settings.clone(),
+                    setup_script_data: setup_script_data.clone(),
+                    delay_before_start: Duration::ZERO,
+                };
+                Some(self.run_test(packet, &resp_tx, &mut req_rx).await)
+            }
+            _ => None,
+        };
+
+        // If failures are being verified, run a test that failed all its
+        // attempts once more to check whether the failure reproduces.
+        let verification = match self.verify_failures {
+            Some(verify_failures) if !last_run_status.result.is_success() => {
+                let packet = TestPacket {
+                    test_instance,
+                    retry_data: RetryData {
+                        attempt: total_attempts + 1,
+                        total_attempts: total_attempts + 1,
+                        ..last_run_status.test.retry_data
+                    },
+                    test_run_uuid,
+                    attempt_uuid: ReportUuid::new_v4(),
+                    settings: settings.clone(),
+                    setup_script_data: setup_script_data.clone(),
+                    delay_before_start: Duration::ZERO,
+                };
+                let run_status = self.run_test(packet, &resp_tx, &mut req_rx).await;
+                Some(Box::new(FailureVerification {
+                    status: run_status.into_external(),
+                    counts_as_flaky: verify_failures == VerifyFailures::CountFlaky,
+                }))
+            }
+            _ => None,
+        };
+
         drain_req_rx(req_rx, UnitExecuteStatus::Test(&last_run_status));
 
+        if let (Some(check), Some(rerun_status)) = (&self.determinism_check, rerun_status) {
+            if let Some(mismatch) = check.compare(
+                last_run_status.result,
+                &last_run_status.output,
+                rerun_status.result,
+                &rerun_status.output,
+            ) {
+                let _ = resp_tx.send(ExecutorEvent::OutputMismatch {
+                    test_instance,
+                    mismatch,
+                    rerun_status: rerun_status.into_external(),
+                });
+            }
+        }
+
         // At this point, either:
         // * the test has succeeded, or
         // * the test has failed and we've run out of retries.
         // In either case, the test is finished.
+        let attachments =
+            TestAttachments::collect(&test_instance.suite_info.cwd, settings.attachments());
         let last_run_status = last_run_status.into_external();
         let _ = resp_tx.send(ExecutorEvent::Finished {
             test_instance,
@@ -289,10 +523,27 @@ impl<'a> ExecutorContext<'a> {
             failure_output: settings.failure_output(),
             junit_store_success_output: settings.junit_store_success_output(),
             junit_store_failure_output: settings.junit_store_failure_output(),
+            owner: settings.owner(),
+            attachments,
+            quarantined: settings.quarantine(),
             last_run_status,
+            verification,
+            passed_iterations: self.repeat_until_failure.map(|_| passed_iterations),
         });
     }
 
+    /// Returns true if, in repeat-until-failure mode, another iteration of a passing test should
+    /// be run.
+    fn should_repeat(&self, retry_data: RetryData, repeat_start: Instant) -> bool {
+        let Some(repeat) = self.repeat_until_failure else {
+            return false;
+        };
+        retry_data.attempt < retry_data.total_attempts
+            && repeat
+                .max_duration
+                .map_or(true, |max_duration| repeat_start.elapsed() < max_duration)
+    }
+
     // ---
     // Helper methods
     // ---
@@ -307,6 +558,19 @@ impl<'a> ExecutorContext<'a> {
     ) -> InternalSetupScriptExecuteStatus<'a> {
         let mut stopwatch = crate::time::stopwatch();
 
+        if self.dry_run.is_some() {
+            return InternalSetupScriptExecuteStatus {
+                script,
+                slow_after: None,
+                output: dry_run_output(Some(ExecutionResult::Pass)),
+                result: ExecutionResult::Pass,
+                stopwatch_end: stopwatch.snapshot(),
+                env_map: Some(SetupScriptEnvMap {
+                    env_map: BTreeMap::new(),
+                }),
+            };
+        }
+
         match self
             .run_setup_script_inner(script.clone(), &mut stopwatch, resp_tx, req_rx)
             .await
@@ -335,6 +599,9 @@ impl<'a> ExecutorContext<'a> {
         let command_mut = cmd.command_mut();
 
         command_mut.env("NEXTEST_RUN_ID", format!("{}", self.run_id));
+        if let ScriptKind::Teardown { run_stats } = &script.kind {
+            command_mut.envs(teardown_script_env(run_stats));
+        }
         command_mut.stdin(Stdio::null());
         super::os::set_process_group(command_mut);
 
@@ -352,7 +619,7 @@ impl<'a> ExecutorContext<'a> {
             }
         }
 
-        let (mut child, env_path) = cmd
+        let (mut child, env_path, json_output_path) = cmd
             .spawn()
             .map_err(|error| ChildStartError::Spawn(Arc::new(error)))?;
         let child_pid = child
@@ -382,14 +649,15 @@ impl<'a> ExecutorContext<'a> {
         let mut timeout_hit = 0;
 
         let child_fds = ChildFds::new_split(child.stdout.take(), child.stderr.take());
-        let mut child_acc = ChildAccumulator::new(child_fds);
+        // Setup script output is typically small, so it isn't subject to max-output-size.
+        let mut child_acc = ChildAccumulator::new(child_fds, MaxOutputSize::Unlimited);
 
         let mut cx = UnitContext {
             packet: UnitPacket::SetupScript(script.clone()),
             slow_after: None,
         };
 
-        let (res, leaked) = {
+        let (res, leaked, leak_details) = {
             let res = loop {
                 tokio::select! {
                     () = child_acc.fill_buf(), if !child_acc.fds.is_done() => {}
@@ -412,6 +680,7 @@ impl<'a> ExecutorContext<'a> {
 
                         if !slow_timeout.grace_period.is_zero() {
                             let _ = resp_tx.send(script.slow_event(
+                                slow_timeout.period,
                                 // Pass in the slow timeout period times timeout_hit, since
                                 // stopwatch.elapsed() tends to be slightly longer.
                                 timeout_hit * slow_timeout.period,
@@ -425,19 +694,21 @@ impl<'a> ExecutorContext<'a> {
                             // own completion, we silently ignore errors to
                             // avoid printing false warnings.
                             //
-                            // The return result of terminate_child is not used
-                            // here, since it is always marked as a timeout.
-                            _ = super::os::terminate_child(
+                            // The result is always marked as a timeout, along
+                            // with whether the unit had to be forcibly killed.
+                            let result = super::os::terminate_child(
                                 &cx,
                                 &mut child,
                                 &mut child_acc,
-                                InternalTerminateReason::Timeout,
+                                InternalTerminateReason::Timeout(slow_timeout.terminate_signal),
                                 stopwatch,
                                 req_rx,
                                 job.as_ref(),
                                 slow_timeout.grace_period,
                             ).await;
-                            status = Some(ExecutionResult::Timeout);
+                            status = Some(ExecutionResult::Timeout {
+                                termination: result.to_timeout_termination(),
+                            });
                             if slow_timeout.grace_period.is_zero() {
                                 break child.wait().await;
                             }
@@ -515,9 +786,17 @@ impl<'a> ExecutorContext<'a> {
             let tentative_status = status.or_else(|| {
                 res.as_ref()
                     .ok()
-                    .map(|res| create_execution_result(*res, &child_acc.errors, false))
+                    .map(|res| create_execution_result(*res, &child_acc.errors, false, None))
             });
 
+            // If standard output or standard error are still open, sample what's holding them
+            // before waiting for the leak timeout, since leaked processes may exit during it.
+            let leak_details = if child_acc.fds.is_done() {
+                None
+            } else {
+                super::os::leak_details(child_pid)
+            };
+
             let leaked = detect_fd_leaks(
                 &cx,
                 child_pid,
@@ -529,7 +808,7 @@ impl<'a> ExecutorContext<'a> {
             )
             .await;
 
-            (res, leaked)
+            (res, leaked, leak_details)
         };
 
         let exit_status = match res {
@@ -542,18 +821,32 @@ impl<'a> ExecutorContext<'a> {
 
         let exit_status = exit_status.expect("None always results in early return");
 
-        let exec_result = status
-            .unwrap_or_else(|| create_execution_result(exit_status, &child_acc.errors, leaked));
+        let exec_result = status.unwrap_or_else(|| {
+            create_execution_result(exit_status, &child_acc.errors, leaked, leak_details)
+        });
 
-        // Read from the environment map. If there's an error here, add it to the list of child errors.
+        // Read from the environment map and the JSON outputs. If there's an error here, add it to
+        // the list of child errors.
         let mut errors: Vec<_> = child_acc.errors.into_iter().map(ChildError::from).collect();
-        let env_map = if exec_result.is_success() {
-            match parse_env_file(&env_path).await {
+        // Teardown scripts run after all tests, so there's nothing to apply their environment to.
+        let env_map = if exec_result.is_success() && matches!(script.kind, ScriptKind::Setup) {
+            let env_map = match parse_setup_script_env_file(&env_path).await {
                 Ok(env_map) => Some(env_map),
                 Err(error) => {
                     errors.push(ChildError::SetupScriptOutput(error));
                     None
                 }
+            };
+            match (env_map, parse_json_output_file(&json_output_path).await) {
+                (Some(mut env_map), Ok(outputs)) => {
+                    apply_json_outputs(&script.script_id, outputs, &mut env_map);
+                    Some(env_map)
+                }
+                (_, Err(error)) => {
+                    errors.push(ChildError::SetupScriptOutput(error));
+                    None
+                }
+                (None, Ok(_)) => None,
             }
         } else {
             None
@@ -583,6 +876,10 @@ impl<'a> ExecutorContext<'a> {
     ) -> InternalExecuteStatus<'a> {
         let mut stopwatch = crate::time::stopwatch();
 
+        if let Some(dry_run) = self.dry_run {
+            return run_test_dry(test, dry_run, &mut stopwatch, req_rx).await;
+        }
+
         match self
             .run_test_inner(test.clone(), &mut stopwatch, resp_tx, req_rx)
             .await
@@ -594,10 +891,41 @@ impl<'a> ExecutorContext<'a> {
                 output: ChildExecutionOutput::StartError(error),
                 result: ExecutionResult::ExecFail,
                 stopwatch_end: stopwatch.snapshot(),
+                failure_phase: None,
+                peak_rss: None,
+                setup_time: None,
+                io_wait: None,
             },
         }
     }
 
+    /// Builds the command for a test, with the environment shared by all attempts applied.
+    fn make_test_command(
+        &self,
+        test_instance: TestInstance<'a>,
+        settings: &TestSettings<'a>,
+        setup_script_data: &SetupScriptExecuteData<'a>,
+    ) -> TestCommand {
+        let ctx = TestExecuteContext {
+            double_spawn: &self.double_spawn,
+            target_runner: &self.target_runner,
+        };
+        let mut extra_args = settings.run_extra_args().to_vec();
+        if let Some(test_args_hook) = &self.test_args_hook {
+            extra_args.extend(test_args_hook.args_for(&test_instance));
+        }
+        let mut cmd = test_instance.make_command(&ctx, self.test_list, &extra_args);
+        let command_mut = cmd.command_mut();
+
+        command_mut.env("NEXTEST_RUN_ID", format!("{}", self.run_id));
+        setup_script_data.apply(
+            &test_instance.to_test_query(),
+            &self.profile.filterset_ecx(),
+            command_mut,
+        );
+        cmd
+    }
+
     #[instrument(level = "debug", skip(self, resp_tx, req_rx))]
     async fn run_test_inner<'test>(
         &self,
@@ -606,30 +934,29 @@ impl<'a> ExecutorContext<'a> {
         resp_tx: &UnboundedSender<ExecutorEvent<'a>>,
         req_rx: &mut UnboundedReceiver<RunUnitRequest<'a>>,
     ) -> Result<InternalExecuteStatus<'a>, ChildStartError> {
-        let ctx = TestExecuteContext {
-            double_spawn: &self.double_spawn,
-            target_runner: &self.target_runner,
-        };
         let mut cmd =
-            test.test_instance
-                .make_command(&ctx, self.test_list, test.settings.run_extra_args());
+            self.make_test_command(test.test_instance, &test.settings, &test.setup_script_data);
         let command_mut = cmd.command_mut();
 
         // Debug environment variable for testing.
         command_mut.env("__NEXTEST_ATTEMPT", format!("{}", test.retry_data.attempt));
-        command_mut.env("NEXTEST_RUN_ID", format!("{}", self.run_id));
         command_mut.stdin(Stdio::null());
-        test.setup_script_data.apply(
-            &test.test_instance.to_test_query(),
-            &self.profile.filterset_ecx(),
-            command_mut,
-        );
         super::os::set_process_group(command_mut);
+        if let Some(affinity) = test.settings.cpu_affinity() {
+            super::os::set_cpu_affinity(command_mut, affinity)
+                .map_err(ChildStartError::CpuAffinity)?;
+        }
 
         // If creating a job fails, we might be on an old system. Ignore this -- job objects are a
         // best-effort thing.
         let job = super::os::Job::create().ok();
+        let memory_limit_monitor = test
+            .settings
+            .memory_limit()
+            .limit()
+            .and_then(|limit| super::os::set_job_memory_limit(job.as_ref(), limit));
 
+        let spawned_at = Instant::now();
         let crate::test_command::Child {
             mut child,
             child_fds,
@@ -649,7 +976,7 @@ impl<'a> ExecutorContext<'a> {
         // exited.
         let _ = super::os::assign_process_to_job(&child, job.as_ref());
 
-        let mut child_acc = ChildAccumulator::new(child_fds);
+        let mut child_acc = ChildAccumulator::new(child_fds, test.settings.max_output_size());
 
         let mut status: Option<ExecutionResult> = None;
         let slow_timeout = test.settings.slow_timeout();
@@ -666,7 +993,18 @@ impl<'a> ExecutorContext<'a> {
             slow_after: None,
         };
 
-        let (res, leaked) = {
+        // Threads can't be inspected once the process has exited, so they're tracked while the
+        // test runs.
+        let mut lingering_tracker = self
+            .profile
+            .lingering_threads()
+            .is_enabled()
+            .then(|| LingeringThreadTracker::new(child_pid, test.test_instance.name));
+        // Similarly, memory statistics are gone once the process has been reaped.
+        let mut peak_memory_tracker = PeakMemoryTracker::new(child_pid);
+        let mut io_wait_tracker = IoWaitTracker::new(child_pid);
+
+        let (res, leaked, leak_details) = {
             let res = loop {
                 tokio::select! {
                     () = child_acc.fill_buf(), if !child_acc.fds.is_done() => {}
@@ -674,6 +1012,9 @@ impl<'a> ExecutorContext<'a> {
                         // The test finished executing.
                         break res;
                     }
+                    () = LingeringThreadTracker::sample_next(lingering_tracker.as_mut()) => {}
+                    () = PeakMemoryTracker::sample_next(peak_memory_tracker.as_mut()) => {}
+                    () = IoWaitTracker::sample_next(io_wait_tracker.as_mut()) => {}
                     _ = &mut interval_sleep, if status.is_none() => {
                         // Mark the test as slow.
                         cx.slow_after = Some(slow_timeout.period);
@@ -689,6 +1030,7 @@ impl<'a> ExecutorContext<'a> {
 
                         if !slow_timeout.grace_period.is_zero() {
                             let _ = resp_tx.send(test.slow_event(
+                                slow_timeout.period,
                                 // Pass in the slow timeout period times timeout_hit, since
                                 // stopwatch.elapsed() tends to be slightly longer.
                                 timeout_hit * slow_timeout.period,
@@ -702,19 +1044,21 @@ impl<'a> ExecutorContext<'a> {
                             // own completion, we silently ignore errors to
                             // avoid printing false warnings.
                             //
-                            // The return result of terminate_child is not used
-                            // here, since it is always marked as a timeout.
-                            _ = super::os::terminate_child(
+                            // The result is always marked as a timeout, along
+                            // with whether the unit had to be forcibly killed.
+                            let result = super::os::terminate_child(
                                 &cx,
                                 &mut child,
                                 &mut child_acc,
-                                InternalTerminateReason::Timeout,
+                                InternalTerminateReason::Timeout(slow_timeout.terminate_signal),
                                 stopwatch,
                                 req_rx,
                                 job.as_ref(),
                                 slow_timeout.grace_period,
                             ).await;
-                            status = Some(ExecutionResult::Timeout);
+                            status = Some(ExecutionResult::Timeout {
+                                termination: result.to_timeout_termination(),
+                            });
                             if slow_timeout.grace_period.is_zero() {
                                 break child.wait().await;
                             }
@@ -791,9 +1135,23 @@ impl<'a> ExecutorContext<'a> {
             let tentative_status = status.or_else(|| {
                 res.as_ref()
                     .ok()
-                    .map(|res| create_execution_result(*res, &child_acc.errors, false))
+                    .map(|res| create_execution_result(*res, &child_acc.errors, false, None))
             });
 
+            // If standard output or standard error are still open, sample what's holding them
+            // before waiting for the leak timeout, since leaked processes may exit during it.
+            let leak_details = if child_acc.fds.is_done() {
+                None
+            } else {
+                super::os::leak_details(child_pid)
+            };
+
+            // Output written just before the test exited may not have been read yet. Read
+            // whatever is immediately available, so that it isn't counted as being written
+            // after exit.
+            while !child_acc.fds.is_done() && child_acc.fill_buf().now_or_never().is_some() {}
+            let len_at_exit = child_acc.output.total_len();
+
             let leaked = detect_fd_leaks(
                 &cx,
                 child_pid,
@@ -805,8 +1163,32 @@ impl<'a> ExecutorContext<'a> {
             )
             .await;
 
-            (res, leaked)
+            let bytes = child_acc.output.total_len() - len_at_exit;
+            if bytes > 0 {
+                let _ = resp_tx.send(ExecutorEvent::OutputAfterExit {
+                    test_instance: test.test_instance,
+                    retry_data: test.retry_data,
+                    bytes,
+                });
+            }
+
+            (res, leaked, leak_details)
+        };
+
+        // Look for any processes the test left behind in its process group. Tests that leave
+        // processes behind are marked as leaky, even if they closed standard output and error.
+        let orphan_processes = self.profile.orphan_processes();
+        let orphans = if orphan_processes.is_enabled() {
+            super::os::find_orphan_processes(child_pid, orphan_processes == OrphanProcesses::Kill)
+        } else {
+            Vec::new()
         };
+        let lingering_threads = lingering_tracker
+            .map(LingeringThreadTracker::finish)
+            .unwrap_or_default();
+        let peak_rss = peak_memory_tracker.and_then(PeakMemoryTracker::finish);
+        let io_wait = io_wait_tracker.and_then(IoWaitTracker::finish);
+        let leaked = leaked || !orphans.is_empty() || !lingering_threads.is_empty();
 
         let exit_status = match res {
             Ok(exit_status) => Some(exit_status),
@@ -817,19 +1199,83 @@ impl<'a> ExecutorContext<'a> {
         };
 
         let exit_status = exit_status.expect("None always results in early return");
-        let exec_result = status
-            .unwrap_or_else(|| create_execution_result(exit_status, &child_acc.errors, leaked));
+        let mut exec_result = status.unwrap_or_else(|| {
+            create_execution_result(exit_status, &child_acc.errors, leaked, leak_details)
+        });
+
+        // A test that goes over its memory limit isn't killed directly: the allocation that would
+        // exceed the limit fails, which typically makes the test abort.
+        if let ExecutionResult::Fail { abort_status, .. } = &mut exec_result {
+            if let Some(status) = memory_limit_monitor
+                .as_ref()
+                .and_then(|monitor| monitor.abort_status())
+            {
+                *abort_status = Some(status);
+            }
+        }
+
+        let setup_time = child_acc.output.body_started_at().map(|body_started_at| {
+            body_started_at.saturating_duration_since(spawned_at.into_std())
+        });
+        let output = child_acc.output.freeze();
+        let mut errors: Vec<_> = child_acc.errors.into_iter().map(ChildError::from).collect();
+        if !orphans.is_empty() {
+            errors.push(ChildError::OrphanProcesses(OrphanProcessesError {
+                processes: orphans,
+                killed: orphan_processes == OrphanProcesses::Kill,
+            }));
+        }
+        if !lingering_threads.is_empty() {
+            errors.push(ChildError::LingeringThreads(LingeringThreadsError {
+                threads: lingering_threads,
+            }));
+        }
+
+        // If the test passed, check that it didn't write anything unexpected to
+        // standard error. This is only possible if stderr is captured
+        // separately.
+        if exec_result.is_success() {
+            if let ChildOutput::Split(ChildSplitOutput {
+                stderr: Some(stderr),
+                ..
+            }) = &output
+            {
+                if let Some(line) = self
+                    .profile
+                    .fail_on_stderr()
+                    .find_unexpected_line(&stderr.buf)
+                {
+                    exec_result = ExecutionResult::Fail {
+                        abort_status: None,
+                        leaked,
+                    };
+                    errors.push(ChildError::UnexpectedStderr(UnexpectedStderrError {
+                        line: String::from_utf8_lossy(line).into_owned(),
+                    }));
+                }
+            }
+        }
+
+        let failure_phase = if exec_result.is_success() {
+            None
+        } else {
+            self.profile.fixture_failure_markers().classify(&output)
+        };
 
         Ok(InternalExecuteStatus {
             test,
             slow_after: cx.slow_after,
             output: ChildExecutionOutput::Output {
                 result: Some(exec_result),
-                output: child_acc.output.freeze(),
-                errors: ErrorList::new(UnitKind::WAITING_ON_TEST_MESSAGE, child_acc.errors),
+                output,
+                errors: ErrorList::new(UnitKind::WAITING_ON_TEST_MESSAGE, errors),
             },
             result: exec_result,
             stopwatch_end: stopwatch.snapshot(),
+            failure_phase,
+            peak_rss,
+            setup_time,
+            io_wait,
         })
     }
 }
@@ -844,6 +1290,12 @@ struct BackoffIter {
 impl BackoffIter {
     const BACKOFF_EXPONENT: f64 = 2.;
 
+    /// The delay that exponential backoff saturates at if no max-delay is set.
+    ///
+    /// This is effectively forever for a test run, while staying well within what timers and
+    /// clocks can represent.
+    const SATURATED_DELAY: Duration = Duration::from_secs(86400 * 365);
+
     fn new(policy: RetryPolicy) -> Self {
         let remaining_attempts = policy.count();
         Self {
@@ -863,7 +1315,10 @@ impl BackoffIter {
                 ..
             } => {
                 let factor = self.current_factor;
-                let exp_delay = delay.mul_f64(factor);
+                // With enough retries and no max_delay, the delay can exceed what timers can
+                // represent. Saturate rather than panicking.
+                let exp_delay = Duration::try_from_secs_f64(delay.as_secs_f64() * factor)
+                    .map_or(Self::SATURATED_DELAY, |d| d.min(Self::SATURATED_DELAY));
 
                 // Stop multiplying the exponential factor if delay is greater than max_delay.
                 if let Some(max_delay) = max_delay {
@@ -872,8 +1327,10 @@ impl BackoffIter {
                     }
                 }
 
-                let next_factor = self.current_factor * Self::BACKOFF_EXPONENT;
-                self.current_factor = next_factor;
+                // Once saturated, there's no point growing the factor further.
+                if exp_delay < Self::SATURATED_DELAY {
+                    self.current_factor *= Self::BACKOFF_EXPONENT;
+                }
 
                 (exp_delay, jitter)
             }
@@ -882,8 +1339,22 @@ impl BackoffIter {
 
     fn apply_jitter(duration: Duration) -> Duration {
         let jitter: f64 = thread_rng().sample(OpenClosed01);
-        // Apply jitter in the range (0.5, 1].
-        duration.mul_f64(0.5 + jitter / 2.)
+        Self::scale_jitter(duration, jitter)
+    }
+
+    /// Scales `duration` by a factor in the range (0.5, 1], given `jitter` in the range (0, 1].
+    ///
+    /// The result is never greater than `duration`, and never zero if `duration` is non-zero.
+    fn scale_jitter(duration: Duration, jitter: f64) -> Duration {
+        // Float rounding can push the result of mul_f64 past Duration::MAX for very large
+        // durations, so use the checked version and clamp the result.
+        let scaled = Duration::try_from_secs_f64(duration.as_secs_f64() * (0.5 + jitter / 2.))
+            .unwrap_or(duration);
+        if duration.is_zero() {
+            duration
+        } else {
+            scaled.clamp(Duration::from_nanos(1), duration)
+        }
     }
 }
 
@@ -949,16 +1420,24 @@ impl UnitPacket<'_> {
 pub(super) struct TestPacket<'a> {
     test_instance: TestInstance<'a>,
     retry_data: RetryData,
+    test_run_uuid: ReportUuid,
+    attempt_uuid: ReportUuid,
     settings: Arc<TestSettings<'a>>,
     setup_script_data: Arc<SetupScriptExecuteData<'a>>,
     delay_before_start: Duration,
 }
 
 impl<'a> TestPacket<'a> {
-    fn slow_event(&self, elapsed: Duration, will_terminate: Option<Duration>) -> ExecutorEvent<'a> {
+    fn slow_event(
+        &self,
+        slow_timeout: Duration,
+        elapsed: Duration,
+        will_terminate: Option<Duration>,
+    ) -> ExecutorEvent<'a> {
         ExecutorEvent::Slow {
             test_instance: self.test_instance,
             retry_data: self.retry_data,
+            slow_timeout,
             elapsed,
             will_terminate,
         }
@@ -968,6 +1447,14 @@ impl<'a> TestPacket<'a> {
         self.retry_data
     }
 
+    pub(super) fn test_run_uuid(&self) -> ReportUuid {
+        self.test_run_uuid
+    }
+
+    pub(super) fn attempt_uuid(&self) -> ReportUuid {
+        self.attempt_uuid
+    }
+
     pub(super) fn delay_before_start(&self) -> Duration {
         self.delay_before_start
     }
@@ -981,6 +1468,11 @@ impl<'a> TestPacket<'a> {
             test_instance: self.test_instance.id(),
             state,
             retry_data: self.retry_data,
+            cpu_affinity: self
+                .settings
+                .cpu_affinity()
+                .filter(|_| CpuAffinity::IS_SUPPORTED)
+                .cloned(),
             output,
         })
     }
@@ -990,6 +1482,34 @@ impl<'a> TestPacket<'a> {
 pub(super) struct SetupScriptPacket<'a> {
     script_id: ScriptId,
     config: &'a ScriptConfig,
+    kind: ScriptKind,
+}
+
+/// Whether a script is run before or after tests.
+#[derive(Clone, Debug)]
+enum ScriptKind {
+    Setup,
+    // Teardown scripts are passed the final statistics for the run.
+    Teardown { run_stats: Box<RunStats> },
+}
+
+/// Returns the environment variables describing the outcome of a run, passed to teardown scripts.
+fn teardown_script_env(run_stats: &RunStats) -> [(&'static str, String); 7] {
+    [
+        (
+            "NEXTEST_RUN_OUTCOME",
+            run_stats.summarize_final().to_static_str().to_owned(),
+        ),
+        (
+            "NEXTEST_RUN_INITIAL",
+            run_stats.initial_run_count.to_string(),
+        ),
+        ("NEXTEST_RUN_FINISHED", run_stats.finished_count.to_string()),
+        ("NEXTEST_RUN_PASSED", run_stats.passed.to_string()),
+        ("NEXTEST_RUN_FAILED", run_stats.failed_count().to_string()),
+        ("NEXTEST_RUN_FLAKY", run_stats.flaky.to_string()),
+        ("NEXTEST_RUN_SKIPPED", run_stats.skipped.to_string()),
+    ]
 }
 
 impl<'a> SetupScriptPacket<'a> {
@@ -1002,12 +1522,27 @@ impl<'a> SetupScriptPacket<'a> {
         SetupScriptCommand::new(self.config, double_spawn, test_list)
     }
 
-    fn slow_event(&self, elapsed: Duration, will_terminate: Option<Duration>) -> ExecutorEvent<'a> {
-        ExecutorEvent::SetupScriptSlow {
-            script_id: self.script_id.clone(),
-            config: self.config,
-            elapsed,
-            will_terminate,
+    fn slow_event(
+        &self,
+        slow_timeout: Duration,
+        elapsed: Duration,
+        will_terminate: Option<Duration>,
+    ) -> ExecutorEvent<'a> {
+        match self.kind {
+            ScriptKind::Setup => ExecutorEvent::SetupScriptSlow {
+                script_id: self.script_id.clone(),
+                config: self.config,
+                slow_timeout,
+                elapsed,
+                will_terminate,
+            },
+            ScriptKind::Teardown { .. } => ExecutorEvent::TeardownScriptSlow {
+                script_id: self.script_id.clone(),
+                config: self.config,
+                slow_timeout,
+                elapsed,
+                will_terminate,
+            },
         }
     }
 
@@ -1047,17 +1582,82 @@ fn drain_req_rx<'a>(
     }
 }
 
+/// Spaces out the starts of tests by the profile's `start-stagger` setting.
+#[derive(Debug)]
+struct StartStagger {
+    interval: Duration,
+    // The earliest time at which the next test may start.
+    next_start: Mutex<Option<Instant>>,
+}
+
+impl StartStagger {
+    fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            next_start: Mutex::new(None),
+        }
+    }
+
+    /// Waits until the next start slot.
+    ///
+    /// Slots are handed out in the order this is called, each at least `interval` after the
+    /// previous one. With a zero interval, this returns immediately.
+    async fn wait(&self) {
+        if self.interval.is_zero() {
+            return;
+        }
+
+        let start = {
+            let mut next_start = self.next_start.lock().expect("lock is not poisoned");
+            let now = Instant::now();
+            let start = next_start.map_or(now, |next_start| next_start.max(now));
+            *next_start = Some(start + self.interval);
+            start
+        };
+        tokio::time::sleep_until(start).await;
+    }
+}
+
+/// Returns the time that a delay of `remaining` starting now ends at, or `None` if that's too far
+/// in the future to represent.
+fn resume_time_after(remaining: Duration) -> Option<DateTime<FixedOffset>> {
+    let remaining = TimeDelta::from_std(remaining).ok()?;
+    Local::now()
+        .checked_add_signed(remaining)
+        .map(|time| time.fixed_offset())
+}
+
 async fn handle_delay_between_attempts<'a>(
     packet: &TestPacket<'a>,
     previous_result: ExecutionResult,
     previous_slow: bool,
     delay: Duration,
+    resp_tx: &UnboundedSender<ExecutorEvent<'a>>,
     req_rx: &mut UnboundedReceiver<RunUnitRequest<'a>>,
 ) {
+    if delay.is_zero() {
+        return;
+    }
+
     let mut sleep = std::pin::pin!(crate::time::pausable_sleep(delay));
     #[cfg_attr(not(unix), expect(unused_mut))]
     let mut waiting_stopwatch = crate::time::stopwatch();
 
+    // Let the dispatcher know when the next attempt is scheduled to start. This is sent again if
+    // the delay is resumed after being paused, since that moves the resume time.
+    let send_delay_started = |remaining: Duration| {
+        _ = resp_tx.send(ExecutorEvent::RetryDelayStarted {
+            test_instance: packet.test_instance,
+            retry_data: RetryData {
+                attempt: packet.retry_data.attempt + 1,
+                ..packet.retry_data
+            },
+            remaining,
+            resume_time: resume_time_after(remaining),
+        });
+    };
+    send_delay_started(delay);
+
     loop {
         tokio::select! {
             _ = &mut sleep => {
@@ -1072,13 +1672,19 @@ async fn handle_delay_between_attempts<'a>(
                     RunUnitRequest::Signal(SignalRequest::Stop(tx)) => {
                         sleep.as_mut().pause();
                         waiting_stopwatch.pause();
-                        _ = tx.send(());
+                        // There's no process to stop while waiting to retry.
+                        _ = tx.send(super::UnitStopStatus::Stopped);
                     }
                     #[cfg(unix)]
                     RunUnitRequest::Signal(SignalRequest::Continue) => {
                         if sleep.is_paused() {
                             sleep.as_mut().resume();
                             waiting_stopwatch.resume();
+                            send_delay_started(
+                                delay
+                                    .checked_sub(waiting_stopwatch.snapshot().active)
+                                    .unwrap_or_default(),
+                            );
                         }
                     }
                     RunUnitRequest::Signal(SignalRequest::Shutdown(_)) => {
@@ -1126,9 +1732,9 @@ async fn handle_delay_between_attempts<'a>(
 /// leaving long-running grandchildren open.
 ///
 /// This is done by waiting for a short period of time after the child has
-/// exited, and checking if stdout and stderr are still open. In the future, we
-/// could do more sophisticated checks around e.g. if any processes with the
-/// same PGID are around.
+/// exited, and checking if stdout and stderr are still open. Processes that
+/// close stdout and stderr can be found via the `orphan-processes` setting,
+/// which looks for processes with the same PGID.
 async fn detect_fd_leaks<'a>(
     cx: &UnitContext<'a>,
     child_pid: u32,
@@ -1227,10 +1833,10 @@ async fn handle_signal_request<'a>(
             // debounced in the main signal handler.
             stopwatch.pause();
             interval_sleep.as_mut().pause();
-            super::os::job_control_child(child, crate::signal::JobControlEvent::Stop);
+            let status = super::os::stop_child(child).await;
             // The receiver being dead probably means the main thread panicked
             // or similar.
-            let _ = sender.send(());
+            let _ = sender.send(status);
             HandleSignalResult::JobControl
         }
         #[cfg(unix)]
@@ -1261,10 +1867,91 @@ async fn handle_signal_request<'a>(
     }
 }
 
+/// Reports a test as passing without running it, for dry-run mode.
+async fn run_test_dry<'a>(
+    test: TestPacket<'a>,
+    dry_run: DryRun,
+    stopwatch: &mut StopwatchStart,
+    req_rx: &mut UnboundedReceiver<RunUnitRequest<'a>>,
+) -> InternalExecuteStatus<'a> {
+    if let DryRun::Simulated(duration) = dry_run {
+        let mut sleep = std::pin::pin!(crate::time::pausable_sleep(duration));
+        loop {
+            tokio::select! {
+                () = &mut sleep => break,
+                recv = req_rx.recv() => {
+                    // The sender stays open longer than the whole loop so a
+                    // RecvError should never happen.
+                    let req = recv.expect("req_rx sender is open");
+
+                    match req {
+                        #[cfg(unix)]
+                        RunUnitRequest::Signal(SignalRequest::Stop(sender)) => {
+                            stopwatch.pause();
+                            sleep.as_mut().pause();
+                            let _ = sender.send(super::UnitStopStatus::Stopped);
+                        }
+                        #[cfg(unix)]
+                        RunUnitRequest::Signal(SignalRequest::Continue) => {
+                            if stopwatch.is_paused() {
+                                stopwatch.resume();
+                                sleep.as_mut().resume();
+                            }
+                        }
+                        RunUnitRequest::Signal(SignalRequest::Shutdown(_)) => {
+                            // There's no process to terminate, so just stop
+                            // waiting.
+                            break;
+                        }
+                        RunUnitRequest::OtherCancel => {
+                            // Let the test finish, as with a real test.
+                        }
+                        RunUnitRequest::Query(RunUnitQuery::GetInfo(tx)) => {
+                            _ = tx.send(test.info_response(
+                                UnitState::Running {
+                                    // No process is spawned in dry-run mode.
+                                    pid: 0,
+                                    time_taken: stopwatch.snapshot().active,
+                                    slow_after: None,
+                                },
+                                dry_run_output(None),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    InternalExecuteStatus {
+        test,
+        slow_after: None,
+        output: dry_run_output(Some(ExecutionResult::Pass)),
+        result: ExecutionResult::Pass,
+        stopwatch_end: stopwatch.snapshot(),
+        failure_phase: None,
+        peak_rss: None,
+        setup_time: None,
+        io_wait: None,
+    }
+}
+
+fn dry_run_output(result: Option<ExecutionResult>) -> ChildExecutionOutput {
+    ChildExecutionOutput::Output {
+        result,
+        output: ChildOutput::Split(ChildSplitOutput {
+            stdout: None,
+            stderr: None,
+        }),
+        errors: None,
+    }
+}
+
 fn create_execution_result(
     exit_status: ExitStatus,
     child_errors: &[ChildFdError],
     leaked: bool,
+    leak_details: Option<LeakDetails>,
 ) -> ExecutionResult {
     if !child_errors.is_empty() {
         // If an error occurred while waiting on the child handles, treat it as
@@ -1272,7 +1959,9 @@ fn create_execution_result(
         ExecutionResult::ExecFail
     } else if exit_status.success() {
         if leaked {
-            ExecutionResult::Leak
+            ExecutionResult::Leak {
+                details: leak_details,
+            }
         } else {
             ExecutionResult::Pass
         }
@@ -1283,3 +1972,96 @@ fn create_execution_result(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_exponential_max_delay() {
+        let policy = RetryPolicy::Exponential {
+            count: 5,
+            delay: Duration::from_secs(1),
+            jitter: false,
+            max_delay: Some(Duration::from_secs(5)),
+        };
+        assert_eq!(
+            BackoffIter::new(policy).collect::<Vec<_>>(),
+            [1, 2, 4, 5, 5].map(Duration::from_secs),
+        );
+    }
+
+    #[test]
+    fn backoff_exponential_saturates() {
+        let policy = RetryPolicy::Exponential {
+            count: 100,
+            delay: Duration::from_secs(1),
+            jitter: true,
+            max_delay: None,
+        };
+        let delays: Vec<_> = BackoffIter::new(policy).collect();
+        assert_eq!(delays.len(), 100);
+        assert!(delays.iter().all(|delay| !delay.is_zero()));
+
+        // The saturated delay can still be scheduled.
+        let policy = RetryPolicy::Exponential {
+            count: 100,
+            delay: Duration::from_secs(1),
+            jitter: false,
+            max_delay: None,
+        };
+        let last = BackoffIter::new(policy)
+            .last()
+            .expect("delays are produced");
+        assert_eq!(last, BackoffIter::SATURATED_DELAY);
+        assert!(resume_time_after(last).is_some());
+    }
+
+    #[test]
+    fn resume_time_overflow() {
+        let resume_time = resume_time_after(Duration::from_secs(60)).expect("representable");
+        assert!(resume_time > Local::now().fixed_offset());
+        assert_eq!(resume_time_after(Duration::MAX), None);
+    }
+
+    #[test]
+    fn scale_jitter_bounds() {
+        let durations = [
+            Duration::ZERO,
+            Duration::from_nanos(1),
+            Duration::from_secs(4),
+            Duration::MAX,
+        ];
+        for duration in durations {
+            for jitter in [f64::MIN_POSITIVE, 0.5, 1.] {
+                let scaled = BackoffIter::scale_jitter(duration, jitter);
+                assert!(scaled <= duration, "{scaled:?} <= {duration:?}");
+                assert_eq!(scaled.is_zero(), duration.is_zero(), "{duration:?}");
+            }
+        }
+        assert_eq!(
+            BackoffIter::scale_jitter(Duration::from_secs(4), 1.),
+            Duration::from_secs(4),
+        );
+        assert_eq!(
+            BackoffIter::scale_jitter(Duration::from_secs(4), 0.5),
+            Duration::from_secs(3),
+        );
+    }
+
+    #[tokio::test]
+    async fn start_stagger_spaces_out_starts() {
+        let stagger = StartStagger::new(Duration::ZERO);
+        stagger.wait().await;
+        stagger.wait().await;
+        assert_eq!(*stagger.next_start.lock().unwrap(), None);
+
+        let stagger = StartStagger::new(Duration::from_millis(20));
+        let start = Instant::now();
+        futures::join!(stagger.wait(), stagger.wait(), stagger.wait());
+        assert!(
+            start.elapsed() >= Duration::from_millis(40),
+            "later starts are spaced out"
+        );
+    }
+}
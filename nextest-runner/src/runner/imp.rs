@@ -1,26 +1,38 @@
 // Copyright (c) The nextest Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use super::{DispatcherContext, ExecutorContext, RunnerTaskState};
+use super::{
+    is_reserved_env_key, DispatcherContext, ExecutionPlan, ExecutorContext, RunnerTaskState,
+    SlowHook,
+};
 use crate::{
     config::{
         EvaluatableProfile, MaxFail, RetryPolicy, SetupScriptExecuteData, TestGroup, TestThreads,
     },
     double_spawn::DoubleSpawnInfo,
-    errors::{ConfigureHandleInheritanceError, TestRunnerBuildError, TestRunnerExecuteErrors},
+    errors::{
+        ConfigureHandleInheritanceError, ShuffleParseError, TestRunnerBuildError,
+        TestRunnerExecuteErrors,
+    },
     input::{InputHandler, InputHandlerKind, InputHandlerStatus},
     list::{TestInstance, TestList},
-    reporter::events::{RunStats, TestEvent},
+    reporter::events::{DurationHistogram, RunPhase, RunStats, SetupScriptEnvMap, TestEvent},
     runner::ExecutorEvent,
     signal::{SignalHandler, SignalHandlerKind},
     target_runner::TargetRunner,
-    test_output::CaptureStrategy,
+    test_filter::TestFilterSummary,
+    test_output::{CaptureStrategy, DeterminismCheck},
+    timings::TimingsStore,
 };
 use async_scoped::TokioScope;
 use future_queue::StreamExt;
-use futures::prelude::*;
+use futures::{future::BoxFuture, prelude::*};
 use quick_junit::ReportUuid;
-use std::{convert::Infallible, fmt, sync::Arc};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use std::{
+    borrow::Cow, convert::Infallible, fmt, num::NonZeroUsize, str::FromStr, sync::Arc,
+    time::Duration,
+};
 use tokio::{
     runtime::Runtime,
     sync::{mpsc::unbounded_channel, oneshot},
@@ -28,13 +40,168 @@ use tokio::{
 };
 use tracing::{debug, warn};
 
+/// How failing tests are treated when failure verification is enabled.
+///
+/// See [`TestRunnerBuilder::set_verify_failures`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerifyFailures {
+    /// Report whether the failure reproduced, but count the test as failed either way.
+    Report,
+
+    /// If the failure doesn't reproduce, count the test as flaky rather than failed.
+    CountFlaky,
+}
+
+/// Bounds for repeat-until-failure mode.
+///
+/// See [`TestRunnerBuilder::set_repeat_until_failure`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RepeatUntilFailure {
+    /// The maximum number of times each test is run.
+    pub max_iterations: NonZeroUsize,
+
+    /// The maximum amount of time spent repeating each test, if any.
+    ///
+    /// Once this has elapsed, no further iterations of the test are started. An iteration that is
+    /// already running is allowed to finish.
+    pub max_duration: Option<Duration>,
+}
+
+/// How tests are reported in dry-run mode.
+///
+/// See [`TestRunnerBuilder::set_dry_run`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DryRun {
+    /// Each test passes as soon as it starts.
+    Instant,
+
+    /// Each test passes after the given duration.
+    ///
+    /// Tests are still scheduled according to the configured number of test threads, so the run
+    /// takes roughly as long as it would if every test took this long.
+    Simulated(Duration),
+}
+
+/// How the order tests are started in is shuffled.
+///
+/// See [`TestRunnerBuilder::set_shuffle`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shuffle {
+    /// Shuffle with a randomly chosen seed.
+    Random,
+
+    /// Shuffle with the given seed.
+    ///
+    /// For the same set of tests and the same version of nextest, the same seed always produces
+    /// the same order.
+    Seed(u64),
+}
+
+impl FromStr for Shuffle {
+    type Err = ShuffleParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "random" {
+            return Ok(Self::Random);
+        }
+
+        s.parse().map(Self::Seed).map_err(|_| ShuffleParseError {
+            input: s.to_owned(),
+        })
+    }
+}
+
+/// A hook that lets the embedding application cancel a test run with a custom reason.
+///
+/// The hook is a function that returns a future. The function is called once the run starts, and
+/// the runner polls the future alongside its other inputs. If the future resolves to `Some`, the
+/// run is cancelled with
+/// [`CancelReason::Custom`](crate::reporter::events::CancelReason::Custom), the same way as it is for a test failure. If it
+/// resolves to `None`, the hook is no longer polled and the run carries on.
+///
+/// Registered with [`TestRunnerBuilder::set_cancel_hook`].
+pub struct CancelHook {
+    make_future: Box<dyn FnOnce() -> BoxFuture<'static, Option<Cow<'static, str>>> + Send>,
+}
+
+impl CancelHook {
+    /// Creates a new cancel hook from a function returning a future.
+    pub fn new<F, Fut>(make_future: F) -> Self
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Option<Cow<'static, str>>> + Send + 'static,
+    {
+        Self {
+            make_future: Box::new(move || make_future().boxed()),
+        }
+    }
+
+    pub(super) fn into_future(self) -> BoxFuture<'static, Option<Cow<'static, str>>> {
+        (self.make_future)()
+    }
+}
+
+impl fmt::Debug for CancelHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CancelHook").finish_non_exhaustive()
+    }
+}
+
+/// A hook that lets the embedding application pass extra arguments to each test.
+///
+/// The hook is a function that is called with each test instance before its process is spawned,
+/// including once for each retry. The arguments it returns are appended after the ones nextest
+/// passes to the test binary, and after any `run-extra-args` from the profile. They're included in
+/// the command line reported in
+/// [`TestEventKind::TestStarted`](crate::reporter::events::TestEventKind::TestStarted).
+///
+/// Registered with [`TestRunnerBuilder::set_test_args_hook`].
+#[derive(Clone)]
+pub struct TestArgsHook {
+    args_fn: Arc<TestArgsFn>,
+}
+
+type TestArgsFn = dyn Fn(&TestInstance<'_>) -> Vec<String> + Send + Sync;
+
+impl TestArgsHook {
+    /// Creates a new test arguments hook from a function.
+    pub fn new<F>(args_fn: F) -> Self
+    where
+        F: Fn(&TestInstance<'_>) -> Vec<String> + Send + Sync + 'static,
+    {
+        Self {
+            args_fn: Arc::new(args_fn),
+        }
+    }
+
+    pub(super) fn args_for(&self, test_instance: &TestInstance<'_>) -> Vec<String> {
+        (self.args_fn)(test_instance)
+    }
+}
+
+impl fmt::Debug for TestArgsHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TestArgsHook").finish_non_exhaustive()
+    }
+}
+
 /// Test runner options.
 #[derive(Debug, Default)]
 pub struct TestRunnerBuilder {
     capture_strategy: CaptureStrategy,
     retries: Option<RetryPolicy>,
     max_fail: Option<MaxFail>,
+    count_setup_script_failures: bool,
     test_threads: Option<TestThreads>,
+    determinism_check: Option<DeterminismCheck>,
+    verify_failures: Option<VerifyFailures>,
+    repeat_until_failure: Option<RepeatUntilFailure>,
+    filter_summary: Option<TestFilterSummary>,
+    extra_env: Option<SetupScriptEnvMap>,
+    cancel_hook: Option<CancelHook>,
+    test_args_hook: Option<TestArgsHook>,
+    dry_run: Option<DryRun>,
+    shuffle: Option<Shuffle>,
 }
 
 impl TestRunnerBuilder {
@@ -65,12 +232,147 @@ impl TestRunnerBuilder {
         self
     }
 
+    /// Sets whether setup script failures count towards the max-fail value.
+    ///
+    /// By default, a failing setup script cancels the run immediately. If this is set to true,
+    /// failing setup scripts are instead counted along with failing tests, and the run is only
+    /// cancelled once the max-fail value is reached.
+    pub fn set_count_setup_script_failures(&mut self, count: bool) -> &mut Self {
+        self.count_setup_script_failures = count;
+        self
+    }
+
     /// Sets the number of tests to run simultaneously.
     pub fn set_test_threads(&mut self, test_threads: TestThreads) -> &mut Self {
         self.test_threads = Some(test_threads);
         self
     }
 
+    /// Enables determinism checks for this test runner.
+    ///
+    /// With determinism checks enabled, each test that passes is run a second time, and its
+    /// result and captured output are compared against the first run. Mismatches are reported
+    /// as [`TestEventKind::TestOutputMismatch`](crate::reporter::events::TestEventKind::TestOutputMismatch)
+    /// events.
+    pub fn set_determinism_check(&mut self, determinism_check: DeterminismCheck) -> &mut Self {
+        self.determinism_check = Some(determinism_check);
+        self
+    }
+
+    /// Enables failure verification for this test runner.
+    ///
+    /// With failure verification enabled, each test that fails all its attempts is immediately
+    /// run once more, to check whether the failure reproduces. The rerun is recorded as a
+    /// [`FailureVerification`](crate::reporter::events::FailureVerification) in the test's
+    /// execution statuses.
+    pub fn set_verify_failures(&mut self, verify_failures: VerifyFailures) -> &mut Self {
+        self.verify_failures = Some(verify_failures);
+        self
+    }
+
+    /// Enables repeat-until-failure mode for this test runner.
+    ///
+    /// In this mode, each test that passes is run again, up to the bounds set in
+    /// `repeat_until_failure`, until it fails. Each iteration after the first is reported as a
+    /// [`TestEventKind::TestRetryStarted`](crate::reporter::events::TestEventKind::TestRetryStarted)
+    /// event, and the test's final status is that of its last iteration. Retries aren't performed
+    /// in this mode, since the goal is to find a failure rather than to recover from one.
+    pub fn set_repeat_until_failure(
+        &mut self,
+        repeat_until_failure: RepeatUntilFailure,
+    ) -> &mut Self {
+        self.repeat_until_failure = Some(repeat_until_failure);
+        self
+    }
+
+    /// Sets the summary of the filters that selected the tests in this run.
+    ///
+    /// The summary is reported as part of
+    /// [`TestEventKind::RunStarted`](crate::reporter::events::TestEventKind::RunStarted).
+    pub fn set_filter_summary(&mut self, filter_summary: TestFilterSummary) -> &mut Self {
+        self.filter_summary = Some(filter_summary);
+        self
+    }
+
+    /// Sets extra environment variables to apply to every test, as if a setup script that ran
+    /// before all others had set them.
+    ///
+    /// Variables set by setup scripts take precedence over these. As with setup scripts, keys
+    /// beginning with `NEXTEST` are reserved: [`Self::build`] returns an error if any are present.
+    pub fn set_extra_env(&mut self, extra_env: SetupScriptEnvMap) -> &mut Self {
+        self.extra_env = Some(extra_env);
+        self
+    }
+
+    /// Registers a hook that can cancel the run with a custom reason.
+    ///
+    /// The run is cancelled the same way as for a test failure: tests that are running are
+    /// allowed to finish, and no new tests are started. A custom reason is considered more severe
+    /// than a test failure, but less severe than a reporting error or a signal; see
+    /// [`CancelReason`](crate::reporter::events::CancelReason) for more.
+    pub fn set_cancel_hook(&mut self, cancel_hook: CancelHook) -> &mut Self {
+        self.cancel_hook = Some(cancel_hook);
+        self
+    }
+
+    /// Registers a hook that returns extra arguments to pass to each test.
+    ///
+    /// See [`TestArgsHook`] for where the arguments are placed.
+    pub fn set_test_args_hook(&mut self, test_args_hook: TestArgsHook) -> &mut Self {
+        self.test_args_hook = Some(test_args_hook);
+        self
+    }
+
+    /// Enables dry-run mode for this test runner.
+    ///
+    /// In dry-run mode, no processes are spawned: every test and setup script is reported as
+    /// passing, with no captured output. The full stream of events is still produced, which makes
+    /// this useful for exercising reporters.
+    pub fn set_dry_run(&mut self, dry_run: DryRun) -> &mut Self {
+        self.dry_run = Some(dry_run);
+        self
+    }
+
+    /// Shuffles the order tests are started in.
+    ///
+    /// Tests are shuffled after filtering and partitioning, so the set of tests that are run
+    /// doesn't change. If smoke tests are configured, smoke tests are still run first, and each
+    /// phase is shuffled separately. The seed used is reported as part of
+    /// [`TestEventKind::RunStarted`](crate::reporter::events::TestEventKind::RunStarted), so that
+    /// the order can be reproduced with [`Shuffle::Seed`].
+    pub fn set_shuffle(&mut self, shuffle: Shuffle) -> &mut Self {
+        self.shuffle = Some(shuffle);
+        self
+    }
+
+    /// Computes the plan for running the tests in `test_list`, without running anything.
+    ///
+    /// Estimated durations are taken from `timings`.
+    pub fn plan<'a>(
+        &self,
+        test_list: &'a TestList<'_>,
+        profile: &EvaluatableProfile<'_>,
+        timings: &TimingsStore,
+    ) -> ExecutionPlan<'a> {
+        ExecutionPlan::new(
+            test_list,
+            profile,
+            self.compute_test_threads(profile),
+            self.retries,
+            timings,
+        )
+    }
+
+    fn compute_test_threads(&self, profile: &EvaluatableProfile<'_>) -> usize {
+        match self.capture_strategy {
+            CaptureStrategy::None => 1,
+            CaptureStrategy::Combined | CaptureStrategy::Split => self
+                .test_threads
+                .unwrap_or_else(|| profile.test_threads())
+                .compute(),
+        }
+    }
+
     /// Creates a new test runner.
     #[expect(clippy::too_many_arguments)]
     pub fn build<'a>(
@@ -83,16 +385,18 @@ impl TestRunnerBuilder {
         double_spawn: DoubleSpawnInfo,
         target_runner: TargetRunner,
     ) -> Result<TestRunner<'a>, TestRunnerBuildError> {
-        let test_threads = match self.capture_strategy {
-            CaptureStrategy::None => 1,
-            CaptureStrategy::Combined | CaptureStrategy::Split => self
-                .test_threads
-                .unwrap_or_else(|| profile.test_threads())
-                .compute(),
-        };
-        let max_fail = self
-            .max_fail
-            .unwrap_or_else(|| MaxFail::from_fail_fast(profile.fail_fast()));
+        let test_threads = self.compute_test_threads(profile);
+        let max_fail = self.max_fail.unwrap_or_else(|| profile.max_fail());
+
+        if let Some(extra_env) = &self.extra_env {
+            if let Some(key) = extra_env
+                .env_map
+                .keys()
+                .find(|key| is_reserved_env_key(key))
+            {
+                return Err(TestRunnerBuildError::ExtraEnvReservedKey { key: key.clone() });
+            }
+        }
 
         let runtime = tokio::runtime::Builder::new_multi_thread()
             .enable_all()
@@ -116,12 +420,25 @@ impl TestRunnerBuilder {
                 target_runner,
                 capture_strategy: self.capture_strategy,
                 force_retries: self.retries,
+                determinism_check: self.determinism_check,
+                verify_failures: self.verify_failures,
+                repeat_until_failure: self.repeat_until_failure,
+                filter_summary: self.filter_summary,
+                extra_env: self.extra_env,
+                test_args_hook: self.test_args_hook,
+                dry_run: self.dry_run,
+                shuffle_seed: self.shuffle.map(|shuffle| match shuffle {
+                    Shuffle::Random => rand::random(),
+                    Shuffle::Seed(seed) => seed,
+                }),
                 cli_args,
                 max_fail,
+                count_setup_script_failures: self.count_setup_script_failures,
                 runtime,
             },
             signal_handler,
             input_handler,
+            cancel_hook: self.cancel_hook,
         })
     }
 }
@@ -134,6 +451,7 @@ pub struct TestRunner<'a> {
     inner: TestRunnerInner<'a>,
     signal_handler: SignalHandler,
     input_handler: InputHandler,
+    cancel_hook: Option<CancelHook>,
 }
 
 impl<'a> TestRunner<'a> {
@@ -186,6 +504,7 @@ impl<'a> TestRunner<'a> {
             &mut self.signal_handler,
             &mut self.input_handler,
             report_cancel_rx,
+            self.cancel_hook.take(),
             |event| {
                 match callback(event) {
                     Ok(()) => {}
@@ -231,8 +550,17 @@ struct TestRunnerInner<'a> {
     target_runner: TargetRunner,
     capture_strategy: CaptureStrategy,
     force_retries: Option<RetryPolicy>,
+    determinism_check: Option<DeterminismCheck>,
+    verify_failures: Option<VerifyFailures>,
+    repeat_until_failure: Option<RepeatUntilFailure>,
+    filter_summary: Option<TestFilterSummary>,
+    extra_env: Option<SetupScriptEnvMap>,
+    test_args_hook: Option<TestArgsHook>,
+    dry_run: Option<DryRun>,
+    shuffle_seed: Option<u64>,
     cli_args: Vec<String>,
     max_fail: MaxFail,
+    count_setup_script_failures: bool,
     runtime: Runtime,
 }
 
@@ -242,6 +570,7 @@ impl<'a> TestRunnerInner<'a> {
         signal_handler: &mut SignalHandler,
         input_handler: &mut InputHandler,
         report_cancel_rx: oneshot::Receiver<()>,
+        cancel_hook: Option<CancelHook>,
         callback: F,
     ) -> Result<RunStats, Vec<JoinError>>
     where
@@ -256,6 +585,15 @@ impl<'a> TestRunnerInner<'a> {
             self.cli_args.clone(),
             self.test_list.run_count(),
             self.max_fail,
+            self.count_setup_script_failures,
+            self.profile.sigtstp(),
+            SlowHook::new(self.profile.on_slow(), self.test_list.workspace_root()),
+            self.profile.smoke().map(|smoke| smoke.mode()),
+            self.profile
+                .duration_histogram()
+                .buckets()
+                .map(|buckets| DurationHistogram::new(buckets.to_vec())),
+            self.profile.cancel_grace(),
         );
 
         let executor_cx = ExecutorContext::new(
@@ -266,12 +604,46 @@ impl<'a> TestRunnerInner<'a> {
             self.target_runner.clone(),
             self.capture_strategy,
             self.force_retries,
+            self.determinism_check.clone(),
+            self.verify_failures,
+            self.repeat_until_failure,
+            self.extra_env.clone(),
+            self.test_args_hook.clone(),
+            self.dry_run,
         );
 
+        let mut phases = smoke_phases(self.test_list, self.profile);
+
         // Send the initial event.
         // (Don't need to set the cancelled atomic if this fails because the run hasn't started
         // yet.)
-        dispatcher_cx.run_started(self.test_list);
+        let queue: Vec<_> = match &mut phases {
+            None => {
+                let mut tests: Vec<_> = self.test_list.iter_tests().collect();
+                if let Some(seed) = self.shuffle_seed {
+                    shuffle_tests(&mut tests, seed);
+                }
+                tests
+            }
+            Some(phases) => {
+                // Each phase is shuffled with its own RNG, so that the order of the main phase
+                // doesn't depend on how many smoke tests there are.
+                if let Some(seed) = self.shuffle_seed {
+                    shuffle_tests(&mut phases.smoke, seed);
+                    shuffle_tests(&mut phases.main, seed);
+                }
+                phases.smoke.iter().chain(&phases.main).copied().collect()
+            }
+        };
+        dispatcher_cx.run_started(
+            self.test_list,
+            self.filter_summary.clone(),
+            self.shuffle_seed,
+            queue
+                .iter()
+                .filter(|test_instance| test_instance.test_info.filter_match.is_match())
+                .map(|test_instance| test_instance.id()),
+        );
 
         let executor_cx_ref = &executor_cx;
         let dispatcher_cx_mut = &mut dispatcher_cx;
@@ -282,8 +654,13 @@ impl<'a> TestRunnerInner<'a> {
             let (resp_tx, resp_rx) = unbounded_channel::<ExecutorEvent<'a>>();
 
             // Run the dispatcher to completion in a task.
-            let dispatcher_fut =
-                dispatcher_cx_mut.run(resp_rx, signal_handler, input_handler, report_cancel_rx);
+            let dispatcher_fut = dispatcher_cx_mut.run(
+                resp_rx,
+                signal_handler,
+                input_handler,
+                report_cancel_rx,
+                cancel_hook,
+            );
             scope.spawn_cancellable(dispatcher_fut, || RunnerTaskState::Cancelled);
 
             let (script_tx, mut script_rx) = unbounded_channel::<SetupScriptExecuteData<'a>>();
@@ -314,78 +691,123 @@ impl<'a> TestRunnerInner<'a> {
                 .map(|(group_name, config)| (group_name, config.max_threads.compute()));
 
             let setup_script_data = Arc::new(script_data);
-
-            let run_tests_fut = futures::stream::iter(self.test_list.iter_tests())
-                .map(move |test_instance: TestInstance<'a>| {
-                    let query = test_instance.to_test_query();
-                    let settings = self.profile.settings_for(&query);
-                    let threads_required = settings.threads_required().compute(self.test_threads);
-                    let test_group = match settings.test_group() {
-                        TestGroup::Global => None,
-                        TestGroup::Custom(name) => Some(name.clone()),
-                    };
-                    let resp_tx = resp_tx.clone();
-                    let setup_script_data = setup_script_data.clone();
-
-                    // Use a separate Tokio task for each test. For repos with
-                    // lots of small tests, this has been observed to be much
-                    // faster than using a single task for all tests (what we
-                    // used to do). It also provides some degree of per-test
-                    // isolation.
-                    let fut = async move {
-                        // SAFETY: Within an outer scope_and_block (which we
-                        // have here), scope_and_collect is safe as long as the
-                        // returned future isn't forgotten. We're not forgetting
-                        // it below -- we're running it to completion
-                        // immediately.
-                        //
-                        // But recursive scoped calls really feel like pushing
-                        // against the limits of async-scoped. For example,
-                        // there's no way built into async-scoped to propagate a
-                        // cancellation signal from the outer scope to the inner
-                        // scope. (But there could be, right? That seems
-                        // solvable via channels. And we could likely do our own
-                        // channels here.)
-                        let ((), mut ret) = unsafe {
-                            TokioScope::scope_and_collect(move |scope| {
-                                scope.spawn(executor_cx_ref.run_test_instance(
-                                    test_instance,
-                                    settings,
-                                    resp_tx.clone(),
-                                    setup_script_data,
-                                ))
-                            })
-                        }
-                        .await;
-
-                        // If no future was started, that's really strange.
-                        // Worth at least logging.
-                        let Some(result) = ret.pop() else {
-                            warn!(
-                                "no task was started for test instance: {}",
-                                test_instance.id()
-                            );
-                            return None;
+            let phase_tx = resp_tx.clone();
+            let teardown_tx = resp_tx.clone();
+
+            // Runs the given tests to completion, returning any join errors.
+            let run_tests = move |tests: Vec<TestInstance<'a>>| {
+                let resp_tx = resp_tx.clone();
+                let setup_script_data = setup_script_data.clone();
+                futures::stream::iter(tests)
+                    .map(move |test_instance: TestInstance<'a>| {
+                        let query = test_instance.to_test_query();
+                        let settings = self.profile.settings_for(&query);
+                        let threads_required =
+                            settings.threads_required().compute(self.test_threads);
+                        let test_group = match settings.test_group() {
+                            TestGroup::Global => None,
+                            TestGroup::Custom(name) => Some(name.clone()),
                         };
-                        match result {
-                            Ok(()) => None,
-                            Err(join_error) => Some(join_error),
-                        }
-                    };
-
-                    (threads_required, test_group, fut)
-                })
-                // future_queue_grouped means tests are spawned in the order
-                // defined, but returned in any order.
-                .future_queue_grouped(self.test_threads, groups)
-                // Drop the None values.
-                .filter_map(std::future::ready)
-                .collect::<Vec<_>>()
-                // Interestingly, using a more idiomatic `async move {
-                // run_tests_fut.await ... }` block causes Rust 1.83 to complain
-                // about a weird lifetime mismatch. FutureExt::map as used below
-                // does not.
-                .map(|child_join_errors| RunnerTaskState::Finished { child_join_errors });
+                        let resp_tx = resp_tx.clone();
+                        let setup_script_data = setup_script_data.clone();
+
+                        // Use a separate Tokio task for each test. For repos with
+                        // lots of small tests, this has been observed to be much
+                        // faster than using a single task for all tests (what we
+                        // used to do). It also provides some degree of per-test
+                        // isolation.
+                        let fut = async move {
+                            // SAFETY: Within an outer scope_and_block (which we
+                            // have here), scope_and_collect is safe as long as the
+                            // returned future isn't forgotten. We're not forgetting
+                            // it below -- we're running it to completion
+                            // immediately.
+                            //
+                            // But recursive scoped calls really feel like pushing
+                            // against the limits of async-scoped. For example,
+                            // there's no way built into async-scoped to propagate a
+                            // cancellation signal from the outer scope to the inner
+                            // scope. (But there could be, right? That seems
+                            // solvable via channels. And we could likely do our own
+                            // channels here.)
+                            let ((), mut ret) = unsafe {
+                                TokioScope::scope_and_collect(move |scope| {
+                                    scope.spawn(executor_cx_ref.run_test_instance(
+                                        test_instance,
+                                        settings,
+                                        resp_tx.clone(),
+                                        setup_script_data,
+                                    ))
+                                })
+                            }
+                            .await;
+
+                            // If no future was started, that's really strange.
+                            // Worth at least logging.
+                            let Some(result) = ret.pop() else {
+                                warn!(
+                                    "no task was started for test instance: {}",
+                                    test_instance.id()
+                                );
+                                return None;
+                            };
+                            match result {
+                                Ok(()) => None,
+                                Err(join_error) => Some(join_error),
+                            }
+                        };
+
+                        (threads_required, test_group, fut)
+                    })
+                    // future_queue_grouped means tests are spawned in the order
+                    // defined, but returned in any order.
+                    .future_queue_grouped(self.test_threads, groups.clone())
+                    // Drop the None values.
+                    .filter_map(std::future::ready)
+                    .collect::<Vec<_>>()
+            };
+
+            let run_tests_fut = match phases {
+                None => future::Either::Left(run_tests(queue)),
+                // Run the smoke tests first. If they fail and smoke tests gate the rest of the
+                // run, the dispatcher cancels the run when the smoke phase finishes, and the
+                // remaining tests aren't started.
+                //
+                // Sends over phase_tx failing means that the dispatcher has shut down. Carry on
+                // in that case: the tests will notice and shut down too.
+                //
+                // This uses combinators rather than an async block, since the latter causes Rust
+                // to complain about the closure in run_tests not being general enough.
+                Some(phases) => future::Either::Right({
+                    let _ = phase_tx.send(ExecutorEvent::PhaseStarted {
+                        phase: RunPhase::Smoke,
+                        run_count: phases.smoke_run_count,
+                    });
+                    run_tests(phases.smoke).then(move |mut child_join_errors| {
+                        let _ = phase_tx.send(ExecutorEvent::PhaseFinished {
+                            phase: RunPhase::Smoke,
+                        });
+                        let _ = phase_tx.send(ExecutorEvent::PhaseStarted {
+                            phase: RunPhase::Main,
+                            run_count: phases.main_run_count,
+                        });
+                        run_tests(phases.main).map(move |main_join_errors| {
+                            let _ = phase_tx.send(ExecutorEvent::PhaseFinished {
+                                phase: RunPhase::Main,
+                            });
+                            child_join_errors.extend(main_join_errors);
+                            child_join_errors
+                        })
+                    })
+                }),
+            }
+            // Once all tests have finished, run teardown scripts.
+            .then(move |child_join_errors| {
+                executor_cx_ref
+                    .run_teardown_scripts(teardown_tx)
+                    .map(move |()| child_join_errors)
+            })
+            .map(|child_join_errors| RunnerTaskState::Finished { child_join_errors });
 
             scope.spawn_cancellable(run_tests_fut, || RunnerTaskState::Cancelled);
         });
@@ -429,6 +851,51 @@ impl<'a> TestRunnerInner<'a> {
     }
 }
 
+/// Splits the tests into a smoke phase and a main phase, if smoke tests are configured and at
+/// least one smoke test is going to run.
+///
+/// This is shared with [`ExecutionPlan`], so that plans list tests in the order they're run in.
+pub(super) fn smoke_phases<'a>(
+    test_list: &'a TestList<'_>,
+    profile: &EvaluatableProfile<'_>,
+) -> Option<SmokePhases<'a>> {
+    let smoke = profile.smoke()?;
+    let ecx = profile.filterset_ecx();
+    let mut phases = SmokePhases::default();
+    for test_instance in test_list.iter_tests() {
+        let query = test_instance.to_test_query();
+        let is_match = test_instance.test_info.filter_match.is_match();
+        if smoke.expr().matches_test(&query, &ecx) {
+            phases.smoke.push(test_instance);
+            phases.smoke_run_count += usize::from(is_match);
+        } else {
+            phases.main.push(test_instance);
+            phases.main_run_count += usize::from(is_match);
+        }
+    }
+
+    if phases.smoke_run_count == 0 {
+        debug!("no smoke tests to run, running all tests in a single phase");
+        return None;
+    }
+    Some(phases)
+}
+
+/// Shuffles `tests` with an RNG seeded by `seed`.
+fn shuffle_tests(tests: &mut [TestInstance<'_>], seed: u64) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    tests.shuffle(&mut rng);
+}
+
+/// The tests in each phase of a staged run.
+#[derive(Debug, Default)]
+pub(super) struct SmokePhases<'a> {
+    pub(super) smoke: Vec<TestInstance<'a>>,
+    pub(super) smoke_run_count: usize,
+    pub(super) main: Vec<TestInstance<'a>>,
+    pub(super) main_run_count: usize,
+}
+
 /// Configures stdout, stdin and stderr inheritance by test processes on Windows.
 ///
 /// With Rust on Windows, these handles can be held open by tests (and therefore by grandchild processes)
@@ -480,4 +947,41 @@ mod tests {
         assert_eq!(runner.inner.capture_strategy, CaptureStrategy::None);
         assert_eq!(runner.inner.test_threads, 1, "tests run serially");
     }
+
+    #[test]
+    fn shuffle_seed() {
+        let test_list = TestList::empty();
+        let config = NextestConfig::default_config("/fake/dir");
+        let profile = config.profile(NextestConfig::DEFAULT_PROFILE).unwrap();
+        let build_platforms = BuildPlatforms::new_with_no_target().unwrap();
+        let profile = profile.apply_build_platforms(&build_platforms);
+
+        let build = |shuffle: Option<Shuffle>| {
+            let mut builder = TestRunnerBuilder::default();
+            if let Some(shuffle) = shuffle {
+                builder.set_shuffle(shuffle);
+            }
+            builder
+                .build(
+                    &test_list,
+                    &profile,
+                    vec![],
+                    SignalHandlerKind::Noop,
+                    InputHandlerKind::Noop,
+                    DoubleSpawnInfo::disabled(),
+                    TargetRunner::empty(),
+                )
+                .unwrap()
+        };
+
+        assert_eq!(build(None).inner.shuffle_seed, None);
+        assert_eq!(
+            build(Some(Shuffle::Seed(12345))).inner.shuffle_seed,
+            Some(12345)
+        );
+        assert!(
+            build(Some(Shuffle::Random)).inner.shuffle_seed.is_some(),
+            "a seed is chosen for random shuffles"
+        );
+    }
 }
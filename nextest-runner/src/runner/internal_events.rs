@@ -9,21 +9,24 @@
 
 use super::{SetupScriptPacket, TestPacket};
 use crate::{
-    config::{ScriptConfig, ScriptId},
+    config::{ScriptConfig, ScriptId, TerminateSignal},
     list::TestInstance,
     reporter::{
         events::{
-            ExecuteStatus, ExecutionResult, InfoResponse, RetryData, SetupScriptEnvMap,
-            SetupScriptExecuteStatus, UnitState,
+            ExecuteStatus, ExecutionResult, FailurePhase, FailureVerification, InfoResponse,
+            RetryData, RunPhase, RunStats, SetupScriptEnvMap, SetupScriptEnvOverride,
+            SetupScriptExecuteStatus, TestAttachments, TimeoutTermination, UnitState,
         },
         TestOutputDisplay,
     },
     signal::ShutdownEvent,
-    test_output::ChildExecutionOutput,
+    test_output::{ChildExecutionOutput, OutputMismatch},
     time::StopwatchSnapshot,
 };
+use chrono::{DateTime, FixedOffset};
 use nextest_metadata::MismatchReason;
-use std::time::Duration;
+use quick_junit::ReportUuid;
+use std::{collections::BTreeMap, time::Duration};
 use tokio::{
     sync::{
         mpsc::{UnboundedReceiver, UnboundedSender},
@@ -50,6 +53,7 @@ pub(super) enum ExecutorEvent<'a> {
     SetupScriptSlow {
         script_id: ScriptId,
         config: &'a ScriptConfig,
+        slow_timeout: Duration,
         elapsed: Duration,
         will_terminate: Option<Duration>,
     },
@@ -60,8 +64,50 @@ pub(super) enum ExecutorEvent<'a> {
         total: usize,
         status: SetupScriptExecuteStatus,
     },
+    SetupScriptEnvOverridden {
+        script_id: ScriptId,
+        overrides: Vec<SetupScriptEnvOverride>,
+    },
+    // Sent once all tests have finished, if there are teardown scripts to run. The dispatcher
+    // responds with the final run statistics, or drops the sender if teardown scripts shouldn't be
+    // run.
+    TeardownScriptsReady {
+        run_stats_tx: oneshot::Sender<RunStats>,
+    },
+    TeardownScriptStarted {
+        script_id: ScriptId,
+        config: &'a ScriptConfig,
+        index: usize,
+        total: usize,
+        // See the note in the `Started` variant.
+        req_rx_tx: oneshot::Sender<UnboundedReceiver<RunUnitRequest<'a>>>,
+    },
+    TeardownScriptSlow {
+        script_id: ScriptId,
+        config: &'a ScriptConfig,
+        slow_timeout: Duration,
+        elapsed: Duration,
+        will_terminate: Option<Duration>,
+    },
+    TeardownScriptFinished {
+        script_id: ScriptId,
+        config: &'a ScriptConfig,
+        index: usize,
+        total: usize,
+        status: SetupScriptExecuteStatus,
+    },
+    PhaseStarted {
+        phase: RunPhase,
+        run_count: usize,
+    },
+    // Sent once all the tests in a phase have finished.
+    PhaseFinished {
+        phase: RunPhase,
+    },
     Started {
         test_instance: TestInstance<'a>,
+        command_line: Vec<String>,
+        extra_env: BTreeMap<String, String>,
         // The channel over which to return the unit request.
         //
         // The callback context is solely responsible for coordinating the
@@ -77,28 +123,53 @@ pub(super) enum ExecutorEvent<'a> {
     Slow {
         test_instance: TestInstance<'a>,
         retry_data: RetryData,
+        slow_timeout: Duration,
         elapsed: Duration,
         will_terminate: Option<Duration>,
     },
+    OutputAfterExit {
+        test_instance: TestInstance<'a>,
+        retry_data: RetryData,
+        bytes: u64,
+    },
     AttemptFailedWillRetry {
         test_instance: TestInstance<'a>,
         failure_output: TestOutputDisplay,
         run_status: ExecuteStatus,
         delay_before_next_attempt: Duration,
     },
+    RetryDelayStarted {
+        test_instance: TestInstance<'a>,
+        retry_data: RetryData,
+        remaining: Duration,
+        resume_time: Option<DateTime<FixedOffset>>,
+    },
     RetryStarted {
         test_instance: TestInstance<'a>,
         retry_data: RetryData,
+        test_run_uuid: ReportUuid,
+        attempt_uuid: ReportUuid,
         // This is used to indicate that the dispatcher still wants to run the test.
         tx: oneshot::Sender<()>,
     },
+    OutputMismatch {
+        test_instance: TestInstance<'a>,
+        mismatch: OutputMismatch,
+        rerun_status: ExecuteStatus,
+    },
     Finished {
         test_instance: TestInstance<'a>,
         success_output: TestOutputDisplay,
         failure_output: TestOutputDisplay,
         junit_store_success_output: bool,
         junit_store_failure_output: bool,
+        owner: Option<&'a str>,
+        attachments: TestAttachments,
+        quarantined: bool,
         last_run_status: ExecuteStatus,
+        verification: Option<Box<FailureVerification>>,
+        // Some in repeat-until-failure mode.
+        passed_iterations: Option<usize>,
     },
     Skipped {
         test_instance: TestInstance<'a>,
@@ -141,18 +212,28 @@ pub(super) struct InternalExecuteStatus<'a> {
     pub(super) output: ChildExecutionOutput,
     pub(super) result: ExecutionResult,
     pub(super) stopwatch_end: StopwatchSnapshot,
+    pub(super) failure_phase: Option<FailurePhase>,
+    pub(super) peak_rss: Option<u64>,
+    pub(super) setup_time: Option<Duration>,
+    pub(super) io_wait: Option<Duration>,
 }
 
 impl InternalExecuteStatus<'_> {
     pub(super) fn into_external(self) -> ExecuteStatus {
         ExecuteStatus {
             retry_data: self.test.retry_data(),
+            test_run_uuid: self.test.test_run_uuid(),
+            attempt_uuid: self.test.attempt_uuid(),
             output: self.output,
             result: self.result,
             start_time: self.stopwatch_end.start_time.fixed_offset(),
             time_taken: self.stopwatch_end.active,
             is_slow: self.slow_after.is_some(),
             delay_before_start: self.test.delay_before_start(),
+            failure_phase: self.failure_phase,
+            peak_rss: self.peak_rss,
+            setup_time: self.setup_time,
+            io_wait: self.io_wait,
         }
     }
 }
@@ -193,9 +274,9 @@ impl<'a> RunUnitRequest<'a> {
     pub(super) fn drain(self, status: UnitExecuteStatus<'a, '_>) {
         match self {
             #[cfg(unix)]
-            Self::Signal(SignalRequest::Stop(sender)) => {
-                // The receiver being dead isn't really important.
-                let _ = sender.send(());
+            Self::Signal(SignalRequest::Stop(_sender)) => {
+                // The unit has exited, which the dispatcher learns about
+                // through the sender being dropped without a response.
             }
             #[cfg(unix)]
             Self::Signal(SignalRequest::Continue) => {}
@@ -211,14 +292,30 @@ impl<'a> RunUnitRequest<'a> {
 
 #[derive(Clone, Debug)]
 pub(super) enum SignalRequest {
-    // The mpsc sender is used by each test to indicate that the stop signal has been sent.
+    // The mpsc sender is used by each unit to indicate that the stop signal has been sent, and
+    // whether the unit was seen to stop.
     #[cfg(unix)]
-    Stop(UnboundedSender<()>),
+    Stop(UnboundedSender<UnitStopStatus>),
     #[cfg(unix)]
     Continue,
     Shutdown(ShutdownRequest),
 }
 
+/// Whether a unit's process was seen to stop after being sent SIGTSTP.
+#[cfg(unix)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(super) enum UnitStopStatus {
+    /// The process stopped, or the unit wasn't running a process.
+    Stopped,
+
+    /// The process was still running after being sent SIGTSTP, most likely because it handles or
+    /// ignores the signal.
+    NotStopped,
+
+    /// It wasn't possible to tell whether the process stopped, for example because it exited.
+    Unknown,
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub(super) enum ShutdownRequest {
     Once(ShutdownEvent),
@@ -232,7 +329,7 @@ pub(super) enum RunUnitQuery<'a> {
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub(super) enum InternalTerminateReason {
-    Timeout,
+    Timeout(TerminateSignal),
     Signal(ShutdownRequest),
 }
 
@@ -271,3 +368,13 @@ pub(super) enum TerminateChildResult {
     /// The child process was forcibly killed.
     Killed,
 }
+
+impl TerminateChildResult {
+    /// Returns how a unit that timed out was terminated.
+    pub(super) fn to_timeout_termination(self) -> TimeoutTermination {
+        match self {
+            TerminateChildResult::Exited => TimeoutTermination::Graceful,
+            TerminateChildResult::Killed => TimeoutTermination::Killed,
+        }
+    }
+}
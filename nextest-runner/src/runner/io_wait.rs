@@ -0,0 +1,91 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Tracking of the time test processes spend waiting for block I/O.
+//!
+//! On Linux, the kernel keeps a running total of block I/O delays for each process if delay
+//! accounting is enabled. This total is gone once the process has been reaped, so the tracker
+//! samples it periodically while the test is running, and keeps the last value seen. I/O done in
+//! the last few milliseconds before the process exits may be missed.
+
+use std::time::Duration;
+use tokio::time::{Interval, MissedTickBehavior};
+
+/// How often the I/O wait of a test process is sampled.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+#[derive(Debug)]
+pub(super) struct IoWaitTracker {
+    pid: u32,
+    interval: Interval,
+    io_wait: Option<Duration>,
+}
+
+impl IoWaitTracker {
+    /// Creates a new tracker and takes an initial sample, or returns `None` if I/O wait can't be
+    /// tracked on this platform.
+    pub(super) fn new(pid: u32) -> Option<Self> {
+        if !cfg!(target_os = "linux") {
+            return None;
+        }
+
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut tracker = Self {
+            pid,
+            interval,
+            io_wait: None,
+        };
+        // Sample right away, so that tests which exit before the first poll have a value.
+        tracker.sample();
+        Some(tracker)
+    }
+
+    /// Waits for the next poll interval, then samples the I/O wait of the test process.
+    ///
+    /// If `tracker` is `None`, this never resolves.
+    pub(super) async fn sample_next(tracker: Option<&mut Self>) {
+        match tracker {
+            Some(tracker) => {
+                tracker.interval.tick().await;
+                tracker.sample();
+            }
+            None => std::future::pending().await,
+        }
+    }
+
+    fn sample(&mut self) {
+        if let Some(io_wait) = super::os::io_wait(self.pid) {
+            self.update(io_wait);
+        }
+    }
+
+    fn update(&mut self, io_wait: Duration) {
+        // The total never decreases, but be defensive against a sample racing with process exit.
+        self.io_wait = Some(self.io_wait.map_or(io_wait, |last| last.max(io_wait)));
+    }
+
+    /// Returns the total time spent waiting for block I/O, or `None` if delay accounting isn't
+    /// available.
+    pub(super) fn finish(self) -> Option<Duration> {
+        self.io_wait
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_io_wait_update() {
+        // This PID doesn't exist, so sampling doesn't find anything.
+        let Some(mut tracker) = IoWaitTracker::new(u32::MAX) else {
+            return;
+        };
+        assert_eq!(tracker.io_wait, None);
+        tracker.update(Duration::from_millis(20));
+        tracker.update(Duration::from_millis(50));
+        tracker.update(Duration::from_millis(10));
+        assert_eq!(tracker.finish(), Some(Duration::from_millis(50)));
+    }
+}
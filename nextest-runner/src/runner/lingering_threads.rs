@@ -0,0 +1,213 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Detection of threads that keep running after a test has finished.
+//!
+//! Once a process exits, its threads are gone, so they can't be inspected after the fact. Instead,
+//! the tracker periodically lists the threads of the test process while it's running. A snapshot
+//! is taken to be "after the test finished" if either:
+//!
+//! * the main thread has exited, but other threads are keeping the process alive; or
+//! * the thread that libtest ran the test on (which is named after the test) has been seen, and
+//!   has since exited.
+//!
+//! Any other threads still running in the most recent such snapshot are reported as lingering.
+//! This is best-effort: a thread that's only around for the short time between the test finishing
+//! and the process exiting may not be seen.
+
+use crate::errors::LingeringThread;
+use std::time::Duration;
+use tokio::time::{Interval, MissedTickBehavior};
+
+/// How often the threads of a test process are listed.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// The maximum length of a thread name on Linux, not including the trailing nul byte.
+const MAX_THREAD_NAME_LEN: usize = 15;
+
+/// A thread in a test process, as listed by [`super::os::list_threads`].
+#[derive(Clone, Debug)]
+pub(super) struct ThreadSnapshot {
+    pub(super) tid: u32,
+    pub(super) name: String,
+    pub(super) exited: bool,
+}
+
+#[derive(Debug)]
+pub(super) struct LingeringThreadTracker {
+    pid: u32,
+    // The name of the thread libtest runs the test on, truncated the way the kernel does it.
+    test_thread_name: String,
+    seen_test_thread: bool,
+    interval: Interval,
+    lingering: Vec<LingeringThread>,
+}
+
+impl LingeringThreadTracker {
+    pub(super) fn new(pid: u32, test_name: &str) -> Self {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        Self {
+            pid,
+            test_thread_name: truncate_thread_name(test_name),
+            seen_test_thread: false,
+            interval,
+            lingering: Vec::new(),
+        }
+    }
+
+    /// Waits for the next poll interval, then lists the threads of the test process.
+    ///
+    /// If `tracker` is `None`, this never resolves.
+    pub(super) async fn sample_next(tracker: Option<&mut Self>) {
+        match tracker {
+            Some(tracker) => {
+                tracker.interval.tick().await;
+                if let Some(threads) = super::os::list_threads(tracker.pid) {
+                    tracker.update(&threads);
+                }
+            }
+            None => std::future::pending().await,
+        }
+    }
+
+    fn update(&mut self, threads: &[ThreadSnapshot]) {
+        // If no threads are running, the process is exiting, and there's nothing to learn.
+        if threads.iter().all(|thread| thread.exited) {
+            return;
+        }
+
+        let main_exited = threads
+            .iter()
+            .any(|thread| thread.tid == self.pid && thread.exited);
+        let test_thread_running = threads
+            .iter()
+            .any(|thread| !thread.exited && thread.name == self.test_thread_name);
+        self.seen_test_thread |= test_thread_running;
+
+        if main_exited || (self.seen_test_thread && !test_thread_running) {
+            self.lingering = threads
+                .iter()
+                .filter(|thread| thread.tid != self.pid && !thread.exited)
+                .map(|thread| LingeringThread {
+                    tid: thread.tid,
+                    name: thread.name.clone(),
+                })
+                .collect();
+            self.lingering.sort_unstable_by_key(|thread| thread.tid);
+        }
+    }
+
+    /// Returns the threads that were still running in the last snapshot taken after the test
+    /// finished.
+    pub(super) fn finish(self) -> Vec<LingeringThread> {
+        self.lingering
+    }
+}
+
+fn truncate_thread_name(name: &str) -> String {
+    let bytes = name.as_bytes();
+    let truncated = &bytes[..bytes.len().min(MAX_THREAD_NAME_LEN)];
+    String::from_utf8_lossy(truncated).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn thread(tid: u32, name: &str, exited: bool) -> ThreadSnapshot {
+        ThreadSnapshot {
+            tid,
+            name: name.to_owned(),
+            exited,
+        }
+    }
+
+    fn lingering(snapshots: &[&[ThreadSnapshot]]) -> Vec<(u32, String)> {
+        let mut tracker = LingeringThreadTracker::new(100, "tests::test_with_a_long_name");
+        for threads in snapshots {
+            tracker.update(threads);
+        }
+        tracker
+            .finish()
+            .into_iter()
+            .map(|thread| (thread.tid, thread.name))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_lingering_after_test_thread_exits() {
+        let main = thread(100, "basic-1234", false);
+        let test_thread = thread(101, "tests::test_wit", false);
+        let background = thread(102, "background", false);
+
+        // While the test thread is running, nothing is reported.
+        assert_eq!(
+            lingering(&[
+                std::slice::from_ref(&main),
+                &[main.clone(), test_thread.clone(), background.clone()]
+            ]),
+            vec![],
+        );
+
+        // The test thread has exited, but the background thread is still around.
+        assert_eq!(
+            lingering(&[
+                &[main.clone(), test_thread.clone(), background.clone()],
+                &[main.clone(), background.clone()],
+            ]),
+            vec![(102, "background".to_owned())],
+        );
+
+        // The process exiting doesn't clear earlier results.
+        assert_eq!(
+            lingering(&[
+                &[main.clone(), test_thread.clone()],
+                &[main.clone(), background.clone()],
+                &[thread(100, "basic-1234", true)],
+            ]),
+            vec![(102, "background".to_owned())],
+        );
+
+        // A thread that exits before the process does isn't lingering.
+        assert_eq!(
+            lingering(&[
+                &[main.clone(), test_thread.clone(), background.clone()],
+                &[main.clone(), background.clone()],
+                std::slice::from_ref(&main),
+            ]),
+            vec![],
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lingering_after_main_thread_exits() {
+        // Without libtest, there's no test thread: the main thread exiting is the only signal.
+        assert_eq!(
+            lingering(&[
+                &[thread(100, "custom", false), thread(103, "worker", false)],
+                &[thread(100, "custom", true), thread(103, "worker", false)],
+            ]),
+            vec![(103, "worker".to_owned())],
+        );
+        assert_eq!(
+            lingering(&[&[thread(100, "custom", false), thread(103, "worker", false)]]),
+            vec![],
+        );
+    }
+
+    #[test]
+    fn test_truncate_thread_name() {
+        assert_eq!(truncate_thread_name("short"), "short");
+        assert_eq!(
+            truncate_thread_name("tests::test_with_a_long_name"),
+            "tests::test_wit"
+        );
+        // Truncation in the middle of a multi-byte character is replaced.
+        assert_eq!(truncate_thread_name("tests::testé_ab"), "tests::testé_a");
+        assert_eq!(
+            truncate_thread_name("tests::test_abé"),
+            "tests::test_ab\u{FFFD}"
+        );
+    }
+}
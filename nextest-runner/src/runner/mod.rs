@@ -14,6 +14,10 @@ mod dispatcher;
 mod executor;
 mod imp;
 mod internal_events;
+mod io_wait;
+mod lingering_threads;
+mod peak_memory;
+mod plan;
 mod script_helpers;
 
 #[cfg(unix)]
@@ -28,4 +32,9 @@ use dispatcher::*;
 use executor::*;
 pub use imp::*;
 use internal_events::*;
+use io_wait::*;
+use lingering_threads::*;
+use peak_memory::*;
+pub use plan::*;
+pub use script_helpers::parse_env_file;
 use script_helpers::*;
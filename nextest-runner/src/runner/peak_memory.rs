@@ -0,0 +1,107 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Tracking of the peak memory usage of test processes.
+//!
+//! Once a process has exited and been reaped, its memory statistics are gone, so the tracker
+//! samples them periodically while the test is running, and keeps the largest value seen. On
+//! Linux, the kernel records the peak resident set size itself, so this is accurate unless the
+//! peak happens in the last few milliseconds before the process exits. On macOS, only the current
+//! resident set size is available, so short-lived peaks may be missed.
+
+use std::time::Duration;
+use tokio::time::{Interval, MissedTickBehavior};
+
+/// How often the memory usage of a test process is sampled.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+#[derive(Debug)]
+pub(super) struct PeakMemoryTracker {
+    pid: u32,
+    interval: Interval,
+    peak_rss: Option<u64>,
+}
+
+impl PeakMemoryTracker {
+    /// Creates a new tracker and takes an initial sample, or returns `None` if memory usage can't
+    /// be tracked on this platform.
+    pub(super) fn new(pid: u32) -> Option<Self> {
+        if !cfg!(any(target_os = "linux", target_os = "macos")) {
+            return None;
+        }
+
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut tracker = Self {
+            pid,
+            interval,
+            peak_rss: None,
+        };
+        // Sample right away, so that tests which exit before the first poll have a value.
+        tracker.sample();
+        Some(tracker)
+    }
+
+    /// Waits for the next poll interval, then samples the memory usage of the test process.
+    ///
+    /// If `tracker` is `None`, this never resolves.
+    pub(super) async fn sample_next(tracker: Option<&mut Self>) {
+        match tracker {
+            Some(tracker) => {
+                tracker.interval.tick().await;
+                tracker.sample();
+            }
+            None => std::future::pending().await,
+        }
+    }
+
+    fn sample(&mut self) {
+        if let Some(rss) = super::os::peak_rss(self.pid) {
+            self.update(rss);
+        }
+    }
+
+    fn update(&mut self, rss: u64) {
+        self.peak_rss = Some(self.peak_rss.map_or(rss, |peak| peak.max(rss)));
+    }
+
+    /// Returns the largest resident set size seen, in bytes.
+    pub(super) fn finish(self) -> Option<u64> {
+        self.peak_rss
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_peak_memory_update() {
+        // This PID doesn't exist, so sampling doesn't find anything.
+        let Some(mut tracker) = PeakMemoryTracker::new(u32::MAX) else {
+            return;
+        };
+        assert_eq!(tracker.peak_rss, None);
+        tracker.update(2048);
+        tracker.update(4096);
+        // A smaller sample after a larger one doesn't lower the peak.
+        tracker.update(1024);
+        assert_eq!(tracker.finish(), Some(4096));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_peak_memory_own_process() {
+        let mut tracker =
+            PeakMemoryTracker::new(std::process::id()).expect("tracking is supported on Linux");
+        let initial = tracker
+            .peak_rss
+            .expect("initial sample of own process is taken");
+        PeakMemoryTracker::sample_next(Some(&mut tracker)).await;
+        let peak_rss = tracker.finish().expect("own process has memory statistics");
+        assert!(
+            peak_rss >= initial && initial > 0,
+            "peak RSS {peak_rss} is positive and doesn't decrease from {initial}"
+        );
+    }
+}
@@ -0,0 +1,453 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Execution plans: what a test run would do, computed without running anything.
+
+use super::imp::smoke_phases;
+use crate::{
+    config::{EvaluatableProfile, RetryPolicy, ScriptId, SlowTimeout, TestGroup},
+    errors::WriteTestListError,
+    helpers::{plural, DisplayScriptInstance, DisplayTestInstance},
+    list::{Styles, TestInstanceId, TestList},
+    timings::TimingsStore,
+    write_str::WriteStr,
+};
+use nextest_metadata::RustBinaryId;
+use owo_colors::OwoColorize;
+use serde::Serialize;
+use std::{io, time::Duration};
+
+/// The plan for a test run: the tests that would be run in order, along with their settings, and
+/// the setup scripts that would be run before them.
+///
+/// Created by [`TestRunnerBuilder::plan`](super::TestRunnerBuilder::plan).
+#[derive(Clone, Debug)]
+pub struct ExecutionPlan<'a> {
+    profile_name: String,
+    test_threads: usize,
+    setup_scripts: Vec<PlannedSetupScript>,
+    tests: Vec<PlannedTest<'a>>,
+    skipped_count: usize,
+}
+
+/// A setup script in an [`ExecutionPlan`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct PlannedSetupScript {
+    /// The script ID.
+    pub id: ScriptId,
+
+    /// The program to run.
+    pub program: String,
+
+    /// The arguments to the program.
+    pub args: Vec<String>,
+}
+
+/// A test in an [`ExecutionPlan`], with its settings resolved against the profile.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct PlannedTest<'a> {
+    /// The binary ID.
+    pub binary_id: &'a RustBinaryId,
+
+    /// The name of the test.
+    pub test_name: &'a str,
+
+    /// The retry policy for the test.
+    pub retries: RetryPolicy,
+
+    /// The slow timeout for the test.
+    pub slow_timeout: SlowTimeout,
+
+    /// The leak timeout for the test.
+    pub leak_timeout: Duration,
+
+    /// The number of test threads the test takes up.
+    pub threads_required: usize,
+
+    /// The test group the test is in.
+    pub test_group: TestGroup,
+
+    /// How long the test is estimated to take, based on recorded timings.
+    pub estimated_duration: Option<Duration>,
+}
+
+impl PlannedTest<'_> {
+    /// Returns the ID of this test.
+    pub fn id(&self) -> TestInstanceId<'_> {
+        TestInstanceId {
+            binary_id: self.binary_id,
+            test_name: self.test_name,
+        }
+    }
+}
+
+impl<'a> ExecutionPlan<'a> {
+    pub(super) fn new(
+        test_list: &'a TestList<'_>,
+        profile: &EvaluatableProfile<'_>,
+        test_threads: usize,
+        force_retries: Option<RetryPolicy>,
+        timings: &TimingsStore,
+    ) -> Self {
+        let setup_scripts = profile
+            .setup_scripts(test_list)
+            .iter()
+            .map(|(id, config)| PlannedSetupScript {
+                id: id.clone(),
+                program: config.program().to_owned(),
+                args: config.args().to_vec(),
+            })
+            .collect();
+
+        // Tests are planned in the same order that the runner starts them in, with smoke tests
+        // first.
+        let ordered: Vec<_> = match smoke_phases(test_list, profile) {
+            Some(phases) => phases.smoke.into_iter().chain(phases.main).collect(),
+            None => test_list.iter_tests().collect(),
+        };
+
+        let mut tests = Vec::new();
+        let mut skipped_count = 0;
+        for test_instance in ordered {
+            if !test_instance.test_info.filter_match.is_match() {
+                skipped_count += 1;
+                continue;
+            }
+            let query = test_instance.to_test_query();
+            let settings = profile.settings_for(&query);
+            let binary_id = &test_instance.suite_info.binary_id;
+            tests.push(PlannedTest {
+                binary_id,
+                test_name: test_instance.name,
+                retries: force_retries.unwrap_or_else(|| settings.retries()),
+                slow_timeout: settings.slow_timeout(),
+                leak_timeout: settings.leak_timeout(),
+                threads_required: settings.threads_required().compute(test_threads),
+                test_group: settings.test_group().clone(),
+                estimated_duration: timings.estimated_duration(binary_id, test_instance.name),
+            });
+        }
+
+        Self {
+            profile_name: profile.name().to_owned(),
+            test_threads,
+            setup_scripts,
+            tests,
+            skipped_count,
+        }
+    }
+
+    /// Returns the setup scripts that would be run, in order.
+    pub fn setup_scripts(&self) -> &[PlannedSetupScript] {
+        &self.setup_scripts
+    }
+
+    /// Returns the tests that would be run, in the order they would be started in.
+    ///
+    /// If smoke tests are configured, they're listed first.
+    pub fn tests(&self) -> &[PlannedTest<'a>] {
+        &self.tests
+    }
+
+    /// Returns the number of tests that would be skipped.
+    pub fn skipped_count(&self) -> usize {
+        self.skipped_count
+    }
+
+    /// Returns the number of tests that have no recorded timings.
+    pub fn tests_without_timings(&self) -> usize {
+        self.tests
+            .iter()
+            .filter(|test| test.estimated_duration.is_none())
+            .count()
+    }
+
+    /// Returns a rough estimate of how long the tests would take to run, or `None` if no tests have
+    /// recorded timings.
+    ///
+    /// The estimate simulates starting each test, in order, as soon as enough test threads are free.
+    /// Tests without recorded timings, retries, setup scripts and test group limits aren't
+    /// accounted for.
+    pub fn estimated_duration(&self) -> Option<Duration> {
+        if self.tests.len() == self.tests_without_timings() {
+            return None;
+        }
+
+        // The time at which each test thread becomes free.
+        let mut free_at = vec![Duration::ZERO; self.test_threads.max(1)];
+        for test in &self.tests {
+            let Some(duration) = test.estimated_duration else {
+                continue;
+            };
+            let threads = test.threads_required.clamp(1, free_at.len());
+            free_at.sort_unstable();
+            let end = free_at[threads - 1] + duration;
+            free_at[..threads].fill(end);
+        }
+        free_at.into_iter().max()
+    }
+
+    /// Writes this plan out in a human-friendly format.
+    pub fn write_human(&self, writer: &mut dyn WriteStr, colorize: bool) -> io::Result<()> {
+        let mut styles = Styles::default();
+        if colorize {
+            styles.colorize();
+        }
+
+        writeln!(
+            writer,
+            "{}",
+            format!(
+                "Plan for profile {} ({} test {})",
+                self.profile_name,
+                self.test_threads,
+                plural::threads_str(self.test_threads),
+            )
+            .style(styles.heading),
+        )?;
+
+        if !self.setup_scripts.is_empty() {
+            writeln!(writer, "\n{}:", "setup scripts".style(styles.field))?;
+            for script in &self.setup_scripts {
+                let display = DisplayScriptInstance::new(
+                    script.id.clone(),
+                    &script.program,
+                    &script.args,
+                    styles.binary_id,
+                );
+                writeln!(writer, "    {display}")?;
+            }
+        }
+
+        writeln!(writer, "\n{}:", "tests".style(styles.field))?;
+        if self.tests.is_empty() {
+            writeln!(writer, "    (no tests)")?;
+        }
+        let width = self.tests.len().to_string().len();
+        for (index, test) in self.tests.iter().enumerate() {
+            write!(
+                writer,
+                "    {:>width$}. {} ({} {}, {} {:?}",
+                index + 1,
+                DisplayTestInstance::new(test.id(), &styles),
+                "retries:".style(styles.field),
+                test.retries.count(),
+                "slow-timeout:".style(styles.field),
+                test.slow_timeout.period,
+            )?;
+            if let Some(terminate_after) = test.slow_timeout.terminate_after {
+                write!(writer, " x {terminate_after}")?;
+            }
+            if test.threads_required != 1 {
+                write!(
+                    writer,
+                    ", {} {}",
+                    "threads:".style(styles.field),
+                    test.threads_required
+                )?;
+            }
+            if test.test_group != TestGroup::Global {
+                write!(
+                    writer,
+                    ", {} {}",
+                    "group:".style(styles.field),
+                    test.test_group
+                )?;
+            }
+            if let Some(duration) = test.estimated_duration {
+                write!(
+                    writer,
+                    ", {} {:.3}s",
+                    "est.".style(styles.field),
+                    duration.as_secs_f64()
+                )?;
+            }
+            writeln!(writer, ")")?;
+        }
+
+        writeln!(
+            writer,
+            "\n{} {} to run, {} skipped",
+            self.tests.len(),
+            plural::tests_str(self.tests.len()),
+            self.skipped_count,
+        )?;
+        match self.estimated_duration() {
+            Some(duration) => {
+                write!(
+                    writer,
+                    "{} {:.3}s",
+                    "estimated time:".style(styles.field),
+                    duration.as_secs_f64()
+                )?;
+                let without_timings = self.tests_without_timings();
+                if without_timings > 0 {
+                    write!(
+                        writer,
+                        " (not including {without_timings} {} without recorded timings)",
+                        plural::tests_str(without_timings),
+                    )?;
+                }
+                writeln!(writer)?;
+            }
+            None => {
+                writeln!(
+                    writer,
+                    "{} unknown (no recorded timings)",
+                    "estimated time:".style(styles.field),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes this plan out as JSON.
+    pub fn write_json(&self, writer: &mut dyn WriteStr) -> Result<(), WriteTestListError> {
+        let summary = ExecutionPlanSummary {
+            profile_name: &self.profile_name,
+            test_threads: self.test_threads,
+            setup_scripts: self
+                .setup_scripts
+                .iter()
+                .map(|script| PlannedSetupScriptSummary {
+                    id: script.id.to_string(),
+                    command: std::iter::once(script.program.as_str())
+                        .chain(script.args.iter().map(|arg| arg.as_str()))
+                        .collect(),
+                })
+                .collect(),
+            tests: self
+                .tests
+                .iter()
+                .map(|test| PlannedTestSummary {
+                    binary_id: test.binary_id,
+                    test_name: test.test_name,
+                    retries: test.retries.count(),
+                    slow_timeout_secs: test.slow_timeout.period.as_secs_f64(),
+                    terminate_after: test.slow_timeout.terminate_after.map(|n| n.get()),
+                    leak_timeout_secs: test.leak_timeout.as_secs_f64(),
+                    threads_required: test.threads_required,
+                    test_group: test.test_group.to_string(),
+                    estimated_duration_secs: test
+                        .estimated_duration
+                        .map(|duration| duration.as_secs_f64()),
+                })
+                .collect(),
+            skipped_count: self.skipped_count,
+            tests_without_timings: self.tests_without_timings(),
+            estimated_duration_secs: self
+                .estimated_duration()
+                .map(|duration| duration.as_secs_f64()),
+        };
+        let json = serde_json::to_string_pretty(&summary).map_err(WriteTestListError::Json)?;
+        writeln!(writer, "{json}").map_err(WriteTestListError::Io)
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct ExecutionPlanSummary<'a> {
+    profile_name: &'a str,
+    test_threads: usize,
+    setup_scripts: Vec<PlannedSetupScriptSummary<'a>>,
+    tests: Vec<PlannedTestSummary<'a>>,
+    skipped_count: usize,
+    tests_without_timings: usize,
+    estimated_duration_secs: Option<f64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct PlannedSetupScriptSummary<'a> {
+    id: String,
+    command: Vec<&'a str>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct PlannedTestSummary<'a> {
+    binary_id: &'a RustBinaryId,
+    test_name: &'a str,
+    retries: usize,
+    slow_timeout_secs: f64,
+    terminate_after: Option<usize>,
+    leak_timeout_secs: f64,
+    threads_required: usize,
+    test_group: String,
+    estimated_duration_secs: Option<f64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn planned_test(
+        binary_id: &RustBinaryId,
+        threads_required: usize,
+        estimated_ms: Option<u64>,
+    ) -> PlannedTest<'_> {
+        PlannedTest {
+            binary_id,
+            test_name: "test",
+            retries: RetryPolicy::default(),
+            slow_timeout: SlowTimeout::VERY_LARGE,
+            leak_timeout: Duration::from_millis(100),
+            threads_required,
+            test_group: TestGroup::Global,
+            estimated_duration: estimated_ms.map(Duration::from_millis),
+        }
+    }
+
+    fn plan(test_threads: usize, tests: Vec<PlannedTest<'_>>) -> ExecutionPlan<'_> {
+        ExecutionPlan {
+            profile_name: "default".to_owned(),
+            test_threads,
+            setup_scripts: Vec::new(),
+            tests,
+            skipped_count: 0,
+        }
+    }
+
+    #[test]
+    fn test_estimated_duration() {
+        let binary_id = RustBinaryId::new("my-crate");
+        let test = |threads_required, estimated_ms| {
+            planned_test(&binary_id, threads_required, estimated_ms)
+        };
+
+        // No recorded timings.
+        assert_eq!(plan(2, vec![test(1, None)]).estimated_duration(), None);
+        assert_eq!(plan(2, vec![]).estimated_duration(), None);
+
+        // Tests run in parallel, and tests without timings are ignored.
+        let estimate = plan(
+            2,
+            vec![test(1, Some(100)), test(1, Some(200)), test(1, None)],
+        )
+        .estimated_duration();
+        assert_eq!(estimate, Some(Duration::from_millis(200)));
+
+        // The third test starts as soon as the first one finishes.
+        let estimate = plan(
+            2,
+            vec![test(1, Some(100)), test(1, Some(300)), test(1, Some(100))],
+        )
+        .estimated_duration();
+        assert_eq!(estimate, Some(Duration::from_millis(300)));
+
+        // A test that takes up both threads waits for both of them to become free.
+        let estimate = plan(
+            2,
+            vec![test(1, Some(100)), test(1, Some(300)), test(2, Some(100))],
+        )
+        .estimated_duration();
+        assert_eq!(estimate, Some(Duration::from_millis(400)));
+
+        // Tests requiring more threads than are available take up all of them.
+        let estimate = plan(2, vec![test(4, Some(100)), test(1, Some(100))]).estimated_duration();
+        assert_eq!(estimate, Some(Duration::from_millis(200)));
+    }
+}
@@ -1,55 +1,391 @@
 // Copyright (c) The nextest Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use crate::{errors::SetupScriptOutputError, reporter::events::SetupScriptEnvMap};
+use crate::{
+    config::ScriptId,
+    errors::{EnvFileError, SetupScriptOutputError},
+    reporter::events::SetupScriptEnvMap,
+};
 use camino::Utf8Path;
+use serde_json::Value;
 use std::{collections::BTreeMap, sync::Arc};
-use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::io::AsyncReadExt;
+
+/// Parses an environment file, such as one passed in with `--env-file`.
+///
+/// Each line is either of the form `KEY=VALUE`, or starts a multi-line value with `KEY<<DELIM`.
+/// In the latter case, the following lines up to a line consisting of exactly `DELIM` form the
+/// value, joined by newlines. Keys starting with `NEXTEST` are reserved, and produce an error.
+///
+/// This is the same format that setup scripts write their environment files in.
+pub fn parse_env_file(env_path: &Utf8Path) -> Result<BTreeMap<String, String>, EnvFileError> {
+    let contents = std::fs::read_to_string(env_path).map_err(|error| EnvFileError::Read {
+        path: env_path.to_owned(),
+        error: Arc::new(error),
+    })?;
+    parse_env_contents(env_path, &contents)
+}
 
 /// Parses an environment file generated by a setup script.
-pub(super) async fn parse_env_file(
+pub(super) async fn parse_setup_script_env_file(
     env_path: &Utf8Path,
 ) -> Result<SetupScriptEnvMap, SetupScriptOutputError> {
-    let mut env_map = BTreeMap::new();
-    let f = tokio::fs::File::open(env_path).await.map_err(|error| {
+    let mut f = tokio::fs::File::open(env_path).await.map_err(|error| {
         SetupScriptOutputError::EnvFileOpen {
             path: env_path.to_owned(),
             error: Arc::new(error),
         }
     })?;
-    let reader = BufReader::new(f);
-    let mut lines = reader.lines();
-    loop {
-        let line =
-            lines
-                .next_line()
-                .await
-                .map_err(|error| SetupScriptOutputError::EnvFileRead {
-                    path: env_path.to_owned(),
-                    error: Arc::new(error),
-                })?;
-        let Some(line) = line else { break };
+    let mut contents = String::new();
+    f.read_to_string(&mut contents)
+        .await
+        .map_err(|error| SetupScriptOutputError::EnvFileRead {
+            path: env_path.to_owned(),
+            error: Arc::new(error),
+        })?;
+    let env_map = parse_env_contents(env_path, &contents)?;
+    Ok(SetupScriptEnvMap { env_map })
+}
 
-        // Split this line into key and value.
-        let (key, value) = match line.split_once('=') {
-            Some((key, value)) => (key, value),
+fn parse_env_contents(
+    env_path: &Utf8Path,
+    contents: &str,
+) -> Result<BTreeMap<String, String>, EnvFileError> {
+    let mut env_map = BTreeMap::new();
+    let mut lines = contents.lines();
+    while let Some(line) = lines.next() {
+        let (key, value) = match split_env_line(line) {
+            Some(EnvLine::Value { key, value }) => (key, value.to_owned()),
+            Some(EnvLine::MultiLine { key, delimiter }) => {
+                // Accumulate lines until the delimiter is seen.
+                let mut value_lines = Vec::new();
+                loop {
+                    match lines.next() {
+                        Some(next) if next == delimiter => break,
+                        Some(next) => value_lines.push(next),
+                        None => {
+                            return Err(EnvFileError::UnterminatedValue {
+                                path: env_path.to_owned(),
+                                key: key.to_owned(),
+                                delimiter: delimiter.to_owned(),
+                            })
+                        }
+                    }
+                }
+                (key, value_lines.join("\n"))
+            }
             None => {
-                return Err(SetupScriptOutputError::EnvFileParse {
+                return Err(EnvFileError::Parse {
                     path: env_path.to_owned(),
                     line: line.to_owned(),
                 })
             }
         };
 
-        // Ban keys starting with `NEXTEST`.
-        if key.starts_with("NEXTEST") {
-            return Err(SetupScriptOutputError::EnvFileReservedKey {
+        if is_reserved_env_key(key) {
+            return Err(EnvFileError::ReservedKey {
                 key: key.to_owned(),
             });
         }
 
-        env_map.insert(key.to_owned(), value.to_owned());
+        env_map.insert(key.to_owned(), value);
     }
 
-    Ok(SetupScriptEnvMap { env_map })
+    Ok(env_map)
+}
+
+enum EnvLine<'a> {
+    Value { key: &'a str, value: &'a str },
+    MultiLine { key: &'a str, delimiter: &'a str },
+}
+
+/// Splits a line in an environment file into its key and either a value or a heredoc delimiter.
+fn split_env_line(line: &str) -> Option<EnvLine<'_>> {
+    // Whichever of `=` and `<<` comes first determines the form, so that `KEY=a<<b` is an ordinary
+    // value.
+    match (line.find('='), line.find("<<")) {
+        (Some(eq), Some(heredoc)) if heredoc < eq => split_heredoc(line, heredoc),
+        (Some(eq), _) => Some(EnvLine::Value {
+            key: &line[..eq],
+            value: &line[eq + 1..],
+        }),
+        (None, Some(heredoc)) => split_heredoc(line, heredoc),
+        (None, None) => None,
+    }
+}
+
+fn split_heredoc(line: &str, index: usize) -> Option<EnvLine<'_>> {
+    let delimiter = &line[index + 2..];
+    // An empty delimiter would end the value at the first blank line, which is almost certainly not
+    // what was intended.
+    (!delimiter.is_empty()).then(|| EnvLine::MultiLine {
+        key: &line[..index],
+        delimiter,
+    })
+}
+
+/// Parses a JSON output file generated by a setup script.
+///
+/// The file must either be empty or contain a JSON object. Nested objects are flattened, with keys
+/// joined by dots: `{"db": {"port": 5432}}` produces the key `db.port`. The returned map preserves
+/// the JSON values.
+pub(super) async fn parse_json_output_file(
+    output_path: &Utf8Path,
+) -> Result<BTreeMap<String, Value>, SetupScriptOutputError> {
+    let contents = tokio::fs::read(output_path).await.map_err(|error| {
+        SetupScriptOutputError::JsonOutputFileRead {
+            path: output_path.to_owned(),
+            error: Arc::new(error),
+        }
+    })?;
+    parse_json_outputs(output_path, &contents)
+}
+
+fn parse_json_outputs(
+    output_path: &Utf8Path,
+    contents: &[u8],
+) -> Result<BTreeMap<String, Value>, SetupScriptOutputError> {
+    let mut outputs = BTreeMap::new();
+    // An empty file means that the script didn't write any outputs.
+    if contents.iter().all(|b| b.is_ascii_whitespace()) {
+        return Ok(outputs);
+    }
+
+    let value: Value = serde_json::from_slice(contents).map_err(|error| {
+        SetupScriptOutputError::JsonOutputFileParse {
+            path: output_path.to_owned(),
+            error: Arc::new(error),
+        }
+    })?;
+    let Value::Object(map) = value else {
+        return Err(SetupScriptOutputError::JsonOutputFileNotObject {
+            path: output_path.to_owned(),
+        });
+    };
+
+    for (key, value) in map {
+        // Apply the same reservation rules as the environment file, so that the two mechanisms
+        // can be used interchangeably.
+        if is_reserved_env_key(&key) {
+            return Err(SetupScriptOutputError::EnvFileReservedKey { key });
+        }
+        flatten_json_output(key, value, &mut outputs)?;
+    }
+
+    Ok(outputs)
+}
+
+fn flatten_json_output(
+    key: String,
+    value: Value,
+    outputs: &mut BTreeMap<String, Value>,
+) -> Result<(), SetupScriptOutputError> {
+    if key.is_empty() || key.contains(['=', '\0']) {
+        return Err(SetupScriptOutputError::JsonOutputInvalidKey { key });
+    }
+
+    match value {
+        Value::Object(map) => {
+            for (child_key, child_value) in map {
+                flatten_json_output(format!("{key}.{child_key}"), child_value, outputs)?;
+            }
+        }
+        value => {
+            outputs.insert(key, value);
+        }
+    }
+    Ok(())
+}
+
+/// Adds the outputs produced by a setup script to its environment map.
+///
+/// Each output is exposed as `NEXTEST_SETUP_<script>_<key>`. Strings are passed through as-is,
+/// `null` becomes the empty string, and other values are written out as JSON.
+pub(super) fn apply_json_outputs(
+    script_id: &ScriptId,
+    outputs: BTreeMap<String, Value>,
+    env_map: &mut SetupScriptEnvMap,
+) {
+    for (key, value) in outputs {
+        let value = match value {
+            Value::String(s) => s,
+            Value::Null => String::new(),
+            other => other.to_string(),
+        };
+        env_map
+            .env_map
+            .insert(format!("NEXTEST_SETUP_{script_id}_{key}"), value);
+    }
+}
+
+/// Returns true if `key` is reserved for nextest's own use, and so can't be set by setup scripts.
+///
+/// Keys starting with `NEXTEST` are reserved.
+pub(super) fn is_reserved_env_key(key: &str) -> bool {
+    key.starts_with("NEXTEST")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use camino_tempfile::NamedUtf8TempFile;
+    use serde_json::json;
+    use std::io::Write;
+
+    async fn parse_env_str(
+        contents: &str,
+    ) -> Result<BTreeMap<String, String>, SetupScriptOutputError> {
+        let mut f = NamedUtf8TempFile::new().unwrap();
+        f.write_all(contents.as_bytes()).unwrap();
+        parse_setup_script_env_file(f.path())
+            .await
+            .map(|env_map| env_map.env_map)
+    }
+
+    #[tokio::test]
+    async fn env_file_multi_line() {
+        let env_map = parse_env_str(
+            "FOO=bar\nCERT<<EOF\n-----BEGIN-----\n\nabc=def\n-----END-----\nEOF\nEMPTY<<END\nEND\nBAZ=a<<b\n",
+        )
+        .await
+        .expect("env file is valid");
+        assert_eq!(
+            env_map,
+            BTreeMap::from([
+                ("BAZ".to_owned(), "a<<b".to_owned()),
+                (
+                    "CERT".to_owned(),
+                    "-----BEGIN-----\n\nabc=def\n-----END-----".to_owned()
+                ),
+                ("EMPTY".to_owned(), String::new()),
+                ("FOO".to_owned(), "bar".to_owned()),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn env_file_invalid() {
+        let cases = [
+            ("FOO\n", "line `FOO` in environment file"),
+            ("FOO<<\nbar\n", "line `FOO<<` in environment file"),
+            (
+                "FOO<<EOF\nbar\nEOF \n",
+                "multi-line value for key `FOO` in environment file",
+            ),
+            (
+                "NEXTEST_FOO<<EOF\nbar\nEOF\n",
+                "key `NEXTEST_FOO` begins with `NEXTEST`",
+            ),
+        ];
+        for (contents, expected) in cases {
+            let error = parse_env_str(contents)
+                .await
+                .expect_err("env file is invalid");
+            assert!(
+                error.to_string().starts_with(expected),
+                "for {contents:?}, {error} starts with {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn env_file_public() {
+        let mut f = NamedUtf8TempFile::new().unwrap();
+        f.write_all(b"FOO=bar\r\nEMPTY=\n").unwrap();
+        assert_eq!(
+            parse_env_file(f.path()).expect("env file is valid"),
+            BTreeMap::from([
+                ("EMPTY".to_owned(), String::new()),
+                ("FOO".to_owned(), "bar".to_owned()),
+            ])
+        );
+
+        let error =
+            parse_env_file(Utf8Path::new("/nonexistent/.env")).expect_err("env file doesn't exist");
+        assert_eq!(
+            error.to_string(),
+            "error reading environment file `/nonexistent/.env`"
+        );
+    }
+
+    #[test]
+    fn json_outputs_flattened() {
+        let path = Utf8Path::new("outputs.json");
+        let outputs = parse_json_outputs(
+            path,
+            br#"{"port": 5432, "db": {"host": "localhost", "tls": {"enabled": true}}, "token": null, "hosts": ["a", "b"]}"#,
+        )
+        .expect("outputs are valid");
+        assert_eq!(
+            outputs,
+            BTreeMap::from([
+                ("db.host".to_owned(), json!("localhost")),
+                ("db.tls.enabled".to_owned(), json!(true)),
+                ("hosts".to_owned(), json!(["a", "b"])),
+                ("port".to_owned(), json!(5432)),
+                ("token".to_owned(), Value::Null),
+            ])
+        );
+
+        let script_id = ScriptId::new("my-script".into()).unwrap();
+        let mut env_map = SetupScriptEnvMap {
+            env_map: BTreeMap::new(),
+        };
+        apply_json_outputs(&script_id, outputs, &mut env_map);
+        assert_eq!(
+            env_map.env_map,
+            BTreeMap::from([
+                (
+                    "NEXTEST_SETUP_my-script_db.host".to_owned(),
+                    "localhost".to_owned()
+                ),
+                (
+                    "NEXTEST_SETUP_my-script_db.tls.enabled".to_owned(),
+                    "true".to_owned()
+                ),
+                (
+                    "NEXTEST_SETUP_my-script_hosts".to_owned(),
+                    r#"["a","b"]"#.to_owned()
+                ),
+                ("NEXTEST_SETUP_my-script_port".to_owned(), "5432".to_owned()),
+                ("NEXTEST_SETUP_my-script_token".to_owned(), String::new()),
+            ])
+        );
+    }
+
+    #[test]
+    fn json_outputs_empty() {
+        let path = Utf8Path::new("outputs.json");
+        assert!(parse_json_outputs(path, b"").unwrap().is_empty());
+        assert!(parse_json_outputs(path, b" \n").unwrap().is_empty());
+        assert!(parse_json_outputs(path, b"{}").unwrap().is_empty());
+    }
+
+    #[test]
+    fn json_outputs_invalid() {
+        let path = Utf8Path::new("outputs.json");
+        let cases: [(&[u8], &str); 5] = [
+            (b"{", "error parsing JSON output file `outputs.json`"),
+            (
+                b"[1, 2]",
+                "JSON output file `outputs.json` does not contain an object",
+            ),
+            (
+                br#"{"NEXTEST_FOO": 1}"#,
+                "key `NEXTEST_FOO` begins with `NEXTEST`, which is reserved for internal use",
+            ),
+            (
+                br#"{"": 1}"#,
+                "JSON output key `` is empty or contains `=` or NUL",
+            ),
+            (
+                br#"{"a": {"b=c": 1}}"#,
+                "JSON output key `a.b=c` is empty or contains `=` or NUL",
+            ),
+        ];
+        for (contents, expected) in cases {
+            let error = parse_json_outputs(path, contents).expect_err("outputs are invalid");
+            assert_eq!(error.to_string(), expected);
+        }
+    }
 }
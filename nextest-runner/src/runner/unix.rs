@@ -1,12 +1,16 @@
 // Copyright (c) The nextest Contributors
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use super::{InternalTerminateReason, ShutdownRequest, TerminateChildResult, UnitContext};
+use super::{
+    InternalTerminateReason, ShutdownRequest, TerminateChildResult, ThreadSnapshot, UnitContext,
+    UnitStopStatus,
+};
 use crate::{
-    errors::ConfigureHandleInheritanceError,
+    config::{CpuAffinity, TerminateSignal},
+    errors::{ConfigureHandleInheritanceError, CpuAffinityError, OrphanProcess},
     reporter::events::{
-        UnitState, UnitTerminateMethod, UnitTerminateReason, UnitTerminateSignal,
-        UnitTerminatingState,
+        AbortStatus, LeakDetails, UnitState, UnitTerminateMethod, UnitTerminateReason,
+        UnitTerminateSignal, UnitTerminatingState,
     },
     runner::{RunUnitQuery, RunUnitRequest, SignalRequest},
     signal::{JobControlEvent, ShutdownEvent},
@@ -31,6 +35,298 @@ pub(super) fn set_process_group(cmd: &mut std::process::Command) {
     cmd.process_group(0);
 }
 
+/// Pins the child process to the given CPUs.
+///
+/// The requested CPUs are validated against the CPUs available to nextest before the child is
+/// spawned.
+#[cfg(target_os = "linux")]
+pub(super) fn set_cpu_affinity(
+    cmd: &mut std::process::Command,
+    affinity: &CpuAffinity,
+) -> Result<(), CpuAffinityError> {
+    use std::{io, mem, sync::Arc};
+
+    const CPU_SET_SIZE: usize = mem::size_of::<libc::cpu_set_t>();
+    let max_cpus = libc::CPU_SETSIZE as usize;
+
+    // SAFETY: cpu_set_t is a plain bitmask, for which all zeroes is a valid (empty) set.
+    let mut available: libc::cpu_set_t = unsafe { mem::zeroed() };
+    if unsafe { libc::sched_getaffinity(0, CPU_SET_SIZE, &mut available) } != 0 {
+        return Err(CpuAffinityError::Query(
+            Arc::new(io::Error::last_os_error()),
+        ));
+    }
+    // SAFETY: CPU_ISSET is only called with indexes within the set.
+    let is_available = |cpu: usize| cpu < max_cpus && unsafe { libc::CPU_ISSET(cpu, &available) };
+
+    if let Some(unavailable) = CpuAffinity::new(affinity.cpus().filter(|cpu| !is_available(*cpu))) {
+        return Err(CpuAffinityError::Unavailable {
+            requested: affinity.clone(),
+            unavailable,
+            available: CpuAffinity::new((0..max_cpus).filter(|cpu| is_available(*cpu)))
+                .expect("nextest is running, so at least one CPU is available to it"),
+        });
+    }
+
+    // SAFETY: see above. All CPUs were checked to be within the set.
+    let mut cpu_set: libc::cpu_set_t = unsafe { mem::zeroed() };
+    for cpu in affinity.cpus() {
+        unsafe { libc::CPU_SET(cpu, &mut cpu_set) };
+    }
+
+    // SAFETY: sched_setaffinity is a thin wrapper around a system call, so it's async-signal-safe.
+    unsafe {
+        cmd.pre_exec(move || {
+            if libc::sched_setaffinity(0, CPU_SET_SIZE, &cpu_set) == 0 {
+                Ok(())
+            } else {
+                Err(io::Error::last_os_error())
+            }
+        });
+    }
+
+    Ok(())
+}
+
+/// CPU affinity is only supported on Linux: this is a no-op on other Unix platforms.
+#[cfg(not(target_os = "linux"))]
+pub(super) fn set_cpu_affinity(
+    _cmd: &mut std::process::Command,
+    _affinity: &CpuAffinity,
+) -> Result<(), CpuAffinityError> {
+    Ok(())
+}
+
+/// Returns the processes that are still in the process group of a test that has exited,
+/// killing them if `kill` is true.
+///
+/// Tests are spawned as the leaders of their own process groups, and any processes they fork
+/// inherit the process group unless they explicitly leave it (for example, by calling `setsid`).
+/// Since the kernel doesn't reuse a PID while it's still in use as a process group ID, the test's
+/// PID continues to identify its process group after the test has been reaped.
+///
+/// Processes are found by scanning `/proc`, so this is only supported on Linux.
+#[cfg(target_os = "linux")]
+pub(super) fn find_orphan_processes(pgid: u32, kill: bool) -> Vec<OrphanProcess> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+
+    let mut processes: Vec<_> = entries
+        .filter_map(|entry| {
+            let pid: u32 = entry.ok()?.file_name().to_str()?.parse().ok()?;
+            // The process may have exited since the directory was read: ignore it if so.
+            let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+            let (command, state, process_pgid) = parse_proc_stat(&stat)?;
+            // Zombies have already exited, and will be reaped by their new parent.
+            (process_pgid == pgid && state != "Z").then(|| OrphanProcess {
+                pid,
+                command: command.to_owned(),
+            })
+        })
+        .collect();
+    processes.sort_unstable_by_key(|process| process.pid);
+
+    if kill && !processes.is_empty() {
+        unsafe {
+            // Send SIGKILL to the entire process group.
+            libc::kill(-(pgid as i32), SIGKILL);
+        }
+    }
+
+    processes
+}
+
+/// Counts the processes still running in the given process group, along with the file
+/// descriptors they hold open.
+///
+/// Like [`find_orphan_processes`], this relies on the test's PID continuing to identify its
+/// process group after the test has been reaped. Returns `None` if `/proc` couldn't be read.
+#[cfg(target_os = "linux")]
+pub(super) fn leak_details(pgid: u32) -> Option<LeakDetails> {
+    let entries = std::fs::read_dir("/proc").ok()?;
+
+    let mut details = LeakDetails {
+        processes: 0,
+        open_fds: 0,
+    };
+    for entry in entries {
+        let Some(pid) = entry
+            .ok()
+            .and_then(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        // The process may have exited since the directory was read: ignore it if so.
+        let Ok(stat) = std::fs::read_to_string(format!("/proc/{pid}/stat")) else {
+            continue;
+        };
+        match parse_proc_stat(&stat) {
+            Some((_, state, process_pgid)) if process_pgid == pgid && state != "Z" => {
+                details.processes += 1;
+                if let Ok(fds) = std::fs::read_dir(format!("/proc/{pid}/fd")) {
+                    details.open_fds += fds.count();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Some(details)
+}
+
+/// Lists the threads of a running process, or returns `None` if they couldn't be listed (for
+/// example, because the process has already been reaped).
+///
+/// Threads are found by scanning `/proc/<pid>/task`, so this is only supported on Linux.
+#[cfg(target_os = "linux")]
+pub(super) fn list_threads(pid: u32) -> Option<Vec<ThreadSnapshot>> {
+    let entries = std::fs::read_dir(format!("/proc/{pid}/task")).ok()?;
+    let threads = entries
+        .filter_map(|entry| {
+            let tid: u32 = entry.ok()?.file_name().to_str()?.parse().ok()?;
+            // The thread may have exited since the directory was read: ignore it if so. Thread
+            // names are truncated to a fixed number of bytes, so they may not be valid UTF-8.
+            let stat = std::fs::read(format!("/proc/{pid}/task/{tid}/stat")).ok()?;
+            let stat = String::from_utf8_lossy(&stat);
+            let (name, state, _) = parse_proc_stat(&stat)?;
+            Some(ThreadSnapshot {
+                tid,
+                name: name.to_owned(),
+                exited: state == "Z" || state == "X",
+            })
+        })
+        .collect();
+    Some(threads)
+}
+
+/// Parses the command, state and process group ID out of the contents of `/proc/<pid>/stat`.
+///
+/// The format is `pid (comm) state ppid pgrp ...`, where `comm` may itself contain spaces and
+/// parentheses.
+#[cfg(target_os = "linux")]
+fn parse_proc_stat(stat: &str) -> Option<(&str, &str, u32)> {
+    let (command, rest) = stat.split_once(" (")?.1.rsplit_once(") ")?;
+    let mut fields = rest.split_ascii_whitespace();
+    let state = fields.next()?;
+    let pgid = fields.nth(1)?.parse().ok()?;
+    Some((command, state, pgid))
+}
+
+/// Returns the peak resident set size of the given process in bytes, or `None` if it can't be
+/// determined.
+///
+/// This is the `VmHWM` ("high water mark") line of `/proc/<pid>/status`.
+#[cfg(target_os = "linux")]
+pub(super) fn peak_rss(pid: u32) -> Option<u64> {
+    let status = std::fs::read_to_string(format!("/proc/{pid}/status")).ok()?;
+    parse_vm_hwm(&status)
+}
+
+/// Parses the `VmHWM` line out of the contents of `/proc/<pid>/status`, returning it in bytes.
+///
+/// The line isn't present for processes that have exited but haven't been reaped yet.
+#[cfg(target_os = "linux")]
+fn parse_vm_hwm(status: &str) -> Option<u64> {
+    let line = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))?;
+    let kib: u64 = line.trim().strip_suffix(" kB")?.trim().parse().ok()?;
+    kib.checked_mul(1024)
+}
+
+/// Returns the current resident set size of the given process in bytes, or `None` if it can't be
+/// determined.
+///
+/// macOS doesn't track the peak resident set size of other processes, so callers are expected to
+/// take the maximum over several samples.
+#[cfg(target_os = "macos")]
+pub(super) fn peak_rss(pid: u32) -> Option<u64> {
+    let mut info = std::mem::MaybeUninit::<libc::proc_taskinfo>::uninit();
+    let size = std::mem::size_of::<libc::proc_taskinfo>() as libc::c_int;
+    // SAFETY: the buffer is valid for writes of `size` bytes, and proc_pidinfo returns the number
+    // of bytes written to it.
+    let written = unsafe {
+        libc::proc_pidinfo(
+            pid as libc::c_int,
+            libc::PROC_PIDTASKINFO,
+            0,
+            info.as_mut_ptr().cast(),
+            size,
+        )
+    };
+    if written != size {
+        return None;
+    }
+    // SAFETY: proc_pidinfo filled in the whole struct.
+    let info = unsafe { info.assume_init() };
+    Some(info.pti_resident_size)
+}
+
+/// Memory usage tracking is only supported on Linux and macOS: this is a no-op on other Unix
+/// platforms.
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub(super) fn peak_rss(_pid: u32) -> Option<u64> {
+    None
+}
+
+/// Returns the total time the given process has spent waiting for block I/O, or `None` if it can't
+/// be determined.
+///
+/// This is the `delayacct_blkio_ticks` field of `/proc/<pid>/stat`, which is only tracked if delay
+/// accounting is enabled. Since Linux 5.14, delay accounting is disabled unless the
+/// `kernel.task_delayacct` sysctl or the `delayacct` boot option is set.
+#[cfg(target_os = "linux")]
+pub(super) fn io_wait(pid: u32) -> Option<Duration> {
+    // Older kernels don't have this sysctl, and always track delays if they were built to.
+    if std::fs::read_to_string("/proc/sys/kernel/task_delayacct")
+        .is_ok_and(|enabled| enabled.trim() == "0")
+    {
+        return None;
+    }
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    let ticks = parse_delayacct_blkio_ticks(&stat)?;
+    // SAFETY: sysconf is always safe to call.
+    let ticks_per_sec = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    let ticks_per_sec = u64::try_from(ticks_per_sec).ok().filter(|&n| n > 0)?;
+    Some(Duration::from_nanos(
+        ticks.checked_mul(1_000_000_000)? / ticks_per_sec,
+    ))
+}
+
+/// Parses the `delayacct_blkio_ticks` field (the 42nd field) out of the contents of
+/// `/proc/<pid>/stat`.
+#[cfg(target_os = "linux")]
+fn parse_delayacct_blkio_ticks(stat: &str) -> Option<u64> {
+    // Fields are counted from after the command, which is the second field.
+    let (_, rest) = stat.rsplit_once(") ")?;
+    rest.split_ascii_whitespace().nth(42 - 3)?.parse().ok()
+}
+
+/// I/O wait tracking is only supported on Linux: this is a no-op on other Unix platforms.
+#[cfg(not(target_os = "linux"))]
+pub(super) fn io_wait(_pid: u32) -> Option<Duration> {
+    None
+}
+
+/// Orphan process detection is only supported on Linux: this is a no-op on other Unix platforms.
+#[cfg(not(target_os = "linux"))]
+pub(super) fn find_orphan_processes(_pgid: u32, _kill: bool) -> Vec<OrphanProcess> {
+    Vec::new()
+}
+
+/// Leak details are only supported on Linux: this is a no-op on other Unix platforms.
+#[cfg(not(target_os = "linux"))]
+pub(super) fn leak_details(_pgid: u32) -> Option<LeakDetails> {
+    None
+}
+
+/// Lingering thread detection is only supported on Linux: this is a no-op on other Unix platforms.
+#[cfg(not(target_os = "linux"))]
+pub(super) fn list_threads(_pid: u32) -> Option<Vec<ThreadSnapshot>> {
+    None
+}
+
 #[derive(Debug)]
 pub(super) struct Job(());
 
@@ -47,6 +343,20 @@ pub(super) fn assign_process_to_job(
     Ok(())
 }
 
+/// Memory limits are only supported on Windows: this is uninhabited on Unix.
+pub(super) struct MemoryLimitMonitor(Infallible);
+
+impl MemoryLimitMonitor {
+    pub(super) fn abort_status(&self) -> Option<AbortStatus> {
+        match self.0 {}
+    }
+}
+
+/// Memory limits are only supported on Windows: this is a no-op on Unix.
+pub(super) fn set_job_memory_limit(_job: Option<&Job>, _limit: u64) -> Option<MemoryLimitMonitor> {
+    None
+}
+
 pub(super) fn job_control_child(child: &Child, event: JobControlEvent) {
     if let Some(pid) = child.id() {
         let pid = pid as i32;
@@ -65,6 +375,60 @@ pub(super) fn job_control_child(child: &Child, event: JobControlEvent) {
     }
 }
 
+/// How often, and how many times, to check whether a child has stopped after sending it SIGTSTP.
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(5);
+const STOP_CHECK_ATTEMPTS: usize = 10;
+
+/// Sends SIGTSTP to the child's process group, then checks whether the child stopped.
+///
+/// Processes can handle or ignore SIGTSTP, in which case they keep running. Only the child itself
+/// is checked, not any other processes in its group.
+pub(super) async fn stop_child(child: &Child) -> UnitStopStatus {
+    let Some(pid) = child.id() else {
+        // The child exited already -- there's nothing to stop.
+        return UnitStopStatus::Unknown;
+    };
+    job_control_child(child, JobControlEvent::Stop);
+
+    // Stopping happens asynchronously, so give the child a little time to act on the signal.
+    for _ in 0..STOP_CHECK_ATTEMPTS {
+        match is_child_stopped(pid) {
+            Some(true) => return UnitStopStatus::Stopped,
+            Some(false) => tokio::time::sleep(STOP_CHECK_INTERVAL).await,
+            None => return UnitStopStatus::Unknown,
+        }
+    }
+    UnitStopStatus::NotStopped
+}
+
+/// Returns whether the child is stopped, or `None` if that can't be determined.
+fn is_child_stopped(pid: u32) -> Option<bool> {
+    // si_pid is only set if the child changed state, so it must be zeroed out beforehand.
+    let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+    // WNOWAIT leaves the child in a waitable state, so that this doesn't interfere with tokio
+    // reaping it.
+    let ret = unsafe {
+        libc::waitid(
+            libc::P_PID,
+            pid as libc::id_t,
+            &mut info,
+            libc::WSTOPPED | libc::WEXITED | libc::WNOHANG | libc::WNOWAIT,
+        )
+    };
+    if ret != 0 {
+        return None;
+    }
+    if unsafe { info.si_pid() } == 0 {
+        // The child is still running.
+        return Some(false);
+    }
+    match info.si_code {
+        libc::CLD_STOPPED | libc::CLD_TRAPPED => Some(true),
+        // The child exited.
+        _ => None,
+    }
+}
+
 // Note this is SIGSTOP rather than SIGTSTP to avoid triggering our signal handler.
 pub(super) fn raise_stop() {
     // This can never error out because SIGSTOP is a valid signal.
@@ -137,8 +501,7 @@ pub(super) async fn terminate_child<'a>(
                         sleep.as_mut().pause();
                         waiting_stopwatch.pause();
 
-                        job_control_child(child, JobControlEvent::Stop);
-                        let _ = sender.send(());
+                        let _ = sender.send(stop_child(child).await);
                     }
                     RunUnitRequest::Signal(SignalRequest::Continue) => {
                         // Possible to receive a Continue at the beginning of execution.
@@ -199,9 +562,9 @@ fn to_terminate_reason_and_method(
     grace_period: Duration,
 ) -> (UnitTerminateReason, UnitTerminateMethod) {
     match reason {
-        InternalTerminateReason::Timeout => (
+        InternalTerminateReason::Timeout(signal) => (
             UnitTerminateReason::Timeout,
-            timeout_terminate_method(grace_period),
+            timeout_terminate_method(*signal, grace_period),
         ),
         InternalTerminateReason::Signal(req) => (
             UnitTerminateReason::Signal,
@@ -210,11 +573,18 @@ fn to_terminate_reason_and_method(
     }
 }
 
-fn timeout_terminate_method(grace_period: Duration) -> UnitTerminateMethod {
+fn timeout_terminate_method(
+    signal: TerminateSignal,
+    grace_period: Duration,
+) -> UnitTerminateMethod {
     if grace_period.is_zero() {
-        UnitTerminateMethod::Signal(UnitTerminateSignal::Kill)
-    } else {
-        UnitTerminateMethod::Signal(UnitTerminateSignal::Term)
+        return UnitTerminateMethod::Signal(UnitTerminateSignal::Kill);
+    }
+
+    match signal {
+        TerminateSignal::Term => UnitTerminateMethod::Signal(UnitTerminateSignal::Term),
+        TerminateSignal::Quit => UnitTerminateMethod::Signal(UnitTerminateSignal::Quit),
+        TerminateSignal::Interrupt => UnitTerminateMethod::Signal(UnitTerminateSignal::Interrupt),
     }
 }
 
@@ -251,3 +621,92 @@ impl UnitTerminateSignal {
         }
     }
 }
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeout_terminate_methods() {
+        let grace_period = Duration::from_secs(10);
+        assert!(matches!(
+            timeout_terminate_method(TerminateSignal::Term, grace_period),
+            UnitTerminateMethod::Signal(UnitTerminateSignal::Term),
+        ));
+        assert!(matches!(
+            timeout_terminate_method(TerminateSignal::Quit, grace_period),
+            UnitTerminateMethod::Signal(UnitTerminateSignal::Quit),
+        ));
+        assert!(matches!(
+            timeout_terminate_method(TerminateSignal::Interrupt, grace_period),
+            UnitTerminateMethod::Signal(UnitTerminateSignal::Interrupt),
+        ));
+        // Without a grace period, the configured signal is skipped.
+        assert!(matches!(
+            timeout_terminate_method(TerminateSignal::Quit, Duration::ZERO),
+            UnitTerminateMethod::Signal(UnitTerminateSignal::Kill),
+        ));
+    }
+
+    #[test]
+    fn parse_proc_stat_command() {
+        assert_eq!(
+            parse_proc_stat("1234 (sleep) S 1 1230 1230 0 -1 4194304"),
+            Some(("sleep", "S", 1230)),
+        );
+        // The command can contain spaces and parentheses.
+        assert_eq!(
+            parse_proc_stat("1234 (my (odd) cmd) Z 1 1230 1230 0 -1 4194304"),
+            Some(("my (odd) cmd", "Z", 1230)),
+        );
+        assert_eq!(parse_proc_stat("1234 (sleep) S 1"), None);
+    }
+
+    #[test]
+    fn parse_vm_hwm_status() {
+        let status =
+            "Name:\tbasic\nVmPeak:\t   10240 kB\nVmHWM:\t    2048 kB\nVmRSS:\t    1024 kB\n";
+        assert_eq!(parse_vm_hwm(status), Some(2048 * 1024));
+        // Zombie processes don't have any memory statistics.
+        assert_eq!(parse_vm_hwm("Name:\tbasic\nState:\tZ (zombie)\n"), None);
+        assert_eq!(parse_vm_hwm("VmHWM:\t    garbage kB\n"), None);
+    }
+
+    #[test]
+    fn parse_delayacct_blkio_ticks_stat() {
+        let mut fields: Vec<String> = (3..=52).map(|n| n.to_string()).collect();
+        fields[42 - 3] = "250".to_owned();
+        let stat = format!("1234 (my (odd) cmd) {}", fields.join(" "));
+        assert_eq!(parse_delayacct_blkio_ticks(&stat), Some(250));
+        assert_eq!(parse_delayacct_blkio_ticks("1234 (sleep) S 1 1230"), None);
+    }
+
+    #[test]
+    fn leak_details_process_group() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("60")
+            .process_group(0)
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn()
+            .expect("sleep spawned");
+        let pgid = child.id();
+
+        let details = leak_details(pgid).expect("/proc is readable");
+        assert_eq!(details.processes, 1);
+        // At least standard input, output and error are open.
+        assert!(details.open_fds >= 3, "open fds: {}", details.open_fds);
+
+        child.kill().expect("sleep killed");
+        child.wait().expect("sleep reaped");
+        let details = leak_details(pgid).expect("/proc is readable");
+        assert_eq!(
+            details,
+            LeakDetails {
+                processes: 0,
+                open_fds: 0,
+            }
+        );
+    }
+}
@@ -2,25 +2,41 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::{
-    errors::ConfigureHandleInheritanceError,
-    reporter::events::{UnitState, UnitTerminateMethod, UnitTerminateReason, UnitTerminatingState},
+    config::CpuAffinity,
+    errors::{ConfigureHandleInheritanceError, CpuAffinityError, OrphanProcess},
+    reporter::events::{
+        AbortStatus, LeakDetails, UnitState, UnitTerminateMethod, UnitTerminateReason,
+        UnitTerminatingState,
+    },
     runner::{
         InternalTerminateReason, RunUnitQuery, RunUnitRequest, ShutdownRequest, SignalRequest,
-        TerminateChildResult, UnitContext,
+        TerminateChildResult, ThreadSnapshot, UnitContext,
     },
     signal::ShutdownEvent,
     test_command::ChildAccumulator,
     time::StopwatchStart,
 };
-use std::time::Duration;
+use std::{
+    ffi::c_void,
+    os::windows::io::{AsRawHandle, FromRawHandle, OwnedHandle},
+    ptr,
+    time::Duration,
+};
 use tokio::{process::Child, sync::mpsc::UnboundedReceiver};
 pub(super) use win32job::Job;
 use win32job::JobError;
 use windows_sys::Win32::{
-    Foundation::{SetHandleInformation, HANDLE_FLAG_INHERIT, INVALID_HANDLE_VALUE},
+    Foundation::{SetHandleInformation, HANDLE, HANDLE_FLAG_INHERIT, INVALID_HANDLE_VALUE},
     System::{
         Console::{GetStdHandle, STD_ERROR_HANDLE, STD_INPUT_HANDLE, STD_OUTPUT_HANDLE},
-        JobObjects::TerminateJobObject,
+        JobObjects::{
+            JobObjectAssociateCompletionPortInformation, JobObjectExtendedLimitInformation,
+            QueryInformationJobObject, SetInformationJobObject, TerminateJobObject,
+            JOBOBJECT_ASSOCIATE_COMPLETION_PORT, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+            JOB_OBJECT_LIMIT_PROCESS_MEMORY,
+        },
+        SystemServices::JOB_OBJECT_MSG_PROCESS_MEMORY_LIMIT,
+        IO::{CreateIoCompletionPort, GetQueuedCompletionStatus, OVERLAPPED},
     },
 };
 
@@ -56,6 +72,39 @@ pub(super) fn set_process_group(_cmd: &mut std::process::Command) {
     // TODO: set process group on Windows for better ctrl-C handling.
 }
 
+/// CPU affinity is only supported on Linux: this is a no-op on Windows.
+pub(super) fn set_cpu_affinity(
+    _cmd: &mut std::process::Command,
+    _affinity: &CpuAffinity,
+) -> Result<(), CpuAffinityError> {
+    Ok(())
+}
+
+/// Orphan process detection is only supported on Linux: this is a no-op on Windows.
+pub(super) fn find_orphan_processes(_pgid: u32, _kill: bool) -> Vec<OrphanProcess> {
+    Vec::new()
+}
+
+/// Leak details are only supported on Linux: this is a no-op on Windows.
+pub(super) fn leak_details(_pgid: u32) -> Option<LeakDetails> {
+    None
+}
+
+/// Lingering thread detection is only supported on Linux: this is a no-op on Windows.
+pub(super) fn list_threads(_pid: u32) -> Option<Vec<ThreadSnapshot>> {
+    None
+}
+
+/// Memory usage tracking is only supported on Linux and macOS: this is a no-op on Windows.
+pub(super) fn peak_rss(_pid: u32) -> Option<u64> {
+    None
+}
+
+/// I/O wait tracking is only supported on Linux: this is a no-op on Windows.
+pub(super) fn io_wait(_pid: u32) -> Option<Duration> {
+    None
+}
+
 pub(super) fn assign_process_to_job(
     child: &tokio::process::Child,
     job: Option<&Job>,
@@ -77,6 +126,124 @@ pub(super) fn assign_process_to_job(
     Ok(())
 }
 
+/// Watches for a process in a job exceeding the memory limit set by [`set_job_memory_limit`].
+pub(super) struct MemoryLimitMonitor {
+    // A completion port that the job posts notifications to.
+    port: OwnedHandle,
+}
+
+impl MemoryLimitMonitor {
+    /// Returns [`AbortStatus::MemoryLimit`] if any process in the job has tried to commit more
+    /// memory than the limit.
+    ///
+    /// The job posts a notification as soon as an allocation fails, so by the time the process
+    /// has exited the notification is already queued.
+    pub(super) fn abort_status(&self) -> Option<AbortStatus> {
+        let mut exceeded = false;
+        loop {
+            let mut message = 0;
+            let mut key = 0;
+            let mut overlapped: *mut OVERLAPPED = ptr::null_mut();
+            // A timeout of 0 means that this returns immediately once the queue is empty.
+            //
+            // SAFETY: the port is a completion port owned by self, so it stays open for the
+            // duration of the call. The out-params are valid for writes of their respective types.
+            // The OVERLAPPED pointer that's written out isn't dereferenced: job notifications
+            // don't come with one, and the message identifier is all that's read.
+            let ret = unsafe {
+                GetQueuedCompletionStatus(
+                    self.port.as_raw_handle() as HANDLE,
+                    &mut message,
+                    &mut key,
+                    &mut overlapped,
+                    0,
+                )
+            };
+            if ret == 0 {
+                break exceeded.then_some(AbortStatus::MemoryLimit);
+            }
+            exceeded |= message == JOB_OBJECT_MSG_PROCESS_MEMORY_LIMIT;
+        }
+    }
+}
+
+/// Limits the amount of memory each process in the job can commit to `limit` bytes.
+///
+/// Like the rest of the job object setup, this is best-effort: `None` is returned if the limit
+/// couldn't be set.
+pub(super) fn set_job_memory_limit(job: Option<&Job>, limit: u64) -> Option<MemoryLimitMonitor> {
+    // The job handle is owned by `job`, which outlives this function.
+    let job_handle = job?.handle() as HANDLE;
+    let limit = usize::try_from(limit).unwrap_or(usize::MAX);
+
+    // SAFETY: passing INVALID_HANDLE_VALUE and a null existing port creates a new completion port
+    // that isn't associated with any file.
+    let port = unsafe { CreateIoCompletionPort(INVALID_HANDLE_VALUE, ptr::null_mut(), 0, 1) };
+    if port.is_null() {
+        return None;
+    }
+    // SAFETY: the port was just created and isn't owned by anything else, so ownership can be
+    // transferred to the OwnedHandle, which closes it on drop.
+    let port = unsafe { OwnedHandle::from_raw_handle(port) };
+
+    let association = JOBOBJECT_ASSOCIATE_COMPLETION_PORT {
+        CompletionKey: ptr::null_mut(),
+        CompletionPort: port.as_raw_handle() as HANDLE,
+    };
+    // SAFETY: JobObjectAssociateCompletionPortInformation expects a
+    // JOBOBJECT_ASSOCIATE_COMPLETION_PORT, and the pointer and size passed in are for one that
+    // lives until the call returns. The job doesn't take ownership of the port, which is kept
+    // open by the returned monitor.
+    let ret = unsafe {
+        SetInformationJobObject(
+            job_handle,
+            JobObjectAssociateCompletionPortInformation,
+            &association as *const _ as *const c_void,
+            size_of::<JOBOBJECT_ASSOCIATE_COMPLETION_PORT>() as u32,
+        )
+    };
+    if ret == 0 {
+        return None;
+    }
+
+    // Preserve any limits that are already set on the job.
+    //
+    // SAFETY: JOBOBJECT_EXTENDED_LIMIT_INFORMATION is a plain C struct of integers, for which all
+    // zeroes is a valid value.
+    let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = unsafe { std::mem::zeroed() };
+    // SAFETY: JobObjectExtendedLimitInformation expects a JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+    // and the pointer and size passed in are for `info`, which is valid for writes of that size.
+    // The returned length is optional, so a null pointer is passed in for it.
+    let ret = unsafe {
+        QueryInformationJobObject(
+            job_handle,
+            JobObjectExtendedLimitInformation,
+            &mut info as *mut _ as *mut c_void,
+            size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+            ptr::null_mut(),
+        )
+    };
+    if ret == 0 {
+        return None;
+    }
+    info.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_PROCESS_MEMORY;
+    info.ProcessMemoryLimit = limit;
+    // SAFETY: see above. `info` was initialized by QueryInformationJobObject.
+    let ret = unsafe {
+        SetInformationJobObject(
+            job_handle,
+            JobObjectExtendedLimitInformation,
+            &info as *const _ as *const c_void,
+            size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+        )
+    };
+    if ret == 0 {
+        return None;
+    }
+
+    Some(MemoryLimitMonitor { port })
+}
+
 #[expect(clippy::too_many_arguments)]
 pub(super) async fn terminate_child<'a>(
     cx: &UnitContext<'a>,
@@ -187,10 +354,10 @@ fn to_terminate_reason_and_method(
     grace_period: Duration,
 ) -> (UnitTerminateReason, UnitTerminateMethod) {
     match reason {
-        InternalTerminateReason::Timeout => (
+        InternalTerminateReason::Timeout(_) => (
             UnitTerminateReason::Timeout,
-            // The grace period is currently ignored for timeouts --
-            // TerminateJobObject is immediately called.
+            // The grace period and terminate signal are currently ignored
+            // for timeouts -- TerminateJobObject is immediately called.
             UnitTerminateMethod::JobObject,
         ),
         InternalTerminateReason::Signal(req) => (
@@ -4,6 +4,7 @@
 //! Support for handling signals in nextest.
 
 use crate::errors::SignalHandlerSetupError;
+use serde::Deserialize;
 
 /// The kind of signal handling to set up for a test run.
 ///
@@ -28,6 +29,20 @@ impl SignalHandlerKind {
     }
 }
 
+/// The policy for handling SIGTSTP (for example, Ctrl-Z at a terminal) during a test run.
+///
+/// This only has an effect on Unix platforms.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SigtstpPolicy {
+    /// Pause the run, including any tests that are currently running, until SIGCONT is received.
+    #[default]
+    Pause,
+
+    /// Ignore SIGTSTP, and keep running tests.
+    Ignore,
+}
+
 /// The signal handler implementation.
 #[derive(Debug)]
 pub(crate) struct SignalHandler {
@@ -247,6 +262,21 @@ impl ShutdownEvent {
         Self::Quit,
         Self::Interrupt,
     ];
+
+    /// Returns the signal number corresponding to this event, if the event is
+    /// not ambient.
+    ///
+    /// Interrupts are treated as ambient (they're usually the result of a user
+    /// pressing Ctrl-C), so this returns `None` for them.
+    #[cfg(unix)]
+    pub(crate) fn trigger_signal(self) -> Option<i32> {
+        match self {
+            Self::Hangup => Some(libc::SIGHUP),
+            Self::Term => Some(libc::SIGTERM),
+            Self::Quit => Some(libc::SIGQUIT),
+            Self::Interrupt => None,
+        }
+    }
 }
 
 // A signal event to query information about tests.
@@ -12,7 +12,7 @@ use camino::{Utf8Path, Utf8PathBuf};
 use guppy::graph::PackageMetadata;
 use once_cell::sync::Lazy;
 use std::{
-    collections::{BTreeSet, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
     ffi::{OsStr, OsString},
     fs::File,
     io::{BufRead, BufReader},
@@ -35,6 +35,8 @@ pub(crate) struct LocalExecuteContext<'a> {
 pub(crate) struct TestCommand {
     /// The command to be run.
     command: std::process::Command,
+    /// The program and arguments, before any double-spawn wrapping.
+    command_line: Vec<String>,
     /// Double-spawn context.
     double_spawn: Option<DoubleSpawnContext>,
 }
@@ -49,6 +51,9 @@ impl TestCommand {
         package: &PackageMetadata<'_>,
         non_test_binaries: &BTreeSet<(String, Utf8PathBuf)>,
     ) -> Self {
+        let command_line = std::iter::once(program.clone())
+            .chain(args.iter().map(|&arg| arg.to_owned()))
+            .collect();
         let mut cmd = create_command(program, args, lctx.double_spawn);
 
         // NB: we will always override user-provided environment variables with the
@@ -94,6 +99,7 @@ impl TestCommand {
 
         Self {
             command: cmd,
+            command_line,
             double_spawn,
         }
     }
@@ -103,6 +109,31 @@ impl TestCommand {
         &mut self.command
     }
 
+    /// Returns the program and arguments that will be run.
+    ///
+    /// If double-spawning is enabled, this is the command run by the double-spawn process.
+    pub(crate) fn command_line(&self) -> &[String] {
+        &self.command_line
+    }
+
+    /// Returns the environment variables set on this command, on top of those inherited from
+    /// nextest's environment.
+    ///
+    /// Non-UTF-8 keys and values are converted lossily.
+    pub(crate) fn env_overrides(&self) -> BTreeMap<String, String> {
+        self.command
+            .get_envs()
+            .filter_map(|(key, value)| {
+                // A value of None means the variable was removed.
+                let value = value?;
+                Some((
+                    key.to_string_lossy().into_owned(),
+                    value.to_string_lossy().into_owned(),
+                ))
+            })
+            .collect()
+    }
+
     pub(crate) fn spawn(self, capture_strategy: CaptureStrategy) -> std::io::Result<imp::Child> {
         let res = imp::spawn(self.command, capture_strategy);
         if let Some(ctx) = self.double_spawn {
@@ -2,11 +2,17 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::{
+    config::MaxOutputSize,
     errors::{ChildFdError, ErrorList},
-    test_output::{CaptureStrategy, ChildExecutionOutput, ChildOutput, ChildSplitOutput},
+    helpers::DisplayBytes,
+    test_output::{
+        CaptureStrategy, ChildExecutionOutput, ChildOutput, ChildSingleOutput, ChildSplitOutput,
+        TEST_BODY_STARTED_MARKER,
+    },
 };
-use bytes::BytesMut;
-use std::{io, process::Stdio, sync::Arc};
+use bstr::ByteSlice;
+use bytes::{Buf, BytesMut};
+use std::{io, process::Stdio, sync::Arc, time::Instant};
 use tokio::{
     fs::File,
     io::{AsyncBufReadExt, AsyncRead, BufReader},
@@ -90,7 +96,7 @@ impl<R: AsyncRead + Unpin> FusedBufReader<R> {
         }
     }
 
-    pub(crate) async fn fill_buf(&mut self, acc: &mut BytesMut) -> Result<(), io::Error> {
+    pub(crate) async fn fill_buf(&mut self, acc: &mut OutputBuf) -> Result<(), io::Error> {
         if self.done {
             return Ok(());
         }
@@ -121,7 +127,7 @@ impl<R: AsyncRead + Unpin> FusedBufReader<R> {
 /// A version of [`FusedBufReader::fill_buf`] that works with an `Option<FusedBufReader>`.
 async fn fill_buf_opt<R: AsyncRead + Unpin>(
     reader: Option<&mut FusedBufReader<R>>,
-    acc: Option<&mut BytesMut>,
+    acc: Option<&mut OutputBuf>,
 ) -> Result<(), io::Error> {
     if let Some(reader) = reader {
         let acc = acc.expect("reader and acc must match");
@@ -146,8 +152,8 @@ pub(crate) struct ChildAccumulator {
 }
 
 impl ChildAccumulator {
-    pub(crate) fn new(fds: ChildFds) -> Self {
-        let output = fds.make_acc();
+    pub(crate) fn new(fds: ChildFds, max_output_size: MaxOutputSize) -> Self {
+        let output = fds.make_acc(max_output_size);
         Self {
             fds,
             output,
@@ -210,13 +216,19 @@ impl ChildFds {
 
 impl ChildFds {
     /// Makes an empty `ChildOutput` with the appropriate buffers for this `ChildFds`.
-    pub(crate) fn make_acc(&self) -> ChildOutputMut {
+    ///
+    /// Each buffer holds at most `max_output_size` bytes.
+    pub(crate) fn make_acc(&self, max_output_size: MaxOutputSize) -> ChildOutputMut {
         match self {
             Self::Split { stdout, stderr } => ChildOutputMut::Split {
-                stdout: stdout.as_ref().map(|_| BytesMut::with_capacity(CHUNK_SIZE)),
-                stderr: stderr.as_ref().map(|_| BytesMut::with_capacity(CHUNK_SIZE)),
+                stdout: stdout
+                    .as_ref()
+                    .map(|_| OutputBuf::new(max_output_size).with_body_marker_search()),
+                stderr: stderr.as_ref().map(|_| OutputBuf::new(max_output_size)),
             },
-            Self::Combined { .. } => ChildOutputMut::Combined(BytesMut::with_capacity(CHUNK_SIZE)),
+            Self::Combined { .. } => {
+                ChildOutputMut::Combined(OutputBuf::new(max_output_size).with_body_marker_search())
+            }
         }
     }
 
@@ -265,22 +277,22 @@ impl ChildFds {
 pub(crate) enum ChildOutputMut {
     /// Separate stdout and stderr (`None` if not captured).
     Split {
-        stdout: Option<BytesMut>,
-        stderr: Option<BytesMut>,
+        stdout: Option<OutputBuf>,
+        stderr: Option<OutputBuf>,
     },
     /// Combined stdout and stderr.
-    Combined(BytesMut),
+    Combined(OutputBuf),
 }
 
 impl ChildOutputMut {
-    fn as_split_mut(&mut self) -> (Option<&mut BytesMut>, Option<&mut BytesMut>) {
+    fn as_split_mut(&mut self) -> (Option<&mut OutputBuf>, Option<&mut OutputBuf>) {
         match self {
             Self::Split { stdout, stderr } => (stdout.as_mut(), stderr.as_mut()),
             _ => panic!("ChildOutput is not split"),
         }
     }
 
-    fn as_combined_mut(&mut self) -> &mut BytesMut {
+    fn as_combined_mut(&mut self) -> &mut OutputBuf {
         match self {
             Self::Combined(combined) => combined,
             _ => panic!("ChildOutput is not combined"),
@@ -293,25 +305,274 @@ impl ChildOutputMut {
     pub(crate) fn snapshot(&self) -> ChildOutput {
         match self {
             Self::Split { stdout, stderr } => ChildOutput::Split(ChildSplitOutput {
-                stdout: stdout.as_ref().map(|x| x.clone().freeze().into()),
-                stderr: stderr.as_ref().map(|x| x.clone().freeze().into()),
+                stdout: stdout.as_ref().map(|x| x.clone().freeze()),
+                stderr: stderr.as_ref().map(|x| x.clone().freeze()),
             }),
             Self::Combined(combined) => ChildOutput::Combined {
-                output: combined.clone().freeze().into(),
+                output: combined.clone().freeze(),
             },
         }
     }
 
+    /// Returns the total number of bytes received so far, including any that were truncated.
+    pub(crate) fn total_len(&self) -> u64 {
+        match self {
+            Self::Split { stdout, stderr } => {
+                stdout.as_ref().map_or(0, OutputBuf::total_len)
+                    + stderr.as_ref().map_or(0, OutputBuf::total_len)
+            }
+            Self::Combined(combined) => combined.total_len(),
+        }
+    }
+
+    /// Returns the time at which [`TEST_BODY_STARTED_MARKER`] was first read from standard output
+    /// (or the combined output), if it has been.
+    pub(crate) fn body_started_at(&self) -> Option<Instant> {
+        let buf = match self {
+            Self::Split { stdout, .. } => stdout.as_ref()?,
+            Self::Combined(combined) => combined,
+        };
+        buf.body_marker.as_ref()?.found_at
+    }
+
     /// Marks the collection as done, returning a `TestOutput`.
     pub(crate) fn freeze(self) -> ChildOutput {
         match self {
             Self::Split { stdout, stderr } => ChildOutput::Split(ChildSplitOutput {
-                stdout: stdout.map(|x| x.freeze().into()),
-                stderr: stderr.map(|x| x.freeze().into()),
+                stdout: stdout.map(OutputBuf::freeze),
+                stderr: stderr.map(OutputBuf::freeze),
             }),
             Self::Combined(combined) => ChildOutput::Combined {
-                output: combined.freeze().into(),
+                output: combined.freeze(),
             },
         }
     }
 }
+
+/// A buffer for a single output stream of a child process, with an optional size limit.
+///
+/// Until the limit is reached, all output is kept. After that, the first half of the limit's worth
+/// of output is kept as the head and the most recent half as the tail. Output in between is
+/// dropped, and only the number of dropped bytes is recorded.
+#[derive(Clone, Debug)]
+pub(crate) struct OutputBuf {
+    head: BytesMut,
+    tail: BytesMut,
+    head_limit: usize,
+    tail_limit: usize,
+    truncated_bytes: u64,
+    // Set for the buffers that are searched for TEST_BODY_STARTED_MARKER.
+    body_marker: Option<MarkerSearch>,
+}
+
+impl OutputBuf {
+    fn new(max_output_size: MaxOutputSize) -> Self {
+        let (head_limit, tail_limit) = match max_output_size.limit() {
+            Some(limit) => {
+                let limit = usize::try_from(limit).unwrap_or(usize::MAX);
+                (limit / 2, limit - limit / 2)
+            }
+            // With no limit, everything goes into the head.
+            None => (usize::MAX, 0),
+        };
+        Self {
+            head: BytesMut::with_capacity(CHUNK_SIZE.min(head_limit)),
+            tail: BytesMut::new(),
+            head_limit,
+            tail_limit,
+            truncated_bytes: 0,
+            body_marker: None,
+        }
+    }
+
+    fn with_body_marker_search(mut self) -> Self {
+        self.body_marker = Some(MarkerSearch::default());
+        self
+    }
+
+    fn extend_from_slice(&mut self, data: &[u8]) {
+        // Search all data, including data that's about to be truncated.
+        if let Some(body_marker) = &mut self.body_marker {
+            body_marker.feed(TEST_BODY_STARTED_MARKER.as_bytes(), data);
+        }
+
+        let to_head = data.len().min(self.head_limit - self.head.len());
+        self.head.extend_from_slice(&data[..to_head]);
+        let data = &data[to_head..];
+        if data.is_empty() {
+            return;
+        }
+
+        if data.len() >= self.tail_limit {
+            // The new data replaces the tail entirely.
+            let keep_from = data.len() - self.tail_limit;
+            self.truncated_bytes += (self.tail.len() + keep_from) as u64;
+            self.tail.clear();
+            self.tail.extend_from_slice(&data[keep_from..]);
+        } else {
+            let excess = (self.tail.len() + data.len()).saturating_sub(self.tail_limit);
+            self.tail.advance(excess);
+            self.truncated_bytes += excess as u64;
+            self.tail.extend_from_slice(data);
+        }
+    }
+
+    fn total_len(&self) -> u64 {
+        (self.head.len() + self.tail.len()) as u64 + self.truncated_bytes
+    }
+
+    fn freeze(self) -> ChildSingleOutput {
+        let Self {
+            mut head,
+            tail,
+            truncated_bytes,
+            ..
+        } = self;
+        if truncated_bytes == 0 {
+            head.unsplit(tail);
+            return head.freeze().into();
+        }
+
+        let marker = format!("\n... {} truncated ...\n", DisplayBytes(truncated_bytes));
+        let mut buf = BytesMut::with_capacity(head.len() + marker.len() + tail.len());
+        buf.extend_from_slice(&head);
+        buf.extend_from_slice(marker.as_bytes());
+        buf.extend_from_slice(&tail);
+        ChildSingleOutput::new_truncated(buf.freeze(), truncated_bytes)
+    }
+}
+
+/// Searches a stream of output for a marker, which may be split across reads.
+#[derive(Clone, Debug, Default)]
+struct MarkerSearch {
+    // The last few bytes of the data seen so far, in case the marker straddles two reads.
+    carry: Vec<u8>,
+    found_at: Option<Instant>,
+}
+
+impl MarkerSearch {
+    fn feed(&mut self, marker: &[u8], data: &[u8]) {
+        if self.found_at.is_some() {
+            return;
+        }
+
+        let keep = marker.len() - 1;
+        self.carry.extend_from_slice(&data[..data.len().min(keep)]);
+        if self.carry.find(marker).is_some() || data.find(marker).is_some() {
+            self.found_at = Some(Instant::now());
+            self.carry = Vec::new();
+            return;
+        }
+
+        if data.len() >= keep {
+            self.carry.clear();
+            self.carry.extend_from_slice(&data[data.len() - keep..]);
+        } else {
+            let excess = self.carry.len().saturating_sub(keep);
+            self.carry.drain(..excess);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn combined_capture() {
+        // Both streams are written to the same pipe. Each end of the pipe must have exactly one
+        // owner, otherwise it is closed twice once the command is dropped.
+        let mut cmd = std::process::Command::new("sh");
+        cmd.args(["-c", "echo out; echo err >&2"]);
+        let mut child = spawn(cmd, CaptureStrategy::Combined).expect("child spawned");
+
+        let mut acc = child.child_fds.make_acc(MaxOutputSize::Unlimited);
+        while !child.child_fds.is_done() {
+            child
+                .child_fds
+                .fill_buf(&mut acc)
+                .await
+                .expect("output read");
+        }
+        let status = child.child.wait().await.expect("child waited on");
+        assert!(status.success(), "child succeeded: {status:?}");
+
+        match acc.snapshot() {
+            ChildOutput::Combined { output } => assert_eq!(output.as_str_lossy(), "out\nerr\n"),
+            other => panic!("expected combined output, found {other:?}"),
+        }
+    }
+
+    #[test]
+    fn output_buf_unlimited() {
+        let mut buf = OutputBuf::new(MaxOutputSize::Unlimited);
+        for _ in 0..100 {
+            buf.extend_from_slice(b"0123456789");
+        }
+        let output = buf.freeze();
+        assert_eq!(output.buf.len(), 1000);
+        assert_eq!(output.truncated_bytes(), None);
+    }
+
+    #[test]
+    fn output_buf_within_limit() {
+        let mut buf = OutputBuf::new(MaxOutputSize::Bytes(10));
+        buf.extend_from_slice(b"0123");
+        buf.extend_from_slice(b"456789");
+        let output = buf.freeze();
+        assert_eq!(&output.buf[..], b"0123456789");
+        assert_eq!(output.truncated_bytes(), None);
+    }
+
+    #[test]
+    fn output_buf_truncated() {
+        // Small writes that trickle past the limit.
+        let mut buf = OutputBuf::new(MaxOutputSize::Bytes(10));
+        for chunk in [&b"abc"[..], b"def", b"ghi", b"jkl", b"mno", b"pqr"] {
+            buf.extend_from_slice(chunk);
+        }
+        // Truncated bytes still count towards the total.
+        assert_eq!(buf.total_len(), 18);
+        // A snapshot in the middle shouldn't affect the final output.
+        let snapshot = buf.clone().freeze();
+        assert_eq!(snapshot.truncated_bytes(), Some(8));
+        let output = buf.freeze();
+        assert_eq!(output.as_str_lossy(), "abcde\n... 8 B truncated ...\nnopqr");
+        assert_eq!(output.truncated_bytes(), Some(8));
+
+        // A single write much larger than the limit.
+        let mut buf = OutputBuf::new(MaxOutputSize::Bytes(4));
+        buf.extend_from_slice(b"x");
+        buf.extend_from_slice(&[b'y'; 2048]);
+        buf.extend_from_slice(b"z");
+        let output = buf.freeze();
+        assert_eq!(output.as_str_lossy(), "xy\n... 2.0 KiB truncated ...\nyz");
+        assert_eq!(output.truncated_bytes(), Some(2046));
+    }
+
+    #[test]
+    fn marker_search() {
+        let found = |chunks: &[&[u8]]| {
+            let mut search = MarkerSearch::default();
+            for chunk in chunks {
+                search.feed(b"MARK", chunk);
+            }
+            search.found_at.is_some()
+        };
+
+        assert!(found(&[b"setup\nMARK\nbody\n"]), "in a single read");
+        assert!(found(&[b"setup\nMA", b"RK\n"]), "split across reads");
+        assert!(found(&[b"M", b"A", b"R", b"K"]), "one byte at a time");
+        assert!(found(&[b"xM", b"", b"AR", b"Kx"]), "with an empty read");
+        assert!(!found(&[b"MAR", b"xK"]), "interrupted");
+        assert!(!found(&[b"MA", b"R"]), "incomplete");
+
+        // The marker is found even if it's in output that's truncated away.
+        let mut buf = OutputBuf::new(MaxOutputSize::Bytes(4)).with_body_marker_search();
+        buf.extend_from_slice(b"0123456789");
+        buf.extend_from_slice(b"##nextest:body-started\n");
+        buf.extend_from_slice(b"0123456789");
+        assert!(buf.body_marker.unwrap().found_at.is_some());
+    }
+}
@@ -4,13 +4,16 @@
 use super::Stdio;
 use std::{
     io,
-    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+    os::fd::{FromRawFd, OwnedFd},
 };
 
+/// The parent's end of the pipe that the child's stdout and stderr are combined into.
+///
+/// The child's end is owned by the command, which closes it once the command is dropped after
+/// spawning. It must not be kept here as well: the same file descriptor would then be closed twice,
+/// which aborts the process in debug builds.
 pub(super) struct State {
     pub(super) ours: OwnedFd,
-    #[expect(dead_code)]
-    theirs: OwnedFd,
 }
 
 pub(super) fn setup_io(cmd: &mut std::process::Command) -> io::Result<State> {
@@ -100,6 +103,8 @@ pub(super) fn setup_io(cmd: &mut std::process::Command) -> io::Result<State> {
                 ours = std::os::fd::OwnedFd::from_raw_fd(fds[0]);
                 theirs = std::os::fd::OwnedFd::from_raw_fd(fds[1]);
             } else {
+                use std::os::fd::AsRawFd;
+
                 cvt(libc::pipe(fds.as_mut_ptr()))?;
 
                 ours = std::os::fd::OwnedFd::from_raw_fd(fds[0]);
@@ -109,10 +114,11 @@ pub(super) fn setup_io(cmd: &mut std::process::Command) -> io::Result<State> {
                 set_cloexec(theirs.as_raw_fd())?;
             }
         }
-
-        cmd.stderr(Stdio::from_raw_fd(theirs.as_raw_fd()))
-            .stdout(Stdio::from_raw_fd(theirs.as_raw_fd()));
     }
 
-    Ok(State { ours, theirs })
+    // Each Stdio owns (and closes) its own file descriptor, so give stdout a duplicate.
+    cmd.stdout(Stdio::from(theirs.try_clone()?))
+        .stderr(Stdio::from(theirs));
+
+    Ok(State { ours })
 }
@@ -6,17 +6,24 @@
 //! The main structure in this module is [`TestFilter`], which is created by a [`TestFilterBuilder`].
 
 use crate::{
+    config::CompiledDefaultFilter,
     errors::TestFilterBuilderError,
-    list::RustTestArtifact,
+    list::{RustTestArtifact, TestInstanceId, TestList},
     partition::{Partitioner, PartitionerBuilder},
+    timings::{TestOutcome, TimingsStore},
 };
 use aho_corasick::AhoCorasick;
 use nextest_filtering::{EvalContext, Filterset, TestQuery};
-use nextest_metadata::{FilterMatch, MismatchReason};
-use std::{collections::HashSet, fmt, mem};
+use nextest_metadata::{FilterMatch, MismatchReason, RustBinaryId};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt, mem,
+};
 
 /// Whether to run ignored tests.
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Default)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum RunIgnored {
     /// Only run tests that aren't ignored.
     ///
@@ -41,6 +48,131 @@ pub enum FilterBound {
     All,
 }
 
+/// A serializable summary of the filters that select the tests for a run.
+///
+/// Returned by [`TestFilterBuilder::summary`].
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub struct TestFilterSummary {
+    /// Whether ignored tests are run.
+    pub run_ignored: RunIgnored,
+
+    /// Substring patterns that test names are matched against, in sorted order.
+    pub patterns: Vec<String>,
+
+    /// Patterns that test names are matched against exactly, in sorted order.
+    pub exact_patterns: Vec<String>,
+
+    /// Substring patterns for tests to skip, in sorted order.
+    pub skip_patterns: Vec<String>,
+
+    /// Patterns for tests to skip that are matched exactly, in sorted order.
+    pub skip_exact_patterns: Vec<String>,
+
+    /// Filtersets passed in via `-E`. A test is selected if it matches any of them.
+    pub filtersets: Vec<String>,
+
+    /// The default filter from the profile, or `None` if the default filter
+    /// is ignored.
+    pub default_filter: Option<String>,
+
+    /// The partition of tests to run, if any.
+    pub partition: Option<PartitionerBuilder>,
+
+    /// If set, only tests with a matching outcome in the previous run are selected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_outcome: Option<PreviousResult>,
+}
+
+/// The outcomes of tests in the previous run that a [`PreviousResultFilter`] selects.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PreviousResult {
+    /// Tests that timed out.
+    TimedOut,
+
+    /// Tests that failed, timed out, or were flaky.
+    Failed,
+}
+
+impl PreviousResult {
+    /// Returns true if a test with `outcome` in the previous run is selected.
+    pub fn matches(self, outcome: TestOutcome) -> bool {
+        match self {
+            Self::TimedOut => outcome == TestOutcome::TimedOut,
+            Self::Failed => matches!(
+                outcome,
+                TestOutcome::Failed | TestOutcome::TimedOut | TestOutcome::Flaky
+            ),
+        }
+    }
+}
+
+/// Selects tests by their outcome in the previous run, as recorded in the
+/// [`TimingsStore`].
+///
+/// Set on a [`TestFilterBuilder`] with [`TestFilterBuilder::set_previous_results`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PreviousResultFilter {
+    result: PreviousResult,
+    last_run: BTreeMap<RustBinaryId, BTreeMap<String, TestOutcome>>,
+}
+
+impl PreviousResultFilter {
+    /// Creates a new filter selecting tests whose outcome in the most recent run recorded in
+    /// `store` matches `result`.
+    ///
+    /// Returns `None` if no outcomes were recorded for the most recent run.
+    pub fn new(store: &TimingsStore, result: PreviousResult) -> Option<Self> {
+        if !store.has_last_run() {
+            return None;
+        }
+        let mut last_run: BTreeMap<_, BTreeMap<_, _>> = BTreeMap::new();
+        for (binary_id, test_name, outcome) in store.last_run() {
+            last_run
+                .entry(binary_id.clone())
+                .or_default()
+                .insert(test_name.to_owned(), outcome);
+        }
+        Some(Self { result, last_run })
+    }
+
+    /// Returns the outcomes that tests are selected by.
+    pub fn result(&self) -> PreviousResult {
+        self.result
+    }
+
+    /// Returns the tests in `test_list` that were skipped by this filter because they weren't run
+    /// in the previous run.
+    ///
+    /// These tests matched all other filters, so they might have been selected had they been run.
+    pub fn absent_tests<'a>(&self, test_list: &'a TestList<'_>) -> Vec<TestInstanceId<'a>> {
+        test_list
+            .iter_tests()
+            .filter(|instance| {
+                instance.test_info.filter_match
+                    == FilterMatch::Mismatch {
+                        reason: MismatchReason::PreviousResult,
+                    }
+                    && self
+                        .previous_outcome(&instance.suite_info.binary_id, instance.name)
+                        .is_none()
+            })
+            .map(|instance| instance.id())
+            .collect()
+    }
+
+    fn previous_outcome(&self, binary_id: &RustBinaryId, test_name: &str) -> Option<TestOutcome> {
+        self.last_run.get(binary_id)?.get(test_name).copied()
+    }
+
+    fn test_matches(&self, binary_id: &RustBinaryId, test_name: &str) -> bool {
+        self.previous_outcome(binary_id, test_name)
+            .is_some_and(|outcome| self.result.matches(outcome))
+    }
+}
+
 /// A builder for `TestFilter` instances.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct TestFilterBuilder {
@@ -48,6 +180,7 @@ pub struct TestFilterBuilder {
     partitioner_builder: Option<PartitionerBuilder>,
     patterns: ResolvedFilterPatterns,
     exprs: TestFilterExprs,
+    previous: Option<PreviousResultFilter>,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -386,6 +519,7 @@ impl TestFilterBuilder {
             partitioner_builder,
             patterns,
             exprs,
+            previous: None,
         })
     }
 
@@ -396,6 +530,79 @@ impl TestFilterBuilder {
             partitioner_builder: None,
             patterns: ResolvedFilterPatterns::default(),
             exprs: TestFilterExprs::All,
+            previous: None,
+        }
+    }
+
+    /// Only selects tests by their outcome in the previous run.
+    ///
+    /// Tests that don't match are skipped with [`MismatchReason::PreviousResult`]. This is applied
+    /// after all other filters, except for partitioning.
+    pub fn set_previous_results(&mut self, previous: PreviousResultFilter) -> &mut Self {
+        self.previous = Some(previous);
+        self
+    }
+
+    /// Returns a serializable summary of this filter.
+    ///
+    /// The default filter is included in the summary if `bound` is [`FilterBound::DefaultSet`].
+    pub fn summary(
+        &self,
+        bound: FilterBound,
+        default_filter: &CompiledDefaultFilter,
+    ) -> TestFilterSummary {
+        fn sorted<'a>(patterns: impl IntoIterator<Item = &'a String>) -> Vec<String> {
+            let mut patterns: Vec<_> = patterns.into_iter().cloned().collect();
+            patterns.sort_unstable();
+            patterns
+        }
+
+        let (patterns, exact_patterns, skip_patterns, skip_exact_patterns) = match &self.patterns {
+            ResolvedFilterPatterns::All => Default::default(),
+            ResolvedFilterPatterns::SkipOnly {
+                skip_patterns,
+                skip_exact_patterns,
+                ..
+            } => (
+                Vec::new(),
+                Vec::new(),
+                sorted(skip_patterns),
+                sorted(skip_exact_patterns),
+            ),
+            ResolvedFilterPatterns::Patterns {
+                patterns,
+                exact_patterns,
+                skip_patterns,
+                skip_exact_patterns,
+                ..
+            } => (
+                sorted(patterns),
+                sorted(exact_patterns),
+                sorted(skip_patterns),
+                sorted(skip_exact_patterns),
+            ),
+        };
+
+        let filtersets = match &self.exprs {
+            TestFilterExprs::All => Vec::new(),
+            TestFilterExprs::Sets(exprs) => exprs.iter().map(|expr| expr.input.clone()).collect(),
+        };
+
+        let default_filter = match bound {
+            FilterBound::DefaultSet => Some(default_filter.input().to_owned()),
+            FilterBound::All => None,
+        };
+
+        TestFilterSummary {
+            run_ignored: self.run_ignored,
+            patterns,
+            exact_patterns,
+            skip_patterns,
+            skip_exact_patterns,
+            filtersets,
+            default_filter,
+            partition: self.partitioner_builder.clone(),
+            previous_outcome: self.previous.as_ref().map(PreviousResultFilter::result),
         }
     }
 
@@ -609,6 +816,7 @@ impl TestFilter<'_> {
                     }
                 }
             })
+            .or_else(|| self.filter_previous_mismatch(test_binary, test_name))
             // Note that partition-based filtering MUST come after all other kinds of filtering,
             // so that count-based bucketing applies after ignored, name and expression matching.
             // This also means that mutable count state must be maintained by the partitioner.
@@ -676,6 +884,19 @@ impl TestFilter<'_> {
         }
     }
 
+    fn filter_previous_mismatch(
+        &self,
+        test_binary: &RustTestArtifact<'_>,
+        test_name: &str,
+    ) -> Option<FilterMatch> {
+        let previous = self.builder.previous.as_ref()?;
+        (!previous.test_matches(&test_binary.binary_id, test_name)).then_some(
+            FilterMatch::Mismatch {
+                reason: MismatchReason::PreviousResult,
+            },
+        )
+    }
+
     fn filter_partition_mismatch(&mut self, test_name: &str) -> Option<FilterMatch> {
         let partition_match = match &mut self.partitioner {
             Some(partitioner) => partitioner.test_matches(test_name),
@@ -785,6 +1006,32 @@ mod tests {
         prop_assert!(!single_filter.filter_name_match(&substring).is_match());
     }
 
+    #[test]
+    fn previous_result_matches() {
+        let outcomes = [
+            TestOutcome::Passed,
+            TestOutcome::Flaky,
+            TestOutcome::Failed,
+            TestOutcome::TimedOut,
+        ];
+        let selected = |result: PreviousResult| {
+            outcomes
+                .into_iter()
+                .filter(|&outcome| result.matches(outcome))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(selected(PreviousResult::TimedOut), [TestOutcome::TimedOut]);
+        assert_eq!(
+            selected(PreviousResult::Failed),
+            [
+                TestOutcome::Flaky,
+                TestOutcome::Failed,
+                TestOutcome::TimedOut
+            ]
+        );
+    }
+
     #[test]
     fn pattern_examples() {
         let mut patterns = TestFilterPatterns::new(vec!["foo".to_string()]);
@@ -936,4 +1183,49 @@ mod tests {
             FilterNameMatch::MatchEmptyPatterns,
         );
     }
+    #[test]
+    fn filter_summary() {
+        let mut patterns = TestFilterPatterns::new(vec!["foo".to_string(), "bar".to_string()]);
+        patterns.add_exact_pattern("baz".to_string());
+        patterns.add_skip_pattern("quux".to_string());
+        let test_filter = TestFilterBuilder::new(
+            RunIgnored::All,
+            Some(PartitionerBuilder::Count {
+                shard: 1,
+                total_shards: 2,
+            }),
+            patterns,
+            Vec::new(),
+        )
+        .unwrap();
+
+        let default_filter = CompiledDefaultFilter::for_default_config();
+        let summary = test_filter.summary(FilterBound::DefaultSet, &default_filter);
+        assert_eq!(summary.patterns, ["bar", "foo"]);
+        assert_eq!(summary.exact_patterns, ["baz"]);
+        assert_eq!(summary.skip_patterns, ["quux"]);
+        assert!(summary.filtersets.is_empty());
+        assert_eq!(summary.default_filter.as_deref(), Some("all()"));
+
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "run-ignored": "all",
+                "patterns": ["bar", "foo"],
+                "exact-patterns": ["baz"],
+                "skip-patterns": ["quux"],
+                "skip-exact-patterns": [],
+                "filtersets": [],
+                "default-filter": "all()",
+                "partition": { "kind": "count", "shard": 1, "total-shards": 2 },
+            }),
+        );
+        let roundtrip: TestFilterSummary = serde_json::from_value(json).unwrap();
+        assert_eq!(roundtrip, summary);
+
+        // The default filter isn't applied with --ignore-default-filter.
+        let summary = test_filter.summary(FilterBound::All, &default_filter);
+        assert_eq!(summary.default_filter, None);
+    }
 }
@@ -6,7 +6,12 @@ use crate::{
 };
 use bstr::{ByteSlice, Lines};
 use bytes::Bytes;
-use std::{borrow::Cow, sync::OnceLock};
+use std::{
+    borrow::Cow,
+    fmt,
+    sync::{Arc, OnceLock},
+};
+use tracing::debug;
 
 /// The strategy used to capture test executable output
 #[derive(Copy, Clone, PartialEq, Default, Debug)]
@@ -42,6 +47,10 @@ pub struct ChildSingleOutput {
     ///
     /// `None` means the output is valid UTF-8.
     as_str: OnceLock<Option<Box<str>>>,
+
+    /// The number of bytes dropped from the middle of the output because it exceeded the
+    /// configured `max-output-size`.
+    truncated_bytes: Option<u64>,
 }
 
 impl From<Bytes> for ChildSingleOutput {
@@ -50,11 +59,30 @@ impl From<Bytes> for ChildSingleOutput {
         Self {
             buf,
             as_str: OnceLock::new(),
+            truncated_bytes: None,
         }
     }
 }
 
 impl ChildSingleOutput {
+    /// Creates a new output that had `truncated_bytes` bytes dropped from it.
+    ///
+    /// `buf` is expected to already contain a marker indicating where the bytes were dropped.
+    pub(crate) fn new_truncated(buf: Bytes, truncated_bytes: u64) -> Self {
+        Self {
+            buf,
+            as_str: OnceLock::new(),
+            truncated_bytes: Some(truncated_bytes),
+        }
+    }
+
+    /// Returns the number of bytes dropped from the middle of this output, if it was truncated
+    /// because it exceeded the configured `max-output-size`.
+    #[inline]
+    pub fn truncated_bytes(&self) -> Option<u64> {
+        self.truncated_bytes
+    }
+
     /// Gets this output as a lossy UTF-8 string.
     #[inline]
     pub fn as_str_lossy(&self) -> &str {
@@ -146,6 +174,55 @@ pub enum ChildOutput {
     },
 }
 
+/// The prefix of lines that a test can print to standard output to attach a property to its JUnit
+/// test case.
+///
+/// The rest of the line has the form `key=value`. For example, a test that prints
+/// `##nextest:property jira=PROJ-123` gets a `jira` property with the value `PROJ-123`.
+pub const TEST_PROPERTY_PREFIX: &str = "##nextest:property ";
+
+/// A marker that a test can print to standard output once its fixtures are set up and the body of
+/// the test starts running.
+///
+/// The time between the test process being spawned and this marker being read is reported as
+/// [`ExecuteStatus::setup_time`](crate::reporter::events::ExecuteStatus::setup_time).
+pub const TEST_BODY_STARTED_MARKER: &str = "##nextest:body-started";
+
+impl ChildOutput {
+    /// Returns the properties a test attached to itself by printing lines containing
+    /// [`TEST_PROPERTY_PREFIX`], in the order they were printed.
+    ///
+    /// Only standard output (or the combined output, if the streams were captured together) is
+    /// searched. Lines where the text after the prefix isn't of the form `key=value`, with a
+    /// non-empty key without whitespace, are ignored.
+    pub fn test_properties(&self) -> Vec<(String, String)> {
+        let output = match self {
+            ChildOutput::Split(split) => split.stdout.as_ref(),
+            ChildOutput::Combined { output } => Some(output),
+        };
+        let Some(output) = output else {
+            return Vec::new();
+        };
+
+        output
+            .lines()
+            .filter_map(|line| {
+                // The prefix doesn't have to be at the start of the line, since the first line a
+                // test prints follows libtest's "test <name> ... " on the same line.
+                let start = line.find(TEST_PROPERTY_PREFIX)? + TEST_PROPERTY_PREFIX.len();
+                let rest = line[start..].to_str_lossy();
+                let property = rest
+                    .split_once('=')
+                    .filter(|(key, _)| !key.is_empty() && !key.contains(char::is_whitespace));
+                if property.is_none() {
+                    debug!(line = %rest, "ignoring malformed test property line");
+                }
+                property.map(|(key, value)| (key.to_owned(), value.to_owned()))
+            })
+            .collect()
+    }
+}
+
 /// The output of a child process (test or setup script) with split stdout and stderr.
 ///
 /// One of the variants of [`ChildOutput`].
@@ -157,3 +234,249 @@ pub struct ChildSplitOutput {
     /// The captured stderr, or `None` if the output was not captured.
     pub stderr: Option<ChildSingleOutput>,
 }
+
+/// Configuration for determinism checks: running each passing test a second time and comparing
+/// its output against the first run.
+///
+/// Before comparison, captured output is passed through a normalizer. By default the normalizer
+/// is the identity function, but a custom one can be supplied with
+/// [`with_normalizer`](Self::with_normalizer) to strip out data that is expected to vary between
+/// runs, such as timestamps or memory addresses.
+///
+/// A `DeterminismCheck` can be passed into
+/// [`TestRunnerBuilder::set_determinism_check`](crate::runner::TestRunnerBuilder::set_determinism_check).
+#[derive(Clone, Default)]
+pub struct DeterminismCheck {
+    normalizer: Option<Arc<OutputNormalizerFn>>,
+}
+
+type OutputNormalizerFn = dyn Fn(&[u8]) -> Vec<u8> + Send + Sync;
+
+impl DeterminismCheck {
+    /// Creates a new `DeterminismCheck` which compares output verbatim.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new `DeterminismCheck` which passes output through the given normalizer before
+    /// comparing it.
+    pub fn with_normalizer<F>(normalizer: F) -> Self
+    where
+        F: Fn(&[u8]) -> Vec<u8> + Send + Sync + 'static,
+    {
+        Self {
+            normalizer: Some(Arc::new(normalizer)),
+        }
+    }
+
+    /// Normalizes a single output buffer.
+    pub fn normalize<'b>(&self, buf: &'b [u8]) -> Cow<'b, [u8]> {
+        match &self.normalizer {
+            Some(normalizer) => Cow::Owned(normalizer(buf)),
+            None => Cow::Borrowed(buf),
+        }
+    }
+
+    /// Compares the results and outputs of two runs of the same test.
+    ///
+    /// Returns `None` if the runs are equivalent after normalization, and the first difference
+    /// found otherwise.
+    pub fn compare(
+        &self,
+        first_result: ExecutionResult,
+        first: &ChildExecutionOutput,
+        second_result: ExecutionResult,
+        second: &ChildExecutionOutput,
+    ) -> Option<OutputMismatch> {
+        // Leak details depend on timing, so they're ignored here.
+        if first_result.without_leak_details() != second_result.without_leak_details() {
+            return Some(OutputMismatch::Result);
+        }
+
+        let (first, second) = match (first, second) {
+            (
+                ChildExecutionOutput::Output { output: first, .. },
+                ChildExecutionOutput::Output { output: second, .. },
+            ) => (first, second),
+            // Start errors don't have any output to compare, and in any case the results are
+            // ExecFail for both runs.
+            (ChildExecutionOutput::StartError(_), ChildExecutionOutput::StartError(_)) => {
+                return None;
+            }
+            _ => return Some(OutputMismatch::Result),
+        };
+
+        match (first, second) {
+            (ChildOutput::Split(first), ChildOutput::Split(second)) => {
+                if !self.single_eq(first.stdout.as_ref(), second.stdout.as_ref()) {
+                    Some(OutputMismatch::Stdout)
+                } else if !self.single_eq(first.stderr.as_ref(), second.stderr.as_ref()) {
+                    Some(OutputMismatch::Stderr)
+                } else {
+                    None
+                }
+            }
+            (ChildOutput::Combined { output: first }, ChildOutput::Combined { output: second }) => {
+                (!self.single_eq(Some(first), Some(second))).then_some(OutputMismatch::Combined)
+            }
+            // The capture strategy is fixed for a run, so this shouldn't happen in practice.
+            _ => Some(OutputMismatch::Combined),
+        }
+    }
+
+    fn single_eq(
+        &self,
+        first: Option<&ChildSingleOutput>,
+        second: Option<&ChildSingleOutput>,
+    ) -> bool {
+        match (first, second) {
+            (Some(first), Some(second)) => {
+                self.normalize(&first.buf) == self.normalize(&second.buf)
+            }
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Debug for DeterminismCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeterminismCheck")
+            .field("has_normalizer", &self.normalizer.is_some())
+            .finish()
+    }
+}
+
+/// The first difference found by a [`DeterminismCheck`] between two runs of a test.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum OutputMismatch {
+    /// The execution results differed.
+    Result,
+
+    /// Standard output differed after normalization.
+    Stdout,
+
+    /// Standard error differed after normalization.
+    Stderr,
+
+    /// The combined output differed after normalization.
+    Combined,
+}
+
+impl OutputMismatch {
+    /// Returns a short description of the mismatch.
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Result => "result",
+            Self::Stdout => "stdout",
+            Self::Stderr => "stderr",
+            Self::Combined => "output",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split_output(stdout: &'static str, stderr: &'static str) -> ChildExecutionOutput {
+        ChildExecutionOutput::Output {
+            result: Some(ExecutionResult::Pass),
+            output: ChildOutput::Split(ChildSplitOutput {
+                stdout: Some(Bytes::from_static(stdout.as_bytes()).into()),
+                stderr: Some(Bytes::from_static(stderr.as_bytes()).into()),
+            }),
+            errors: None,
+        }
+    }
+
+    #[test]
+    fn determinism_check_compare() {
+        let check = DeterminismCheck::new();
+        let pass = ExecutionResult::Pass;
+
+        let first = split_output("running 1 test\n", "");
+        assert_eq!(check.compare(pass, &first, pass, &first.clone()), None);
+        assert_eq!(
+            check.compare(pass, &first, pass, &split_output("running 2 tests\n", "")),
+            Some(OutputMismatch::Stdout),
+        );
+        assert_eq!(
+            check.compare(
+                pass,
+                &first,
+                pass,
+                &split_output("running 1 test\n", "warning")
+            ),
+            Some(OutputMismatch::Stderr),
+        );
+        assert_eq!(
+            check.compare(
+                pass,
+                &first,
+                ExecutionResult::Leak { details: None },
+                &first
+            ),
+            Some(OutputMismatch::Result),
+        );
+    }
+
+    #[test]
+    fn determinism_check_normalizer() {
+        // Replace all ASCII digits with a placeholder.
+        let check = DeterminismCheck::with_normalizer(|buf| {
+            buf.iter()
+                .map(|b| if b.is_ascii_digit() { b'N' } else { *b })
+                .collect()
+        });
+        let pass = ExecutionResult::Pass;
+
+        assert_eq!(check.normalize(b"at 0x7ff3"), &b"at NxNffN"[..]);
+        assert_eq!(
+            check.compare(
+                pass,
+                &split_output("started at 12:00:01\n", ""),
+                pass,
+                &split_output("started at 12:00:02\n", ""),
+            ),
+            None,
+        );
+        assert_eq!(
+            check.compare(
+                pass,
+                &split_output("started at 12:00:01\n", ""),
+                pass,
+                &split_output("finished at 12:00:02\n", ""),
+            ),
+            Some(OutputMismatch::Stdout),
+        );
+    }
+
+    #[test]
+    fn test_properties() {
+        let ChildExecutionOutput::Output { output, .. } = split_output(
+            "running 1 test\n\
+             ##nextest:property owner=team-a\n\
+             ##nextest:property jira=PROJ-123\r\n\
+             ##nextest:property url=https://example.com/?a=b\n\
+             ##nextest:property missing-value\n\
+             ##nextest:property =no-key\n\
+             ##nextest:property two words=value\n\
+             test with_props ... ##nextest:property after-libtest=value\n\
+             ##nextest:property empty=\n",
+            "##nextest:property stderr=ignored\n",
+        ) else {
+            unreachable!("split_output always returns Output")
+        };
+        assert_eq!(
+            output.test_properties(),
+            vec![
+                ("owner".to_owned(), "team-a".to_owned()),
+                ("jira".to_owned(), "PROJ-123".to_owned()),
+                ("url".to_owned(), "https://example.com/?a=b".to_owned()),
+                ("after-libtest".to_owned(), "value".to_owned()),
+                ("empty".to_owned(), String::new()),
+            ],
+        );
+    }
+}
@@ -0,0 +1,572 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Historical test timings.
+//!
+//! The timings store records how long each test took across recent runs, along with the outcome of
+//! each run and how many attempts it took. It is written to the profile's store directory at the end of each run if
+//! `record-timings` is enabled, and can be read back to estimate how long tests will take and how
+//! often they're flaky. The store also records the outcome of each test in the most recent run, so
+//! that tests can be selected by their previous result.
+
+use crate::errors::TimingsStoreError;
+use atomicwrites::{AtomicFile, OverwriteBehavior};
+use camino::{Utf8Path, Utf8PathBuf};
+use nextest_metadata::{FlakyRate, RustBinaryId};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, io::Write, time::Duration};
+
+/// Recorded durations for tests across recent runs.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TimingsStore {
+    tests: BTreeMap<RustBinaryId, BTreeMap<String, TestTimings>>,
+    // The outcomes of the tests in the most recent run.
+    last_run: BTreeMap<RustBinaryId, BTreeMap<String, TestOutcome>>,
+}
+
+impl TimingsStore {
+    /// The name of the timings file within the store directory.
+    pub const FILE_NAME: &'static str = "timings.json";
+
+    /// The current format version of the timings file.
+    ///
+    /// Version 2 records the number of attempts along with each outcome.
+    pub const FORMAT_VERSION: u32 = 2;
+
+    /// The maximum number of durations and outcomes retained for each test.
+    ///
+    /// Once this many durations (or outcomes) have been recorded for a test, the oldest one is
+    /// discarded each time a new one is recorded.
+    pub const MAX_SAMPLES: usize = 10;
+
+    /// Creates a new, empty timings store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the path to the timings file within the given store directory.
+    pub fn path(store_dir: &Utf8Path) -> Utf8PathBuf {
+        store_dir.join(Self::FILE_NAME)
+    }
+
+    /// Loads the timings store from the given store directory.
+    ///
+    /// Returns an empty store if no timings have been recorded yet, or if the timings were recorded
+    /// in an older format.
+    pub fn load(store_dir: &Utf8Path) -> Result<Self, TimingsStoreError> {
+        let path = Self::path(store_dir);
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self::new());
+            }
+            Err(error) => return Err(TimingsStoreError::Read { path, error }),
+        };
+
+        let deserialize_error = |error| TimingsStoreError::Deserialize {
+            path: path.clone(),
+            error,
+        };
+        // Check the version before deserializing the rest of the store, since older formats don't
+        // deserialize as the current one.
+        let version: TimingsStoreVersion =
+            serde_json::from_str(&contents).map_err(deserialize_error)?;
+        if version.format_version < Self::FORMAT_VERSION {
+            // Timings are only a cache of recent runs, so older ones are discarded and replaced.
+            return Ok(Self::new());
+        }
+        if version.format_version != Self::FORMAT_VERSION {
+            return Err(TimingsStoreError::UnsupportedVersion {
+                path,
+                version: version.format_version,
+            });
+        }
+        let serialized: TimingsStoreSerialized =
+            serde_json::from_str(&contents).map_err(deserialize_error)?;
+
+        let tests = serialized
+            .tests
+            .into_iter()
+            .map(|(binary_id, tests)| {
+                let tests = tests
+                    .into_iter()
+                    .map(|(test_name, timings)| (test_name, timings.into_timings()))
+                    .collect();
+                (binary_id, tests)
+            })
+            .collect();
+        Ok(Self {
+            tests,
+            last_run: serialized.last_run,
+        })
+    }
+
+    /// Writes the timings store to the given store directory.
+    ///
+    /// The file is written atomically, so concurrent readers never observe a partially-written
+    /// store.
+    pub fn save(&self, store_dir: &Utf8Path) -> Result<(), TimingsStoreError> {
+        let path = Self::path(store_dir);
+        std::fs::create_dir_all(store_dir).map_err(|error| TimingsStoreError::Write {
+            path: path.clone(),
+            error,
+        })?;
+
+        let serialized = TimingsStoreSerialized {
+            format_version: Self::FORMAT_VERSION,
+            tests: self
+                .tests
+                .iter()
+                .map(|(binary_id, tests)| {
+                    let tests = tests
+                        .iter()
+                        .map(|(test_name, timings)| {
+                            (test_name.clone(), TestTimingsSerialized::new(timings))
+                        })
+                        .collect();
+                    (binary_id.clone(), tests)
+                })
+                .collect(),
+            last_run: self.last_run.clone(),
+        };
+
+        let file = AtomicFile::new(&path, OverwriteBehavior::AllowOverwrite);
+        file.write(|f| {
+            serde_json::to_writer(&mut *f, &serialized)?;
+            f.write_all(b"\n")
+        })
+        .map_err(|error| TimingsStoreError::Write {
+            path,
+            error: match error {
+                atomicwrites::Error::Internal(error) | atomicwrites::Error::User(error) => error,
+            },
+        })
+    }
+
+    /// Records a duration for the given test.
+    pub fn record(&mut self, binary_id: &RustBinaryId, test_name: &str, duration: Duration) {
+        let timings = self
+            .tests
+            .entry(binary_id.clone())
+            .or_default()
+            .entry(test_name.to_owned())
+            .or_default();
+        timings.record(duration);
+    }
+
+    /// Records the outcome of a run of the given test, along with the number of times the test was
+    /// attempted in the run.
+    ///
+    /// The outcome is also recorded as the test's result in the most recent run.
+    pub fn record_outcome(
+        &mut self,
+        binary_id: &RustBinaryId,
+        test_name: &str,
+        outcome: TestOutcome,
+        attempts: usize,
+    ) {
+        self.last_run
+            .entry(binary_id.clone())
+            .or_default()
+            .insert(test_name.to_owned(), outcome);
+        let timings = self
+            .tests
+            .entry(binary_id.clone())
+            .or_default()
+            .entry(test_name.to_owned())
+            .or_default();
+        timings.record_outcome(RecordedOutcome { outcome, attempts });
+    }
+
+    /// Merges the durations and outcomes recorded in `other` into this store.
+    ///
+    /// Records in `other` are treated as more recent than the ones in this store. If `other`
+    /// recorded any outcomes, its most recent run replaces the one in this store.
+    pub fn merge(&mut self, other: &TimingsStore) {
+        let last_run = if other.last_run.is_empty() {
+            std::mem::take(&mut self.last_run)
+        } else {
+            other.last_run.clone()
+        };
+        for (binary_id, tests) in &other.tests {
+            for (test_name, timings) in tests {
+                for duration in timings.durations() {
+                    self.record(binary_id, test_name, *duration);
+                }
+                for recorded in timings.outcomes() {
+                    self.record_outcome(binary_id, test_name, recorded.outcome, recorded.attempts);
+                }
+            }
+        }
+        // Recording outcomes above also updates the last run, so it's restored afterwards.
+        self.last_run = last_run;
+    }
+
+    /// Returns the recorded timings for a test, if any.
+    pub fn test_timings(&self, binary_id: &RustBinaryId, test_name: &str) -> Option<&TestTimings> {
+        self.tests.get(binary_id)?.get(test_name)
+    }
+
+    /// Returns the estimated duration for a test: the median of its recorded durations.
+    ///
+    /// Returns `None` if the test has no recorded history.
+    pub fn estimated_duration(
+        &self,
+        binary_id: &RustBinaryId,
+        test_name: &str,
+    ) -> Option<Duration> {
+        self.test_timings(binary_id, test_name)?.median()
+    }
+
+    /// Returns how often a test was flaky in its recorded runs.
+    ///
+    /// Returns `None` if the test has no recorded outcomes.
+    pub fn flaky_rate(&self, binary_id: &RustBinaryId, test_name: &str) -> Option<FlakyRate> {
+        self.test_timings(binary_id, test_name)?.flaky_rate()
+    }
+
+    /// Returns the outcome of a test in the most recent run.
+    ///
+    /// Returns `None` if the test wasn't run in the most recent run.
+    pub fn last_run_outcome(
+        &self,
+        binary_id: &RustBinaryId,
+        test_name: &str,
+    ) -> Option<TestOutcome> {
+        self.last_run.get(binary_id)?.get(test_name).copied()
+    }
+
+    /// Iterates over the tests in the most recent run, along with their outcomes.
+    pub fn last_run(&self) -> impl Iterator<Item = (&RustBinaryId, &str, TestOutcome)> + '_ {
+        self.last_run.iter().flat_map(|(binary_id, tests)| {
+            tests
+                .iter()
+                .map(move |(test_name, outcome)| (binary_id, test_name.as_str(), *outcome))
+        })
+    }
+
+    /// Returns true if outcomes were recorded for the most recent run.
+    pub fn has_last_run(&self) -> bool {
+        !self.last_run.is_empty()
+    }
+
+    /// Returns true if no timings have been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.tests.is_empty()
+    }
+}
+
+/// Recorded durations and outcomes for a single test, oldest first.
+///
+/// Part of a [`TimingsStore`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TestTimings {
+    durations: Vec<Duration>,
+    outcomes: Vec<RecordedOutcome>,
+}
+
+impl TestTimings {
+    /// Returns the recorded durations, oldest first.
+    pub fn durations(&self) -> &[Duration] {
+        &self.durations
+    }
+
+    /// Returns the most recently recorded duration.
+    pub fn last(&self) -> Option<Duration> {
+        self.durations.last().copied()
+    }
+
+    /// Returns the median of the recorded durations.
+    ///
+    /// For an even number of durations, this is the mean of the two middle values.
+    pub fn median(&self) -> Option<Duration> {
+        let mut sorted = self.durations.clone();
+        sorted.sort_unstable();
+        let mid = sorted.len() / 2;
+        match sorted.len() {
+            0 => None,
+            len if len % 2 == 1 => Some(sorted[mid]),
+            _ => Some((sorted[mid - 1] + sorted[mid]) / 2),
+        }
+    }
+
+    /// Returns the recorded outcomes, oldest first.
+    pub fn outcomes(&self) -> &[RecordedOutcome] {
+        &self.outcomes
+    }
+
+    /// Returns how many of the recorded outcomes are [`TestOutcome::Flaky`].
+    pub fn flaky_rate(&self) -> Option<FlakyRate> {
+        if self.outcomes.is_empty() {
+            return None;
+        }
+        let flaky_runs = self
+            .outcomes
+            .iter()
+            .filter(|recorded| recorded.outcome == TestOutcome::Flaky)
+            .count();
+        Some(FlakyRate {
+            flaky_runs,
+            total_runs: self.outcomes.len(),
+        })
+    }
+
+    fn record(&mut self, duration: Duration) {
+        if self.durations.len() >= TimingsStore::MAX_SAMPLES {
+            self.durations.remove(0);
+        }
+        self.durations.push(duration);
+    }
+
+    fn record_outcome(&mut self, recorded: RecordedOutcome) {
+        if self.outcomes.len() >= TimingsStore::MAX_SAMPLES {
+            self.outcomes.remove(0);
+        }
+        self.outcomes.push(recorded);
+    }
+}
+
+/// The outcome of a single run of a test, along with the number of attempts it took.
+///
+/// Part of a [`TestTimings`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RecordedOutcome {
+    /// The outcome of the run.
+    pub outcome: TestOutcome,
+
+    /// The number of times the test was attempted in the run, including retries.
+    pub attempts: usize,
+}
+
+/// The outcome of a single run of a test, as recorded in a [`TimingsStore`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TestOutcome {
+    /// The test passed on the first attempt.
+    Passed,
+
+    /// The test failed at least once, but passed on a retry.
+    Flaky,
+
+    /// All attempts of the test failed.
+    Failed,
+
+    /// All attempts of the test failed, and the last one timed out.
+    TimedOut,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct TimingsStoreVersion {
+    format_version: u32,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct TimingsStoreSerialized {
+    format_version: u32,
+    tests: BTreeMap<RustBinaryId, BTreeMap<String, TestTimingsSerialized>>,
+    // The last run was added after the first version of the format, so it's optional.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    last_run: BTreeMap<RustBinaryId, BTreeMap<String, TestOutcome>>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct TestTimingsSerialized {
+    // Durations are stored as fractional seconds.
+    durations: Vec<f64>,
+    // Outcomes were added after the first version of the format, so they're optional.
+    #[serde(default)]
+    outcomes: Vec<RecordedOutcome>,
+}
+
+impl TestTimingsSerialized {
+    fn new(timings: &TestTimings) -> Self {
+        Self {
+            durations: timings
+                .durations
+                .iter()
+                .map(Duration::as_secs_f64)
+                .collect(),
+            outcomes: timings.outcomes.clone(),
+        }
+    }
+
+    fn into_timings(self) -> TestTimings {
+        let mut durations: Vec<_> = self
+            .durations
+            .into_iter()
+            // Skip over invalid (negative, NaN or huge) durations rather than failing the load.
+            .filter_map(|secs| Duration::try_from_secs_f64(secs).ok())
+            .collect();
+        if durations.len() > TimingsStore::MAX_SAMPLES {
+            durations.drain(..durations.len() - TimingsStore::MAX_SAMPLES);
+        }
+        let mut outcomes = self.outcomes;
+        if outcomes.len() > TimingsStore::MAX_SAMPLES {
+            outcomes.drain(..outcomes.len() - TimingsStore::MAX_SAMPLES);
+        }
+        TestTimings {
+            durations,
+            outcomes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use camino_tempfile::tempdir;
+
+    #[test]
+    fn median_and_window() {
+        let binary_id = RustBinaryId::new("my-package::my-binary");
+        let mut store = TimingsStore::new();
+        assert_eq!(store.estimated_duration(&binary_id, "test_foo"), None);
+
+        store.record(&binary_id, "test_foo", Duration::from_millis(30));
+        store.record(&binary_id, "test_foo", Duration::from_millis(10));
+        store.record(&binary_id, "test_foo", Duration::from_millis(20));
+        assert_eq!(
+            store.estimated_duration(&binary_id, "test_foo"),
+            Some(Duration::from_millis(20)),
+        );
+
+        store.record(&binary_id, "test_foo", Duration::from_millis(50));
+        assert_eq!(
+            store.estimated_duration(&binary_id, "test_foo"),
+            Some(Duration::from_millis(25)),
+            "even number of samples => mean of middle two",
+        );
+
+        for _ in 0..TimingsStore::MAX_SAMPLES {
+            store.record(&binary_id, "test_foo", Duration::from_secs(1));
+        }
+        let timings = store.test_timings(&binary_id, "test_foo").unwrap();
+        assert_eq!(timings.durations().len(), TimingsStore::MAX_SAMPLES);
+        assert_eq!(timings.median(), Some(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn flaky_rate() {
+        let binary_id = RustBinaryId::new("my-package::my-binary");
+        let mut store = TimingsStore::new();
+        assert_eq!(store.flaky_rate(&binary_id, "test_foo"), None);
+
+        store.record_outcome(&binary_id, "test_foo", TestOutcome::Passed, 1);
+        store.record_outcome(&binary_id, "test_foo", TestOutcome::Flaky, 2);
+        store.record_outcome(&binary_id, "test_foo", TestOutcome::Failed, 3);
+        store.record_outcome(&binary_id, "test_foo", TestOutcome::Flaky, 2);
+        assert_eq!(
+            store.flaky_rate(&binary_id, "test_foo"),
+            Some(FlakyRate {
+                flaky_runs: 2,
+                total_runs: 4,
+            })
+        );
+
+        // Older outcomes fall out of the window.
+        for _ in 0..TimingsStore::MAX_SAMPLES - 1 {
+            store.record_outcome(&binary_id, "test_foo", TestOutcome::Passed, 1);
+        }
+        assert_eq!(
+            store.flaky_rate(&binary_id, "test_foo"),
+            Some(FlakyRate {
+                flaky_runs: 1,
+                total_runs: TimingsStore::MAX_SAMPLES,
+            })
+        );
+
+        // Durations and outcomes are tracked independently.
+        assert_eq!(store.estimated_duration(&binary_id, "test_foo"), None);
+    }
+
+    #[test]
+    fn last_run() {
+        let binary_id = RustBinaryId::new("my-package::my-binary");
+        let mut store = TimingsStore::new();
+        assert!(!store.has_last_run());
+        store.record_outcome(&binary_id, "test_foo", TestOutcome::Passed, 1);
+        store.record_outcome(&binary_id, "test_bar", TestOutcome::TimedOut, 1);
+        assert_eq!(
+            store.last_run_outcome(&binary_id, "test_bar"),
+            Some(TestOutcome::TimedOut)
+        );
+
+        // A newer run replaces the last run, but not the history.
+        let mut newer = TimingsStore::new();
+        newer.record_outcome(&binary_id, "test_bar", TestOutcome::Passed, 1);
+        store.merge(&newer);
+        assert_eq!(store.last_run_outcome(&binary_id, "test_foo"), None);
+        assert_eq!(
+            store.last_run_outcome(&binary_id, "test_bar"),
+            Some(TestOutcome::Passed)
+        );
+        assert_eq!(
+            store
+                .test_timings(&binary_id, "test_bar")
+                .unwrap()
+                .outcomes(),
+            &[
+                RecordedOutcome {
+                    outcome: TestOutcome::TimedOut,
+                    attempts: 1,
+                },
+                RecordedOutcome {
+                    outcome: TestOutcome::Passed,
+                    attempts: 1,
+                },
+            ]
+        );
+
+        // Merging a store without outcomes keeps the last run.
+        store.merge(&TimingsStore::new());
+        assert!(store.has_last_run());
+    }
+
+    #[test]
+    fn load_and_save() {
+        let dir = tempdir().unwrap();
+        let store_dir = dir.path().join("store");
+
+        let store = TimingsStore::load(&store_dir).expect("missing store is empty");
+        assert!(store.is_empty());
+
+        let binary_id = RustBinaryId::new("my-package");
+        let mut store = TimingsStore::new();
+        store.record(&binary_id, "test_foo", Duration::from_millis(125));
+        store.record(&binary_id, "test_bar", Duration::from_secs(2));
+        store.record_outcome(&binary_id, "test_bar", TestOutcome::Flaky, 2);
+        store.record(&binary_id, "test_baz", Duration::from_secs(60));
+        store.record_outcome(&binary_id, "test_baz", TestOutcome::TimedOut, 1);
+        store.save(&store_dir).expect("saved store");
+
+        let loaded = TimingsStore::load(&store_dir).expect("loaded store");
+        assert_eq!(loaded, store);
+
+        // Stores in an older format are discarded.
+        std::fs::write(
+            TimingsStore::path(&store_dir),
+            r#"{"format-version": 1, "tests": {"my-package": {"test_bar": {"durations": [2.0], "outcomes": ["flaky"]}}}}"#,
+        )
+        .unwrap();
+        let loaded = TimingsStore::load(&store_dir).expect("older store is discarded");
+        assert!(loaded.is_empty());
+
+        std::fs::write(
+            TimingsStore::path(&store_dir),
+            r#"{"format-version": 99, "tests": {}}"#,
+        )
+        .unwrap();
+        let error = TimingsStore::load(&store_dir).expect_err("unsupported version");
+        assert!(
+            matches!(
+                error,
+                TimingsStoreError::UnsupportedVersion { version: 99, .. }
+            ),
+            "unexpected error: {error:?}",
+        );
+    }
+}
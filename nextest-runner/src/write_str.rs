@@ -103,4 +103,14 @@ impl<T: WriteStr + ?Sized> WriteStr for &mut T {
     }
 }
 
+impl<W: io::Write> WriteStr for io::BufWriter<W> {
+    fn write_str(&mut self, s: &str) -> io::Result<()> {
+        io::Write::write_all(self, s.as_bytes())
+    }
+
+    fn write_str_flush(&mut self) -> io::Result<()> {
+        io::Write::flush(self)
+    }
+}
+
 // Add more impls as needed.
@@ -9,27 +9,35 @@ use fixture_data::{
     nextest_tests::{get_expected_test, EXPECTED_TEST_SUITES},
 };
 use nextest_filtering::{Filterset, FiltersetKind, ParseContext};
-use nextest_metadata::{FilterMatch, MismatchReason};
+use nextest_metadata::{FilterMatch, MismatchReason, RustBinaryId};
 use nextest_runner::{
     config::{NextestConfig, RetryPolicy},
     double_spawn::DoubleSpawnInfo,
+    errors::{ChildError, TestRunnerBuildError, UnexpectedStderrError},
     input::InputHandlerKind,
     list::BinaryList,
     platform::BuildPlatforms,
     reporter::{
         events::{
-            ExecutionDescription, ExecutionResult, FinalRunStats, RunStatsFailureKind, UnitKind,
+            ExecutionDescription, ExecutionResult, FinalRunStats, RunStatsFailureKind,
+            SetupScriptEnvMap, TestEventKind, UnitKind,
         },
         UnitErrorDescription,
     },
-    runner::TestRunnerBuilder,
+    runner::{DryRun, RepeatUntilFailure, TestArgsHook, TestRunnerBuilder, VerifyFailures},
     signal::SignalHandlerKind,
     target_runner::TargetRunner,
     test_filter::{RunIgnored, TestFilterBuilder, TestFilterPatterns},
     test_output::{ChildExecutionOutput, ChildOutput},
+    timings::TimingsStore,
 };
 use pretty_assertions::assert_eq;
-use std::{io::Cursor, time::Duration};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::Cursor,
+    num::NonZeroUsize,
+    time::Duration,
+};
 use test_case::test_case;
 
 #[test]
@@ -160,7 +168,9 @@ fn test_run() -> Result<()> {
                     );
                     let run_status = run_statuses.last_status();
 
-                    if run_status.result != make_execution_result(fixture.status, 1) {
+                    if run_status.result.without_leak_details()
+                        != make_execution_result(fixture.status, 1)
+                    {
                         false
                     } else {
                         // Extracting descriptions works for segfaults on Unix but not on Windows.
@@ -285,7 +295,8 @@ fn test_run_ignored() -> Result<()> {
                         fixture.name
                     );
                     let run_status = run_statuses.last_status();
-                    run_status.result == make_execution_result(fixture.status, 1)
+                    run_status.result.without_leak_details()
+                        == make_execution_result(fixture.status, 1)
                 }
             };
             if !valid {
@@ -536,10 +547,30 @@ fn test_retries(retries: Option<RetryPolicy>) -> Result<()> {
                         expected_len,
                     );
 
+                    // All attempts share a test run UUID, but have distinct attempt UUIDs.
+                    let test_run_uuid = run_statuses.last_status().test_run_uuid;
+                    assert!(
+                        run_statuses
+                            .iter()
+                            .all(|status| status.test_run_uuid == test_run_uuid),
+                        "attempts of test {} share a test run UUID",
+                        fixture.name,
+                    );
+                    let attempt_uuids: BTreeSet<_> = run_statuses
+                        .iter()
+                        .map(|status| status.attempt_uuid)
+                        .collect();
+                    assert_eq!(
+                        attempt_uuids.len(),
+                        run_statuses.len(),
+                        "attempts of test {} have distinct attempt UUIDs",
+                        fixture.name,
+                    );
+
                     match run_statuses.describe() {
                         ExecutionDescription::Success { single_status } => {
                             if fixture.status == TestCaseFixtureStatus::Leak {
-                                single_status.result == ExecutionResult::Leak
+                                matches!(single_status.result, ExecutionResult::Leak { .. })
                             } else {
                                 single_status.result == ExecutionResult::Pass
                             }
@@ -576,7 +607,7 @@ fn test_retries(retries: Option<RetryPolicy>) -> Result<()> {
                                 assert!(
                                     matches!(
                                         retry.result,
-                                        ExecutionResult::Fail { .. } | ExecutionResult::Leak
+                                        ExecutionResult::Fail { .. } | ExecutionResult::Leak { .. }
                                     ),
                                     "retry {} should be fail or leak",
                                     retry.retry_data.attempt
@@ -584,7 +615,7 @@ fn test_retries(retries: Option<RetryPolicy>) -> Result<()> {
                             }
                             matches!(
                                 first_status.result,
-                                ExecutionResult::Fail { .. } | ExecutionResult::Leak
+                                ExecutionResult::Fail { .. } | ExecutionResult::Leak { .. }
                             )
                         }
                     }
@@ -612,17 +643,122 @@ fn test_retries(retries: Option<RetryPolicy>) -> Result<()> {
     Ok(())
 }
 
+#[test_case(VerifyFailures::Report ; "report")]
+#[test_case(VerifyFailures::CountFlaky ; "count flaky")]
+fn test_verify_failures(verify_failures: VerifyFailures) -> Result<()> {
+    set_env_vars();
+
+    let test_filter = TestFilterBuilder::default_set(RunIgnored::Default);
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty())?;
+    let config = load_config();
+    let profile = config
+        .profile(NextestConfig::DEFAULT_PROFILE)
+        .expect("default config is valid");
+    let build_platforms = BuildPlatforms::new_with_no_target().unwrap();
+    let profile = profile.apply_build_platforms(&build_platforms);
+
+    // With 3 attempts, test_flaky_mod_4 fails all of them and then passes on
+    // the verification rerun (attempt 4), while test_flaky_mod_6 and
+    // test_failure_assert fail the verification rerun as well.
+    let mut builder = TestRunnerBuilder::default();
+    builder
+        .set_retries(RetryPolicy::new_without_delay(2))
+        .set_verify_failures(verify_failures);
+    let runner = builder
+        .build(
+            &test_list,
+            &profile,
+            vec![],
+            SignalHandlerKind::Noop,
+            InputHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    let (instance_statuses, run_stats) = execute_collect(runner);
+
+    let test_binary = FIXTURE_TARGETS
+        .test_artifacts
+        .get(&RustBinaryId::new("nextest-tests::basic"))
+        .expect("nextest-tests::basic is a test binary");
+    let run_statuses =
+        |name| match &instance_statuses[&(test_binary.binary_path.as_path(), name)].status {
+            InstanceStatus::Finished(run_statuses) => run_statuses.clone(),
+            InstanceStatus::Skipped(reason) => panic!("test {name} skipped: {reason}"),
+        };
+
+    // Passing tests aren't verified.
+    let statuses = run_statuses("test_success");
+    assert!(statuses.verification().is_none(), "test_success passed");
+
+    for name in ["test_flaky_mod_6", "test_failure_assert"] {
+        let statuses = run_statuses(name);
+        let verification = statuses
+            .verification()
+            .unwrap_or_else(|| panic!("{name} failed, so it was verified"));
+        assert!(verification.reproduced(), "{name} failure reproduced");
+        assert_eq!(verification.status.retry_data.attempt, 4);
+        assert_eq!(statuses.len(), 3, "{name}: verification isn't an attempt");
+        assert!(
+            matches!(statuses.describe(), ExecutionDescription::Failure { .. }),
+            "{name} is a failure"
+        );
+    }
+
+    let statuses = run_statuses("test_flaky_mod_4");
+    let verification = statuses
+        .verification()
+        .expect("test_flaky_mod_4 failed all attempts, so it was verified");
+    assert!(
+        !verification.reproduced(),
+        "test_flaky_mod_4 passed on attempt 4"
+    );
+    assert_eq!(statuses.len(), 3);
+    match (verify_failures, statuses.describe()) {
+        (VerifyFailures::Report, ExecutionDescription::Failure { .. }) => {}
+        (
+            VerifyFailures::CountFlaky,
+            ExecutionDescription::Flaky {
+                last_status,
+                prior_statuses,
+            },
+        ) => {
+            assert_eq!(last_status.retry_data.attempt, 4);
+            assert_eq!(prior_statuses.len(), 3);
+        }
+        (verify_failures, describe) => {
+            panic!("for {verify_failures:?}, unexpected description {describe:?}")
+        }
+    }
+
+    let expected_flaky = match verify_failures {
+        VerifyFailures::Report => 0,
+        VerifyFailures::CountFlaky => 1,
+    };
+    assert_eq!(
+        run_stats.flaky, expected_flaky,
+        "verification reruns only count as flaky if configured"
+    );
+
+    Ok(())
+}
+
 #[test]
-fn test_termination() -> Result<()> {
+fn test_repeat_until_failure() -> Result<()> {
     set_env_vars();
 
     let pcx = ParseContext {
         graph: &PACKAGE_GRAPH,
         kind: FiltersetKind::Test,
     };
-    let expr = Filterset::parse("test(/^test_slow_timeout/)".to_owned(), &pcx).unwrap();
+    let expr = Filterset::parse(
+        "test(=test_success) | test(=test_failure_assert)".to_owned(),
+        &pcx,
+    )
+    .unwrap();
     let test_filter = TestFilterBuilder::new(
-        RunIgnored::Only,
+        RunIgnored::Default,
         None,
         TestFilterPatterns::default(),
         vec![expr],
@@ -632,12 +768,17 @@ fn test_termination() -> Result<()> {
     let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty())?;
     let config = load_config();
     let profile = config
-        .profile("with-termination")
-        .expect("with-termination config is valid");
+        .profile(NextestConfig::DEFAULT_PROFILE)
+        .expect("default config is valid");
     let build_platforms = BuildPlatforms::new_with_no_target().unwrap();
     let profile = profile.apply_build_platforms(&build_platforms);
 
-    let runner = TestRunnerBuilder::default()
+    let mut builder = TestRunnerBuilder::default();
+    builder.set_repeat_until_failure(RepeatUntilFailure {
+        max_iterations: NonZeroUsize::new(3).unwrap(),
+        max_duration: None,
+    });
+    let runner = builder
         .build(
             &test_list,
             &profile,
@@ -649,45 +790,1375 @@ fn test_termination() -> Result<()> {
         )
         .unwrap();
 
-    let (instance_statuses, run_stats) = execute_collect(runner);
-    assert_eq!(run_stats.timed_out, 3, "3 tests timed out");
-    for test_name in [
-        "test_slow_timeout",
-        "test_slow_timeout_2",
-        "test_slow_timeout_subprocess",
-    ] {
-        let (_, instance_value) = instance_statuses
-            .iter()
-            .find(|(&(_, name), _)| name == test_name)
-            .unwrap_or_else(|| panic!("{test_name} should be present"));
-        let valid = match &instance_value.status {
-            InstanceStatus::Skipped(_) => panic!("{test_name} should have been run"),
-            InstanceStatus::Finished(run_statuses) => {
-                // This test should not have been retried since retries aren't configured.
-                assert_eq!(
-                    run_statuses.len(),
-                    1,
-                    "{test_name} should have been run exactly once",
-                );
-                let run_status = run_statuses.last_status();
-                // The test should have taken less than 5 seconds (most relevant for
-                // test_slow_timeout_subprocess -- without job objects it gets stuck on Windows
-                // until the subprocess exits.)
+    let mut retries_started = Vec::new();
+    let mut run_statuses = BTreeMap::new();
+    let run_stats = runner
+        .execute(|event| match event.kind {
+            TestEventKind::TestRetryStarted {
+                test_instance,
+                retry_data,
+                ..
+            } => {
+                retries_started.push(format!("{} {}", test_instance.name, retry_data.attempt));
+            }
+            TestEventKind::TestFinished {
+                test_instance,
+                run_statuses: statuses,
+                ..
+            } => {
+                run_statuses.insert(test_instance.name, statuses);
+            }
+            _ => {}
+        })
+        .unwrap();
+
+    // The passing test is run for every iteration, and the failing test isn't repeated.
+    assert_eq!(retries_started, ["test_success 2", "test_success 3"]);
+
+    let statuses = &run_statuses["test_success"];
+    assert_eq!(statuses.passed_iterations(), Some(2));
+    assert_eq!(statuses.len(), 1, "only the last iteration is recorded");
+    assert_eq!(statuses.last_status().retry_data.attempt, 3);
+    assert!(
+        matches!(statuses.describe(), ExecutionDescription::Success { .. }),
+        "test_success passed"
+    );
+
+    let statuses = &run_statuses["test_failure_assert"];
+    assert_eq!(statuses.passed_iterations(), Some(0));
+    assert_eq!(statuses.len(), 1, "failing tests aren't retried");
+    assert!(
+        matches!(statuses.describe(), ExecutionDescription::Failure { .. }),
+        "test_failure_assert failed"
+    );
+
+    assert_eq!(run_stats.passed, 1);
+    assert_eq!(run_stats.failed, 1);
+    assert_eq!(run_stats.flaky, 0);
+    assert_eq!(run_stats.iterations_before_first_failure, Some(0));
+
+    Ok(())
+}
+
+#[test]
+fn test_retry_delay_started() -> Result<()> {
+    set_env_vars();
+
+    let pcx = ParseContext {
+        graph: &PACKAGE_GRAPH,
+        kind: FiltersetKind::Test,
+    };
+    let expr = Filterset::parse("test(=test_failure_assert)".to_owned(), &pcx).unwrap();
+    let test_filter = TestFilterBuilder::new(
+        RunIgnored::Default,
+        None,
+        TestFilterPatterns::default(),
+        vec![expr],
+    )
+    .unwrap();
+
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty())?;
+    let config = load_config();
+    let profile = config
+        .profile(NextestConfig::DEFAULT_PROFILE)
+        .expect("default config is valid");
+    let build_platforms = BuildPlatforms::new_with_no_target().unwrap();
+    let profile = profile.apply_build_platforms(&build_platforms);
+
+    let delay = Duration::from_millis(100);
+    let mut builder = TestRunnerBuilder::default();
+    builder.set_retries(RetryPolicy::Fixed {
+        count: 2,
+        delay,
+        jitter: false,
+    });
+    let runner = builder
+        .build(
+            &test_list,
+            &profile,
+            vec![],
+            SignalHandlerKind::Noop,
+            InputHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    let mut events = Vec::new();
+    runner
+        .execute(|event| match event.kind {
+            TestEventKind::TestAttemptFailedWillRetry { run_status, .. } => {
+                events.push(format!("will-retry {}", run_status.retry_data.attempt));
+            }
+            TestEventKind::TestRetryDelayStarted {
+                retry_data,
+                remaining,
+                resume_time,
+                ..
+            } => {
+                assert_eq!(remaining, delay, "no time has passed yet");
+                let resume_time = resume_time.expect("resume time is representable");
+                let until_resume = (resume_time - event.timestamp).to_std().unwrap();
                 assert!(
-                    run_status.time_taken < Duration::from_secs(5),
-                    "{test_name} should have taken less than 5 seconds, actually took {:?}",
-                    run_status.time_taken
+                    until_resume <= delay,
+                    "resume time {resume_time} is within {delay:?} of {}",
+                    event.timestamp
                 );
-                run_status.result == ExecutionResult::Timeout
+                events.push(format!("delay-started {}", retry_data.attempt));
             }
-        };
-        if !valid {
-            panic!(
-                "for test_slow_timeout, mismatch in status: expected timeout, actual {:?}",
-                instance_value.status
-            );
-        }
+            TestEventKind::TestRetryStarted { retry_data, .. } => {
+                events.push(format!("retry-started {}", retry_data.attempt));
+            }
+            _ => {}
+        })
+        .unwrap();
+
+    assert_eq!(
+        events,
+        [
+            "will-retry 1",
+            "delay-started 2",
+            "retry-started 2",
+            "will-retry 2",
+            "delay-started 3",
+            "retry-started 3",
+        ],
+    );
+
+    Ok(())
+}
+
+#[test_case(
+    "test(=test_success) | test(=test_cwd)",
+    &[
+        "phase-started smoke 1",
+        "finished test_success",
+        "phase-finished smoke 1/1 passed",
+        "phase-started main 1",
+        "finished test_cwd",
+        "phase-finished main 1/1 passed",
+    ],
+    false
+
+    ; "smoke tests pass"
+)]
+#[test_case(
+    "test(=test_failure_assert) | test(=test_cwd)",
+    &[
+        "phase-started smoke 1",
+        "finished test_failure_assert",
+        "phase-finished smoke 0/1 passed",
+        "cancel SmokeTestFailure",
+    ],
+    true
+
+    ; "smoke tests fail"
+)]
+fn test_smoke_gate(filter: &str, expected_events: &[&str], smoke_failed: bool) -> Result<()> {
+    set_env_vars();
+
+    let pcx = ParseContext {
+        graph: &PACKAGE_GRAPH,
+        kind: FiltersetKind::Test,
+    };
+    let expr = Filterset::parse(filter.to_owned(), &pcx).unwrap();
+    let test_filter = TestFilterBuilder::new(
+        RunIgnored::Default,
+        None,
+        TestFilterPatterns::default(),
+        vec![expr],
+    )
+    .unwrap();
+
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty())?;
+    let config = load_config();
+    let profile = config
+        .profile("with-smoke")
+        .expect("with-smoke config is valid");
+    let build_platforms = BuildPlatforms::new_with_no_target().unwrap();
+    let profile = profile.apply_build_platforms(&build_platforms);
+
+    let runner = TestRunnerBuilder::default()
+        .build(
+            &test_list,
+            &profile,
+            vec![],
+            SignalHandlerKind::Noop,
+            InputHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    let mut events = Vec::new();
+    let run_stats = runner
+        .execute(|event| match event.kind {
+            TestEventKind::PhaseStarted { phase, run_count } => {
+                events.push(format!("phase-started {phase:?} {run_count}").to_lowercase());
+            }
+            TestEventKind::PhaseFinished {
+                phase, run_stats, ..
+            } => {
+                events.push(format!(
+                    "phase-finished {} {}/{} passed",
+                    format!("{phase:?}").to_lowercase(),
+                    run_stats.passed,
+                    run_stats.finished_count
+                ));
+            }
+            TestEventKind::TestFinished { test_instance, .. } => {
+                events.push(format!("finished {}", test_instance.name));
+            }
+            TestEventKind::RunBeginCancel { reason, .. } => {
+                events.push(format!("cancel {reason:?}"));
+            }
+            _ => {}
+        })
+        .unwrap();
+
+    assert_eq!(events, expected_events);
+    assert_eq!(run_stats.initial_run_count, 2);
+    assert_eq!(run_stats.smoke_failed, smoke_failed);
+    if smoke_failed {
+        assert_eq!(run_stats.finished_count, 1, "main phase was not run");
+        assert!(
+            matches!(
+                run_stats.summarize_final(),
+                FinalRunStats::Failed(RunStatsFailureKind::Test { not_run: 1, .. })
+            ),
+            "run failed with one test not run: {run_stats:?}"
+        );
+    } else {
+        assert_eq!(run_stats.finished_count, 2, "both phases were run");
     }
 
     Ok(())
 }
+
+#[test_case("default", &["test_cwd", "test_success"]; "without smoke tests")]
+#[test_case("with-smoke", &["test_success", "test_cwd"]; "smoke tests first")]
+fn test_plan_order(profile_name: &str, expected_order: &[&str]) -> Result<()> {
+    set_env_vars();
+
+    let pcx = ParseContext {
+        graph: &PACKAGE_GRAPH,
+        kind: FiltersetKind::Test,
+    };
+    let expr = Filterset::parse("test(=test_success) | test(=test_cwd)".to_owned(), &pcx).unwrap();
+    let test_filter = TestFilterBuilder::new(
+        RunIgnored::Default,
+        None,
+        TestFilterPatterns::default(),
+        vec![expr],
+    )
+    .unwrap();
+
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty())?;
+    let config = load_config();
+    let profile = config
+        .profile(profile_name)
+        .expect("profile config is valid");
+    let build_platforms = BuildPlatforms::new_with_no_target().unwrap();
+    let profile = profile.apply_build_platforms(&build_platforms);
+
+    let plan = TestRunnerBuilder::default().plan(&test_list, &profile, &TimingsStore::new());
+    let order: Vec<_> = plan.tests().iter().map(|test| test.test_name).collect();
+    assert_eq!(order, expected_order);
+
+    Ok(())
+}
+
+#[test]
+fn test_attachments() -> Result<()> {
+    set_env_vars();
+
+    let pcx = ParseContext {
+        graph: &PACKAGE_GRAPH,
+        kind: FiltersetKind::Test,
+    };
+    let expr = Filterset::parse("test(=test_success) | test(=test_cwd)".to_owned(), &pcx).unwrap();
+    let test_filter = TestFilterBuilder::new(
+        RunIgnored::Default,
+        None,
+        TestFilterPatterns::default(),
+        vec![expr],
+    )
+    .unwrap();
+
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty())?;
+    let config = load_config();
+    let profile = config
+        .profile("with-attachments")
+        .expect("with-attachments config is valid");
+    let build_platforms = BuildPlatforms::new_with_no_target().unwrap();
+    let profile = profile.apply_build_platforms(&build_platforms);
+
+    let runner = TestRunnerBuilder::default()
+        .build(
+            &test_list,
+            &profile,
+            vec![],
+            SignalHandlerKind::Noop,
+            InputHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    let mut attachments = BTreeMap::new();
+    runner
+        .execute(|event| {
+            if let TestEventKind::TestFinished {
+                test_instance,
+                attachments: test_attachments,
+                ..
+            } = event.kind
+            {
+                attachments.insert(
+                    test_instance.name.to_owned(),
+                    (test_instance.suite_info.cwd.clone(), test_attachments),
+                );
+            }
+        })
+        .unwrap();
+
+    // Attachments are resolved against the test's working directory.
+    let (cwd, success_attachments) = &attachments["test_success"];
+    assert_eq!(success_attachments.found, vec![cwd.join("Cargo.toml")]);
+    assert_eq!(
+        success_attachments.missing,
+        vec![cwd.join("missing-attachment.txt")]
+    );
+
+    let (_, cwd_attachments) = &attachments["test_cwd"];
+    assert!(
+        cwd_attachments.is_empty(),
+        "no attachments configured for test_cwd: {cwd_attachments:?}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_binary_events() -> Result<()> {
+    set_env_vars();
+
+    let pcx = ParseContext {
+        graph: &PACKAGE_GRAPH,
+        kind: FiltersetKind::Test,
+    };
+    let expr = Filterset::parse("test(=test_success) | test(=test_cwd)".to_owned(), &pcx).unwrap();
+    let test_filter = TestFilterBuilder::new(
+        RunIgnored::Default,
+        None,
+        TestFilterPatterns::default(),
+        vec![expr],
+    )
+    .unwrap();
+
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty())?;
+    let config = load_config();
+    let profile = config
+        .profile(NextestConfig::DEFAULT_PROFILE)
+        .expect("default config is valid");
+    let build_platforms = BuildPlatforms::new_with_no_target().unwrap();
+    let profile = profile.apply_build_platforms(&build_platforms);
+
+    let runner = TestRunnerBuilder::default()
+        .build(
+            &test_list,
+            &profile,
+            vec![],
+            SignalHandlerKind::Noop,
+            InputHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    // binary ID -> (run count, finished run stats)
+    let mut binaries = BTreeMap::new();
+    let mut run_stats = None;
+    let mut package_stats = None;
+    runner
+        .execute(|event| match event.kind {
+            TestEventKind::BinaryStarted {
+                binary_id,
+                run_count,
+                ..
+            } => {
+                let prev = binaries.insert(binary_id.clone(), (run_count, None));
+                assert!(prev.is_none(), "{binary_id} started more than once");
+            }
+            TestEventKind::TestStarted { test_instance, .. }
+            | TestEventKind::TestSkipped { test_instance, .. } => {
+                let binary_id = &test_instance.suite_info.binary_id;
+                let (_, finished) = binaries
+                    .get(binary_id)
+                    .unwrap_or_else(|| panic!("{binary_id} started before its tests"));
+                assert!(finished.is_none(), "{binary_id} finished before its tests");
+            }
+            TestEventKind::BinaryFinished {
+                binary_id,
+                run_stats,
+                ..
+            } => {
+                let (_, finished) = binaries
+                    .get_mut(binary_id)
+                    .unwrap_or_else(|| panic!("{binary_id} finished without starting"));
+                assert!(finished.is_none(), "{binary_id} finished more than once");
+                *finished = Some(run_stats);
+            }
+            TestEventKind::RunFinished {
+                run_stats: stats,
+                package_stats: pkg_stats,
+                ..
+            } => {
+                run_stats = Some(stats);
+                package_stats = Some(pkg_stats);
+            }
+            _ => {}
+        })
+        .unwrap();
+
+    let run_stats = run_stats.expect("run finished");
+    let package_stats = package_stats.expect("run finished");
+    assert_eq!(
+        binaries.len(),
+        test_list.iter().count(),
+        "every listed binary is reported"
+    );
+    let mut total_run_count = 0;
+    let mut total_passed = 0;
+    for (binary_id, (run_count, finished)) in &binaries {
+        let finished = finished
+            .as_ref()
+            .unwrap_or_else(|| panic!("{binary_id} didn't finish"));
+        assert_eq!(
+            finished.finished_count, *run_count,
+            "{binary_id}: all tests that were meant to run finished"
+        );
+        total_run_count += run_count;
+        total_passed += finished.passed;
+    }
+    assert_eq!(total_run_count, run_stats.initial_run_count);
+    assert_eq!(total_passed, run_stats.passed);
+
+    // Per-package stats add up to the totals, and packages where every test
+    // was skipped are still present.
+    let packages: BTreeSet<_> = test_list
+        .iter()
+        .filter(|suite| suite.status.test_count() > 0)
+        .map(|suite| suite.package.name())
+        .collect();
+    assert_eq!(
+        package_stats
+            .iter()
+            .map(|(name, _)| name)
+            .collect::<BTreeSet<_>>(),
+        packages,
+        "every package with tests is reported"
+    );
+    let nextest_tests = package_stats
+        .get("nextest-tests")
+        .expect("nextest-tests is present");
+    assert_eq!(nextest_tests.initial_run_count, 2);
+    assert_eq!(nextest_tests.passed, 2);
+    assert!(nextest_tests.skipped > 0, "other tests were skipped");
+    for (name, stats) in package_stats.iter() {
+        if name != "nextest-tests" {
+            assert_eq!(stats.finished_count, 0, "{name}: no tests ran");
+            assert!(stats.skipped > 0, "{name}: tests were skipped");
+        }
+    }
+    let skipped: usize = package_stats.iter().map(|(_, stats)| stats.skipped).sum();
+    assert_eq!(skipped, run_stats.skipped);
+
+    Ok(())
+}
+
+#[test]
+fn test_dry_run() -> Result<()> {
+    set_env_vars();
+
+    let test_filter = TestFilterBuilder::default_set(RunIgnored::Default);
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty())?;
+    let config = load_config();
+    let profile = config
+        .profile(NextestConfig::DEFAULT_PROFILE)
+        .expect("default config is valid");
+    let build_platforms = BuildPlatforms::new_with_no_target().unwrap();
+    let profile = profile.apply_build_platforms(&build_platforms);
+
+    // With an instant dry run, every test passes on the first attempt,
+    // including the ones that would fail if run.
+    let mut builder = TestRunnerBuilder::default();
+    builder.set_dry_run(DryRun::Instant);
+    let runner = builder
+        .build(
+            &test_list,
+            &profile,
+            vec![],
+            SignalHandlerKind::Noop,
+            InputHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    let (instance_statuses, run_stats) = execute_collect(runner);
+    assert!(run_stats.initial_run_count > 0, "some tests are run");
+    assert_eq!(run_stats.finished_count, run_stats.initial_run_count);
+    assert_eq!(run_stats.passed, run_stats.initial_run_count);
+    assert_eq!(run_stats.failed_count(), 0, "no tests failed");
+    for ((_, name), instance_value) in &instance_statuses {
+        if let InstanceStatus::Finished(run_statuses) = &instance_value.status {
+            assert_eq!(run_statuses.len(), 1, "{name} was run exactly once");
+            assert_eq!(
+                run_statuses.last_status().result,
+                ExecutionResult::Pass,
+                "{name} passed"
+            );
+        }
+    }
+
+    // A simulated dry run takes the given amount of time per test.
+    let pcx = ParseContext {
+        graph: &PACKAGE_GRAPH,
+        kind: FiltersetKind::Test,
+    };
+    let expr = Filterset::parse("test(=test_failure_assert)".to_owned(), &pcx).unwrap();
+    let test_filter = TestFilterBuilder::new(
+        RunIgnored::Default,
+        None,
+        TestFilterPatterns::default(),
+        vec![expr],
+    )
+    .unwrap();
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty())?;
+
+    let mut builder = TestRunnerBuilder::default();
+    builder.set_dry_run(DryRun::Simulated(Duration::from_millis(100)));
+    let runner = builder
+        .build(
+            &test_list,
+            &profile,
+            vec![],
+            SignalHandlerKind::Noop,
+            InputHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    let (instance_statuses, run_stats) = execute_collect(runner);
+    assert_eq!(run_stats.passed, 1, "test_failure_assert passed");
+    let (_, instance_value) = instance_statuses
+        .iter()
+        .find(|(&(_, name), _)| name == "test_failure_assert")
+        .expect("test_failure_assert should be present");
+    let InstanceStatus::Finished(run_statuses) = &instance_value.status else {
+        panic!("test_failure_assert should have been run");
+    };
+    assert!(
+        run_statuses.last_status().time_taken >= Duration::from_millis(100),
+        "simulated test took at least 100ms, actually took {:?}",
+        run_statuses.last_status().time_taken
+    );
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_peak_rss() -> Result<()> {
+    set_env_vars();
+
+    let pcx = ParseContext {
+        graph: &PACKAGE_GRAPH,
+        kind: FiltersetKind::Test,
+    };
+    let expr = Filterset::parse("test(=test_success)".to_owned(), &pcx).unwrap();
+    let test_filter = TestFilterBuilder::new(
+        RunIgnored::Default,
+        None,
+        TestFilterPatterns::default(),
+        vec![expr],
+    )
+    .unwrap();
+
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty())?;
+    let config = load_config();
+    let profile = config
+        .profile(NextestConfig::DEFAULT_PROFILE)
+        .expect("default config is valid");
+    let build_platforms = BuildPlatforms::new_with_no_target().unwrap();
+    let profile = profile.apply_build_platforms(&build_platforms);
+
+    let runner = TestRunnerBuilder::default()
+        .build(
+            &test_list,
+            &profile,
+            vec![],
+            SignalHandlerKind::Noop,
+            InputHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    let (instance_statuses, run_stats) = execute_collect(runner);
+    assert_eq!(run_stats.finished_count, 1, "1 test was run");
+
+    let test_binary = FIXTURE_TARGETS
+        .test_artifacts
+        .get(&RustBinaryId::new("nextest-tests::basic"))
+        .expect("nextest-tests::basic is a test binary");
+    let status =
+        match &instance_statuses[&(test_binary.binary_path.as_path(), "test_success")].status {
+            InstanceStatus::Finished(run_statuses) => run_statuses.last_status().clone(),
+            InstanceStatus::Skipped(reason) => panic!("test_success skipped: {reason}"),
+        };
+    let peak_rss = status.peak_rss.expect("peak RSS is sampled on Linux");
+    assert!(peak_rss > 0, "peak RSS is positive: {peak_rss}");
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_orphan_processes() -> Result<()> {
+    set_env_vars();
+
+    let pcx = ParseContext {
+        graph: &PACKAGE_GRAPH,
+        kind: FiltersetKind::Test,
+    };
+    let expr = Filterset::parse(
+        "test(=test_subprocess_orphaned) | test(=test_success)".to_owned(),
+        &pcx,
+    )
+    .unwrap();
+    let test_filter = TestFilterBuilder::new(
+        RunIgnored::Default,
+        None,
+        TestFilterPatterns::default(),
+        vec![expr],
+    )
+    .unwrap();
+
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty())?;
+    let config = load_config();
+    let profile = config
+        .profile("with-orphan-processes")
+        .expect("with-orphan-processes config is valid");
+    let build_platforms = BuildPlatforms::new_with_no_target().unwrap();
+    let profile = profile.apply_build_platforms(&build_platforms);
+
+    let runner = TestRunnerBuilder::default()
+        .build(
+            &test_list,
+            &profile,
+            vec![],
+            SignalHandlerKind::Noop,
+            InputHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    let (instance_statuses, run_stats) = execute_collect(runner);
+    assert_eq!(run_stats.finished_count, 2, "2 tests were run");
+    assert_eq!(run_stats.passed, 2, "tests with orphans still pass");
+    assert_eq!(run_stats.leaky, 1, "test_subprocess_orphaned is leaky");
+
+    let test_binary = FIXTURE_TARGETS
+        .test_artifacts
+        .get(&RustBinaryId::new("nextest-tests::basic"))
+        .expect("nextest-tests::basic is a test binary");
+    let run_status =
+        |name| match &instance_statuses[&(test_binary.binary_path.as_path(), name)].status {
+            InstanceStatus::Finished(run_statuses) => run_statuses.last_status().clone(),
+            InstanceStatus::Skipped(reason) => panic!("test {name} skipped: {reason}"),
+        };
+
+    assert_eq!(run_status("test_success").result, ExecutionResult::Pass);
+
+    // The sleep left behind by the double fork in test_subprocess_orphaned is found, and reported
+    // along with its command.
+    let status = run_status("test_subprocess_orphaned");
+    assert!(
+        matches!(status.result, ExecutionResult::Leak { .. }),
+        "unexpected result: {:?}",
+        status.result
+    );
+    let error = match &status.output {
+        ChildExecutionOutput::Output {
+            errors: Some(errors),
+            ..
+        } => errors.to_string(),
+        other => panic!("orphan processes should be reported as an error, found {other:?}"),
+    };
+    assert!(
+        error.contains("test left behind 1 process in its process group, which was killed")
+            && error.contains("(sleep)"),
+        "unexpected error: {error}"
+    );
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_lingering_threads() -> Result<()> {
+    set_env_vars();
+
+    let pcx = ParseContext {
+        graph: &PACKAGE_GRAPH,
+        kind: FiltersetKind::Test,
+    };
+    let expr = Filterset::parse(
+        "test(=test_thread_lingers) | test(=test_success)".to_owned(),
+        &pcx,
+    )
+    .unwrap();
+    let test_filter = TestFilterBuilder::new(
+        RunIgnored::Default,
+        None,
+        TestFilterPatterns::default(),
+        vec![expr],
+    )
+    .unwrap();
+
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty())?;
+    let config = load_config();
+    let profile = config
+        .profile("with-lingering-threads")
+        .expect("with-lingering-threads config is valid");
+    let build_platforms = BuildPlatforms::new_with_no_target().unwrap();
+    let profile = profile.apply_build_platforms(&build_platforms);
+
+    let runner = TestRunnerBuilder::default()
+        .build(
+            &test_list,
+            &profile,
+            vec![],
+            SignalHandlerKind::Noop,
+            InputHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    let (instance_statuses, run_stats) = execute_collect(runner);
+    assert_eq!(run_stats.finished_count, 2, "2 tests were run");
+    assert_eq!(
+        run_stats.passed, 2,
+        "tests with lingering threads still pass"
+    );
+    assert_eq!(run_stats.leaky, 1, "test_thread_lingers is leaky");
+
+    let test_binary = FIXTURE_TARGETS
+        .test_artifacts
+        .get(&RustBinaryId::new("nextest-tests::basic"))
+        .expect("nextest-tests::basic is a test binary");
+    let run_status =
+        |name| match &instance_statuses[&(test_binary.binary_path.as_path(), name)].status {
+            InstanceStatus::Finished(run_statuses) => run_statuses.last_status().clone(),
+            InstanceStatus::Skipped(reason) => panic!("test {name} skipped: {reason}"),
+        };
+
+    assert_eq!(run_status("test_success").result, ExecutionResult::Pass);
+
+    // The thread spawned by test_thread_lingers is found, and reported along with its name.
+    let status = run_status("test_thread_lingers");
+    assert!(
+        matches!(status.result, ExecutionResult::Leak { .. }),
+        "unexpected result: {:?}",
+        status.result
+    );
+    let error = match &status.output {
+        ChildExecutionOutput::Output {
+            errors: Some(errors),
+            ..
+        } => errors.to_string(),
+        other => panic!("lingering threads should be reported as an error, found {other:?}"),
+    };
+    assert!(
+        error.contains("test left 1 thread running after it finished")
+            && error.contains("(lingering)"),
+        "unexpected error: {error}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_fail_on_stderr() -> Result<()> {
+    set_env_vars();
+
+    let pcx = ParseContext {
+        graph: &PACKAGE_GRAPH,
+        kind: FiltersetKind::Test,
+    };
+    // test_success_should_panic passes, but its panic message is written to standard error.
+    let expr = Filterset::parse(
+        "test(=test_success_should_panic) | test(=test_success)".to_owned(),
+        &pcx,
+    )
+    .unwrap();
+    let test_filter = TestFilterBuilder::new(
+        RunIgnored::Default,
+        None,
+        TestFilterPatterns::default(),
+        vec![expr],
+    )
+    .unwrap();
+
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty())?;
+    let config = load_config();
+    let profile = config
+        .profile("with-fail-on-stderr")
+        .expect("with-fail-on-stderr config is valid");
+    let build_platforms = BuildPlatforms::new_with_no_target().unwrap();
+    let profile = profile.apply_build_platforms(&build_platforms);
+
+    let runner = TestRunnerBuilder::default()
+        .build(
+            &test_list,
+            &profile,
+            vec![],
+            SignalHandlerKind::Noop,
+            InputHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    let (instance_statuses, run_stats) = execute_collect(runner);
+    assert_eq!(run_stats.finished_count, 2, "2 tests were run");
+    assert_eq!(run_stats.passed, 1, "test_success still passes");
+    assert_eq!(run_stats.failed, 1, "test_success_should_panic fails");
+
+    let test_binary = FIXTURE_TARGETS
+        .test_artifacts
+        .get(&RustBinaryId::new("nextest-tests::basic"))
+        .expect("nextest-tests::basic is a test binary");
+    let run_status =
+        |name| match &instance_statuses[&(test_binary.binary_path.as_path(), name)].status {
+            InstanceStatus::Finished(run_statuses) => run_statuses.last_status().clone(),
+            InstanceStatus::Skipped(reason) => panic!("test {name} skipped: {reason}"),
+        };
+
+    assert_eq!(run_status("test_success").result, ExecutionResult::Pass);
+
+    let status = run_status("test_success_should_panic");
+    assert_eq!(
+        status.result,
+        ExecutionResult::Fail {
+            abort_status: None,
+            leaked: false,
+        }
+    );
+    let errors = match &status.output {
+        ChildExecutionOutput::Output {
+            errors: Some(errors),
+            ..
+        } => errors,
+        other => panic!("unexpected stderr should be reported as an error, found {other:?}"),
+    };
+    let lines: Vec<_> = errors
+        .iter()
+        .map(|error| match error {
+            ChildError::UnexpectedStderr(UnexpectedStderrError { line }) => line.as_str(),
+            other => panic!("unexpected error: {other}"),
+        })
+        .collect();
+    assert_eq!(lines.len(), 1, "exactly one error reported");
+    assert!(
+        lines[0].contains("panicked at"),
+        "the first line of stderr is reported: {}",
+        lines[0]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_extra_env() -> Result<()> {
+    set_env_vars();
+
+    let pcx = ParseContext {
+        graph: &PACKAGE_GRAPH,
+        kind: FiltersetKind::Test,
+    };
+    let expr = Filterset::parse("test(=test_success)".to_owned(), &pcx).unwrap();
+    let test_filter = TestFilterBuilder::new(
+        RunIgnored::Default,
+        None,
+        TestFilterPatterns::default(),
+        vec![expr],
+    )
+    .unwrap();
+
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty())?;
+    let config = load_config();
+    let profile = config
+        .profile(NextestConfig::DEFAULT_PROFILE)
+        .expect("default config is valid");
+    let build_platforms = BuildPlatforms::new_with_no_target().unwrap();
+    let profile = profile.apply_build_platforms(&build_platforms);
+
+    let build_runner = |env_map: BTreeMap<String, String>| {
+        let mut builder = TestRunnerBuilder::default();
+        builder.set_extra_env(SetupScriptEnvMap { env_map });
+        builder.build(
+            &test_list,
+            &profile,
+            vec![],
+            SignalHandlerKind::Noop,
+            InputHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+    };
+
+    // Keys reserved for nextest are rejected.
+    let error = build_runner(BTreeMap::from([(
+        "NEXTEST_FOO".to_owned(),
+        "bar".to_owned(),
+    )]))
+    .expect_err("NEXTEST-prefixed keys are rejected");
+    assert!(
+        matches!(&error, TestRunnerBuildError::ExtraEnvReservedKey { key } if key == "NEXTEST_FOO"),
+        "unexpected error: {error}"
+    );
+
+    // Other keys are passed through to tests: test_success fails if MY_ENV_VAR is set.
+    let runner = build_runner(BTreeMap::from([(
+        "MY_ENV_VAR".to_owned(),
+        "my-env-var".to_owned(),
+    )]))
+    .unwrap();
+    let (_, run_stats) = execute_collect(runner);
+    assert_eq!(run_stats.finished_count, 1, "1 test was run");
+    assert_eq!(
+        run_stats.failed, 1,
+        "test_success sees MY_ENV_VAR and fails"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_started_command_line() -> Result<()> {
+    set_env_vars();
+
+    let pcx = ParseContext {
+        graph: &PACKAGE_GRAPH,
+        kind: FiltersetKind::Test,
+    };
+    let expr = Filterset::parse("test(=test_success)".to_owned(), &pcx).unwrap();
+    let test_filter = TestFilterBuilder::new(
+        RunIgnored::Default,
+        None,
+        TestFilterPatterns::default(),
+        vec![expr],
+    )
+    .unwrap();
+
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty())?;
+    let config = load_config();
+    let profile = config
+        .profile("with-redact-env")
+        .expect("with-redact-env config is valid");
+    let build_platforms = BuildPlatforms::new_with_no_target().unwrap();
+    let profile = profile.apply_build_platforms(&build_platforms);
+
+    let mut builder = TestRunnerBuilder::default();
+    builder.set_extra_env(SetupScriptEnvMap {
+        env_map: BTreeMap::from([
+            ("MY_SECRET".to_owned(), "hunter2".to_owned()),
+            ("MY_VISIBLE_VAR".to_owned(), "visible".to_owned()),
+        ]),
+    });
+    let runner = builder
+        .build(
+            &test_list,
+            &profile,
+            vec![],
+            SignalHandlerKind::Noop,
+            InputHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    let mut started = Vec::new();
+    runner
+        .execute(|event| {
+            if let TestEventKind::TestStarted {
+                test_instance,
+                command_line,
+                extra_env,
+                ..
+            } = event.kind
+            {
+                started.push((test_instance.name, command_line, extra_env));
+            }
+        })
+        .unwrap();
+
+    assert_eq!(started.len(), 1, "one test was started");
+    let (name, command_line, extra_env) = &started[0];
+    assert_eq!(*name, "test_success");
+    assert_eq!(
+        command_line[1..],
+        ["--exact", "test_success", "--nocapture"],
+        "command line has the expected arguments"
+    );
+    assert_eq!(extra_env.get("NEXTEST").map(String::as_str), Some("1"));
+    assert!(
+        extra_env.contains_key("NEXTEST_RUN_ID"),
+        "run ID is reported: {extra_env:?}"
+    );
+    assert_eq!(
+        extra_env.get("MY_VISIBLE_VAR").map(String::as_str),
+        Some("visible")
+    );
+    assert_eq!(
+        extra_env.get("MY_SECRET").map(String::as_str),
+        Some("[redacted]"),
+        "variables matching redact-env are redacted"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_args_hook() -> Result<()> {
+    set_env_vars();
+
+    let pcx = ParseContext {
+        graph: &PACKAGE_GRAPH,
+        kind: FiltersetKind::Test,
+    };
+    let expr = Filterset::parse("test(=test_success) | test(=test_cwd)".to_owned(), &pcx).unwrap();
+    let test_filter = TestFilterBuilder::new(
+        RunIgnored::Default,
+        None,
+        TestFilterPatterns::default(),
+        vec![expr],
+    )
+    .unwrap();
+
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty())?;
+    let config = load_config();
+    let profile = config
+        .profile(NextestConfig::DEFAULT_PROFILE)
+        .expect("default config is valid");
+    let build_platforms = BuildPlatforms::new_with_no_target().unwrap();
+    let profile = profile.apply_build_platforms(&build_platforms);
+
+    let mut builder = TestRunnerBuilder::default();
+    builder.set_test_args_hook(TestArgsHook::new(|test_instance| {
+        if test_instance.name == "test_success" {
+            vec!["--test-threads=1".to_owned()]
+        } else {
+            Vec::new()
+        }
+    }));
+    let runner = builder
+        .build(
+            &test_list,
+            &profile,
+            vec![],
+            SignalHandlerKind::Noop,
+            InputHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    let mut started = BTreeMap::new();
+    let run_stats = runner
+        .execute(|event| {
+            if let TestEventKind::TestStarted {
+                test_instance,
+                command_line,
+                ..
+            } = event.kind
+            {
+                started.insert(test_instance.name, command_line);
+            }
+        })
+        .unwrap();
+
+    assert_eq!(
+        run_stats.passed, 2,
+        "both tests pass with the extra arguments"
+    );
+    assert_eq!(
+        started["test_success"][1..],
+        ["--exact", "test_success", "--nocapture", "--test-threads=1"],
+        "extra arguments are appended after nextest's own"
+    );
+    assert_eq!(
+        started["test_cwd"][1..],
+        ["--exact", "test_cwd", "--nocapture"],
+        "hook can return no arguments"
+    );
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_teardown_scripts() -> Result<()> {
+    set_env_vars();
+
+    let pcx = ParseContext {
+        graph: &PACKAGE_GRAPH,
+        kind: FiltersetKind::Test,
+    };
+    let expr = Filterset::parse(
+        "test(=test_success) | test(=test_failure_assert)".to_owned(),
+        &pcx,
+    )
+    .unwrap();
+    let test_filter = TestFilterBuilder::new(
+        RunIgnored::Default,
+        None,
+        TestFilterPatterns::default(),
+        vec![expr],
+    )
+    .unwrap();
+
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty())?;
+    let config = load_config();
+    let profile = config
+        .profile("with-teardown")
+        .expect("with-teardown config is valid");
+    let build_platforms = BuildPlatforms::new_with_no_target().unwrap();
+    let profile = profile.apply_build_platforms(&build_platforms);
+
+    let runner = TestRunnerBuilder::default()
+        .build(
+            &test_list,
+            &profile,
+            vec![],
+            SignalHandlerKind::Noop,
+            InputHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    let mut teardown_results = Vec::new();
+    let mut tests_finished_before_teardown = true;
+    let run_stats = runner
+        .execute(|event| match event.kind {
+            TestEventKind::TeardownScriptFinished {
+                script_id,
+                run_status,
+                ..
+            } => {
+                teardown_results.push((script_id.to_string(), run_status.result.is_success()));
+            }
+            TestEventKind::TestFinished { .. } => {
+                tests_finished_before_teardown &= teardown_results.is_empty();
+            }
+            _ => {}
+        })
+        .unwrap();
+
+    assert!(
+        tests_finished_before_teardown,
+        "teardown scripts are run after all tests have finished"
+    );
+    // check-run-outcome passes if the run's statistics were passed in through the environment.
+    assert_eq!(
+        teardown_results,
+        [
+            ("check-run-outcome".to_owned(), true),
+            ("failing-teardown".to_owned(), false),
+        ]
+    );
+    assert_eq!(run_stats.teardown_scripts_finished_count, 2);
+    assert_eq!(run_stats.teardown_scripts_passed, 1);
+    assert_eq!(run_stats.teardown_scripts_failed, 1);
+    assert_eq!(
+        run_stats.summarize_final(),
+        FinalRunStats::Failed(RunStatsFailureKind::Test {
+            initial_run_count: 2,
+            not_run: 0,
+        }),
+        "teardown script failures don't change the outcome of the run"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_termination() -> Result<()> {
+    set_env_vars();
+
+    let pcx = ParseContext {
+        graph: &PACKAGE_GRAPH,
+        kind: FiltersetKind::Test,
+    };
+    let expr = Filterset::parse("test(/^test_slow_timeout/)".to_owned(), &pcx).unwrap();
+    let test_filter = TestFilterBuilder::new(
+        RunIgnored::Only,
+        None,
+        TestFilterPatterns::default(),
+        vec![expr],
+    )
+    .unwrap();
+
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty())?;
+    let config = load_config();
+    let profile = config
+        .profile("with-termination")
+        .expect("with-termination config is valid");
+    let build_platforms = BuildPlatforms::new_with_no_target().unwrap();
+    let profile = profile.apply_build_platforms(&build_platforms);
+
+    let runner = TestRunnerBuilder::default()
+        .build(
+            &test_list,
+            &profile,
+            vec![],
+            SignalHandlerKind::Noop,
+            InputHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    let (instance_statuses, run_stats) = execute_collect(runner);
+    assert_eq!(run_stats.timed_out, 3, "3 tests timed out");
+    // On Unix, the tests don't handle SIGTERM, so they exit within the grace period.
+    #[cfg(unix)]
+    assert_eq!(
+        run_stats.timed_out_killed, 0,
+        "no tests needed to be killed after SIGTERM"
+    );
+    for test_name in [
+        "test_slow_timeout",
+        "test_slow_timeout_2",
+        "test_slow_timeout_subprocess",
+    ] {
+        let (_, instance_value) = instance_statuses
+            .iter()
+            .find(|(&(_, name), _)| name == test_name)
+            .unwrap_or_else(|| panic!("{test_name} should be present"));
+        let valid = match &instance_value.status {
+            InstanceStatus::Skipped(_) => panic!("{test_name} should have been run"),
+            InstanceStatus::Finished(run_statuses) => {
+                // This test should not have been retried since retries aren't configured.
+                assert_eq!(
+                    run_statuses.len(),
+                    1,
+                    "{test_name} should have been run exactly once",
+                );
+                let run_status = run_statuses.last_status();
+                // The test should have taken less than 5 seconds (most relevant for
+                // test_slow_timeout_subprocess -- without job objects it gets stuck on Windows
+                // until the subprocess exits.)
+                assert!(
+                    run_status.time_taken < Duration::from_secs(5),
+                    "{test_name} should have taken less than 5 seconds, actually took {:?}",
+                    run_status.time_taken
+                );
+                matches!(run_status.result, ExecutionResult::Timeout { .. })
+            }
+        };
+        if !valid {
+            panic!(
+                "for test_slow_timeout, mismatch in status: expected timeout, actual {:?}",
+                instance_value.status
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_slow_timeout_overrides() -> Result<()> {
+    set_env_vars();
+
+    let pcx = ParseContext {
+        graph: &PACKAGE_GRAPH,
+        kind: FiltersetKind::Test,
+    };
+    let expr = Filterset::parse(
+        "test(=test_slow_timeout) | test(=test_slow_timeout_2)".to_owned(),
+        &pcx,
+    )
+    .unwrap();
+    let test_filter = TestFilterBuilder::new(
+        RunIgnored::Only,
+        None,
+        TestFilterPatterns::default(),
+        vec![expr],
+    )
+    .unwrap();
+
+    let test_list = FIXTURE_TARGETS.make_test_list(&test_filter, &TargetRunner::empty())?;
+    let config = load_config();
+    let profile = config
+        .profile("with-termination")
+        .expect("with-termination config is valid");
+    let build_platforms = BuildPlatforms::new_with_no_target().unwrap();
+    let profile = profile.apply_build_platforms(&build_platforms);
+
+    let runner = TestRunnerBuilder::default()
+        .build(
+            &test_list,
+            &profile,
+            vec![],
+            SignalHandlerKind::Noop,
+            InputHandlerKind::Noop,
+            DoubleSpawnInfo::disabled(),
+            TargetRunner::empty(),
+        )
+        .unwrap();
+
+    // test name -> slow timeouts reported for it
+    let mut slow_timeouts: BTreeMap<&str, Vec<Duration>> = BTreeMap::new();
+    runner
+        .execute(|event| {
+            if let TestEventKind::TestSlow {
+                test_instance,
+                slow_timeout,
+                elapsed,
+                ..
+            } = event.kind
+            {
+                assert_eq!(
+                    elapsed.as_nanos() % slow_timeout.as_nanos(),
+                    0,
+                    "elapsed is a multiple of the slow timeout"
+                );
+                slow_timeouts
+                    .entry(test_instance.name)
+                    .or_default()
+                    .push(slow_timeout);
+            }
+        })
+        .unwrap();
+
+    // test_slow_timeout_2 has an override for the slow timeout, which should be
+    // reported rather than the profile's.
+    assert_eq!(
+        slow_timeouts,
+        BTreeMap::from([
+            ("test_slow_timeout", vec![Duration::from_secs(1); 2]),
+            ("test_slow_timeout_2", vec![Duration::from_millis(500); 2]),
+        ])
+    );
+
+    Ok(())
+}
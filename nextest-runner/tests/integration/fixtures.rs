@@ -75,7 +75,7 @@ pub(crate) fn make_execution_result(
             abort_status: None,
             leaked: true,
         },
-        TestCaseFixtureStatus::Leak => ExecutionResult::Leak,
+        TestCaseFixtureStatus::Leak => ExecutionResult::Leak { details: None },
     }
 }
 
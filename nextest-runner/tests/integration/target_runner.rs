@@ -287,7 +287,7 @@ fn test_run_with_target_runner() -> Result<()> {
                             }
                         }
                     }
-                    run_status.result == expected_status
+                    run_status.result.without_leak_details() == expected_status
                 }
             };
             if !valid {